@@ -1,4 +1,5 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::time::{Duration, Instant};
 
 /// Show upgrade status for all cluster nodes
 pub async fn run_upgrade_status(api_url: &str) -> Result<()> {
@@ -76,7 +77,14 @@ pub async fn run_drain(api_url: &str, node_id: &str) -> Result<()> {
     let resp = client.post(&url).send().await?;
 
     if resp.status().is_success() {
-        println!("Node {} is now draining", node_id);
+        let body: serde_json::Value = resp.json().await.unwrap_or_default();
+        println!(
+            "Node {} is now draining ({} primaries handed off)",
+            node_id,
+            body.get("primaries_handed_off")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0)
+        );
     } else {
         let status = resp.status();
         let body = resp.text().await.unwrap_or_default();
@@ -106,3 +114,552 @@ pub async fn run_undrain(api_url: &str, node_id: &str) -> Result<()> {
 
     Ok(())
 }
+
+/// Options of `prism cluster rolling-upgrade`
+pub struct RollingUpgradeOptions {
+    /// API URLs, tried in order until one answers
+    pub api_urls: Vec<String>,
+    /// Nodes to upgrade in this order, or every node if empty
+    pub nodes: Vec<String>,
+    /// Version the nodes are upgraded to
+    pub target_version: Option<String>,
+    /// Only print the plan
+    pub dry_run: bool,
+    /// How long to wait for each step of a node
+    pub timeout: Duration,
+    /// Continue when a drained node keeps primaries it cannot hand off
+    pub force: bool,
+}
+
+/// Delay between polls while waiting for a node
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Upgrade the nodes of a cluster one at a time.
+///
+/// Each node is drained, which hands its primaries to in-sync replicas,
+/// then restarted on the new release by the operator. Once it is back with
+/// the protocol features every node shared at the start and the cluster is
+/// healthy, it is undrained and the next node follows. The
+/// metadata leader goes last so the cluster elects a new leader only once.
+pub async fn run_rolling_upgrade(options: RollingUpgradeOptions) -> Result<()> {
+    let api = ClusterApi::new(options.api_urls.clone());
+    let status = api.get("/cluster/upgrade/status").await?;
+    let health = api.get("/cluster/health").await?;
+
+    let plan = plan_rolling_upgrade(&status, &health, &options)?;
+    let features = check_preflight(&status, &health, options.dry_run)?;
+
+    println!("Rolling Upgrade Plan");
+    println!("====================");
+    if let Some(target) = &options.target_version {
+        println!("Target version: {}", target);
+    }
+    if !features.is_empty() {
+        println!("Protocol features to keep: {}", features.join(", "));
+    }
+    if plan.is_empty() {
+        println!("Every node already runs the target version");
+        return Ok(());
+    }
+    for (i, node_id) in plan.iter().enumerate() {
+        println!(
+            "{}. {} (version {})",
+            i + 1,
+            node_id,
+            node_field(&status, node_id, "version")
+                .and_then(|v| v.as_str().map(str::to_string))
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| "?".to_string())
+        );
+    }
+    println!();
+    println!("For each node: drain, wait for its primaries to move, wait for it to");
+    println!("rejoin on the new release, verify cluster health, undrain.");
+
+    if options.dry_run {
+        return Ok(());
+    }
+
+    for (i, node_id) in plan.iter().enumerate() {
+        println!();
+        println!("[{}/{}] Upgrading {}", i + 1, plan.len(), node_id);
+        upgrade_node(&api, node_id, &features, &options)
+            .await
+            .with_context(|| format!("rolling upgrade stopped at node {}", node_id))?;
+    }
+
+    println!();
+    run_upgrade_status(&api.urls[0]).await.ok();
+    Ok(())
+}
+
+/// Order in which nodes are upgraded: the requested nodes, or every node
+/// not on the target version with the metadata leader last
+fn plan_rolling_upgrade(
+    status: &serde_json::Value,
+    health: &serde_json::Value,
+    options: &RollingUpgradeOptions,
+) -> Result<Vec<String>> {
+    let known: Vec<String> = status
+        .get("nodes")
+        .and_then(|v| v.as_array())
+        .map(|nodes| {
+            nodes
+                .iter()
+                .filter_map(|n| n.get("node_id").and_then(|v| v.as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if !options.nodes.is_empty() {
+        if let Some(unknown) = options.nodes.iter().find(|n| !known.contains(n)) {
+            anyhow::bail!("Node {} is not part of the cluster", unknown);
+        }
+        return Ok(options.nodes.clone());
+    }
+
+    let leader = metadata_leader(health);
+    let mut plan: Vec<String> = known
+        .into_iter()
+        .filter(|node_id| match &options.target_version {
+            Some(target) => {
+                node_field(status, node_id, "version").and_then(|v| v.as_str().map(str::to_string))
+                    != Some(target.clone())
+            }
+            None => true,
+        })
+        .collect();
+    plan.sort_by_key(|node_id| (Some(node_id) == leader.as_ref(), node_id.clone()));
+    Ok(plan)
+}
+
+/// Refuse to start on a cluster that is already degraded. Returns the
+/// protocol features every node negotiates, which upgraded nodes must keep.
+fn check_preflight(
+    status: &serde_json::Value,
+    health: &serde_json::Value,
+    dry_run: bool,
+) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+    let cluster_status = health
+        .get("status")
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown");
+    if cluster_status != "green" {
+        problems.push(format!("cluster health is {}", cluster_status));
+    }
+    let draining = status
+        .get("draining_count")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    if draining > 0 {
+        problems.push(format!("{} nodes are already draining", draining));
+    }
+    if !status
+        .get("compatible")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true)
+    {
+        problems.push("nodes have no protocol version in common".to_string());
+    }
+    let features: Vec<String> = status
+        .get("common_features")
+        .and_then(|v| v.as_array())
+        .map(|features| {
+            features
+                .iter()
+                .filter_map(|f| f.as_str())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if problems.is_empty() {
+        return Ok(features);
+    }
+    if dry_run {
+        for problem in &problems {
+            println!("Warning: {}", problem);
+        }
+        return Ok(features);
+    }
+    anyhow::bail!("Cannot start a rolling upgrade: {}", problems.join(", "))
+}
+
+/// Drain, wait for the upgrade, verify and undrain one node
+async fn upgrade_node(
+    api: &ClusterApi,
+    node_id: &str,
+    features: &[String],
+    options: &RollingUpgradeOptions,
+) -> Result<()> {
+    let drained = api
+        .post(&format!("/cluster/nodes/{}/drain", node_id))
+        .await?;
+    println!(
+        "  Drained, {} primaries handed off",
+        drained
+            .get("primaries_handed_off")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0)
+    );
+
+    // Primaries without an in-sync replica elsewhere stay on the node
+    let relocated = wait_for(options.timeout, || async {
+        let status = api.get("/cluster/upgrade/status").await?;
+        Ok(node_field(&status, node_id, "primary_shards").and_then(|v| v.as_u64()) == Some(0))
+    })
+    .await?;
+    if relocated {
+        println!("  No primaries left on {}", node_id);
+    } else if options.force {
+        println!("  Warning: {} still holds primaries, continuing", node_id);
+    } else {
+        anyhow::bail!(
+            "{} still holds primaries without an in-sync replica on another node \
+             (pass --force to upgrade it anyway)",
+            node_id
+        );
+    }
+
+    match &options.target_version {
+        Some(target) => {
+            println!("  Upgrade {} to {} and restart it now", node_id, target);
+            let upgraded = wait_for(options.timeout, || async {
+                let status = api.get("/cluster/upgrade/status").await?;
+                let version = node_field(&status, node_id, "version");
+                let reachable = node_field(&status, node_id, "reachable");
+                Ok(version
+                    .and_then(|v| v.as_str().map(str::to_string))
+                    .as_ref()
+                    == Some(target)
+                    && reachable.and_then(|v| v.as_bool()) == Some(true))
+            })
+            .await?;
+            if !upgraded {
+                anyhow::bail!("{} did not rejoin on version {} in time", node_id, target);
+            }
+        }
+        None => {
+            println!("  Upgrade and restart {}, then press Enter", node_id);
+            tokio::task::spawn_blocking(|| {
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line)
+            })
+            .await??;
+        }
+    }
+
+    let healthy = wait_for(options.timeout, || async {
+        let health = api.get("/cluster/health").await?;
+        let green = health.get("status").and_then(|v| v.as_str()) == Some("green");
+        let status = api.get("/cluster/upgrade/status").await?;
+        let compatible = status
+            .get("compatible")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        Ok(green && compatible && missing_features(&status, node_id, features).is_empty())
+    })
+    .await?;
+    if !healthy {
+        let status = api.get("/cluster/upgrade/status").await?;
+        let missing = missing_features(&status, node_id, features);
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "{} rejoined without protocol features the cluster uses: {}",
+                node_id,
+                missing.join(", ")
+            );
+        }
+        anyhow::bail!("cluster did not become healthy after upgrading {}", node_id);
+    }
+    println!("  Cluster is healthy");
+
+    api.post(&format!("/cluster/nodes/{}/undrain", node_id))
+        .await?;
+    println!("  Undrained {}", node_id);
+    Ok(())
+}
+
+/// Poll until `check` holds, returning false once `timeout` elapsed.
+///
+/// Failed requests are retried, since the node behind an API URL may be
+/// the one restarting.
+async fn wait_for<F, Fut>(timeout: Duration, mut check: F) -> Result<bool>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<bool>>,
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Ok(true) = check().await {
+            return Ok(true);
+        }
+        if Instant::now() >= deadline {
+            return Ok(false);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Node ID of the metadata leader, matched by its cluster address
+fn metadata_leader(health: &serde_json::Value) -> Option<String> {
+    let leader = health
+        .get("metadata")
+        .and_then(|m| m.get("leader"))
+        .and_then(|v| v.as_str())?;
+    health
+        .get("nodes")
+        .and_then(|v| v.as_array())?
+        .iter()
+        .find(|n| n.get("address").and_then(|v| v.as_str()) == Some(leader))
+        .and_then(|n| n.get("node_id").and_then(|v| v.as_str()))
+        .map(str::to_string)
+}
+
+/// A field of a node in the upgrade status
+fn node_field<'a>(
+    status: &'a serde_json::Value,
+    node_id: &str,
+    field: &str,
+) -> Option<&'a serde_json::Value> {
+    status
+        .get("nodes")
+        .and_then(|v| v.as_array())?
+        .iter()
+        .find(|n| n.get("node_id").and_then(|v| v.as_str()) == Some(node_id))?
+        .get(field)
+}
+
+/// Which of `features` a node has not negotiated, all of them while the node
+/// is unknown
+fn missing_features(status: &serde_json::Value, node_id: &str, features: &[String]) -> Vec<String> {
+    let supported: Vec<&str> = node_field(status, node_id, "features")
+        .and_then(|v| v.as_array())
+        .map(|features| features.iter().filter_map(|f| f.as_str()).collect())
+        .unwrap_or_default();
+    features
+        .iter()
+        .filter(|f| !supported.contains(&f.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Cluster API reached through the first URL that answers
+struct ClusterApi {
+    client: reqwest::Client,
+    urls: Vec<String>,
+}
+
+impl ClusterApi {
+    fn new(urls: Vec<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            urls: urls
+                .into_iter()
+                .map(|url| url.trim_end_matches('/').to_string())
+                .collect(),
+        }
+    }
+
+    async fn get(&self, path: &str) -> Result<serde_json::Value> {
+        self.send(reqwest::Method::GET, path).await
+    }
+
+    async fn post(&self, path: &str) -> Result<serde_json::Value> {
+        self.send(reqwest::Method::POST, path).await
+    }
+
+    async fn send(&self, method: reqwest::Method, path: &str) -> Result<serde_json::Value> {
+        let mut last_error = None;
+        for url in &self.urls {
+            let resp = match self
+                .client
+                .request(method.clone(), format!("{}{}", url, path))
+                .timeout(Duration::from_secs(30))
+                .send()
+                .await
+            {
+                Ok(resp) => resp,
+                Err(e) => {
+                    last_error = Some(anyhow::anyhow!("{}: {}", url, e));
+                    continue;
+                }
+            };
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                anyhow::bail!("Request to {}{} failed ({}): {}", url, path, status, body);
+            }
+            return Ok(resp.json().await?);
+        }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("no API URL given")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn options(target_version: Option<&str>) -> RollingUpgradeOptions {
+        RollingUpgradeOptions {
+            api_urls: vec!["http://localhost:8080".to_string()],
+            nodes: Vec::new(),
+            target_version: target_version.map(str::to_string),
+            dry_run: false,
+            timeout: Duration::from_secs(1),
+            force: false,
+        }
+    }
+
+    /// Upgrade status of nodes given as (node_id, version, protocol_version,
+    /// min_supported_version)
+    fn upgrade_status(nodes: &[(&str, &str, u32, u32)], compatible: bool) -> serde_json::Value {
+        let nodes: Vec<serde_json::Value> = nodes
+            .iter()
+            .map(|(node_id, version, protocol, min_supported)| {
+                json!({
+                    "node_id": node_id,
+                    "version": version,
+                    "protocol_version": protocol,
+                    "min_supported_version": min_supported,
+                    "draining": false,
+                    "reachable": true,
+                })
+            })
+            .collect();
+        json!({
+            "nodes": nodes,
+            "total_nodes": nodes.len(),
+            "draining_count": 0,
+            "compatible": compatible,
+        })
+    }
+
+    /// Cluster health with `leader` as the metadata leader of nodes a, b, c
+    fn health(cluster_status: &str, leader: &str) -> serde_json::Value {
+        json!({
+            "status": cluster_status,
+            "metadata": { "leader": format!("{}:9090", leader) },
+            "nodes": [
+                { "node_id": "a", "address": "a:9090" },
+                { "node_id": "b", "address": "b:9090" },
+                { "node_id": "c", "address": "c:9090" },
+            ],
+        })
+    }
+
+    #[test]
+    fn test_plan_upgrades_leader_last() {
+        let status = upgrade_status(
+            &[("a", "1.0", 1, 1), ("b", "1.0", 1, 1), ("c", "1.0", 1, 1)],
+            true,
+        );
+
+        let plan = plan_rolling_upgrade(&status, &health("green", "a"), &options(None)).unwrap();
+        assert_eq!(plan, vec!["b", "c", "a"]);
+        let plan = plan_rolling_upgrade(&status, &health("green", "b"), &options(None)).unwrap();
+        assert_eq!(plan, vec!["a", "c", "b"]);
+    }
+
+    #[test]
+    fn test_plan_skips_upgraded_nodes() {
+        let status = upgrade_status(
+            &[("a", "1.0", 1, 1), ("b", "1.1", 2, 1), ("c", "1.0", 1, 1)],
+            true,
+        );
+        let plan =
+            plan_rolling_upgrade(&status, &health("green", "c"), &options(Some("1.1"))).unwrap();
+        assert_eq!(plan, vec!["a", "c"]);
+
+        // Nothing left to do once every node runs the target version
+        let status = upgrade_status(&[("a", "1.1", 2, 1)], true);
+        let plan =
+            plan_rolling_upgrade(&status, &health("green", "a"), &options(Some("1.1"))).unwrap();
+        assert!(plan.is_empty());
+    }
+
+    #[test]
+    fn test_plan_requested_nodes() {
+        let status = upgrade_status(&[("a", "1.0", 1, 1), ("b", "1.0", 1, 1)], true);
+        let mut requested = options(None);
+        requested.nodes = vec!["b".to_string(), "a".to_string()];
+        let plan = plan_rolling_upgrade(&status, &health("green", "b"), &requested).unwrap();
+        assert_eq!(plan, vec!["b", "a"]);
+
+        requested.nodes = vec!["z".to_string()];
+        let err = plan_rolling_upgrade(&status, &health("green", "b"), &requested).unwrap_err();
+        assert!(err.to_string().contains("Node z"), "{}", err);
+    }
+
+    #[test]
+    fn test_preflight_refuses_unhealthy_cluster() {
+        let status = upgrade_status(&[("a", "1.0", 1, 1), ("b", "1.0", 1, 1)], true);
+        assert!(check_preflight(&status, &health("green", "a"), false).is_ok());
+
+        for cluster_status in ["yellow", "red"] {
+            let err = check_preflight(&status, &health(cluster_status, "a"), false).unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains(&format!("cluster health is {}", cluster_status)),
+                "{}",
+                err
+            );
+            // A dry run only warns
+            assert!(check_preflight(&status, &health(cluster_status, "a"), true).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_preflight_refuses_mixed_protocol_versions() {
+        // Node b only speaks protocol 3, which node a does not support
+        let status = upgrade_status(&[("a", "1.0", 2, 1), ("b", "2.0", 3, 3)], false);
+        let err = check_preflight(&status, &health("green", "a"), false).unwrap_err();
+        assert!(
+            err.to_string().contains("no protocol version in common"),
+            "{}",
+            err
+        );
+
+        // A mix left by an interrupted upgrade can be resumed
+        let status = upgrade_status(&[("a", "1.0", 2, 1), ("b", "1.1", 3, 2)], true);
+        assert!(check_preflight(&status, &health("green", "a"), false).is_ok());
+    }
+
+    #[test]
+    fn test_preflight_returns_common_features() {
+        let mut status = upgrade_status(&[("a", "1.0", 2, 1), ("b", "1.0", 2, 1)], true);
+        let features = check_preflight(&status, &health("green", "a"), false).unwrap();
+        assert!(features.is_empty(), "servers without feature reporting");
+
+        status["common_features"] = json!(["raft", "replication"]);
+        let features = check_preflight(&status, &health("green", "a"), false).unwrap();
+        assert_eq!(features, vec!["raft", "replication"]);
+    }
+
+    #[test]
+    fn test_upgraded_node_keeps_features() {
+        let features = vec!["raft".to_string(), "replication".to_string()];
+        let mut status = upgrade_status(&[("a", "1.1", 2, 1), ("b", "1.0", 2, 1)], true);
+        status["nodes"][0]["features"] = json!(["raft", "replication", "shard_scan"]);
+        status["nodes"][1]["features"] = json!(["raft"]);
+
+        assert!(missing_features(&status, "a", &features).is_empty());
+        assert_eq!(
+            missing_features(&status, "b", &features),
+            vec!["replication"]
+        );
+        // Not rejoined yet
+        assert_eq!(missing_features(&status, "c", &features), features);
+    }
+
+    #[test]
+    fn test_preflight_refuses_draining_nodes() {
+        let mut status = upgrade_status(&[("a", "1.0", 1, 1), ("b", "1.0", 1, 1)], true);
+        status["draining_count"] = json!(1);
+        let err = check_preflight(&status, &health("green", "a"), false).unwrap_err();
+        assert!(err.to_string().contains("already draining"), "{}", err);
+    }
+}
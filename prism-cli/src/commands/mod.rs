@@ -12,7 +12,9 @@ pub mod restore;
 
 pub use attach::run_attach;
 pub use benchmark::run_benchmark;
-pub use cluster::{run_drain, run_undrain, run_upgrade_status};
+pub use cluster::{
+    run_drain, run_rolling_upgrade, run_undrain, run_upgrade_status, RollingUpgradeOptions,
+};
 pub use detach::run_detach;
 pub use export::run_export;
 pub use graph_merge::run_graph_merge;
@@ -278,6 +278,36 @@ enum ClusterCommands {
         #[arg(long, default_value = "http://localhost:3080")]
         api_url: String,
     },
+
+    /// Upgrade nodes one at a time: drain, wait for primaries to move, wait
+    /// for the node to rejoin upgraded, verify health, undrain
+    RollingUpgrade {
+        /// Prism API URLs, tried in order (list several so the upgrade goes
+        /// on while the node behind one of them restarts)
+        #[arg(long, value_delimiter = ',', default_value = "http://localhost:3080")]
+        api_url: Vec<String>,
+
+        /// Nodes to upgrade, in order (default: every node not on the target
+        /// version, metadata leader last)
+        #[arg(long, value_delimiter = ',')]
+        nodes: Vec<String>,
+
+        /// Version the nodes are upgraded to (prompts after each node if unset)
+        #[arg(long)]
+        target_version: Option<String>,
+
+        /// Print the plan without draining anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Seconds to wait for each step of a node
+        #[arg(long, default_value = "600")]
+        timeout_secs: u64,
+
+        /// Upgrade nodes that keep primaries without an in-sync replica elsewhere
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 #[tokio::main]
@@ -397,6 +427,24 @@ async fn main() -> Result<()> {
             ClusterCommands::Undrain { node, api_url } => {
                 commands::run_undrain(&api_url, &node).await?;
             }
+            ClusterCommands::RollingUpgrade {
+                api_url,
+                nodes,
+                target_version,
+                dry_run,
+                timeout_secs,
+                force,
+            } => {
+                commands::run_rolling_upgrade(commands::RollingUpgradeOptions {
+                    api_urls: api_url,
+                    nodes,
+                    target_version,
+                    dry_run,
+                    timeout: std::time::Duration::from_secs(timeout_secs),
+                    force,
+                })
+                .await?;
+            }
         },
 
         Commands::Benchmark {
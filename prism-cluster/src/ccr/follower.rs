@@ -0,0 +1,905 @@
+//! Following a leader collection and the followers of this node
+
+use super::{
+    FollowConfig, FollowState, FollowerSink, FollowerStatus, LeaderTransport, ShardFollowProgress,
+};
+use crate::error::{ClusterError, Result};
+use crate::metrics::{record_follower_error, record_follower_operations, update_follower_lag};
+use crate::types::{RpcLeaderShard, RpcOperationsRequest, RpcShardScanRequest, RpcWriteOp};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Documents read per page when a leader shard is copied whole
+const COPY_BATCH_SIZE: usize = 500;
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Persisted state of a follower
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FollowerRecord {
+    config: FollowConfig,
+    state: FollowState,
+    #[serde(default)]
+    shards: BTreeMap<String, ShardFollowProgress>,
+}
+
+struct FollowerInner {
+    state: FollowState,
+    shards: BTreeMap<String, ShardFollowProgress>,
+    operations_applied: u64,
+    documents_copied: u64,
+    last_error: Option<String>,
+    /// Whether progress changed since it was last persisted
+    dirty: bool,
+}
+
+/// A collection following a leader collection on another cluster
+pub struct Follower {
+    collection: String,
+    config: FollowConfig,
+    transport: Arc<dyn LeaderTransport>,
+    sink: Arc<dyn FollowerSink>,
+    inner: Mutex<FollowerInner>,
+    /// Held while pulling, so pausing and promoting wait for the pull in flight
+    pull_lock: tokio::sync::Mutex<()>,
+}
+
+impl Follower {
+    fn new(
+        collection: &str,
+        record: FollowerRecord,
+        transport: Arc<dyn LeaderTransport>,
+        sink: Arc<dyn FollowerSink>,
+    ) -> Self {
+        Self {
+            collection: collection.to_string(),
+            config: record.config,
+            transport,
+            sink,
+            inner: Mutex::new(FollowerInner {
+                state: record.state,
+                shards: record.shards,
+                operations_applied: 0,
+                documents_copied: 0,
+                last_error: None,
+                dirty: false,
+            }),
+            pull_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn collection(&self) -> &str {
+        &self.collection
+    }
+
+    pub fn config(&self) -> &FollowConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> FollowState {
+        self.inner.lock().state
+    }
+
+    /// Progress and lag
+    pub fn status(&self) -> FollowerStatus {
+        let inner = self.inner.lock();
+        let now = now_ms();
+        let time_since_last_read_ms = inner
+            .shards
+            .values()
+            .map(|shard| shard.last_read_ms.map(|at| now.saturating_sub(at)))
+            .collect::<Option<Vec<_>>>()
+            .and_then(|waits| waits.into_iter().max());
+        FollowerStatus {
+            collection: self.collection.clone(),
+            leader_collection: self.config.leader_collection.clone(),
+            leader_addresses: self.config.leader_addresses.clone(),
+            state: inner.state,
+            shards: inner.shards.clone(),
+            lag_operations: inner
+                .shards
+                .values()
+                .map(ShardFollowProgress::lag_operations)
+                .sum(),
+            time_since_last_read_ms,
+            operations_applied: inner.operations_applied,
+            documents_copied: inner.documents_copied,
+            last_error: inner.last_error.clone(),
+        }
+    }
+
+    fn record(&self) -> FollowerRecord {
+        let inner = self.inner.lock();
+        FollowerRecord {
+            config: self.config.clone(),
+            state: inner.state,
+            shards: inner.shards.clone(),
+        }
+    }
+
+    /// Change the state once the pull in flight, if any, finished
+    async fn set_state(&self, state: FollowState) {
+        let _guard = self.pull_lock.lock().await;
+        let mut inner = self.inner.lock();
+        inner.state = state;
+        inner.dirty = true;
+    }
+
+    /// Pull the leader's writes and apply them once.
+    ///
+    /// Returns the number of writes applied; nothing is pulled unless the
+    /// follower is following.
+    pub async fn pull(&self) -> Result<usize> {
+        let _guard = self.pull_lock.lock().await;
+        if self.state() != FollowState::Following {
+            return Ok(0);
+        }
+        let result = self.pull_locked().await;
+        match &result {
+            Ok(_) => self.inner.lock().last_error = None,
+            Err(e) => {
+                record_follower_error(&self.collection, e.error_type());
+                self.inner.lock().last_error = Some(e.to_string());
+            }
+        }
+        result
+    }
+
+    async fn pull_locked(&self) -> Result<usize> {
+        let shards = self.leader_shards().await?;
+        {
+            // Shards gone from the leader, after it was resharded, are done
+            let current: HashSet<&str> = shards.iter().map(|s| s.shard_id.as_str()).collect();
+            let mut inner = self.inner.lock();
+            let before = inner.shards.len();
+            inner.shards.retain(|id, _| current.contains(id.as_str()));
+            inner.dirty |= inner.shards.len() != before;
+        }
+
+        let mut applied = 0;
+        let mut first_error = None;
+        for shard in &shards {
+            match self.pull_shard(shard, shards.len()).await {
+                Ok(count) => applied += count,
+                Err(e) => {
+                    warn!(
+                        "Follower {} failed to pull leader shard {}: {}",
+                        self.collection, shard.shard_id, e
+                    );
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(applied),
+        }
+    }
+
+    /// Read the leader's shards from the first leader node that answers
+    async fn leader_shards(&self) -> Result<Vec<RpcLeaderShard>> {
+        let mut last_error = ClusterError::Config(format!(
+            "Follower {} has no leader addresses",
+            self.collection
+        ));
+        for addr in &self.config.leader_addresses {
+            match self
+                .transport
+                .leader_shards(addr, &self.config.leader_collection)
+                .await
+            {
+                Ok(shards) => return Ok(shards),
+                Err(e) => {
+                    debug!("Leader node {} did not answer: {}", addr, e);
+                    last_error = e;
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Apply the operations of a leader shard this follower is missing,
+    /// copying the shard whole first when they cannot be read from its log
+    async fn pull_shard(&self, shard: &RpcLeaderShard, shard_count: usize) -> Result<usize> {
+        let progress = self.inner.lock().shards.get(&shard.shard_id).cloned();
+        let Some(mut progress) = progress else {
+            self.copy_shard(shard, shard_count).await?;
+            return Ok(0);
+        };
+
+        let fetch = |from_seq| {
+            self.transport.get_operations(
+                &shard.primary_address,
+                RpcOperationsRequest {
+                    shard_id: shard.shard_id.clone(),
+                    from_seq,
+                },
+            )
+        };
+        let mut operations = match fetch(progress.checkpoint + 1).await {
+            Err(ClusterError::Replication(reason)) => {
+                info!(
+                    "Follower {} copies leader shard {} again: {}",
+                    self.collection, shard.shard_id, reason
+                );
+                self.copy_shard(shard, shard_count).await?;
+                return Ok(0);
+            }
+            result => result?,
+        };
+        if operations.primary_term < progress.primary_term {
+            return Err(ClusterError::Replication(format!(
+                "Leader primary of shard {} is at term {}, follower is at term {}",
+                shard.shard_id, operations.primary_term, progress.primary_term
+            )));
+        }
+        if operations.primary_term > progress.primary_term {
+            // A new primary restarted the sequence
+            progress.primary_term = operations.primary_term;
+            if progress.checkpoint > 0 {
+                progress.checkpoint = 0;
+                operations = match fetch(1).await {
+                    Err(ClusterError::Replication(_)) => {
+                        self.copy_shard(shard, shard_count).await?;
+                        return Ok(0);
+                    }
+                    result => result?,
+                };
+            }
+        }
+
+        let mut applied = 0;
+        let mut result = Ok(());
+        for op in operations.ops {
+            if op.seq_no != progress.checkpoint + 1 {
+                result = Err(ClusterError::Replication(format!(
+                    "Operation log of leader shard {} skips from {} to {}",
+                    shard.shard_id, progress.checkpoint, op.seq_no
+                )));
+                break;
+            }
+            if let Err(e) = self.sink.apply(&self.collection, op.op).await {
+                result = Err(e);
+                break;
+            }
+            progress.checkpoint = op.seq_no;
+            applied += 1;
+        }
+        progress.leader_checkpoint = operations.checkpoint.max(progress.checkpoint);
+        progress.last_read_ms = Some(now_ms());
+
+        update_follower_lag(&self.collection, &shard.shard_id, progress.lag_operations());
+        record_follower_operations(&self.collection, applied);
+        let mut inner = self.inner.lock();
+        inner.dirty |= applied > 0 || inner.shards.get(&shard.shard_id) != Some(&progress);
+        inner.shards.insert(shard.shard_id.clone(), progress);
+        inner.operations_applied += applied as u64;
+        result.map(|_| applied)
+    }
+
+    /// Copy every document of a leader shard and follow it from the
+    /// checkpoint it had before the copy; writes made meanwhile are pulled
+    /// from its log afterwards
+    async fn copy_shard(&self, shard: &RpcLeaderShard, shard_count: usize) -> Result<()> {
+        let addr = &shard.primary_address;
+        let checkpoint = self
+            .transport
+            .shard_checkpoint(addr, &shard.shard_id)
+            .await?;
+
+        let mut copied = 0;
+        let mut offset = Some(0);
+        while let Some(from) = offset {
+            let page = self
+                .transport
+                .scan_shard(
+                    addr,
+                    RpcShardScanRequest {
+                        collection: self.config.leader_collection.clone(),
+                        shard_number: shard.shard_number,
+                        shard_count,
+                        offset: from,
+                        limit: COPY_BATCH_SIZE,
+                    },
+                )
+                .await?;
+            offset = page.next_offset;
+            if page.documents.is_empty() {
+                continue;
+            }
+            copied += page.documents.len();
+            self.sink
+                .apply(&self.collection, RpcWriteOp::Index(page.documents))
+                .await?;
+        }
+        info!(
+            "Follower {} copied {} documents of leader shard {} at checkpoint {}",
+            self.collection, copied, shard.shard_id, checkpoint.checkpoint
+        );
+
+        let progress = ShardFollowProgress {
+            primary_term: checkpoint.primary_term,
+            checkpoint: checkpoint.checkpoint,
+            leader_checkpoint: checkpoint.checkpoint,
+            last_read_ms: Some(now_ms()),
+        };
+        let mut inner = self.inner.lock();
+        inner.shards.insert(shard.shard_id.clone(), progress);
+        inner.documents_copied += copied as u64;
+        inner.dirty = true;
+        Ok(())
+    }
+}
+
+/// The follower collections of this node, persisted so following resumes
+/// after a restart
+pub struct CrossClusterReplication {
+    path: Option<PathBuf>,
+    transport: Arc<dyn LeaderTransport>,
+    sink: Arc<dyn FollowerSink>,
+    followers: RwLock<HashMap<String, Arc<Follower>>>,
+    tasks: Mutex<HashMap<String, JoinHandle<()>>>,
+    /// Whether followers pull in the background
+    started: AtomicBool,
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl CrossClusterReplication {
+    /// Followers kept in memory only
+    pub fn in_memory(transport: Arc<dyn LeaderTransport>, sink: Arc<dyn FollowerSink>) -> Self {
+        Self {
+            path: None,
+            transport,
+            sink,
+            followers: RwLock::new(HashMap::new()),
+            tasks: Mutex::new(HashMap::new()),
+            started: AtomicBool::new(false),
+            write_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Load the followers saved in `dir`, starting with none if none were
+    pub fn load(
+        dir: &Path,
+        transport: Arc<dyn LeaderTransport>,
+        sink: Arc<dyn FollowerSink>,
+    ) -> Result<Self> {
+        let path = dir.join("ccr-followers.json");
+        let records: BTreeMap<String, FollowerRecord> = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|e| ClusterError::Serialization(e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let followers = records
+            .into_iter()
+            .map(|(collection, record)| {
+                let follower = Follower::new(
+                    &collection,
+                    record,
+                    Arc::clone(&transport),
+                    Arc::clone(&sink),
+                );
+                (collection, Arc::new(follower))
+            })
+            .collect();
+        Ok(Self {
+            path: Some(path),
+            transport,
+            sink,
+            followers: RwLock::new(followers),
+            tasks: Mutex::new(HashMap::new()),
+            started: AtomicBool::new(false),
+            write_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Start pulling in the background, for the loaded followers and those
+    /// added later
+    pub fn start(self: &Arc<Self>) {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let followers: Vec<_> = self.followers.read().values().cloned().collect();
+        for follower in followers {
+            info!(
+                "Resuming follower {} of {}",
+                follower.collection, follower.config.leader_collection
+            );
+            self.spawn(follower);
+        }
+    }
+
+    /// Make `collection` follow a collection on another cluster.
+    ///
+    /// The collection must exist on this cluster. It is read-only for
+    /// clients until promoted.
+    pub async fn follow(
+        self: &Arc<Self>,
+        collection: &str,
+        config: FollowConfig,
+    ) -> Result<FollowerStatus> {
+        if config.leader_addresses.is_empty() || config.leader_collection.is_empty() {
+            return Err(ClusterError::InvalidQuery(
+                "Following needs leader addresses and a leader collection".to_string(),
+            ));
+        }
+        let follower = {
+            let mut followers = self.followers.write();
+            if followers.contains_key(collection) {
+                return Err(ClusterError::InvalidQuery(format!(
+                    "Collection {} already follows a leader",
+                    collection
+                )));
+            }
+            let follower = Arc::new(Follower::new(
+                collection,
+                FollowerRecord {
+                    config,
+                    state: FollowState::Following,
+                    shards: BTreeMap::new(),
+                },
+                Arc::clone(&self.transport),
+                Arc::clone(&self.sink),
+            ));
+            followers.insert(collection.to_string(), Arc::clone(&follower));
+            follower
+        };
+        self.persist().await?;
+        info!(
+            "Collection {} follows {} on {:?}",
+            collection, follower.config.leader_collection, follower.config.leader_addresses
+        );
+        if self.started.load(Ordering::SeqCst) {
+            self.spawn(Arc::clone(&follower));
+        }
+        Ok(follower.status())
+    }
+
+    /// Stop pulling writes, keeping the collection read-only
+    pub async fn pause(&self, collection: &str) -> Result<FollowerStatus> {
+        let follower = self.follower(collection)?;
+        follower.set_state(FollowState::Paused).await;
+        self.persist().await?;
+        Ok(follower.status())
+    }
+
+    /// Pull writes again, from where the follower paused
+    pub async fn resume(&self, collection: &str) -> Result<FollowerStatus> {
+        let follower = self.follower(collection)?;
+        follower.set_state(FollowState::Following).await;
+        self.persist().await?;
+        Ok(follower.status())
+    }
+
+    /// Stop following for good and make the collection writable.
+    ///
+    /// Writes the leader made after the last pull are not applied; pause
+    /// first and check the lag for a clean cutover.
+    pub async fn promote(&self, collection: &str) -> Result<FollowerStatus> {
+        let follower = self.follower(collection)?;
+        follower.set_state(FollowState::Promoted).await;
+        self.followers.write().remove(collection);
+        if let Some(task) = self.tasks.lock().remove(collection) {
+            task.abort();
+        }
+        self.persist().await?;
+        info!("Follower {} promoted to a writable collection", collection);
+        Ok(follower.status())
+    }
+
+    /// Pull the leader's writes once now
+    pub async fn pull(&self, collection: &str) -> Result<usize> {
+        let follower = self.follower(collection)?;
+        let result = follower.pull().await;
+        self.persist_if_changed(&follower).await;
+        result
+    }
+
+    /// Whether a collection follows a leader and is read-only
+    pub fn is_following(&self, collection: &str) -> bool {
+        self.followers.read().contains_key(collection)
+    }
+
+    pub fn status(&self, collection: &str) -> Option<FollowerStatus> {
+        self.followers.read().get(collection).map(|f| f.status())
+    }
+
+    /// Status of every follower, by collection name
+    pub fn statuses(&self) -> Vec<FollowerStatus> {
+        let mut statuses: Vec<_> = self.followers.read().values().map(|f| f.status()).collect();
+        statuses.sort_by(|a, b| a.collection.cmp(&b.collection));
+        statuses
+    }
+
+    fn follower(&self, collection: &str) -> Result<Arc<Follower>> {
+        self.followers
+            .read()
+            .get(collection)
+            .cloned()
+            .ok_or_else(|| {
+                ClusterError::CollectionNotFound(format!("{} is not a follower", collection))
+            })
+    }
+
+    /// Pull in the background until the follower is promoted
+    fn spawn(self: &Arc<Self>, follower: Arc<Follower>) {
+        let registry: Weak<Self> = Arc::downgrade(self);
+        let collection = follower.collection.clone();
+        let interval = Duration::from_millis(follower.config.poll_interval_ms.max(1));
+        let task = tokio::spawn(async move {
+            loop {
+                let applied = match follower.state() {
+                    FollowState::Promoted => break,
+                    FollowState::Paused => 0,
+                    FollowState::Following => follower.pull().await.unwrap_or(0),
+                };
+                let Some(registry) = registry.upgrade() else {
+                    break;
+                };
+                registry.persist_if_changed(&follower).await;
+                drop(registry);
+                // Keep pulling without delay while catching up
+                if applied == 0 {
+                    tokio::time::sleep(interval).await;
+                }
+            }
+        });
+        if let Some(previous) = self.tasks.lock().insert(collection, task) {
+            previous.abort();
+        }
+    }
+
+    async fn persist_if_changed(&self, follower: &Follower) {
+        let dirty = std::mem::take(&mut follower.inner.lock().dirty);
+        if dirty {
+            if let Err(e) = self.persist().await {
+                warn!(
+                    "Failed to persist progress of follower {}: {}",
+                    follower.collection, e
+                );
+            }
+        }
+    }
+
+    /// Save every follower's state
+    async fn persist(&self) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        let records: BTreeMap<String, FollowerRecord> = self
+            .followers
+            .read()
+            .iter()
+            .map(|(collection, follower)| (collection.clone(), follower.record()))
+            .collect();
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let data = serde_json::to_vec_pretty(&records)
+            .map_err(|e| ClusterError::Serialization(e.to_string()))?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, data).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ccr::leader_shards;
+    use crate::config::{ClusterConfig, NodeTopology, OperationLogConfig};
+    use crate::placement::{ClusterState, NodeInfo, ShardAssignment, ShardState};
+    use crate::replication::{ClientTransport, ReplicationCheckpoints, Replicator};
+    use crate::reshard::scan_shard;
+    use crate::transfer::ShardTransfers;
+    use crate::types::{
+        RpcDocument, RpcOperations, RpcPrimaryWrite, RpcShardCheckpoint, RpcShardScan,
+    };
+    use async_trait::async_trait;
+    use prism::backends::{TextBackend, VectorBackend};
+    use prism::collection::CollectionManager;
+    use tempfile::TempDir;
+
+    const SHARD: &str = "products-shard-0";
+
+    /// A single-node leader cluster reached in-process
+    struct LoopbackLeader {
+        state: Arc<ClusterState>,
+        replicator: Arc<Replicator>,
+        manager: Arc<CollectionManager>,
+        down: AtomicBool,
+    }
+
+    impl LoopbackLeader {
+        fn check(&self) -> Result<()> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(ClusterError::Connection("leader is down".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl LeaderTransport for LoopbackLeader {
+        async fn leader_shards(
+            &self,
+            _addr: &str,
+            collection: &str,
+        ) -> Result<Vec<RpcLeaderShard>> {
+            self.check()?;
+            leader_shards(&self.state, collection)
+        }
+
+        async fn get_operations(
+            &self,
+            _addr: &str,
+            request: RpcOperationsRequest,
+        ) -> Result<RpcOperations> {
+            self.check()?;
+            self.replicator.get_operations(request)
+        }
+
+        async fn shard_checkpoint(
+            &self,
+            _addr: &str,
+            shard_id: &str,
+        ) -> Result<RpcShardCheckpoint> {
+            self.check()?;
+            Ok(self.replicator.shard_checkpoint(shard_id))
+        }
+
+        async fn scan_shard(
+            &self,
+            _addr: &str,
+            request: RpcShardScanRequest,
+        ) -> Result<RpcShardScan> {
+            self.check()?;
+            scan_shard(&self.manager, &request).await
+        }
+    }
+
+    struct Setup {
+        temp: TempDir,
+        leader: Arc<LoopbackLeader>,
+        follower: Arc<CollectionManager>,
+    }
+
+    impl Setup {
+        async fn write(&self, op: RpcWriteOp) {
+            self.leader
+                .replicator
+                .primary_write(RpcPrimaryWrite {
+                    shard_id: SHARD.to_string(),
+                    collection: "products".to_string(),
+                    op,
+                })
+                .await
+                .unwrap();
+        }
+
+        async fn index(&self, id: &str) {
+            self.write(RpcWriteOp::Index(vec![RpcDocument {
+                id: id.to_string(),
+                fields: HashMap::from([(
+                    "title".to_string(),
+                    serde_json::json!(format!("Product {}", id)),
+                )]),
+            }]))
+            .await;
+        }
+
+        fn follower_ids(&self) -> Vec<String> {
+            self.follower.document_ids("products").unwrap()
+        }
+
+        fn registry(&self) -> Arc<CrossClusterReplication> {
+            Arc::new(
+                CrossClusterReplication::load(
+                    &self.temp.path().join("ccr"),
+                    Arc::clone(&self.leader) as Arc<dyn LeaderTransport>,
+                    Arc::clone(&self.follower) as Arc<dyn FollowerSink>,
+                )
+                .unwrap(),
+            )
+        }
+    }
+
+    fn follow_config() -> FollowConfig {
+        FollowConfig {
+            leader_addresses: vec!["leader-1:9080".to_string()],
+            leader_collection: "products".to_string(),
+            poll_interval_ms: 1000,
+        }
+    }
+
+    async fn make_manager(dir: &Path) -> Arc<CollectionManager> {
+        let schemas_dir = dir.join("schemas");
+        std::fs::create_dir_all(&schemas_dir).unwrap();
+        std::fs::write(
+            schemas_dir.join("products.yaml"),
+            r#"
+collection: products
+backends:
+  text:
+    fields:
+      - name: title
+        type: text
+        indexed: true
+        stored: true
+"#,
+        )
+        .unwrap();
+        let text_backend = Arc::new(TextBackend::new(dir.join("data")).unwrap());
+        let vector_backend = Arc::new(VectorBackend::new(dir.join("data")).unwrap());
+        let manager = Arc::new(
+            CollectionManager::new(&schemas_dir, text_backend, vector_backend, None).unwrap(),
+        );
+        manager.initialize().await.unwrap();
+        manager
+    }
+
+    async fn setup(retention: usize) -> Setup {
+        let temp = TempDir::new().unwrap();
+        let manager = make_manager(&temp.path().join("leader")).await;
+        let state = Arc::new(ClusterState::new());
+        state.register_node(NodeInfo {
+            node_id: "leader-1".to_string(),
+            address: "leader-1:9080".to_string(),
+            topology: NodeTopology::default(),
+            healthy: true,
+            shard_count: 0,
+            disk_used_bytes: 0,
+            disk_total_bytes: 0,
+            index_size_bytes: 0,
+            draining: false,
+        });
+        let mut shard = ShardAssignment::new("products", 0, "leader-1");
+        shard.state = ShardState::Active;
+        state.assign_shard(shard);
+
+        let replicator = Arc::new(Replicator::new(
+            "leader-1".to_string(),
+            Arc::clone(&manager),
+            Arc::clone(&state),
+            Arc::new(ShardTransfers::new()),
+            // The shard has no replicas to reach
+            Arc::new(ClientTransport::new(ClusterConfig::default())),
+            ReplicationCheckpoints::in_memory(),
+            &OperationLogConfig {
+                retention_ops: retention,
+                state_dir: None,
+            },
+        ));
+        let follower = make_manager(&temp.path().join("follower")).await;
+        Setup {
+            leader: Arc::new(LoopbackLeader {
+                state,
+                replicator,
+                manager,
+                down: AtomicBool::new(false),
+            }),
+            follower,
+            temp,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_follower_copies_then_tails_leader() {
+        let setup = setup(100).await;
+        setup.index("a").await;
+        setup.index("b").await;
+
+        let ccr = setup.registry();
+        ccr.follow("products", follow_config()).await.unwrap();
+        assert!(ccr.is_following("products"));
+        ccr.pull("products").await.unwrap();
+        assert_eq!(setup.follower_ids(), vec!["a", "b"]);
+
+        setup.index("c").await;
+        setup.write(RpcWriteOp::Delete(vec!["a".to_string()])).await;
+        assert_eq!(ccr.pull("products").await.unwrap(), 2);
+        assert_eq!(setup.follower_ids(), vec!["b", "c"]);
+
+        let status = ccr.status("products").unwrap();
+        assert_eq!(status.state, FollowState::Following);
+        assert_eq!(status.documents_copied, 2);
+        assert_eq!(status.operations_applied, 2);
+        assert_eq!(status.lag_operations, 0);
+        assert_eq!(status.shards[SHARD].checkpoint, 4);
+        assert!(status.time_since_last_read_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_pause_resume_and_promote() {
+        let setup = setup(100).await;
+        let ccr = setup.registry();
+        ccr.follow("products", follow_config()).await.unwrap();
+        ccr.pull("products").await.unwrap();
+
+        ccr.pause("products").await.unwrap();
+        setup.index("a").await;
+        assert_eq!(ccr.pull("products").await.unwrap(), 0);
+        assert!(setup.follower_ids().is_empty());
+        assert_eq!(ccr.status("products").unwrap().state, FollowState::Paused);
+
+        ccr.resume("products").await.unwrap();
+        assert_eq!(ccr.pull("products").await.unwrap(), 1);
+        assert_eq!(setup.follower_ids(), vec!["a"]);
+
+        let status = ccr.promote("products").await.unwrap();
+        assert_eq!(status.state, FollowState::Promoted);
+        assert!(!ccr.is_following("products"));
+        assert!(ccr.pull("products").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_truncated_log_copies_shard_again() {
+        let setup = setup(2).await;
+        let ccr = setup.registry();
+        ccr.follow("products", follow_config()).await.unwrap();
+        ccr.pull("products").await.unwrap();
+
+        for id in ["a", "b", "c", "d"] {
+            setup.index(id).await;
+        }
+        assert_eq!(ccr.pull("products").await.unwrap(), 0);
+        assert_eq!(setup.follower_ids(), vec!["a", "b", "c", "d"]);
+        let status = ccr.status("products").unwrap();
+        assert_eq!(status.documents_copied, 4);
+        assert_eq!(status.shards[SHARD].checkpoint, 4);
+    }
+
+    #[tokio::test]
+    async fn test_progress_survives_restart() {
+        let setup = setup(100).await;
+        setup.index("a").await;
+        {
+            let ccr = setup.registry();
+            ccr.follow("products", follow_config()).await.unwrap();
+            ccr.pull("products").await.unwrap();
+            ccr.pause("products").await.unwrap();
+        }
+
+        let ccr = setup.registry();
+        let status = ccr.status("products").unwrap();
+        assert_eq!(status.state, FollowState::Paused);
+        assert_eq!(status.shards[SHARD].checkpoint, 1);
+
+        // Resumes from the checkpoint rather than copying again
+        ccr.resume("products").await.unwrap();
+        setup.index("b").await;
+        assert_eq!(ccr.pull("products").await.unwrap(), 1);
+        assert_eq!(ccr.status("products").unwrap().documents_copied, 0);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_leader_is_reported() {
+        let setup = setup(100).await;
+        let ccr = setup.registry();
+        ccr.follow("products", follow_config()).await.unwrap();
+        setup.leader.down.store(true, Ordering::SeqCst);
+
+        assert!(ccr.pull("products").await.is_err());
+        let status = ccr.status("products").unwrap();
+        assert!(status.last_error.unwrap().contains("leader is down"));
+
+        setup.leader.down.store(false, Ordering::SeqCst);
+        ccr.pull("products").await.unwrap();
+        assert!(ccr.status("products").unwrap().last_error.is_none());
+    }
+}
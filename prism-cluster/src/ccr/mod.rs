@@ -0,0 +1,224 @@
+//! Cross-cluster replication
+//!
+//! A follower collection on one cluster continuously pulls the writes of a
+//! leader collection on another cluster, for a disaster-recovery site.
+//!
+//! The follower reads the leader's shard layout from any of the leader's
+//! nodes, then pulls each shard's operation log from its primary, starting
+//! after the last sequence number it applied. A shard it has not followed
+//! yet, or whose log no longer holds the operations it is missing, is first
+//! copied whole, as of the primary's checkpoint, and followed from there.
+//! Writes are applied through the follower cluster's own write path, so the
+//! follower's shards may be laid out differently from the leader's.
+//!
+//! The follower is read-only for clients until it is promoted, which stops
+//! following and makes it a regular writable collection. Following can be
+//! paused and resumed, and the progress of each shard is persisted so a
+//! restarted node resumes where it stopped.
+
+mod follower;
+
+pub use follower::{CrossClusterReplication, Follower};
+
+use crate::error::{ClusterError, Result};
+use crate::federation::FederatedSearch;
+use crate::placement::ClusterState;
+use crate::replication::ClientTransport;
+use crate::types::{
+    RpcLeaderShard, RpcOperations, RpcOperationsRequest, RpcShardCheckpoint, RpcShardScan,
+    RpcShardScanRequest, RpcWriteOp,
+};
+use async_trait::async_trait;
+use prism::collection::CollectionManager;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+fn default_poll_interval_ms() -> u64 {
+    1000
+}
+
+/// Where a follower collection pulls writes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FollowConfig {
+    /// Cluster RPC addresses of nodes of the leader cluster, tried in order
+    pub leader_addresses: Vec<String>,
+    /// Name of the collection on the leader cluster
+    pub leader_collection: String,
+    /// Delay between pulls once caught up
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+/// Whether a follower is pulling writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FollowState {
+    /// Pulling writes from the leader
+    Following,
+    /// Not pulling writes until resumed, still read-only
+    Paused,
+    /// No longer following, writable
+    Promoted,
+}
+
+/// How far a follower got in the operation log of a leader shard
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardFollowProgress {
+    /// Term of the leader primary the checkpoint belongs to
+    pub primary_term: u64,
+    /// Highest sequence number applied on the follower
+    pub checkpoint: u64,
+    /// Highest sequence number the leader primary had applied when last read
+    pub leader_checkpoint: u64,
+    /// When the leader's log was last read, in milliseconds since the epoch
+    #[serde(default)]
+    pub last_read_ms: Option<u64>,
+}
+
+impl ShardFollowProgress {
+    /// Operations applied on the leader but not yet on the follower
+    pub fn lag_operations(&self) -> u64 {
+        self.leader_checkpoint.saturating_sub(self.checkpoint)
+    }
+}
+
+/// Progress and lag of a follower collection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowerStatus {
+    pub collection: String,
+    pub leader_collection: String,
+    pub leader_addresses: Vec<String>,
+    pub state: FollowState,
+    /// Progress by leader shard ID
+    pub shards: BTreeMap<String, ShardFollowProgress>,
+    /// Operations behind the leader, over all shards
+    pub lag_operations: u64,
+    /// Time since the least recently read shard was read
+    pub time_since_last_read_ms: Option<u64>,
+    /// Writes applied since this node started following
+    pub operations_applied: u64,
+    /// Documents copied whole from the leader since this node started following
+    pub documents_copied: u64,
+    /// Error of the last pull, if it failed
+    pub last_error: Option<String>,
+}
+
+/// How a follower reaches the leader cluster
+#[async_trait]
+pub trait LeaderTransport: Send + Sync {
+    /// Read the shards of the leader collection from a leader node
+    async fn leader_shards(&self, addr: &str, collection: &str) -> Result<Vec<RpcLeaderShard>>;
+
+    /// Read operations from a leader primary's log
+    async fn get_operations(
+        &self,
+        addr: &str,
+        request: RpcOperationsRequest,
+    ) -> Result<RpcOperations>;
+
+    /// Read the replication progress of a leader primary
+    async fn shard_checkpoint(&self, addr: &str, shard_id: &str) -> Result<RpcShardCheckpoint>;
+
+    /// Read a page of the documents of a leader shard
+    async fn scan_shard(&self, addr: &str, request: RpcShardScanRequest) -> Result<RpcShardScan>;
+}
+
+#[async_trait]
+impl LeaderTransport for ClientTransport {
+    async fn leader_shards(&self, addr: &str, collection: &str) -> Result<Vec<RpcLeaderShard>> {
+        self.client().await?.leader_shards(addr, collection).await
+    }
+
+    async fn get_operations(
+        &self,
+        addr: &str,
+        request: RpcOperationsRequest,
+    ) -> Result<RpcOperations> {
+        self.client().await?.get_operations(addr, request).await
+    }
+
+    async fn shard_checkpoint(&self, addr: &str, shard_id: &str) -> Result<RpcShardCheckpoint> {
+        self.client().await?.shard_checkpoint(addr, shard_id).await
+    }
+
+    async fn scan_shard(&self, addr: &str, request: RpcShardScanRequest) -> Result<RpcShardScan> {
+        self.client().await?.scan_shard(addr, request).await
+    }
+}
+
+/// Where a follower applies the writes pulled from the leader
+#[async_trait]
+pub trait FollowerSink: Send + Sync {
+    /// Apply a write to the follower collection
+    async fn apply(&self, collection: &str, op: RpcWriteOp) -> Result<()>;
+}
+
+/// Writes go through the follower cluster's primaries
+#[async_trait]
+impl FollowerSink for FederatedSearch {
+    async fn apply(&self, collection: &str, op: RpcWriteOp) -> Result<()> {
+        let (succeeded, total) = match op {
+            RpcWriteOp::Index(docs) => {
+                let total = docs.len();
+                let status = self.index(collection, docs).await?;
+                (status.successful_docs, total)
+            }
+            RpcWriteOp::Delete(ids) => {
+                let total = ids.len();
+                let status = self.delete(collection, ids).await?;
+                (status.successful_deletes, total)
+            }
+        };
+        // Writes to a collection without shards are dropped by the router
+        if succeeded < total {
+            return Err(ClusterError::Replication(format!(
+                "{} of {} follower writes to {} failed",
+                total - succeeded,
+                total,
+                collection
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Writes go to the local collection, for a single-node follower
+#[async_trait]
+impl FollowerSink for CollectionManager {
+    async fn apply(&self, collection: &str, op: RpcWriteOp) -> Result<()> {
+        match op {
+            RpcWriteOp::Index(docs) => {
+                self.index(collection, docs.into_iter().map(Into::into).collect())
+                    .await?;
+            }
+            RpcWriteOp::Delete(ids) => self.delete(collection, ids).await?,
+        }
+        Ok(())
+    }
+}
+
+/// Shards of a collection with the addresses of their primaries
+pub fn leader_shards(
+    cluster_state: &ClusterState,
+    collection: &str,
+) -> Result<Vec<RpcLeaderShard>> {
+    let mut shards = cluster_state.get_collection_shards(collection);
+    if shards.is_empty() {
+        return Err(ClusterError::CollectionNotFound(collection.to_string()));
+    }
+    shards.sort_by_key(|s| s.shard_number);
+    shards
+        .into_iter()
+        .map(|shard| {
+            let node = cluster_state
+                .get_node(&shard.primary_node)
+                .ok_or_else(|| ClusterError::NodeUnavailable(shard.primary_node.clone()))?;
+            Ok(RpcLeaderShard {
+                shard_id: shard.shard_id,
+                shard_number: shard.shard_number,
+                primary_node: shard.primary_node,
+                primary_address: node.info.address,
+            })
+        })
+        .collect()
+}
@@ -5,12 +5,17 @@
 //! tarpc client, executes the call, and tears down the stream.
 //! This matches QUIC's design: connections are expensive (TLS handshake),
 //! streams are cheap (single frame to open).
+//!
+//! Every new connection starts with a protocol handshake. RPCs that need an
+//! optional feature fail without being sent if the peer did not negotiate it.
 
 use crate::config::ClusterConfig;
 use crate::error::{ClusterError, Result};
+use crate::metadata::MetadataCommand;
 use crate::metrics::{
     record_connection_established, record_connection_failed, record_connection_pool_size, RpcTimer,
 };
+use crate::protocol::{features, legacy_handshake, local_handshake, negotiate, NegotiatedProtocol};
 use crate::service::PrismClusterClient;
 use crate::transport::make_client_endpoint;
 use crate::types::*;
@@ -33,6 +38,7 @@ struct NodeVersionInfo {
 /// Pooled QUIC connection (long-lived, multiplexed)
 struct PooledConnection {
     connection: quinn::Connection,
+    protocol: NegotiatedProtocol,
     #[allow(dead_code)]
     created_at: std::time::Instant,
 }
@@ -63,13 +69,13 @@ impl ClusterClient {
         &self,
         addr: SocketAddr,
         server_name: &str,
-    ) -> Result<quinn::Connection> {
+    ) -> Result<(quinn::Connection, NegotiatedProtocol)> {
         // Check pool for a live connection
         {
             let connections = self.connections.read();
             if let Some(pooled) = connections.get(&addr) {
                 if pooled.connection.close_reason().is_none() {
-                    return Ok(pooled.connection.clone());
+                    return Ok((pooled.connection.clone(), pooled.protocol.clone()));
                 }
             }
         }
@@ -77,6 +83,16 @@ impl ClusterClient {
         // Create new QUIC connection (TLS handshake)
         let connection = self.create_quic_connection(addr, server_name).await?;
 
+        // Agree on a protocol version before sending any other RPC
+        let protocol = match self.handshake(&connection, addr).await {
+            Ok(protocol) => protocol,
+            Err(e) => {
+                connection.close(0u32.into(), b"protocol handshake failed");
+                record_connection_failed(&addr.to_string(), e.error_type());
+                return Err(e);
+            }
+        };
+
         // Store in pool
         {
             let mut connections = self.connections.write();
@@ -84,13 +100,56 @@ impl ClusterClient {
                 addr,
                 PooledConnection {
                     connection: connection.clone(),
+                    protocol: protocol.clone(),
                     created_at: std::time::Instant::now(),
                 },
             );
             record_connection_pool_size(connections.len());
         }
 
-        Ok(connection)
+        Ok((connection, protocol))
+    }
+
+    /// Exchange protocol versions and features on a new connection.
+    ///
+    /// A node that fails the handshake call predates it and is treated as
+    /// speaking the legacy protocol.
+    async fn handshake(
+        &self,
+        connection: &quinn::Connection,
+        addr: SocketAddr,
+    ) -> Result<NegotiatedProtocol> {
+        let local = local_handshake(&self.config);
+        let (send, recv) = connection.open_bi().await.map_err(|e| {
+            ClusterError::Transport(format!("Failed to open stream to {}: {}", addr, e))
+        })?;
+        let client = Self::make_tarpc_client(send, recv)?;
+
+        let peer = match client.handshake(self.context(), local.clone()).await {
+            Ok(Ok(peer)) => peer,
+            Ok(Err(e)) => return Err(e),
+            Err(tarpc::client::RpcError::DeadlineExceeded) => {
+                return Err(ClusterError::Timeout(format!(
+                    "Protocol handshake with {} timed out",
+                    addr
+                )));
+            }
+            Err(e) => {
+                debug!(
+                    "Protocol handshake with {} failed ({}), assuming legacy protocol",
+                    addr, e
+                );
+                legacy_handshake(&addr.to_string())
+            }
+        };
+
+        let protocol = negotiate(&local, &peer)?;
+        debug!(
+            "Negotiated protocol v{} with {} ({}), features: {:?}",
+            protocol.version, peer.node_id, addr, protocol.features
+        );
+        self.record_node_version(addr, peer.protocol_version, peer.min_supported_version);
+        Ok(protocol)
     }
 
     /// Establish a new QUIC connection (TLS handshake)
@@ -136,7 +195,7 @@ impl ClusterClient {
         addr: SocketAddr,
         server_name: &str,
     ) -> Result<PrismClusterClient> {
-        let connection = self.get_connection(addr, server_name).await?;
+        let (connection, _) = self.get_connection(addr, server_name).await?;
 
         match connection.open_bi().await {
             Ok((send, recv)) => {
@@ -152,7 +211,7 @@ impl ClusterClient {
         }
 
         // Retry with a fresh connection
-        let connection = self.get_connection(addr, server_name).await?;
+        let (connection, _) = self.get_connection(addr, server_name).await?;
         let (send, recv) = connection.open_bi().await.map_err(|e| {
             self.evict_connection(addr);
             ClusterError::Transport(format!("Failed to open stream to {}: {}", addr, e))
@@ -184,6 +243,18 @@ impl ClusterClient {
         record_connection_pool_size(connections.len());
     }
 
+    /// Open an RPC client to a node, failing unless it negotiated a feature
+    async fn new_rpc_client_with(
+        &self,
+        addr: SocketAddr,
+        server_name: &str,
+        feature: &str,
+    ) -> Result<PrismClusterClient> {
+        let (_, protocol) = self.get_connection(addr, server_name).await?;
+        protocol.require(feature, &addr.to_string())?;
+        self.new_rpc_client(addr, server_name).await
+    }
+
     /// Create a context with the configured request timeout
     fn context(&self) -> context::Context {
         let mut ctx = context::current();
@@ -264,6 +335,34 @@ impl ClusterClient {
         }
     }
 
+    /// Collect BM25 term statistics from a remote node
+    pub async fn term_statistics(
+        &self,
+        addr: &str,
+        collection: &str,
+        query: RpcQuery,
+    ) -> Result<prism::backends::TermStatistics> {
+        let timer = RpcTimer::new("term_statistics", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::TERM_STATISTICS)
+            .await?;
+        match client
+            .term_statistics(self.context(), collection.to_string(), query)
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(statistics) => {
+                timer.success();
+                Ok(statistics)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
     /// Get document from a remote node
     pub async fn get(&self, addr: &str, collection: &str, id: &str) -> Result<Option<RpcDocument>> {
         let timer = RpcTimer::new("get", addr);
@@ -552,6 +651,360 @@ impl ClusterClient {
         }
     }
 
+    /// Prepare a remote node to receive a shard
+    pub async fn begin_shard_receive(
+        &self,
+        addr: &str,
+        request: RpcBeginShardReceive,
+    ) -> Result<RpcShardReceiveState> {
+        let timer = RpcTimer::new("begin_shard_receive", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::SHARD_TRANSFER)
+            .await?;
+        match client
+            .begin_shard_receive(self.context(), request)
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(state) => {
+                timer.success();
+                Ok(state)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Send a chunk of a shard file to a remote node
+    pub async fn write_shard_chunk(&self, addr: &str, chunk: RpcShardChunk) -> Result<u64> {
+        let timer = RpcTimer::new("write_shard_chunk", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::SHARD_TRANSFER)
+            .await?;
+        match client
+            .write_shard_chunk(self.context(), chunk)
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(staged) => {
+                timer.success();
+                Ok(staged)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Install a staged shard on a remote node
+    pub async fn complete_shard_receive(
+        &self,
+        addr: &str,
+        transfer_id: &str,
+    ) -> Result<RpcShardInstallResult> {
+        let timer = RpcTimer::new("complete_shard_receive", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::SHARD_TRANSFER)
+            .await?;
+        match client
+            .complete_shard_receive(self.context(), transfer_id.to_string())
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Read a page of the documents of a shard
+    pub async fn scan_shard(
+        &self,
+        addr: &str,
+        request: RpcShardScanRequest,
+    ) -> Result<RpcShardScan> {
+        let timer = RpcTimer::new("scan_shard", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::SHARD_SCAN)
+            .await?;
+        match client
+            .scan_shard(self.context(), request)
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Send a write to the primary of a shard
+    pub async fn primary_write(&self, addr: &str, request: RpcPrimaryWrite) -> Result<RpcWriteAck> {
+        let timer = RpcTimer::new("primary_write", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::REPLICATION)
+            .await?;
+        match client
+            .primary_write(self.context(), request)
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Forward a write to a replica
+    pub async fn replica_write(&self, addr: &str, request: RpcReplicaWrite) -> Result<u64> {
+        let timer = RpcTimer::new("replica_write", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::REPLICATION)
+            .await?;
+        match client
+            .replica_write(self.context(), request)
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Read operations from a primary's log
+    pub async fn get_operations(
+        &self,
+        addr: &str,
+        request: RpcOperationsRequest,
+    ) -> Result<RpcOperations> {
+        let timer = RpcTimer::new("get_operations", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::REPLICATION)
+            .await?;
+        match client
+            .get_operations(self.context(), request)
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Get the replication progress of a node's copy of a shard
+    pub async fn shard_checkpoint(&self, addr: &str, shard_id: &str) -> Result<RpcShardCheckpoint> {
+        let timer = RpcTimer::new("shard_checkpoint", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::REPLICATION)
+            .await?;
+        match client
+            .shard_checkpoint(self.context(), shard_id.to_string())
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Read documents of a shard with their versions
+    pub async fn get_versioned_documents(
+        &self,
+        addr: &str,
+        request: RpcVersionedDocumentsRequest,
+    ) -> Result<Vec<RpcVersionedDocument>> {
+        let timer = RpcTimer::new("get_versioned_documents", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::REPLICATION)
+            .await?;
+        match client
+            .get_versioned_documents(self.context(), request)
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Read the shards of a collection on another cluster
+    pub async fn leader_shards(&self, addr: &str, collection: &str) -> Result<Vec<RpcLeaderShard>> {
+        let timer = RpcTimer::new("leader_shards", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::LEADER_SHARDS)
+            .await?;
+        match client
+            .leader_shards(self.context(), collection.to_string())
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Ask a node for its vote in a metadata leader election
+    pub async fn raft_request_vote(
+        &self,
+        addr: &str,
+        request: RpcRequestVote,
+    ) -> Result<RpcVoteResponse> {
+        let timer = RpcTimer::new("raft_request_vote", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::RAFT)
+            .await?;
+        match client
+            .raft_request_vote(self.context(), request)
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Send metadata log entries to a follower
+    pub async fn raft_append_entries(
+        &self,
+        addr: &str,
+        request: RpcAppendEntries,
+    ) -> Result<RpcAppendEntriesResponse> {
+        let timer = RpcTimer::new("raft_append_entries", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::RAFT)
+            .await?;
+        match client
+            .raft_append_entries(self.context(), request)
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Send a metadata snapshot to a follower, returning its term
+    pub async fn raft_install_snapshot(
+        &self,
+        addr: &str,
+        request: RpcInstallSnapshot,
+    ) -> Result<u64> {
+        let timer = RpcTimer::new("raft_install_snapshot", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::RAFT)
+            .await?;
+        match client
+            .raft_install_snapshot(self.context(), request)
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    /// Forward a metadata change to the leader, returning its log index
+    pub async fn propose_metadata(&self, addr: &str, command: MetadataCommand) -> Result<u64> {
+        let timer = RpcTimer::new("propose_metadata", addr);
+        let (sock_addr, server_name) = Self::resolve_addr(addr).await?;
+        let client = self
+            .new_rpc_client_with(sock_addr, &server_name, features::RAFT)
+            .await?;
+        match client
+            .propose_metadata(self.context(), command)
+            .await
+            .map_err(|e| ClusterError::Transport(e.to_string()))?
+        {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
     /// Record a node's protocol version in the cache
     pub fn record_node_version(&self, addr: SocketAddr, protocol_version: u32, min_supported: u32) {
         self.version_cache.write().insert(
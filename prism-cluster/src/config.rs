@@ -1,6 +1,7 @@
 //! Cluster configuration
 
-use crate::discovery::DiscoveryConfig;
+use crate::discovery::{DiscoveryConfig, GossipDiscovery, NodeDiscovery};
+use crate::error::ClusterError;
 use crate::federation::FederationConfig;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -70,17 +71,29 @@ pub struct ClusterConfig {
     #[serde(default)]
     pub federation: FederationConfig,
 
+    /// Write replication operation log configuration
+    #[serde(default)]
+    pub op_log: OperationLogConfig,
+
+    /// Replicated metadata log configuration
+    #[serde(default)]
+    pub metadata: MetadataConfig,
+
     /// Protocol version this node speaks (for rolling upgrades)
     #[serde(default = "default_protocol_version")]
     pub protocol_version: u32,
 
     /// Minimum protocol version this node can communicate with
-    #[serde(default = "default_protocol_version")]
+    #[serde(default = "default_min_supported_version")]
     pub min_supported_version: u32,
 }
 
 fn default_protocol_version() -> u32 {
-    1
+    crate::protocol::PROTOCOL_VERSION
+}
+
+fn default_min_supported_version() -> u32 {
+    crate::protocol::MIN_SUPPORTED_VERSION
 }
 
 fn default_node_id() -> String {
@@ -121,8 +134,10 @@ impl Default for ClusterConfig {
             discovery: DiscoveryConfig::default(),
             consistency: ConsistencyConfig::default(),
             federation: FederationConfig::default(),
+            op_log: OperationLogConfig::default(),
+            metadata: MetadataConfig::default(),
             protocol_version: default_protocol_version(),
-            min_supported_version: default_protocol_version(),
+            min_supported_version: default_min_supported_version(),
         }
     }
 }
@@ -194,6 +209,37 @@ impl ClusterConfig {
     pub fn request_timeout(&self) -> std::time::Duration {
         std::time::Duration::from_millis(self.request_timeout_ms)
     }
+
+    /// Create the configured node discovery for this node
+    pub fn create_discovery(&self) -> Result<Box<dyn NodeDiscovery>, ClusterError> {
+        match &self.discovery {
+            DiscoveryConfig::Gossip(gossip) => {
+                let cluster_addr =
+                    std::net::ToSocketAddrs::to_socket_addrs(self.advertise_address())
+                        .map_err(|e| {
+                            ClusterError::Discovery(format!(
+                                "Failed to resolve {}: {}",
+                                self.advertise_address(),
+                                e
+                            ))
+                        })?
+                        .next()
+                        .ok_or_else(|| {
+                            ClusterError::Discovery(format!(
+                                "No address found for {}",
+                                self.advertise_address()
+                            ))
+                        })?;
+                Ok(Box::new(GossipDiscovery::new(
+                    gossip.clone(),
+                    self.node_id.clone(),
+                    cluster_addr,
+                    Some(self.topology.zone.clone()).filter(|zone| !zone.is_empty()),
+                )))
+            }
+            other => other.create_discovery(),
+        }
+    }
 }
 
 /// Node topology for zone-aware shard placement
@@ -252,6 +298,86 @@ impl NodeTopology {
     }
 }
 
+/// Operation log kept by shard primaries for replicas to catch up from
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OperationLogConfig {
+    /// Operations retained per shard; a replica further behind cannot
+    /// catch up from the log
+    #[serde(default = "default_op_log_retention")]
+    pub retention_ops: usize,
+
+    /// Directory for replication checkpoints and operation logs (default:
+    /// system temp dir)
+    #[serde(default)]
+    pub state_dir: Option<PathBuf>,
+}
+
+fn default_op_log_retention() -> usize {
+    10_000
+}
+
+impl Default for OperationLogConfig {
+    fn default() -> Self {
+        Self {
+            retention_ops: default_op_log_retention(),
+            state_dir: None,
+        }
+    }
+}
+
+/// Raft log replicating cluster metadata between the nodes
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MetadataConfig {
+    /// Time in milliseconds without hearing from a leader before a node
+    /// stands for election, randomized up to twice this value
+    #[serde(default = "default_election_timeout")]
+    pub election_timeout_ms: u64,
+
+    /// Interval in milliseconds between leader heartbeats
+    #[serde(default = "default_raft_heartbeat_interval")]
+    pub heartbeat_interval_ms: u64,
+
+    /// Log entries kept before the log is compacted into a snapshot
+    #[serde(default = "default_snapshot_threshold")]
+    pub snapshot_threshold: usize,
+
+    /// Maximum entries sent to a follower in one request
+    #[serde(default = "default_max_append_entries")]
+    pub max_append_entries: usize,
+
+    /// Directory for the metadata log (default: system temp dir)
+    #[serde(default)]
+    pub state_dir: Option<PathBuf>,
+}
+
+fn default_election_timeout() -> u64 {
+    1000
+}
+
+fn default_raft_heartbeat_interval() -> u64 {
+    200
+}
+
+fn default_snapshot_threshold() -> usize {
+    1000
+}
+
+fn default_max_append_entries() -> usize {
+    256
+}
+
+impl Default for MetadataConfig {
+    fn default() -> Self {
+        Self {
+            election_timeout_ms: default_election_timeout(),
+            heartbeat_interval_ms: default_raft_heartbeat_interval(),
+            snapshot_threshold: default_snapshot_threshold(),
+            max_append_entries: default_max_append_entries(),
+            state_dir: None,
+        }
+    }
+}
+
 /// Configuration for automatic shard rebalancing
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RebalancingConfig {
@@ -275,6 +401,15 @@ pub struct RebalancingConfig {
     /// Minimum time between rebalance operations (in seconds)
     #[serde(default = "default_rebalance_cooldown")]
     pub cooldown_secs: u64,
+
+    /// Size of the chunks shard files are sent in
+    #[serde(default = "default_transfer_chunk_bytes")]
+    pub transfer_chunk_bytes: usize,
+
+    /// Directory where incoming shard files are staged
+    /// (defaults to a directory under the system temp dir)
+    #[serde(default)]
+    pub transfer_staging_dir: Option<PathBuf>,
 }
 
 fn default_rebalancing_enabled() -> bool {
@@ -297,6 +432,10 @@ fn default_rebalance_cooldown() -> u64 {
     300 // 5 minutes
 }
 
+fn default_transfer_chunk_bytes() -> usize {
+    262_144 // 256 KiB
+}
+
 impl Default for RebalancingConfig {
     fn default() -> Self {
         Self {
@@ -305,6 +444,8 @@ impl Default for RebalancingConfig {
             max_concurrent_moves: default_max_concurrent_moves(),
             max_bytes_per_sec: default_max_bytes_per_sec(),
             cooldown_secs: default_rebalance_cooldown(),
+            transfer_chunk_bytes: default_transfer_chunk_bytes(),
+            transfer_staging_dir: None,
         }
     }
 }
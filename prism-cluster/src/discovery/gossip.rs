@@ -0,0 +1,972 @@
+//! SWIM gossip-based node discovery
+//!
+//! Nodes find each other and detect failures by gossiping over UDP, so no
+//! node needs the full member list up front: joining only takes the
+//! address of any one member.
+//!
+//! Every probe interval a node pings the next member in a shuffled round.
+//! Without an ack within the probe timeout it asks a few other members to
+//! ping the target on its behalf (indirect probes), so a single lossy link
+//! does not get a node declared failed. A target that answers neither way
+//! becomes suspect, and dead once the suspicion timeout passes without it
+//! refuting the suspicion by gossiping a higher incarnation.
+//!
+//! Membership changes are piggybacked on pings and acks and retransmitted a
+//! number of times growing with the logarithm of the cluster size.
+//!
+//! Configure discovery with:
+//! ```toml
+//! [cluster.discovery]
+//! backend = "gossip"
+//! bind_addr = "0.0.0.0:9081"
+//! seeds = ["node1:9081"]
+//! ```
+
+use super::{ClusterEvent, DiscoveredNode, NodeDiscovery};
+use crate::error::ClusterError;
+use async_trait::async_trait;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// Largest gossip datagram
+const MAX_PACKET_BYTES: usize = 65_507;
+
+/// Members told directly when a node leaves
+const LEAVE_FANOUT: usize = 3;
+
+/// Gossip discovery configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GossipConfig {
+    /// UDP address to gossip on
+    #[serde(default = "default_gossip_bind_addr")]
+    pub bind_addr: String,
+
+    /// Gossip address to advertise to other nodes (defaults to the bound address)
+    #[serde(default)]
+    pub advertise_addr: Option<String>,
+
+    /// Gossip addresses of members to join through; any one live member is enough
+    #[serde(default)]
+    pub seeds: Vec<String>,
+
+    /// Time between probes in milliseconds
+    #[serde(default = "default_probe_interval")]
+    pub probe_interval_ms: u64,
+
+    /// Time to wait for a direct ack before probing indirectly, in milliseconds
+    #[serde(default = "default_probe_timeout")]
+    pub probe_timeout_ms: u64,
+
+    /// Members asked to probe a node that did not ack directly
+    #[serde(default = "default_indirect_probes")]
+    pub indirect_probes: usize,
+
+    /// Time a node stays suspect before it is declared dead, in milliseconds
+    #[serde(default = "default_suspicion_timeout")]
+    pub suspicion_timeout_ms: u64,
+
+    /// Membership updates piggybacked on each message
+    #[serde(default = "default_max_piggyback")]
+    pub max_piggyback: usize,
+
+    /// Retransmissions of each update, multiplied by log2 of the member count
+    #[serde(default = "default_retransmit_multiplier")]
+    pub retransmit_multiplier: usize,
+}
+
+fn default_gossip_bind_addr() -> String {
+    "0.0.0.0:9081".to_string()
+}
+
+fn default_probe_interval() -> u64 {
+    1000
+}
+
+fn default_probe_timeout() -> u64 {
+    500
+}
+
+fn default_indirect_probes() -> usize {
+    3
+}
+
+fn default_suspicion_timeout() -> u64 {
+    5000
+}
+
+fn default_max_piggyback() -> usize {
+    16
+}
+
+fn default_retransmit_multiplier() -> usize {
+    3
+}
+
+impl Default for GossipConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: default_gossip_bind_addr(),
+            advertise_addr: None,
+            seeds: Vec::new(),
+            probe_interval_ms: default_probe_interval(),
+            probe_timeout_ms: default_probe_timeout(),
+            indirect_probes: default_indirect_probes(),
+            suspicion_timeout_ms: default_suspicion_timeout(),
+            max_piggyback: default_max_piggyback(),
+            retransmit_multiplier: default_retransmit_multiplier(),
+        }
+    }
+}
+
+/// State of a member as gossiped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberState {
+    Alive,
+    Suspect,
+    Dead,
+    /// Left the cluster on purpose
+    Left,
+}
+
+impl MemberState {
+    fn is_live(self) -> bool {
+        matches!(self, MemberState::Alive | MemberState::Suspect)
+    }
+}
+
+/// What a node gossips about a member
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemberUpdate {
+    pub node_id: String,
+    /// Address the member gossips on
+    pub gossip_addr: SocketAddr,
+    /// Address of the member's cluster RPC server
+    pub cluster_addr: SocketAddr,
+    pub zone: Option<String>,
+    /// Raised by the member to refute suspicion; newer incarnations win
+    pub incarnation: u64,
+    pub state: MemberState,
+}
+
+impl MemberUpdate {
+    /// Whether this update replaces what is known at `current`
+    fn overrides(&self, current: &MemberUpdate) -> bool {
+        match self.state {
+            MemberState::Alive => self.incarnation > current.incarnation,
+            MemberState::Suspect => match current.state {
+                MemberState::Alive => self.incarnation >= current.incarnation,
+                MemberState::Suspect => self.incarnation > current.incarnation,
+                MemberState::Dead | MemberState::Left => false,
+            },
+            MemberState::Dead | MemberState::Left => {
+                current.state.is_live() && self.incarnation >= current.incarnation
+            }
+        }
+    }
+
+    fn discovered(&self) -> DiscoveredNode {
+        DiscoveredNode {
+            address: self.cluster_addr,
+            node_id: Some(self.node_id.clone()),
+            zone: self.zone.clone(),
+            priority: 0,
+            weight: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum GossipMessage {
+    Ping {
+        seq: u64,
+    },
+    /// Ask the receiver to ping `target` and relay its ack
+    PingReq {
+        seq: u64,
+        target: SocketAddr,
+    },
+    Ack {
+        seq: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GossipPacket {
+    /// The sending member as of this message
+    sender: MemberUpdate,
+    message: GossipMessage,
+    /// Piggybacked membership updates
+    updates: Vec<MemberUpdate>,
+}
+
+struct Member {
+    update: MemberUpdate,
+    /// When the member entered its current state
+    since: Instant,
+}
+
+struct Broadcast {
+    update: MemberUpdate,
+    transmits_left: usize,
+}
+
+/// Who is waiting for an ack
+enum PendingAck {
+    /// This node's own probe
+    Probe(oneshot::Sender<()>),
+    /// A probe made for another node, whose ack is relayed to it
+    Relay { requester: SocketAddr, seq: u64 },
+}
+
+struct Gossip {
+    config: GossipConfig,
+    socket: OnceLock<Arc<UdpSocket>>,
+    local: RwLock<MemberUpdate>,
+    members: RwLock<HashMap<String, Member>>,
+    broadcasts: Mutex<Vec<Broadcast>>,
+    pending: Mutex<HashMap<u64, PendingAck>>,
+    /// Members left to probe in the current round
+    probe_order: Mutex<Vec<String>>,
+    next_seq: AtomicU64,
+    event_tx: broadcast::Sender<ClusterEvent>,
+}
+
+/// SWIM gossip discovery
+pub struct GossipDiscovery {
+    gossip: Arc<Gossip>,
+
+    /// Whether the gossip tasks are running
+    running: AtomicBool,
+
+    /// Receive and probe task handles
+    task_handles: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl GossipDiscovery {
+    /// Create gossip discovery for the node with the given id and cluster
+    /// RPC address
+    pub fn new(
+        config: GossipConfig,
+        node_id: impl Into<String>,
+        cluster_addr: SocketAddr,
+        zone: Option<String>,
+    ) -> Self {
+        let (event_tx, _) = broadcast::channel(64);
+        // A restarted node starts with a newer incarnation than the one it
+        // was declared dead with
+        let incarnation = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        Self {
+            gossip: Arc::new(Gossip {
+                config,
+                socket: OnceLock::new(),
+                local: RwLock::new(MemberUpdate {
+                    node_id: node_id.into(),
+                    gossip_addr: cluster_addr,
+                    cluster_addr,
+                    zone,
+                    incarnation,
+                    state: MemberState::Alive,
+                }),
+                members: RwLock::new(HashMap::new()),
+                broadcasts: Mutex::new(Vec::new()),
+                pending: Mutex::new(HashMap::new()),
+                probe_order: Mutex::new(Vec::new()),
+                next_seq: AtomicU64::new(1),
+                event_tx,
+            }),
+            running: AtomicBool::new(false),
+            task_handles: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Address this node gossips on, once started
+    pub fn gossip_addr(&self) -> Option<SocketAddr> {
+        self.gossip
+            .socket
+            .get()
+            .map(|_| self.gossip.local.read().gossip_addr)
+    }
+
+    /// Gossiped state of every other member, including dead ones
+    pub fn members(&self) -> Vec<MemberUpdate> {
+        self.gossip
+            .members
+            .read()
+            .values()
+            .map(|m| m.update.clone())
+            .collect()
+    }
+
+    /// Stop gossiping without telling other members, as a crash would
+    fn halt(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        for handle in self.task_handles.lock().drain(..) {
+            handle.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl NodeDiscovery for GossipDiscovery {
+    async fn get_nodes(&self) -> Result<Vec<DiscoveredNode>, ClusterError> {
+        Ok(self
+            .gossip
+            .members
+            .read()
+            .values()
+            .filter(|m| m.update.state.is_live())
+            .map(|m| m.update.discovered())
+            .collect())
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<ClusterEvent> {
+        self.gossip.event_tx.subscribe()
+    }
+
+    async fn refresh(&self) -> Result<(), ClusterError> {
+        self.gossip.join().await;
+        let node_count = self.get_nodes().await?.len();
+        let _ = self
+            .gossip
+            .event_tx
+            .send(ClusterEvent::RefreshComplete { node_count });
+        Ok(())
+    }
+
+    async fn start(&self) -> Result<(), ClusterError> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Ok(()); // Already running
+        }
+
+        let socket = UdpSocket::bind(&self.gossip.config.bind_addr)
+            .await
+            .map_err(|e| {
+                self.running.store(false, Ordering::SeqCst);
+                ClusterError::Discovery(format!(
+                    "Failed to bind gossip socket {}: {}",
+                    self.gossip.config.bind_addr, e
+                ))
+            })?;
+        let gossip_addr = match &self.gossip.config.advertise_addr {
+            Some(addr) => resolve(addr).await?,
+            None => socket.local_addr()?,
+        };
+        self.gossip.local.write().gossip_addr = gossip_addr;
+        if self.gossip.socket.set(Arc::new(socket)).is_err() {
+            return Err(ClusterError::Discovery(
+                "Gossip discovery cannot be restarted".into(),
+            ));
+        }
+        info!(
+            gossip_addr = %gossip_addr,
+            seeds = self.gossip.config.seeds.len(),
+            "Starting gossip discovery"
+        );
+
+        let receiver = tokio::spawn(Arc::clone(&self.gossip).receive_loop());
+        let prober = tokio::spawn(Arc::clone(&self.gossip).probe_loop());
+        self.task_handles.lock().extend([receiver, prober]);
+
+        self.gossip.join().await;
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<(), ClusterError> {
+        if !self.running.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        self.halt();
+        self.gossip.leave().await;
+        Ok(())
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "gossip"
+    }
+}
+
+impl Drop for GossipDiscovery {
+    fn drop(&mut self) {
+        self.halt();
+    }
+}
+
+impl Gossip {
+    fn probe_interval(&self) -> Duration {
+        Duration::from_millis(self.config.probe_interval_ms)
+    }
+
+    fn probe_timeout(&self) -> Duration {
+        Duration::from_millis(self.config.probe_timeout_ms)
+    }
+
+    /// Ping the seeds so they learn about this node and gossip it on
+    async fn join(&self) {
+        let local_addr = self.local.read().gossip_addr;
+        for seed in &self.config.seeds {
+            match resolve(seed).await {
+                Ok(addr) if addr == local_addr => {}
+                Ok(addr) => {
+                    let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+                    self.send(addr, GossipMessage::Ping { seq }).await;
+                }
+                Err(e) => warn!(seed = %seed, error = %e, "Failed to resolve gossip seed"),
+            }
+        }
+    }
+
+    /// Tell a few members this node is leaving
+    async fn leave(&self) {
+        let update = {
+            let mut local = self.local.write();
+            local.incarnation += 1;
+            local.state = MemberState::Left;
+            local.clone()
+        };
+        self.queue(update);
+        let targets = self.random_live_members(LEAVE_FANOUT, None);
+        for target in targets {
+            let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+            self.send(target.gossip_addr, GossipMessage::Ping { seq })
+                .await;
+        }
+        info!("Left the gossip cluster");
+    }
+
+    async fn receive_loop(self: Arc<Self>) {
+        let Some(socket) = self.socket.get().cloned() else {
+            return;
+        };
+        let mut buf = vec![0u8; MAX_PACKET_BYTES];
+        loop {
+            let (len, from) = match socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                Err(e) => {
+                    debug!(error = %e, "Gossip receive failed");
+                    continue;
+                }
+            };
+            match serde_json::from_slice::<GossipPacket>(&buf[..len]) {
+                Ok(packet) => Arc::clone(&self).handle(from, packet).await,
+                Err(e) => debug!(from = %from, error = %e, "Ignoring malformed gossip packet"),
+            }
+        }
+    }
+
+    async fn handle(self: Arc<Self>, from: SocketAddr, packet: GossipPacket) {
+        self.merge(packet.sender);
+        for update in packet.updates {
+            self.merge(update);
+        }
+
+        match packet.message {
+            GossipMessage::Ping { seq } => {
+                self.send(from, GossipMessage::Ack { seq }).await;
+            }
+            GossipMessage::PingReq { seq, target } => {
+                let relay_seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+                self.pending.lock().insert(
+                    relay_seq,
+                    PendingAck::Relay {
+                        requester: from,
+                        seq,
+                    },
+                );
+                self.send(target, GossipMessage::Ping { seq: relay_seq })
+                    .await;
+                // Forget the relay once the requester stopped waiting
+                let gossip = Arc::clone(&self);
+                tokio::spawn(async move {
+                    tokio::time::sleep(gossip.probe_interval()).await;
+                    gossip.pending.lock().remove(&relay_seq);
+                });
+            }
+            GossipMessage::Ack { seq } => {
+                let pending = self.pending.lock().remove(&seq);
+                match pending {
+                    Some(PendingAck::Probe(waiter)) => {
+                        let _ = waiter.send(());
+                    }
+                    Some(PendingAck::Relay { requester, seq }) => {
+                        self.send(requester, GossipMessage::Ack { seq }).await;
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+
+    async fn probe_loop(self: Arc<Self>) {
+        let mut ticker = tokio::time::interval(self.probe_interval());
+        loop {
+            ticker.tick().await;
+            if let Some(target) = self.next_probe_target() {
+                if !self.probe(&target).await {
+                    self.suspect(&target);
+                }
+            }
+            self.expire_suspects();
+        }
+    }
+
+    /// Probe a member directly, then through others, returning whether it
+    /// acked
+    async fn probe(&self, target: &MemberUpdate) -> bool {
+        let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+        let (tx, mut rx) = oneshot::channel();
+        self.pending.lock().insert(seq, PendingAck::Probe(tx));
+
+        self.send(target.gossip_addr, GossipMessage::Ping { seq })
+            .await;
+        let acked = tokio::time::timeout(self.probe_timeout(), &mut rx)
+            .await
+            .is_ok_and(|r| r.is_ok());
+        if acked {
+            return true;
+        }
+
+        let helpers = self.random_live_members(self.config.indirect_probes, Some(&target.node_id));
+        debug!(
+            node_id = %target.node_id,
+            helpers = helpers.len(),
+            "No direct ack, probing indirectly"
+        );
+        for helper in &helpers {
+            self.send(
+                helper.gossip_addr,
+                GossipMessage::PingReq {
+                    seq,
+                    target: target.gossip_addr,
+                },
+            )
+            .await;
+        }
+        let remaining = self.probe_interval().saturating_sub(self.probe_timeout());
+        let acked = tokio::time::timeout(remaining, rx)
+            .await
+            .is_ok_and(|r| r.is_ok());
+        self.pending.lock().remove(&seq);
+        acked
+    }
+
+    /// Next live member of the current round, starting a new shuffled
+    /// round once every member was probed
+    fn next_probe_target(&self) -> Option<MemberUpdate> {
+        let mut order = self.probe_order.lock();
+        loop {
+            if order.is_empty() {
+                *order = self
+                    .members
+                    .read()
+                    .values()
+                    .filter(|m| m.update.state.is_live())
+                    .map(|m| m.update.node_id.clone())
+                    .collect();
+                shuffle(&mut order);
+                if order.is_empty() {
+                    return None;
+                }
+            }
+            let node_id = order.pop()?;
+            if let Some(member) = self.members.read().get(&node_id) {
+                if member.update.state.is_live() {
+                    return Some(member.update.clone());
+                }
+            }
+        }
+    }
+
+    /// Up to `count` random live members other than `except`
+    fn random_live_members(&self, count: usize, except: Option<&str>) -> Vec<MemberUpdate> {
+        let mut candidates: Vec<MemberUpdate> = self
+            .members
+            .read()
+            .values()
+            .filter(|m| m.update.state == MemberState::Alive)
+            .filter(|m| Some(m.update.node_id.as_str()) != except)
+            .map(|m| m.update.clone())
+            .collect();
+        shuffle(&mut candidates);
+        candidates.truncate(count);
+        candidates
+    }
+
+    fn suspect(&self, target: &MemberUpdate) {
+        let current = self
+            .members
+            .read()
+            .get(&target.node_id)
+            .map(|m| m.update.clone());
+        if let Some(current) = current.filter(|m| m.state == MemberState::Alive) {
+            warn!(node_id = %current.node_id, "Member did not ack probes, suspecting it");
+            self.merge(MemberUpdate {
+                state: MemberState::Suspect,
+                ..current
+            });
+        }
+    }
+
+    /// Declare dead the members suspect for longer than the suspicion timeout
+    fn expire_suspects(&self) {
+        let timeout = Duration::from_millis(self.config.suspicion_timeout_ms);
+        let expired: Vec<MemberUpdate> = self
+            .members
+            .read()
+            .values()
+            .filter(|m| m.update.state == MemberState::Suspect && m.since.elapsed() >= timeout)
+            .map(|m| m.update.clone())
+            .collect();
+        for member in expired {
+            warn!(node_id = %member.node_id, "Suspect member timed out, declaring it dead");
+            self.merge(MemberUpdate {
+                state: MemberState::Dead,
+                ..member
+            });
+        }
+    }
+
+    /// Apply a membership update, gossiping it on and emitting events if it
+    /// changes what this node knows
+    fn merge(&self, update: MemberUpdate) {
+        // Refute suspicion of this node with a newer incarnation
+        let refutation = {
+            let mut local = self.local.write();
+            if update.node_id != local.node_id {
+                None
+            } else if update.state != MemberState::Alive
+                && local.state == MemberState::Alive
+                && update.incarnation >= local.incarnation
+            {
+                local.incarnation = update.incarnation + 1;
+                Some(local.clone())
+            } else {
+                return;
+            }
+        };
+        if let Some(refutation) = refutation {
+            info!(
+                incarnation = refutation.incarnation,
+                "Refuting suspicion of this node"
+            );
+            self.queue(refutation);
+            return;
+        }
+
+        let (previous, is_new) = {
+            let mut members = self.members.write();
+            match members.get_mut(&update.node_id) {
+                Some(member) if !update.overrides(&member.update) => return,
+                Some(member) => {
+                    let previous = member.update.state;
+                    if previous != update.state {
+                        member.since = Instant::now();
+                    }
+                    member.update = update.clone();
+                    (Some(previous), false)
+                }
+                None => {
+                    members.insert(
+                        update.node_id.clone(),
+                        Member {
+                            update: update.clone(),
+                            since: Instant::now(),
+                        },
+                    );
+                    (None, true)
+                }
+            }
+        };
+
+        let was_live = previous.is_some_and(MemberState::is_live);
+        if update.state.is_live() && !was_live {
+            info!(node_id = %update.node_id, address = %update.cluster_addr, "Member joined");
+            let _ = self
+                .event_tx
+                .send(ClusterEvent::NodeJoined(update.discovered()));
+        } else if !update.state.is_live() && was_live {
+            info!(node_id = %update.node_id, state = ?update.state, "Member left");
+            let _ = self
+                .event_tx
+                .send(ClusterEvent::NodeLeft(update.cluster_addr));
+        } else if previous != Some(update.state) {
+            debug!(node_id = %update.node_id, state = ?update.state, "Member state changed");
+        }
+
+        self.queue(update);
+        if is_new {
+            // Bring the new member up to date with everyone known
+            let known: Vec<MemberUpdate> = self
+                .members
+                .read()
+                .values()
+                .map(|m| m.update.clone())
+                .collect();
+            for member in known {
+                self.queue(member);
+            }
+            let local = self.local.read().clone();
+            self.queue(local);
+        }
+    }
+
+    /// Gossip an update on, replacing older updates about the same member
+    fn queue(&self, update: MemberUpdate) {
+        let member_count = self.members.read().len() + 1;
+        let transmits = self.config.retransmit_multiplier.max(1)
+            * (usize::BITS - member_count.leading_zeros()) as usize;
+        let mut broadcasts = self.broadcasts.lock();
+        broadcasts.retain(|b| b.update.node_id != update.node_id);
+        broadcasts.push(Broadcast {
+            update,
+            transmits_left: transmits,
+        });
+    }
+
+    /// Updates to piggyback on the next message, least sent first
+    fn take_broadcasts(&self) -> Vec<MemberUpdate> {
+        let mut broadcasts = self.broadcasts.lock();
+        broadcasts.sort_by_key(|b| std::cmp::Reverse(b.transmits_left));
+        let updates = broadcasts
+            .iter_mut()
+            .take(self.config.max_piggyback)
+            .map(|b| {
+                b.transmits_left -= 1;
+                b.update.clone()
+            })
+            .collect();
+        broadcasts.retain(|b| b.transmits_left > 0);
+        updates
+    }
+
+    async fn send(&self, to: SocketAddr, message: GossipMessage) {
+        let Some(socket) = self.socket.get() else {
+            return;
+        };
+        let packet = GossipPacket {
+            sender: self.local.read().clone(),
+            message,
+            updates: self.take_broadcasts(),
+        };
+        match serde_json::to_vec(&packet) {
+            Ok(data) => {
+                if let Err(e) = socket.send_to(&data, to).await {
+                    debug!(to = %to, error = %e, "Gossip send failed");
+                }
+            }
+            Err(e) => warn!(error = %e, "Failed to encode gossip packet"),
+        }
+    }
+}
+
+async fn resolve(addr: &str) -> Result<SocketAddr, ClusterError> {
+    tokio::net::lookup_host(addr)
+        .await
+        .map_err(|e| ClusterError::Discovery(format!("Failed to resolve {}: {}", addr, e)))?
+        .next()
+        .ok_or_else(|| ClusterError::Discovery(format!("No address found for {}", addr)))
+}
+
+/// Shuffle in place with uuid randomness
+fn shuffle<T>(items: &mut [T]) {
+    for i in (1..items.len()).rev() {
+        let j = (uuid::Uuid::new_v4().as_u128() % (i as u128 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(seeds: Vec<String>) -> GossipConfig {
+        GossipConfig {
+            bind_addr: "127.0.0.1:0".to_string(),
+            seeds,
+            probe_interval_ms: 50,
+            probe_timeout_ms: 20,
+            suspicion_timeout_ms: 200,
+            ..Default::default()
+        }
+    }
+
+    fn cluster_addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    async fn start_node(id: &str, port: u16, seeds: Vec<String>) -> GossipDiscovery {
+        let node = GossipDiscovery::new(config(seeds), id, cluster_addr(port), None);
+        node.start().await.unwrap();
+        node
+    }
+
+    async fn live_ids(node: &GossipDiscovery) -> Vec<String> {
+        let mut ids: Vec<String> = node
+            .get_nodes()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|n| n.node_id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Poll until `node` sees exactly the live members `expected`
+    async fn wait_for_members(node: &GossipDiscovery, expected: &[&str]) {
+        for _ in 0..200 {
+            if live_ids(node).await == expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!(
+            "expected members {:?}, saw {:?}",
+            expected,
+            live_ids(node).await
+        );
+    }
+
+    fn member(state: MemberState, incarnation: u64) -> MemberUpdate {
+        MemberUpdate {
+            node_id: "node-2".to_string(),
+            gossip_addr: cluster_addr(9081),
+            cluster_addr: cluster_addr(9080),
+            zone: None,
+            incarnation,
+            state,
+        }
+    }
+
+    #[test]
+    fn test_update_precedence() {
+        let alive = member(MemberState::Alive, 1);
+        assert!(member(MemberState::Suspect, 1).overrides(&alive));
+        assert!(!member(MemberState::Alive, 1).overrides(&alive));
+        assert!(member(MemberState::Alive, 2).overrides(&member(MemberState::Suspect, 1)));
+        assert!(member(MemberState::Dead, 1).overrides(&member(MemberState::Suspect, 1)));
+        assert!(!member(MemberState::Suspect, 0).overrides(&alive));
+
+        // Only a newer incarnation brings a dead member back
+        let dead = member(MemberState::Dead, 1);
+        assert!(!member(MemberState::Suspect, 5).overrides(&dead));
+        assert!(!member(MemberState::Alive, 1).overrides(&dead));
+        assert!(member(MemberState::Alive, 2).overrides(&dead));
+    }
+
+    #[tokio::test]
+    async fn test_suspicion_of_self_is_refuted() {
+        let node = GossipDiscovery::new(config(vec![]), "node-1", cluster_addr(9080), None);
+        let local = node.gossip.local.read().clone();
+
+        node.gossip.merge(MemberUpdate {
+            state: MemberState::Suspect,
+            ..local.clone()
+        });
+
+        let refuted = node.gossip.local.read().clone();
+        assert_eq!(refuted.state, MemberState::Alive);
+        assert_eq!(refuted.incarnation, local.incarnation + 1);
+        assert!(node
+            .gossip
+            .take_broadcasts()
+            .iter()
+            .any(|u| u.node_id == "node-1" && u.incarnation == refuted.incarnation));
+        assert!(node.members().is_empty());
+    }
+
+    #[test]
+    fn test_config_defaults() {
+        let config: GossipConfig = serde_json::from_str(r#"{"seeds": ["node1:9081"]}"#).unwrap();
+        assert_eq!(config.bind_addr, "0.0.0.0:9081");
+        assert_eq!(config.seeds, vec!["node1:9081"]);
+        assert_eq!(config.indirect_probes, 3);
+        assert_eq!(config.suspicion_timeout_ms, 5000);
+    }
+
+    #[tokio::test]
+    async fn test_nodes_join_through_any_member() {
+        let node1 = start_node("node-1", 9001, vec![]).await;
+        let mut events = node1.subscribe();
+        let seed1 = node1.gossip_addr().unwrap().to_string();
+        let node2 = start_node("node-2", 9002, vec![seed1]).await;
+        // node-3 only knows node-2
+        let seed2 = node2.gossip_addr().unwrap().to_string();
+        let node3 = start_node("node-3", 9003, vec![seed2]).await;
+
+        wait_for_members(&node1, &["node-2", "node-3"]).await;
+        wait_for_members(&node2, &["node-1", "node-3"]).await;
+        wait_for_members(&node3, &["node-1", "node-2"]).await;
+
+        let mut joined = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let ClusterEvent::NodeJoined(node) = event {
+                joined.push((node.node_id.unwrap(), node.address));
+            }
+        }
+        joined.sort();
+        assert_eq!(
+            joined,
+            vec![
+                ("node-2".to_string(), cluster_addr(9002)),
+                ("node-3".to_string(), cluster_addr(9003)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_node_is_declared_dead() {
+        let node1 = start_node("node-1", 9001, vec![]).await;
+        let seed = node1.gossip_addr().unwrap().to_string();
+        let node2 = start_node("node-2", 9002, vec![seed.clone()]).await;
+        let node3 = start_node("node-3", 9003, vec![seed]).await;
+        wait_for_members(&node1, &["node-2", "node-3"]).await;
+        let mut events = node1.subscribe();
+
+        // A crash sends nothing; probes and suspicion detect it
+        node3.halt();
+        wait_for_members(&node1, &["node-2"]).await;
+        wait_for_members(&node2, &["node-1"]).await;
+
+        let left = loop {
+            match events.recv().await.unwrap() {
+                ClusterEvent::NodeLeft(addr) => break addr,
+                _ => continue,
+            }
+        };
+        assert_eq!(left, cluster_addr(9003));
+        assert!(node1
+            .members()
+            .iter()
+            .any(|m| m.node_id == "node-3" && m.state == MemberState::Dead));
+    }
+
+    #[tokio::test]
+    async fn test_leaving_node_is_removed() {
+        let node1 = start_node("node-1", 9001, vec![]).await;
+        let seed = node1.gossip_addr().unwrap().to_string();
+        let node2 = start_node("node-2", 9002, vec![seed]).await;
+        wait_for_members(&node1, &["node-2"]).await;
+
+        node2.stop().await.unwrap();
+        wait_for_members(&node1, &[]).await;
+        assert!(node1
+            .members()
+            .iter()
+            .any(|m| m.node_id == "node-2" && m.state == MemberState::Left));
+    }
+}
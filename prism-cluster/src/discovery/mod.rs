@@ -6,6 +6,8 @@
 //!
 //! - **Static**: Manual list of node addresses in configuration
 //! - **DNS**: SRV record-based discovery (ideal for Kubernetes)
+//! - **Gossip**: SWIM membership and failure detection over UDP, joining
+//!   through any live member
 //!
 //! # Example
 //!
@@ -30,9 +32,11 @@
 //! ```
 
 mod dns;
+mod gossip;
 mod r#static;
 
 pub use dns::DnsDiscovery;
+pub use gossip::{GossipConfig, GossipDiscovery, MemberState, MemberUpdate};
 pub use r#static::StaticDiscovery;
 
 use crate::error::ClusterError;
@@ -148,6 +152,9 @@ pub enum DiscoveryConfig {
         #[serde(default = "default_port")]
         default_port: u16,
     },
+
+    /// SWIM gossip between the nodes themselves
+    Gossip(GossipConfig),
 }
 
 fn default_refresh_interval() -> u64 {
@@ -192,7 +199,18 @@ impl DiscoveryConfig {
         }
     }
 
+    /// Create gossip discovery configuration joining through `seeds`
+    pub fn gossip<S: Into<String>>(seeds: Vec<S>) -> Self {
+        DiscoveryConfig::Gossip(GossipConfig {
+            seeds: seeds.into_iter().map(|s| s.into()).collect(),
+            ..Default::default()
+        })
+    }
+
     /// Create the appropriate discovery implementation
+    ///
+    /// Gossip discovery also needs the local node's identity, so it is
+    /// created through [`ClusterConfig::create_discovery`](crate::ClusterConfig::create_discovery).
     pub fn create_discovery(&self) -> Result<Box<dyn NodeDiscovery>, ClusterError> {
         match self {
             DiscoveryConfig::Static { nodes } => Ok(Box::new(StaticDiscovery::new(nodes.clone())?)),
@@ -207,6 +225,9 @@ impl DiscoveryConfig {
                 server.clone(),
                 *default_port,
             ))),
+            DiscoveryConfig::Gossip(_) => Err(ClusterError::Discovery(
+                "Gossip discovery needs the local node; create it from the cluster config".into(),
+            )),
         }
     }
 }
@@ -253,5 +274,11 @@ mod tests {
         };
         let json = serde_json::to_string(&config).unwrap();
         assert!(json.contains("dns"));
+
+        let config: DiscoveryConfig =
+            serde_json::from_str(r#"{"backend": "gossip", "seeds": ["node1:9081"]}"#).unwrap();
+        assert!(
+            matches!(config, DiscoveryConfig::Gossip(gossip) if gossip.seeds == ["node1:9081"])
+        );
     }
 }
@@ -44,6 +44,18 @@ pub enum ClusterError {
 
     #[error("Discovery error: {0}")]
     Discovery(String),
+
+    #[error("Shard transfer error: {0}")]
+    Transfer(String),
+
+    #[error("Replication error: {0}")]
+    Replication(String),
+
+    #[error("Not the metadata leader: {0}")]
+    NotLeader(String),
+
+    #[error("Incompatible protocol version: {0}")]
+    IncompatibleVersion(String),
 }
 
 impl ClusterError {
@@ -63,6 +75,10 @@ impl ClusterError {
             ClusterError::Internal(_) => "internal",
             ClusterError::NotImplemented(_) => "not_implemented",
             ClusterError::Discovery(_) => "discovery",
+            ClusterError::Transfer(_) => "transfer",
+            ClusterError::Replication(_) => "replication",
+            ClusterError::NotLeader(_) => "not_leader",
+            ClusterError::IncompatibleVersion(_) => "incompatible_version",
         }
     }
 }
@@ -116,6 +132,13 @@ mod tests {
             (ClusterError::Internal("".into()), "internal"),
             (ClusterError::NotImplemented("".into()), "not_implemented"),
             (ClusterError::Discovery("".into()), "discovery"),
+            (ClusterError::Transfer("".into()), "transfer"),
+            (ClusterError::Replication("".into()), "replication"),
+            (ClusterError::NotLeader("".into()), "not_leader"),
+            (
+                ClusterError::IncompatibleVersion("".into()),
+                "incompatible_version",
+            ),
         ];
 
         for (err, expected) in cases {
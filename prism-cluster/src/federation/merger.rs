@@ -4,9 +4,21 @@
 //! - Simple: Just concatenate and sort (for filters/exact match)
 //! - Score normalized: Normalize scores across shards (for BM25)
 //! - RRF: Reciprocal Rank Fusion (for hybrid search)
+//!
+//! Aggregations are merged by [`AggregationMerger`]: shards compute partial
+//! results from rewritten requests and the coordinator combines them.
 
+use crate::error::{ClusterError, Result};
 use crate::types::{RpcSearchResult, RpcSearchResults};
+use prism::aggregations::{
+    apply_parent_pipelines, AggregationRequest, AggregationResult, AggregationType,
+    AggregationValue, Bucket, CompositeBucket, CompositeResult, CompositeSource,
+    HyperLogLogPlusPlus, PercentilesResult, SignificantTermsBucket, SignificantTermsResult,
+    SortDirection, StatsResult, TopHit, TopHitsResult, TopHitsSort,
+};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 /// Strategy for merging search results from multiple shards
@@ -40,6 +52,16 @@ pub enum MergeStrategy {
         /// Weight per shard (shard_id -> weight)
         weights: HashMap<String, f32>,
     },
+
+    /// Hybrid weighted fusion: sum of text and vector scores, each divided
+    /// by its maximum across all shards
+    /// Best for: hybrid search where score magnitudes matter
+    HybridWeighted {
+        /// Weight of text scores
+        text_weight: f32,
+        /// Weight of vector scores
+        vector_weight: f32,
+    },
 }
 
 impl MergeStrategy {
@@ -62,6 +84,7 @@ impl MergeStrategy {
             MergeStrategy::ReciprocalRankFusion { .. } => "rrf",
             MergeStrategy::TwoPhase => "two_phase",
             MergeStrategy::Weighted { .. } => "weighted",
+            MergeStrategy::HybridWeighted { .. } => "hybrid_weighted",
         }
     }
 }
@@ -93,6 +116,14 @@ impl ResultMerger {
             };
         }
 
+        // Hybrid shards return separate text and vector lists to fuse globally
+        if shard_results
+            .iter()
+            .any(|r| !r.text_results.is_empty() || !r.vector_results.is_empty())
+        {
+            return self.merge_hybrid(shard_results, limit, strategy);
+        }
+
         match strategy {
             MergeStrategy::Simple => self.merge_simple(shard_results, limit),
             MergeStrategy::ScoreNormalized => self.merge_normalized(shard_results, limit),
@@ -101,6 +132,60 @@ impl ResultMerger {
             MergeStrategy::Weighted { weights } => {
                 self.merge_weighted(shard_results, limit, weights)
             }
+            MergeStrategy::HybridWeighted { .. } => self.merge_simple(shard_results, limit),
+        }
+    }
+
+    /// Hybrid merge: rank the text and vector hits of all shards globally by
+    /// raw score, then fuse the two rankings
+    ///
+    /// Vector similarities are comparable across shards as-is; text scores
+    /// are when the shards scored them with DFS statistics.
+    fn merge_hybrid(
+        &self,
+        shard_results: Vec<RpcSearchResults>,
+        limit: usize,
+        strategy: &MergeStrategy,
+    ) -> MergedResults {
+        let total: usize = shard_results.iter().map(|r| r.total).sum();
+
+        let mut text = Vec::new();
+        let mut vector = Vec::new();
+        for shard_result in shard_results {
+            text.extend(shard_result.text_results);
+            vector.extend(shard_result.vector_results);
+        }
+        let text = rank_by_score(text);
+        let vector = rank_by_score(vector);
+
+        let (mut results, strategy_used) = match strategy {
+            MergeStrategy::HybridWeighted {
+                text_weight,
+                vector_weight,
+            } => (
+                fuse_weighted(text, vector, *text_weight, *vector_weight),
+                strategy.clone(),
+            ),
+            MergeStrategy::ReciprocalRankFusion { k } => {
+                (fuse_rrf(text, vector, *k), strategy.clone())
+            }
+            _ => (
+                fuse_rrf(text, vector, 60),
+                MergeStrategy::ReciprocalRankFusion { k: 60 },
+            ),
+        };
+
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        results.truncate(limit);
+
+        MergedResults {
+            results,
+            total,
+            strategy_used,
         }
     }
 
@@ -230,6 +315,71 @@ impl ResultMerger {
     }
 }
 
+/// Sort hits by score descending, keeping the best hit per document
+fn rank_by_score(mut results: Vec<RpcSearchResult>) -> Vec<RpcSearchResult> {
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let mut seen = std::collections::HashSet::new();
+    results.retain(|r| seen.insert(r.id.clone()));
+    results
+}
+
+/// Reciprocal Rank Fusion of the global text and vector rankings
+fn fuse_rrf(
+    text: Vec<RpcSearchResult>,
+    vector: Vec<RpcSearchResult>,
+    k: u32,
+) -> Vec<RpcSearchResult> {
+    let mut fused: HashMap<String, RpcSearchResult> = HashMap::new();
+    for ranking in [text, vector] {
+        for (rank, mut result) in ranking.into_iter().enumerate() {
+            let contribution = 1.0 / (k as f32 + rank as f32 + 1.0);
+            fused
+                .entry(result.id.clone())
+                .and_modify(|r| r.score += contribution)
+                .or_insert_with(|| {
+                    result.score = contribution;
+                    result
+                });
+        }
+    }
+    fused.into_values().collect()
+}
+
+/// Weighted fusion of text and vector scores, each divided by its global
+/// maximum
+fn fuse_weighted(
+    text: Vec<RpcSearchResult>,
+    vector: Vec<RpcSearchResult>,
+    text_weight: f32,
+    vector_weight: f32,
+) -> Vec<RpcSearchResult> {
+    let mut fused: HashMap<String, RpcSearchResult> = HashMap::new();
+    for (ranking, weight) in [(text, text_weight), (vector, vector_weight)] {
+        // Rankings are sorted, so the first hit holds the maximum
+        let max = ranking.first().map(|r| r.score).unwrap_or(0.0);
+        for mut result in ranking {
+            let normalized = if max > 0.0 {
+                result.score / max
+            } else {
+                result.score
+            };
+            let contribution = weight * normalized;
+            fused
+                .entry(result.id.clone())
+                .and_modify(|r| r.score += contribution)
+                .or_insert_with(|| {
+                    result.score = contribution;
+                    result
+                });
+        }
+    }
+    fused.into_values().collect()
+}
+
 /// Score normalization utilities
 pub struct ScoreNormalizer;
 
@@ -297,6 +447,746 @@ pub struct MergedResults {
     pub strategy_used: MergeStrategy,
 }
 
+/// Suffix of the `stats` aggregation sent alongside each `percentiles`
+/// aggregation; its count weighs the shard's percentiles when merging
+const PERCENTILES_COUNT_SUFFIX: &str = "#count";
+
+/// Percentiles each shard reports for a `percentiles` aggregation: every
+/// whole percent plus the requested ones, describing the shard's value
+/// distribution closely enough to merge
+fn percentile_sketch_points(percents: &[f64]) -> Vec<f64> {
+    let mut points: Vec<f64> = (0..=100)
+        .map(f64::from)
+        .chain(percents.iter().copied())
+        .collect();
+    points.sort_by(|a, b| a.total_cmp(b));
+    points.dedup();
+    points
+}
+
+/// Whether `agg_type` runs its pipeline sub-aggregations on merged buckets
+fn supports_pipelines(agg_type: &AggregationType) -> bool {
+    matches!(
+        agg_type,
+        AggregationType::Terms { .. }
+            | AggregationType::Histogram { .. }
+            | AggregationType::DateHistogram { .. }
+            | AggregationType::Range { .. }
+            | AggregationType::Filters { .. }
+    )
+}
+
+/// Two-phase aggregation merging
+///
+/// Aggregations whose final value cannot be combined across shards are
+/// rewritten before they are shipped: `avg`, `min` and `max` become `stats`,
+/// `terms` and `significant_terms` request `shard_size` buckets,
+/// `percentiles` report a fixed set of points plus a value count, and
+/// `cardinality` returns its HyperLogLog++ sketch. Pipeline sub-aggregations
+/// run on the coordinator after merging.
+pub struct AggregationMerger;
+
+impl AggregationMerger {
+    /// Buckets requested from each shard for `size` final terms buckets
+    pub fn shard_size(size: usize) -> usize {
+        size + size / 2 + 10
+    }
+
+    /// Rewrite aggregations into the partial form shards compute
+    pub fn shard_requests(requests: &[AggregationRequest]) -> Vec<AggregationRequest> {
+        let mut shard_requests = Vec::with_capacity(requests.len());
+        for request in requests {
+            let agg_type = match &request.agg_type {
+                AggregationType::Avg { field }
+                | AggregationType::Min { field }
+                | AggregationType::Max { field } => AggregationType::Stats {
+                    field: field.clone(),
+                },
+                AggregationType::Percentiles { field, percents } => {
+                    shard_requests.push(AggregationRequest {
+                        name: format!("{}{}", request.name, PERCENTILES_COUNT_SUFFIX),
+                        agg_type: AggregationType::Stats {
+                            field: field.clone(),
+                        },
+                        aggs: None,
+                    });
+                    AggregationType::Percentiles {
+                        field: field.clone(),
+                        percents: percentile_sketch_points(percents),
+                    }
+                }
+                AggregationType::Terms { field, size } => AggregationType::Terms {
+                    field: field.clone(),
+                    size: Some(Self::shard_size(size.unwrap_or(10))),
+                },
+                AggregationType::Cardinality {
+                    field,
+                    precision_threshold,
+                    ..
+                } => AggregationType::Cardinality {
+                    field: field.clone(),
+                    precision_threshold: *precision_threshold,
+                    sketch: true,
+                },
+                // Empty buckets are dropped after merging, since a bucket
+                // sparse on every shard may still reach the minimum overall
+                AggregationType::Histogram {
+                    field,
+                    interval,
+                    extended_bounds,
+                    ..
+                } => AggregationType::Histogram {
+                    field: field.clone(),
+                    interval: *interval,
+                    min_doc_count: None,
+                    extended_bounds: extended_bounds.clone(),
+                },
+                AggregationType::DateHistogram {
+                    field,
+                    calendar_interval,
+                    ..
+                } => AggregationType::DateHistogram {
+                    field: field.clone(),
+                    calendar_interval: calendar_interval.clone(),
+                    min_doc_count: None,
+                },
+                AggregationType::TopHits {
+                    size,
+                    from,
+                    sort,
+                    source,
+                } => AggregationType::TopHits {
+                    size: Some(from.unwrap_or(0) + size.unwrap_or(3)),
+                    from: None,
+                    sort: sort.clone(),
+                    source: source.clone(),
+                },
+                AggregationType::SignificantTerms {
+                    field,
+                    size,
+                    background_filter,
+                    ..
+                } => AggregationType::SignificantTerms {
+                    field: field.clone(),
+                    size: Some(Self::shard_size(size.unwrap_or(10))),
+                    min_doc_count: Some(1),
+                    background_filter: background_filter.clone(),
+                },
+                other => other.clone(),
+            };
+
+            let aggs = request.aggs.as_deref().map(|sub_aggs| {
+                if supports_pipelines(&request.agg_type) {
+                    let doc_aggs: Vec<AggregationRequest> = sub_aggs
+                        .iter()
+                        .filter(|a| !a.agg_type.is_pipeline())
+                        .cloned()
+                        .collect();
+                    Self::shard_requests(&doc_aggs)
+                } else {
+                    Self::shard_requests(sub_aggs)
+                }
+            });
+
+            shard_requests.push(AggregationRequest {
+                name: request.name.clone(),
+                agg_type,
+                aggs,
+            });
+        }
+        shard_requests
+    }
+
+    /// Merge the partial results of each shard into final results for
+    /// `requests`
+    pub fn merge(
+        requests: &[AggregationRequest],
+        shard_results: &[HashMap<String, AggregationResult>],
+    ) -> Result<HashMap<String, AggregationResult>> {
+        let shards: Vec<&HashMap<String, AggregationResult>> = shard_results.iter().collect();
+        Ok(Self::merge_all(requests, &shards)?
+            .into_iter()
+            .map(|result| (result.name.clone(), result))
+            .collect())
+    }
+
+    fn merge_all(
+        requests: &[AggregationRequest],
+        shards: &[&HashMap<String, AggregationResult>],
+    ) -> Result<Vec<AggregationResult>> {
+        requests
+            .iter()
+            .filter(|request| !request.agg_type.is_pipeline())
+            .map(|request| {
+                Ok(AggregationResult {
+                    name: request.name.clone(),
+                    value: Self::merge_one(request, shards)?,
+                })
+            })
+            .collect()
+    }
+
+    fn merge_one(
+        request: &AggregationRequest,
+        shards: &[&HashMap<String, AggregationResult>],
+    ) -> Result<AggregationValue> {
+        let partials: Vec<&AggregationValue> = shards
+            .iter()
+            .filter_map(|shard| shard.get(&request.name))
+            .map(|result| &result.value)
+            .collect();
+        let sub_aggs = request.aggs.as_deref().unwrap_or(&[]);
+
+        let mut value = match &request.agg_type {
+            AggregationType::Count | AggregationType::Sum { .. } => {
+                AggregationValue::Single(partials.iter().filter_map(|v| single(v)).sum())
+            }
+            AggregationType::Cardinality {
+                precision_threshold,
+                ..
+            } => AggregationValue::Single(merge_cardinality(&partials, *precision_threshold)),
+            AggregationType::Min { .. } => {
+                AggregationValue::Single(merge_stats(&partials).min.unwrap_or(0.0))
+            }
+            AggregationType::Max { .. } => {
+                AggregationValue::Single(merge_stats(&partials).max.unwrap_or(0.0))
+            }
+            AggregationType::Avg { .. } => {
+                AggregationValue::Single(merge_stats(&partials).avg.unwrap_or(0.0))
+            }
+            AggregationType::Stats { .. } => AggregationValue::Stats(merge_stats(&partials)),
+            AggregationType::Percentiles { percents, .. } => {
+                AggregationValue::Percentiles(merge_percentiles(&request.name, percents, shards))
+            }
+            AggregationType::Terms { size, .. } => {
+                AggregationValue::Buckets(merge_terms(&partials, size.unwrap_or(10), sub_aggs)?)
+            }
+            AggregationType::Histogram { min_doc_count, .. } => {
+                let mut buckets = merge_buckets(&partials, sub_aggs)?;
+                buckets.sort_by(|a, b| numeric_key(&a.key).total_cmp(&numeric_key(&b.key)));
+                buckets.retain(|b| b.doc_count >= min_doc_count.unwrap_or(0));
+                AggregationValue::Buckets(buckets)
+            }
+            AggregationType::DateHistogram { min_doc_count, .. } => {
+                let mut buckets = merge_buckets(&partials, sub_aggs)?;
+                buckets.sort_by(|a, b| a.key.cmp(&b.key));
+                buckets.retain(|b| b.doc_count >= min_doc_count.unwrap_or(0));
+                AggregationValue::Buckets(buckets)
+            }
+            AggregationType::Range { .. }
+            | AggregationType::Filter { .. }
+            | AggregationType::Filters { .. }
+            | AggregationType::Global {} => {
+                AggregationValue::Buckets(merge_buckets(&partials, sub_aggs)?)
+            }
+            AggregationType::TopHits {
+                size, from, sort, ..
+            } => AggregationValue::TopHits(merge_top_hits(
+                &partials,
+                from.unwrap_or(0),
+                size.unwrap_or(3),
+                sort,
+            )),
+            AggregationType::SignificantTerms {
+                size,
+                min_doc_count,
+                ..
+            } => AggregationValue::SignificantTerms(merge_significant_terms(
+                &partials,
+                size.unwrap_or(10),
+                min_doc_count.unwrap_or(3),
+            )),
+            AggregationType::Composite { sources, size, .. } => AggregationValue::Composite(
+                merge_composite(&partials, sources, size.unwrap_or(10), sub_aggs)?,
+            ),
+            AggregationType::Derivative { .. }
+            | AggregationType::CumulativeSum { .. }
+            | AggregationType::MovingFn { .. }
+            | AggregationType::BucketSort { .. }
+            | AggregationType::BucketSelector { .. } => {
+                return Err(ClusterError::InvalidQuery(format!(
+                    "Pipeline aggregation '{}' must be a sub-aggregation",
+                    request.name
+                )))
+            }
+        };
+
+        if supports_pipelines(&request.agg_type)
+            && sub_aggs.iter().any(|a| a.agg_type.is_pipeline())
+        {
+            if let AggregationValue::Buckets(buckets) = &mut value {
+                apply_parent_pipelines(buckets, sub_aggs)?;
+            }
+        }
+
+        Ok(value)
+    }
+}
+
+/// Estimate distinct values from the union of the shards' sketches. Shards
+/// that only report a count (nodes from before sketches were shipped) can
+/// share values with the others, so their counts only bound the result from
+/// below.
+fn merge_cardinality(partials: &[&AggregationValue], precision_threshold: Option<u64>) -> f64 {
+    let mut merged = HyperLogLogPlusPlus::new(
+        precision_threshold.unwrap_or(HyperLogLogPlusPlus::DEFAULT_PRECISION_THRESHOLD),
+    );
+    let mut lower_bound: f64 = 0.0;
+    for partial in partials {
+        match partial {
+            AggregationValue::Cardinality(sketch) => merged.merge(sketch),
+            AggregationValue::Single(count) => lower_bound = lower_bound.max(*count),
+            _ => {}
+        }
+    }
+    (merged.estimate() as f64).max(lower_bound)
+}
+
+fn single(value: &AggregationValue) -> Option<f64> {
+    match value {
+        AggregationValue::Single(v) => Some(*v),
+        _ => None,
+    }
+}
+
+fn numeric_key(key: &str) -> f64 {
+    key.parse().unwrap_or(f64::NAN)
+}
+
+fn merge_stats(partials: &[&AggregationValue]) -> StatsResult {
+    let mut merged = StatsResult {
+        count: 0,
+        min: None,
+        max: None,
+        sum: None,
+        avg: None,
+    };
+    for partial in partials {
+        let AggregationValue::Stats(stats) = partial else {
+            continue;
+        };
+        merged.count += stats.count;
+        merged.min = match (merged.min, stats.min) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        merged.max = match (merged.max, stats.max) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        if let Some(sum) = stats.sum {
+            merged.sum = Some(merged.sum.unwrap_or(0.0) + sum);
+        }
+    }
+    if merged.count > 0 {
+        merged.avg = merged.sum.map(|sum| sum / merged.count as f64);
+    }
+    merged
+}
+
+/// A shard's value distribution: `(value, percentile)` points in ascending
+/// order, weighted by the shard's value count
+struct PercentileSketch {
+    count: f64,
+    points: Vec<(f64, f64)>,
+}
+
+impl PercentileSketch {
+    /// Percentage of the shard's values at or below `x`, interpolated
+    /// between points
+    fn rank(&self, x: f64) -> f64 {
+        let below = self.points.partition_point(|&(value, _)| value <= x);
+        if below == 0 {
+            return 0.0;
+        }
+        if below == self.points.len() {
+            return 100.0;
+        }
+        let (v0, p0) = self.points[below - 1];
+        let (v1, p1) = self.points[below];
+        p0 + (p1 - p0) * (x - v0) / (v1 - v0)
+    }
+}
+
+fn merge_percentiles(
+    name: &str,
+    percents: &[f64],
+    shards: &[&HashMap<String, AggregationResult>],
+) -> PercentilesResult {
+    let count_name = format!("{}{}", name, PERCENTILES_COUNT_SUFFIX);
+    let sketch_points = percentile_sketch_points(percents);
+
+    let sketches: Vec<PercentileSketch> = shards
+        .iter()
+        .filter_map(|shard| {
+            let AggregationValue::Percentiles(result) = &shard.get(name)?.value else {
+                return None;
+            };
+            let AggregationValue::Stats(stats) = &shard.get(&count_name)?.value else {
+                return None;
+            };
+            let points: Vec<(f64, f64)> = sketch_points
+                .iter()
+                .filter_map(|&p| Some((result.values.get(&format!("{}", p)).copied()??, p)))
+                .collect();
+            (stats.count > 0 && !points.is_empty()).then_some(PercentileSketch {
+                count: stats.count as f64,
+                points,
+            })
+        })
+        .collect();
+
+    let total: f64 = sketches.iter().map(|s| s.count).sum();
+    let rank = |x: f64| -> f64 { sketches.iter().map(|s| s.count * s.rank(x) / 100.0).sum() };
+    let lowest = sketches
+        .iter()
+        .map(|s| s.points[0].0)
+        .fold(f64::INFINITY, f64::min);
+    let highest = sketches
+        .iter()
+        .map(|s| s.points[s.points.len() - 1].0)
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    let values = percents
+        .iter()
+        .map(|&p| {
+            let value = (total > 0.0).then(|| {
+                // Smallest value whose merged rank reaches the percentile
+                let target = p / 100.0 * total;
+                let (mut lo, mut hi) = (lowest, highest);
+                if rank(lo) >= target {
+                    return lo;
+                }
+                for _ in 0..100 {
+                    let mid = lo + (hi - lo) / 2.0;
+                    if rank(mid) >= target {
+                        hi = mid;
+                    } else {
+                        lo = mid;
+                    }
+                }
+                hi
+            });
+            (format!("{}", p), value)
+        })
+        .collect();
+
+    PercentilesResult { values }
+}
+
+/// A bucket merged from several shards, with the sub-aggregations of each
+struct MergedBucket {
+    bucket: Bucket,
+    shards: Vec<usize>,
+    sub_aggs: Vec<HashMap<String, AggregationResult>>,
+}
+
+/// Combine buckets with the same key, in order of first appearance
+fn collect_buckets(partials: &[&AggregationValue]) -> Vec<MergedBucket> {
+    let mut merged: Vec<MergedBucket> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for (shard, partial) in partials.iter().enumerate() {
+        let AggregationValue::Buckets(buckets) = partial else {
+            continue;
+        };
+        for bucket in buckets {
+            let pos = *index.entry(bucket.key.clone()).or_insert_with(|| {
+                merged.push(MergedBucket {
+                    bucket: Bucket {
+                        doc_count: 0,
+                        sub_aggs: None,
+                        ..bucket.clone()
+                    },
+                    shards: Vec::new(),
+                    sub_aggs: Vec::new(),
+                });
+                merged.len() - 1
+            });
+            let entry = &mut merged[pos];
+            entry.bucket.doc_count += bucket.doc_count;
+            entry.shards.push(shard);
+            if let Some(sub_aggs) = &bucket.sub_aggs {
+                entry.sub_aggs.push(
+                    sub_aggs
+                        .iter()
+                        .map(|r| (r.name.clone(), r.clone()))
+                        .collect(),
+                );
+            }
+        }
+    }
+    merged
+}
+
+/// Merge the sub-aggregations of a bucket
+fn finish_sub_aggs(
+    sub_results: &[HashMap<String, AggregationResult>],
+    sub_aggs: &[AggregationRequest],
+) -> Result<Option<Vec<AggregationResult>>> {
+    if sub_results.is_empty() {
+        return Ok(None);
+    }
+    let shards: Vec<&HashMap<String, AggregationResult>> = sub_results.iter().collect();
+    let merged = AggregationMerger::merge_all(sub_aggs, &shards)?;
+    Ok((!merged.is_empty()).then_some(merged))
+}
+
+fn merge_buckets(
+    partials: &[&AggregationValue],
+    sub_aggs: &[AggregationRequest],
+) -> Result<Vec<Bucket>> {
+    collect_buckets(partials)
+        .into_iter()
+        .map(|merged| {
+            Ok(Bucket {
+                sub_aggs: finish_sub_aggs(&merged.sub_aggs, sub_aggs)?,
+                ..merged.bucket
+            })
+        })
+        .collect()
+}
+
+/// Merge per-shard top terms
+///
+/// A shard that returned a full `shard_size` list may hold a term it did not
+/// return at most as often as its last bucket, which bounds the count each
+/// merged bucket may be missing.
+fn merge_terms(
+    partials: &[&AggregationValue],
+    size: usize,
+    sub_aggs: &[AggregationRequest],
+) -> Result<Vec<Bucket>> {
+    let shard_size = AggregationMerger::shard_size(size);
+    let shard_bounds: Vec<u64> = partials
+        .iter()
+        .map(|partial| match partial {
+            AggregationValue::Buckets(buckets) if buckets.len() >= shard_size => {
+                buckets.last().map(|b| b.doc_count).unwrap_or(0)
+            }
+            _ => 0,
+        })
+        .collect();
+
+    let mut merged = collect_buckets(partials);
+    merged.sort_by(|a, b| {
+        b.bucket
+            .doc_count
+            .cmp(&a.bucket.doc_count)
+            .then_with(|| a.bucket.key.cmp(&b.bucket.key))
+    });
+    merged.truncate(size);
+
+    merged
+        .into_iter()
+        .map(|merged| {
+            let error = shard_bounds
+                .iter()
+                .enumerate()
+                .filter(|(shard, _)| !merged.shards.contains(shard))
+                .map(|(_, bound)| bound)
+                .sum();
+            Ok(Bucket {
+                sub_aggs: finish_sub_aggs(&merged.sub_aggs, sub_aggs)?,
+                doc_count_error_upper_bound: Some(error),
+                ..merged.bucket
+            })
+        })
+        .collect()
+}
+
+/// Order two JSON sort values; numbers and strings compare naturally, other
+/// combinations are equal
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .unwrap_or(0.0)
+            .total_cmp(&b.as_f64().unwrap_or(0.0)),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        _ => Ordering::Equal,
+    }
+}
+
+fn compare_with_direction(a: &Value, b: &Value, order: SortDirection) -> Ordering {
+    match order {
+        SortDirection::Asc => compare_values(a, b),
+        SortDirection::Desc => compare_values(b, a),
+    }
+}
+
+fn merge_top_hits(
+    partials: &[&AggregationValue],
+    from: usize,
+    size: usize,
+    sort: &[TopHitsSort],
+) -> TopHitsResult {
+    let mut total = 0;
+    let mut max_score: Option<f32> = None;
+    let mut hits: Vec<TopHit> = Vec::new();
+    for partial in partials {
+        let AggregationValue::TopHits(result) = partial else {
+            continue;
+        };
+        total += result.total;
+        max_score = match (max_score, result.max_score) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        hits.extend(result.hits.iter().cloned());
+    }
+
+    if sort.is_empty() {
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    } else {
+        hits.sort_by(|a, b| {
+            sort.iter()
+                .zip(a.sort.iter().zip(&b.sort))
+                .map(|(key, (a, b))| compare_with_direction(a, b, key.order))
+                .find(|ord| ord.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+
+    TopHitsResult {
+        total,
+        max_score,
+        hits: hits.into_iter().skip(from).take(size).collect(),
+    }
+}
+
+/// JLH significance score, as computed by the text backend
+fn jlh_score(fg_count: u64, fg_size: u64, bg_count: u64, bg_size: u64) -> f64 {
+    if fg_size == 0 || bg_size == 0 {
+        return 0.0;
+    }
+    let fg_pct = fg_count as f64 / fg_size as f64;
+    let bg_pct = bg_count.max(1) as f64 / bg_size as f64;
+    if fg_pct <= bg_pct {
+        return 0.0;
+    }
+    (fg_pct - bg_pct) * (fg_pct / bg_pct)
+}
+
+fn merge_significant_terms(
+    partials: &[&AggregationValue],
+    size: usize,
+    min_doc_count: u64,
+) -> SignificantTermsResult {
+    let mut doc_count = 0;
+    let mut bg_count = 0;
+    let mut terms: HashMap<String, (u64, u64)> = HashMap::new();
+    for partial in partials {
+        let AggregationValue::SignificantTerms(result) = partial else {
+            continue;
+        };
+        doc_count += result.doc_count;
+        bg_count += result.bg_count;
+        for bucket in &result.buckets {
+            let counts = terms.entry(bucket.key.clone()).or_default();
+            counts.0 += bucket.doc_count;
+            counts.1 += bucket.bg_count;
+        }
+    }
+
+    let mut buckets: Vec<SignificantTermsBucket> = terms
+        .into_iter()
+        .filter(|(_, (fg, _))| *fg >= min_doc_count)
+        .filter_map(|(key, (fg, bg))| {
+            let score = jlh_score(fg, doc_count, bg, bg_count);
+            (score > 0.0).then_some(SignificantTermsBucket {
+                key,
+                doc_count: fg,
+                bg_count: bg,
+                score,
+            })
+        })
+        .collect();
+    buckets.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.key.cmp(&b.key)));
+    buckets.truncate(size);
+
+    SignificantTermsResult {
+        doc_count,
+        bg_count,
+        buckets,
+    }
+}
+
+/// Merge composite pages
+///
+/// Every shard returns its first `size` keys after `after`, so the first
+/// `size` merged keys are exact.
+fn merge_composite(
+    partials: &[&AggregationValue],
+    sources: &[CompositeSource],
+    size: usize,
+    sub_aggs: &[AggregationRequest],
+) -> Result<CompositeResult> {
+    let mut merged: Vec<(CompositeBucket, Vec<HashMap<String, AggregationResult>>)> = Vec::new();
+    for partial in partials {
+        let AggregationValue::Composite(result) = partial else {
+            continue;
+        };
+        for bucket in &result.buckets {
+            let pos = match merged.iter().position(|(b, _)| b.key == bucket.key) {
+                Some(pos) => pos,
+                None => {
+                    merged.push((
+                        CompositeBucket {
+                            key: bucket.key.clone(),
+                            doc_count: 0,
+                            sub_aggs: None,
+                        },
+                        Vec::new(),
+                    ));
+                    merged.len() - 1
+                }
+            };
+            let (entry, sub_results) = &mut merged[pos];
+            entry.doc_count += bucket.doc_count;
+            if let Some(sub_aggs) = &bucket.sub_aggs {
+                sub_results.push(
+                    sub_aggs
+                        .iter()
+                        .map(|r| (r.name.clone(), r.clone()))
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    merged.sort_by(|(a, _), (b, _)| {
+        sources
+            .iter()
+            .map(|source| {
+                let null = Value::Null;
+                let a = a.key.get(&source.name).unwrap_or(&null);
+                let b = b.key.get(&source.name).unwrap_or(&null);
+                compare_with_direction(a, b, source.order)
+            })
+            .find(|ord| ord.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+    merged.truncate(size);
+
+    let buckets = merged
+        .into_iter()
+        .map(|(bucket, sub_results)| {
+            Ok(CompositeBucket {
+                sub_aggs: finish_sub_aggs(&sub_results, sub_aggs)?,
+                ..bucket
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(CompositeResult {
+        after_key: buckets.last().map(|b| b.key.clone()),
+        buckets,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -316,6 +1206,9 @@ mod tests {
             results,
             total: 100, // Arbitrary
             latency_ms: 10,
+            aggregations: HashMap::new(),
+            text_results: Vec::new(),
+            vector_results: Vec::new(),
         }
     }
 
@@ -387,6 +1280,64 @@ mod tests {
         assert_eq!(normalized[2].score, 0.0);
     }
 
+    fn make_hybrid_shard(
+        text: Vec<RpcSearchResult>,
+        vector: Vec<RpcSearchResult>,
+    ) -> RpcSearchResults {
+        RpcSearchResults {
+            text_results: text,
+            vector_results: vector,
+            ..make_shard_results(Vec::new())
+        }
+    }
+
+    fn hybrid_shards() -> Vec<RpcSearchResults> {
+        vec![
+            make_hybrid_shard(
+                vec![make_result("a", 5.0), make_result("b", 4.0)],
+                vec![make_result("b", 0.9)],
+            ),
+            make_hybrid_shard(
+                vec![make_result("c", 9.0)],
+                vec![make_result("d", 0.95), make_result("c", 0.5)],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_merge_hybrid_rrf_ranks_globally() {
+        let merger = ResultMerger::new(MergeStrategy::Simple);
+
+        let merged = merger.merge(
+            hybrid_shards(),
+            10,
+            &MergeStrategy::ReciprocalRankFusion { k: 60 },
+        );
+
+        // Global text ranking is c, a, b and vector ranking is d, b, c
+        let ids: Vec<_> = merged.results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "b", "d", "a"]);
+        let expected_c = 1.0 / 61.0 + 1.0 / 63.0;
+        assert!((merged.results[0].score - expected_c).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_merge_hybrid_weighted_normalizes_by_global_max() {
+        let merger = ResultMerger::new(MergeStrategy::Simple);
+
+        let strategy = MergeStrategy::HybridWeighted {
+            text_weight: 0.5,
+            vector_weight: 0.5,
+        };
+        let merged = merger.merge(hybrid_shards(), 10, &strategy);
+
+        let ids: Vec<_> = merged.results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["c", "b", "d", "a"]);
+        let expected_c = 0.5 * 1.0 + 0.5 * (0.5 / 0.95);
+        assert!((merged.results[0].score - expected_c).abs() < 1e-6);
+        assert_eq!(merged.strategy_used, strategy);
+    }
+
     #[test]
     fn test_merge_strategy_from_string() {
         assert_eq!(
@@ -403,4 +1354,350 @@ mod tests {
         );
         assert_eq!(MergeStrategy::from_string("unknown"), None);
     }
+
+    fn agg(name: &str, agg_type: AggregationType) -> AggregationRequest {
+        AggregationRequest {
+            name: name.to_string(),
+            agg_type,
+            aggs: None,
+        }
+    }
+
+    fn result(name: &str, value: AggregationValue) -> (String, AggregationResult) {
+        (
+            name.to_string(),
+            AggregationResult {
+                name: name.to_string(),
+                value,
+            },
+        )
+    }
+
+    fn stats(values: &[f64]) -> AggregationValue {
+        let count = values.len() as u64;
+        let sum: f64 = values.iter().sum();
+        AggregationValue::Stats(StatsResult {
+            count,
+            min: values.iter().cloned().reduce(f64::min),
+            max: values.iter().cloned().reduce(f64::max),
+            sum: Some(sum),
+            avg: (count > 0).then(|| sum / count as f64),
+        })
+    }
+
+    fn buckets(counts: &[(&str, u64)]) -> AggregationValue {
+        AggregationValue::Buckets(
+            counts
+                .iter()
+                .map(|(key, doc_count)| Bucket {
+                    key: key.to_string(),
+                    doc_count: *doc_count,
+                    from: None,
+                    to: None,
+                    sub_aggs: None,
+                    doc_count_error_upper_bound: None,
+                })
+                .collect(),
+        )
+    }
+
+    /// Percentiles the way the text backend computes them on one shard
+    fn shard_percentiles(values: &mut [f64], percents: &[f64]) -> AggregationValue {
+        values.sort_by(|a, b| a.total_cmp(b));
+        let values = &*values;
+        AggregationValue::Percentiles(PercentilesResult {
+            values: percents
+                .iter()
+                .map(|&p| {
+                    let rank = p / 100.0 * (values.len() as f64 - 1.0);
+                    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+                    let frac = rank - lower as f64;
+                    let value = values[lower] * (1.0 - frac) + values[upper] * frac;
+                    (format!("{}", p), Some(value))
+                })
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn test_shard_requests_rewrite_partial_aggregations() {
+        let mut terms = agg(
+            "by_brand",
+            AggregationType::Terms {
+                field: "brand".into(),
+                size: Some(10),
+            },
+        );
+        terms.aggs = Some(vec![
+            agg(
+                "avg_price",
+                AggregationType::Avg {
+                    field: "price".into(),
+                },
+            ),
+            agg(
+                "running",
+                AggregationType::CumulativeSum {
+                    buckets_path: "avg_price".into(),
+                },
+            ),
+        ]);
+        let requests = vec![
+            terms,
+            agg(
+                "latency",
+                AggregationType::Percentiles {
+                    field: "ms".into(),
+                    percents: vec![99.9],
+                },
+            ),
+            agg(
+                "users",
+                AggregationType::Cardinality {
+                    field: "user".into(),
+                    precision_threshold: None,
+                    sketch: false,
+                },
+            ),
+        ];
+
+        let shard = AggregationMerger::shard_requests(&requests);
+        assert_eq!(shard.len(), 4);
+
+        let AggregationType::Terms { size, .. } = &shard[0].agg_type else {
+            panic!("expected terms");
+        };
+        assert_eq!(*size, Some(25));
+        let sub_aggs = shard[0].aggs.as_ref().unwrap();
+        assert_eq!(sub_aggs.len(), 1, "pipelines stay on the coordinator");
+        assert!(matches!(
+            sub_aggs[0].agg_type,
+            AggregationType::Stats { .. }
+        ));
+
+        assert_eq!(shard[1].name, "latency#count");
+        let AggregationType::Percentiles { percents, .. } = &shard[2].agg_type else {
+            panic!("expected percentiles");
+        };
+        assert_eq!(percents.len(), 102);
+        assert!(percents.contains(&99.9));
+        assert!(matches!(
+            shard[3].agg_type,
+            AggregationType::Cardinality { sketch: true, .. }
+        ));
+    }
+
+    #[test]
+    fn test_merge_metric_aggregations() {
+        let requests = vec![
+            agg("docs", AggregationType::Count),
+            agg(
+                "total",
+                AggregationType::Sum {
+                    field: "price".into(),
+                },
+            ),
+            agg(
+                "cheapest",
+                AggregationType::Min {
+                    field: "price".into(),
+                },
+            ),
+            agg(
+                "priciest",
+                AggregationType::Max {
+                    field: "price".into(),
+                },
+            ),
+            agg(
+                "mean",
+                AggregationType::Avg {
+                    field: "price".into(),
+                },
+            ),
+        ];
+        let shard = |values: &[f64]| -> HashMap<String, AggregationResult> {
+            HashMap::from([
+                result("docs", AggregationValue::Single(values.len() as f64)),
+                result("total", AggregationValue::Single(values.iter().sum())),
+                result("cheapest", stats(values)),
+                result("priciest", stats(values)),
+                result("mean", stats(values)),
+            ])
+        };
+
+        // The empty shard must not drag min/max to zero or skew the average
+        let merged = AggregationMerger::merge(
+            &requests,
+            &[shard(&[10.0, 20.0]), shard(&[30.0]), shard(&[])],
+        )
+        .unwrap();
+
+        let value = |name: &str| single(&merged[name].value).unwrap();
+        assert_eq!(value("docs"), 3.0);
+        assert_eq!(value("total"), 60.0);
+        assert_eq!(value("cheapest"), 10.0);
+        assert_eq!(value("priciest"), 30.0);
+        assert_eq!(value("mean"), 20.0);
+    }
+
+    #[test]
+    fn test_merge_cardinality_from_sketches() {
+        let requests = vec![agg(
+            "users",
+            AggregationType::Cardinality {
+                field: "user".into(),
+                precision_threshold: Some(100),
+                sketch: false,
+            },
+        )];
+        let shard = |values: std::ops::Range<u32>| -> HashMap<String, AggregationResult> {
+            let mut sketch = HyperLogLogPlusPlus::new(100);
+            for value in values {
+                sketch.insert(format!("user-{}", value).as_bytes());
+            }
+            // As sent over RPC
+            let value = AggregationValue::Cardinality(sketch);
+            let value = serde_json::from_value(serde_json::to_value(value).unwrap()).unwrap();
+            assert!(matches!(value, AggregationValue::Cardinality(_)));
+            HashMap::from([result("users", value)])
+        };
+        // Within a few standard errors (about 0.81% each)
+        let assert_near = |merged: &HashMap<String, AggregationResult>, expected: f64| {
+            let count = single(&merged["users"].value).unwrap();
+            let error = (count - expected).abs() / expected;
+            assert!(
+                error < 0.03,
+                "estimated {} for {} distinct values",
+                count,
+                expected
+            );
+        };
+
+        let disjoint =
+            AggregationMerger::merge(&requests, &[shard(0..5000), shard(5000..10000)]).unwrap();
+        assert_near(&disjoint, 10000.0);
+
+        let overlapping = AggregationMerger::merge(
+            &requests,
+            &[shard(0..6000), shard(4000..10000), shard(8000..12000)],
+        )
+        .unwrap();
+        assert_near(&overlapping, 12000.0);
+
+        // A shard that only reports a count still bounds the result from below
+        let mixed = AggregationMerger::merge(
+            &requests,
+            &[
+                shard(0..100),
+                HashMap::from([result("users", AggregationValue::Single(5000.0))]),
+            ],
+        )
+        .unwrap();
+        assert_eq!(single(&mixed["users"].value), Some(5000.0));
+    }
+
+    #[test]
+    fn test_merge_terms_reports_error_bounds() {
+        let requests = vec![agg(
+            "tags",
+            AggregationType::Terms {
+                field: "tag".into(),
+                size: Some(2),
+            },
+        )];
+        // Shard size for two buckets is 13: the first shard was truncated
+        // at a count of 2, the second returned every term it has
+        let mut full: Vec<(String, u64)> = vec![("a".into(), 50), ("b".into(), 30)];
+        full.extend((0..11).map(|i| (format!("t{}", i), 2)));
+        let full: Vec<(&str, u64)> = full.iter().map(|(k, c)| (k.as_str(), *c)).collect();
+
+        let merged = AggregationMerger::merge(
+            &requests,
+            &[
+                HashMap::from([result("tags", buckets(&full))]),
+                HashMap::from([result("tags", buckets(&[("c", 40), ("a", 5)]))]),
+            ],
+        )
+        .unwrap();
+
+        let AggregationValue::Buckets(buckets) = &merged["tags"].value else {
+            panic!("expected buckets");
+        };
+        assert_eq!(buckets.len(), 2);
+        assert_eq!((buckets[0].key.as_str(), buckets[0].doc_count), ("a", 55));
+        assert_eq!(buckets[0].doc_count_error_upper_bound, Some(0));
+        // "c" may occur up to twice on the truncated shard
+        assert_eq!((buckets[1].key.as_str(), buckets[1].doc_count), ("c", 40));
+        assert_eq!(buckets[1].doc_count_error_upper_bound, Some(2));
+    }
+
+    #[test]
+    fn test_merge_histogram_applies_min_doc_count_after_merging() {
+        let requests = vec![agg(
+            "prices",
+            AggregationType::Histogram {
+                field: "price".into(),
+                interval: 10.0,
+                min_doc_count: Some(2),
+                extended_bounds: None,
+            },
+        )];
+
+        let merged = AggregationMerger::merge(
+            &requests,
+            &[
+                HashMap::from([result("prices", buckets(&[("10", 1), ("20", 3)]))]),
+                HashMap::from([result(
+                    "prices",
+                    buckets(&[("0", 1), ("10", 1), ("100", 1)]),
+                )]),
+            ],
+        )
+        .unwrap();
+
+        let AggregationValue::Buckets(buckets) = &merged["prices"].value else {
+            panic!("expected buckets");
+        };
+        let keys: Vec<(&str, u64)> = buckets
+            .iter()
+            .map(|b| (b.key.as_str(), b.doc_count))
+            .collect();
+        assert_eq!(keys, vec![("10", 2), ("20", 3)]);
+    }
+
+    #[test]
+    fn test_merge_percentiles_from_sketches() {
+        let percents = vec![50.0, 90.0];
+        let requests = vec![agg(
+            "latency",
+            AggregationType::Percentiles {
+                field: "ms".into(),
+                percents: percents.clone(),
+            },
+        )];
+        let points = percentile_sketch_points(&percents);
+
+        let mut low: Vec<f64> = (0..300).map(f64::from).collect();
+        let mut high: Vec<f64> = (300..1000).map(f64::from).collect();
+        let shards: Vec<HashMap<String, AggregationResult>> = [&mut low, &mut high]
+            .into_iter()
+            .map(|values| {
+                HashMap::from([
+                    result("latency#count", stats(values)),
+                    result("latency", shard_percentiles(values, &points)),
+                ])
+            })
+            .collect();
+
+        let merged = AggregationMerger::merge(&requests, &shards).unwrap();
+        let AggregationValue::Percentiles(result) = &merged["latency"].value else {
+            panic!("expected percentiles");
+        };
+        // 0..1000 across both shards: the median is ~500 and p90 ~900
+        let median = result.values["50"].unwrap();
+        let p90 = result.values["90"].unwrap();
+        assert!((median - 499.5).abs() < 5.0, "median {}", median);
+        assert!((p90 - 899.5).abs() < 5.0, "p90 {}", p90);
+    }
 }
@@ -32,13 +32,19 @@
 mod merger;
 mod router;
 
-pub use merger::{MergeStrategy, ResultMerger, ScoreNormalizer};
+pub use merger::{AggregationMerger, MergeStrategy, ResultMerger, ScoreNormalizer};
 pub use router::{QueryRouter, RoutingDecision, RoutingStrategy, ShardTarget};
 
 use crate::error::{ClusterError, Result};
 use crate::placement::ClusterState;
-use crate::types::{RpcDocument, RpcQuery, RpcSearchResult, RpcSearchResults};
+use crate::types::{
+    RpcDocument, RpcPrimaryWrite, RpcQuery, RpcSearchResult, RpcSearchResults, RpcWriteOp,
+};
 use crate::ClusterClient;
+use async_trait::async_trait;
+use prism::aggregations::AggregationResult;
+use prism::backends::TermStatistics;
+use prism::collection::CollectionManager;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -172,6 +178,9 @@ pub struct FederatedResults {
     pub is_partial: bool,
     /// Merge strategy used
     pub merge_strategy: MergeStrategy,
+    /// Aggregation results merged across shards
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub aggregations: HashMap<String, AggregationResult>,
 }
 
 impl FederatedResults {
@@ -181,10 +190,28 @@ impl FederatedResults {
             results: self.results.clone(),
             total: self.total,
             latency_ms: self.latency_ms,
+            aggregations: self.aggregations.clone(),
+            text_results: Vec::new(),
+            vector_results: Vec::new(),
         }
     }
 }
 
+/// Embeds query text on the coordinator, so every shard of a hybrid search
+/// searches with the same vector
+#[async_trait]
+pub trait QueryEmbedder: Send + Sync {
+    /// Embed `text` with the embedder of `collection`
+    async fn embed_query(&self, collection: &str, text: &str) -> Result<Vec<f32>>;
+}
+
+#[async_trait]
+impl QueryEmbedder for CollectionManager {
+    async fn embed_query(&self, collection: &str, text: &str) -> Result<Vec<f32>> {
+        Ok(CollectionManager::embed_query(self, collection, text).await?)
+    }
+}
+
 /// Federated search executor
 pub struct FederatedSearch {
     client: Arc<ClusterClient>,
@@ -193,6 +220,7 @@ pub struct FederatedSearch {
     router: QueryRouter,
     merger: ResultMerger,
     semaphore: Arc<Semaphore>,
+    embedder: Option<Arc<dyn QueryEmbedder>>,
 }
 
 impl FederatedSearch {
@@ -213,13 +241,32 @@ impl FederatedSearch {
             router,
             merger,
             semaphore,
+            embedder: None,
         }
     }
 
+    /// Embed the text of hybrid queries that carry no vector with `embedder`
+    pub fn with_embedder(mut self, embedder: Arc<dyn QueryEmbedder>) -> Self {
+        self.embedder = Some(embedder);
+        self
+    }
+
     /// Execute a federated search across all relevant shards
-    pub async fn search(&self, collection: &str, query: RpcQuery) -> Result<FederatedResults> {
+    ///
+    /// A query with a `query_vector`, or a `vector_weight` and an embedder
+    /// to produce one, runs as a hybrid search whose text and vector hits
+    /// are fused across all shards.
+    pub async fn search(&self, collection: &str, mut query: RpcQuery) -> Result<FederatedResults> {
         let start = Instant::now();
 
+        // Embed once here rather than on every shard
+        if query.query_vector.is_none() && query.vector_weight.is_some_and(|w| w > 0.0) {
+            if let Some(embedder) = &self.embedder {
+                query.query_vector =
+                    Some(embedder.embed_query(collection, &query.query_string).await?);
+            }
+        }
+
         // Get routing decision
         let routing = self.router.route(collection, &query)?;
         debug!(
@@ -240,12 +287,25 @@ impl FederatedSearch {
                 shard_status: ShardStatus::new(0),
                 is_partial: false,
                 merge_strategy: self.config.default_merge_strategy.clone(),
+                aggregations: HashMap::new(),
             });
         }
 
+        // Shards compute partial aggregations that merge into the requested ones
+        let agg_requests = query.aggregations.clone();
+        let mut shard_query = query.clone();
+        shard_query.aggregations = AggregationMerger::shard_requests(&agg_requests);
+
+        if shard_query.dfs && shard_query.term_statistics.is_none() {
+            shard_query.term_statistics = Some(
+                self.collect_term_statistics(collection, &shard_query, &routing.targets)
+                    .await,
+            );
+        }
+
         // Execute scatter-gather
-        let (shard_results, shard_status) = self
-            .scatter_gather(collection, &query, &routing.targets)
+        let (mut shard_results, shard_status) = self
+            .scatter_gather(collection, &shard_query, &routing.targets)
             .await;
 
         // Check if we have enough results
@@ -257,11 +317,25 @@ impl FederatedSearch {
         }
 
         // Merge results
-        let merge_strategy = query
-            .merge_strategy
-            .as_ref()
-            .and_then(|s| MergeStrategy::from_string(s))
-            .unwrap_or_else(|| self.config.default_merge_strategy.clone());
+        let merge_strategy = if query.query_vector.is_some() {
+            Self::hybrid_strategy(&query)
+        } else {
+            query
+                .merge_strategy
+                .as_ref()
+                .and_then(|s| MergeStrategy::from_string(s))
+                .unwrap_or_else(|| self.config.default_merge_strategy.clone())
+        };
+
+        let aggregations = if agg_requests.is_empty() {
+            HashMap::new()
+        } else {
+            let partials: Vec<_> = shard_results
+                .iter_mut()
+                .map(|r| std::mem::take(&mut r.aggregations))
+                .collect();
+            AggregationMerger::merge(&agg_requests, &partials)?
+        };
 
         let merged = self
             .merger
@@ -284,9 +358,59 @@ impl FederatedSearch {
             shard_status,
             is_partial,
             merge_strategy,
+            aggregations,
         })
     }
 
+    /// Fusion of a hybrid query's text and vector rankings: weighted when
+    /// the query asks for `weighted`, RRF otherwise
+    fn hybrid_strategy(query: &RpcQuery) -> MergeStrategy {
+        match query.merge_strategy.as_deref() {
+            Some("weighted") => {
+                let vector_weight = query.vector_weight.unwrap_or(0.5);
+                MergeStrategy::HybridWeighted {
+                    text_weight: query.text_weight.unwrap_or(1.0 - vector_weight),
+                    vector_weight,
+                }
+            }
+            _ => MergeStrategy::ReciprocalRankFusion {
+                k: query.rrf_k.unwrap_or(60) as u32,
+            },
+        }
+    }
+
+    /// DFS phase: sum the term statistics of every shard
+    ///
+    /// Shards that fail to answer are left out; their search still scores
+    /// with the statistics of the others.
+    async fn collect_term_statistics(
+        &self,
+        collection: &str,
+        query: &RpcQuery,
+        targets: &[ShardTarget],
+    ) -> TermStatistics {
+        let futures = targets.iter().map(|target| async move {
+            let _permit = self.semaphore.acquire().await.ok();
+            let result = self
+                .client
+                .term_statistics(&target.node_address, collection, query.clone())
+                .await;
+            (target, result)
+        });
+
+        let mut statistics = TermStatistics::default();
+        for (target, result) in futures::future::join_all(futures).await {
+            match result {
+                Ok(shard_statistics) => statistics.merge(&shard_statistics),
+                Err(e) => warn!(
+                    "DFS phase failed for shard {} ({}): {}",
+                    target.shard_id, target.node_address, e
+                ),
+            }
+        }
+        statistics
+    }
+
     /// Scatter query to shards and gather results
     async fn scatter_gather(
         &self,
@@ -388,18 +512,21 @@ impl FederatedSearch {
     }
 
     /// Execute a federated index (route documents to correct shards)
+    ///
+    /// Each shard's documents go to its primary, which replicates them
+    /// before acknowledging.
     pub async fn index(&self, collection: &str, docs: Vec<RpcDocument>) -> Result<IndexStatus> {
         let start = Instant::now();
 
         // Group documents by target shard
-        let mut shard_docs: HashMap<String, Vec<RpcDocument>> = HashMap::new();
+        let mut shard_docs: HashMap<String, (ShardTarget, Vec<RpcDocument>)> = HashMap::new();
 
         for doc in docs {
-            let routing = self.router.route_by_id(collection, &doc.id)?;
-            if let Some(target) = routing.targets.first() {
+            if let Some(target) = self.router.route_write(collection, &doc.id)? {
                 shard_docs
-                    .entry(target.node_address.clone())
-                    .or_default()
+                    .entry(target.shard_id.clone())
+                    .or_insert_with(|| (target, Vec::new()))
+                    .1
                     .push(doc);
             }
         }
@@ -414,22 +541,20 @@ impl FederatedSearch {
         };
 
         let futures: Vec<_> = shard_docs
-            .into_iter()
-            .map(|(addr, docs)| {
-                let client = Arc::clone(&self.client);
-                let collection = collection.to_string();
+            .into_values()
+            .map(|(target, docs)| {
                 let doc_count = docs.len();
-
+                let op = RpcWriteOp::Index(docs);
                 async move {
-                    let result = client.index(&addr, &collection, docs).await;
-                    (addr, doc_count, result)
+                    let result = self.write_to_primary(collection, &target, op).await;
+                    (target, doc_count, result)
                 }
             })
             .collect();
 
         let results = futures::future::join_all(futures).await;
 
-        for (addr, doc_count, result) in results {
+        for (target, doc_count, result) in results {
             status.total_docs += doc_count;
             match result {
                 Ok(()) => {
@@ -439,10 +564,10 @@ impl FederatedSearch {
                 Err(e) => {
                     status.failed_docs += doc_count;
                     status.shard_status.record_failure(ShardFailure {
-                        shard_id: addr.clone(),
-                        node: addr,
+                        shard_id: target.shard_id,
+                        node: target.node_address,
                         reason: e.to_string(),
-                        is_timeout: false,
+                        is_timeout: matches!(e, ClusterError::Timeout(_)),
                     });
                 }
             }
@@ -457,14 +582,14 @@ impl FederatedSearch {
         let start = Instant::now();
 
         // Group IDs by target shard
-        let mut shard_ids: HashMap<String, Vec<String>> = HashMap::new();
+        let mut shard_ids: HashMap<String, (ShardTarget, Vec<String>)> = HashMap::new();
 
         for id in ids {
-            let routing = self.router.route_by_id(collection, &id)?;
-            if let Some(target) = routing.targets.first() {
+            if let Some(target) = self.router.route_write(collection, &id)? {
                 shard_ids
-                    .entry(target.node_address.clone())
-                    .or_default()
+                    .entry(target.shard_id.clone())
+                    .or_insert_with(|| (target, Vec::new()))
+                    .1
                     .push(id);
             }
         }
@@ -479,22 +604,20 @@ impl FederatedSearch {
         };
 
         let futures: Vec<_> = shard_ids
-            .into_iter()
-            .map(|(addr, ids)| {
-                let client = Arc::clone(&self.client);
-                let collection = collection.to_string();
+            .into_values()
+            .map(|(target, ids)| {
                 let id_count = ids.len();
-
+                let op = RpcWriteOp::Delete(ids);
                 async move {
-                    let result = client.delete(&addr, &collection, ids).await;
-                    (addr, id_count, result)
+                    let result = self.write_to_primary(collection, &target, op).await;
+                    (target, id_count, result)
                 }
             })
             .collect();
 
         let results = futures::future::join_all(futures).await;
 
-        for (addr, id_count, result) in results {
+        for (target, id_count, result) in results {
             status.total_ids += id_count;
             match result {
                 Ok(()) => {
@@ -504,10 +627,10 @@ impl FederatedSearch {
                 Err(e) => {
                     status.failed_deletes += id_count;
                     status.shard_status.record_failure(ShardFailure {
-                        shard_id: addr.clone(),
-                        node: addr,
+                        shard_id: target.shard_id,
+                        node: target.node_address,
                         reason: e.to_string(),
-                        is_timeout: false,
+                        is_timeout: matches!(e, ClusterError::Timeout(_)),
                     });
                 }
             }
@@ -517,6 +640,32 @@ impl FederatedSearch {
         Ok(status)
     }
 
+    /// Send a write to a shard's primary and record which replicas are
+    /// in sync afterwards, so reads only go to up-to-date copies
+    async fn write_to_primary(
+        &self,
+        collection: &str,
+        target: &ShardTarget,
+        op: RpcWriteOp,
+    ) -> Result<()> {
+        let request = RpcPrimaryWrite {
+            shard_id: target.shard_id.clone(),
+            collection: collection.to_string(),
+            op,
+        };
+        let ack = self
+            .client
+            .primary_write(&target.node_address, request)
+            .await?;
+        debug!(
+            "Write {} to shard {} acknowledged by {}/{} copies",
+            ack.seq_no, ack.shard_id, ack.acks, ack.required
+        );
+        self.cluster_state
+            .update_in_sync_replicas(&ack.shard_id, ack.in_sync_replicas);
+        Ok(())
+    }
+
     /// Get aggregated stats across all shards
     pub async fn stats(&self, collection: &str) -> Result<AggregatedStats> {
         let shards = self.cluster_state.get_collection_shards(collection);
@@ -734,6 +883,7 @@ mod tests {
             shard_status: ShardStatus::new(1),
             is_partial: false,
             merge_strategy: MergeStrategy::Simple,
+            aggregations: HashMap::new(),
         };
 
         let rpc = result.to_rpc_results();
@@ -751,6 +901,7 @@ mod tests {
             shard_status: ShardStatus::new(0),
             is_partial: false,
             merge_strategy: MergeStrategy::Simple,
+            aggregations: HashMap::new(),
         };
 
         let rpc = result.to_rpc_results();
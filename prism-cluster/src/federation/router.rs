@@ -9,6 +9,7 @@ use crate::types::RpcQuery;
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 /// Routing strategy for queries
@@ -69,12 +70,25 @@ pub struct RoutingDecision {
 /// Query router
 pub struct QueryRouter {
     cluster_state: Arc<ClusterState>,
+    /// Rotates reads across the in-sync copies of each shard
+    read_counter: AtomicUsize,
 }
 
 impl QueryRouter {
     /// Create a new query router
     pub fn new(cluster_state: Arc<ClusterState>) -> Self {
-        Self { cluster_state }
+        Self {
+            cluster_state,
+            read_counter: AtomicUsize::new(0),
+        }
+    }
+
+    /// Address of a node that can serve requests (registered, not draining)
+    fn serving_address(&self, node_id: &str) -> Option<String> {
+        self.cluster_state
+            .get_node(node_id)
+            .filter(|node| !node.draining)
+            .map(|node| node.info.address)
     }
 
     /// Route a search query to appropriate shards
@@ -97,36 +111,30 @@ impl QueryRouter {
         let mut unavailable = 0;
 
         for shard in &shards {
-            if shard.state.can_serve_reads() {
-                // Get node address from cluster state, skip draining nodes
-                if let Some(node) = self.cluster_state.get_node(&shard.primary_node) {
-                    if !node.draining {
-                        targets.push(ShardTarget::from_assignment(
-                            shard,
-                            node.info.address.clone(),
-                        ));
-                        continue;
-                    }
-                }
-                // Primary unavailable or draining - try replicas
-                let mut found = false;
-                for replica in &shard.replica_nodes {
-                    if let Some(node) = self.cluster_state.get_node(replica) {
-                        if !node.draining {
-                            targets.push(ShardTarget::from_assignment(
-                                shard,
-                                node.info.address.clone(),
-                            ));
-                            found = true;
-                            break;
-                        }
-                    }
-                }
-                if !found {
-                    unavailable += 1;
-                }
-            } else {
+            if !shard.state.can_serve_reads() {
                 unavailable += 1;
+                continue;
+            }
+
+            // Spread reads over the primary and its in-sync replicas
+            let in_sync: Vec<String> = std::iter::once(&shard.primary_node)
+                .chain(&shard.in_sync_replicas)
+                .filter_map(|node_id| self.serving_address(node_id))
+                .collect();
+            let address = if in_sync.is_empty() {
+                // Primary unavailable or draining - fall back to any replica
+                shard
+                    .replica_nodes
+                    .iter()
+                    .find_map(|node_id| self.serving_address(node_id))
+            } else {
+                let turn = self.read_counter.fetch_add(1, Ordering::Relaxed);
+                Some(in_sync[turn % in_sync.len()].clone())
+            };
+
+            match address {
+                Some(address) => targets.push(ShardTarget::from_assignment(shard, address)),
+                None => unavailable += 1,
             }
         }
 
@@ -149,20 +157,25 @@ impl QueryRouter {
             });
         }
 
-        // Hash the ID to determine target shard
-        let shard_count = shards.len();
-        let shard_index = Self::hash_to_shard(id, shard_count);
+        let shard = self.shard_for_id(collection, &shards, id)?;
 
-        // Find the shard with this number
-        let target_shard = shards
-            .iter()
-            .find(|s| s.shard_number as usize == shard_index);
+        let mut targets = Vec::new();
 
-        if let Some(shard) = target_shard {
-            let mut targets = Vec::new();
+        // Add primary (if not draining)
+        if let Some(node) = self.cluster_state.get_node(&shard.primary_node) {
+            if !node.draining {
+                targets.push(ShardTarget::from_assignment(
+                    shard,
+                    node.info.address.clone(),
+                ));
+            }
+        }
 
-            // Add primary (if not draining)
-            if let Some(node) = self.cluster_state.get_node(&shard.primary_node) {
+        // Add replicas for failover (skip draining), in-sync ones first
+        let mut replicas: Vec<&String> = shard.replica_nodes.iter().collect();
+        replicas.sort_by_key(|node_id| !shard.in_sync_replicas.contains(node_id));
+        for replica in replicas {
+            if let Some(node) = self.cluster_state.get_node(replica) {
                 if !node.draining {
                     targets.push(ShardTarget::from_assignment(
                         shard,
@@ -170,31 +183,51 @@ impl QueryRouter {
                     ));
                 }
             }
+        }
 
-            // Add replicas for failover (skip draining)
-            for replica in &shard.replica_nodes {
-                if let Some(node) = self.cluster_state.get_node(replica) {
-                    if !node.draining {
-                        targets.push(ShardTarget::from_assignment(
-                            shard,
-                            node.info.address.clone(),
-                        ));
-                    }
-                }
-            }
+        Ok(RoutingDecision {
+            targets,
+            strategy: RoutingStrategy::HashRouting,
+            is_partial: false,
+        })
+    }
 
-            Ok(RoutingDecision {
-                targets,
-                strategy: RoutingStrategy::HashRouting,
-                is_partial: false,
-            })
-        } else {
-            // Shard not found - this shouldn't happen
-            Err(ClusterError::Internal(format!(
-                "Shard {} not found for collection {}",
-                shard_index, collection
-            )))
+    /// Route a write by document ID to the primary of its shard
+    ///
+    /// Returns `None` when the collection has no shards. Writes go to the
+    /// primary even while it drains, since only the primary orders writes.
+    pub fn route_write(&self, collection: &str, id: &str) -> Result<Option<ShardTarget>> {
+        let shards = self.cluster_state.get_collection_shards(collection);
+        if shards.is_empty() {
+            return Ok(None);
         }
+
+        let shard = self.shard_for_id(collection, &shards, id)?;
+        let node = self
+            .cluster_state
+            .get_node(&shard.primary_node)
+            .ok_or_else(|| ClusterError::NodeUnavailable(shard.primary_node.clone()))?;
+        Ok(Some(ShardTarget::from_assignment(shard, node.info.address)))
+    }
+
+    /// Find the shard a document ID hashes to
+    fn shard_for_id<'a>(
+        &self,
+        collection: &str,
+        shards: &'a [ShardAssignment],
+        id: &str,
+    ) -> Result<&'a ShardAssignment> {
+        let shard_index = Self::hash_to_shard(id, shards.len());
+        shards
+            .iter()
+            .find(|s| s.shard_number as usize == shard_index)
+            .ok_or_else(|| {
+                // Shard not found - this shouldn't happen
+                ClusterError::Internal(format!(
+                    "Shard {} not found for collection {}",
+                    shard_index, collection
+                ))
+            })
     }
 
     /// Route with custom routing key
@@ -204,7 +237,7 @@ impl QueryRouter {
     }
 
     /// Hash a string to a shard index
-    fn hash_to_shard(key: &str, shard_count: usize) -> usize {
+    pub(crate) fn hash_to_shard(key: &str, shard_count: usize) -> usize {
         if shard_count == 0 {
             return 0;
         }
@@ -233,6 +266,7 @@ mod tests {
     use crate::config::NodeTopology;
     use crate::placement::NodeInfo;
     use crate::ShardState;
+    use std::collections::HashSet;
 
     fn setup_test_state() -> Arc<ClusterState> {
         let state = Arc::new(ClusterState::new());
@@ -296,6 +330,10 @@ mod tests {
             min_score: None,
             score_function: None,
             skip_ranking: false,
+            aggregations: vec![],
+            query_vector: None,
+            dfs: false,
+            term_statistics: None,
         };
 
         let decision = router.route("products", &query).unwrap();
@@ -341,6 +379,10 @@ mod tests {
             min_score: None,
             score_function: None,
             skip_ranking: false,
+            aggregations: vec![],
+            query_vector: None,
+            dfs: false,
+            term_statistics: None,
         };
 
         let decision = router.route("products", &query).unwrap();
@@ -396,6 +438,10 @@ mod tests {
             min_score: None,
             score_function: None,
             skip_ranking: false,
+            aggregations: vec![],
+            query_vector: None,
+            dfs: false,
+            term_statistics: None,
         };
         let decision = router.route("products", &query).unwrap();
         assert_eq!(decision.targets.len(), 3);
@@ -411,4 +457,54 @@ mod tests {
             assert_ne!(target.node_address, "127.0.0.1:9080");
         }
     }
+
+    #[test]
+    fn test_route_reads_from_in_sync_copies() {
+        let state = setup_test_state();
+        let mut shard = state.get_shard("products-shard-0").unwrap();
+        shard.replica_nodes = vec!["node-2".to_string(), "node-3".to_string()];
+        shard.in_sync_replicas = vec!["node-2".to_string()];
+        state.assign_shard(shard);
+        let router = QueryRouter::new(Arc::clone(&state));
+
+        let query = RpcQuery {
+            query_string: "test".into(),
+            fields: vec![],
+            limit: 10,
+            offset: 0,
+            merge_strategy: None,
+            text_weight: None,
+            vector_weight: None,
+            highlight: None,
+            rrf_k: None,
+            min_score: None,
+            score_function: None,
+            skip_ranking: false,
+            aggregations: vec![],
+            query_vector: None,
+            dfs: false,
+            term_statistics: None,
+        };
+        let mut addresses = HashSet::new();
+        for _ in 0..4 {
+            let decision = router.route("products", &query).unwrap();
+            let target = decision
+                .targets
+                .iter()
+                .find(|t| t.shard_id == "products-shard-0")
+                .unwrap();
+            addresses.insert(target.node_address.clone());
+        }
+        // Primary and the in-sync replica, never the lagging node-3
+        assert_eq!(
+            addresses,
+            HashSet::from(["127.0.0.1:9080".to_string(), "127.0.0.1:9081".to_string()])
+        );
+
+        // Writes always go to the primary
+        let primary = router.route_write("products", "doc-1").unwrap().unwrap();
+        let shard = state.get_shard(&primary.shard_id).unwrap();
+        let node = state.get_node(&shard.primary_node).unwrap();
+        assert_eq!(primary.node_address, node.info.address);
+    }
 }
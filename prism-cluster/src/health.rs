@@ -10,16 +10,20 @@
 //!   alive → suspect: Missed heartbeats (failure_threshold)
 //!   suspect → dead: Timeout without recovery (suspect_timeout)
 //!   suspect → alive: Heartbeat received
+//!   dead → alive: Heartbeat received again (node rejoined)
 //!   dead → removed: Admin action or auto-cleanup
 //! ```
 
 use crate::config::{ClusterConfig, FailureAction, HealthConfig};
+use crate::discovery::ClusterEvent;
 use crate::metrics;
 use crate::placement::ClusterState;
+use crate::protocol::local_handshake;
 use crate::ClusterClient;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
@@ -147,6 +151,8 @@ pub struct HealthChecker {
     cluster_state: Arc<ClusterState>,
     event_tx: broadcast::Sender<HealthEvent>,
     running: Arc<RwLock<bool>>,
+    /// Node ids of nodes reported by discovery, by cluster address
+    discovered: RwLock<HashMap<SocketAddr, String>>,
 }
 
 impl HealthChecker {
@@ -164,6 +170,7 @@ impl HealthChecker {
             cluster_state,
             event_tx,
             running: Arc::new(RwLock::new(false)),
+            discovered: RwLock::new(HashMap::new()),
         }
     }
 
@@ -198,6 +205,43 @@ impl HealthChecker {
         self.node_health.write().remove(node_id);
     }
 
+    /// React to a membership change reported by node discovery.
+    ///
+    /// Joined nodes are watched from then on. A node discovery reports gone
+    /// is declared dead at once: discovery already confirmed the failure.
+    pub fn handle_discovery_event(&self, event: &ClusterEvent) {
+        match event {
+            ClusterEvent::NodeJoined(node) | ClusterEvent::NodeUpdated(node) => {
+                let Some(node_id) = &node.node_id else {
+                    return;
+                };
+                self.discovered
+                    .write()
+                    .insert(node.address, node_id.clone());
+                self.register_node(node_id);
+                if self
+                    .node_health(node_id)
+                    .is_some_and(|info| info.state != HealthState::Alive)
+                {
+                    self.record_heartbeat(node_id, 0);
+                }
+            }
+            ClusterEvent::NodeLeft(address) => {
+                let node_id = self.discovered.read().get(address).cloned().or_else(|| {
+                    self.cluster_state
+                        .get_nodes()
+                        .into_iter()
+                        .find(|n| n.info.address.parse::<SocketAddr>().ok() == Some(*address))
+                        .map(|n| n.info.node_id)
+                });
+                if let Some(node_id) = node_id {
+                    self.mark_dead(&node_id);
+                }
+            }
+            ClusterEvent::RefreshComplete { .. } => {}
+        }
+    }
+
     /// Start the health checker background task
     pub fn start(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
         *self.running.write() = true;
@@ -247,12 +291,27 @@ impl HealthChecker {
 
     /// Check all registered nodes
     async fn check_all_nodes(&self) {
+        // Pick up nodes that joined through the metadata log
+        for node in self.cluster_state.get_nodes() {
+            self.register_node(&node.info.node_id);
+        }
         let node_ids: Vec<String> = self.node_health.read().keys().cloned().collect();
 
         for node_id in node_ids {
             // Skip self
             if node_id == self.cluster_config.node_id {
                 self.record_heartbeat(&node_id, 0);
+                self.cluster_state.update_node_version(
+                    &node_id,
+                    self.cluster_config.protocol_version,
+                    self.cluster_config.min_supported_version,
+                );
+                self.cluster_state.update_node_features(
+                    &node_id,
+                    &local_handshake(&self.cluster_config).features,
+                );
+                self.cluster_state
+                    .update_node_release(&node_id, env!("CARGO_PKG_VERSION"));
                 continue;
             }
 
@@ -294,6 +353,10 @@ impl HealthChecker {
                     response.protocol_version,
                     response.min_supported_version,
                 );
+                self.cluster_state
+                    .update_node_features(node_id, &response.features);
+                self.cluster_state
+                    .update_node_release(node_id, &response.version);
 
                 debug!(
                     "Heartbeat from {} in {}ms (proto v{})",
@@ -327,8 +390,9 @@ impl HealthChecker {
             info.missed_heartbeats = 0;
             info.last_latency_ms = Some(latency_ms);
 
-            // Transition suspect → alive
-            if info.state == HealthState::Suspect {
+            // Transition suspect → alive, or dead → alive when a node comes
+            // back, e.g. after a network partition heals
+            if info.state != HealthState::Alive {
                 info.state = HealthState::Alive;
                 info.state_since = Instant::now();
 
@@ -398,15 +462,39 @@ impl HealthChecker {
         }
 
         // Emit events and trigger actions outside of lock
-        for (node_id, previous, new) in transitions {
-            self.emit_state_change(&node_id, previous, new);
+        for (node_id, previous, _) in transitions {
+            self.on_node_dead(&node_id, previous);
+        }
+    }
 
-            // Mark unreachable in cluster state
-            self.cluster_state.mark_unreachable(&node_id);
+    /// Declare a node dead regardless of its missed heartbeats
+    fn mark_dead(&self, node_id: &str) {
+        let previous = {
+            let mut health = self.node_health.write();
+            let Some(info) = health.get_mut(node_id) else {
+                return;
+            };
+            if info.state == HealthState::Dead {
+                return;
+            }
+            let previous = info.state;
+            info.state = HealthState::Dead;
+            info.state_since = Instant::now();
+            previous
+        };
+        warn!("Node {} is now dead: discovery reported it gone", node_id);
+        self.on_node_dead(node_id, previous);
+    }
 
-            // Handle failure action
-            self.handle_node_failure(&node_id);
-        }
+    /// Emit the transition to dead and act on the failure
+    fn on_node_dead(&self, node_id: &str, previous: HealthState) {
+        self.emit_state_change(node_id, previous, HealthState::Dead);
+
+        // Mark unreachable in cluster state
+        self.cluster_state.mark_unreachable(node_id);
+
+        // Handle failure action
+        self.handle_node_failure(node_id);
     }
 
     /// Handle a node failure based on configured action
@@ -884,10 +972,61 @@ mod tests {
             checker.node_health("node-1").unwrap().state,
             HealthState::Dead
         );
+
+        // A dead node that answers again rejoins
+        checker.record_heartbeat("node-1", 5);
+        assert_eq!(
+            checker.node_health("node-1").unwrap().state,
+            HealthState::Alive
+        );
+        assert!(checker.cluster_state.get_node("node-1").unwrap().reachable);
     }
 
     // --- handle_node_failure coverage ---
 
+    #[test]
+    fn test_discovery_events() {
+        let (health_config, cluster_config) = make_config();
+        let checker =
+            HealthChecker::new(health_config, cluster_config, Arc::new(ClusterState::new()));
+        let mut events = checker.subscribe();
+        let address: SocketAddr = "127.0.0.1:9080".parse().unwrap();
+        let node = crate::discovery::DiscoveredNode {
+            node_id: Some("node-1".to_string()),
+            ..crate::discovery::DiscoveredNode::new(address)
+        };
+
+        checker.handle_discovery_event(&ClusterEvent::NodeJoined(node.clone()));
+        assert_eq!(
+            checker.node_health("node-1").unwrap().state,
+            HealthState::Alive
+        );
+
+        // A node reported gone is dead without going through suspect
+        checker.handle_discovery_event(&ClusterEvent::NodeLeft(address));
+        assert_eq!(
+            checker.node_health("node-1").unwrap().state,
+            HealthState::Dead
+        );
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.previous_state, HealthState::Alive);
+        assert_eq!(event.new_state, HealthState::Dead);
+
+        // It is alive again once it rejoins
+        checker.handle_discovery_event(&ClusterEvent::NodeJoined(node));
+        assert_eq!(
+            checker.node_health("node-1").unwrap().state,
+            HealthState::Alive
+        );
+
+        // Nodes discovery knows nothing about are ignored
+        checker.handle_discovery_event(&ClusterEvent::NodeLeft("127.0.0.1:9999".parse().unwrap()));
+        checker.handle_discovery_event(&ClusterEvent::NodeJoined(
+            crate::discovery::DiscoveredNode::new(address),
+        ));
+        assert_eq!(checker.cluster_health().total_count, 1);
+    }
+
     #[test]
     fn test_handle_failure_rebalance_action() {
         let health_config = HealthConfig {
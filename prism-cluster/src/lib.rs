@@ -9,9 +9,15 @@
 //! - **Transport**: Quinn QUIC with TLS for secure, multiplexed connections
 //! - **Server**: Wraps CollectionManager to serve cluster RPC requests
 //! - **Client**: Connection-pooled client for calling remote nodes
-//! - **Discovery**: Pluggable node discovery (static, DNS)
+//! - **Discovery**: Pluggable node discovery (static, DNS, SWIM gossip)
 //! - **Placement**: Zone-aware shard placement with configurable strategies
 //! - **Rebalancing**: Automatic and manual shard rebalancing
+//! - **Transfer**: Chunked, resumable copying of shard data between nodes
+//! - **Replication**: Sequenced primary/replica write replication with catch-up
+//! - **Metadata**: Raft-replicated membership, shard assignments, schemas and ILM policies
+//! - **Resharding**: Splitting and shrinking the shards of a collection
+//! - **Cross-cluster replication**: Follower collections pulling a leader collection's writes
+//! - **Protocol**: Version and feature negotiation between nodes of different releases
 //!
 //! # Key Operations
 //!
@@ -20,40 +26,56 @@
 //! - import_by_query: Cross-cluster data migration
 //! - Shard management: assign, transfer, get assignments
 //! - Rebalancing: trigger rebalance, get status
-//! - Discovery: static config, DNS-based node discovery
+//! - Discovery: static config, DNS-based and gossip-based node discovery
 
+pub mod ccr;
 pub mod config;
 pub mod discovery;
 pub mod error;
 pub mod federation;
 pub mod health;
+pub mod metadata;
 pub mod metrics;
 pub mod partition;
 pub mod placement;
+pub mod protocol;
 pub mod rebalance;
+pub mod replication;
+pub mod reshard;
 pub mod schema;
 pub mod service;
+pub mod transfer;
 pub mod transport;
 pub mod types;
 
 mod client;
 mod server;
 
+pub use ccr::{
+    CrossClusterReplication, FollowConfig, FollowState, Follower, FollowerSink, FollowerStatus,
+    LeaderTransport, ShardFollowProgress,
+};
 pub use client::ClusterClient;
 pub use config::{
     ClusterConfig, ClusterTlsConfig, ConflictResolution, ConsistencyConfig, FailureAction,
-    HealthConfig, NodeTopology, PartitionBehavior, RebalancingConfig, WriteQuorum,
+    HealthConfig, MetadataConfig, NodeTopology, OperationLogConfig, PartitionBehavior,
+    RebalancingConfig, WriteQuorum,
 };
 pub use discovery::{
-    ClusterEvent, DiscoveredNode, DiscoveryConfig, DnsDiscovery, NodeDiscovery, StaticDiscovery,
+    ClusterEvent, DiscoveredNode, DiscoveryConfig, DnsDiscovery, GossipConfig, GossipDiscovery,
+    NodeDiscovery, StaticDiscovery,
 };
 pub use error::ClusterError;
 pub use federation::{
     AggregatedStats, DeleteStatus, FederatedResults, FederatedSearch, FederationConfig,
-    IndexStatus, MergeStrategy, QueryRouter, ResultMerger, RoutingDecision, RoutingStrategy,
-    ShardFailure, ShardStatus, ShardTarget,
+    IndexStatus, MergeStrategy, QueryEmbedder, QueryRouter, ResultMerger, RoutingDecision,
+    RoutingStrategy, ShardFailure, ShardStatus, ShardTarget,
 };
 pub use health::{ClusterHealth, HealthChecker, HealthEvent, HealthState, NodeHealthInfo};
+pub use metadata::{
+    ClusterMetadata, MetadataCommand, MetadataSnapshot, MetadataTransport, RaftNode, RaftRole,
+    RaftStatus,
+};
 pub use partition::{
     PartitionAwareOp, PartitionDetector, PartitionError, PartitionEvent, PartitionState,
 };
@@ -61,10 +83,17 @@ pub use placement::{
     BalanceFactor, ClusterState, ClusterStateSnapshot, NodeInfo, NodeState, PlacementDecision,
     PlacementError, PlacementStrategy, ReplicaRole, ShardAssignment, ShardState, SpreadLevel,
 };
+pub use protocol::{NegotiatedProtocol, MIN_SUPPORTED_VERSION, PROTOCOL_VERSION};
 pub use rebalance::{
     OperationStatus, RebalanceEngine, RebalanceOperation, RebalanceOperationStatus, RebalancePhase,
     RebalancePlan, RebalanceStatus, RebalanceTrigger,
 };
+pub use replication::{
+    ClientTransport, ConflictReport, Reconciliation, ReplicaTransport, Replicator,
+};
+pub use reshard::{
+    ReshardKind, ReshardPlan, ReshardReport, ReshardTransport, Resharder, ShardFold,
+};
 pub use schema::{
     ChangeType, PropagationConfig, PropagationStatus, PropagationStrategy, SchemaChange,
     SchemaOperationResult, SchemaPropagator, SchemaRegistry, SchemaRegistrySnapshot, SchemaVersion,
@@ -72,4 +101,7 @@ pub use schema::{
 };
 pub use server::ClusterServer;
 pub use service::PrismClusterClient;
+pub use transfer::{
+    RemoteTarget, ShardReceiver, ShardSender, ShardTransfers, TransferJob, TransferTarget,
+};
 pub use types::*;
@@ -0,0 +1,315 @@
+//! Persisted Raft log of metadata changes
+
+use super::MetadataSnapshot;
+use crate::error::{ClusterError, Result};
+use crate::types::RpcRaftEntry;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Snapshot replacing the log entries up to and including `last_index`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogSnapshot {
+    /// Index of the last entry the snapshot covers
+    pub last_index: u64,
+    /// Term of the last entry the snapshot covers
+    pub last_term: u64,
+    /// Metadata after applying that entry
+    pub metadata: MetadataSnapshot,
+}
+
+/// What a node must remember across restarts
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PersistedLog {
+    term: u64,
+    voted_for: Option<String>,
+    snapshot: Option<LogSnapshot>,
+    /// Entries after the snapshot, in index order
+    entries: Vec<RpcRaftEntry>,
+}
+
+/// Current term, vote and log entries of a node.
+///
+/// The whole log is rewritten on every save; metadata changes are rare and
+/// the log is kept short by snapshots.
+pub struct RaftLog {
+    path: Option<PathBuf>,
+    log: PersistedLog,
+}
+
+impl RaftLog {
+    /// Log kept in memory only
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            log: PersistedLog::default(),
+        }
+    }
+
+    /// Load the log from `dir`, starting empty if none was saved
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join("metadata-log.json");
+        let log = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|e| ClusterError::Serialization(e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => PersistedLog::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path: Some(path),
+            log,
+        })
+    }
+
+    /// Persist the log
+    pub async fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let data = serde_json::to_vec(&self.log)
+            .map_err(|e| ClusterError::Serialization(e.to_string()))?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, data).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+
+    /// Latest term this node has seen
+    pub fn term(&self) -> u64 {
+        self.log.term
+    }
+
+    /// Candidate voted for in the current term
+    pub fn voted_for(&self) -> Option<&str> {
+        self.log.voted_for.as_deref()
+    }
+
+    /// Move to a term, with the vote cast in it
+    pub fn set_term(&mut self, term: u64, voted_for: Option<String>) {
+        self.log.term = term;
+        self.log.voted_for = voted_for;
+    }
+
+    /// Snapshot replacing the oldest entries
+    pub fn snapshot(&self) -> Option<&LogSnapshot> {
+        self.log.snapshot.as_ref()
+    }
+
+    /// Index of the last entry covered by the snapshot, 0 without one
+    pub fn snapshot_index(&self) -> u64 {
+        self.log.snapshot.as_ref().map_or(0, |s| s.last_index)
+    }
+
+    /// Index of the last entry, 0 for an empty log
+    pub fn last_index(&self) -> u64 {
+        self.log
+            .entries
+            .last()
+            .map_or(self.snapshot_index(), |e| e.index)
+    }
+
+    /// Term of the last entry, 0 for an empty log
+    pub fn last_term(&self) -> u64 {
+        self.term_at(self.last_index()).unwrap_or(0)
+    }
+
+    /// Term of the entry at `index`, `None` if it is not in the log or was
+    /// compacted into the snapshot
+    pub fn term_at(&self, index: u64) -> Option<u64> {
+        match &self.log.snapshot {
+            _ if index == 0 => Some(0),
+            Some(snapshot) if index == snapshot.last_index => Some(snapshot.last_term),
+            _ => self.entry(index).map(|e| e.term),
+        }
+    }
+
+    /// Entry at `index`, unless compacted or not in the log
+    pub fn entry(&self, index: u64) -> Option<&RpcRaftEntry> {
+        let offset = index.checked_sub(self.snapshot_index() + 1)?;
+        self.log.entries.get(offset as usize)
+    }
+
+    /// Up to `max` entries starting at `index`
+    pub fn entries_from(&self, index: u64, max: usize) -> Vec<RpcRaftEntry> {
+        let offset = index.saturating_sub(self.snapshot_index() + 1) as usize;
+        self.log
+            .entries
+            .iter()
+            .skip(offset)
+            .take(max)
+            .cloned()
+            .collect()
+    }
+
+    /// Number of entries not compacted into the snapshot
+    pub fn len(&self) -> usize {
+        self.log.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.log.entries.is_empty()
+    }
+
+    /// Append an entry after the last one
+    pub fn append(&mut self, entry: RpcRaftEntry) {
+        debug_assert_eq!(entry.index, self.last_index() + 1);
+        self.log.entries.push(entry);
+    }
+
+    /// Drop the entry at `index` and every later one
+    pub fn truncate_from(&mut self, index: u64) {
+        let offset = index.saturating_sub(self.snapshot_index() + 1) as usize;
+        self.log.entries.truncate(offset);
+    }
+
+    /// Replace the entries up to `last_index` with a snapshot taken after
+    /// applying them
+    pub fn compact(&mut self, last_index: u64, metadata: MetadataSnapshot) {
+        let Some(last_term) = self.term_at(last_index) else {
+            return;
+        };
+        let drop = last_index.saturating_sub(self.snapshot_index()) as usize;
+        self.log.entries.drain(..drop.min(self.log.entries.len()));
+        self.log.snapshot = Some(LogSnapshot {
+            last_index,
+            last_term,
+            metadata,
+        });
+    }
+
+    /// Take a snapshot received from the leader.
+    ///
+    /// Entries after it are kept if the log holds the snapshot's last entry;
+    /// otherwise the snapshot replaces the whole log.
+    pub fn install(&mut self, snapshot: LogSnapshot) {
+        if self.term_at(snapshot.last_index) == Some(snapshot.last_term) {
+            let drop = snapshot.last_index.saturating_sub(self.snapshot_index()) as usize;
+            self.log.entries.drain(..drop.min(self.log.entries.len()));
+        } else {
+            self.log.entries.clear();
+        }
+        self.log.snapshot = Some(snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::MetadataCommand;
+    use crate::placement::ClusterState;
+    use std::collections::{BTreeMap, HashMap};
+
+    fn entry(index: u64, term: u64) -> RpcRaftEntry {
+        RpcRaftEntry {
+            index,
+            term,
+            command: MetadataCommand::Noop,
+        }
+    }
+
+    fn metadata() -> MetadataSnapshot {
+        MetadataSnapshot {
+            cluster: ClusterState::new().snapshot(),
+            schemas: HashMap::new(),
+            ilm_policies: BTreeMap::new(),
+        }
+    }
+
+    fn log_with_terms(terms: &[u64]) -> RaftLog {
+        let mut log = RaftLog::in_memory();
+        for (i, term) in terms.iter().enumerate() {
+            log.append(entry(i as u64 + 1, *term));
+        }
+        log
+    }
+
+    #[test]
+    fn test_entries_and_truncation() {
+        let mut log = log_with_terms(&[1, 1, 2, 2]);
+        assert_eq!(log.last_index(), 4);
+        assert_eq!(log.last_term(), 2);
+        assert_eq!(log.term_at(0), Some(0));
+        assert_eq!(log.term_at(2), Some(1));
+        assert_eq!(log.term_at(5), None);
+        assert_eq!(
+            log.entries_from(3, 10)
+                .iter()
+                .map(|e| e.index)
+                .collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+
+        log.truncate_from(3);
+        assert_eq!(log.last_index(), 2);
+        assert_eq!(log.last_term(), 1);
+    }
+
+    #[test]
+    fn test_compact_keeps_later_entries() {
+        let mut log = log_with_terms(&[1, 1, 2, 2, 3]);
+        log.compact(3, metadata());
+
+        assert_eq!(log.snapshot_index(), 3);
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.term_at(3), Some(2));
+        assert!(log.entry(3).is_none());
+        assert_eq!(log.entry(4).unwrap().term, 2);
+        assert_eq!(log.last_index(), 5);
+        assert_eq!(
+            log.entries_from(1, 10)
+                .iter()
+                .map(|e| e.index)
+                .collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+
+        log.truncate_from(5);
+        log.append(entry(5, 4));
+        assert_eq!(log.last_term(), 4);
+    }
+
+    #[test]
+    fn test_install_snapshot() {
+        // A log holding the snapshot's last entry keeps what follows it
+        let mut log = log_with_terms(&[1, 1, 2]);
+        log.install(LogSnapshot {
+            last_index: 2,
+            last_term: 1,
+            metadata: metadata(),
+        });
+        assert_eq!(log.last_index(), 3);
+        assert_eq!(log.len(), 1);
+
+        // A conflicting or shorter log is replaced
+        let mut log = log_with_terms(&[1, 1]);
+        log.install(LogSnapshot {
+            last_index: 4,
+            last_term: 3,
+            metadata: metadata(),
+        });
+        assert!(log.is_empty());
+        assert_eq!(log.last_index(), 4);
+        assert_eq!(log.last_term(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_log_persists() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut log = RaftLog::load(temp.path()).unwrap();
+        assert_eq!(log.term(), 0);
+
+        log.set_term(3, Some("node-2:9080".to_string()));
+        log.append(entry(1, 3));
+        log.append(entry(2, 3));
+        log.compact(1, metadata());
+        log.save().await.unwrap();
+
+        let reloaded = RaftLog::load(temp.path()).unwrap();
+        assert_eq!(reloaded.term(), 3);
+        assert_eq!(reloaded.voted_for(), Some("node-2:9080"));
+        assert_eq!(reloaded.snapshot_index(), 1);
+        assert_eq!(reloaded.last_index(), 2);
+    }
+}
@@ -0,0 +1,91 @@
+//! Consensus-backed cluster metadata
+//!
+//! Node membership, shard assignments, schema versions and ILM policies are
+//! changed through a log replicated with Raft between the configured nodes.
+//! Only the leader appends to the log; other nodes forward their writes to
+//! it. An entry is committed once a majority of the nodes stored it, and
+//! every node applies committed entries in log order to its
+//! [`ClusterState`](crate::placement::ClusterState), [`SchemaRegistry`] and
+//! ILM policies, so all nodes go through the same metadata versions.
+//!
+//! Reads are served from the local copy on any node. A write returns once
+//! the node it was made on applied it, so reading back from that node sees
+//! it; other followers may lag by up to a heartbeat.
+//!
+//! The log is persisted and compacted into a snapshot of the metadata once
+//! it grows past [`MetadataConfig::snapshot_threshold`] entries. Followers
+//! missing compacted entries are sent the snapshot instead.
+//!
+//! Heartbeats, reachability and the replicas in sync with a shard's primary
+//! change too often to go through the log and are tracked by each node.
+//!
+//! [`SchemaRegistry`]: crate::schema::SchemaRegistry
+//! [`MetadataConfig::snapshot_threshold`]: crate::config::MetadataConfig::snapshot_threshold
+
+mod log;
+mod raft;
+mod state;
+
+pub use log::{LogSnapshot, RaftLog};
+pub use raft::{MetadataTransport, RaftNode, RaftRole, RaftStatus};
+pub use state::ClusterMetadata;
+
+use crate::placement::{ClusterStateSnapshot, NodeInfo, ShardAssignment, ShardState};
+use crate::schema::VersionedSchema;
+use prism::ilm::IlmPolicy;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A change to the cluster metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataCommand {
+    /// Appended by a new leader to commit entries of earlier terms
+    Noop,
+    /// Add a node to the cluster, or update its information
+    RegisterNode(NodeInfo),
+    /// Remove a node from the cluster
+    RemoveNode { node_id: String },
+    /// Stop or resume routing queries to a node
+    SetDraining { node_id: String, draining: bool },
+    /// Add or replace a shard assignment
+    AssignShard(ShardAssignment),
+    /// Assign the shards of a collection, unless it already has some
+    CreateShards {
+        collection: String,
+        assignments: Vec<ShardAssignment>,
+    },
+    /// Replace every shard of a collection after it was split or shrunk
+    ReplaceShards {
+        collection: String,
+        assignments: Vec<ShardAssignment>,
+    },
+    /// Remove a shard assignment
+    RemoveShard { shard_id: String },
+    /// Change the state of a shard
+    UpdateShardState { shard_id: String, state: ShardState },
+    /// Register the next schema version of a collection
+    PutSchema {
+        collection: String,
+        schema: serde_json::Value,
+        created_by: String,
+        created_at: u64,
+    },
+    /// Remove a collection's schema
+    RemoveSchema { collection: String },
+    /// Create or replace an ILM policy
+    PutIlmPolicy(IlmPolicy),
+    /// Delete an ILM policy
+    DeleteIlmPolicy { name: String },
+}
+
+/// The cluster metadata as of a log entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataSnapshot {
+    /// Nodes and shard assignments
+    pub cluster: ClusterStateSnapshot,
+    /// Current schema of each collection
+    pub schemas: HashMap<String, VersionedSchema>,
+    /// ILM policies by name
+    pub ilm_policies: BTreeMap<String, IlmPolicy>,
+}
@@ -0,0 +1,1156 @@
+//! Raft consensus over the cluster RPC transport
+
+use super::{ClusterMetadata, LogSnapshot, MetadataCommand, RaftLog};
+use crate::config::{ClusterConfig, MetadataConfig};
+use crate::error::{ClusterError, Result};
+use crate::replication::ClientTransport;
+use crate::types::{
+    RpcAppendEntries, RpcAppendEntriesResponse, RpcInstallSnapshot, RpcRaftEntry, RpcRequestVote,
+    RpcVoteResponse,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Mutex};
+use tracing::{debug, info, warn};
+
+/// How a node reaches the other members of the metadata log
+#[async_trait]
+pub trait MetadataTransport: Send + Sync {
+    /// Ask a node for its vote
+    async fn request_vote(&self, addr: &str, request: RpcRequestVote) -> Result<RpcVoteResponse>;
+
+    /// Send log entries, or a heartbeat, to a follower
+    async fn append_entries(
+        &self,
+        addr: &str,
+        request: RpcAppendEntries,
+    ) -> Result<RpcAppendEntriesResponse>;
+
+    /// Send a snapshot to a follower, returning its term
+    async fn install_snapshot(&self, addr: &str, request: RpcInstallSnapshot) -> Result<u64>;
+
+    /// Forward a change to the leader, returning its log index
+    async fn propose(&self, addr: &str, command: MetadataCommand) -> Result<u64>;
+}
+
+#[async_trait]
+impl MetadataTransport for ClientTransport {
+    async fn request_vote(&self, addr: &str, request: RpcRequestVote) -> Result<RpcVoteResponse> {
+        self.client().await?.raft_request_vote(addr, request).await
+    }
+
+    async fn append_entries(
+        &self,
+        addr: &str,
+        request: RpcAppendEntries,
+    ) -> Result<RpcAppendEntriesResponse> {
+        self.client()
+            .await?
+            .raft_append_entries(addr, request)
+            .await
+    }
+
+    async fn install_snapshot(&self, addr: &str, request: RpcInstallSnapshot) -> Result<u64> {
+        self.client()
+            .await?
+            .raft_install_snapshot(addr, request)
+            .await
+    }
+
+    async fn propose(&self, addr: &str, command: MetadataCommand) -> Result<u64> {
+        self.client().await?.propose_metadata(addr, command).await
+    }
+}
+
+/// Role of a node in the metadata log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RaftRole {
+    Follower,
+    Candidate,
+    Leader,
+}
+
+/// Progress of a node in the metadata log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaftStatus {
+    /// Address identifying this node in the log
+    pub address: String,
+    pub role: RaftRole,
+    pub term: u64,
+    /// Address of the leader this node follows, if known
+    pub leader: Option<String>,
+    /// Last entry known to be committed
+    pub commit_index: u64,
+    /// Last entry applied to the metadata
+    pub applied_index: u64,
+    /// Last entry compacted into the snapshot
+    pub snapshot_index: u64,
+    /// Last entry in the log
+    pub last_index: u64,
+}
+
+struct RaftState {
+    log: RaftLog,
+    role: RaftRole,
+    leader: Option<String>,
+    commit_index: u64,
+    last_applied: u64,
+    election_deadline: Instant,
+    /// Next entry to send to each peer, tracked by the leader
+    next_index: HashMap<String, u64>,
+    /// Last entry known to be stored on each peer, tracked by the leader
+    match_index: HashMap<String, u64>,
+    /// Peers with a request in flight
+    in_flight: HashSet<String>,
+}
+
+impl RaftState {
+    /// Follow `leader` in `term`, forgetting the vote if the term is newer
+    fn become_follower(&mut self, term: u64, leader: Option<String>) {
+        if term > self.log.term() {
+            self.log.set_term(term, None);
+        }
+        self.role = RaftRole::Follower;
+        self.leader = leader;
+    }
+
+    fn not_leader(&self) -> ClusterError {
+        ClusterError::NotLeader(match &self.leader {
+            Some(leader) => format!("leader is {}", leader),
+            None => "no leader elected".to_string(),
+        })
+    }
+}
+
+/// A request to a peer and what it tells the leader once answered
+enum Outgoing {
+    Append(RpcAppendEntries),
+    Snapshot(RpcInstallSnapshot),
+}
+
+struct PeerReply {
+    term: u64,
+    /// Last entry now known to be stored on the peer
+    matched: Option<u64>,
+    /// Entry to retry from when the peer's log did not match
+    retry_from: u64,
+}
+
+/// A member of the replicated metadata log.
+///
+/// Members are identified by their cluster address: this node's advertised
+/// address and the configured seed nodes.
+pub struct RaftNode {
+    address: String,
+    peers: Vec<String>,
+    config: MetadataConfig,
+    request_timeout: Duration,
+    metadata: Arc<ClusterMetadata>,
+    transport: Arc<dyn MetadataTransport>,
+    state: Mutex<RaftState>,
+    /// Held while entries or a snapshot are applied to the metadata
+    apply_lock: Mutex<()>,
+    applied: watch::Sender<u64>,
+    running: AtomicBool,
+}
+
+impl RaftNode {
+    pub fn new(
+        config: &ClusterConfig,
+        metadata: Arc<ClusterMetadata>,
+        transport: Arc<dyn MetadataTransport>,
+        log: RaftLog,
+    ) -> Self {
+        let address = config.advertise_address().to_string();
+        let mut peers: Vec<String> = config
+            .seed_nodes
+            .iter()
+            .filter(|peer| **peer != address)
+            .cloned()
+            .collect();
+        peers.sort();
+        peers.dedup();
+
+        let election_deadline = Instant::now() + election_timeout(&config.metadata);
+        Self {
+            address,
+            peers,
+            config: config.metadata.clone(),
+            request_timeout: config.request_timeout(),
+            metadata,
+            transport,
+            state: Mutex::new(RaftState {
+                log,
+                role: RaftRole::Follower,
+                leader: None,
+                commit_index: 0,
+                last_applied: 0,
+                election_deadline,
+                next_index: HashMap::new(),
+                match_index: HashMap::new(),
+                in_flight: HashSet::new(),
+            }),
+            apply_lock: Mutex::new(()),
+            applied: watch::channel(0).0,
+            running: AtomicBool::new(false),
+        }
+    }
+
+    /// Address identifying this node in the log
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// The metadata this node applies committed changes to
+    pub fn metadata(&self) -> Arc<ClusterMetadata> {
+        Arc::clone(&self.metadata)
+    }
+
+    /// Current role, term and log progress
+    pub async fn status(&self) -> RaftStatus {
+        let state = self.state.lock().await;
+        RaftStatus {
+            address: self.address.clone(),
+            role: state.role,
+            term: state.log.term(),
+            leader: state.leader.clone(),
+            commit_index: state.commit_index,
+            applied_index: state.last_applied,
+            snapshot_index: state.log.snapshot_index(),
+            last_index: state.log.last_index(),
+        }
+    }
+
+    /// Start taking part in elections and, as leader, replication
+    pub fn start(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        self.running.store(true, Ordering::SeqCst);
+        tokio::spawn(async move {
+            self.recover().await;
+            self.run_loop().await;
+        })
+    }
+
+    /// Stop the background task
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+
+    /// Change the metadata from any node.
+    ///
+    /// Followers forward the change to the leader. Returns the change's log
+    /// index once this node applied it, waiting for a leader to be elected
+    /// for up to the request timeout.
+    pub async fn write(self: &Arc<Self>, command: MetadataCommand) -> Result<u64> {
+        let deadline = Instant::now() + self.request_timeout;
+        loop {
+            let leader = self.state.lock().await.leader.clone();
+            let result = match leader {
+                Some(leader) if leader == self.address => self.propose(command.clone()).await,
+                Some(leader) => self.transport.propose(&leader, command.clone()).await,
+                None => Err(ClusterError::NotLeader("no leader elected".to_string())),
+            };
+            match result {
+                Ok(index) => {
+                    self.wait_applied(index).await?;
+                    return Ok(index);
+                }
+                Err(ClusterError::NotLeader(_) | ClusterError::Connection(_))
+                    if Instant::now() < deadline =>
+                {
+                    tokio::time::sleep(self.heartbeat_interval()).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Stop routing queries to a node, then hand the primaries it holds to
+    /// in-sync replicas on other nodes, each in a new primary term.
+    ///
+    /// Returns the number of primaries handed off. Shards without an in-sync
+    /// replica elsewhere keep their primary on the draining node.
+    pub async fn drain_node(self: &Arc<Self>, node_id: &str) -> Result<usize> {
+        self.write(MetadataCommand::SetDraining {
+            node_id: node_id.to_string(),
+            draining: true,
+        })
+        .await?;
+
+        let hand_offs = self.metadata().cluster_state().primary_hand_offs(node_id);
+        let count = hand_offs.len();
+        for shard in hand_offs {
+            info!(
+                "Handing off primary of {} from draining node {} to {}",
+                shard.shard_id, node_id, shard.primary_node
+            );
+            self.write(MetadataCommand::AssignShard(shard)).await?;
+        }
+        Ok(count)
+    }
+
+    /// Append a change to the log as leader and wait until it is applied
+    pub async fn propose(self: &Arc<Self>, command: MetadataCommand) -> Result<u64> {
+        let (index, term, committed) = {
+            let mut state = self.state.lock().await;
+            if state.role != RaftRole::Leader {
+                return Err(state.not_leader());
+            }
+            let index = state.log.last_index() + 1;
+            let term = state.log.term();
+            state.log.append(RpcRaftEntry {
+                index,
+                term,
+                command,
+            });
+            state.log.save().await?;
+            (index, term, self.advance_commit(&mut state))
+        };
+        if committed {
+            self.apply_committed().await;
+        }
+        self.replicate_all();
+
+        self.wait_applied(index).await?;
+        let state = self.state.lock().await;
+        match state.log.term_at(index) {
+            Some(entry_term) if entry_term != term => Err(ClusterError::NotLeader(
+                "leadership was lost before the change committed".to_string(),
+            )),
+            _ => Ok(index),
+        }
+    }
+
+    /// Handle a vote request from a candidate
+    pub async fn handle_request_vote(&self, request: RpcRequestVote) -> Result<RpcVoteResponse> {
+        let mut state = self.state.lock().await;
+        let mut changed = false;
+        if request.term > state.log.term() {
+            state.become_follower(request.term, None);
+            changed = true;
+        }
+
+        let up_to_date = (request.last_log_term, request.last_log_index)
+            >= (state.log.last_term(), state.log.last_index());
+        let free = match state.log.voted_for() {
+            Some(candidate) => candidate == request.candidate,
+            None => true,
+        };
+        let granted = request.term == state.log.term() && free && up_to_date;
+        if granted {
+            state
+                .log
+                .set_term(request.term, Some(request.candidate.clone()));
+            state.election_deadline = Instant::now() + election_timeout(&self.config);
+            changed = true;
+        }
+        if changed {
+            state.log.save().await?;
+        }
+        debug!(
+            "Vote for {} in term {}: {}",
+            request.candidate, request.term, granted
+        );
+        Ok(RpcVoteResponse {
+            term: state.log.term(),
+            vote_granted: granted,
+        })
+    }
+
+    /// Handle entries or a heartbeat from the leader
+    pub async fn handle_append_entries(
+        &self,
+        request: RpcAppendEntries,
+    ) -> Result<RpcAppendEntriesResponse> {
+        let (response, committed) = {
+            let mut state = self.state.lock().await;
+            let term = state.log.term();
+            if request.term < term {
+                return Ok(RpcAppendEntriesResponse {
+                    term,
+                    success: false,
+                    match_index: state.log.last_index(),
+                });
+            }
+            let mut changed = request.term > term;
+            state.become_follower(request.term, Some(request.leader.clone()));
+            state.election_deadline = Instant::now() + election_timeout(&self.config);
+
+            // Entries up to the snapshot are committed, so they match
+            let snapshot_index = state.log.snapshot_index();
+            let matches = request.prev_log_index <= state.log.last_index()
+                && (request.prev_log_index <= snapshot_index
+                    || state.log.term_at(request.prev_log_index) == Some(request.prev_log_term));
+
+            let mut committed = false;
+            let response = if matches {
+                let matched = request.prev_log_index + request.entries.len() as u64;
+                for entry in request.entries {
+                    if entry.index <= snapshot_index {
+                        continue;
+                    }
+                    match state.log.term_at(entry.index) {
+                        Some(existing) if existing == entry.term => continue,
+                        Some(_) => state.log.truncate_from(entry.index),
+                        None => {}
+                    }
+                    state.log.append(entry);
+                    changed = true;
+                }
+                let commit = request.leader_commit.min(matched);
+                if commit > state.commit_index {
+                    state.commit_index = commit;
+                    committed = true;
+                }
+                RpcAppendEntriesResponse {
+                    term: request.term,
+                    success: true,
+                    match_index: matched,
+                }
+            } else {
+                RpcAppendEntriesResponse {
+                    term: request.term,
+                    success: false,
+                    match_index: state
+                        .log
+                        .last_index()
+                        .min(request.prev_log_index.saturating_sub(1)),
+                }
+            };
+            if changed {
+                state.log.save().await?;
+            }
+            (response, committed)
+        };
+        if committed {
+            self.apply_committed().await;
+        }
+        Ok(response)
+    }
+
+    /// Handle a snapshot from the leader, returning this node's term
+    pub async fn handle_install_snapshot(&self, request: RpcInstallSnapshot) -> Result<u64> {
+        let _apply = self.apply_lock.lock().await;
+        let mut state = self.state.lock().await;
+        if request.term < state.log.term() {
+            return Ok(state.log.term());
+        }
+        state.become_follower(request.term, Some(request.leader.clone()));
+        state.election_deadline = Instant::now() + election_timeout(&self.config);
+
+        let last_index = request.last_included_index;
+        if last_index > state.last_applied {
+            info!(
+                "Installing metadata snapshot up to entry {} from {}",
+                last_index, request.leader
+            );
+            state.log.install(LogSnapshot {
+                last_index,
+                last_term: request.last_included_term,
+                metadata: request.snapshot.clone(),
+            });
+            self.metadata.restore(request.snapshot).await;
+            state.commit_index = state.commit_index.max(last_index);
+            state.last_applied = last_index;
+            self.applied.send_replace(last_index);
+        }
+        state.log.save().await?;
+        Ok(state.log.term())
+    }
+
+    fn heartbeat_interval(&self) -> Duration {
+        Duration::from_millis(self.config.heartbeat_interval_ms)
+    }
+
+    /// Copies needed to commit an entry, this node's included
+    fn quorum(&self) -> usize {
+        let members = self.peers.len() + 1;
+        members / 2 + 1
+    }
+
+    /// Apply the snapshot of a reloaded log
+    async fn recover(&self) {
+        let _apply = self.apply_lock.lock().await;
+        let mut state = self.state.lock().await;
+        let Some(snapshot) = state.log.snapshot().cloned() else {
+            return;
+        };
+        if snapshot.last_index > state.last_applied {
+            self.metadata.restore(snapshot.metadata).await;
+            state.commit_index = state.commit_index.max(snapshot.last_index);
+            state.last_applied = snapshot.last_index;
+            self.applied.send_replace(snapshot.last_index);
+        }
+    }
+
+    async fn run_loop(self: &Arc<Self>) {
+        info!(
+            "Metadata log started at {} with {} peers",
+            self.address,
+            self.peers.len()
+        );
+        while self.running.load(Ordering::SeqCst) {
+            tokio::time::sleep(self.heartbeat_interval()).await;
+            if !self.running.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let (role, expired) = {
+                let state = self.state.lock().await;
+                (state.role, Instant::now() >= state.election_deadline)
+            };
+            match role {
+                RaftRole::Leader => self.replicate_all(),
+                _ if expired => self.start_election().await,
+                _ => {}
+            }
+        }
+        info!("Metadata log stopped at {}", self.address);
+    }
+
+    async fn start_election(self: &Arc<Self>) {
+        let request = {
+            let mut state = self.state.lock().await;
+            let term = state.log.term() + 1;
+            state.log.set_term(term, Some(self.address.clone()));
+            if let Err(e) = state.log.save().await {
+                warn!("Failed to persist metadata log: {}", e);
+                return;
+            }
+            state.role = RaftRole::Candidate;
+            state.leader = None;
+            state.election_deadline = Instant::now() + election_timeout(&self.config);
+            RpcRequestVote {
+                term,
+                candidate: self.address.clone(),
+                last_log_index: state.log.last_index(),
+                last_log_term: state.log.last_term(),
+            }
+        };
+        debug!("Standing for metadata leader in term {}", request.term);
+
+        let timeout = Duration::from_millis(self.config.election_timeout_ms);
+        let responses = futures::future::join_all(self.peers.iter().map(|peer| {
+            let request = request.clone();
+            async move {
+                tokio::time::timeout(timeout, self.transport.request_vote(peer, request)).await
+            }
+        }))
+        .await;
+
+        let committed = {
+            let mut state = self.state.lock().await;
+            if state.role != RaftRole::Candidate || state.log.term() != request.term {
+                return;
+            }
+            let mut votes = 1;
+            for response in responses.into_iter().flatten().flatten() {
+                if response.term > request.term {
+                    state.become_follower(response.term, None);
+                    if let Err(e) = state.log.save().await {
+                        warn!("Failed to persist metadata log: {}", e);
+                    }
+                    return;
+                }
+                if response.vote_granted {
+                    votes += 1;
+                }
+            }
+            if votes < self.quorum() {
+                return;
+            }
+
+            info!(
+                "{} elected metadata leader for term {} with {} votes",
+                self.address, request.term, votes
+            );
+            state.role = RaftRole::Leader;
+            state.leader = Some(self.address.clone());
+            let next = state.log.last_index() + 1;
+            state.next_index = self.peers.iter().map(|p| (p.clone(), next)).collect();
+            state.match_index = self.peers.iter().map(|p| (p.clone(), 0)).collect();
+            // Entries of earlier terms commit along with one of this term
+            state.log.append(RpcRaftEntry {
+                index: next,
+                term: request.term,
+                command: MetadataCommand::Noop,
+            });
+            if let Err(e) = state.log.save().await {
+                warn!("Failed to persist metadata log: {}", e);
+            }
+            self.advance_commit(&mut state)
+        };
+        if committed {
+            self.apply_committed().await;
+        }
+        self.replicate_all();
+    }
+
+    /// Send every peer what it is missing, or a heartbeat
+    fn replicate_all(self: &Arc<Self>) {
+        for peer in &self.peers {
+            tokio::spawn(Arc::clone(self).replicate_to(peer.clone()));
+        }
+    }
+
+    async fn replicate_to(self: Arc<Self>, peer: String) {
+        loop {
+            let (term, outgoing) = {
+                let mut state = self.state.lock().await;
+                if state.role != RaftRole::Leader || !state.in_flight.insert(peer.clone()) {
+                    return;
+                }
+                let term = state.log.term();
+                let next = state.next_index.get(&peer).copied().unwrap_or(1);
+                let outgoing = match state.log.snapshot() {
+                    Some(snapshot) if next <= snapshot.last_index => {
+                        Outgoing::Snapshot(RpcInstallSnapshot {
+                            term,
+                            leader: self.address.clone(),
+                            last_included_index: snapshot.last_index,
+                            last_included_term: snapshot.last_term,
+                            snapshot: snapshot.metadata.clone(),
+                        })
+                    }
+                    _ => Outgoing::Append(RpcAppendEntries {
+                        term,
+                        leader: self.address.clone(),
+                        prev_log_index: next - 1,
+                        prev_log_term: state.log.term_at(next - 1).unwrap_or(0),
+                        entries: state.log.entries_from(next, self.config.max_append_entries),
+                        leader_commit: state.commit_index,
+                    }),
+                };
+                (term, outgoing)
+            };
+
+            let result = self.send(&peer, outgoing).await;
+
+            let (committed, more) = {
+                let mut state = self.state.lock().await;
+                state.in_flight.remove(&peer);
+                if state.role != RaftRole::Leader || state.log.term() != term {
+                    return;
+                }
+                let reply = match result {
+                    Ok(reply) => reply,
+                    Err(e) => {
+                        debug!("Metadata replication to {} failed: {}", peer, e);
+                        return;
+                    }
+                };
+                if reply.term > term {
+                    info!(
+                        "{} stepping down as metadata leader: {} is in term {}",
+                        self.address, peer, reply.term
+                    );
+                    state.become_follower(reply.term, None);
+                    if let Err(e) = state.log.save().await {
+                        warn!("Failed to persist metadata log: {}", e);
+                    }
+                    return;
+                }
+                let next = match reply.matched {
+                    Some(matched) => {
+                        let matched = state
+                            .match_index
+                            .get(&peer)
+                            .copied()
+                            .unwrap_or(0)
+                            .max(matched);
+                        state.match_index.insert(peer.clone(), matched);
+                        matched + 1
+                    }
+                    None => reply.retry_from.max(1),
+                };
+                state.next_index.insert(peer.clone(), next);
+                (
+                    self.advance_commit(&mut state),
+                    next <= state.log.last_index(),
+                )
+            };
+            if committed {
+                self.apply_committed().await;
+                // Let followers apply the change without waiting for the
+                // next heartbeat
+                self.replicate_all();
+            }
+            if !more {
+                return;
+            }
+        }
+    }
+
+    async fn send(&self, peer: &str, outgoing: Outgoing) -> Result<PeerReply> {
+        let timeout = |what: &str| ClusterError::Timeout(format!("{} to {}", what, peer));
+        match outgoing {
+            Outgoing::Append(request) => {
+                let prev_log_index = request.prev_log_index;
+                let sent = request.entries.len() as u64;
+                let response = tokio::time::timeout(
+                    Duration::from_millis(self.config.election_timeout_ms),
+                    self.transport.append_entries(peer, request),
+                )
+                .await
+                .map_err(|_| timeout("Append entries"))??;
+                Ok(PeerReply {
+                    term: response.term,
+                    matched: response.success.then_some(prev_log_index + sent),
+                    retry_from: prev_log_index.min(response.match_index + 1),
+                })
+            }
+            Outgoing::Snapshot(request) => {
+                let last_included_index = request.last_included_index;
+                let term = tokio::time::timeout(
+                    self.request_timeout,
+                    self.transport.install_snapshot(peer, request),
+                )
+                .await
+                .map_err(|_| timeout("Install snapshot"))??;
+                Ok(PeerReply {
+                    term,
+                    matched: Some(last_included_index),
+                    retry_from: last_included_index + 1,
+                })
+            }
+        }
+    }
+
+    /// Commit the entries of this term stored on a majority, returning
+    /// whether the commit index moved
+    fn advance_commit(&self, state: &mut RaftState) -> bool {
+        if state.role != RaftRole::Leader {
+            return false;
+        }
+        let term = state.log.term();
+        let mut committed = false;
+        for index in state.commit_index + 1..=state.log.last_index() {
+            let copies = 1 + state.match_index.values().filter(|m| **m >= index).count();
+            if copies < self.quorum() {
+                break;
+            }
+            if state.log.term_at(index) == Some(term) {
+                state.commit_index = index;
+                committed = true;
+            }
+        }
+        committed
+    }
+
+    /// Apply committed entries to the metadata in log order
+    async fn apply_committed(&self) {
+        let _apply = self.apply_lock.lock().await;
+        let entries = {
+            let state = self.state.lock().await;
+            (state.last_applied + 1..=state.commit_index)
+                .filter_map(|index| state.log.entry(index).cloned())
+                .collect::<Vec<_>>()
+        };
+        for entry in entries {
+            self.metadata.apply(&entry.command).await;
+            self.state.lock().await.last_applied = entry.index;
+            self.applied.send_replace(entry.index);
+        }
+        self.compact().await;
+    }
+
+    /// Replace applied entries with a snapshot once the log grows too long
+    async fn compact(&self) {
+        let last_applied = {
+            let state = self.state.lock().await;
+            if state.log.len() <= self.config.snapshot_threshold {
+                return;
+            }
+            state.last_applied
+        };
+        let snapshot = self.metadata.snapshot().await;
+        let mut state = self.state.lock().await;
+        state.log.compact(last_applied, snapshot);
+        debug!("Compacted metadata log up to entry {}", last_applied);
+        if let Err(e) = state.log.save().await {
+            warn!("Failed to persist metadata log: {}", e);
+        }
+    }
+
+    async fn wait_applied(&self, index: u64) -> Result<()> {
+        let mut applied = self.applied.subscribe();
+        tokio::time::timeout(self.request_timeout, applied.wait_for(|a| *a >= index))
+            .await
+            .map_err(|_| {
+                ClusterError::Timeout(format!("Metadata change {} was not applied in time", index))
+            })?
+            .map_err(|_| ClusterError::Internal("Metadata log stopped".to_string()))?;
+        Ok(())
+    }
+}
+
+/// Random election timeout between one and two base timeouts, so nodes
+/// rarely stand for election at the same time
+fn election_timeout(config: &MetadataConfig) -> Duration {
+    let base = config.election_timeout_ms.max(1);
+    let jitter = (uuid::Uuid::new_v4().as_u128() % base as u128) as u64;
+    Duration::from_millis(base + jitter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NodeTopology;
+    use crate::placement::{ClusterState, NodeInfo, ShardAssignment};
+    use crate::schema::SchemaRegistry;
+    use parking_lot::{Mutex as SyncMutex, RwLock};
+    use tempfile::TempDir;
+
+    const NODES: [&str; 3] = ["node-1:9080", "node-2:9080", "node-3:9080"];
+
+    /// Nodes reached in-process by address
+    #[derive(Default)]
+    struct Network {
+        nodes: RwLock<HashMap<String, Arc<RaftNode>>>,
+        /// Nodes cut off from every other node
+        down: SyncMutex<HashSet<String>>,
+    }
+
+    impl Network {
+        fn route(&self, from: &str, to: &str) -> Result<Arc<RaftNode>> {
+            let down = self.down.lock();
+            if down.contains(from) || down.contains(to) {
+                return Err(ClusterError::Connection(format!("{} is unreachable", to)));
+            }
+            self.nodes
+                .read()
+                .get(to)
+                .cloned()
+                .ok_or_else(|| ClusterError::NodeUnavailable(to.to_string()))
+        }
+    }
+
+    struct LoopbackTransport {
+        from: String,
+        network: Arc<Network>,
+    }
+
+    #[async_trait]
+    impl MetadataTransport for LoopbackTransport {
+        async fn request_vote(
+            &self,
+            addr: &str,
+            request: RpcRequestVote,
+        ) -> Result<RpcVoteResponse> {
+            self.network
+                .route(&self.from, addr)?
+                .handle_request_vote(request)
+                .await
+        }
+
+        async fn append_entries(
+            &self,
+            addr: &str,
+            request: RpcAppendEntries,
+        ) -> Result<RpcAppendEntriesResponse> {
+            self.network
+                .route(&self.from, addr)?
+                .handle_append_entries(request)
+                .await
+        }
+
+        async fn install_snapshot(&self, addr: &str, request: RpcInstallSnapshot) -> Result<u64> {
+            self.network
+                .route(&self.from, addr)?
+                .handle_install_snapshot(request)
+                .await
+        }
+
+        async fn propose(&self, addr: &str, command: MetadataCommand) -> Result<u64> {
+            let node = self.network.route(&self.from, addr)?;
+            node.propose(command).await
+        }
+    }
+
+    fn config(address: &str, seeds: &[&str], snapshot_threshold: usize) -> ClusterConfig {
+        ClusterConfig {
+            node_id: address.split(':').next().unwrap().to_string(),
+            bind_addr: address.to_string(),
+            seed_nodes: seeds.iter().map(|s| s.to_string()).collect(),
+            request_timeout_ms: 2000,
+            metadata: MetadataConfig {
+                election_timeout_ms: 100,
+                heartbeat_interval_ms: 20,
+                snapshot_threshold,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn start_node(network: &Arc<Network>, config: &ClusterConfig, log: RaftLog) -> Arc<RaftNode> {
+        let address = config.advertise_address().to_string();
+        let node = Arc::new(RaftNode::new(
+            config,
+            Arc::new(ClusterMetadata::new(
+                Arc::new(ClusterState::new()),
+                Arc::new(SchemaRegistry::new(config.node_id.clone())),
+            )),
+            Arc::new(LoopbackTransport {
+                from: address.clone(),
+                network: Arc::clone(network),
+            }),
+            log,
+        ));
+        network.nodes.write().insert(address, Arc::clone(&node));
+        Arc::clone(&node).start();
+        node
+    }
+
+    fn start_cluster(snapshot_threshold: usize) -> (Arc<Network>, Vec<Arc<RaftNode>>) {
+        let network = Arc::new(Network::default());
+        let nodes = NODES
+            .iter()
+            .map(|address| {
+                start_node(
+                    &network,
+                    &config(address, &NODES, snapshot_threshold),
+                    RaftLog::in_memory(),
+                )
+            })
+            .collect();
+        (network, nodes)
+    }
+
+    fn node_info(id: &str) -> NodeInfo {
+        NodeInfo {
+            node_id: id.to_string(),
+            address: format!("{}:9080", id),
+            topology: NodeTopology::default(),
+            healthy: true,
+            shard_count: 0,
+            disk_used_bytes: 0,
+            disk_total_bytes: 0,
+            index_size_bytes: 0,
+            draining: false,
+        }
+    }
+
+    fn register(id: &str) -> MetadataCommand {
+        MetadataCommand::RegisterNode(node_info(id))
+    }
+
+    /// Poll until `check` holds, failing after a few seconds
+    async fn eventually<F, Fut>(what: &str, mut check: F)
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = bool>,
+    {
+        for _ in 0..250 {
+            if check().await {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("timed out waiting for {}", what);
+    }
+
+    /// The only leader among `nodes`, once elected
+    async fn wait_for_leader(nodes: &[Arc<RaftNode>]) -> Arc<RaftNode> {
+        for _ in 0..250 {
+            let mut leaders = Vec::new();
+            for node in nodes {
+                if node.status().await.role == RaftRole::Leader {
+                    leaders.push(Arc::clone(node));
+                }
+            }
+            if leaders.len() == 1 {
+                return leaders.pop().unwrap();
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("timed out waiting for a single leader");
+    }
+
+    #[tokio::test]
+    async fn test_elects_leader_and_replicates_writes() {
+        let (_network, nodes) = start_cluster(1000);
+        let leader = wait_for_leader(&nodes).await;
+        let follower = nodes
+            .iter()
+            .find(|n| n.address() != leader.address())
+            .unwrap();
+
+        // A follower forwards the write and has applied it once it returns
+        follower.write(register("node-1")).await.unwrap();
+        assert!(follower
+            .metadata()
+            .cluster_state()
+            .get_node("node-1")
+            .is_some());
+
+        for node in &nodes {
+            eventually("the write to reach every node", || async {
+                node.metadata().cluster_state().get_node("node-1").is_some()
+            })
+            .await;
+            let status = node.status().await;
+            assert_eq!(status.leader.as_deref(), Some(leader.address()));
+        }
+
+        // Only the leader appends to the log
+        let err = follower.propose(register("node-2")).await.unwrap_err();
+        assert_eq!(err.error_type(), "not_leader");
+    }
+
+    #[tokio::test]
+    async fn test_drain_hands_off_primaries() {
+        let (_network, nodes) = start_cluster(1000);
+        let leader = wait_for_leader(&nodes).await;
+        for id in ["node-1", "node-2"] {
+            leader.write(register(id)).await.unwrap();
+        }
+        let mut shard = ShardAssignment::new("products", 0, "node-1");
+        shard.replica_nodes = vec!["node-2".to_string()];
+        leader
+            .write(MetadataCommand::AssignShard(shard))
+            .await
+            .unwrap();
+        let state = leader.metadata().cluster_state();
+        let term = state.get_shard("products-shard-0").unwrap().epoch;
+        // In-sync replicas are tracked by each node, not the log
+        state.update_in_sync_replicas("products-shard-0", vec!["node-2".to_string()]);
+
+        assert_eq!(leader.drain_node("node-1").await.unwrap(), 1);
+        assert!(state.get_node("node-1").unwrap().draining);
+
+        for node in &nodes {
+            eventually("the hand-off to reach every node", || async {
+                node.metadata()
+                    .cluster_state()
+                    .get_shard("products-shard-0")
+                    .is_some_and(|s| s.primary_node == "node-2")
+            })
+            .await;
+            let moved = node
+                .metadata()
+                .cluster_state()
+                .get_shard("products-shard-0")
+                .unwrap();
+            assert_eq!(moved.replica_nodes, vec!["node-1"]);
+            assert!(moved.epoch > term);
+        }
+
+        // Nothing left to hand off
+        assert_eq!(leader.drain_node("node-1").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stale_leader_write_is_discarded() {
+        let (network, nodes) = start_cluster(1000);
+        let old_leader = wait_for_leader(&nodes).await;
+        old_leader.write(register("node-1")).await.unwrap();
+
+        // Cut the leader off; the others elect a new one
+        network.down.lock().insert(old_leader.address().to_string());
+        let others: Vec<_> = nodes
+            .iter()
+            .filter(|n| n.address() != old_leader.address())
+            .cloned()
+            .collect();
+        let new_leader = wait_for_leader(&others).await;
+
+        // The stale leader cannot commit without a majority
+        let stale = tokio::spawn({
+            let old_leader = Arc::clone(&old_leader);
+            async move { old_leader.propose(register("stale")).await }
+        });
+        new_leader.write(register("node-2")).await.unwrap();
+
+        // Once healed it follows the new leader and drops its stale entry
+        network.down.lock().clear();
+        assert!(stale.await.unwrap().is_err());
+        eventually("the old leader to catch up", || async {
+            old_leader
+                .metadata()
+                .cluster_state()
+                .get_node("node-2")
+                .is_some()
+        })
+        .await;
+        for node in &nodes {
+            eventually("every node to apply the new leader's write", || async {
+                node.metadata().cluster_state().get_node("node-2").is_some()
+            })
+            .await;
+            assert!(node.metadata().cluster_state().get_node("stale").is_none());
+            assert!(node.metadata().cluster_state().get_node("node-1").is_some());
+        }
+        assert_eq!(
+            old_leader.status().await.leader.as_deref(),
+            Some(new_leader.address())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lagging_follower_catches_up_from_snapshot() {
+        let (network, nodes) = start_cluster(5);
+        let leader = wait_for_leader(&nodes).await;
+        let lagging = nodes
+            .iter()
+            .find(|n| n.address() != leader.address())
+            .unwrap();
+
+        network.down.lock().insert(lagging.address().to_string());
+        for i in 0..20 {
+            leader
+                .write(register(&format!("node-{}", i)))
+                .await
+                .unwrap();
+        }
+        assert!(leader.status().await.snapshot_index > lagging.status().await.last_index);
+
+        network.down.lock().clear();
+        eventually("the lagging follower to install the snapshot", || async {
+            lagging.metadata().cluster_state().node_count() == 20
+        })
+        .await;
+        assert!(lagging.status().await.snapshot_index > 0);
+
+        // It keeps following the log after the snapshot
+        leader.write(register("node-20")).await.unwrap();
+        eventually("the next write to reach the follower", || async {
+            lagging
+                .metadata()
+                .cluster_state()
+                .get_node("node-20")
+                .is_some()
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_restart_recovers_from_persisted_log() {
+        let temp = TempDir::new().unwrap();
+        let network = Arc::new(Network::default());
+        let config = config(NODES[0], &[], 3);
+
+        let node = start_node(&network, &config, RaftLog::load(temp.path()).unwrap());
+        wait_for_leader(std::slice::from_ref(&node)).await;
+        for i in 0..5 {
+            node.write(register(&format!("node-{}", i))).await.unwrap();
+        }
+        let term = node.status().await.term;
+        node.stop();
+
+        let restarted = start_node(&network, &config, RaftLog::load(temp.path()).unwrap());
+        // The snapshot is applied on start, later entries once re-elected
+        wait_for_leader(std::slice::from_ref(&restarted)).await;
+        eventually("the restarted node to apply its log", || async {
+            restarted.metadata().cluster_state().node_count() == 5
+        })
+        .await;
+        let status = restarted.status().await;
+        assert!(status.term > term);
+        assert!(status.snapshot_index > 0);
+    }
+}
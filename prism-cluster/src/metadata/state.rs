@@ -0,0 +1,344 @@
+//! Metadata state machine applying committed log entries
+
+use super::{MetadataCommand, MetadataSnapshot};
+use crate::placement::ClusterState;
+use crate::schema::{SchemaRegistry, SchemaRegistrySnapshot};
+use parking_lot::RwLock;
+use prism::ilm::IlmPolicy;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::warn;
+
+/// This node's copy of the cluster metadata
+pub struct ClusterMetadata {
+    cluster_state: Arc<ClusterState>,
+    schemas: Arc<SchemaRegistry>,
+    ilm_policies: RwLock<BTreeMap<String, IlmPolicy>>,
+}
+
+impl ClusterMetadata {
+    pub fn new(cluster_state: Arc<ClusterState>, schemas: Arc<SchemaRegistry>) -> Self {
+        Self {
+            cluster_state,
+            schemas,
+            ilm_policies: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Nodes and shard assignments
+    pub fn cluster_state(&self) -> Arc<ClusterState> {
+        Arc::clone(&self.cluster_state)
+    }
+
+    /// Collection schemas
+    pub fn schemas(&self) -> Arc<SchemaRegistry> {
+        Arc::clone(&self.schemas)
+    }
+
+    /// Get an ILM policy by name
+    pub fn ilm_policy(&self, name: &str) -> Option<IlmPolicy> {
+        self.ilm_policies.read().get(name).cloned()
+    }
+
+    /// All ILM policies, by name
+    pub fn ilm_policies(&self) -> Vec<IlmPolicy> {
+        self.ilm_policies.read().values().cloned().collect()
+    }
+
+    /// Apply a committed change.
+    ///
+    /// Every node applies the same changes in the same order, so the result
+    /// must only depend on the command and the current metadata.
+    pub async fn apply(&self, command: &MetadataCommand) {
+        match command {
+            MetadataCommand::Noop => {}
+            MetadataCommand::RegisterNode(info) => {
+                let draining = self
+                    .cluster_state
+                    .get_node(&info.node_id)
+                    .is_some_and(|node| node.draining);
+                self.cluster_state.register_node(info.clone());
+                if draining {
+                    self.cluster_state.drain_node(&info.node_id);
+                }
+            }
+            MetadataCommand::RemoveNode { node_id } => {
+                self.cluster_state.remove_node(node_id);
+            }
+            MetadataCommand::SetDraining { node_id, draining } => {
+                if *draining {
+                    self.cluster_state.drain_node(node_id);
+                } else {
+                    self.cluster_state.undrain_node(node_id);
+                }
+            }
+            MetadataCommand::AssignShard(assignment) => {
+                let mut assignment = assignment.clone();
+                assignment.epoch = self.cluster_state.next_epoch();
+                self.cluster_state.assign_shard(assignment);
+            }
+            MetadataCommand::CreateShards {
+                collection,
+                assignments,
+            } => {
+                if !self
+                    .cluster_state
+                    .get_collection_shards(collection)
+                    .is_empty()
+                {
+                    return;
+                }
+                let epoch = self.cluster_state.next_epoch();
+                for assignment in assignments {
+                    let mut assignment = assignment.clone();
+                    assignment.epoch = epoch;
+                    self.cluster_state.assign_shard(assignment);
+                }
+            }
+            MetadataCommand::ReplaceShards {
+                collection,
+                assignments,
+            } => {
+                self.cluster_state
+                    .replace_collection_shards(collection, assignments.clone());
+            }
+            MetadataCommand::RemoveShard { shard_id } => {
+                if self.cluster_state.remove_shard(shard_id).is_some() {
+                    self.cluster_state.next_epoch();
+                }
+            }
+            MetadataCommand::UpdateShardState { shard_id, state } => {
+                self.cluster_state.update_shard_state(shard_id, *state);
+            }
+            MetadataCommand::PutSchema {
+                collection,
+                schema,
+                created_by,
+                created_at,
+            } => {
+                if let Err(e) = self
+                    .schemas
+                    .register_as(collection, schema.clone(), created_by, *created_at)
+                    .await
+                {
+                    warn!("Failed to register schema of {}: {}", collection, e);
+                }
+            }
+            MetadataCommand::RemoveSchema { collection } => {
+                self.schemas.remove(collection).await;
+            }
+            MetadataCommand::PutIlmPolicy(policy) => {
+                self.ilm_policies
+                    .write()
+                    .insert(policy.name.clone(), policy.clone());
+            }
+            MetadataCommand::DeleteIlmPolicy { name } => {
+                self.ilm_policies.write().remove(name);
+            }
+        }
+    }
+
+    /// Capture the current metadata
+    pub async fn snapshot(&self) -> MetadataSnapshot {
+        MetadataSnapshot {
+            cluster: self.cluster_state.snapshot(),
+            schemas: self.schemas.snapshot().await.schemas,
+            ilm_policies: self.ilm_policies.read().clone(),
+        }
+    }
+
+    /// Replace the metadata with a snapshot
+    pub async fn restore(&self, snapshot: MetadataSnapshot) {
+        self.cluster_state.restore(snapshot.cluster);
+        self.schemas
+            .replace(SchemaRegistrySnapshot {
+                schemas: snapshot.schemas,
+                node_id: String::new(),
+            })
+            .await;
+        *self.ilm_policies.write() = snapshot.ilm_policies;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NodeTopology;
+    use crate::placement::{NodeInfo, ShardAssignment, ShardState};
+    use crate::schema::SchemaVersion;
+    use serde_json::json;
+
+    fn metadata() -> ClusterMetadata {
+        ClusterMetadata::new(
+            Arc::new(ClusterState::new()),
+            Arc::new(SchemaRegistry::new("node-1")),
+        )
+    }
+
+    fn node(id: &str) -> NodeInfo {
+        NodeInfo {
+            node_id: id.to_string(),
+            address: format!("{}:9080", id),
+            topology: NodeTopology::default(),
+            healthy: true,
+            shard_count: 0,
+            disk_used_bytes: 0,
+            disk_total_bytes: 0,
+            index_size_bytes: 0,
+            draining: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_shards_only_once() {
+        let metadata = metadata();
+        let create = |primary: &str| MetadataCommand::CreateShards {
+            collection: "products".to_string(),
+            assignments: vec![ShardAssignment::new("products", 0, primary)],
+        };
+
+        metadata.apply(&create("node-1")).await;
+        metadata.apply(&create("node-2")).await;
+
+        let shards = metadata.cluster_state().get_collection_shards("products");
+        assert_eq!(shards.len(), 1);
+        assert_eq!(shards[0].primary_node, "node-1");
+        assert_eq!(shards[0].epoch, 1);
+    }
+
+    #[tokio::test]
+    async fn test_replace_shards() {
+        let metadata = metadata();
+        metadata
+            .apply(&MetadataCommand::CreateShards {
+                collection: "products".to_string(),
+                assignments: vec![
+                    ShardAssignment::new("products", 0, "node-1"),
+                    ShardAssignment::new("products", 1, "node-2"),
+                ],
+            })
+            .await;
+        metadata
+            .apply(&MetadataCommand::AssignShard(ShardAssignment::new(
+                "orders", 0, "node-1",
+            )))
+            .await;
+
+        metadata
+            .apply(&MetadataCommand::ReplaceShards {
+                collection: "products".to_string(),
+                assignments: vec![ShardAssignment::new("products", 0, "node-1")],
+            })
+            .await;
+
+        let shards = metadata.cluster_state().get_collection_shards("products");
+        assert_eq!(shards.len(), 1);
+        assert_eq!(shards[0].epoch, 3);
+        assert_eq!(
+            metadata
+                .cluster_state()
+                .get_collection_shards("orders")
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_assignments_and_membership() {
+        let metadata = metadata();
+        metadata
+            .apply(&MetadataCommand::RegisterNode(node("node-1")))
+            .await;
+        metadata
+            .apply(&MetadataCommand::SetDraining {
+                node_id: "node-1".to_string(),
+                draining: true,
+            })
+            .await;
+        // Registering again keeps the node draining
+        metadata
+            .apply(&MetadataCommand::RegisterNode(node("node-1")))
+            .await;
+        assert!(
+            metadata
+                .cluster_state()
+                .get_node("node-1")
+                .unwrap()
+                .draining
+        );
+
+        metadata
+            .apply(&MetadataCommand::AssignShard(ShardAssignment::new(
+                "products", 0, "node-1",
+            )))
+            .await;
+        metadata
+            .apply(&MetadataCommand::UpdateShardState {
+                shard_id: "products-shard-0".to_string(),
+                state: ShardState::Active,
+            })
+            .await;
+        let shard = metadata
+            .cluster_state()
+            .get_shard("products-shard-0")
+            .unwrap();
+        assert_eq!(shard.state, ShardState::Active);
+        assert_eq!(shard.epoch, 1);
+
+        metadata
+            .apply(&MetadataCommand::RemoveShard {
+                shard_id: "products-shard-0".to_string(),
+            })
+            .await;
+        metadata
+            .apply(&MetadataCommand::RemoveNode {
+                node_id: "node-1".to_string(),
+            })
+            .await;
+        assert!(metadata.cluster_state().get_all_shards().is_empty());
+        assert_eq!(metadata.cluster_state().node_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_restore() {
+        let metadata = metadata();
+        metadata
+            .apply(&MetadataCommand::RegisterNode(node("node-1")))
+            .await;
+        metadata
+            .apply(&MetadataCommand::PutSchema {
+                collection: "products".to_string(),
+                schema: json!({"fields": ["title"]}),
+                created_by: "node-1".to_string(),
+                created_at: 1000,
+            })
+            .await;
+        metadata
+            .apply(&MetadataCommand::PutIlmPolicy(IlmPolicy::new("logs")))
+            .await;
+
+        let other = ClusterMetadata::new(
+            Arc::new(ClusterState::new()),
+            Arc::new(SchemaRegistry::new("node-2")),
+        );
+        other
+            .apply(&MetadataCommand::PutIlmPolicy(IlmPolicy::new("metrics")))
+            .await;
+        other.restore(metadata.snapshot().await).await;
+
+        assert!(other.cluster_state().get_node("node-1").is_some());
+        assert_eq!(
+            other.schemas().get_version("products").await,
+            Some(SchemaVersion::new(1))
+        );
+        assert!(other.ilm_policy("logs").is_some());
+        assert!(other.ilm_policy("metrics").is_none());
+
+        other
+            .apply(&MetadataCommand::DeleteIlmPolicy {
+                name: "logs".to_string(),
+            })
+            .await;
+        assert!(other.ilm_policies().is_empty());
+    }
+}
@@ -185,6 +185,7 @@ pub fn update_shard_status(shard_id: &str, state: &ShardState, role: &ReplicaRol
         ShardState::Syncing => 4.0,
         ShardState::Deleting => 5.0,
         ShardState::Error => 6.0,
+        ShardState::Resharding => 7.0,
     };
 
     metrics::gauge!(
@@ -664,6 +665,39 @@ pub fn update_schema_registry_metrics(collection_count: usize, total_versions: u
     metrics::gauge!("prism_schema_registry_total_versions").set(total_versions as f64);
 }
 
+// ========================================
+// Cross-Cluster Replication Metrics
+// ========================================
+
+/// Update how far a follower collection is behind a leader shard
+pub fn update_follower_lag(collection: &str, shard_id: &str, lag_operations: u64) {
+    metrics::gauge!(
+        "prism_ccr_follower_lag_operations",
+        "collection" => collection.to_string(),
+        "shard" => shard_id.to_string(),
+    )
+    .set(lag_operations as f64);
+}
+
+/// Record writes a follower collection applied from its leader
+pub fn record_follower_operations(collection: &str, operations: usize) {
+    metrics::counter!(
+        "prism_ccr_follower_operations_total",
+        "collection" => collection.to_string(),
+    )
+    .increment(operations as u64);
+}
+
+/// Record a failed pull of a follower collection
+pub fn record_follower_error(collection: &str, error_type: &str) {
+    metrics::counter!(
+        "prism_ccr_follower_errors_total",
+        "collection" => collection.to_string(),
+        "error_type" => error_type.to_string(),
+    )
+    .increment(1);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -130,12 +130,17 @@ pub enum ShardState {
     Deleting,
     /// Shard is in an error state
     Error,
+    /// Shard is read-only while its collection is split or shrunk
+    Resharding,
 }
 
 impl ShardState {
     /// Check if the shard can serve read requests
     pub fn can_serve_reads(&self) -> bool {
-        matches!(self, ShardState::Active | ShardState::Relocating)
+        matches!(
+            self,
+            ShardState::Active | ShardState::Relocating | ShardState::Resharding
+        )
     }
 
     /// Check if the shard can accept write requests
@@ -185,6 +190,10 @@ pub struct ShardAssignment {
 
     /// Epoch/version for conflict resolution
     pub epoch: u64,
+
+    /// Replicas that have applied every acknowledged write
+    #[serde(default)]
+    pub in_sync_replicas: Vec<String>,
 }
 
 impl ShardAssignment {
@@ -200,6 +209,7 @@ impl ShardAssignment {
             size_bytes: 0,
             document_count: 0,
             epoch: 1,
+            in_sync_replicas: Vec::new(),
         }
     }
 
@@ -31,6 +31,10 @@ pub struct NodeState {
     #[serde(default)]
     pub min_supported_version: u32,
 
+    /// Optional protocol features this node supports
+    #[serde(default)]
+    pub features: Vec<String>,
+
     /// Whether this node is draining (not accepting new queries)
     #[serde(default)]
     pub draining: bool,
@@ -46,6 +50,7 @@ impl NodeState {
             version: String::new(),
             protocol_version: 0,
             min_supported_version: 0,
+            features: Vec::new(),
             draining: false,
         }
     }
@@ -215,6 +220,20 @@ impl ClusterState {
         }
     }
 
+    /// Update the optional protocol features a node supports
+    pub fn update_node_features(&self, node_id: &str, features: &[String]) {
+        if let Some(node) = self.nodes.write().get_mut(node_id) {
+            node.features = features.to_vec();
+        }
+    }
+
+    /// Update the release a node runs
+    pub fn update_node_release(&self, node_id: &str, version: &str) {
+        if let Some(node) = self.nodes.write().get_mut(node_id) {
+            node.version = version.to_string();
+        }
+    }
+
     /// Get a specific node
     pub fn get_node(&self, node_id: &str) -> Option<NodeState> {
         self.nodes.read().get(node_id).cloned()
@@ -250,6 +269,18 @@ impl ClusterState {
         self.assignments.write().remove(shard_id)
     }
 
+    /// Atomically replace every shard of a collection, stamping the new
+    /// assignments with one epoch
+    pub fn replace_collection_shards(&self, collection: &str, assignments: Vec<ShardAssignment>) {
+        let epoch = self.next_epoch();
+        let mut current = self.assignments.write();
+        current.retain(|_, a| a.collection != collection);
+        for mut assignment in assignments {
+            assignment.epoch = epoch;
+            current.insert(assignment.shard_id.clone(), assignment);
+        }
+    }
+
     /// Get a shard assignment
     pub fn get_shard(&self, shard_id: &str) -> Option<ShardAssignment> {
         self.assignments.read().get(shard_id).cloned()
@@ -290,6 +321,54 @@ impl ClusterState {
         }
     }
 
+    /// Update the replicas in sync with a shard's primary
+    pub fn update_in_sync_replicas(&self, shard_id: &str, in_sync: Vec<String>) -> bool {
+        if let Some(shard) = self.assignments.write().get_mut(shard_id) {
+            shard.in_sync_replicas = in_sync;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Shards with their primary on a node, each moved to an in-sync
+    /// replica on a node that is not draining, with the old primary kept
+    /// as an in-sync replica.
+    ///
+    /// Shards without such a replica are left out; they keep their primary.
+    pub fn primary_hand_offs(&self, node_id: &str) -> Vec<ShardAssignment> {
+        let available: Vec<String> = self
+            .nodes
+            .read()
+            .values()
+            .filter(|node| !node.draining && node.info.node_id != node_id)
+            .map(|node| node.info.node_id.clone())
+            .collect();
+
+        let mut hand_offs: Vec<ShardAssignment> = self
+            .assignments
+            .read()
+            .values()
+            .filter(|shard| shard.primary_node == node_id)
+            .filter_map(|shard| {
+                let target = shard
+                    .in_sync_replicas
+                    .iter()
+                    .find(|r| shard.replica_nodes.contains(r) && available.contains(r))?
+                    .clone();
+                let mut shard = shard.clone();
+                shard.replica_nodes.retain(|r| *r != target);
+                shard.replica_nodes.push(node_id.to_string());
+                shard.in_sync_replicas.retain(|r| *r != target);
+                shard.in_sync_replicas.push(node_id.to_string());
+                shard.primary_node = target;
+                Some(shard)
+            })
+            .collect();
+        hand_offs.sort_by(|a, b| a.shard_id.cmp(&b.shard_id));
+        hand_offs
+    }
+
     /// Get shard count per node
     pub fn shard_counts_by_node(&self) -> HashMap<String, usize> {
         let mut counts: HashMap<String, usize> = HashMap::new();
@@ -530,6 +609,47 @@ mod tests {
         assert!(!state.drain_node("nonexistent"));
     }
 
+    #[test]
+    fn test_primary_hand_offs() {
+        let state = ClusterState::with_heartbeat_timeout(3600);
+        for (id, zone) in [
+            ("node-1", "zone-a"),
+            ("node-2", "zone-b"),
+            ("node-3", "zone-c"),
+        ] {
+            state.register_node(make_node_info(id, zone));
+        }
+
+        // In sync on node-3 only, node-2 is lagging
+        let mut synced = ShardAssignment::new("products", 0, "node-1");
+        synced.replica_nodes = vec!["node-2".to_string(), "node-3".to_string()];
+        synced.in_sync_replicas = vec!["node-3".to_string()];
+        state.assign_shard(synced);
+
+        // No in-sync replica to take over
+        let mut unsynced = ShardAssignment::new("products", 1, "node-1");
+        unsynced.replica_nodes = vec!["node-2".to_string()];
+        state.assign_shard(unsynced);
+
+        // Primary elsewhere
+        let mut other = ShardAssignment::new("products", 2, "node-2");
+        other.replica_nodes = vec!["node-1".to_string()];
+        other.in_sync_replicas = vec!["node-1".to_string()];
+        state.assign_shard(other);
+
+        let hand_offs = state.primary_hand_offs("node-1");
+        assert_eq!(hand_offs.len(), 1);
+        let moved = &hand_offs[0];
+        assert_eq!(moved.shard_id, "products-shard-0");
+        assert_eq!(moved.primary_node, "node-3");
+        assert_eq!(moved.replica_nodes, vec!["node-2", "node-1"]);
+        assert_eq!(moved.in_sync_replicas, vec!["node-1"]);
+
+        // Draining replicas do not take over
+        state.drain_node("node-3");
+        assert!(state.primary_hand_offs("node-1").is_empty());
+    }
+
     #[test]
     fn test_update_node_version() {
         let state = ClusterState::new();
@@ -544,5 +664,11 @@ mod tests {
         let node = state.get_node("node-1").unwrap();
         assert_eq!(node.protocol_version, 2);
         assert_eq!(node.min_supported_version, 1);
+
+        state.update_node_features("node-1", &["raft".to_string()]);
+        assert_eq!(state.get_node("node-1").unwrap().features, vec!["raft"]);
+
+        state.update_node_release("node-1", "0.2.0");
+        assert_eq!(state.get_node("node-1").unwrap().version, "0.2.0");
     }
 }
@@ -0,0 +1,231 @@
+//! Protocol version negotiation
+//!
+//! During a rolling upgrade, nodes of different releases talk to each other.
+//! When a client opens a connection to a node, both sides exchange the range
+//! of protocol versions they speak and the optional features they support.
+//! The connection then uses the highest version both speak and only the
+//! features both support, and is refused if the version ranges do not
+//! overlap. Every RPC added after protocol version 1 is behind a feature, so
+//! a node is never sent a message it cannot deserialize: calling one on a
+//! peer without its feature fails with `IncompatibleVersion` before anything
+//! is sent.
+//!
+//! Nodes released before the handshake do not understand it. They are
+//! treated as speaking protocol version 1 without any optional feature.
+
+use crate::config::ClusterConfig;
+use crate::error::{ClusterError, Result};
+use crate::types::RpcHandshake;
+use std::collections::BTreeSet;
+
+/// Protocol version spoken by this release
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Oldest protocol version this release can talk to
+pub const MIN_SUPPORTED_VERSION: u32 = 1;
+
+/// Protocol version of nodes released before the handshake
+pub const LEGACY_PROTOCOL_VERSION: u32 = 1;
+
+/// Optional features, negotiated per connection
+pub mod features {
+    /// `term_statistics` RPC, for the DFS phase of federated search
+    pub const TERM_STATISTICS: &str = "term_statistics";
+    /// `scan_shard` RPC, for resharding and follower bootstrap
+    pub const SHARD_SCAN: &str = "shard_scan";
+    /// `leader_shards` RPC, for cross-cluster replication
+    pub const LEADER_SHARDS: &str = "leader_shards";
+    /// `primary_write`, `replica_write`, `get_operations`, `shard_checkpoint`
+    /// and `get_versioned_documents` RPCs, for shard replication
+    pub const REPLICATION: &str = "replication";
+    /// `begin_shard_receive`, `write_shard_chunk` and `complete_shard_receive`
+    /// RPCs, for moving shards between nodes
+    pub const SHARD_TRANSFER: &str = "shard_transfer";
+    /// `raft_request_vote`, `raft_append_entries`, `raft_install_snapshot`
+    /// and `propose_metadata` RPCs, for the replicated metadata log
+    pub const RAFT: &str = "raft";
+
+    /// Every feature this release supports
+    pub const ALL: &[&str] = &[
+        TERM_STATISTICS,
+        SHARD_SCAN,
+        LEADER_SHARDS,
+        REPLICATION,
+        SHARD_TRANSFER,
+        RAFT,
+    ];
+}
+
+/// What this node offers when a connection is opened. A node configured to
+/// speak only the legacy protocol offers no features, like the nodes it
+/// stands in for.
+pub fn local_handshake(config: &ClusterConfig) -> RpcHandshake {
+    let features = if config.protocol_version > LEGACY_PROTOCOL_VERSION {
+        features::ALL.iter().map(|f| f.to_string()).collect()
+    } else {
+        Vec::new()
+    };
+    RpcHandshake {
+        node_id: config.node_id.clone(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        protocol_version: config.protocol_version,
+        min_supported_version: config.min_supported_version,
+        features,
+    }
+}
+
+/// What a node that does not understand the handshake speaks
+pub fn legacy_handshake(node_id: &str) -> RpcHandshake {
+    RpcHandshake {
+        node_id: node_id.to_string(),
+        version: String::new(),
+        protocol_version: LEGACY_PROTOCOL_VERSION,
+        min_supported_version: LEGACY_PROTOCOL_VERSION,
+        features: Vec::new(),
+    }
+}
+
+/// Protocol version and features agreed on for a connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegotiatedProtocol {
+    /// Highest protocol version both nodes speak
+    pub version: u32,
+    /// Features both nodes support
+    pub features: BTreeSet<String>,
+    /// Highest protocol version the peer speaks
+    pub peer_protocol_version: u32,
+    /// Lowest protocol version the peer speaks
+    pub peer_min_supported_version: u32,
+}
+
+impl NegotiatedProtocol {
+    /// Whether both nodes support a feature
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+
+    /// Fail unless both nodes support a feature
+    pub fn require(&self, feature: &str, peer: &str) -> Result<()> {
+        if self.supports(feature) {
+            Ok(())
+        } else {
+            Err(ClusterError::IncompatibleVersion(format!(
+                "{} does not support {} (protocol version {})",
+                peer, feature, self.version
+            )))
+        }
+    }
+}
+
+/// Agree on a protocol version and features with a peer.
+///
+/// Fails if the peer's range of protocol versions does not overlap ours.
+pub fn negotiate(local: &RpcHandshake, peer: &RpcHandshake) -> Result<NegotiatedProtocol> {
+    let version = local.protocol_version.min(peer.protocol_version);
+    if version < local.min_supported_version || version < peer.min_supported_version {
+        return Err(ClusterError::IncompatibleVersion(format!(
+            "{} speaks protocol versions {}-{}, {} speaks {}-{}",
+            local.node_id,
+            local.min_supported_version,
+            local.protocol_version,
+            peer.node_id,
+            peer.min_supported_version,
+            peer.protocol_version
+        )));
+    }
+
+    let features = local
+        .features
+        .iter()
+        .filter(|feature| peer.features.contains(feature))
+        .cloned()
+        .collect();
+
+    Ok(NegotiatedProtocol {
+        version,
+        features,
+        peer_protocol_version: peer.protocol_version,
+        peer_min_supported_version: peer.min_supported_version,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handshake(node_id: &str, min: u32, max: u32, features: &[&str]) -> RpcHandshake {
+        RpcHandshake {
+            node_id: node_id.to_string(),
+            version: "0.0.0".to_string(),
+            protocol_version: max,
+            min_supported_version: min,
+            features: features.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_negotiate_uses_highest_common_version() {
+        let local = handshake("node-1", 1, 3, &[]);
+        let peer = handshake("node-2", 2, 2, &[]);
+
+        assert_eq!(negotiate(&local, &peer).unwrap().version, 2);
+        assert_eq!(negotiate(&peer, &local).unwrap().version, 2);
+    }
+
+    #[test]
+    fn test_negotiate_keeps_common_features() {
+        let local = handshake(
+            "node-1",
+            1,
+            2,
+            &[features::SHARD_SCAN, features::LEADER_SHARDS],
+        );
+        let peer = handshake(
+            "node-2",
+            1,
+            2,
+            &[features::SHARD_SCAN, features::TERM_STATISTICS, "future"],
+        );
+
+        let negotiated = negotiate(&local, &peer).unwrap();
+        assert!(negotiated.supports(features::SHARD_SCAN));
+        assert!(!negotiated.supports(features::LEADER_SHARDS));
+        assert!(!negotiated.supports(features::TERM_STATISTICS));
+        assert!(!negotiated.supports("future"));
+        assert!(negotiated.require(features::SHARD_SCAN, "node-2").is_ok());
+        let err = negotiated
+            .require(features::LEADER_SHARDS, "node-2")
+            .unwrap_err();
+        assert_eq!(err.error_type(), "incompatible_version");
+    }
+
+    #[test]
+    fn test_negotiate_rejects_disjoint_versions() {
+        let old = handshake("node-1", 1, 1, features::ALL);
+        let new = handshake("node-2", 2, 3, features::ALL);
+
+        let err = negotiate(&old, &new).unwrap_err();
+        assert!(matches!(err, ClusterError::IncompatibleVersion(_)));
+        assert!(negotiate(&new, &old).is_err());
+    }
+
+    #[test]
+    fn test_legacy_peer_gets_no_features() {
+        let config = ClusterConfig::default();
+        let local = local_handshake(&config);
+        assert_eq!(local.protocol_version, PROTOCOL_VERSION);
+        assert_eq!(local.min_supported_version, MIN_SUPPORTED_VERSION);
+
+        let negotiated = negotiate(&local, &legacy_handshake("old-node")).unwrap();
+        assert_eq!(negotiated.version, LEGACY_PROTOCOL_VERSION);
+        assert!(negotiated.features.is_empty());
+        assert_eq!(negotiated.peer_protocol_version, LEGACY_PROTOCOL_VERSION);
+
+        let pinned = ClusterConfig {
+            protocol_version: LEGACY_PROTOCOL_VERSION,
+            min_supported_version: LEGACY_PROTOCOL_VERSION,
+            ..ClusterConfig::default()
+        };
+        assert!(local_handshake(&pinned).features.is_empty());
+    }
+}
@@ -5,6 +5,7 @@
 
 use super::{OperationStatus, RebalanceOperationStatus, RebalancePhase, RebalanceStatus};
 use crate::config::RebalancingConfig;
+use crate::discovery::ClusterEvent;
 use crate::placement::{
     find_rebalance_target, ClusterState, NodeInfo, PlacementStrategy, ShardState,
 };
@@ -160,11 +161,8 @@ impl RebalanceEngine {
             return false;
         }
 
-        // Check cooldown
-        if let Some(last) = *self.last_rebalance.read() {
-            if last.elapsed() < Duration::from_secs(self.config.cooldown_secs) {
-                return false;
-            }
+        if self.in_cooldown() {
+            return false;
         }
 
         // Check imbalance
@@ -172,6 +170,45 @@ impl RebalanceEngine {
             .is_imbalanced(self.config.imbalance_threshold_percent as f64)
     }
 
+    /// Whether the last rebalance finished less than the cooldown ago
+    fn in_cooldown(&self) -> bool {
+        self.last_rebalance
+            .read()
+            .is_some_and(|last| last.elapsed() < Duration::from_secs(self.config.cooldown_secs))
+    }
+
+    /// Start rebalancing after node discovery reported a membership change.
+    ///
+    /// A departed node's shards are moved right away; joins wait for the
+    /// cooldown. Returns the new status if rebalancing started.
+    pub fn handle_discovery_event(&self, event: &ClusterEvent) -> Option<RebalanceStatus> {
+        if !self.config.enabled {
+            return None;
+        }
+        let trigger = match event {
+            ClusterEvent::NodeJoined(node) if node.node_id.is_some() => {
+                if self.in_cooldown() {
+                    return None;
+                }
+                RebalanceTrigger::NodeJoined
+            }
+            ClusterEvent::NodeLeft(_) => RebalanceTrigger::NodeLeft,
+            _ => return None,
+        };
+        if self.status.read().in_progress {
+            debug!("Not rebalancing for {:?}: already in progress", trigger);
+            return None;
+        }
+        match self.trigger(trigger) {
+            Ok(status) if status.in_progress => Some(status),
+            Ok(_) => None,
+            Err(e) => {
+                debug!("Not rebalancing for {:?}: {}", trigger, e);
+                None
+            }
+        }
+    }
+
     /// Create a rebalancing plan
     pub fn create_plan(
         &self,
@@ -406,6 +443,7 @@ impl RebalanceEngine {
                 bytes_transferred: 0,
                 total_bytes: op.expected_bytes,
                 status: OperationStatus::Transferring,
+                transfer_id: None,
             });
 
             status.shards_in_transit += 1;
@@ -428,7 +466,8 @@ impl RebalanceEngine {
         let mut newly_completed = Vec::new();
 
         for op in &mut status.current_operations {
-            if op.status == OperationStatus::Transferring {
+            // Moves backed by a shard transfer report their own progress
+            if op.status == OperationStatus::Transferring && op.transfer_id.is_none() {
                 // Simulate progress
                 op.progress += 0.1;
                 op.bytes_transferred = (op.total_bytes as f64 * op.progress.min(1.0)) as u64;
@@ -452,6 +491,43 @@ impl RebalanceEngine {
         Ok(())
     }
 
+    /// Record the progress of a shard transfer.
+    ///
+    /// Transfers run independently of rebalance plans; their progress is
+    /// kept in the status so `get_rebalance_status` reports it. An entry is
+    /// replaced by the next update for the same shard.
+    pub fn record_transfer(&self, update: RebalanceOperationStatus) {
+        let mut status = self.status.write();
+        let previous = status
+            .current_operations
+            .iter()
+            .position(|op| op.shard_id == update.shard_id)
+            .map(|i| status.current_operations.remove(i));
+
+        let was_active = previous.as_ref().is_some_and(|op| !op.status.is_terminal());
+        let is_active = !update.status.is_terminal();
+        if is_active && !was_active {
+            status.shards_in_transit += 1;
+        } else if !is_active && was_active {
+            status.shards_in_transit = status.shards_in_transit.saturating_sub(1);
+        }
+        if !is_active && was_active {
+            match update.status {
+                OperationStatus::Completed => status.completed_moves += 1,
+                OperationStatus::Failed => status.failed_moves += 1,
+                _ => {}
+            }
+        }
+
+        status.current_operations.push(update);
+    }
+
+    /// Record a failed shard transfer with its error
+    pub fn record_transfer_error(&self, update: RebalanceOperationStatus, error: String) {
+        self.record_transfer(update);
+        self.status.write().last_error = Some(error);
+    }
+
     /// Check if all operations are complete
     fn all_operations_complete(&self) -> bool {
         let plan = self.current_plan.read();
@@ -740,6 +816,7 @@ mod tests {
             max_concurrent_moves: 2,
             max_bytes_per_sec: 1000,
             cooldown_secs: 0,
+            ..Default::default()
         };
         let strategy = PlacementStrategy {
             spread_across: SpreadLevel::None,
@@ -774,6 +851,42 @@ mod tests {
         assert!(engine.should_rebalance());
     }
 
+    #[test]
+    fn test_discovery_events_trigger_rebalance() {
+        let left = ClusterEvent::NodeLeft("127.0.0.1:9080".parse().unwrap());
+        let joined = ClusterEvent::NodeJoined(crate::discovery::DiscoveredNode {
+            node_id: Some("node-3".to_string()),
+            ..crate::discovery::DiscoveredNode::new("127.0.0.1:9080".parse().unwrap())
+        });
+
+        let (engine, _state) = make_imbalanced_engine();
+        let status = engine.handle_discovery_event(&left).unwrap();
+        assert!(status.in_progress);
+        assert_eq!(
+            engine.current_plan.read().as_ref().unwrap().trigger,
+            RebalanceTrigger::NodeLeft
+        );
+        // Only one rebalance runs at a time
+        assert!(engine.handle_discovery_event(&joined).is_none());
+
+        // Joins wait for the cooldown
+        let (engine, _state) = make_imbalanced_engine();
+        *engine.last_rebalance.write() = Some(Instant::now());
+        let engine = RebalanceEngine {
+            config: RebalancingConfig {
+                cooldown_secs: 9999,
+                ..engine.config.clone()
+            },
+            ..engine
+        };
+        assert!(engine.handle_discovery_event(&joined).is_none());
+        assert!(engine.handle_discovery_event(&left).is_some());
+
+        // Nothing happens with rebalancing disabled
+        let (engine, _state) = make_test_engine();
+        assert!(engine.handle_discovery_event(&left).is_none());
+    }
+
     #[test]
     fn test_should_rebalance_cooldown_active() {
         let state = Arc::new(ClusterState::new());
@@ -1088,4 +1201,37 @@ mod tests {
         // No plan => all_operations_complete returns true
         assert!(engine.all_operations_complete());
     }
+
+    #[test]
+    fn test_record_transfer_tracks_progress() {
+        let (engine, _state) = make_test_engine();
+        let op = |status, bytes| RebalanceOperationStatus {
+            shard_id: "products-shard-0".to_string(),
+            from_node: "node-1".to_string(),
+            to_node: "node-2".to_string(),
+            progress: bytes as f64 / 100.0,
+            bytes_transferred: bytes,
+            total_bytes: 100,
+            status,
+            transfer_id: Some("t-1".to_string()),
+        };
+
+        engine.record_transfer(op(OperationStatus::Transferring, 10));
+        engine.record_transfer(op(OperationStatus::Transferring, 60));
+        let status = engine.status();
+        assert_eq!(status.shards_in_transit, 1);
+        assert_eq!(status.current_operations.len(), 1);
+        assert_eq!(status.current_operations[0].bytes_transferred, 60);
+
+        engine.record_transfer(op(OperationStatus::Completed, 100));
+        let status = engine.status();
+        assert_eq!(status.shards_in_transit, 0);
+        assert_eq!(status.completed_moves, 1);
+
+        engine.record_transfer(op(OperationStatus::Transferring, 0));
+        engine.record_transfer_error(op(OperationStatus::Failed, 0), "boom".to_string());
+        let status = engine.status();
+        assert_eq!(status.failed_moves, 1);
+        assert_eq!(status.last_error.as_deref(), Some("boom"));
+    }
 }
@@ -105,6 +105,10 @@ pub struct RebalanceOperationStatus {
 
     /// Status of this operation
     pub status: OperationStatus,
+
+    /// ID of the shard transfer copying the data, if one is running
+    #[serde(default)]
+    pub transfer_id: Option<String>,
 }
 
 /// Status of a single shard move operation
@@ -125,6 +129,16 @@ pub enum OperationStatus {
     Cancelled,
 }
 
+impl OperationStatus {
+    /// Whether the operation has finished, successfully or not
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            OperationStatus::Completed | OperationStatus::Failed | OperationStatus::Cancelled
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
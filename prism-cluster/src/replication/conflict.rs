@@ -0,0 +1,340 @@
+//! Document versions and conflict resolution for partition healing
+
+use super::ShardCheckpoint;
+use crate::config::ConflictResolution;
+use crate::types::{RpcConflictWinner, RpcDocVersion, RpcDocument, RpcDocumentConflict};
+use crate::types::{RpcShardCheckpoint, RpcWriteOp};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Conflicts kept for the health report, oldest dropped first
+const CONFLICT_REPORT_CAPACITY: usize = 1000;
+
+/// Hybrid logical clock.
+///
+/// Timestamps are wall-clock milliseconds that never go backwards and always
+/// move past the timestamps observed from other nodes, so a write stamped
+/// after another one was seen orders after it even with skewed clocks.
+#[derive(Debug, Default)]
+pub struct HybridClock {
+    last: AtomicU64,
+}
+
+impl HybridClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Timestamp for a new write
+    pub fn now(&self) -> u64 {
+        let wall = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let previous = self
+            .last
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+                Some(wall.max(last + 1))
+            })
+            .expect("update always succeeds");
+        wall.max(previous + 1)
+    }
+
+    /// Move past a timestamp received from another node
+    pub fn observe(&self, timestamp: u64) {
+        self.last.fetch_max(timestamp, Ordering::SeqCst);
+    }
+}
+
+/// Last write a diverged shard copy shares with its primary.
+///
+/// Writes ordered after it were made on one side of a partition only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForkPoint {
+    pub primary_term: u64,
+    pub seq_no: u64,
+}
+
+impl ForkPoint {
+    /// Fork point of a local copy, from the primary's checkpoint and where
+    /// the primary saw the local copy's term end
+    pub fn between(local: &ShardCheckpoint, primary: &RpcShardCheckpoint) -> Self {
+        let shared = if local.primary_term == primary.primary_term {
+            primary.checkpoint
+        } else {
+            primary
+                .previous_terms
+                .get(&local.primary_term)
+                .copied()
+                .unwrap_or(0)
+        };
+        Self {
+            primary_term: local.primary_term,
+            seq_no: local.checkpoint.min(shared),
+        }
+    }
+
+    /// Whether a write with this version was made after the fork
+    pub fn precedes(&self, version: &RpcDocVersion) -> bool {
+        (version.primary_term, version.seq_no) > (self.primary_term, self.seq_no)
+    }
+}
+
+/// Outcome of resolving a conflicting write
+#[derive(Debug, Clone)]
+pub enum Resolved {
+    /// Keep the diverged copy's write
+    Local,
+    /// Keep the primary's write
+    Primary,
+    /// Keep a merge of both
+    Merged(RpcDocument),
+}
+
+impl Resolved {
+    pub fn winner(&self) -> RpcConflictWinner {
+        match self {
+            Resolved::Local => RpcConflictWinner::Local,
+            Resolved::Primary => RpcConflictWinner::Primary,
+            Resolved::Merged(_) => RpcConflictWinner::Merged,
+        }
+    }
+}
+
+/// One side of a conflicting write
+#[derive(Debug, Clone, Copy)]
+pub struct ConflictSide<'a> {
+    pub version: RpcDocVersion,
+    /// The document, `None` if the write deleted it
+    pub document: Option<&'a RpcDocument>,
+}
+
+/// Resolve a document written on both sides of a partition.
+///
+/// Last-write-wins keeps the write with the later timestamp, the primary's
+/// on a tie. Merge combines the fields of both documents, the later write's
+/// fields taking precedence, and falls back to last-write-wins when either
+/// side deleted the document. Manual keeps the primary's write; the report
+/// holds both for an operator to act on.
+pub fn resolve(
+    strategy: ConflictResolution,
+    local: ConflictSide<'_>,
+    primary: ConflictSide<'_>,
+) -> Resolved {
+    let local_is_later = local.version.timestamp > primary.version.timestamp;
+    let last_write = if local_is_later {
+        Resolved::Local
+    } else {
+        Resolved::Primary
+    };
+    match strategy {
+        ConflictResolution::LastWriteWins => last_write,
+        ConflictResolution::Manual => Resolved::Primary,
+        ConflictResolution::Merge => match (local.document, primary.document) {
+            (Some(local_doc), Some(primary_doc)) => {
+                let (older, newer) = if local_is_later {
+                    (primary_doc, local_doc)
+                } else {
+                    (local_doc, primary_doc)
+                };
+                let mut fields = older.fields.clone();
+                fields.extend(newer.fields.clone());
+                Resolved::Merged(RpcDocument {
+                    id: newer.id.clone(),
+                    fields,
+                })
+            }
+            _ => last_write,
+        },
+    }
+}
+
+/// Writes that make documents match the given values, `None` meaning
+/// deleted: at most one index and one delete
+pub fn writes_for(documents: Vec<(String, Option<RpcDocument>)>) -> Vec<RpcWriteOp> {
+    let mut docs = Vec::new();
+    let mut deletes = Vec::new();
+    for (id, document) in documents {
+        match document {
+            Some(doc) => docs.push(doc),
+            None => deletes.push(id),
+        }
+    }
+    let mut writes = Vec::new();
+    if !docs.is_empty() {
+        writes.push(RpcWriteOp::Index(docs));
+    }
+    if !deletes.is_empty() {
+        writes.push(RpcWriteOp::Delete(deletes));
+    }
+    writes
+}
+
+/// Recently resolved conflicts, reported through cluster health
+#[derive(Debug, Default)]
+pub struct ConflictReport {
+    conflicts: Mutex<VecDeque<RpcDocumentConflict>>,
+}
+
+impl ConflictReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a resolved conflict
+    pub fn record(&self, conflict: RpcDocumentConflict) {
+        let mut conflicts = self.conflicts.lock();
+        conflicts.push_back(conflict);
+        while conflicts.len() > CONFLICT_REPORT_CAPACITY {
+            conflicts.pop_front();
+        }
+    }
+
+    /// Conflicts reported, oldest first
+    pub fn list(&self) -> Vec<RpcDocumentConflict> {
+        self.conflicts.lock().iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.conflicts.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.lock().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn version(primary_term: u64, seq_no: u64, timestamp: u64) -> RpcDocVersion {
+        RpcDocVersion {
+            primary_term,
+            seq_no,
+            timestamp,
+            deleted: false,
+        }
+    }
+
+    fn doc(fields: serde_json::Value) -> RpcDocument {
+        RpcDocument {
+            id: "a".to_string(),
+            fields: serde_json::from_value(fields).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_hybrid_clock_is_monotonic() {
+        let clock = HybridClock::new();
+        let first = clock.now();
+        assert!(clock.now() > first);
+
+        // A timestamp from a node whose clock runs ahead moves this one past it
+        clock.observe(first + 60_000);
+        assert!(clock.now() > first + 60_000);
+    }
+
+    #[test]
+    fn test_fork_point() {
+        let local = ShardCheckpoint {
+            primary_term: 1,
+            checkpoint: 4,
+            previous_terms: BTreeMap::new(),
+        };
+        // The new primary saw term 1 end at 2
+        let primary = RpcShardCheckpoint {
+            primary_term: 2,
+            checkpoint: 5,
+            previous_terms: BTreeMap::from([(1, 2)]),
+        };
+        let fork = ForkPoint::between(&local, &primary);
+        assert_eq!(
+            fork,
+            ForkPoint {
+                primary_term: 1,
+                seq_no: 2
+            }
+        );
+        assert!(!fork.precedes(&version(1, 2, 0)));
+        assert!(fork.precedes(&version(1, 3, 0)));
+        assert!(fork.precedes(&version(2, 1, 0)));
+
+        // A copy that fell behind in the primary's own term did not diverge
+        let behind = ShardCheckpoint {
+            primary_term: 2,
+            checkpoint: 3,
+            previous_terms: BTreeMap::new(),
+        };
+        assert_eq!(ForkPoint::between(&behind, &primary).seq_no, 3);
+    }
+
+    #[test]
+    fn test_resolve_strategies() {
+        let local_doc = doc(serde_json::json!({"title": "local", "color": "red"}));
+        let primary_doc = doc(serde_json::json!({"title": "primary", "size": 2}));
+        let local = ConflictSide {
+            version: version(1, 3, 200),
+            document: Some(&local_doc),
+        };
+        let primary = ConflictSide {
+            version: version(2, 1, 100),
+            document: Some(&primary_doc),
+        };
+
+        assert!(matches!(
+            resolve(ConflictResolution::LastWriteWins, local, primary),
+            Resolved::Local
+        ));
+        assert!(matches!(
+            resolve(ConflictResolution::Manual, local, primary),
+            Resolved::Primary
+        ));
+        match resolve(ConflictResolution::Merge, local, primary) {
+            Resolved::Merged(merged) => {
+                assert_eq!(merged.fields["title"], "local");
+                assert_eq!(merged.fields["color"], "red");
+                assert_eq!(merged.fields["size"], 2);
+            }
+            other => panic!("expected a merge, got {:?}", other),
+        }
+
+        // A tie keeps the primary's write
+        let tied = ConflictSide {
+            version: version(1, 3, 100),
+            ..local
+        };
+        assert!(matches!(
+            resolve(ConflictResolution::LastWriteWins, tied, primary),
+            Resolved::Primary
+        ));
+
+        // A delete cannot be merged
+        let deleted = ConflictSide {
+            version: version(1, 3, 200),
+            document: None,
+        };
+        assert!(matches!(
+            resolve(ConflictResolution::Merge, deleted, primary),
+            Resolved::Local
+        ));
+    }
+
+    #[test]
+    fn test_writes_for_groups_indexes_and_deletes() {
+        let writes = writes_for(vec![
+            (
+                "a".to_string(),
+                Some(doc(serde_json::json!({"title": "one"}))),
+            ),
+            ("b".to_string(), None),
+            ("c".to_string(), None),
+        ]);
+        assert_eq!(writes.len(), 2);
+        assert!(matches!(&writes[0], RpcWriteOp::Index(docs) if docs.len() == 1));
+        assert!(matches!(&writes[1], RpcWriteOp::Delete(ids) if ids == &["b", "c"]));
+        assert!(writes_for(vec![]).is_empty());
+    }
+}
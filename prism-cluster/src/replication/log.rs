@@ -0,0 +1,360 @@
+//! Operation log and persisted replication checkpoints
+
+use crate::error::{ClusterError, Result};
+use crate::types::{RpcReplicatedOp, RpcShardCheckpoint, RpcWriteOp};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Most recent writes applied by a shard primary, oldest first.
+///
+/// A log opened from a file appends every write to it, so a restarted
+/// primary still has the writes lagging replicas are missing. The file is
+/// rewritten with only the retained writes once it holds twice as many.
+#[derive(Debug)]
+pub struct OperationLog {
+    ops: VecDeque<RpcReplicatedOp>,
+    retention: usize,
+    file: Option<LogFile>,
+}
+
+/// File a log appends its writes to, one JSON line each
+#[derive(Debug)]
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    /// Lines in the file, including writes no longer retained
+    lines: usize,
+}
+
+impl OperationLog {
+    /// Create a log keeping at most `retention` operations in memory only
+    pub fn new(retention: usize) -> Self {
+        Self {
+            ops: VecDeque::new(),
+            retention: retention.max(1),
+            file: None,
+        }
+    }
+
+    /// Open the log persisted at `path`, keeping at most `retention`
+    /// operations.
+    ///
+    /// A line torn by a crash while it was written ends the log.
+    pub fn open(path: &Path, retention: usize) -> Result<Self> {
+        let mut log = Self::new(retention);
+        match File::open(path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    match serde_json::from_str(&line?) {
+                        Ok(op) => log.push(op),
+                        Err(e) => {
+                            warn!(
+                                "Operation log {} ends in a torn write: {}",
+                                path.display(),
+                                e
+                            );
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        log.file = Some(log.rewrite(path)?);
+        Ok(log)
+    }
+
+    /// Append the write with the next sequence number
+    pub fn append(&mut self, seq_no: u64, timestamp: u64, op: RpcWriteOp) -> Result<()> {
+        let op = RpcReplicatedOp {
+            seq_no,
+            timestamp,
+            op,
+        };
+        let line = match self.file {
+            Some(_) => Some(
+                serde_json::to_vec(&op).map_err(|e| ClusterError::Serialization(e.to_string()))?,
+            ),
+            None => None,
+        };
+        // Kept in memory even if it cannot be persisted
+        self.push(op);
+
+        if let (Some(mut line), Some(log_file)) = (line, self.file.as_mut()) {
+            line.push(b'\n');
+            log_file.file.write_all(&line)?;
+            log_file.lines += 1;
+        }
+        if self
+            .file
+            .as_ref()
+            .is_some_and(|log_file| log_file.lines >= self.retention * 2)
+        {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Drop every operation, as when a new primary term starts
+    pub fn clear(&mut self) -> Result<()> {
+        self.ops.clear();
+        self.compact()
+    }
+
+    /// Rewrite the file with only the retained operations
+    fn compact(&mut self) -> Result<()> {
+        if let Some(log_file) = &self.file {
+            let path = log_file.path.clone();
+            self.file = Some(self.rewrite(&path)?);
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, op: RpcReplicatedOp) {
+        self.ops.push_back(op);
+        while self.ops.len() > self.retention {
+            self.ops.pop_front();
+        }
+    }
+
+    /// Replace the file at `path` with the retained operations, returning
+    /// it open for appending
+    fn rewrite(&self, path: &Path) -> Result<LogFile> {
+        let mut data = Vec::new();
+        for op in &self.ops {
+            serde_json::to_writer(&mut data, op)
+                .map_err(|e| ClusterError::Serialization(e.to_string()))?;
+            data.push(b'\n');
+        }
+        let tmp = path.with_extension("jsonl.tmp");
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(LogFile {
+            path: path.to_path_buf(),
+            file: OpenOptions::new().append(true).open(path)?,
+            lines: self.ops.len(),
+        })
+    }
+
+    /// Operations from `from_seq` up to `checkpoint`.
+    ///
+    /// Returns `None` when some of them have already been dropped.
+    pub fn since(&self, from_seq: u64, checkpoint: u64) -> Option<Vec<RpcReplicatedOp>> {
+        if from_seq > checkpoint {
+            return Some(Vec::new());
+        }
+        match self.ops.front() {
+            Some(oldest) if oldest.seq_no <= from_seq => Some(
+                self.ops
+                    .iter()
+                    .filter(|op| op.seq_no >= from_seq)
+                    .cloned()
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Sequence number of the oldest retained operation
+    pub fn oldest(&self) -> Option<u64> {
+        self.ops.front().map(|op| op.seq_no)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+/// Replication progress of one shard copy
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardCheckpoint {
+    /// Highest primary term seen
+    pub primary_term: u64,
+    /// Highest sequence number applied, with every earlier one applied too
+    pub checkpoint: u64,
+    /// Checkpoint each earlier term ended at on this copy, by term
+    #[serde(default)]
+    pub previous_terms: BTreeMap<u64, u64>,
+}
+
+impl From<ShardCheckpoint> for RpcShardCheckpoint {
+    fn from(checkpoint: ShardCheckpoint) -> Self {
+        Self {
+            primary_term: checkpoint.primary_term,
+            checkpoint: checkpoint.checkpoint,
+            previous_terms: checkpoint.previous_terms,
+        }
+    }
+}
+
+/// Checkpoints of the shard copies on this node, persisted so sequence
+/// numbers continue where they stopped after a restart
+pub struct ReplicationCheckpoints {
+    path: Option<PathBuf>,
+    shards: Mutex<HashMap<String, ShardCheckpoint>>,
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl ReplicationCheckpoints {
+    /// Checkpoints kept in memory only
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            shards: Mutex::new(HashMap::new()),
+            write_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Load checkpoints from `dir`, starting empty if none were saved
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join("replication-checkpoints.json");
+        let shards = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|e| ClusterError::Serialization(e.to_string()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path: Some(path),
+            shards: Mutex::new(shards),
+            write_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Directory the checkpoints are saved in, `None` when kept in memory
+    pub fn dir(&self) -> Option<&Path> {
+        self.path.as_deref().and_then(Path::parent)
+    }
+
+    /// Checkpoint of a shard, zero if it has none
+    pub fn get(&self, shard_id: &str) -> ShardCheckpoint {
+        self.shards
+            .lock()
+            .get(shard_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Record a shard's checkpoint and persist all of them
+    pub async fn set(&self, shard_id: &str, checkpoint: ShardCheckpoint) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        let data = {
+            let mut shards = self.shards.lock();
+            shards.insert(shard_id.to_string(), checkpoint);
+            serde_json::to_vec_pretty(&*shards)
+                .map_err(|e| ClusterError::Serialization(e.to_string()))?
+        };
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let tmp = path.with_extension("json.tmp");
+            tokio::fs::write(&tmp, data).await?;
+            tokio::fs::rename(&tmp, path).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delete(id: &str) -> RpcWriteOp {
+        RpcWriteOp::Delete(vec![id.to_string()])
+    }
+
+    #[test]
+    fn test_operation_log_since() {
+        let mut log = OperationLog::new(3);
+        for seq in 1..=5 {
+            log.append(seq, seq * 10, delete(&format!("doc-{}", seq)))
+                .unwrap();
+        }
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.oldest(), Some(3));
+
+        let ops = log.since(4, 5).unwrap();
+        assert_eq!(
+            ops.iter().map(|op| op.seq_no).collect::<Vec<_>>(),
+            vec![4, 5]
+        );
+        assert!(log.since(6, 5).unwrap().is_empty());
+        // Operations 1 and 2 were dropped
+        assert!(log.since(2, 5).is_none());
+        assert!(OperationLog::new(3).since(1, 1).is_none());
+    }
+
+    #[test]
+    fn test_operation_log_persists() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("op-logs").join("products-shard-0.jsonl");
+        let mut log = OperationLog::open(&path, 3).unwrap();
+        for seq in 1..=7 {
+            log.append(seq, seq * 10, delete(&format!("doc-{}", seq)))
+                .unwrap();
+        }
+        drop(log);
+
+        // The file is trimmed to the retained writes as it grows
+        let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+        assert!(lines < 6, "{} lines", lines);
+
+        let mut reopened = OperationLog::open(&path, 3).unwrap();
+        let ops = reopened.since(5, 7).unwrap();
+        assert_eq!(
+            ops.iter().map(|op| op.seq_no).collect::<Vec<_>>(),
+            vec![5, 6, 7]
+        );
+        assert_eq!(ops[0].timestamp, 50);
+        assert!(reopened.since(4, 7).is_none());
+
+        // A torn last line is dropped
+        reopened.append(8, 80, delete("doc-8")).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"seq_no\":9,\"ti").unwrap();
+        let reopened = OperationLog::open(&path, 3).unwrap();
+        assert_eq!(reopened.oldest(), Some(6));
+        assert_eq!(reopened.len(), 3);
+
+        let mut cleared = reopened;
+        cleared.clear().unwrap();
+        assert!(OperationLog::open(&path, 3).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_persist() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let checkpoints = ReplicationCheckpoints::load(temp.path()).unwrap();
+        assert_eq!(
+            checkpoints.get("products-shard-0"),
+            ShardCheckpoint::default()
+        );
+
+        let checkpoint = ShardCheckpoint {
+            primary_term: 2,
+            checkpoint: 17,
+            previous_terms: BTreeMap::from([(1, 40)]),
+        };
+        checkpoints
+            .set("products-shard-0", checkpoint.clone())
+            .await
+            .unwrap();
+
+        let reloaded = ReplicationCheckpoints::load(temp.path()).unwrap();
+        assert_eq!(reloaded.get("products-shard-0"), checkpoint);
+    }
+}
@@ -0,0 +1,28 @@
+//! Primary/replica write replication
+//!
+//! Every write to a shard goes through its primary. The primary assigns the
+//! write the next sequence number, applies it, appends it to its operation
+//! log and forwards it to the replicas. The write is acknowledged once the
+//! collection's `min_replicas_for_write` copies, primary included, have
+//! applied it.
+//!
+//! Replicas apply writes strictly in sequence order. A replica that finds a
+//! gap, after a restart or a missed write, first pulls the missing operations
+//! from the primary's log. Replicas that miss a write leave the in-sync set
+//! until they catch up, and reads are only routed to in-sync copies.
+//!
+//! Each copy keeps the version of the last write to every document: the
+//! primary term, the sequence number and a hybrid logical clock timestamp.
+//! When a partition heals, a copy that kept taking writes finds where it
+//! forked from the primary by comparing checkpoints, sends the primary the
+//! writes it made since, and settles documents both sides wrote with the
+//! configured [`ConflictResolution`](crate::config::ConflictResolution).
+//! Versions and operation logs are kept in memory.
+
+mod conflict;
+mod log;
+mod replicator;
+
+pub use conflict::{ConflictReport, HybridClock};
+pub use log::{OperationLog, ReplicationCheckpoints, ShardCheckpoint};
+pub use replicator::{ClientTransport, Reconciliation, ReplicaTransport, Replicator};
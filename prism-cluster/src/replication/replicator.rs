@@ -0,0 +1,1445 @@
+//! Applying and forwarding replicated writes
+
+use super::conflict::{resolve, writes_for, ConflictSide, ForkPoint, Resolved};
+use super::{ConflictReport, HybridClock, OperationLog, ReplicationCheckpoints, ShardCheckpoint};
+use crate::client::ClusterClient;
+use crate::config::{ClusterConfig, ConflictResolution, OperationLogConfig};
+use crate::error::{ClusterError, Result};
+use crate::placement::{ClusterState, ShardAssignment, ShardState};
+use crate::transfer::{CapturedWrite, ShardTransfers};
+use crate::types::{
+    RpcDocVersion, RpcDocument, RpcDocumentConflict, RpcOperations, RpcOperationsRequest,
+    RpcPrimaryWrite, RpcReplicaWrite, RpcShardCheckpoint, RpcVersionedDocument,
+    RpcVersionedDocumentsRequest, RpcWriteAck, RpcWriteOp,
+};
+use async_trait::async_trait;
+use parking_lot::{Mutex, RwLock};
+use prism::collection::CollectionManager;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+use tracing::{debug, info, warn};
+
+/// How a primary reaches its replicas and a replica its primary
+#[async_trait]
+pub trait ReplicaTransport: Send + Sync {
+    /// Forward a write to a replica, returning its checkpoint
+    async fn replica_write(&self, addr: &str, request: RpcReplicaWrite) -> Result<u64>;
+
+    /// Read operations from a primary's log
+    async fn get_operations(
+        &self,
+        addr: &str,
+        request: RpcOperationsRequest,
+    ) -> Result<RpcOperations>;
+
+    /// Send a write to a primary
+    async fn primary_write(&self, addr: &str, request: RpcPrimaryWrite) -> Result<RpcWriteAck>;
+
+    /// Read the replication progress of a node's copy of a shard
+    async fn shard_checkpoint(&self, addr: &str, shard_id: &str) -> Result<RpcShardCheckpoint>;
+
+    /// Read documents of a shard with their versions
+    async fn get_versioned_documents(
+        &self,
+        addr: &str,
+        request: RpcVersionedDocumentsRequest,
+    ) -> Result<Vec<RpcVersionedDocument>>;
+}
+
+/// Transport over cluster RPC, connecting on first use
+pub struct ClientTransport {
+    config: ClusterConfig,
+    client: OnceCell<Arc<ClusterClient>>,
+}
+
+impl ClientTransport {
+    pub fn new(config: ClusterConfig) -> Self {
+        Self {
+            config,
+            client: OnceCell::new(),
+        }
+    }
+
+    pub(crate) async fn client(&self) -> Result<&Arc<ClusterClient>> {
+        self.client
+            .get_or_try_init(|| async {
+                Ok(Arc::new(ClusterClient::new(self.config.clone()).await?))
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl ReplicaTransport for ClientTransport {
+    async fn replica_write(&self, addr: &str, request: RpcReplicaWrite) -> Result<u64> {
+        self.client().await?.replica_write(addr, request).await
+    }
+
+    async fn get_operations(
+        &self,
+        addr: &str,
+        request: RpcOperationsRequest,
+    ) -> Result<RpcOperations> {
+        self.client().await?.get_operations(addr, request).await
+    }
+
+    async fn primary_write(&self, addr: &str, request: RpcPrimaryWrite) -> Result<RpcWriteAck> {
+        self.client().await?.primary_write(addr, request).await
+    }
+
+    async fn shard_checkpoint(&self, addr: &str, shard_id: &str) -> Result<RpcShardCheckpoint> {
+        self.client().await?.shard_checkpoint(addr, shard_id).await
+    }
+
+    async fn get_versioned_documents(
+        &self,
+        addr: &str,
+        request: RpcVersionedDocumentsRequest,
+    ) -> Result<Vec<RpcVersionedDocument>> {
+        self.client()
+            .await?
+            .get_versioned_documents(addr, request)
+            .await
+    }
+}
+
+/// Replication state of a shard copy on this node
+struct ShardReplica {
+    /// Held while a write is applied so writes apply in sequence order
+    write_lock: tokio::sync::Mutex<()>,
+    state: Mutex<ReplicaState>,
+}
+
+struct ReplicaState {
+    primary_term: u64,
+    checkpoint: u64,
+    /// Checkpoint each earlier term ended at
+    previous_terms: BTreeMap<u64, u64>,
+    /// Only filled on the primary
+    log: OperationLog,
+    /// Replicas that applied every write so far, tracked on the primary
+    in_sync: BTreeSet<String>,
+    /// Version of the last write applied to each document
+    versions: HashMap<String, RpcDocVersion>,
+}
+
+impl ReplicaState {
+    /// Move to a newer primary term, whose sequence starts over
+    fn start_term(&mut self, primary_term: u64) {
+        if self.primary_term > 0 {
+            self.previous_terms
+                .insert(self.primary_term, self.checkpoint);
+        }
+        self.primary_term = primary_term;
+        self.checkpoint = 0;
+    }
+
+    /// Record the version of every document a write touched
+    fn record_versions(&mut self, op: &RpcWriteOp, seq_no: u64, timestamp: u64) {
+        let version = |deleted| RpcDocVersion {
+            primary_term: self.primary_term,
+            seq_no,
+            timestamp,
+            deleted,
+        };
+        let touched: Vec<(String, RpcDocVersion)> = match op {
+            RpcWriteOp::Index(docs) => docs
+                .iter()
+                .map(|doc| (doc.id.clone(), version(false)))
+                .collect(),
+            RpcWriteOp::Delete(ids) => ids.iter().map(|id| (id.clone(), version(true))).collect(),
+        };
+        self.versions.extend(touched);
+    }
+
+    fn saved(&self) -> ShardCheckpoint {
+        ShardCheckpoint {
+            primary_term: self.primary_term,
+            checkpoint: self.checkpoint,
+            previous_terms: self.previous_terms.clone(),
+        }
+    }
+}
+
+/// Outcome of reconciling this node's shard copies with their primaries
+#[derive(Debug, Default)]
+pub struct Reconciliation {
+    /// Shards whose copy here had diverged from the primary
+    pub diverged_shards: Vec<String>,
+    /// Documents written here during the partition and sent to the primary
+    pub forwarded_writes: usize,
+    /// Documents written on both sides
+    pub conflicts: Vec<RpcDocumentConflict>,
+    /// Shards that could not be reconciled
+    pub failures: Vec<(String, ClusterError)>,
+}
+
+/// Replicates writes between the copies of the shards on this node
+pub struct Replicator {
+    node_id: String,
+    manager: Arc<CollectionManager>,
+    cluster_state: Arc<ClusterState>,
+    transfers: Arc<ShardTransfers>,
+    transport: Arc<dyn ReplicaTransport>,
+    checkpoints: ReplicationCheckpoints,
+    retention: usize,
+    clock: HybridClock,
+    conflicts: Arc<ConflictReport>,
+    shards: RwLock<HashMap<String, Arc<ShardReplica>>>,
+}
+
+impl Replicator {
+    pub fn new(
+        node_id: impl Into<String>,
+        manager: Arc<CollectionManager>,
+        cluster_state: Arc<ClusterState>,
+        transfers: Arc<ShardTransfers>,
+        transport: Arc<dyn ReplicaTransport>,
+        checkpoints: ReplicationCheckpoints,
+        config: &OperationLogConfig,
+    ) -> Self {
+        Self {
+            node_id: node_id.into(),
+            manager,
+            cluster_state,
+            transfers,
+            transport,
+            checkpoints,
+            retention: config.retention_ops,
+            clock: HybridClock::new(),
+            conflicts: Arc::new(ConflictReport::new()),
+            shards: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn shard(&self, shard_id: &str) -> Arc<ShardReplica> {
+        if let Some(shard) = self.shards.read().get(shard_id) {
+            return Arc::clone(shard);
+        }
+        let saved = self.checkpoints.get(shard_id);
+        let mut shards = self.shards.write();
+        Arc::clone(shards.entry(shard_id.to_string()).or_insert_with(|| {
+            Arc::new(ShardReplica {
+                write_lock: tokio::sync::Mutex::new(()),
+                state: Mutex::new(ReplicaState {
+                    primary_term: saved.primary_term,
+                    checkpoint: saved.checkpoint,
+                    previous_terms: saved.previous_terms,
+                    log: self.open_log(shard_id),
+                    in_sync: BTreeSet::new(),
+                    versions: HashMap::new(),
+                }),
+            })
+        }))
+    }
+
+    /// Operation log of a shard, persisted next to the checkpoints
+    fn open_log(&self, shard_id: &str) -> OperationLog {
+        let Some(dir) = self.checkpoints.dir() else {
+            return OperationLog::new(self.retention);
+        };
+        let path = dir.join("op-logs").join(format!("{}.jsonl", shard_id));
+        OperationLog::open(&path, self.retention).unwrap_or_else(|e| {
+            warn!(
+                "Failed to open operation log {}, keeping it in memory: {}",
+                path.display(),
+                e
+            );
+            OperationLog::new(self.retention)
+        })
+    }
+
+    /// Checkpoint of this node's copy of a shard
+    pub fn checkpoint(&self, shard_id: &str) -> ShardCheckpoint {
+        self.shard(shard_id).state.lock().saved()
+    }
+
+    /// Conflicts resolved while reconciling shard copies on this node
+    pub fn conflict_report(&self) -> Arc<ConflictReport> {
+        Arc::clone(&self.conflicts)
+    }
+
+    fn assignment(&self, shard_id: &str) -> Result<ShardAssignment> {
+        self.cluster_state
+            .get_shard(shard_id)
+            .ok_or_else(|| ClusterError::CollectionNotFound(shard_id.to_string()))
+    }
+
+    /// Copies that must apply a write before it is acknowledged
+    fn required_copies(&self, collection: &str) -> usize {
+        self.manager
+            .get_schema(collection)
+            .and_then(|schema| schema.replication)
+            .map_or(1, |replication| replication.min_replicas_for_write.max(1))
+    }
+
+    /// Apply a write on this node's primary copy and replicate it
+    pub async fn primary_write(&self, request: RpcPrimaryWrite) -> Result<RpcWriteAck> {
+        let shard = self.assignment(&request.shard_id)?;
+        if shard.primary_node != self.node_id {
+            return Err(ClusterError::Replication(format!(
+                "Node {} is not the primary of shard {}, {} is",
+                self.node_id, shard.shard_id, shard.primary_node
+            )));
+        }
+        if shard.collection != request.collection {
+            return Err(ClusterError::InvalidQuery(format!(
+                "Shard {} belongs to collection {}, not {}",
+                shard.shard_id, shard.collection, request.collection
+            )));
+        }
+        if !matches!(shard.state, ShardState::Active | ShardState::Relocating) {
+            return Err(ClusterError::Replication(format!(
+                "Shard {} is {:?} and cannot accept writes",
+                shard.shard_id, shard.state
+            )));
+        }
+
+        let required = self.required_copies(&shard.collection);
+        let replicas: Vec<(String, String)> = shard
+            .replica_nodes
+            .iter()
+            .filter_map(|node_id| {
+                self.cluster_state
+                    .get_node(node_id)
+                    .filter(|node| node.reachable)
+                    .map(|node| (node_id.clone(), node.info.address))
+            })
+            .collect();
+        if 1 + replicas.len() < required {
+            return Err(ClusterError::Replication(format!(
+                "Shard {} has {} reachable copies, {} required for writes",
+                shard.shard_id,
+                1 + replicas.len(),
+                required
+            )));
+        }
+
+        let replica = self.shard(&shard.shard_id);
+        let _guard = replica.write_lock.lock().await;
+        let (primary_term, seq_no) = {
+            let mut state = replica.state.lock();
+            if shard.epoch > state.primary_term {
+                // A new primary term starts a new sequence
+                state.start_term(shard.epoch);
+                if let Err(e) = state.log.clear() {
+                    warn!(
+                        "Failed to clear operation log of shard {}: {}",
+                        shard.shard_id, e
+                    );
+                }
+            }
+            (state.primary_term, state.checkpoint + 1)
+        };
+        let timestamp = self.clock.now();
+
+        self.apply(&shard.collection, &request.op).await?;
+        let saved = {
+            let mut state = replica.state.lock();
+            state.checkpoint = seq_no;
+            state.record_versions(&request.op, seq_no, timestamp);
+            if let Err(e) = state.log.append(seq_no, timestamp, request.op.clone()) {
+                warn!(
+                    "Failed to persist write {} to the operation log of shard {}: {}",
+                    seq_no, shard.shard_id, e
+                );
+            }
+            state.saved()
+        };
+        self.persist(&shard.shard_id, saved).await;
+
+        let forwarded = replicas.iter().map(|(node_id, addr)| {
+            let write = RpcReplicaWrite {
+                shard_id: shard.shard_id.clone(),
+                collection: shard.collection.clone(),
+                primary_node: self.node_id.clone(),
+                primary_term,
+                seq_no,
+                timestamp,
+                op: request.op.clone(),
+            };
+            async move { (node_id, self.transport.replica_write(addr, write).await) }
+        });
+        let results = futures::future::join_all(forwarded).await;
+
+        let mut acks = 1;
+        let in_sync_replicas: Vec<String> = {
+            let mut state = replica.state.lock();
+            state
+                .in_sync
+                .retain(|node| shard.replica_nodes.contains(node));
+            for (node_id, result) in results {
+                match result {
+                    Ok(checkpoint) if checkpoint >= seq_no => {
+                        acks += 1;
+                        state.in_sync.insert(node_id.clone());
+                    }
+                    Ok(checkpoint) => {
+                        warn!(
+                            "Replica {} of shard {} is at {} after write {}",
+                            node_id, shard.shard_id, checkpoint, seq_no
+                        );
+                        state.in_sync.remove(node_id);
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Replicating write {} of shard {} to {} failed: {}",
+                            seq_no, shard.shard_id, node_id, e
+                        );
+                        state.in_sync.remove(node_id);
+                    }
+                }
+            }
+            // Unreachable replicas missed the write too
+            let reached: Vec<&String> = replicas.iter().map(|(node_id, _)| node_id).collect();
+            state.in_sync.retain(|node| reached.contains(&node));
+            state.in_sync.iter().cloned().collect()
+        };
+        self.cluster_state
+            .update_in_sync_replicas(&shard.shard_id, in_sync_replicas.clone());
+
+        if acks < required {
+            return Err(ClusterError::Replication(format!(
+                "Write {} to shard {} was applied by {} of {} required copies",
+                seq_no, shard.shard_id, acks, required
+            )));
+        }
+        debug!(
+            "Write {} to shard {} applied by {} copies",
+            seq_no, shard.shard_id, acks
+        );
+        Ok(RpcWriteAck {
+            shard_id: shard.shard_id,
+            seq_no,
+            primary_term,
+            acks,
+            required,
+            in_sync_replicas,
+        })
+    }
+
+    /// Apply a write forwarded by the primary, catching up first if earlier
+    /// writes are missing
+    pub async fn replica_write(&self, request: RpcReplicaWrite) -> Result<u64> {
+        let shard = self.assignment(&request.shard_id)?;
+        if !shard.replica_nodes.contains(&self.node_id) {
+            return Err(ClusterError::Replication(format!(
+                "Node {} is not a replica of shard {}",
+                self.node_id, shard.shard_id
+            )));
+        }
+
+        let replica = self.shard(&request.shard_id);
+        let _guard = replica.write_lock.lock().await;
+        {
+            let mut state = replica.state.lock();
+            if request.primary_term < state.primary_term {
+                return Err(ClusterError::Replication(format!(
+                    "Write {} to shard {} is from term {}, replica is at term {}",
+                    request.seq_no, request.shard_id, request.primary_term, state.primary_term
+                )));
+            }
+            if request.primary_term > state.primary_term {
+                state.start_term(request.primary_term);
+            }
+            if request.seq_no <= state.checkpoint {
+                // Already applied, e.g. pulled while catching up
+                return Ok(state.checkpoint);
+            }
+        }
+
+        self.catch_up_locked(
+            &replica,
+            &request.shard_id,
+            &request.collection,
+            &request.primary_node,
+            Some(request.seq_no - 1),
+        )
+        .await?;
+
+        self.clock.observe(request.timestamp);
+        self.apply(&request.collection, &request.op).await?;
+        let saved = {
+            let mut state = replica.state.lock();
+            state.checkpoint = request.seq_no;
+            state.record_versions(&request.op, request.seq_no, request.timestamp);
+            state.saved()
+        };
+        self.persist(&request.shard_id, saved).await;
+        Ok(request.seq_no)
+    }
+
+    /// Operations from this node's log of a primary shard
+    pub fn get_operations(&self, request: RpcOperationsRequest) -> Result<RpcOperations> {
+        let replica = self.shard(&request.shard_id);
+        let state = replica.state.lock();
+        let ops = state
+            .log
+            .since(request.from_seq, state.checkpoint)
+            .ok_or_else(|| {
+                ClusterError::Replication(format!(
+                    "Operations of shard {} from {} are no longer in the log (oldest kept: {})",
+                    request.shard_id,
+                    request.from_seq,
+                    state
+                        .log
+                        .oldest()
+                        .map_or_else(|| "none".to_string(), |seq| seq.to_string())
+                ))
+            })?;
+        Ok(RpcOperations {
+            primary_term: state.primary_term,
+            checkpoint: state.checkpoint,
+            ops,
+        })
+    }
+
+    /// Replication progress of this node's copy of a shard
+    pub fn shard_checkpoint(&self, shard_id: &str) -> RpcShardCheckpoint {
+        self.checkpoint(shard_id).into()
+    }
+
+    /// Documents of a shard copy with the versions of their last writes
+    pub async fn get_versioned_documents(
+        &self,
+        request: RpcVersionedDocumentsRequest,
+    ) -> Result<Vec<RpcVersionedDocument>> {
+        let replica = self.shard(&request.shard_id);
+        let mut documents = Vec::with_capacity(request.ids.len());
+        for id in request.ids {
+            let document = self
+                .manager
+                .get(&request.collection, &id)
+                .await?
+                .map(RpcDocument::from);
+            let version = replica.state.lock().versions.get(&id).copied();
+            documents.push(RpcVersionedDocument {
+                id,
+                version,
+                document,
+            });
+        }
+        Ok(documents)
+    }
+
+    /// Reconcile the shard copies on this node that are not primaries with
+    /// their primaries, after a partition healed.
+    ///
+    /// Writes this copy took after it last agreed with the primary are sent
+    /// to the primary, unless the primary wrote the same document since;
+    /// those conflicts are settled with `resolution` and reported. The copy
+    /// then catches up with the primary's writes.
+    pub async fn reconcile(&self, resolution: ConflictResolution) -> Reconciliation {
+        let mut shard_ids: BTreeSet<String> = self.shards.read().keys().cloned().collect();
+        shard_ids.extend(
+            self.cluster_state
+                .get_node_shards(&self.node_id)
+                .into_iter()
+                .map(|shard| shard.shard_id),
+        );
+
+        let mut reconciliation = Reconciliation::default();
+        for shard_id in shard_ids {
+            let Some(shard) = self.cluster_state.get_shard(&shard_id) else {
+                continue;
+            };
+            if shard.primary_node == self.node_id {
+                continue;
+            }
+            if let Err(e) = self
+                .reconcile_shard(&shard, resolution, &mut reconciliation)
+                .await
+            {
+                warn!("Shard {} cannot be reconciled: {}", shard_id, e);
+                reconciliation.failures.push((shard_id, e));
+            }
+        }
+        reconciliation
+    }
+
+    async fn reconcile_shard(
+        &self,
+        shard: &ShardAssignment,
+        resolution: ConflictResolution,
+        reconciliation: &mut Reconciliation,
+    ) -> Result<()> {
+        let addr = self
+            .cluster_state
+            .get_node(&shard.primary_node)
+            .map(|node| node.info.address)
+            .ok_or_else(|| ClusterError::NodeUnavailable(shard.primary_node.clone()))?;
+        let replica = self.shard(&shard.shard_id);
+
+        let forward = {
+            let _guard = replica.write_lock.lock().await;
+            let local = replica.state.lock().saved();
+            let primary = self
+                .transport
+                .shard_checkpoint(&addr, &shard.shard_id)
+                .await?;
+            if primary.primary_term < local.primary_term {
+                return Err(ClusterError::Replication(format!(
+                    "Primary {} of shard {} is at term {}, this copy is at term {}",
+                    shard.primary_node, shard.shard_id, primary.primary_term, local.primary_term
+                )));
+            }
+
+            let fork = ForkPoint::between(&local, &primary);
+            let mut diverged: Vec<(String, RpcDocVersion)> = replica
+                .state
+                .lock()
+                .versions
+                .iter()
+                .filter(|(_, version)| fork.precedes(version))
+                .map(|(id, version)| (id.clone(), *version))
+                .collect();
+            if diverged.is_empty() && fork.seq_no == local.checkpoint {
+                Vec::new()
+            } else {
+                diverged.sort_by(|a, b| a.0.cmp(&b.0));
+                info!(
+                    "Copy of shard {} diverged from primary {} after {}/{}: {} documents written since",
+                    shard.shard_id,
+                    shard.primary_node,
+                    fork.primary_term,
+                    fork.seq_no,
+                    diverged.len()
+                );
+                reconciliation.diverged_shards.push(shard.shard_id.clone());
+                let forward = self
+                    .resolve_diverged(shard, &addr, fork, diverged, resolution, &replica)
+                    .await
+                    .map(|(forward, conflicts)| {
+                        reconciliation.conflicts.extend(conflicts);
+                        forward
+                    })?;
+
+                // Rewind to the last write shared with the primary; catching
+                // up replays the primary's writes from there
+                let saved = {
+                    let mut state = replica.state.lock();
+                    state.checkpoint = fork.seq_no;
+                    state.saved()
+                };
+                self.persist(&shard.shard_id, saved).await;
+                forward
+            }
+        };
+
+        // Sent without holding the write lock: the primary replicates them
+        // back to this copy
+        let forwarded = forward.len();
+        if forwarded > 0 {
+            info!(
+                "Sending {} documents of shard {} written during the partition to {}",
+                forwarded, shard.shard_id, shard.primary_node
+            );
+        }
+        for op in writes_for(forward) {
+            let ack = self
+                .transport
+                .primary_write(
+                    &addr,
+                    RpcPrimaryWrite {
+                        shard_id: shard.shard_id.clone(),
+                        collection: shard.collection.clone(),
+                        op: op.clone(),
+                    },
+                )
+                .await?;
+            // Track the new version so the write is not sent twice
+            let mut state = replica.state.lock();
+            let versions: Vec<(String, bool)> = match &op {
+                RpcWriteOp::Index(docs) => docs.iter().map(|d| (d.id.clone(), false)).collect(),
+                RpcWriteOp::Delete(ids) => ids.iter().map(|id| (id.clone(), true)).collect(),
+            };
+            for (id, deleted) in versions {
+                if let Some(version) = state.versions.get_mut(&id) {
+                    version.primary_term = ack.primary_term;
+                    version.seq_no = ack.seq_no;
+                    version.deleted = deleted;
+                }
+            }
+        }
+        reconciliation.forwarded_writes += forwarded;
+
+        if shard.replica_nodes.contains(&self.node_id) {
+            let _guard = replica.write_lock.lock().await;
+            self.catch_up_locked(
+                &replica,
+                &shard.shard_id,
+                &shard.collection,
+                &shard.primary_node,
+                None,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Settle the documents written on this copy after the fork, returning
+    /// the writes to send to the primary and the conflicts resolved. The
+    /// caller holds the write lock.
+    async fn resolve_diverged(
+        &self,
+        shard: &ShardAssignment,
+        addr: &str,
+        fork: ForkPoint,
+        diverged: Vec<(String, RpcDocVersion)>,
+        resolution: ConflictResolution,
+        replica: &ShardReplica,
+    ) -> Result<(Vec<(String, Option<RpcDocument>)>, Vec<RpcDocumentConflict>)> {
+        if diverged.is_empty() {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        let on_primary: HashMap<String, RpcVersionedDocument> = self
+            .transport
+            .get_versioned_documents(
+                addr,
+                RpcVersionedDocumentsRequest {
+                    shard_id: shard.shard_id.clone(),
+                    collection: shard.collection.clone(),
+                    ids: diverged.iter().map(|(id, _)| id.clone()).collect(),
+                },
+            )
+            .await?
+            .into_iter()
+            .map(|doc| (doc.id.clone(), doc))
+            .collect();
+
+        let mut forward = Vec::new();
+        let mut keep_primary = Vec::new();
+        let mut conflicts = Vec::new();
+        for (id, local_version) in diverged {
+            let local_document = self
+                .manager
+                .get(&shard.collection, &id)
+                .await?
+                .map(RpcDocument::from);
+            let primary = on_primary.get(&id);
+            let primary_version = primary
+                .and_then(|doc| doc.version)
+                .filter(|version| fork.precedes(version));
+            let Some(primary_version) = primary_version else {
+                // Only this copy wrote the document since the fork
+                forward.push((id, local_document));
+                continue;
+            };
+
+            let primary_document = primary.and_then(|doc| doc.document.clone());
+            let resolved = resolve(
+                resolution,
+                ConflictSide {
+                    version: local_version,
+                    document: local_document.as_ref(),
+                },
+                ConflictSide {
+                    version: primary_version,
+                    document: primary_document.as_ref(),
+                },
+            );
+            let winner = resolved.winner();
+            match resolved {
+                Resolved::Local => forward.push((id.clone(), local_document.clone())),
+                Resolved::Merged(merged) => forward.push((id.clone(), Some(merged))),
+                Resolved::Primary => {
+                    keep_primary.push((id.clone(), primary_document.clone()));
+                    replica
+                        .state
+                        .lock()
+                        .versions
+                        .insert(id.clone(), primary_version);
+                }
+            }
+            warn!(
+                "Document {} of shard {} was written on both sides of a partition, kept {:?} ({:?})",
+                id, shard.shard_id, winner, resolution
+            );
+            let conflict = RpcDocumentConflict {
+                collection: shard.collection.clone(),
+                shard_id: shard.shard_id.clone(),
+                document_id: id,
+                local_node: self.node_id.clone(),
+                primary_node: shard.primary_node.clone(),
+                local_version,
+                primary_version,
+                local_document,
+                primary_document,
+                resolution,
+                winner,
+                resolved_at: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+            };
+            self.conflicts.record(conflict.clone());
+            conflicts.push(conflict);
+        }
+
+        for op in writes_for(keep_primary) {
+            self.apply(&shard.collection, &op).await?;
+        }
+        Ok((forward, conflicts))
+    }
+
+    /// Catch every replica copy on this node up with its primary.
+    ///
+    /// Run after a restart; returns the shards that could not catch up.
+    pub async fn catch_up_replicas(&self) -> Vec<(String, ClusterError)> {
+        let mut failures = Vec::new();
+        for shard in self.cluster_state.get_node_shards(&self.node_id) {
+            if !shard.replica_nodes.contains(&self.node_id) {
+                continue;
+            }
+            let replica = self.shard(&shard.shard_id);
+            let _guard = replica.write_lock.lock().await;
+            let before = replica.state.lock().checkpoint;
+            match self
+                .catch_up_locked(
+                    &replica,
+                    &shard.shard_id,
+                    &shard.collection,
+                    &shard.primary_node,
+                    None,
+                )
+                .await
+            {
+                Ok(checkpoint) => info!(
+                    "Replica of shard {} caught up from {} to {}",
+                    shard.shard_id, before, checkpoint
+                ),
+                Err(e) => {
+                    warn!("Replica of shard {} cannot catch up: {}", shard.shard_id, e);
+                    failures.push((shard.shard_id, e));
+                }
+            }
+        }
+        failures
+    }
+
+    /// Pull and apply the operations this copy is missing, up to `up_to`
+    /// when given. The caller holds the shard's write lock.
+    async fn catch_up_locked(
+        &self,
+        replica: &ShardReplica,
+        shard_id: &str,
+        collection: &str,
+        primary_node: &str,
+        up_to: Option<u64>,
+    ) -> Result<u64> {
+        let (mut checkpoint, term) = {
+            let state = replica.state.lock();
+            (state.checkpoint, state.primary_term)
+        };
+        if up_to.is_some_and(|up_to| checkpoint >= up_to) {
+            return Ok(checkpoint);
+        }
+
+        let addr = self
+            .cluster_state
+            .get_node(primary_node)
+            .map(|node| node.info.address)
+            .ok_or_else(|| ClusterError::NodeUnavailable(primary_node.to_string()))?;
+        let fetch = |from_seq| {
+            self.transport.get_operations(
+                &addr,
+                RpcOperationsRequest {
+                    shard_id: shard_id.to_string(),
+                    from_seq,
+                },
+            )
+        };
+        let mut operations = fetch(checkpoint + 1).await?;
+        if operations.primary_term < term {
+            return Err(ClusterError::Replication(format!(
+                "Primary {} of shard {} is at term {}, replica is at term {}",
+                primary_node, shard_id, operations.primary_term, term
+            )));
+        }
+        if operations.primary_term > term {
+            // The primary changed and restarted the sequence
+            replica.state.lock().start_term(operations.primary_term);
+            if checkpoint > 0 {
+                checkpoint = 0;
+                operations = fetch(1).await?;
+            }
+        }
+
+        let mut applied = 0;
+        for op in operations.ops {
+            if up_to.is_some_and(|up_to| op.seq_no > up_to) {
+                break;
+            }
+            if op.seq_no != checkpoint + 1 {
+                return Err(ClusterError::Replication(format!(
+                    "Operation log of shard {} skips from {} to {}",
+                    shard_id, checkpoint, op.seq_no
+                )));
+            }
+            self.clock.observe(op.timestamp);
+            self.apply(collection, &op.op).await?;
+            checkpoint = op.seq_no;
+            let mut state = replica.state.lock();
+            state.checkpoint = checkpoint;
+            state.record_versions(&op.op, op.seq_no, op.timestamp);
+            applied += 1;
+        }
+        if let Some(up_to) = up_to.filter(|&up_to| checkpoint < up_to) {
+            return Err(ClusterError::Replication(format!(
+                "Primary {} of shard {} returned operations up to {}, {} needed",
+                primary_node, shard_id, checkpoint, up_to
+            )));
+        }
+
+        if applied > 0 {
+            debug!(
+                "Applied {} missed operations of shard {}",
+                applied, shard_id
+            );
+            let saved = replica.state.lock().saved();
+            self.persist(shard_id, saved).await;
+        }
+        Ok(checkpoint)
+    }
+
+    /// Apply a write locally, capturing it for a shard transfer in progress
+    async fn apply(&self, collection: &str, op: &RpcWriteOp) -> Result<()> {
+        let capture = self.transfers.capture(collection);
+        let _gate = match &capture {
+            Some(capture) => Some(capture.enter().await?),
+            None => None,
+        };
+        match op {
+            RpcWriteOp::Index(docs) => {
+                let docs = docs.iter().cloned().map(Into::into).collect();
+                self.manager.index(collection, docs).await?;
+            }
+            RpcWriteOp::Delete(ids) => self.manager.delete(collection, ids.clone()).await?,
+        }
+        if let Some(capture) = &capture {
+            capture.record(match op {
+                RpcWriteOp::Index(docs) => CapturedWrite::Index(docs.clone()),
+                RpcWriteOp::Delete(ids) => CapturedWrite::Delete(ids.clone()),
+            });
+        }
+        Ok(())
+    }
+
+    async fn persist(&self, shard_id: &str, saved: ShardCheckpoint) {
+        if let Err(e) = self.checkpoints.set(shard_id, saved).await {
+            warn!(
+                "Failed to persist replication checkpoint of shard {}: {}",
+                shard_id, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NodeTopology;
+    use crate::placement::NodeInfo;
+    use crate::types::{RpcConflictWinner, RpcDocument};
+    use parking_lot::Mutex as SyncMutex;
+    use prism::backends::{TextBackend, VectorBackend};
+    use std::collections::HashSet;
+    use tempfile::TempDir;
+
+    const SHARD: &str = "products-shard-0";
+
+    /// Nodes reached in-process by address
+    #[derive(Default)]
+    struct LoopbackTransport {
+        nodes: RwLock<HashMap<String, Arc<Replicator>>>,
+        down: SyncMutex<HashSet<String>>,
+    }
+
+    impl LoopbackTransport {
+        fn node(&self, addr: &str) -> Result<Arc<Replicator>> {
+            if self.down.lock().contains(addr) {
+                return Err(ClusterError::Connection(format!("{} is down", addr)));
+            }
+            self.nodes
+                .read()
+                .get(addr)
+                .cloned()
+                .ok_or_else(|| ClusterError::NodeUnavailable(addr.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl ReplicaTransport for LoopbackTransport {
+        async fn replica_write(&self, addr: &str, request: RpcReplicaWrite) -> Result<u64> {
+            self.node(addr)?.replica_write(request).await
+        }
+
+        async fn get_operations(
+            &self,
+            addr: &str,
+            request: RpcOperationsRequest,
+        ) -> Result<RpcOperations> {
+            self.node(addr)?.get_operations(request)
+        }
+
+        async fn primary_write(&self, addr: &str, request: RpcPrimaryWrite) -> Result<RpcWriteAck> {
+            self.node(addr)?.primary_write(request).await
+        }
+
+        async fn shard_checkpoint(&self, addr: &str, shard_id: &str) -> Result<RpcShardCheckpoint> {
+            Ok(self.node(addr)?.shard_checkpoint(shard_id))
+        }
+
+        async fn get_versioned_documents(
+            &self,
+            addr: &str,
+            request: RpcVersionedDocumentsRequest,
+        ) -> Result<Vec<RpcVersionedDocument>> {
+            self.node(addr)?.get_versioned_documents(request).await
+        }
+    }
+
+    struct Cluster {
+        temp: TempDir,
+        transport: Arc<LoopbackTransport>,
+        managers: Vec<Arc<CollectionManager>>,
+        state: Arc<ClusterState>,
+        retention: usize,
+    }
+
+    impl Cluster {
+        /// Start a node's replicator, loading the state it persisted
+        fn start(&self, node: usize) {
+            let node_id = format!("node-{}", node);
+            let state_dir = self.temp.path().join(&node_id).join("replication");
+            let replicator = Replicator::new(
+                node_id.clone(),
+                Arc::clone(&self.managers[node - 1]),
+                Arc::clone(&self.state),
+                Arc::new(ShardTransfers::new()),
+                Arc::clone(&self.transport) as Arc<dyn ReplicaTransport>,
+                ReplicationCheckpoints::load(&state_dir).unwrap(),
+                &OperationLogConfig {
+                    retention_ops: self.retention,
+                    state_dir: None,
+                },
+            );
+            self.transport
+                .nodes
+                .write()
+                .insert(format!("{}:9080", node_id), Arc::new(replicator));
+        }
+
+        fn replicator(&self, node: usize) -> Arc<Replicator> {
+            self.transport.node(&format!("node-{}:9080", node)).unwrap()
+        }
+
+        fn set_down(&self, node: usize, down: bool) {
+            let addr = format!("node-{}:9080", node);
+            if down {
+                self.transport.down.lock().insert(addr);
+            } else {
+                self.transport.down.lock().remove(&addr);
+            }
+        }
+
+        async fn has_doc(&self, node: usize, id: &str) -> bool {
+            self.title(node, id).await.is_some()
+        }
+
+        async fn title(&self, node: usize, id: &str) -> Option<String> {
+            self.managers[node - 1]
+                .get("products", id)
+                .await
+                .unwrap()
+                .map(|doc| doc.fields["title"].as_str().unwrap().to_string())
+        }
+
+        /// Make `node` the primary in a new term, the others its replicas
+        fn promote(&self, node: usize) {
+            let mut shard = self.state.get_shard(SHARD).unwrap();
+            let primary = format!("node-{}", node);
+            shard.replica_nodes = (1..=3)
+                .map(|n| format!("node-{}", n))
+                .filter(|n| *n != primary)
+                .collect();
+            shard.primary_node = primary;
+            shard.epoch += 1;
+            self.state.assign_shard(shard);
+        }
+    }
+
+    async fn make_cluster(min_replicas_for_write: usize, retention: usize) -> Cluster {
+        let temp = TempDir::new().unwrap();
+        let state = Arc::new(ClusterState::new());
+        let transport = Arc::new(LoopbackTransport::default());
+        let mut managers = Vec::new();
+
+        for node in 1..=3 {
+            let dir = temp.path().join(format!("node-{}", node));
+            let schemas_dir = dir.join("schemas");
+            std::fs::create_dir_all(&schemas_dir).unwrap();
+            std::fs::write(
+                schemas_dir.join("products.yaml"),
+                format!(
+                    r#"
+collection: products
+replication:
+  factor: 3
+  min_replicas_for_write: {min_replicas_for_write}
+backends:
+  text:
+    fields:
+      - name: title
+        type: text
+        indexed: true
+        stored: true
+"#
+                ),
+            )
+            .unwrap();
+            let text_backend = Arc::new(TextBackend::new(dir.join("data")).unwrap());
+            let vector_backend = Arc::new(VectorBackend::new(dir.join("data")).unwrap());
+            let manager = Arc::new(
+                CollectionManager::new(&schemas_dir, text_backend, vector_backend, None).unwrap(),
+            );
+            manager.initialize().await.unwrap();
+
+            let node_id = format!("node-{}", node);
+            state.register_node(NodeInfo {
+                node_id: node_id.clone(),
+                address: format!("{}:9080", node_id),
+                topology: NodeTopology::default(),
+                healthy: true,
+                shard_count: 0,
+                disk_used_bytes: 0,
+                disk_total_bytes: 0,
+                index_size_bytes: 0,
+                draining: false,
+            });
+            managers.push(manager);
+        }
+
+        let mut shard = ShardAssignment::new("products", 0, "node-1");
+        shard.replica_nodes = vec!["node-2".to_string(), "node-3".to_string()];
+        shard.state = ShardState::Active;
+        state.assign_shard(shard);
+
+        let cluster = Cluster {
+            temp,
+            transport,
+            managers,
+            state,
+            retention,
+        };
+        for node in 1..=3 {
+            cluster.start(node);
+        }
+        cluster
+    }
+
+    fn index(id: &str) -> RpcPrimaryWrite {
+        index_titled(id, id)
+    }
+
+    fn index_titled(id: &str, title: &str) -> RpcPrimaryWrite {
+        RpcPrimaryWrite {
+            shard_id: SHARD.to_string(),
+            collection: "products".to_string(),
+            op: RpcWriteOp::Index(vec![RpcDocument {
+                id: id.to_string(),
+                fields: HashMap::from([("title".to_string(), serde_json::json!(title))]),
+            }]),
+        }
+    }
+
+    /// Node 1 keeps taking writes cut off from nodes 2 and 3, which promote
+    /// node 2 and write through it. Node 1's write to `b` is the later one
+    /// when `local_writes_last` is set.
+    async fn diverge(cluster: &Cluster, local_writes_last: bool) {
+        let old_primary = cluster.replicator(1);
+        old_primary.primary_write(index("a")).await.unwrap();
+        old_primary.primary_write(index("b")).await.unwrap();
+
+        let stale = cluster.state.get_shard(SHARD).unwrap();
+        cluster.promote(2);
+        let promoted = cluster.state.get_shard(SHARD).unwrap();
+
+        let write_local = || async {
+            // Node 1 has not heard of the promotion
+            cluster.state.assign_shard(stale.clone());
+            cluster.set_down(2, true);
+            cluster.set_down(3, true);
+            old_primary
+                .primary_write(index_titled("b", "b from node-1"))
+                .await
+                .unwrap();
+            old_primary.primary_write(index("c")).await.unwrap();
+            cluster.set_down(2, false);
+            cluster.set_down(3, false);
+            cluster.state.assign_shard(promoted.clone());
+        };
+        let write_primary = || async {
+            cluster.set_down(1, true);
+            let primary = cluster.replicator(2);
+            primary
+                .primary_write(index_titled("b", "b from node-2"))
+                .await
+                .unwrap();
+            primary.primary_write(index("d")).await.unwrap();
+            cluster.set_down(1, false);
+        };
+
+        if local_writes_last {
+            write_primary().await;
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            write_local().await;
+        } else {
+            write_local().await;
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            write_primary().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_is_replicated_in_sequence() {
+        let cluster = make_cluster(3, 100).await;
+        let primary = cluster.replicator(1);
+
+        let first = primary.primary_write(index("a")).await.unwrap();
+        let second = primary.primary_write(index("b")).await.unwrap();
+        assert_eq!((first.seq_no, second.seq_no), (1, 2));
+        assert_eq!(second.acks, 3);
+        assert_eq!(second.required, 3);
+        assert_eq!(second.in_sync_replicas, vec!["node-2", "node-3"]);
+
+        for node in 1..=3 {
+            assert!(cluster.has_doc(node, "a").await);
+            assert!(cluster.has_doc(node, "b").await);
+            assert_eq!(cluster.replicator(node).checkpoint(SHARD).checkpoint, 2);
+        }
+        let shard = cluster.state.get_shard(SHARD).unwrap();
+        assert_eq!(shard.in_sync_replicas, vec!["node-2", "node-3"]);
+
+        let delete = RpcPrimaryWrite {
+            op: RpcWriteOp::Delete(vec!["a".to_string()]),
+            ..index("a")
+        };
+        primary.primary_write(delete).await.unwrap();
+        assert!(!cluster.has_doc(3, "a").await);
+    }
+
+    #[tokio::test]
+    async fn test_write_waits_for_required_copies() {
+        let cluster = make_cluster(2, 100).await;
+        let primary = cluster.replicator(1);
+        cluster.set_down(3, true);
+
+        // Two of three copies satisfy min_replicas_for_write: 2
+        let ack = primary.primary_write(index("a")).await.unwrap();
+        assert_eq!(ack.acks, 2);
+        assert_eq!(ack.in_sync_replicas, vec!["node-2"]);
+        assert!(!cluster.has_doc(3, "a").await);
+
+        cluster.set_down(2, true);
+        let err = primary.primary_write(index("b")).await.unwrap_err();
+        assert_eq!(err.error_type(), "replication");
+        assert!(cluster
+            .state
+            .get_shard(SHARD)
+            .unwrap()
+            .in_sync_replicas
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replica_catches_up_from_primary_log() {
+        let cluster = make_cluster(1, 100).await;
+        let primary = cluster.replicator(1);
+
+        primary.primary_write(index("a")).await.unwrap();
+        cluster.set_down(3, true);
+        primary.primary_write(index("b")).await.unwrap();
+        primary.primary_write(index("c")).await.unwrap();
+        cluster.set_down(3, false);
+
+        // A restarted replica pulls what it missed
+        assert!(cluster.replicator(3).catch_up_replicas().await.is_empty());
+        assert!(cluster.has_doc(3, "b").await);
+        assert!(cluster.has_doc(3, "c").await);
+        assert_eq!(cluster.replicator(3).checkpoint(SHARD).checkpoint, 3);
+
+        // A replica that missed a write catches up when the next one arrives
+        cluster.set_down(2, true);
+        primary.primary_write(index("d")).await.unwrap();
+        cluster.set_down(2, false);
+        let ack = primary.primary_write(index("e")).await.unwrap();
+        assert_eq!(ack.acks, 3);
+        assert_eq!(ack.in_sync_replicas, vec!["node-2", "node-3"]);
+        assert!(cluster.has_doc(2, "d").await);
+    }
+
+    #[tokio::test]
+    async fn test_restarted_primary_serves_lagging_replica() {
+        let cluster = make_cluster(1, 100).await;
+
+        cluster.set_down(3, true);
+        for id in ["a", "b", "c"] {
+            cluster
+                .replicator(1)
+                .primary_write(index(id))
+                .await
+                .unwrap();
+        }
+        cluster.start(1);
+        cluster.set_down(3, false);
+
+        // The restarted primary still has the writes node 3 missed
+        assert!(cluster.replicator(3).catch_up_replicas().await.is_empty());
+        assert!(cluster.has_doc(3, "c").await);
+        assert_eq!(cluster.replicator(3).checkpoint(SHARD).checkpoint, 3);
+
+        let ack = cluster
+            .replicator(1)
+            .primary_write(index("d"))
+            .await
+            .unwrap();
+        assert_eq!(ack.seq_no, 4);
+        assert_eq!(ack.acks, 3);
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_fails_beyond_log_retention() {
+        let cluster = make_cluster(1, 2).await;
+        let primary = cluster.replicator(1);
+
+        cluster.set_down(3, true);
+        for id in ["a", "b", "c"] {
+            primary.primary_write(index(id)).await.unwrap();
+        }
+        cluster.set_down(3, false);
+
+        let failures = cluster.replicator(3).catch_up_replicas().await;
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].1.error_type(), "replication");
+        assert_eq!(cluster.replicator(3).checkpoint(SHARD).checkpoint, 0);
+    }
+
+    #[tokio::test]
+    async fn test_writes_rejected_off_primary() {
+        let cluster = make_cluster(1, 100).await;
+        let err = cluster
+            .replicator(2)
+            .primary_write(index("a"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.error_type(), "replication");
+
+        // A write from an older primary term is refused
+        let replica = cluster.replicator(2);
+        cluster
+            .replicator(1)
+            .primary_write(index("a"))
+            .await
+            .unwrap();
+        let stale = RpcReplicaWrite {
+            shard_id: SHARD.to_string(),
+            collection: "products".to_string(),
+            primary_node: "node-1".to_string(),
+            primary_term: 0,
+            seq_no: 2,
+            timestamp: 0,
+            op: RpcWriteOp::Delete(vec!["a".to_string()]),
+        };
+        assert!(replica.replica_write(stale).await.is_err());
+        assert!(cluster.has_doc(2, "a").await);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_resolves_divergent_writes() {
+        let cluster = make_cluster(1, 100).await;
+        diverge(&cluster, false).await;
+
+        let reconciliation = cluster
+            .replicator(1)
+            .reconcile(ConflictResolution::LastWriteWins)
+            .await;
+        assert!(reconciliation.failures.is_empty());
+        assert_eq!(reconciliation.diverged_shards, vec![SHARD]);
+        // `c` was only written on node 1 and is sent to the new primary
+        assert_eq!(reconciliation.forwarded_writes, 1);
+
+        // `b` was written on both sides; node 2's write is the later one
+        assert_eq!(reconciliation.conflicts.len(), 1);
+        let conflict = &reconciliation.conflicts[0];
+        assert_eq!(conflict.document_id, "b");
+        assert_eq!(conflict.winner, RpcConflictWinner::Primary);
+        assert_eq!(
+            conflict.local_document.as_ref().unwrap().fields["title"],
+            "b from node-1"
+        );
+        assert_eq!(cluster.replicator(1).conflict_report().len(), 1);
+
+        for node in 1..=3 {
+            assert_eq!(
+                cluster.title(node, "b").await.as_deref(),
+                Some("b from node-2")
+            );
+            assert!(cluster.has_doc(node, "c").await);
+            assert!(cluster.has_doc(node, "d").await);
+        }
+        let checkpoint = cluster.replicator(1).checkpoint(SHARD);
+        assert_eq!(checkpoint, cluster.replicator(2).checkpoint(SHARD));
+        assert_eq!((checkpoint.primary_term, checkpoint.checkpoint), (2, 3));
+
+        // Reconciling again finds nothing left to do
+        let again = cluster
+            .replicator(1)
+            .reconcile(ConflictResolution::LastWriteWins)
+            .await;
+        assert!(again.diverged_shards.is_empty());
+        assert!(again.failures.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_keeps_later_local_write() {
+        let cluster = make_cluster(1, 100).await;
+        diverge(&cluster, true).await;
+
+        let reconciliation = cluster
+            .replicator(1)
+            .reconcile(ConflictResolution::LastWriteWins)
+            .await;
+        assert!(reconciliation.failures.is_empty());
+        assert_eq!(reconciliation.conflicts[0].winner, RpcConflictWinner::Local);
+        assert_eq!(reconciliation.forwarded_writes, 2);
+        for node in 1..=3 {
+            assert_eq!(
+                cluster.title(node, "b").await.as_deref(),
+                Some("b from node-1")
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_manual_keeps_primary_and_reports() {
+        let cluster = make_cluster(1, 100).await;
+        diverge(&cluster, true).await;
+
+        let reconciliation = cluster
+            .replicator(1)
+            .reconcile(ConflictResolution::Manual)
+            .await;
+        let conflict = &reconciliation.conflicts[0];
+        assert_eq!(conflict.resolution, ConflictResolution::Manual);
+        assert_eq!(conflict.winner, RpcConflictWinner::Primary);
+        assert_eq!(conflict.local_version.primary_term, 1);
+        assert_eq!(conflict.primary_version.primary_term, 2);
+        assert_eq!(
+            cluster.title(1, "b").await.as_deref(),
+            Some("b from node-2")
+        );
+        assert!(cluster.has_doc(3, "c").await);
+    }
+}
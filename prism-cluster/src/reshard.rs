@@ -0,0 +1,725 @@
+//! Splitting and shrinking the shards of a collection across the cluster
+//!
+//! Documents are routed to a shard by hashing their ID modulo the shard
+//! count. Splitting into a multiple of the count keeps every document on a
+//! node that already holds it, since new shard `t` is a subset of old shard
+//! `t % from`: a split only re-labels assignments, much like hard-linking
+//! segments. Shrinking to a divisor of the count folds every shard `s` into
+//! shard `s % to`, copying its documents to the nodes of that shard which do
+//! not hold them yet.
+//!
+//! The source shards stay read-only while documents are copied, then one
+//! metadata change swaps the new assignments in for the old ones.
+
+use crate::error::{ClusterError, Result};
+use crate::federation::QueryRouter;
+use crate::metadata::{MetadataCommand, RaftNode, RaftRole};
+use crate::metrics::update_cluster_state_metrics;
+use crate::placement::{ClusterState, ShardAssignment, ShardState};
+use crate::replication::ClientTransport;
+use crate::types::{RpcDocument, RpcShardScan, RpcShardScanRequest};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use prism::collection::CollectionManager;
+use prism::ilm::ShardShrinker;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// Documents read from a source shard per scan request
+const SCAN_BATCH_SIZE: usize = 500;
+
+/// Whether a collection gets more or fewer shards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReshardKind {
+    /// Divide every shard into several
+    Split,
+    /// Fold several shards into one
+    Shrink,
+}
+
+impl ReshardKind {
+    /// Check that documents can move from `from` to `to` shards by hashing
+    fn validate(self, from: usize, to: usize) -> Result<()> {
+        let valid = match self {
+            ReshardKind::Split => to > from && to.is_multiple_of(from),
+            ReshardKind::Shrink => to >= 1 && to < from && from.is_multiple_of(to),
+        };
+        if valid {
+            Ok(())
+        } else {
+            let need = match self {
+                ReshardKind::Split => "a multiple of",
+                ReshardKind::Shrink => "a divisor of",
+            };
+            Err(ClusterError::InvalidQuery(format!(
+                "Cannot {:?} {} shards into {}: the new count must be {} the current one",
+                self, from, to, need
+            )))
+        }
+    }
+}
+
+/// A source shard folded into a target shard while shrinking
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardFold {
+    /// Number of the source shard
+    pub source_shard: u32,
+    /// Node the source shard's documents are read from
+    pub source_node: String,
+    /// Number of the target shard
+    pub target_shard: u32,
+    /// Nodes of the target shard that do not hold the source shard
+    pub copy_to: Vec<String>,
+    /// Nodes of the source shard that do not hold the target shard, whose
+    /// copies of the documents are removed after the swap
+    pub remove_from: Vec<String>,
+}
+
+/// How a collection's shards are replaced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReshardPlan {
+    pub collection: String,
+    pub kind: ReshardKind,
+    pub from_shards: usize,
+    pub to_shards: usize,
+    /// Assignments replacing the current ones
+    pub targets: Vec<ShardAssignment>,
+    /// Source shards whose documents move to other nodes
+    pub folds: Vec<ShardFold>,
+}
+
+impl ReshardPlan {
+    /// Plan a split or shrink of the given shards.
+    ///
+    /// Every target shard stays on the nodes of a source shard holding its
+    /// documents, so only folded shards need copying.
+    pub fn new(
+        collection: &str,
+        sources: &[ShardAssignment],
+        kind: ReshardKind,
+        to_shards: usize,
+    ) -> Result<Self> {
+        if sources.is_empty() {
+            return Err(ClusterError::CollectionNotFound(collection.to_string()));
+        }
+        let mut sources = sources.to_vec();
+        sources.sort_by_key(|s| s.shard_number);
+        let from_shards = sources.len();
+        if sources
+            .iter()
+            .enumerate()
+            .any(|(n, s)| s.shard_number as usize != n)
+        {
+            return Err(ClusterError::Internal(format!(
+                "Shards of {} are not numbered 0..{}",
+                collection, from_shards
+            )));
+        }
+        if let Some(shard) = sources.iter().find(|s| s.state != ShardState::Active) {
+            return Err(ClusterError::InvalidQuery(format!(
+                "Shard {} is {:?}, resharding needs every shard active",
+                shard.shard_id, shard.state
+            )));
+        }
+        kind.validate(from_shards, to_shards)?;
+
+        let targets = (0..to_shards)
+            .map(|t| {
+                let base = &sources[t % from_shards];
+                let mut target = ShardAssignment::new(collection, t as u32, &base.primary_node);
+                target.replica_nodes = base.replica_nodes.clone();
+                target.in_sync_replicas = base.in_sync_replicas.clone();
+                target.state = ShardState::Active;
+                target
+            })
+            .collect::<Vec<_>>();
+
+        let mut folds = Vec::new();
+        if kind == ReshardKind::Shrink {
+            for source in sources.iter().skip(to_shards) {
+                let target = &targets[source.shard_number as usize % to_shards];
+                let copy_to = target
+                    .all_nodes()
+                    .into_iter()
+                    .filter(|n| !source.is_on_node(n))
+                    .map(str::to_string)
+                    .collect();
+                let remove_from = source
+                    .all_nodes()
+                    .into_iter()
+                    .filter(|n| !target.is_on_node(n))
+                    .map(str::to_string)
+                    .collect();
+                folds.push(ShardFold {
+                    source_shard: source.shard_number,
+                    source_node: source.primary_node.clone(),
+                    target_shard: target.shard_number,
+                    copy_to,
+                    remove_from,
+                });
+            }
+        }
+
+        Ok(Self {
+            collection: collection.to_string(),
+            kind,
+            from_shards,
+            to_shards,
+            targets,
+            folds,
+        })
+    }
+}
+
+/// Outcome of a split or shrink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReshardReport {
+    pub collection: String,
+    pub kind: ReshardKind,
+    pub from_shards: usize,
+    pub to_shards: usize,
+    /// Documents copied to nodes of the shards they were folded into
+    pub documents_copied: usize,
+    /// IDs of the new shards
+    pub shards: Vec<String>,
+    pub duration_ms: u64,
+}
+
+/// How the resharder reaches the nodes holding a collection
+#[async_trait]
+pub trait ReshardTransport: Send + Sync {
+    /// Read a page of the documents of a shard
+    async fn scan_shard(&self, addr: &str, request: RpcShardScanRequest) -> Result<RpcShardScan>;
+
+    /// Index documents on a node
+    async fn index(&self, addr: &str, collection: &str, docs: Vec<RpcDocument>) -> Result<()>;
+
+    /// Delete documents on a node
+    async fn delete(&self, addr: &str, collection: &str, ids: Vec<String>) -> Result<()>;
+}
+
+#[async_trait]
+impl ReshardTransport for ClientTransport {
+    async fn scan_shard(&self, addr: &str, request: RpcShardScanRequest) -> Result<RpcShardScan> {
+        self.client().await?.scan_shard(addr, request).await
+    }
+
+    async fn index(&self, addr: &str, collection: &str, docs: Vec<RpcDocument>) -> Result<()> {
+        self.client().await?.index(addr, collection, docs).await
+    }
+
+    async fn delete(&self, addr: &str, collection: &str, ids: Vec<String>) -> Result<()> {
+        self.client().await?.delete(addr, collection, ids).await
+    }
+}
+
+/// Read a page of the local documents that hash to a shard
+pub async fn scan_shard(
+    manager: &CollectionManager,
+    request: &RpcShardScanRequest,
+) -> Result<RpcShardScan> {
+    let ids = manager.document_ids(&request.collection)?;
+    let mut documents = Vec::new();
+    let mut offset = request.offset;
+    while offset < ids.len() && documents.len() < request.limit.max(1) {
+        let id = &ids[offset];
+        offset += 1;
+        if QueryRouter::hash_to_shard(id, request.shard_count) != request.shard_number as usize {
+            continue;
+        }
+        if let Some(doc) = manager.get(&request.collection, id).await? {
+            documents.push(doc.into());
+        }
+    }
+    Ok(RpcShardScan {
+        documents,
+        next_offset: (offset < ids.len()).then_some(offset),
+    })
+}
+
+/// Splits and shrinks the shards of collections
+pub struct Resharder {
+    cluster_state: Arc<ClusterState>,
+    transport: Arc<dyn ReshardTransport>,
+    metadata: Option<Arc<RaftNode>>,
+    /// Collections being resharded
+    running: Mutex<HashSet<String>>,
+}
+
+impl Resharder {
+    pub fn new(cluster_state: Arc<ClusterState>, transport: Arc<dyn ReshardTransport>) -> Self {
+        Self {
+            cluster_state,
+            transport,
+            metadata: None,
+            running: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Change assignments through the replicated metadata log
+    pub fn with_metadata(mut self, metadata: Arc<RaftNode>) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Split every shard of a collection, for `num_shards` shards in total
+    pub async fn split(&self, collection: &str, num_shards: usize) -> Result<ReshardReport> {
+        self.reshard(collection, ReshardKind::Split, num_shards)
+            .await
+    }
+
+    /// Fold the shards of a collection into `num_shards` shards
+    pub async fn shrink(&self, collection: &str, num_shards: usize) -> Result<ReshardReport> {
+        self.reshard(collection, ReshardKind::Shrink, num_shards)
+            .await
+    }
+
+    /// Whether a collection is being resharded
+    pub fn is_resharding(&self, collection: &str) -> bool {
+        self.running.lock().contains(collection)
+    }
+
+    async fn reshard(
+        &self,
+        collection: &str,
+        kind: ReshardKind,
+        num_shards: usize,
+    ) -> Result<ReshardReport> {
+        let sources = self.cluster_state.get_collection_shards(collection);
+        let plan = ReshardPlan::new(collection, &sources, kind, num_shards)?;
+        if !self.running.lock().insert(collection.to_string()) {
+            return Err(ClusterError::InvalidQuery(format!(
+                "Collection {} is already being resharded",
+                collection
+            )));
+        }
+
+        let result = self.execute(&plan, &sources).await;
+        self.running.lock().remove(collection);
+        result
+    }
+
+    /// Copy documents with the sources read-only, then swap the assignments.
+    ///
+    /// On failure the sources become writable again; documents already
+    /// copied stay on the target nodes and are copied again on retry.
+    async fn execute(
+        &self,
+        plan: &ReshardPlan,
+        sources: &[ShardAssignment],
+    ) -> Result<ReshardReport> {
+        let started = Instant::now();
+        info!(
+            "Resharding {}: {:?} {} shards into {}",
+            plan.collection, plan.kind, plan.from_shards, plan.to_shards
+        );
+        self.set_states(sources, ShardState::Resharding).await?;
+
+        let mut copied = Vec::with_capacity(plan.folds.len());
+        for fold in &plan.folds {
+            match self.copy_fold(plan, fold).await {
+                Ok(ids) => copied.push(ids),
+                Err(e) => {
+                    warn!(
+                        "Resharding {} failed copying shard {}: {}",
+                        plan.collection, fold.source_shard, e
+                    );
+                    if let Err(e) = self.set_states(sources, ShardState::Active).await {
+                        warn!("Failed to reactivate shards of {}: {}", plan.collection, e);
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        let swap = MetadataCommand::ReplaceShards {
+            collection: plan.collection.clone(),
+            assignments: plan.targets.clone(),
+        };
+        if let Err(e) = self.write(swap).await {
+            if let Err(e) = self.set_states(sources, ShardState::Active).await {
+                warn!("Failed to reactivate shards of {}: {}", plan.collection, e);
+            }
+            return Err(e);
+        }
+
+        // Copies left on nodes that no longer hold the shard would show up
+        // in searches of the shards those nodes still hold
+        for (fold, ids) in plan.folds.iter().zip(&copied) {
+            if ids.is_empty() {
+                continue;
+            }
+            for node in &fold.remove_from {
+                let result = match self.address(node) {
+                    Ok(addr) => {
+                        self.transport
+                            .delete(&addr, &plan.collection, ids.clone())
+                            .await
+                    }
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    warn!(
+                        "Failed to remove moved documents of {} from {}: {}",
+                        fold.source_shard, node, e
+                    );
+                }
+            }
+        }
+
+        let report = ReshardReport {
+            collection: plan.collection.clone(),
+            kind: plan.kind,
+            from_shards: plan.from_shards,
+            to_shards: plan.to_shards,
+            documents_copied: copied.iter().map(Vec::len).sum(),
+            shards: plan.targets.iter().map(|t| t.shard_id.clone()).collect(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        info!(
+            "Resharded {} into {} shards, {} documents copied in {}ms",
+            report.collection, report.to_shards, report.documents_copied, report.duration_ms
+        );
+        Ok(report)
+    }
+
+    /// Copy the documents of a folded shard, returning their IDs
+    async fn copy_fold(&self, plan: &ReshardPlan, fold: &ShardFold) -> Result<Vec<String>> {
+        let source = self.address(&fold.source_node)?;
+        let targets = fold
+            .copy_to
+            .iter()
+            .map(|node| self.address(node))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut ids = Vec::new();
+        let mut offset = Some(0);
+        while let Some(from) = offset {
+            let page = self
+                .transport
+                .scan_shard(
+                    &source,
+                    RpcShardScanRequest {
+                        collection: plan.collection.clone(),
+                        shard_number: fold.source_shard,
+                        shard_count: plan.from_shards,
+                        offset: from,
+                        limit: SCAN_BATCH_SIZE,
+                    },
+                )
+                .await?;
+            offset = page.next_offset;
+            if page.documents.is_empty() {
+                continue;
+            }
+            for addr in &targets {
+                self.transport
+                    .index(addr, &plan.collection, page.documents.clone())
+                    .await?;
+            }
+            ids.extend(page.documents.into_iter().map(|doc| doc.id));
+        }
+        Ok(ids)
+    }
+
+    async fn set_states(&self, shards: &[ShardAssignment], state: ShardState) -> Result<()> {
+        for shard in shards {
+            self.write(MetadataCommand::UpdateShardState {
+                shard_id: shard.shard_id.clone(),
+                state,
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn write(&self, command: MetadataCommand) -> Result<()> {
+        match &self.metadata {
+            Some(metadata) => {
+                metadata.write(command).await?;
+            }
+            None => match command {
+                MetadataCommand::UpdateShardState { shard_id, state } => {
+                    self.cluster_state.update_shard_state(&shard_id, state);
+                }
+                MetadataCommand::ReplaceShards {
+                    collection,
+                    assignments,
+                } => {
+                    self.cluster_state
+                        .replace_collection_shards(&collection, assignments);
+                }
+                other => {
+                    return Err(ClusterError::Internal(format!(
+                        "Unexpected metadata change while resharding: {:?}",
+                        other
+                    )))
+                }
+            },
+        }
+        update_cluster_state_metrics(&self.cluster_state);
+        Ok(())
+    }
+
+    fn address(&self, node_id: &str) -> Result<String> {
+        self.cluster_state
+            .get_node(node_id)
+            .map(|node| node.info.address)
+            .ok_or_else(|| ClusterError::NodeUnavailable(node_id.to_string()))
+    }
+}
+
+/// The ILM shrink action on a cluster shrinks the cluster shards, run by
+/// the metadata leader only so nodes do not race each other
+#[async_trait]
+impl ShardShrinker for Resharder {
+    async fn shrink(&self, collection: &str, num_shards: usize) -> prism::Result<bool> {
+        if let Some(metadata) = &self.metadata {
+            if metadata.status().await.role != RaftRole::Leader {
+                return Ok(false);
+            }
+        }
+        let current = self.cluster_state.get_collection_shards(collection).len();
+        if current <= num_shards {
+            return Ok(false);
+        }
+        Resharder::shrink(self, collection, num_shards)
+            .await
+            .map(|_| true)
+            .map_err(|e| prism::Error::Backend(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NodeTopology;
+    use crate::placement::NodeInfo;
+    use prism::backends::{TextBackend, VectorBackend};
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    /// Nodes reached in-process by address
+    struct LoopbackTransport {
+        nodes: HashMap<String, Arc<CollectionManager>>,
+    }
+
+    impl LoopbackTransport {
+        fn node(&self, addr: &str) -> Result<&Arc<CollectionManager>> {
+            self.nodes
+                .get(addr)
+                .ok_or_else(|| ClusterError::NodeUnavailable(addr.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl ReshardTransport for LoopbackTransport {
+        async fn scan_shard(
+            &self,
+            addr: &str,
+            request: RpcShardScanRequest,
+        ) -> Result<RpcShardScan> {
+            scan_shard(self.node(addr)?, &request).await
+        }
+
+        async fn index(&self, addr: &str, collection: &str, docs: Vec<RpcDocument>) -> Result<()> {
+            let docs = docs
+                .into_iter()
+                .map(|d| prism::backends::Document {
+                    id: d.id,
+                    fields: d.fields,
+                })
+                .collect();
+            Ok(self.node(addr)?.index(collection, docs).await?)
+        }
+
+        async fn delete(&self, addr: &str, collection: &str, ids: Vec<String>) -> Result<()> {
+            Ok(self.node(addr)?.delete(collection, ids).await?)
+        }
+    }
+
+    struct Cluster {
+        _temp: TempDir,
+        managers: Vec<Arc<CollectionManager>>,
+        state: Arc<ClusterState>,
+        resharder: Resharder,
+    }
+
+    impl Cluster {
+        fn node_ids(&self, node: usize) -> Vec<String> {
+            self.managers[node - 1].document_ids("products").unwrap()
+        }
+
+        fn shards(&self) -> Vec<ShardAssignment> {
+            let mut shards = self.state.get_collection_shards("products");
+            shards.sort_by_key(|s| s.shard_number);
+            shards
+        }
+    }
+
+    /// Three nodes, each the only holder of one shard of "products", with
+    /// documents indexed on the node of the shard they hash to
+    async fn make_cluster(docs: usize) -> Cluster {
+        let temp = TempDir::new().unwrap();
+        let state = Arc::new(ClusterState::new());
+        let mut nodes = HashMap::new();
+        let mut managers = Vec::new();
+
+        for node in 1..=3 {
+            let dir = temp.path().join(format!("node-{}", node));
+            let schemas_dir = dir.join("schemas");
+            std::fs::create_dir_all(&schemas_dir).unwrap();
+            std::fs::write(
+                schemas_dir.join("products.yaml"),
+                r#"
+collection: products
+backends:
+  text:
+    fields:
+      - name: title
+        type: text
+        indexed: true
+        stored: true
+"#,
+            )
+            .unwrap();
+            let text_backend = Arc::new(TextBackend::new(dir.join("data")).unwrap());
+            let vector_backend = Arc::new(VectorBackend::new(dir.join("data")).unwrap());
+            let manager = Arc::new(
+                CollectionManager::new(&schemas_dir, text_backend, vector_backend, None).unwrap(),
+            );
+            manager.initialize().await.unwrap();
+
+            let node_id = format!("node-{}", node);
+            state.register_node(NodeInfo {
+                node_id: node_id.clone(),
+                address: format!("{}:9080", node_id),
+                topology: NodeTopology::default(),
+                healthy: true,
+                shard_count: 0,
+                disk_used_bytes: 0,
+                disk_total_bytes: 0,
+                index_size_bytes: 0,
+                draining: false,
+            });
+            let mut shard = ShardAssignment::new("products", node as u32 - 1, &node_id);
+            shard.state = ShardState::Active;
+            state.assign_shard(shard);
+            nodes.insert(format!("{}:9080", node_id), Arc::clone(&manager));
+            managers.push(manager);
+        }
+
+        for i in 0..docs {
+            let id = format!("doc-{}", i);
+            let node = QueryRouter::hash_to_shard(&id, 3);
+            managers[node]
+                .index(
+                    "products",
+                    vec![prism::backends::Document {
+                        id,
+                        fields: HashMap::from([(
+                            "title".to_string(),
+                            serde_json::json!(format!("Product {}", i)),
+                        )]),
+                    }],
+                )
+                .await
+                .unwrap();
+        }
+
+        let resharder = Resharder::new(
+            Arc::clone(&state),
+            Arc::new(LoopbackTransport { nodes }) as Arc<dyn ReshardTransport>,
+        );
+        Cluster {
+            _temp: temp,
+            managers,
+            state,
+            resharder,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plan_split_keeps_documents_in_place() {
+        let cluster = make_cluster(0).await;
+        let plan = ReshardPlan::new("products", &cluster.shards(), ReshardKind::Split, 6).unwrap();
+
+        assert!(plan.folds.is_empty());
+        assert_eq!(plan.targets.len(), 6);
+        for target in &plan.targets {
+            assert_eq!(
+                target.primary_node,
+                format!("node-{}", target.shard_number % 3 + 1)
+            );
+        }
+
+        assert!(ReshardPlan::new("products", &cluster.shards(), ReshardKind::Split, 4).is_err());
+        assert!(ReshardPlan::new("products", &cluster.shards(), ReshardKind::Shrink, 2).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_split_routes_documents_to_new_shards() {
+        let cluster = make_cluster(30).await;
+        let report = cluster.resharder.split("products", 6).await.unwrap();
+        assert_eq!(report.documents_copied, 0);
+
+        let shards = cluster.shards();
+        assert_eq!(shards.len(), 6);
+        assert!(shards.iter().all(|s| s.state == ShardState::Active));
+        assert!(shards.iter().all(|s| s.epoch == shards[0].epoch));
+
+        // Every document still lives on the node of the shard it hashes to
+        for i in 0..30 {
+            let id = format!("doc-{}", i);
+            let shard = &shards[QueryRouter::hash_to_shard(&id, 6)];
+            let node: usize = shard.primary_node["node-".len()..].parse().unwrap();
+            assert!(cluster.node_ids(node).contains(&id));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shrink_folds_documents_into_target_shard() {
+        let cluster = make_cluster(30).await;
+        let local = cluster.node_ids(1).len();
+        let report = cluster.resharder.shrink("products", 1).await.unwrap();
+
+        let shards = cluster.shards();
+        assert_eq!(shards.len(), 1);
+        assert_eq!(shards[0].primary_node, "node-1");
+        assert_eq!(shards[0].state, ShardState::Active);
+        assert_eq!(cluster.node_ids(1).len(), 30);
+        assert_eq!(report.documents_copied, 30 - local);
+
+        // Folded copies were removed from the nodes that lost their shard
+        assert!(cluster.node_ids(2).is_empty());
+        assert!(cluster.node_ids(3).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_copy_reactivates_sources() {
+        let cluster = make_cluster(10).await;
+        cluster.state.remove_node("node-2");
+
+        assert!(cluster.resharder.shrink("products", 1).await.is_err());
+        let shards = cluster.shards();
+        assert_eq!(shards.len(), 3);
+        assert!(shards.iter().all(|s| s.state == ShardState::Active));
+        assert!(!cluster.resharder.is_resharding("products"));
+    }
+
+    #[tokio::test]
+    async fn test_ilm_shrink_skips_collections_already_small_enough() {
+        let cluster = make_cluster(0).await;
+        assert!(!ShardShrinker::shrink(&cluster.resharder, "products", 3)
+            .await
+            .unwrap());
+        assert!(ShardShrinker::shrink(&cluster.resharder, "products", 1)
+            .await
+            .unwrap());
+        assert_eq!(cluster.shards().len(), 1);
+    }
+}
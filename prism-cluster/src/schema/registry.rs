@@ -9,6 +9,7 @@ use crate::error::ClusterError;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
@@ -46,6 +47,25 @@ impl SchemaRegistry {
         &self,
         collection: &str,
         schema: serde_json::Value,
+    ) -> Result<VersionedSchema, ClusterError> {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.register_as(collection, schema, &self.node_id, created_at)
+            .await
+    }
+
+    /// Register a new schema version created by another node.
+    ///
+    /// Registering the same schemas in the same order yields the same
+    /// versions on every node, which the replicated metadata log relies on.
+    pub async fn register_as(
+        &self,
+        collection: &str,
+        schema: serde_json::Value,
+        created_by: &str,
+        created_at: u64,
     ) -> Result<VersionedSchema, ClusterError> {
         let mut schemas = self.schemas.write().await;
         let mut history = self.history.write().await;
@@ -60,8 +80,9 @@ impl SchemaRegistry {
         };
 
         // Create versioned schema
-        let versioned =
-            VersionedSchema::new(collection, version, schema, &self.node_id).with_changes(changes);
+        let mut versioned =
+            VersionedSchema::new(collection, version, schema, created_by).with_changes(changes);
+        versioned.created_at = created_at;
 
         // Store in current schemas
         schemas.insert(collection.to_string(), versioned.clone());
@@ -228,6 +249,21 @@ impl SchemaRegistry {
         info!("Restored schema registry from snapshot");
     }
 
+    /// Replace every schema with those of a snapshot, dropping history
+    pub async fn replace(&self, snapshot: SchemaRegistrySnapshot) {
+        let mut schemas = self.schemas.write().await;
+        let mut history = self.history.write().await;
+
+        history.clear();
+        for (collection, versioned) in &snapshot.schemas {
+            history
+                .entry(collection.clone())
+                .or_default()
+                .insert(versioned.version.version(), versioned.clone());
+        }
+        *schemas = snapshot.schemas;
+    }
+
     /// Remove a collection's schema
     pub async fn remove(&self, collection: &str) -> Option<VersionedSchema> {
         let mut schemas = self.schemas.write().await;
@@ -677,4 +713,48 @@ mod tests {
         let current = registry.get("col1").await.unwrap();
         assert_eq!(current.version, SchemaVersion::new(3));
     }
+
+    // --- replicated registration ---
+
+    #[tokio::test]
+    async fn test_register_as_is_deterministic() {
+        let node1 = SchemaRegistry::new("node-1");
+        let node2 = SchemaRegistry::new("node-2");
+        for registry in [&node1, &node2] {
+            registry
+                .register_as("col1", json!({"v": 1}), "node-3", 1000)
+                .await
+                .unwrap();
+            registry
+                .register_as("col1", json!({"v": 2}), "node-3", 2000)
+                .await
+                .unwrap();
+        }
+
+        let a = node1.get("col1").await.unwrap();
+        let b = node2.get("col1").await.unwrap();
+        assert_eq!(a.version, SchemaVersion::new(2));
+        assert_eq!(a.version, b.version);
+        assert_eq!(a.created_by, "node-3");
+        assert_eq!(b.created_at, 2000);
+    }
+
+    #[tokio::test]
+    async fn test_replace_downgrades_and_drops_history() {
+        let registry = SchemaRegistry::new("node-1");
+        registry.register("col1", json!({"v": 1})).await.unwrap();
+        registry.register("col1", json!({"v": 2})).await.unwrap();
+        registry.register("col2", json!({"v": 1})).await.unwrap();
+
+        let other = SchemaRegistry::new("node-2");
+        other.register("col1", json!({"v": 1})).await.unwrap();
+        registry.replace(other.snapshot().await).await;
+
+        assert_eq!(
+            registry.get_version("col1").await,
+            Some(SchemaVersion::new(1))
+        );
+        assert!(registry.get("col2").await.is_none());
+        assert_eq!(registry.get_history("col1").await.len(), 1);
+    }
 }
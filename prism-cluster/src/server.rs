@@ -2,26 +2,45 @@
 //!
 //! Wraps CollectionManager to serve cluster RPC requests.
 
-use crate::config::ClusterConfig;
+use crate::ccr::leader_shards;
+use crate::client::ClusterClient;
+use crate::config::{ClusterConfig, NodeTopology};
+use crate::discovery::{ClusterEvent, NodeDiscovery};
 use crate::error::ClusterError;
+use crate::health::HealthChecker;
+use crate::metadata::{ClusterMetadata, MetadataCommand, RaftLog, RaftNode};
 use crate::metrics::{
     record_rebalance_operation, update_cluster_state_metrics, update_rebalance_status_metrics,
     RpcHandlerTimer,
 };
+use crate::partition::PartitionDetector;
 use crate::placement::{ClusterState, PlacementStrategy, ShardAssignment, ShardState};
-use crate::rebalance::{RebalanceEngine, RebalanceTrigger};
+use crate::protocol::{local_handshake, negotiate};
+use crate::rebalance::{RebalanceEngine, RebalanceStatus, RebalanceTrigger};
+use crate::replication::{ClientTransport, ConflictReport, ReplicationCheckpoints, Replicator};
+use crate::reshard::{scan_shard, Resharder};
+use crate::schema::SchemaRegistry;
 use crate::service::PrismCluster;
+use crate::transfer::{
+    CapturedWrite, RemoteTarget, ShardReceiver, ShardSender, ShardTransfers, TransferJob,
+};
 use crate::transport::make_server_endpoint;
 use crate::types::*;
 use futures::StreamExt;
 use prism::collection::CollectionManager;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tarpc::context::Context;
 use tarpc::server::{BaseChannel, Channel};
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
+/// Attempts at catching replicas up after startup, while primaries come up
+const CATCH_UP_ATTEMPTS: u32 = 5;
+
+/// Delay before the next catch-up attempt, multiplied by the attempt number
+const CATCH_UP_RETRY_DELAY: Duration = Duration::from_secs(2);
+
 /// Cluster RPC server that wraps CollectionManager
 pub struct ClusterServer {
     config: ClusterConfig,
@@ -29,26 +48,19 @@ pub struct ClusterServer {
     start_time: Instant,
     cluster_state: Arc<ClusterState>,
     rebalance_engine: Arc<RebalanceEngine>,
+    transfers: Arc<ShardTransfers>,
+    receiver: Arc<ShardReceiver>,
+    replicator: Arc<Replicator>,
+    health_checker: Arc<HealthChecker>,
+    partition_detector: Arc<PartitionDetector>,
+    metadata: Arc<RaftNode>,
+    resharder: Arc<Resharder>,
 }
 
 impl ClusterServer {
     /// Create a new cluster server
     pub fn new(config: ClusterConfig, manager: Arc<CollectionManager>) -> Self {
-        let cluster_state = Arc::new(ClusterState::new());
-        let strategy = PlacementStrategy::default();
-        let rebalance_engine = Arc::new(RebalanceEngine::new(
-            config.rebalancing.clone(),
-            Arc::clone(&cluster_state),
-            strategy,
-        ));
-
-        Self {
-            config,
-            manager,
-            start_time: Instant::now(),
-            cluster_state,
-            rebalance_engine,
-        }
+        Self::with_state(config, manager, Arc::new(ClusterState::new()))
     }
 
     /// Create a new cluster server with existing state
@@ -64,12 +76,95 @@ impl ClusterServer {
             strategy,
         ));
 
+        let staging_dir = config
+            .rebalancing
+            .transfer_staging_dir
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join("prism-shard-transfers"))
+            .join(&config.node_id);
+        let receiver = Arc::new(ShardReceiver::new(Arc::clone(&manager), staging_dir));
+
+        let state_dir = config
+            .op_log
+            .state_dir
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join("prism-replication"))
+            .join(&config.node_id);
+        let checkpoints = ReplicationCheckpoints::load(&state_dir).unwrap_or_else(|e| {
+            warn!(
+                "Failed to load replication checkpoints from {}: {}",
+                state_dir.display(),
+                e
+            );
+            ReplicationCheckpoints::in_memory()
+        });
+        let transfers = Arc::new(ShardTransfers::new());
+        let transport = Arc::new(ClientTransport::new(config.clone()));
+        let replicator = Arc::new(Replicator::new(
+            config.node_id.clone(),
+            Arc::clone(&manager),
+            Arc::clone(&cluster_state),
+            Arc::clone(&transfers),
+            Arc::clone(&transport) as _,
+            checkpoints,
+            &config.op_log,
+        ));
+
+        let metadata_dir = config
+            .metadata
+            .state_dir
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join("prism-metadata"))
+            .join(&config.node_id);
+        let log = RaftLog::load(&metadata_dir).unwrap_or_else(|e| {
+            warn!(
+                "Failed to load metadata log from {}: {}",
+                metadata_dir.display(),
+                e
+            );
+            RaftLog::in_memory()
+        });
+        let metadata = Arc::new(RaftNode::new(
+            &config,
+            Arc::new(ClusterMetadata::new(
+                Arc::clone(&cluster_state),
+                Arc::new(SchemaRegistry::new(config.node_id.clone())),
+            )),
+            Arc::clone(&transport) as _,
+            log,
+        ));
+        let resharder = Arc::new(
+            Resharder::new(Arc::clone(&cluster_state), transport)
+                .with_metadata(Arc::clone(&metadata)),
+        );
+
+        let health_checker = Arc::new(HealthChecker::new(
+            config.health.clone(),
+            config.clone(),
+            Arc::clone(&cluster_state),
+        ));
+        let partition_detector = Arc::new(
+            PartitionDetector::new(
+                config.consistency.clone(),
+                config.clone(),
+                Arc::clone(&health_checker),
+            )
+            .with_reconciler(Arc::clone(&replicator)),
+        );
+
         Self {
             config,
             manager,
             start_time: Instant::now(),
             cluster_state,
             rebalance_engine,
+            transfers,
+            receiver,
+            replicator,
+            health_checker,
+            partition_detector,
+            metadata,
+            resharder,
         }
     }
 
@@ -83,9 +178,76 @@ impl ClusterServer {
         Arc::clone(&self.rebalance_engine)
     }
 
+    /// Get a reference to the partition detector
+    pub fn partition_detector(&self) -> Arc<PartitionDetector> {
+        Arc::clone(&self.partition_detector)
+    }
+
+    /// Get this node's member of the replicated metadata log
+    pub fn metadata(&self) -> Arc<RaftNode> {
+        Arc::clone(&self.metadata)
+    }
+
+    /// Get the splitter and shrinker of collection shards
+    pub fn resharder(&self) -> Arc<Resharder> {
+        Arc::clone(&self.resharder)
+    }
+
+    /// Get the report of conflicts resolved after partitions healed
+    pub fn conflict_report(&self) -> Arc<ConflictReport> {
+        self.replicator.conflict_report()
+    }
+
     /// Start the cluster RPC server
     pub async fn serve(self) -> crate::error::Result<()> {
         let endpoint = make_server_endpoint(&self.config).await?;
+
+        // Replicas catch up with writes they missed while down
+        let replicator = Arc::clone(&self.replicator);
+        tokio::spawn(async move {
+            for attempt in 1..=CATCH_UP_ATTEMPTS {
+                if replicator.catch_up_replicas().await.is_empty() {
+                    break;
+                }
+                if attempt < CATCH_UP_ATTEMPTS {
+                    tokio::time::sleep(CATCH_UP_RETRY_DELAY * attempt).await;
+                }
+            }
+        });
+
+        // Take part in the metadata log
+        Arc::clone(&self.metadata).start();
+
+        // Watch the other nodes; shard copies are reconciled when a
+        // partition heals
+        for node in self.cluster_state.get_nodes() {
+            self.health_checker.register_node(&node.info.node_id);
+        }
+        Arc::clone(&self.health_checker).start();
+        Arc::clone(&self.partition_detector).start();
+
+        // React to nodes joining and failing as discovery sees them
+        match self.config.create_discovery() {
+            Ok(discovery) => {
+                let events = discovery.subscribe();
+                if let Err(e) = discovery.start().await {
+                    warn!(
+                        "Failed to start {} discovery: {}",
+                        discovery.backend_name(),
+                        e
+                    );
+                }
+                tokio::spawn(Self::watch_discovery(
+                    discovery,
+                    events,
+                    Arc::clone(&self.health_checker),
+                    Arc::clone(&self.rebalance_engine),
+                    Arc::clone(&self.metadata),
+                ));
+            }
+            Err(e) => warn!("Node discovery disabled: {}", e),
+        }
+
         let server = Arc::new(RwLock::new(self));
 
         info!(
@@ -132,6 +294,68 @@ impl ClusterServer {
         Ok(())
     }
 
+    /// Pass membership changes from discovery on to health checking and
+    /// rebalancing, and register joined nodes through the metadata log.
+    ///
+    /// Owns `discovery`, which keeps running for as long as this does.
+    async fn watch_discovery(
+        discovery: Box<dyn NodeDiscovery>,
+        mut events: tokio::sync::broadcast::Receiver<ClusterEvent>,
+        health_checker: Arc<HealthChecker>,
+        rebalance_engine: Arc<RebalanceEngine>,
+        metadata: Arc<RaftNode>,
+    ) {
+        use tokio::sync::broadcast::error::RecvError;
+
+        debug!("Watching {} discovery events", discovery.backend_name());
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Missed {} discovery events", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+
+            // Nodes found by discovery join the cluster; the leader registers
+            // them so it happens once
+            if let ClusterEvent::NodeJoined(node) = &event {
+                let cluster_state = metadata.metadata().cluster_state();
+                let is_leader =
+                    metadata.status().await.leader.as_deref() == Some(metadata.address());
+                if let Some(node_id) = node
+                    .node_id
+                    .clone()
+                    .filter(|id| is_leader && cluster_state.get_node(id).is_none())
+                {
+                    let info = crate::placement::NodeInfo {
+                        node_id,
+                        address: node.address.to_string(),
+                        topology: NodeTopology {
+                            zone: node.zone.clone().unwrap_or_default(),
+                            ..Default::default()
+                        },
+                        healthy: true,
+                        shard_count: 0,
+                        disk_used_bytes: 0,
+                        disk_total_bytes: 0,
+                        index_size_bytes: 0,
+                        draining: false,
+                    };
+                    if let Err(e) = metadata.write(MetadataCommand::RegisterNode(info)).await {
+                        warn!("Failed to register discovered node: {}", e);
+                    }
+                }
+            }
+
+            health_checker.handle_discovery_event(&event);
+            if let Some(status) = rebalance_engine.handle_discovery_event(&event) {
+                update_rebalance_status_metrics(&status);
+            }
+        }
+    }
+
     /// Handle a single bidirectional stream
     async fn handle_stream(
         server: Arc<RwLock<ClusterServer>>,
@@ -180,9 +404,27 @@ impl PrismCluster for ClusterHandler {
         let server = self.server.read().await;
         let doc_count = docs.len();
         info!("RPC index: collection={}, docs={}", collection, doc_count);
+
+        // Writes during a shard transfer are replayed on the target
+        let capture = server.transfers.capture(&collection);
+        let _gate = match &capture {
+            Some(c) => match c.enter().await {
+                Ok(gate) => Some(gate),
+                Err(e) => {
+                    timer.error(e.error_type());
+                    return Err(e);
+                }
+            },
+            None => None,
+        };
+        let captured = capture.as_ref().map(|_| docs.clone());
+
         let docs: Vec<prism::backends::Document> = docs.into_iter().map(Into::into).collect();
         match server.manager.index(&collection, docs).await {
             Ok(()) => {
+                if let (Some(capture), Some(docs)) = (&capture, captured) {
+                    capture.record(CapturedWrite::Index(docs));
+                }
                 info!(
                     "RPC index OK: collection={}, docs={}",
                     collection, doc_count
@@ -203,7 +445,7 @@ impl PrismCluster for ClusterHandler {
         self,
         _ctx: Context,
         collection: String,
-        query: RpcQuery,
+        mut query: RpcQuery,
     ) -> Result<RpcSearchResults, ClusterError> {
         let timer = RpcHandlerTimer::new("search");
         let server = self.server.read().await;
@@ -211,8 +453,36 @@ impl PrismCluster for ClusterHandler {
             "RPC search: collection={}, query='{}', limit={}",
             collection, query.query_string, query.limit
         );
-        let query: prism::backends::Query = query.into();
-        match server.manager.search(&collection, query, None).await {
+        let manager = &server.manager;
+        let aggregations = std::mem::take(&mut query.aggregations);
+        let result = async {
+            let mut results = if let Some(vector) = query.query_vector.clone() {
+                search_hybrid(manager, &collection, &query, vector).await?
+            } else if let Some(statistics) = &query.term_statistics {
+                let text_query: prism::backends::Query = query.clone().into();
+                RpcSearchResults::from(manager.text_backend().search_with_statistics(
+                    &collection,
+                    &text_query,
+                    statistics,
+                )?)
+            } else {
+                let query: prism::backends::Query = query.clone().into();
+                RpcSearchResults::from(manager.search(&collection, query, None).await?)
+            };
+            if !aggregations.is_empty() {
+                // Aggregations cover every match, independent of the hits page
+                let mut agg_query: prism::backends::Query = query.into();
+                agg_query.limit = 0;
+                results.aggregations = manager
+                    .search_with_aggs(&collection, &agg_query, aggregations)
+                    .await?
+                    .aggregations;
+            }
+            Ok::<_, prism::Error>(results)
+        }
+        .await;
+
+        match result {
             Ok(results) => {
                 info!(
                     "RPC search OK: collection={}, results={}, total={}",
@@ -221,7 +491,7 @@ impl PrismCluster for ClusterHandler {
                     results.total
                 );
                 timer.success();
-                Ok(RpcSearchResults::from(results))
+                Ok(results)
             }
             Err(e) => {
                 warn!("RPC search ERROR: collection={}, error={}", collection, e);
@@ -232,6 +502,32 @@ impl PrismCluster for ClusterHandler {
         }
     }
 
+    async fn term_statistics(
+        self,
+        _ctx: Context,
+        collection: String,
+        query: RpcQuery,
+    ) -> Result<prism::backends::TermStatistics, ClusterError> {
+        let timer = RpcHandlerTimer::new("term_statistics");
+        let server = self.server.read().await;
+        let query: prism::backends::Query = query.into();
+        match server
+            .manager
+            .text_backend()
+            .term_statistics(&collection, &query)
+        {
+            Ok(statistics) => {
+                timer.success();
+                Ok(statistics)
+            }
+            Err(e) => {
+                let err = ClusterError::from(e);
+                timer.error(err.error_type());
+                Err(err)
+            }
+        }
+    }
+
     async fn get(
         self,
         _ctx: Context,
@@ -261,8 +557,25 @@ impl PrismCluster for ClusterHandler {
     ) -> Result<(), ClusterError> {
         let timer = RpcHandlerTimer::new("delete");
         let server = self.server.read().await;
+
+        let capture = server.transfers.capture(&collection);
+        let _gate = match &capture {
+            Some(c) => match c.enter().await {
+                Ok(gate) => Some(gate),
+                Err(e) => {
+                    timer.error(e.error_type());
+                    return Err(e);
+                }
+            },
+            None => None,
+        };
+        let captured = capture.as_ref().map(|_| ids.clone());
+
         match server.manager.delete(&collection, ids).await {
             Ok(()) => {
+                if let (Some(capture), Some(ids)) = (&capture, captured) {
+                    capture.record(CapturedWrite::Delete(ids));
+                }
                 timer.success();
                 Ok(())
             }
@@ -522,6 +835,7 @@ impl PrismCluster for ClusterHandler {
             dead_count,
             total_count,
             quorum_available,
+            conflicts: server.replicator.conflict_report().list(),
         }
     }
 
@@ -542,6 +856,7 @@ impl PrismCluster for ClusterHandler {
             timestamp,
             protocol_version: server.config.protocol_version,
             min_supported_version: server.config.min_supported_version,
+            features: local_handshake(&server.config).features,
         }
     }
 
@@ -549,9 +864,18 @@ impl PrismCluster for ClusterHandler {
         let timer = RpcHandlerTimer::new("drain_node");
         let server = self.server.read().await;
         let node_id = server.config.node_id.clone();
-        let result = server.cluster_state.drain_node(&node_id);
+        let result = server.cluster_state.get_node(&node_id).is_some();
         if result {
-            info!("Node {} is now draining", node_id);
+            match server.metadata.drain_node(&node_id).await {
+                Ok(moved) => info!(
+                    "Node {} is now draining, {} primaries handed off",
+                    node_id, moved
+                ),
+                Err(e) => {
+                    timer.error(e.error_type());
+                    return Err(e);
+                }
+            }
         } else {
             warn!(
                 "Failed to drain node {} (not found in cluster state)",
@@ -566,8 +890,19 @@ impl PrismCluster for ClusterHandler {
         let timer = RpcHandlerTimer::new("undrain_node");
         let server = self.server.read().await;
         let node_id = server.config.node_id.clone();
-        let result = server.cluster_state.undrain_node(&node_id);
+        let result = server.cluster_state.get_node(&node_id).is_some();
         if result {
+            if let Err(e) = server
+                .metadata
+                .write(MetadataCommand::SetDraining {
+                    node_id: node_id.clone(),
+                    draining: false,
+                })
+                .await
+            {
+                timer.error(e.error_type());
+                return Err(e);
+            }
             info!("Node {} is no longer draining", node_id);
         } else {
             warn!(
@@ -579,6 +914,31 @@ impl PrismCluster for ClusterHandler {
         Ok(result)
     }
 
+    async fn handshake(
+        self,
+        _ctx: Context,
+        peer: RpcHandshake,
+    ) -> Result<RpcHandshake, ClusterError> {
+        let timer = RpcHandlerTimer::new("handshake");
+        let server = self.server.read().await;
+        let local = local_handshake(&server.config);
+        if let Err(e) = negotiate(&local, &peer) {
+            warn!("Refusing connection from {}: {}", peer.node_id, e);
+            timer.error(e.error_type());
+            return Err(e);
+        }
+        server.cluster_state.update_node_version(
+            &peer.node_id,
+            peer.protocol_version,
+            peer.min_supported_version,
+        );
+        server
+            .cluster_state
+            .update_node_features(&peer.node_id, &peer.features);
+        timer.success();
+        Ok(local)
+    }
+
     // ========================================
     // Shard Management
     // ========================================
@@ -606,9 +966,19 @@ impl PrismCluster for ClusterHandler {
         assignment.replica_nodes = request.replica_nodes;
         assignment.state = ShardState::Initializing;
 
-        // Store in cluster state
-        server.cluster_state.assign_shard(assignment);
-        let epoch = server.cluster_state.next_epoch();
+        // Store through the metadata log
+        if let Err(e) = server
+            .metadata
+            .write(MetadataCommand::AssignShard(assignment))
+            .await
+        {
+            timer.error(e.error_type());
+            return Err(e);
+        }
+        let epoch = server
+            .cluster_state
+            .get_shard(&shard_id)
+            .map_or(0, |shard| shard.epoch);
 
         // Update cluster state metrics
         update_cluster_state_metrics(&server.cluster_state);
@@ -684,13 +1054,54 @@ impl PrismCluster for ClusterHandler {
             )));
         }
 
+        // Data is pushed from the node holding it
+        if request.from_node != server.config.node_id {
+            timer.error("invalid_query");
+            return Err(ClusterError::InvalidQuery(format!(
+                "Shard transfers must be requested on the source node {}, this is {}",
+                request.from_node, server.config.node_id
+            )));
+        }
+        if shard.is_on_node(&request.to_node) {
+            timer.error("invalid_query");
+            return Err(ClusterError::InvalidQuery(format!(
+                "Shard {} is already on node {}",
+                request.shard_id, request.to_node
+            )));
+        }
+        let target_addr = match server.cluster_state.get_node(&request.to_node) {
+            Some(node) => node.info.address,
+            None => {
+                timer.error("node_unavailable");
+                return Err(ClusterError::NodeUnavailable(request.to_node.clone()));
+            }
+        };
+
+        let (transfer_id, resumed) =
+            match server
+                .transfers
+                .start(&request.shard_id, &shard.collection, &request.to_node)
+            {
+                Ok(started) => started,
+                Err(e) => {
+                    timer.error(e.error_type());
+                    return Err(e);
+                }
+            };
+        let client = match ClusterClient::new(server.config.clone()).await {
+            Ok(client) => Arc::new(client),
+            Err(e) => {
+                server.transfers.finish(&request.shard_id, false);
+                timer.error(e.error_type());
+                return Err(e);
+            }
+        };
+
         // Mark shard as relocating
         server
             .cluster_state
             .update_shard_state(&request.shard_id, ShardState::Relocating);
 
-        let transfer_id = uuid::Uuid::new_v4().to_string();
-
         // Record shard transfer metric
         crate::metrics::record_shard_transfer(
             &request.shard_id,
@@ -702,12 +1113,31 @@ impl PrismCluster for ClusterHandler {
         update_cluster_state_metrics(&server.cluster_state);
 
         info!(
-            "Initiated shard transfer {} from {} to {}, transfer_id={}",
-            request.shard_id, request.from_node, request.to_node, transfer_id
+            "Initiated shard transfer {} from {} to {}, transfer_id={}, resumed={}",
+            request.shard_id, request.from_node, request.to_node, transfer_id, resumed
         );
 
-        // The actual transfer would be handled by a background task
-        // For now, we just return the transfer ID
+        let sender = ShardSender::new(
+            Arc::clone(&server.manager),
+            Arc::clone(&server.cluster_state),
+            Arc::clone(&server.rebalance_engine),
+            Arc::clone(&server.transfers),
+            &server.config.rebalancing,
+        )
+        .with_metadata(server.metadata());
+        let job = TransferJob {
+            transfer_id: transfer_id.clone(),
+            shard_id: request.shard_id,
+            collection: shard.collection,
+            from_node: request.from_node,
+            to_node: request.to_node,
+        };
+        tokio::spawn(async move {
+            let target = RemoteTarget::new(client, target_addr);
+            // Failures are reported through the rebalance status
+            let _ = sender.run(&job, &target).await;
+        });
+
         timer.success();
         Ok(ShardTransferResponse {
             success: true,
@@ -716,6 +1146,287 @@ impl PrismCluster for ClusterHandler {
         })
     }
 
+    async fn begin_shard_receive(
+        self,
+        _ctx: Context,
+        request: RpcBeginShardReceive,
+    ) -> Result<RpcShardReceiveState, ClusterError> {
+        let timer = RpcHandlerTimer::new("begin_shard_receive");
+        let server = self.server.read().await;
+        match server.receiver.begin(request).await {
+            Ok(state) => {
+                timer.success();
+                Ok(state)
+            }
+            Err(e) => {
+                warn!("RPC begin_shard_receive ERROR: {}", e);
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    async fn write_shard_chunk(
+        self,
+        _ctx: Context,
+        chunk: RpcShardChunk,
+    ) -> Result<u64, ClusterError> {
+        let timer = RpcHandlerTimer::new("write_shard_chunk");
+        let server = self.server.read().await;
+        match server.receiver.write_chunk(chunk).await {
+            Ok(staged) => {
+                timer.success();
+                Ok(staged)
+            }
+            Err(e) => {
+                warn!("RPC write_shard_chunk ERROR: {}", e);
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    async fn complete_shard_receive(
+        self,
+        _ctx: Context,
+        transfer_id: String,
+    ) -> Result<RpcShardInstallResult, ClusterError> {
+        let timer = RpcHandlerTimer::new("complete_shard_receive");
+        let server = self.server.read().await;
+        match server.receiver.complete(&transfer_id).await {
+            Ok(result) => {
+                timer.success();
+                Ok(result)
+            }
+            Err(e) => {
+                warn!(
+                    "RPC complete_shard_receive ERROR: transfer={}, error={}",
+                    transfer_id, e
+                );
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    async fn scan_shard(
+        self,
+        _ctx: Context,
+        request: RpcShardScanRequest,
+    ) -> Result<RpcShardScan, ClusterError> {
+        let timer = RpcHandlerTimer::new("scan_shard");
+        let server = self.server.read().await;
+        match scan_shard(&server.manager, &request).await {
+            Ok(scan) => {
+                timer.success();
+                Ok(scan)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    // ========================================
+    // Write Replication
+    // ========================================
+
+    async fn primary_write(
+        self,
+        _ctx: Context,
+        request: RpcPrimaryWrite,
+    ) -> Result<RpcWriteAck, ClusterError> {
+        let timer = RpcHandlerTimer::new("primary_write");
+        let server = self.server.read().await;
+        let shard_id = request.shard_id.clone();
+        match server.replicator.primary_write(request).await {
+            Ok(ack) => {
+                timer.success();
+                Ok(ack)
+            }
+            Err(e) => {
+                warn!("RPC primary_write ERROR: shard={}, error={}", shard_id, e);
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    async fn replica_write(
+        self,
+        _ctx: Context,
+        request: RpcReplicaWrite,
+    ) -> Result<u64, ClusterError> {
+        let timer = RpcHandlerTimer::new("replica_write");
+        let server = self.server.read().await;
+        let shard_id = request.shard_id.clone();
+        match server.replicator.replica_write(request).await {
+            Ok(checkpoint) => {
+                timer.success();
+                Ok(checkpoint)
+            }
+            Err(e) => {
+                warn!("RPC replica_write ERROR: shard={}, error={}", shard_id, e);
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    async fn get_operations(
+        self,
+        _ctx: Context,
+        request: RpcOperationsRequest,
+    ) -> Result<RpcOperations, ClusterError> {
+        let timer = RpcHandlerTimer::new("get_operations");
+        let server = self.server.read().await;
+        match server.replicator.get_operations(request) {
+            Ok(operations) => {
+                timer.success();
+                Ok(operations)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    async fn shard_checkpoint(
+        self,
+        _ctx: Context,
+        shard_id: String,
+    ) -> Result<RpcShardCheckpoint, ClusterError> {
+        let timer = RpcHandlerTimer::new("shard_checkpoint");
+        let server = self.server.read().await;
+        let checkpoint = server.replicator.shard_checkpoint(&shard_id);
+        timer.success();
+        Ok(checkpoint)
+    }
+
+    async fn get_versioned_documents(
+        self,
+        _ctx: Context,
+        request: RpcVersionedDocumentsRequest,
+    ) -> Result<Vec<RpcVersionedDocument>, ClusterError> {
+        let timer = RpcHandlerTimer::new("get_versioned_documents");
+        let server = self.server.read().await;
+        match server.replicator.get_versioned_documents(request).await {
+            Ok(documents) => {
+                timer.success();
+                Ok(documents)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    // ========================================
+    // Cross-Cluster Replication
+    // ========================================
+
+    async fn leader_shards(
+        self,
+        _ctx: Context,
+        collection: String,
+    ) -> Result<Vec<RpcLeaderShard>, ClusterError> {
+        let timer = RpcHandlerTimer::new("leader_shards");
+        let server = self.server.read().await;
+        match leader_shards(&server.cluster_state, &collection) {
+            Ok(shards) => {
+                timer.success();
+                Ok(shards)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    // ========================================
+    // Metadata Log
+    // ========================================
+
+    async fn raft_request_vote(
+        self,
+        _ctx: Context,
+        request: RpcRequestVote,
+    ) -> Result<RpcVoteResponse, ClusterError> {
+        let timer = RpcHandlerTimer::new("raft_request_vote");
+        let metadata = self.server.read().await.metadata();
+        match metadata.handle_request_vote(request).await {
+            Ok(response) => {
+                timer.success();
+                Ok(response)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    async fn raft_append_entries(
+        self,
+        _ctx: Context,
+        request: RpcAppendEntries,
+    ) -> Result<RpcAppendEntriesResponse, ClusterError> {
+        let timer = RpcHandlerTimer::new("raft_append_entries");
+        let metadata = self.server.read().await.metadata();
+        match metadata.handle_append_entries(request).await {
+            Ok(response) => {
+                timer.success();
+                Ok(response)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    async fn raft_install_snapshot(
+        self,
+        _ctx: Context,
+        request: RpcInstallSnapshot,
+    ) -> Result<u64, ClusterError> {
+        let timer = RpcHandlerTimer::new("raft_install_snapshot");
+        let metadata = self.server.read().await.metadata();
+        match metadata.handle_install_snapshot(request).await {
+            Ok(term) => {
+                timer.success();
+                Ok(term)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
+    async fn propose_metadata(
+        self,
+        _ctx: Context,
+        command: MetadataCommand,
+    ) -> Result<u64, ClusterError> {
+        let timer = RpcHandlerTimer::new("propose_metadata");
+        let metadata = self.server.read().await.metadata();
+        match metadata.propose(command).await {
+            Ok(index) => {
+                timer.success();
+                Ok(index)
+            }
+            Err(e) => {
+                timer.error(e.error_type());
+                Err(e)
+            }
+        }
+    }
+
     // ========================================
     // Rebalancing
     // ========================================
@@ -752,16 +1463,7 @@ impl PrismCluster for ClusterHandler {
         update_rebalance_status_metrics(&status);
 
         timer.success();
-        Ok(RpcRebalanceStatus {
-            in_progress: status.in_progress,
-            phase: format!("{:?}", status.phase),
-            shards_in_transit: status.shards_in_transit,
-            total_shards_to_move: status.total_shards_to_move,
-            completed_moves: status.completed_moves,
-            failed_moves: status.failed_moves,
-            started_at: status.started_at,
-            last_error: status.last_error,
-        })
+        Ok(rpc_rebalance_status(status))
     }
 
     async fn get_rebalance_status(self, _ctx: Context) -> Result<RpcRebalanceStatus, ClusterError> {
@@ -773,16 +1475,7 @@ impl PrismCluster for ClusterHandler {
         update_rebalance_status_metrics(&status);
 
         timer.success();
-        Ok(RpcRebalanceStatus {
-            in_progress: status.in_progress,
-            phase: format!("{:?}", status.phase),
-            shards_in_transit: status.shards_in_transit,
-            total_shards_to_move: status.total_shards_to_move,
-            completed_moves: status.completed_moves,
-            failed_moves: status.failed_moves,
-            started_at: status.started_at,
-            last_error: status.last_error,
-        })
+        Ok(rpc_rebalance_status(status))
     }
 
     // ========================================
@@ -795,22 +1488,42 @@ impl PrismCluster for ClusterHandler {
         request: RpcApplySchemaRequest,
     ) -> Result<RpcApplySchemaResponse, ClusterError> {
         let timer = RpcHandlerTimer::new("apply_schema");
+        let metadata = self.server.read().await.metadata();
 
-        // For now, just acknowledge the schema
-        // A full implementation would:
-        // 1. Validate the schema
-        // 2. Store it in a local schema registry
-        // 3. Possibly trigger collection re-configuration
-
+        // The metadata log numbers schema versions, so every node agrees on
+        // them; the version in the request is informational
         info!(
             "Received schema version {} for collection {} from {}",
             request.version, request.collection, request.created_by
         );
+        let collection = request.collection.clone();
+        if let Err(e) = metadata
+            .write(MetadataCommand::PutSchema {
+                collection: request.collection,
+                schema: request.schema,
+                created_by: request.created_by,
+                created_at: request.created_at,
+            })
+            .await
+        {
+            timer.error(e.error_type());
+            return Ok(RpcApplySchemaResponse {
+                applied: false,
+                current_version: request.version,
+                error: Some(e.to_string()),
+            });
+        }
+        let current_version = metadata
+            .metadata()
+            .schemas()
+            .get_version(&collection)
+            .await
+            .map_or(0, |version| version.version());
 
         timer.success();
         Ok(RpcApplySchemaResponse {
             applied: true,
-            current_version: request.version,
+            current_version,
             error: None,
         })
     }
@@ -821,16 +1534,112 @@ impl PrismCluster for ClusterHandler {
         collection: String,
     ) -> Result<Option<u64>, ClusterError> {
         let timer = RpcHandlerTimer::new("get_schema_version");
+        let metadata = self.server.read().await.metadata();
 
-        // For now, return None indicating no version is tracked locally
-        // A full implementation would check the local schema registry
         debug!("Getting schema version for collection {}", collection);
+        let version = metadata
+            .metadata()
+            .schemas()
+            .get_version(&collection)
+            .await
+            .map(|version| version.version());
 
         timer.success();
-        Ok(None)
+        Ok(version)
+    }
+}
+
+/// Convert rebalance status for RPC
+fn rpc_rebalance_status(status: RebalanceStatus) -> RpcRebalanceStatus {
+    RpcRebalanceStatus {
+        in_progress: status.in_progress,
+        phase: format!("{:?}", status.phase),
+        shards_in_transit: status.shards_in_transit,
+        total_shards_to_move: status.total_shards_to_move,
+        completed_moves: status.completed_moves,
+        failed_moves: status.failed_moves,
+        started_at: status.started_at,
+        last_error: status.last_error,
+        operations: status
+            .current_operations
+            .into_iter()
+            .map(|op| RpcRebalanceOperation {
+                shard_id: op.shard_id,
+                from_node: op.from_node,
+                to_node: op.to_node,
+                transfer_id: op.transfer_id,
+                progress: op.progress,
+                bytes_transferred: op.bytes_transferred,
+                total_bytes: op.total_bytes,
+                status: format!("{:?}", op.status).to_lowercase(),
+            })
+            .collect(),
     }
 }
 
+/// Search one shard for a hybrid query
+///
+/// Text and vector hits come back as separate lists with raw scores, so the
+/// coordinator can rank each globally before fusing them. Text scores use
+/// the query's DFS statistics when present.
+async fn search_hybrid(
+    manager: &CollectionManager,
+    collection: &str,
+    query: &RpcQuery,
+    vector: Vec<f32>,
+) -> prism::Result<RpcSearchResults> {
+    use prism::backends::SearchBackend;
+
+    let start = Instant::now();
+    let schema = manager
+        .get_schema(collection)
+        .ok_or_else(|| prism::Error::CollectionNotFound(collection.to_string()))?;
+
+    let text = if schema.backends.text.is_some() && !query.query_string.trim().is_empty() {
+        let text_query: prism::backends::Query = query.clone().into();
+        match &query.term_statistics {
+            Some(statistics) => manager.text_backend().search_with_statistics(
+                collection,
+                &text_query,
+                statistics,
+            )?,
+            None => {
+                manager
+                    .text_backend()
+                    .search(collection, text_query)
+                    .await?
+            }
+        }
+        .results
+    } else {
+        Vec::new()
+    };
+
+    let vector = if schema.backends.vector.is_some() {
+        let mut vector_query: prism::backends::Query = query.clone().into();
+        vector_query.query_string = serde_json::to_string(&vector)
+            .map_err(|e| prism::Error::InvalidQuery(e.to_string()))?;
+        vector_query.fields = Vec::new();
+        vector_query.highlight = None;
+        manager
+            .vector_backend()
+            .search(collection, vector_query)
+            .await?
+            .results
+    } else {
+        Vec::new()
+    };
+
+    Ok(RpcSearchResults {
+        results: Vec::new(),
+        total: text.len().max(vector.len()),
+        latency_ms: start.elapsed().as_millis() as u64,
+        aggregations: Default::default(),
+        text_results: text.into_iter().map(RpcSearchResult::from).collect(),
+        vector_results: vector.into_iter().map(RpcSearchResult::from).collect(),
+    })
+}
+
 /// Wrapper around QUIC bidirectional streams for tokio I/O
 struct QuicBiStream {
     send: quinn::SendStream,
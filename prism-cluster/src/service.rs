@@ -6,6 +6,7 @@
 #![allow(async_fn_in_trait)]
 
 use crate::error::ClusterError;
+use crate::metadata::MetadataCommand;
 use crate::types::*;
 
 /// Prism cluster RPC service definition.
@@ -25,6 +26,12 @@ pub trait PrismCluster {
     /// Search documents in a collection
     async fn search(collection: String, query: RpcQuery) -> Result<RpcSearchResults, ClusterError>;
 
+    /// Collect BM25 statistics of the query's terms (DFS phase of a search)
+    async fn term_statistics(
+        collection: String,
+        query: RpcQuery,
+    ) -> Result<prism::backends::TermStatistics, ClusterError>;
+
     /// Get a document by ID
     async fn get(collection: String, id: String) -> Result<Option<RpcDocument>, ClusterError>;
 
@@ -82,6 +89,10 @@ pub trait PrismCluster {
     /// Undrain this node - resume routing queries to it
     async fn undrain_node() -> Result<bool, ClusterError>;
 
+    /// Exchange protocol versions and features on a new connection,
+    /// failing if the two nodes have no protocol version in common
+    async fn handshake(peer: RpcHandshake) -> Result<RpcHandshake, ClusterError>;
+
     // ========================================
     // Shard Management
     // ========================================
@@ -108,6 +119,86 @@ pub trait PrismCluster {
         request: ShardTransferRequest,
     ) -> Result<ShardTransferResponse, ClusterError>;
 
+    /// Prepare to receive a shard's files
+    ///
+    /// Called by the source node on the target. Returns what is already
+    /// staged so an interrupted transfer resumes where it stopped.
+    async fn begin_shard_receive(
+        request: RpcBeginShardReceive,
+    ) -> Result<RpcShardReceiveState, ClusterError>;
+
+    /// Stage a chunk of a shard file
+    ///
+    /// Returns the number of bytes staged for the file after this chunk.
+    async fn write_shard_chunk(chunk: RpcShardChunk) -> Result<u64, ClusterError>;
+
+    /// Install a fully staged shard and load it on the target
+    async fn complete_shard_receive(
+        transfer_id: String,
+    ) -> Result<RpcShardInstallResult, ClusterError>;
+
+    /// Read a page of the documents that hash to a shard
+    ///
+    /// Used to copy documents while a collection's shards are split or shrunk.
+    async fn scan_shard(request: RpcShardScanRequest) -> Result<RpcShardScan, ClusterError>;
+
+    // ========================================
+    // Write Replication
+    // ========================================
+
+    /// Apply a write on the primary of a shard and replicate it
+    ///
+    /// Acknowledged once the number of copies required by the collection's
+    /// replication config have applied the write.
+    async fn primary_write(request: RpcPrimaryWrite) -> Result<RpcWriteAck, ClusterError>;
+
+    /// Apply a write forwarded by the primary
+    ///
+    /// Returns the replica's checkpoint after the write.
+    async fn replica_write(request: RpcReplicaWrite) -> Result<u64, ClusterError>;
+
+    /// Read operations from the primary's log for a replica catching up
+    async fn get_operations(request: RpcOperationsRequest) -> Result<RpcOperations, ClusterError>;
+
+    /// Replication progress of this node's copy of a shard
+    async fn shard_checkpoint(shard_id: String) -> Result<RpcShardCheckpoint, ClusterError>;
+
+    /// Read documents of a shard with the versions of their last writes
+    async fn get_versioned_documents(
+        request: RpcVersionedDocumentsRequest,
+    ) -> Result<Vec<RpcVersionedDocument>, ClusterError>;
+
+    // ========================================
+    // Cross-Cluster Replication
+    // ========================================
+
+    /// Shards of a collection with the addresses of their primaries
+    ///
+    /// Called by follower clusters to find the operation logs to pull from.
+    async fn leader_shards(collection: String) -> Result<Vec<RpcLeaderShard>, ClusterError>;
+
+    // ========================================
+    // Metadata Log
+    // ========================================
+
+    /// Ask this node for its vote in a metadata leader election
+    async fn raft_request_vote(request: RpcRequestVote) -> Result<RpcVoteResponse, ClusterError>;
+
+    /// Append metadata log entries sent by the leader
+    async fn raft_append_entries(
+        request: RpcAppendEntries,
+    ) -> Result<RpcAppendEntriesResponse, ClusterError>;
+
+    /// Replace this node's metadata with the leader's snapshot
+    ///
+    /// Returns this node's term.
+    async fn raft_install_snapshot(request: RpcInstallSnapshot) -> Result<u64, ClusterError>;
+
+    /// Append a metadata change to the log, on the leader only
+    ///
+    /// Returns the change's log index once the leader applied it.
+    async fn propose_metadata(command: MetadataCommand) -> Result<u64, ClusterError>;
+
     // ========================================
     // Rebalancing
     // ========================================
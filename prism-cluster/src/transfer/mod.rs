@@ -0,0 +1,290 @@
+//! Shard data transfer between nodes
+//!
+//! A shard moves by copying its persisted files: the Tantivy segment files
+//! and the vector index. The source node streams them to the target over the
+//! cluster RPC transport in checksummed chunks ([`ShardSender`]); the target
+//! stages them on disk and installs them once every file verifies
+//! ([`ShardReceiver`]).
+//!
+//! Writes reaching the source while files are copied are captured and
+//! replayed on the target before the assignment flips to it. A failed
+//! transfer keeps its ID and the target keeps what it staged, so requesting
+//! the same move again resumes it instead of starting over.
+
+mod receiver;
+mod sender;
+
+pub use receiver::ShardReceiver;
+pub use sender::{RemoteTarget, ShardSender, TransferJob, TransferTarget};
+
+use crate::error::{ClusterError, Result};
+use crate::types::RpcDocument;
+use parking_lot::{Mutex, RwLock};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A write captured on the source while its shard is copied
+#[derive(Debug, Clone)]
+pub enum CapturedWrite {
+    /// Documents indexed
+    Index(Vec<RpcDocument>),
+    /// Documents deleted by ID
+    Delete(Vec<String>),
+}
+
+/// Writes to a collection captured while one of its shards is copied.
+///
+/// Each write holds the gate while it is applied and recorded. Sealing the
+/// capture for the handoff waits for writes in flight and rejects new ones
+/// until the assignment has moved.
+#[derive(Default)]
+pub struct WriteCapture {
+    sealed: tokio::sync::RwLock<bool>,
+    writes: Mutex<Vec<CapturedWrite>>,
+}
+
+impl WriteCapture {
+    /// Enter the gate for one write
+    pub async fn enter(&self) -> Result<tokio::sync::RwLockReadGuard<'_, bool>> {
+        let guard = self.sealed.read().await;
+        if *guard {
+            return Err(ClusterError::NodeUnavailable(
+                "Shard is being handed off to another node, retry the write".to_string(),
+            ));
+        }
+        Ok(guard)
+    }
+
+    /// Record a write that was applied locally
+    pub fn record(&self, write: CapturedWrite) {
+        self.writes.lock().push(write);
+    }
+
+    /// Number of writes waiting to be replayed
+    pub fn pending(&self) -> usize {
+        self.writes.lock().len()
+    }
+
+    fn drain(&self) -> Vec<CapturedWrite> {
+        std::mem::take(&mut *self.writes.lock())
+    }
+
+    async fn seal(&self) {
+        *self.sealed.write().await = true;
+    }
+}
+
+/// State of a transfer tracked on the source node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TransferState {
+    Running,
+    Failed,
+    Completed,
+}
+
+#[derive(Debug, Clone)]
+struct TransferRecord {
+    transfer_id: String,
+    collection: String,
+    to_node: String,
+    state: TransferState,
+}
+
+/// Shard transfers started on this node and the write captures backing them
+#[derive(Default)]
+pub struct ShardTransfers {
+    /// Transfers by shard ID
+    transfers: RwLock<HashMap<String, TransferRecord>>,
+    /// Write captures by collection
+    captures: RwLock<HashMap<String, Arc<WriteCapture>>>,
+}
+
+impl ShardTransfers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking a transfer, returning its ID and whether it resumes
+    /// an earlier failed attempt to the same node.
+    ///
+    /// Only one shard of a collection can be copied off a node at a time.
+    pub fn start(&self, shard_id: &str, collection: &str, to_node: &str) -> Result<(String, bool)> {
+        let mut transfers = self.transfers.write();
+        if let Some(running) = transfers
+            .values()
+            .find(|t| t.collection == collection && t.state == TransferState::Running)
+        {
+            return Err(ClusterError::Transfer(format!(
+                "Transfer {} of collection {} is already running",
+                running.transfer_id, collection
+            )));
+        }
+
+        let resumed = transfers
+            .get(shard_id)
+            .filter(|t| t.state == TransferState::Failed && t.to_node == to_node)
+            .map(|t| t.transfer_id.clone());
+        let transfer_id = resumed
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        transfers.insert(
+            shard_id.to_string(),
+            TransferRecord {
+                transfer_id: transfer_id.clone(),
+                collection: collection.to_string(),
+                to_node: to_node.to_string(),
+                state: TransferState::Running,
+            },
+        );
+        Ok((transfer_id, resumed.is_some()))
+    }
+
+    /// Mark a transfer finished
+    pub fn finish(&self, shard_id: &str, success: bool) {
+        if let Some(record) = self.transfers.write().get_mut(shard_id) {
+            record.state = if success {
+                TransferState::Completed
+            } else {
+                TransferState::Failed
+            };
+        }
+    }
+
+    /// ID of the transfer running for a shard, if any
+    pub fn running(&self, shard_id: &str) -> Option<String> {
+        self.transfers
+            .read()
+            .get(shard_id)
+            .filter(|t| t.state == TransferState::Running)
+            .map(|t| t.transfer_id.clone())
+    }
+
+    /// Write capture for a collection with a shard being copied
+    pub fn capture(&self, collection: &str) -> Option<Arc<WriteCapture>> {
+        self.captures.read().get(collection).cloned()
+    }
+
+    fn begin_capture(&self, collection: &str) -> Arc<WriteCapture> {
+        Arc::clone(
+            self.captures
+                .write()
+                .entry(collection.to_string())
+                .or_default(),
+        )
+    }
+
+    fn end_capture(&self, collection: &str) {
+        self.captures.write().remove(collection);
+    }
+}
+
+/// SHA-256 of some data as lowercase hex
+pub fn checksum(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Whether a collection file is part of a shard's data.
+///
+/// Tantivy lock files only mean something to the process holding them.
+fn is_shard_file(relative_path: &str) -> bool {
+    let name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+    !name.ends_with(".lock")
+}
+
+/// Validate a collection-relative path received from another node
+fn validate_relative_path(relative_path: &str) -> Result<()> {
+    let valid = !relative_path.is_empty()
+        && !relative_path.starts_with('/')
+        && relative_path
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != "..");
+    if valid {
+        Ok(())
+    } else {
+        Err(ClusterError::Transfer(format!(
+            "Invalid shard file path: {}",
+            relative_path
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_start_resumes_failed_transfer_to_same_node() {
+        let transfers = ShardTransfers::new();
+        let (first, resumed) = transfers
+            .start("products-shard-0", "products", "node-2")
+            .unwrap();
+        assert!(!resumed);
+        assert_eq!(transfers.running("products-shard-0"), Some(first.clone()));
+
+        transfers.finish("products-shard-0", false);
+        assert!(transfers.running("products-shard-0").is_none());
+
+        let (second, resumed) = transfers
+            .start("products-shard-0", "products", "node-2")
+            .unwrap();
+        assert!(resumed);
+        assert_eq!(first, second);
+
+        transfers.finish("products-shard-0", false);
+        let (third, resumed) = transfers
+            .start("products-shard-0", "products", "node-3")
+            .unwrap();
+        assert!(!resumed);
+        assert_ne!(first, third);
+    }
+
+    #[test]
+    fn test_start_rejects_concurrent_transfer_of_collection() {
+        let transfers = ShardTransfers::new();
+        transfers
+            .start("products-shard-0", "products", "node-2")
+            .unwrap();
+        let err = transfers
+            .start("products-shard-1", "products", "node-3")
+            .unwrap_err();
+        assert_eq!(err.error_type(), "transfer");
+
+        transfers.finish("products-shard-0", true);
+        assert!(transfers
+            .start("products-shard-1", "products", "node-3")
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_sealed_capture_rejects_writes() {
+        let transfers = ShardTransfers::new();
+        let capture = transfers.begin_capture("products");
+        assert!(transfers.capture("products").is_some());
+
+        drop(capture.enter().await.unwrap());
+        capture.record(CapturedWrite::Delete(vec!["a".to_string()]));
+        assert_eq!(capture.pending(), 1);
+
+        capture.seal().await;
+        let err = capture.enter().await.unwrap_err();
+        assert_eq!(err.error_type(), "node_unavailable");
+        assert_eq!(capture.drain().len(), 1);
+
+        transfers.end_capture("products");
+        assert!(transfers.capture("products").is_none());
+    }
+
+    #[test]
+    fn test_shard_file_paths() {
+        assert!(is_shard_file("tantivy/default/meta.json"));
+        assert!(!is_shard_file("tantivy/default/.tantivy-writer.lock"));
+
+        assert!(validate_relative_path("tantivy/default/meta.json").is_ok());
+        assert!(validate_relative_path("vector/default/sharded_index.json").is_ok());
+        assert!(validate_relative_path("").is_err());
+        assert!(validate_relative_path("/etc/passwd").is_err());
+        assert!(validate_relative_path("tantivy/../../etc").is_err());
+        assert!(validate_relative_path("tantivy//meta.json").is_err());
+    }
+}
@@ -0,0 +1,369 @@
+//! Target side of a shard transfer: staging and installing received files
+
+use super::{checksum, validate_relative_path};
+use crate::error::{ClusterError, Result};
+use crate::types::{
+    RpcBeginShardReceive, RpcShardChunk, RpcShardInstallResult, RpcShardReceiveState, RpcStagedFile,
+};
+use prism::backends::{Document, Query};
+use prism::collection::CollectionManager;
+use prism::schema::CollectionSchema;
+use prism_storage::{Bytes, SegmentStorage, StorageBackend, StoragePath};
+use std::collections::{BTreeMap, HashMap};
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+/// Documents indexed per batch when merging into an existing collection
+const MERGE_BATCH_SIZE: usize = 500;
+
+/// A file being staged
+#[derive(Debug, Clone)]
+struct StagedFile {
+    size: u64,
+    staged_bytes: u64,
+    checksum: Option<String>,
+}
+
+/// A shard being received
+struct ReceiveSession {
+    shard_id: String,
+    collection: String,
+    schema: CollectionSchema,
+    files: BTreeMap<String, StagedFile>,
+}
+
+/// Receives shard files from source nodes.
+///
+/// Files are staged under `<staging_dir>/<transfer_id>/` and survive a
+/// failed attempt, so the source can resume from the bytes already staged.
+pub struct ShardReceiver {
+    manager: Arc<CollectionManager>,
+    staging_dir: PathBuf,
+    sessions: Mutex<HashMap<String, ReceiveSession>>,
+}
+
+impl ShardReceiver {
+    pub fn new(manager: Arc<CollectionManager>, staging_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            manager,
+            staging_dir: staging_dir.into(),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn transfer_dir(&self, transfer_id: &str) -> Result<PathBuf> {
+        // Transfer IDs become directory names
+        if transfer_id.is_empty()
+            || !transfer_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            return Err(ClusterError::Transfer(format!(
+                "Invalid transfer ID: {}",
+                transfer_id
+            )));
+        }
+        Ok(self.staging_dir.join(transfer_id))
+    }
+
+    /// Start or resume receiving a shard.
+    ///
+    /// Files staged by an earlier attempt are kept when the manifest still
+    /// lists them with the same size; anything else is discarded.
+    pub async fn begin(&self, request: RpcBeginShardReceive) -> Result<RpcShardReceiveState> {
+        CollectionManager::validate_collection_name(&request.collection)?;
+        let schema: CollectionSchema = serde_json::from_value(request.schema)
+            .map_err(|e| ClusterError::Transfer(format!("Invalid schema: {}", e)))?;
+        if schema.collection != request.collection {
+            return Err(ClusterError::Transfer(format!(
+                "Schema is for collection {}, not {}",
+                schema.collection, request.collection
+            )));
+        }
+        for file in &request.files {
+            validate_relative_path(&file.path)?;
+        }
+
+        let dir = self.transfer_dir(&request.transfer_id)?;
+        tokio::fs::create_dir_all(&dir).await?;
+
+        let mut sessions = self.sessions.lock().await;
+        let previous = sessions
+            .remove(&request.transfer_id)
+            .map(|s| s.files)
+            .unwrap_or_default();
+
+        let mut files = BTreeMap::new();
+        for file in &request.files {
+            let staged_path = dir.join(&file.path);
+            let on_disk = tokio::fs::metadata(&staged_path)
+                .await
+                .map(|m| m.len())
+                .unwrap_or(0);
+            let staged = if on_disk <= file.size {
+                let checksum = previous
+                    .get(&file.path)
+                    .filter(|p| p.size == file.size && p.staged_bytes == on_disk)
+                    .and_then(|p| p.checksum.clone());
+                StagedFile {
+                    size: file.size,
+                    staged_bytes: on_disk,
+                    checksum,
+                }
+            } else {
+                tokio::fs::remove_file(&staged_path).await?;
+                StagedFile {
+                    size: file.size,
+                    staged_bytes: 0,
+                    checksum: None,
+                }
+            };
+            files.insert(file.path.clone(), staged);
+        }
+
+        // Drop staged files the manifest no longer lists
+        for path in previous.keys().filter(|p| !files.contains_key(*p)) {
+            let _ = tokio::fs::remove_file(dir.join(path)).await;
+        }
+
+        let state = RpcShardReceiveState {
+            files: files
+                .iter()
+                .filter(|(_, f)| f.staged_bytes > 0)
+                .map(|(path, f)| RpcStagedFile {
+                    path: path.clone(),
+                    staged_bytes: f.staged_bytes,
+                    checksum: f.checksum.clone(),
+                })
+                .collect(),
+        };
+
+        info!(
+            "Receiving shard {} of {} (transfer {}): {} files, {} already staged",
+            request.shard_id,
+            request.collection,
+            request.transfer_id,
+            files.len(),
+            state.files.len()
+        );
+        sessions.insert(
+            request.transfer_id,
+            ReceiveSession {
+                shard_id: request.shard_id,
+                collection: request.collection,
+                schema,
+                files,
+            },
+        );
+        Ok(state)
+    }
+
+    /// Stage a chunk, returning the bytes staged for its file.
+    ///
+    /// Chunks must arrive in order. The last chunk of a file carries the
+    /// checksum of the whole file; a mismatch discards the staged file.
+    pub async fn write_chunk(&self, chunk: RpcShardChunk) -> Result<u64> {
+        let dir = self.transfer_dir(&chunk.transfer_id)?;
+        let mut sessions = self.sessions.lock().await;
+        let session = sessions.get_mut(&chunk.transfer_id).ok_or_else(|| {
+            ClusterError::Transfer(format!("Unknown transfer: {}", chunk.transfer_id))
+        })?;
+        let file = session.files.get_mut(&chunk.path).ok_or_else(|| {
+            ClusterError::Transfer(format!(
+                "File {} is not part of transfer {}",
+                chunk.path, chunk.transfer_id
+            ))
+        })?;
+
+        if checksum(&chunk.data) != chunk.checksum {
+            return Err(ClusterError::Transfer(format!(
+                "Checksum mismatch for {} at offset {}",
+                chunk.path, chunk.offset
+            )));
+        }
+        if chunk.offset > file.staged_bytes {
+            return Err(ClusterError::Transfer(format!(
+                "Chunk of {} at offset {} leaves a gap, {} bytes staged",
+                chunk.path, chunk.offset, file.staged_bytes
+            )));
+        }
+        let end = chunk.offset + chunk.data.len() as u64;
+        if end > file.size {
+            return Err(ClusterError::Transfer(format!(
+                "Chunk of {} ends at {}, past its size {}",
+                chunk.path, end, file.size
+            )));
+        }
+
+        let staged_path = dir.join(&chunk.path);
+        if let Some(parent) = staged_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut out = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&staged_path)
+            .await?;
+        // A resent chunk overwrites what was staged from its offset on
+        out.set_len(chunk.offset).await?;
+        out.seek(SeekFrom::End(0)).await?;
+        out.write_all(&chunk.data).await?;
+        out.flush().await?;
+        file.staged_bytes = end;
+        file.checksum = None;
+
+        if let Some(expected) = &chunk.file_checksum {
+            let actual = if end == file.size {
+                checksum(&tokio::fs::read(&staged_path).await?)
+            } else {
+                String::new()
+            };
+            if &actual != expected {
+                out.set_len(0).await?;
+                file.staged_bytes = 0;
+                return Err(ClusterError::Transfer(format!(
+                    "File {} failed verification, it will be sent again",
+                    chunk.path
+                )));
+            }
+            file.checksum = Some(actual);
+        }
+
+        Ok(file.staged_bytes)
+    }
+
+    /// Install a fully staged shard.
+    ///
+    /// When this node has no copy of the collection the files are installed
+    /// as they are. Otherwise they are loaded as a scratch collection and
+    /// their documents are indexed into the existing one; that path carries
+    /// over stored fields only, like a portable export.
+    pub async fn complete(&self, transfer_id: &str) -> Result<RpcShardInstallResult> {
+        let dir = self.transfer_dir(transfer_id)?;
+        let session = {
+            let mut sessions = self.sessions.lock().await;
+            let session = sessions.get(transfer_id).ok_or_else(|| {
+                ClusterError::Transfer(format!("Unknown transfer: {}", transfer_id))
+            })?;
+            let unverified: Vec<&str> = session
+                .files
+                .iter()
+                .filter(|(_, f)| f.checksum.is_none())
+                .map(|(path, _)| path.as_str())
+                .collect();
+            if !unverified.is_empty() {
+                return Err(ClusterError::Transfer(format!(
+                    "Transfer {} has unverified files: {}",
+                    transfer_id,
+                    unverified.join(", ")
+                )));
+            }
+            sessions.remove(transfer_id).expect("session checked above")
+        };
+
+        let collection = session.collection.clone();
+        let merged = self.manager.get_schema(&collection).is_some();
+        let result = if merged {
+            let scratch = format!("{}__{}", collection, transfer_id);
+            self.install_files(&dir, &session, &scratch).await?;
+            self.merge_collection(&scratch, &collection).await
+        } else {
+            self.install_files(&dir, &session, &collection).await
+        };
+        result?;
+
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+        let document_count = self.manager.stats(&collection).await?.document_count as u64;
+        info!(
+            "Installed shard {} of {} (transfer {}, merged={}): {} documents",
+            session.shard_id, collection, transfer_id, merged, document_count
+        );
+        Ok(RpcShardInstallResult {
+            collection,
+            merged,
+            document_count,
+        })
+    }
+
+    /// Write staged files into storage under `name` and load the collection
+    async fn install_files(&self, dir: &Path, session: &ReceiveSession, name: &str) -> Result<()> {
+        for path in session.files.keys() {
+            let storage_path =
+                StoragePath::parse(&format!("{}/{}", name, path)).ok_or_else(|| {
+                    ClusterError::Transfer(format!("Invalid shard file path: {}", path))
+                })?;
+            let storage = self.storage_for(storage_path.backend)?;
+            let data = tokio::fs::read(dir.join(path)).await?;
+            storage
+                .write(&storage_path, Bytes::from(data))
+                .await
+                .map_err(|e| ClusterError::Backend(e.to_string()))?;
+            debug!("Installed {}", storage_path);
+        }
+
+        let mut schema = session.schema.clone();
+        schema.collection = name.to_string();
+        self.manager.add_collection(schema).await?;
+        Ok(())
+    }
+
+    fn storage_for(&self, backend: StorageBackend) -> Result<Arc<dyn SegmentStorage>> {
+        match backend {
+            StorageBackend::Tantivy => Ok(self.manager.text_backend().segment_storage()),
+            StorageBackend::Vector => Ok(self.manager.vector_backend().segment_storage()),
+            other => Err(ClusterError::Transfer(format!(
+                "Shard files of the {} backend cannot be transferred",
+                other
+            ))),
+        }
+    }
+
+    /// Index every document of `scratch` into `collection`, then drop `scratch`
+    async fn merge_collection(&self, scratch: &str, collection: &str) -> Result<()> {
+        let total = self.manager.stats(scratch).await?.document_count;
+        let merged = async {
+            if total == 0 {
+                return Ok(());
+            }
+            let query = Query {
+                query_string: "*".to_string(),
+                fields: vec![],
+                limit: total,
+                offset: 0,
+                merge_strategy: None,
+                text_weight: None,
+                vector_weight: None,
+                highlight: None,
+                rrf_k: None,
+                min_score: None,
+                score_function: None,
+                skip_ranking: false,
+            };
+            let results = self.manager.search(scratch, query, None).await?;
+            let mut batch: Vec<Document> = Vec::with_capacity(MERGE_BATCH_SIZE);
+            for result in results.results {
+                if let Some(doc) = self.manager.get(scratch, &result.id).await? {
+                    batch.push(doc);
+                }
+                if batch.len() >= MERGE_BATCH_SIZE {
+                    self.manager
+                        .index(collection, std::mem::take(&mut batch))
+                        .await?;
+                }
+            }
+            if !batch.is_empty() {
+                self.manager.index(collection, batch).await?;
+            }
+            Ok::<(), prism::Error>(())
+        }
+        .await;
+
+        self.manager.delete_collection(scratch).await?;
+        merged.map_err(ClusterError::from)
+    }
+}
@@ -0,0 +1,836 @@
+//! Source side of a shard transfer: streaming files, replaying writes and
+//! handing the shard over
+
+use super::{checksum, is_shard_file, CapturedWrite, ShardTransfers, WriteCapture};
+use crate::client::ClusterClient;
+use crate::config::RebalancingConfig;
+use crate::error::{ClusterError, Result};
+use crate::metadata::{MetadataCommand, RaftNode};
+use crate::metrics::{record_shard_transfer_complete, update_cluster_state_metrics};
+use crate::placement::{ClusterState, ShardState};
+use crate::rebalance::{OperationStatus, RebalanceEngine, RebalanceOperationStatus};
+use crate::types::{
+    RpcBeginShardReceive, RpcDocument, RpcShardChunk, RpcShardFile, RpcShardInstallResult,
+    RpcShardReceiveState, RpcStagedFile,
+};
+use async_trait::async_trait;
+use prism::collection::CollectionManager;
+use prism_storage::{SegmentStorage, StorageBackend, StoragePath};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Attempts at copying files before a transfer fails
+const MAX_COPY_ATTEMPTS: u32 = 3;
+
+/// Node receiving a shard
+#[async_trait]
+pub trait TransferTarget: Send + Sync {
+    /// Start or resume receiving, returning what is already staged
+    async fn begin(&self, request: RpcBeginShardReceive) -> Result<RpcShardReceiveState>;
+
+    /// Stage a chunk, returning the bytes staged for its file
+    async fn write_chunk(&self, chunk: RpcShardChunk) -> Result<u64>;
+
+    /// Install the staged shard
+    async fn complete(&self, transfer_id: &str) -> Result<RpcShardInstallResult>;
+
+    /// Replay indexed documents
+    async fn index(&self, collection: &str, docs: Vec<RpcDocument>) -> Result<()>;
+
+    /// Replay deletes
+    async fn delete(&self, collection: &str, ids: Vec<String>) -> Result<()>;
+}
+
+/// A target node reached over cluster RPC
+pub struct RemoteTarget {
+    client: Arc<ClusterClient>,
+    addr: String,
+}
+
+impl RemoteTarget {
+    pub fn new(client: Arc<ClusterClient>, addr: impl Into<String>) -> Self {
+        Self {
+            client,
+            addr: addr.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl TransferTarget for RemoteTarget {
+    async fn begin(&self, request: RpcBeginShardReceive) -> Result<RpcShardReceiveState> {
+        self.client.begin_shard_receive(&self.addr, request).await
+    }
+
+    async fn write_chunk(&self, chunk: RpcShardChunk) -> Result<u64> {
+        self.client.write_shard_chunk(&self.addr, chunk).await
+    }
+
+    async fn complete(&self, transfer_id: &str) -> Result<RpcShardInstallResult> {
+        self.client
+            .complete_shard_receive(&self.addr, transfer_id)
+            .await
+    }
+
+    async fn index(&self, collection: &str, docs: Vec<RpcDocument>) -> Result<()> {
+        self.client.index(&self.addr, collection, docs).await
+    }
+
+    async fn delete(&self, collection: &str, ids: Vec<String>) -> Result<()> {
+        self.client.delete(&self.addr, collection, ids).await
+    }
+}
+
+/// A shard move to run
+#[derive(Debug, Clone)]
+pub struct TransferJob {
+    pub transfer_id: String,
+    pub shard_id: String,
+    pub collection: String,
+    pub from_node: String,
+    pub to_node: String,
+}
+
+/// A file of the shard being sent
+struct SourceFile {
+    relative_path: String,
+    path: StoragePath,
+    storage: Arc<dyn SegmentStorage>,
+    size: u64,
+}
+
+/// Runs shard transfers from this node
+pub struct ShardSender {
+    manager: Arc<CollectionManager>,
+    cluster_state: Arc<ClusterState>,
+    rebalance_engine: Arc<RebalanceEngine>,
+    transfers: Arc<ShardTransfers>,
+    metadata: Option<Arc<RaftNode>>,
+    chunk_bytes: usize,
+    max_bytes_per_sec: u64,
+}
+
+impl ShardSender {
+    pub fn new(
+        manager: Arc<CollectionManager>,
+        cluster_state: Arc<ClusterState>,
+        rebalance_engine: Arc<RebalanceEngine>,
+        transfers: Arc<ShardTransfers>,
+        config: &RebalancingConfig,
+    ) -> Self {
+        Self {
+            manager,
+            cluster_state,
+            rebalance_engine,
+            transfers,
+            metadata: None,
+            chunk_bytes: config.transfer_chunk_bytes.max(1),
+            max_bytes_per_sec: config.max_bytes_per_sec,
+        }
+    }
+
+    /// Flip assignments through the replicated metadata log
+    pub fn with_metadata(mut self, metadata: Arc<RaftNode>) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Move a shard to the target node.
+    ///
+    /// Copies the files, installs them on the target, replays writes made
+    /// meanwhile, flips the assignment and removes the local copy. On failure
+    /// the shard stays active here and the transfer can be resumed.
+    pub async fn run(
+        &self,
+        job: &TransferJob,
+        target: &dyn TransferTarget,
+    ) -> Result<RpcShardInstallResult> {
+        let started = Instant::now();
+        let capture = self.transfers.begin_capture(&job.collection);
+        let result = self.transfer(job, target, &capture).await;
+        self.transfers.end_capture(&job.collection);
+
+        match result {
+            Ok((installed, bytes)) => {
+                self.transfers.finish(&job.shard_id, true);
+                self.report(job, OperationStatus::Completed, bytes, bytes);
+                record_shard_transfer_complete(&job.shard_id, true, bytes, started.elapsed());
+                info!(
+                    "Shard {} moved from {} to {} ({} bytes, {} documents)",
+                    job.shard_id, job.from_node, job.to_node, bytes, installed.document_count
+                );
+                Ok(installed)
+            }
+            Err(e) => {
+                self.cluster_state
+                    .update_shard_state(&job.shard_id, ShardState::Active);
+                update_cluster_state_metrics(&self.cluster_state);
+                self.transfers.finish(&job.shard_id, false);
+                let status = self.operation_status(job, OperationStatus::Failed, 0, 0);
+                self.rebalance_engine.record_transfer_error(
+                    status,
+                    format!("Transfer of shard {} failed: {}", job.shard_id, e),
+                );
+                record_shard_transfer_complete(&job.shard_id, false, 0, started.elapsed());
+                warn!("Transfer of shard {} failed: {}", job.shard_id, e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn transfer(
+        &self,
+        job: &TransferJob,
+        target: &dyn TransferTarget,
+        capture: &WriteCapture,
+    ) -> Result<(RpcShardInstallResult, u64)> {
+        let mut attempt = 1;
+        let bytes = loop {
+            match self.copy_files(job, target).await {
+                Ok(bytes) => break bytes,
+                Err(e) if attempt < MAX_COPY_ATTEMPTS => {
+                    warn!(
+                        "Copying shard {} failed (attempt {}), resuming: {}",
+                        job.shard_id, attempt, e
+                    );
+                    tokio::time::sleep(Duration::from_millis(200 * u64::from(attempt))).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        self.report(job, OperationStatus::Verifying, bytes, bytes);
+        let installed = target.complete(&job.transfer_id).await?;
+
+        // Catch up while writes still flow, then hold them for the handoff
+        self.replay(job, target, capture).await?;
+        capture.seal().await;
+        self.replay(job, target, capture).await?;
+
+        self.flip_assignment(job).await?;
+        self.remove_local_copy(job).await;
+        Ok((installed, bytes))
+    }
+
+    /// Send every file of the shard, skipping what the target already holds
+    async fn copy_files(&self, job: &TransferJob, target: &dyn TransferTarget) -> Result<u64> {
+        let schema = self
+            .manager
+            .get_schema(&job.collection)
+            .ok_or_else(|| ClusterError::CollectionNotFound(job.collection.clone()))?;
+        let files = self.list_files(&job.collection).await?;
+        let total: u64 = files.iter().map(|f| f.size).sum();
+
+        let state = target
+            .begin(RpcBeginShardReceive {
+                transfer_id: job.transfer_id.clone(),
+                shard_id: job.shard_id.clone(),
+                collection: job.collection.clone(),
+                schema: serde_json::to_value(&schema)
+                    .map_err(|e| ClusterError::Serialization(e.to_string()))?,
+                files: files
+                    .iter()
+                    .map(|f| RpcShardFile {
+                        path: f.relative_path.clone(),
+                        size: f.size,
+                    })
+                    .collect(),
+            })
+            .await?;
+        let staged: HashMap<String, RpcStagedFile> = state
+            .files
+            .into_iter()
+            .map(|f| (f.path.clone(), f))
+            .collect();
+
+        let mut sent = 0u64;
+        self.report(job, OperationStatus::Transferring, sent, total);
+        for file in files {
+            let data = file.storage.read(&file.path).await.map_err(|e| {
+                ClusterError::Transfer(format!("Failed to read {}: {}", file.path, e))
+            })?;
+            if data.len() as u64 != file.size {
+                return Err(ClusterError::Transfer(format!(
+                    "{} changed while being sent",
+                    file.path
+                )));
+            }
+            let file_checksum = checksum(&data);
+
+            let already = staged.get(&file.relative_path);
+            if already.and_then(|s| s.checksum.as_ref()) == Some(&file_checksum) {
+                sent += file.size;
+                self.report(job, OperationStatus::Transferring, sent, total);
+                continue;
+            }
+            let mut offset = already
+                .map(|s| s.staged_bytes)
+                .filter(|&b| b < file.size)
+                .unwrap_or(0);
+            sent += offset;
+
+            loop {
+                let end = (offset + self.chunk_bytes as u64).min(file.size);
+                let chunk = &data[offset as usize..end as usize];
+                let last = end == file.size;
+                let staged_bytes = target
+                    .write_chunk(RpcShardChunk {
+                        transfer_id: job.transfer_id.clone(),
+                        path: file.relative_path.clone(),
+                        offset,
+                        data: chunk.to_vec(),
+                        checksum: checksum(chunk),
+                        file_checksum: last.then(|| file_checksum.clone()),
+                    })
+                    .await?;
+                if staged_bytes != end {
+                    return Err(ClusterError::Transfer(format!(
+                        "Target staged {} bytes of {}, expected {}",
+                        staged_bytes, file.relative_path, end
+                    )));
+                }
+
+                sent += end - offset;
+                self.report(job, OperationStatus::Transferring, sent, total);
+                self.throttle(end - offset).await;
+                offset = end;
+                if last {
+                    break;
+                }
+            }
+        }
+        Ok(total)
+    }
+
+    /// Files of a collection in its text and vector storage.
+    ///
+    /// `meta.json` files come first: segments are immutable, so sending
+    /// the segment list before the segments means a segment merged away
+    /// mid-copy fails the attempt rather than being silently missed.
+    async fn list_files(&self, collection: &str) -> Result<Vec<SourceFile>> {
+        let mut files = Vec::new();
+        let storages = [
+            (
+                StorageBackend::Tantivy,
+                self.manager.text_backend().segment_storage(),
+            ),
+            (
+                StorageBackend::Vector,
+                self.manager.vector_backend().segment_storage(),
+            ),
+        ];
+        let prefix = format!("{}/", collection);
+        for (backend, storage) in storages {
+            let objects = storage
+                .list(&StoragePath::new(collection, backend))
+                .await
+                .map_err(|e| ClusterError::Backend(e.to_string()))?;
+            for object in objects {
+                let full = object.path.to_string();
+                let Some(relative_path) = full.strip_prefix(&prefix) else {
+                    continue;
+                };
+                if !is_shard_file(relative_path) {
+                    continue;
+                }
+                files.push(SourceFile {
+                    relative_path: relative_path.to_string(),
+                    path: object.path,
+                    storage: Arc::clone(&storage),
+                    size: object.size,
+                });
+            }
+        }
+        files.sort_by(|a, b| {
+            let a_meta = a.relative_path.ends_with("meta.json");
+            let b_meta = b.relative_path.ends_with("meta.json");
+            b_meta
+                .cmp(&a_meta)
+                .then_with(|| a.relative_path.cmp(&b.relative_path))
+        });
+        Ok(files)
+    }
+
+    async fn replay(
+        &self,
+        job: &TransferJob,
+        target: &dyn TransferTarget,
+        capture: &WriteCapture,
+    ) -> Result<()> {
+        let writes = capture.drain();
+        if !writes.is_empty() {
+            info!(
+                "Replaying {} writes to shard {} on {}",
+                writes.len(),
+                job.shard_id,
+                job.to_node
+            );
+        }
+        for write in writes {
+            match write {
+                CapturedWrite::Index(docs) => target.index(&job.collection, docs).await?,
+                CapturedWrite::Delete(ids) => target.delete(&job.collection, ids).await?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Point the assignment at the target and mark it active
+    async fn flip_assignment(&self, job: &TransferJob) -> Result<()> {
+        let mut shard = self
+            .cluster_state
+            .get_shard(&job.shard_id)
+            .ok_or_else(|| ClusterError::CollectionNotFound(job.shard_id.clone()))?;
+        if shard.primary_node == job.from_node {
+            shard.primary_node = job.to_node.clone();
+        } else if let Some(replica) = shard
+            .replica_nodes
+            .iter_mut()
+            .find(|n| **n == job.from_node)
+        {
+            *replica = job.to_node.clone();
+        } else {
+            return Err(ClusterError::Transfer(format!(
+                "Shard {} is no longer on node {}",
+                job.shard_id, job.from_node
+            )));
+        }
+        // The source no longer holds a copy
+        shard.in_sync_replicas.retain(|n| *n != job.from_node);
+        shard.state = ShardState::Active;
+        match &self.metadata {
+            Some(metadata) => {
+                metadata.write(MetadataCommand::AssignShard(shard)).await?;
+            }
+            None => {
+                shard.epoch = self.cluster_state.next_epoch();
+                self.cluster_state.assign_shard(shard);
+            }
+        }
+        update_cluster_state_metrics(&self.cluster_state);
+        Ok(())
+    }
+
+    /// Delete the collection here unless another of its shards remains
+    async fn remove_local_copy(&self, job: &TransferJob) {
+        let still_hosted = self
+            .cluster_state
+            .get_node_shards(&job.from_node)
+            .iter()
+            .any(|s| s.collection == job.collection);
+        if still_hosted {
+            info!(
+                "Keeping local copy of {}: other shards remain on {}",
+                job.collection, job.from_node
+            );
+            return;
+        }
+        if let Err(e) = self.manager.delete_collection(&job.collection).await {
+            warn!(
+                "Shard {} moved but removing the local copy of {} failed: {}",
+                job.shard_id, job.collection, e
+            );
+        }
+    }
+
+    async fn throttle(&self, bytes: u64) {
+        if self.max_bytes_per_sec > 0 {
+            let secs = bytes as f64 / self.max_bytes_per_sec as f64;
+            tokio::time::sleep(Duration::from_secs_f64(secs)).await;
+        }
+    }
+
+    fn report(&self, job: &TransferJob, status: OperationStatus, bytes: u64, total: u64) {
+        self.rebalance_engine
+            .record_transfer(self.operation_status(job, status, bytes, total));
+    }
+
+    fn operation_status(
+        &self,
+        job: &TransferJob,
+        status: OperationStatus,
+        bytes: u64,
+        total: u64,
+    ) -> RebalanceOperationStatus {
+        RebalanceOperationStatus {
+            shard_id: job.shard_id.clone(),
+            from_node: job.from_node.clone(),
+            to_node: job.to_node.clone(),
+            progress: if total == 0 {
+                1.0
+            } else {
+                bytes as f64 / total as f64
+            },
+            bytes_transferred: bytes,
+            total_bytes: total,
+            status,
+            transfer_id: Some(job.transfer_id.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NodeTopology;
+    use crate::placement::{NodeInfo, PlacementStrategy, ShardAssignment};
+    use crate::transfer::ShardReceiver;
+    use prism::backends::{Document, TextBackend, VectorBackend};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::TempDir;
+
+    const SCHEMA: &str = r#"
+collection: products
+backends:
+  text:
+    fields:
+      - name: title
+        type: text
+        indexed: true
+        stored: true
+"#;
+
+    async fn make_manager(temp: &TempDir, name: &str, with_schema: bool) -> Arc<CollectionManager> {
+        let schemas_dir = temp.path().join(name).join("schemas");
+        let data_dir = temp.path().join(name).join("data");
+        std::fs::create_dir_all(&schemas_dir).unwrap();
+        if with_schema {
+            std::fs::write(schemas_dir.join("products.yaml"), SCHEMA).unwrap();
+        }
+        let text_backend = Arc::new(TextBackend::new(&data_dir).unwrap());
+        let vector_backend = Arc::new(VectorBackend::new(&data_dir).unwrap());
+        let manager =
+            CollectionManager::new(&schemas_dir, text_backend, vector_backend, None).unwrap();
+        manager.initialize().await.unwrap();
+        Arc::new(manager)
+    }
+
+    fn doc(id: &str, title: &str) -> Document {
+        Document {
+            id: id.to_string(),
+            fields: HashMap::from([("title".to_string(), serde_json::json!(title))]),
+        }
+    }
+
+    fn make_node_info(id: &str) -> NodeInfo {
+        NodeInfo {
+            node_id: id.to_string(),
+            address: format!("{}:9080", id),
+            topology: NodeTopology {
+                zone: "zone-a".to_string(),
+                rack: None,
+                region: None,
+                attributes: HashMap::new(),
+            },
+            healthy: true,
+            shard_count: 0,
+            disk_used_bytes: 0,
+            disk_total_bytes: 100_000_000_000,
+            index_size_bytes: 0,
+            draining: false,
+        }
+    }
+
+    /// Target node reached in-process
+    struct LoopbackTarget {
+        receiver: ShardReceiver,
+        manager: Arc<CollectionManager>,
+        /// Chunk writes to fail, counting from one
+        fail_chunk: Option<usize>,
+        chunks: AtomicUsize,
+        /// Source written to once while its files are copied
+        source: parking_lot::Mutex<Option<(Arc<CollectionManager>, Arc<ShardTransfers>)>>,
+    }
+
+    impl LoopbackTarget {
+        fn new(temp: &TempDir, manager: Arc<CollectionManager>) -> Self {
+            Self {
+                receiver: ShardReceiver::new(Arc::clone(&manager), temp.path().join("staging")),
+                manager,
+                fail_chunk: None,
+                chunks: AtomicUsize::new(0),
+                source: parking_lot::Mutex::new(None),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl TransferTarget for LoopbackTarget {
+        async fn begin(&self, request: RpcBeginShardReceive) -> Result<RpcShardReceiveState> {
+            let source = self.source.lock().take();
+            if let Some((manager, transfers)) = source {
+                // What the index handler does for a write arriving mid-copy
+                let capture = transfers.capture(&request.collection).unwrap();
+                let _gate = capture.enter().await?;
+                let late = doc("late", "written during the copy");
+                manager
+                    .index(&request.collection, vec![late.clone()])
+                    .await?;
+                capture.record(CapturedWrite::Index(vec![late.into()]));
+            }
+            self.receiver.begin(request).await
+        }
+
+        async fn write_chunk(&self, chunk: RpcShardChunk) -> Result<u64> {
+            let n = self.chunks.fetch_add(1, Ordering::SeqCst) + 1;
+            if self.fail_chunk == Some(n) {
+                return Err(ClusterError::Connection("injected failure".to_string()));
+            }
+            self.receiver.write_chunk(chunk).await
+        }
+
+        async fn complete(&self, transfer_id: &str) -> Result<RpcShardInstallResult> {
+            self.receiver.complete(transfer_id).await
+        }
+
+        async fn index(&self, collection: &str, docs: Vec<RpcDocument>) -> Result<()> {
+            let docs = docs.into_iter().map(Into::into).collect();
+            Ok(self.manager.index(collection, docs).await?)
+        }
+
+        async fn delete(&self, collection: &str, ids: Vec<String>) -> Result<()> {
+            Ok(self.manager.delete(collection, ids).await?)
+        }
+    }
+
+    struct Fixture {
+        source: Arc<CollectionManager>,
+        cluster_state: Arc<ClusterState>,
+        engine: Arc<RebalanceEngine>,
+        transfers: Arc<ShardTransfers>,
+        sender: ShardSender,
+    }
+
+    async fn make_fixture(temp: &TempDir) -> Fixture {
+        let source = make_manager(temp, "node-1", true).await;
+        source
+            .index(
+                "products",
+                (0..20)
+                    .map(|i| doc(&format!("p{}", i), &format!("product {}", i)))
+                    .collect(),
+            )
+            .await
+            .unwrap();
+
+        let cluster_state = Arc::new(ClusterState::new());
+        cluster_state.register_node(make_node_info("node-1"));
+        cluster_state.register_node(make_node_info("node-2"));
+        let mut shard = ShardAssignment::new("products", 0, "node-1");
+        shard.state = ShardState::Relocating;
+        cluster_state.assign_shard(shard);
+
+        let config = RebalancingConfig {
+            transfer_chunk_bytes: 512,
+            max_bytes_per_sec: 0,
+            ..Default::default()
+        };
+        let engine = Arc::new(RebalanceEngine::new(
+            config.clone(),
+            Arc::clone(&cluster_state),
+            PlacementStrategy::default(),
+        ));
+        let transfers = Arc::new(ShardTransfers::new());
+        let sender = ShardSender::new(
+            Arc::clone(&source),
+            Arc::clone(&cluster_state),
+            Arc::clone(&engine),
+            Arc::clone(&transfers),
+            &config,
+        );
+        Fixture {
+            source,
+            cluster_state,
+            engine,
+            transfers,
+            sender,
+        }
+    }
+
+    fn start_job(fixture: &Fixture) -> TransferJob {
+        let (transfer_id, _) = fixture
+            .transfers
+            .start("products-shard-0", "products", "node-2")
+            .unwrap();
+        TransferJob {
+            transfer_id,
+            shard_id: "products-shard-0".to_string(),
+            collection: "products".to_string(),
+            from_node: "node-1".to_string(),
+            to_node: "node-2".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_installs_shard_and_flips_assignment() {
+        let temp = TempDir::new().unwrap();
+        let fixture = make_fixture(&temp).await;
+        let target_manager = make_manager(&temp, "node-2", false).await;
+        let target = LoopbackTarget::new(&temp, Arc::clone(&target_manager));
+        *target.source.lock() = Some((Arc::clone(&fixture.source), Arc::clone(&fixture.transfers)));
+
+        let job = start_job(&fixture);
+        let installed = fixture.sender.run(&job, &target).await.unwrap();
+        assert!(!installed.merged);
+
+        // The captured write was replayed after the install
+        assert_eq!(
+            target_manager
+                .stats("products")
+                .await
+                .unwrap()
+                .document_count,
+            21
+        );
+        assert!(target_manager
+            .get("products", "p7")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(target_manager
+            .get("products", "late")
+            .await
+            .unwrap()
+            .is_some());
+
+        let shard = fixture.cluster_state.get_shard("products-shard-0").unwrap();
+        assert_eq!(shard.primary_node, "node-2");
+        assert_eq!(shard.state, ShardState::Active);
+        assert!(fixture.source.get_schema("products").is_none());
+        assert!(fixture.transfers.capture("products").is_none());
+
+        let status = fixture.engine.status();
+        assert_eq!(status.completed_moves, 1);
+        assert_eq!(
+            status.current_operations[0].status,
+            OperationStatus::Completed
+        );
+        assert!(status.current_operations[0].total_bytes > 0);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_merges_into_existing_collection() {
+        let temp = TempDir::new().unwrap();
+        let fixture = make_fixture(&temp).await;
+        let target_manager = make_manager(&temp, "node-2", true).await;
+        target_manager
+            .index("products", vec![doc("b1", "already here")])
+            .await
+            .unwrap();
+        let target = LoopbackTarget::new(&temp, Arc::clone(&target_manager));
+
+        let job = start_job(&fixture);
+        let installed = fixture.sender.run(&job, &target).await.unwrap();
+        assert!(installed.merged);
+        assert_eq!(installed.document_count, 21);
+        assert!(target_manager
+            .get("products", "b1")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(target_manager
+            .get("products", "p0")
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            target_manager.list_collections(),
+            vec!["products".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_copy_resumes_from_staged_bytes() {
+        let temp = TempDir::new().unwrap();
+        let fixture = make_fixture(&temp).await;
+        let target_manager = make_manager(&temp, "node-2", false).await;
+        let mut target = LoopbackTarget::new(&temp, Arc::clone(&target_manager));
+        target.fail_chunk = Some(3);
+
+        let job = start_job(&fixture);
+        let sender = ShardSender {
+            chunk_bytes: 64,
+            ..fixture.sender
+        };
+        let chunks_needed: u64 = sender
+            .list_files("products")
+            .await
+            .unwrap()
+            .iter()
+            .map(|f| f.size.div_ceil(64))
+            .sum();
+        sender.run(&job, &target).await.unwrap();
+
+        // Chunks staged before the failure are not sent again
+        assert_eq!(
+            target.chunks.load(Ordering::SeqCst) as u64,
+            chunks_needed + 1
+        );
+        assert_eq!(
+            target_manager
+                .stats("products")
+                .await
+                .unwrap()
+                .document_count,
+            20
+        );
+        assert_eq!(fixture.engine.status().completed_moves, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_transfer_keeps_shard_and_can_resume() {
+        let temp = TempDir::new().unwrap();
+        let fixture = make_fixture(&temp).await;
+        let target_manager = make_manager(&temp, "node-2", false).await;
+        let target = FailingTarget(LoopbackTarget::new(&temp, target_manager));
+
+        let job = start_job(&fixture);
+        assert!(fixture.sender.run(&job, &target).await.is_err());
+
+        let shard = fixture.cluster_state.get_shard("products-shard-0").unwrap();
+        assert_eq!(shard.primary_node, "node-1");
+        assert_eq!(shard.state, ShardState::Active);
+        assert!(fixture.source.get_schema("products").is_some());
+        assert!(fixture.transfers.capture("products").is_none());
+
+        let status = fixture.engine.status();
+        assert_eq!(status.failed_moves, 1);
+        assert!(status.last_error.is_some());
+
+        let (transfer_id, resumed) = fixture
+            .transfers
+            .start("products-shard-0", "products", "node-2")
+            .unwrap();
+        assert!(resumed);
+        assert_eq!(transfer_id, job.transfer_id);
+    }
+
+    /// Target that never accepts chunks
+    struct FailingTarget(LoopbackTarget);
+
+    #[async_trait]
+    impl TransferTarget for FailingTarget {
+        async fn begin(&self, request: RpcBeginShardReceive) -> Result<RpcShardReceiveState> {
+            self.0.begin(request).await
+        }
+
+        async fn write_chunk(&self, _chunk: RpcShardChunk) -> Result<u64> {
+            Err(ClusterError::Connection("target unreachable".to_string()))
+        }
+
+        async fn complete(&self, transfer_id: &str) -> Result<RpcShardInstallResult> {
+            self.0.complete(transfer_id).await
+        }
+
+        async fn index(&self, collection: &str, docs: Vec<RpcDocument>) -> Result<()> {
+            self.0.index(collection, docs).await
+        }
+
+        async fn delete(&self, collection: &str, ids: Vec<String>) -> Result<()> {
+            self.0.delete(collection, ids).await
+        }
+    }
+}
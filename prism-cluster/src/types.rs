@@ -3,9 +3,11 @@
 //! These types are serializable wrappers around core Prism types,
 //! designed for efficient bincode serialization over the wire.
 
+use crate::config::ConflictResolution;
+use crate::metadata::{MetadataCommand, MetadataSnapshot};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Serializable query type for RPC transport.
 /// Mirrors prism::backends::Query but with Serialize/Deserialize derives.
@@ -27,6 +29,21 @@ pub struct RpcQuery {
     pub score_function: Option<String>,
     #[serde(default)]
     pub skip_ranking: bool,
+    /// Aggregations computed by each shard over its matches and merged by
+    /// the coordinator
+    #[serde(default)]
+    pub aggregations: Vec<prism::aggregations::AggregationRequest>,
+    /// Query embedding for hybrid search; shards then return their text and
+    /// vector hits separately and the coordinator fuses them
+    #[serde(default)]
+    pub query_vector: Option<Vec<f32>>,
+    /// Collect global term statistics before searching so BM25 scores are
+    /// comparable across shards
+    #[serde(default)]
+    pub dfs: bool,
+    /// Global term statistics from the DFS phase, used by shards for BM25
+    #[serde(default)]
+    pub term_statistics: Option<prism::backends::TermStatistics>,
 }
 
 impl From<prism::backends::Query> for RpcQuery {
@@ -44,6 +61,10 @@ impl From<prism::backends::Query> for RpcQuery {
             min_score: q.min_score,
             score_function: q.score_function,
             skip_ranking: q.skip_ranking,
+            aggregations: Vec::new(),
+            query_vector: None,
+            dfs: false,
+            term_statistics: None,
         }
     }
 }
@@ -75,6 +96,8 @@ pub struct RpcHighlightConfig {
     pub post_tag: String,
     pub fragment_size: usize,
     pub number_of_fragments: usize,
+    #[serde(default)]
+    pub field_options: HashMap<String, prism::backends::HighlightFieldOptions>,
 }
 
 impl From<prism::backends::HighlightConfig> for RpcHighlightConfig {
@@ -85,6 +108,7 @@ impl From<prism::backends::HighlightConfig> for RpcHighlightConfig {
             post_tag: h.post_tag,
             fragment_size: h.fragment_size,
             number_of_fragments: h.number_of_fragments,
+            field_options: h.field_options,
         }
     }
 }
@@ -97,6 +121,7 @@ impl From<RpcHighlightConfig> for prism::backends::HighlightConfig {
             post_tag: h.post_tag,
             fragment_size: h.fragment_size,
             number_of_fragments: h.number_of_fragments,
+            field_options: h.field_options,
         }
     }
 }
@@ -163,6 +188,15 @@ pub struct RpcSearchResults {
     pub results: Vec<RpcSearchResult>,
     pub total: usize,
     pub latency_ms: u64,
+    /// Partial aggregation results of this shard, by aggregation name
+    #[serde(default)]
+    pub aggregations: HashMap<String, prism::aggregations::AggregationResult>,
+    /// Text hits of a hybrid search, with raw scores
+    #[serde(default)]
+    pub text_results: Vec<RpcSearchResult>,
+    /// Vector hits of a hybrid search, with raw scores
+    #[serde(default)]
+    pub vector_results: Vec<RpcSearchResult>,
 }
 
 impl From<prism::backends::SearchResults> for RpcSearchResults {
@@ -171,6 +205,9 @@ impl From<prism::backends::SearchResults> for RpcSearchResults {
             results: r.results.into_iter().map(RpcSearchResult::from).collect(),
             total: r.total,
             latency_ms: r.latency_ms,
+            aggregations: HashMap::new(),
+            text_results: Vec::new(),
+            vector_results: Vec::new(),
         }
     }
 }
@@ -372,6 +409,103 @@ pub struct ShardTransferResponse {
     pub error: Option<String>,
 }
 
+// ========================================
+// Shard Data Transfer Types
+// ========================================
+
+/// A persisted file of a shard, relative to its collection
+/// (e.g. `tantivy/default/meta.json`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcShardFile {
+    /// Path relative to the collection
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+}
+
+/// Request to start (or resume) receiving a shard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcBeginShardReceive {
+    /// Transfer ID, stable across resumed attempts
+    pub transfer_id: String,
+    /// Shard being transferred
+    pub shard_id: String,
+    /// Collection the shard belongs to
+    pub collection: String,
+    /// Collection schema
+    pub schema: Value,
+    /// Files making up the shard
+    pub files: Vec<RpcShardFile>,
+}
+
+/// What the target already holds for a transfer
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RpcShardReceiveState {
+    /// Files with data staged on the target
+    pub files: Vec<RpcStagedFile>,
+}
+
+/// A file partially or fully staged on the target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcStagedFile {
+    /// Path relative to the collection
+    pub path: String,
+    /// Bytes staged so far
+    pub staged_bytes: u64,
+    /// Checksum of the complete file, once verified
+    pub checksum: Option<String>,
+}
+
+/// A chunk of a shard file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcShardChunk {
+    /// Transfer ID
+    pub transfer_id: String,
+    /// Path relative to the collection
+    pub path: String,
+    /// Offset of this chunk in the file
+    pub offset: u64,
+    /// Chunk data
+    pub data: Vec<u8>,
+    /// SHA-256 of `data`
+    pub checksum: String,
+    /// SHA-256 of the whole file, sent with its last chunk
+    pub file_checksum: Option<String>,
+}
+
+/// Result of installing a received shard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcShardInstallResult {
+    /// Collection the shard was installed into
+    pub collection: String,
+    /// Whether documents were merged into an existing collection
+    /// rather than the files being installed as-is
+    pub merged: bool,
+    /// Documents in the collection after installing
+    pub document_count: u64,
+}
+
+/// Progress of a single shard move for RPC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRebalanceOperation {
+    /// Shard being moved
+    pub shard_id: String,
+    /// Source node
+    pub from_node: String,
+    /// Target node
+    pub to_node: String,
+    /// Transfer ID when the move copies data
+    pub transfer_id: Option<String>,
+    /// Progress (0.0 to 1.0)
+    pub progress: f64,
+    /// Bytes transferred
+    pub bytes_transferred: u64,
+    /// Total bytes to transfer
+    pub total_bytes: u64,
+    /// Status: pending, transferring, verifying, completed, failed, cancelled
+    pub status: String,
+}
+
 /// Request to trigger rebalancing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TriggerRebalanceRequest {
@@ -400,6 +534,231 @@ pub struct RpcRebalanceStatus {
     pub started_at: Option<u64>,
     /// Last error
     pub last_error: Option<String>,
+    /// Shard moves in progress or recently finished
+    #[serde(default)]
+    pub operations: Vec<RpcRebalanceOperation>,
+}
+
+// ========================================
+// Write Replication Types
+// ========================================
+
+/// A write applied to every copy of a shard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RpcWriteOp {
+    /// Index (upsert) documents
+    Index(Vec<RpcDocument>),
+    /// Delete documents by ID
+    Delete(Vec<String>),
+}
+
+/// A write sent to the primary of a shard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcPrimaryWrite {
+    /// Shard written to
+    pub shard_id: String,
+    /// Collection the shard belongs to
+    pub collection: String,
+    /// The write
+    pub op: RpcWriteOp,
+}
+
+/// Acknowledgement of a replicated write
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcWriteAck {
+    /// Shard written to
+    pub shard_id: String,
+    /// Sequence number assigned by the primary
+    pub seq_no: u64,
+    /// Term of the primary that assigned it
+    pub primary_term: u64,
+    /// Copies that applied the write, primary included
+    pub acks: usize,
+    /// Copies required to acknowledge the write
+    pub required: usize,
+    /// Replicas caught up with the primary after this write
+    pub in_sync_replicas: Vec<String>,
+}
+
+/// A write forwarded by the primary to a replica
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcReplicaWrite {
+    /// Shard written to
+    pub shard_id: String,
+    /// Collection the shard belongs to
+    pub collection: String,
+    /// Node holding the primary
+    pub primary_node: String,
+    /// Term of the primary
+    pub primary_term: u64,
+    /// Sequence number of the write
+    pub seq_no: u64,
+    /// Hybrid logical clock timestamp assigned by the primary
+    #[serde(default)]
+    pub timestamp: u64,
+    /// The write
+    pub op: RpcWriteOp,
+}
+
+/// A write in the primary's operation log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcReplicatedOp {
+    /// Sequence number of the write
+    pub seq_no: u64,
+    /// Hybrid logical clock timestamp assigned by the primary
+    #[serde(default)]
+    pub timestamp: u64,
+    /// The write
+    pub op: RpcWriteOp,
+}
+
+/// Request for operations a replica is missing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcOperationsRequest {
+    /// Shard to read the log of
+    pub shard_id: String,
+    /// First sequence number wanted
+    pub from_seq: u64,
+}
+
+/// Operations from the primary's log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcOperations {
+    /// Term of the primary
+    pub primary_term: u64,
+    /// Highest sequence number applied on the primary
+    pub checkpoint: u64,
+    /// Operations from the requested sequence number on, in order
+    pub ops: Vec<RpcReplicatedOp>,
+}
+
+/// Version of the last write to a document on a shard copy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcDocVersion {
+    /// Term of the primary that assigned the write
+    pub primary_term: u64,
+    /// Sequence number of the write within that term
+    pub seq_no: u64,
+    /// Hybrid logical clock timestamp of the write
+    pub timestamp: u64,
+    /// Whether the write deleted the document
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+/// Replication progress of a shard copy, with where earlier terms ended
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RpcShardCheckpoint {
+    /// Current primary term
+    pub primary_term: u64,
+    /// Highest sequence number applied in the current term
+    pub checkpoint: u64,
+    /// Last sequence number applied in each earlier term, by term
+    #[serde(default)]
+    pub previous_terms: BTreeMap<u64, u64>,
+}
+
+/// Request for documents of a shard with their versions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcVersionedDocumentsRequest {
+    /// Shard the documents belong to
+    pub shard_id: String,
+    /// Collection the shard belongs to
+    pub collection: String,
+    /// Document IDs
+    pub ids: Vec<String>,
+}
+
+/// A document with the version of its last write
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcVersionedDocument {
+    /// Document ID
+    pub id: String,
+    /// Version of the last write, if the copy tracked one
+    pub version: Option<RpcDocVersion>,
+    /// Current document, `None` if it does not exist
+    pub document: Option<RpcDocument>,
+}
+
+/// Request for a page of the documents of one shard, read from a node
+/// holding it while its collection is resharded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcShardScanRequest {
+    /// Collection the shard belongs to
+    pub collection: String,
+    /// Number of the shard to scan
+    pub shard_number: u32,
+    /// Number of shards the documents are hashed over
+    pub shard_count: usize,
+    /// Position in the node's sorted document IDs to resume from
+    pub offset: usize,
+    /// Maximum number of documents to return
+    pub limit: usize,
+}
+
+/// A page of the documents of a shard
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RpcShardScan {
+    /// Documents of the shard
+    pub documents: Vec<RpcDocument>,
+    /// Offset of the next page, `None` once the scan is complete
+    pub next_offset: Option<usize>,
+}
+
+/// A shard of a leader collection and where its operation log is read,
+/// for a follower cluster
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcLeaderShard {
+    /// Shard ID
+    pub shard_id: String,
+    /// Shard number
+    pub shard_number: u32,
+    /// Node holding the primary
+    pub primary_node: String,
+    /// Address of the primary's node
+    pub primary_address: String,
+}
+
+/// Which side of a conflicting write was kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RpcConflictWinner {
+    /// The write made on the reconciled copy during the partition
+    Local,
+    /// The write made through the current primary
+    Primary,
+    /// A merge of both writes
+    Merged,
+}
+
+/// A document written on both sides of a partition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcDocumentConflict {
+    /// Collection of the document
+    pub collection: String,
+    /// Shard of the document
+    pub shard_id: String,
+    /// Document ID
+    pub document_id: String,
+    /// Node whose copy diverged
+    pub local_node: String,
+    /// Primary the copy was reconciled with
+    pub primary_node: String,
+    /// Version written on the diverged copy
+    pub local_version: RpcDocVersion,
+    /// Version written through the primary
+    pub primary_version: RpcDocVersion,
+    /// Document on the diverged copy, `None` if deleted
+    pub local_document: Option<RpcDocument>,
+    /// Document on the primary, `None` if deleted
+    pub primary_document: Option<RpcDocument>,
+    /// Strategy applied
+    pub resolution: ConflictResolution,
+    /// Side that was kept
+    pub winner: RpcConflictWinner,
+    /// When the conflict was resolved (Unix epoch seconds)
+    pub resolved_at: u64,
 }
 
 // ================================
@@ -439,6 +798,9 @@ pub struct RpcClusterHealth {
     pub total_count: usize,
     /// Is quorum available (majority of nodes alive)
     pub quorum_available: bool,
+    /// Documents written on both sides of a healed partition
+    #[serde(default)]
+    pub conflicts: Vec<RpcDocumentConflict>,
 }
 
 /// Response to heartbeat request
@@ -458,6 +820,106 @@ pub struct RpcHeartbeatResponse {
     /// Minimum protocol version this node supports
     #[serde(default)]
     pub min_supported_version: u32,
+    /// Optional protocol features this node supports
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+/// What a node speaks, exchanged when a connection is opened
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcHandshake {
+    /// Node ID
+    pub node_id: String,
+    /// Node version
+    pub version: String,
+    /// Highest protocol version the node speaks
+    pub protocol_version: u32,
+    /// Lowest protocol version the node speaks
+    pub min_supported_version: u32,
+    /// Optional features the node supports
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+// ================================
+// Metadata Log Types
+// ================================
+
+/// An entry in the replicated metadata log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRaftEntry {
+    /// Position in the log, starting at 1
+    pub index: u64,
+    /// Term of the leader that appended the entry
+    pub term: u64,
+    /// The metadata change
+    pub command: MetadataCommand,
+}
+
+/// A candidate asking for a node's vote
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequestVote {
+    /// Candidate's term
+    pub term: u64,
+    /// Address of the candidate
+    pub candidate: String,
+    /// Index of the candidate's last log entry
+    pub last_log_index: u64,
+    /// Term of the candidate's last log entry
+    pub last_log_term: u64,
+}
+
+/// Answer to a vote request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcVoteResponse {
+    /// Voter's term, for the candidate to step down if it is behind
+    pub term: u64,
+    /// Whether the candidate got the vote
+    pub vote_granted: bool,
+}
+
+/// Log entries sent by the leader, empty as a heartbeat
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcAppendEntries {
+    /// Leader's term
+    pub term: u64,
+    /// Address of the leader
+    pub leader: String,
+    /// Index of the entry preceding the new ones
+    pub prev_log_index: u64,
+    /// Term of the entry preceding the new ones
+    pub prev_log_term: u64,
+    /// Entries to append
+    pub entries: Vec<RpcRaftEntry>,
+    /// Leader's commit index
+    pub leader_commit: u64,
+}
+
+/// Answer to an append request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcAppendEntriesResponse {
+    /// Follower's term, for the leader to step down if it is behind
+    pub term: u64,
+    /// Whether the follower's log matched and took the entries
+    pub success: bool,
+    /// Last entry matching the leader's log on success, the follower's
+    /// last entry otherwise
+    pub match_index: u64,
+}
+
+/// Snapshot sent to a follower missing entries the leader compacted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcInstallSnapshot {
+    /// Leader's term
+    pub term: u64,
+    /// Address of the leader
+    pub leader: String,
+    /// Index of the last entry the snapshot covers
+    pub last_included_index: u64,
+    /// Term of the last entry the snapshot covers
+    pub last_included_term: u64,
+    /// Metadata as of the last included entry
+    pub snapshot: MetadataSnapshot,
 }
 
 // ================================
@@ -527,6 +989,7 @@ mod tests {
         assert_eq!(response.node_id, "node-1");
         assert_eq!(response.protocol_version, 0);
         assert_eq!(response.min_supported_version, 0);
+        assert!(response.features.is_empty());
     }
 
     #[test]
@@ -572,12 +1035,14 @@ mod tests {
             "uptime_secs": 200,
             "timestamp": 1700000000,
             "protocol_version": 2,
-            "min_supported_version": 1
+            "min_supported_version": 1,
+            "features": ["raft"]
         }"#;
 
         let response: RpcHeartbeatResponse = serde_json::from_str(json).unwrap();
         assert_eq!(response.protocol_version, 2);
         assert_eq!(response.min_supported_version, 1);
+        assert_eq!(response.features, vec!["raft"]);
     }
 
     // --- From/Into round-trip tests ---
@@ -598,6 +1063,7 @@ mod tests {
                 post_tag: "</em>".into(),
                 fragment_size: 100,
                 number_of_fragments: 3,
+                field_options: Default::default(),
             }),
             rrf_k: Some(60),
             min_score: Some(0.5),
@@ -653,6 +1119,7 @@ mod tests {
             post_tag: "</b>".into(),
             fragment_size: 200,
             number_of_fragments: 5,
+            field_options: Default::default(),
         };
 
         let rpc: RpcHighlightConfig = config.clone().into();
@@ -790,6 +1257,10 @@ mod tests {
                 min_score: None,
                 score_function: None,
                 skip_ranking: false,
+                aggregations: vec![],
+                query_vector: None,
+                dfs: false,
+                term_statistics: None,
             },
             max_docs: 0,
             dry_run: true,
@@ -833,6 +1304,10 @@ mod tests {
                 min_score: None,
                 score_function: None,
                 skip_ranking: false,
+                aggregations: vec![],
+                query_vector: None,
+                dfs: false,
+                term_statistics: None,
             },
             source_node: Some("node-1:9100".into()),
             batch_size: 500,
@@ -894,6 +1369,7 @@ mod tests {
             dead_count: 0,
             total_count: 1,
             quorum_available: true,
+            conflicts: vec![],
         };
 
         let json = serde_json::to_string(&health).unwrap();
@@ -0,0 +1,111 @@
+//! Helpers shared by the cluster integration tests, which start real
+//! `ClusterServer`s on localhost.
+//!
+//! Each test file is its own crate and uses only some of these.
+#![allow(dead_code)]
+
+use prism::backends::{TextBackend, VectorBackend};
+use prism::collection::CollectionManager;
+use prism_cluster::{
+    ClusterConfig, ClusterServer, ClusterTlsConfig, MetadataConfig, OperationLogConfig, RaftNode,
+    RebalancingConfig,
+};
+use std::net::UdpSocket;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// Write a self-signed certificate for localhost, returning the TLS config
+pub fn tls_config(dir: &Path) -> ClusterTlsConfig {
+    let cert =
+        rcgen::generate_simple_self_signed(vec!["localhost".to_string(), "127.0.0.1".to_string()])
+            .unwrap();
+    let cert_path = dir.join("cluster-cert.pem");
+    let key_path = dir.join("cluster-key.pem");
+    std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+    std::fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+    ClusterTlsConfig {
+        enabled: true,
+        cert_path,
+        key_path,
+        ca_cert_path: None,
+        skip_verify: true,
+    }
+}
+
+/// Addresses of free UDP ports on localhost
+pub fn free_addresses(count: usize) -> Vec<String> {
+    let sockets: Vec<UdpSocket> = (0..count)
+        .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+        .collect();
+    sockets
+        .iter()
+        .map(|socket| socket.local_addr().unwrap().to_string())
+        .collect()
+}
+
+pub fn config(node_id: &str, address: &str, seeds: &[String], dir: &Path) -> ClusterConfig {
+    ClusterConfig {
+        enabled: true,
+        node_id: node_id.to_string(),
+        bind_addr: address.to_string(),
+        seed_nodes: seeds.to_vec(),
+        connect_timeout_ms: 1000,
+        request_timeout_ms: 5000,
+        tls: tls_config(dir),
+        rebalancing: RebalancingConfig {
+            transfer_staging_dir: Some(dir.join("transfers")),
+            ..Default::default()
+        },
+        op_log: OperationLogConfig {
+            state_dir: Some(dir.join("replication")),
+            ..Default::default()
+        },
+        metadata: MetadataConfig {
+            election_timeout_ms: 300,
+            heartbeat_interval_ms: 50,
+            state_dir: Some(dir.join("metadata")),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// A running `ClusterServer` and the dir holding its data
+pub struct Node {
+    pub address: String,
+    pub metadata: Arc<RaftNode>,
+    _dir: TempDir,
+}
+
+pub async fn start_node(node_id: &str, address: &str, seeds: &[String]) -> Node {
+    start_node_with(node_id, address, seeds, |_| {}).await
+}
+
+/// Start a server whose config `configure` adjusts first
+pub async fn start_node_with(
+    node_id: &str,
+    address: &str,
+    seeds: &[String],
+    configure: impl FnOnce(&mut ClusterConfig),
+) -> Node {
+    let dir = TempDir::new().unwrap();
+    let schemas_dir = dir.path().join("schemas");
+    std::fs::create_dir_all(&schemas_dir).unwrap();
+    let text_backend = Arc::new(TextBackend::new(dir.path().join("data")).unwrap());
+    let vector_backend = Arc::new(VectorBackend::new(dir.path().join("data")).unwrap());
+    let manager =
+        Arc::new(CollectionManager::new(&schemas_dir, text_backend, vector_backend, None).unwrap());
+    manager.initialize().await.unwrap();
+
+    let mut config = config(node_id, address, seeds, dir.path());
+    configure(&mut config);
+    let server = ClusterServer::new(config, manager);
+    let metadata = server.metadata();
+    tokio::spawn(server.serve());
+    Node {
+        address: address.to_string(),
+        metadata,
+        _dir: dir,
+    }
+}
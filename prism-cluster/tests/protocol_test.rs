@@ -0,0 +1,170 @@
+//! Feature gating between nodes of different releases.
+//!
+//! The peer here is a real `ClusterServer` configured to speak only protocol
+//! version 1, so the handshake offers no optional features, as a node from
+//! before them would.
+
+mod common;
+
+use common::{config, free_addresses, start_node_with, Node};
+use prism_cluster::protocol::LEGACY_PROTOCOL_VERSION;
+use prism_cluster::{
+    ClusterClient, ClusterError, MetadataCommand, RpcBeginShardReceive, RpcOperationsRequest,
+    RpcPrimaryWrite, RpcRequestVote, RpcShardChunk, RpcVersionedDocumentsRequest, RpcWriteOp,
+    PROTOCOL_VERSION,
+};
+use tempfile::TempDir;
+
+async fn start_peer(protocol_version: u32) -> Node {
+    let addresses = free_addresses(1);
+    start_node_with("peer", &addresses[0], &addresses, |config| {
+        config.protocol_version = protocol_version;
+        config.min_supported_version = LEGACY_PROTOCOL_VERSION;
+    })
+    .await
+}
+
+async fn client(dir: &TempDir) -> ClusterClient {
+    ClusterClient::new(config("client", "127.0.0.1:0", &[], dir.path()))
+        .await
+        .unwrap()
+}
+
+fn vote(term: u64) -> RpcRequestVote {
+    RpcRequestVote {
+        term,
+        candidate: "127.0.0.1:1".to_string(),
+        last_log_index: u64::MAX,
+        last_log_term: term,
+    }
+}
+
+fn assert_refused<T: std::fmt::Debug>(rpc: &str, result: Result<T, ClusterError>) {
+    match result {
+        Err(ClusterError::IncompatibleVersion(message)) => {
+            assert!(message.contains("does not support"), "{}: {}", rpc, message)
+        }
+        other => panic!("{} was sent to a version 1 peer: {:?}", rpc, other),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_version_1_peer_is_not_sent_later_rpcs() {
+    let peer = start_peer(LEGACY_PROTOCOL_VERSION).await;
+    let dir = TempDir::new().unwrap();
+    let client = client(&dir).await;
+    let addr = peer.address.as_str();
+
+    // RPCs from protocol version 1 still go through
+    assert!(client.ping(addr).await.is_ok());
+
+    assert_refused(
+        "primary_write",
+        client
+            .primary_write(
+                addr,
+                RpcPrimaryWrite {
+                    shard_id: "docs-0".to_string(),
+                    collection: "docs".to_string(),
+                    op: RpcWriteOp::Delete(vec!["1".to_string()]),
+                },
+            )
+            .await,
+    );
+    assert_refused(
+        "get_operations",
+        client
+            .get_operations(
+                addr,
+                RpcOperationsRequest {
+                    shard_id: "docs-0".to_string(),
+                    from_seq: 0,
+                },
+            )
+            .await,
+    );
+    assert_refused(
+        "shard_checkpoint",
+        client.shard_checkpoint(addr, "docs-0").await,
+    );
+    assert_refused(
+        "get_versioned_documents",
+        client
+            .get_versioned_documents(
+                addr,
+                RpcVersionedDocumentsRequest {
+                    shard_id: "docs-0".to_string(),
+                    collection: "docs".to_string(),
+                    ids: vec!["1".to_string()],
+                },
+            )
+            .await,
+    );
+    assert_refused(
+        "begin_shard_receive",
+        client
+            .begin_shard_receive(
+                addr,
+                RpcBeginShardReceive {
+                    transfer_id: "t-1".to_string(),
+                    shard_id: "docs-0".to_string(),
+                    collection: "docs".to_string(),
+                    schema: serde_json::Value::Null,
+                    files: Vec::new(),
+                },
+            )
+            .await,
+    );
+    assert_refused(
+        "write_shard_chunk",
+        client
+            .write_shard_chunk(
+                addr,
+                RpcShardChunk {
+                    transfer_id: "t-1".to_string(),
+                    path: "data".to_string(),
+                    offset: 0,
+                    data: Vec::new(),
+                    checksum: String::new(),
+                    file_checksum: None,
+                },
+            )
+            .await,
+    );
+    assert_refused(
+        "complete_shard_receive",
+        client.complete_shard_receive(addr, "t-1").await,
+    );
+    assert_refused(
+        "raft_request_vote",
+        client.raft_request_vote(addr, vote(1000)).await,
+    );
+    assert_refused(
+        "propose_metadata",
+        client
+            .propose_metadata(
+                addr,
+                MetadataCommand::RemoveNode {
+                    node_id: "node-a".to_string(),
+                },
+            )
+            .await,
+    );
+
+    // Refused before sending: the peer never saw the higher term
+    assert!(peer.metadata.status().await.term < 1000);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_current_peer_is_sent_later_rpcs() {
+    let peer = start_peer(PROTOCOL_VERSION).await;
+    let dir = TempDir::new().unwrap();
+    let client = client(&dir).await;
+
+    let response = client
+        .raft_request_vote(&peer.address, vote(1000))
+        .await
+        .unwrap();
+    assert_eq!(response.term, 1000);
+    assert_eq!(peer.metadata.status().await.term, 1000);
+}
@@ -0,0 +1,137 @@
+//! Metadata log replicated between cluster servers over QUIC.
+//!
+//! The unit tests in `metadata::raft` drive `RaftNode`s through an in-process
+//! transport. These start real `ClusterServer`s on localhost, so votes,
+//! appends and forwarded writes go through tarpc serialization, QUIC
+//! connections and the RPC handlers.
+
+mod common;
+
+use common::{config, free_addresses, start_node, Node};
+use prism_cluster::{
+    ClusterClient, ClusterError, MetadataCommand, NodeInfo, NodeTopology, RaftRole,
+};
+use std::time::Duration;
+use tempfile::TempDir;
+
+async fn start_cluster() -> Vec<Node> {
+    let addresses = free_addresses(3);
+    let mut nodes = Vec::new();
+    for (i, address) in addresses.iter().enumerate() {
+        nodes.push(start_node(&format!("node-{}", i + 1), address, &addresses).await);
+    }
+    nodes
+}
+
+fn register(node_id: &str) -> MetadataCommand {
+    MetadataCommand::RegisterNode(NodeInfo {
+        node_id: node_id.to_string(),
+        address: format!("{}:9080", node_id),
+        topology: NodeTopology::default(),
+        healthy: true,
+        shard_count: 0,
+        disk_used_bytes: 0,
+        disk_total_bytes: 0,
+        index_size_bytes: 0,
+        draining: false,
+    })
+}
+
+/// Poll until `check` holds, failing after a few seconds
+async fn eventually<F, Fut>(what: &str, mut check: F)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = bool>,
+{
+    for _ in 0..200 {
+        if check().await {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("timed out waiting for {}", what);
+}
+
+/// Index of the only leader among `nodes` that every node follows
+async fn wait_for_leader(nodes: &[Node]) -> usize {
+    for _ in 0..200 {
+        let mut leaders = Vec::new();
+        let mut followed = Vec::new();
+        for (i, node) in nodes.iter().enumerate() {
+            let status = node.metadata.status().await;
+            if status.role == RaftRole::Leader {
+                leaders.push(i);
+            }
+            followed.push(status.leader);
+        }
+        if let [leader] = leaders[..] {
+            let address = &nodes[leader].address;
+            if followed.iter().all(|l| l.as_ref() == Some(address)) {
+                return leader;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("no single leader was elected");
+}
+
+async fn all_registered(nodes: &[Node], node_id: &str) -> bool {
+    nodes.iter().all(|node| {
+        node.metadata
+            .metadata()
+            .cluster_state()
+            .get_node(node_id)
+            .is_some()
+    })
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_servers_elect_leader_and_replicate_metadata() {
+    let nodes = start_cluster().await;
+    let leader = wait_for_leader(&nodes).await;
+    let follower = (leader + 1) % nodes.len();
+
+    // Proposed to the leader through the client
+    let dir = TempDir::new().unwrap();
+    let client = ClusterClient::new(config("client", "127.0.0.1:0", &[], dir.path()))
+        .await
+        .unwrap();
+    let index = client
+        .propose_metadata(&nodes[leader].address, register("node-a"))
+        .await
+        .unwrap();
+    assert!(index > 0);
+    eventually("node-a on every node", || all_registered(&nodes, "node-a")).await;
+
+    // Followers refuse proposals and name the leader
+    let err = client
+        .propose_metadata(&nodes[follower].address, register("node-b"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ClusterError::NotLeader(_)), "{:?}", err);
+    assert!(err.to_string().contains(&nodes[leader].address), "{}", err);
+
+    // Written on a follower, which forwards it to the leader over RPC
+    nodes[follower]
+        .metadata
+        .write(register("node-b"))
+        .await
+        .unwrap();
+    assert!(nodes[follower]
+        .metadata
+        .metadata()
+        .cluster_state()
+        .get_node("node-b")
+        .is_some());
+    eventually("node-b on every node", || all_registered(&nodes, "node-b")).await;
+
+    // Every node went through the same log
+    eventually("logs to converge", || async {
+        let mut applied = Vec::new();
+        for node in &nodes {
+            applied.push(node.metadata.status().await.applied_index);
+        }
+        applied.windows(2).all(|w| w[0] == w[1])
+    })
+    .await;
+}
@@ -5,9 +5,10 @@ use crate::error::EsCompatError;
 use crate::query::{BulkAction, BulkActionMeta};
 use crate::response::{BulkItemResponse, BulkItemResult, EsBulkResponse, EsError, ShardStats};
 use axum::body::Bytes;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::Json;
 use prism::backends::Document;
+use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::time::Instant;
@@ -21,9 +22,17 @@ use uuid::Uuid;
 /// to prevent resource exhaustion.
 const MAX_BULK_ACTIONS: usize = 10_000;
 
+#[derive(Debug, Default, Deserialize)]
+pub struct BulkParams {
+    /// Ingest pipeline for items that do not name their own
+    #[serde(default)]
+    pub pipeline: Option<String>,
+}
+
 pub async fn bulk_handler(
     State(state): State<EsCompatState>,
     default_index: Option<Path<String>>,
+    Query(params): Query<BulkParams>,
     body: Bytes,
 ) -> Result<Json<EsBulkResponse>, EsCompatError> {
     let start = Instant::now();
@@ -49,7 +58,18 @@ pub async fn bulk_handler(
 
     for action in actions {
         match action {
-            BulkAction::Index { index, id, doc } | BulkAction::Create { index, id, doc } => {
+            BulkAction::Index {
+                index,
+                id,
+                doc,
+                pipeline,
+            }
+            | BulkAction::Create {
+                index,
+                id,
+                doc,
+                pipeline,
+            } => {
                 let doc_id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
                 let fields = match doc {
                     Value::Object(obj) => obj.into_iter().collect(),
@@ -75,13 +95,34 @@ pub async fn bulk_handler(
                     }
                 };
 
-                by_index.entry(index).or_default().push((
-                    doc_id,
-                    Document {
-                        id: String::new(),
-                        fields,
-                    },
-                ));
+                let mut document = Document {
+                    id: doc_id.clone(),
+                    fields,
+                };
+                if let Some(pipeline) = pipeline.as_deref().or(params.pipeline.as_deref()) {
+                    if let Err(e) = state.ingest.apply(pipeline, &mut document) {
+                        items.push(BulkItemResponse {
+                            index: Some(BulkItemResult {
+                                index: index.clone(),
+                                id: doc_id,
+                                version: 1,
+                                result: "error".to_string(),
+                                shards: ShardStats::default(),
+                                status: e.status_code().as_u16(),
+                                error: Some(EsError {
+                                    error_type: e.error_type().to_string(),
+                                    reason: e.to_string(),
+                                }),
+                            }),
+                            create: None,
+                            delete: None,
+                        });
+                        has_errors = true;
+                        continue;
+                    }
+                }
+
+                by_index.entry(index).or_default().push((doc_id, document));
             }
             BulkAction::Delete { index, id } => {
                 delete_by_index.entry(index).or_default().push(id);
@@ -317,6 +358,7 @@ fn parse_bulk_body(
                 index,
                 id: index_meta.id,
                 doc,
+                pipeline: index_meta.pipeline,
             });
         } else if let Some(create_meta) = meta.create {
             let index = create_meta
@@ -338,6 +380,7 @@ fn parse_bulk_body(
                 index,
                 id: create_meta.id,
                 doc,
+                pipeline: create_meta.pipeline,
             });
         } else if let Some(delete_meta) = meta.delete {
             let index = delete_meta
@@ -386,7 +429,7 @@ mod tests {
         let actions = parse_bulk_body(&body, None).unwrap();
         assert_eq!(actions.len(), 1);
         match &actions[0] {
-            BulkAction::Index { index, id, doc } => {
+            BulkAction::Index { index, id, doc, .. } => {
                 assert_eq!(index, "products");
                 assert_eq!(id.as_deref(), Some("1"));
                 assert_eq!(doc["title"], "Widget");
@@ -455,7 +498,7 @@ mod tests {
         let actions = parse_bulk_body(&body, None).unwrap();
         assert_eq!(actions.len(), 1);
         match &actions[0] {
-            BulkAction::Create { index, id, doc } => {
+            BulkAction::Create { index, id, doc, .. } => {
                 assert_eq!(index, "products");
                 assert_eq!(id.as_deref(), Some("2"));
                 assert_eq!(doc["title"], "Gadget");
@@ -647,4 +690,24 @@ not valid json
         let actions = parse_bulk_body(&body, None).unwrap();
         assert_eq!(actions.len(), 1);
     }
+
+    #[test]
+    fn test_parse_bulk_item_pipeline() {
+        let body = make_bytes(
+            r#"{"index":{"_index":"logs","pipeline":"tidy"}}
+{"msg":"hello"}
+{"create":{"_index":"logs"}}
+{"msg":"world"}
+"#,
+        );
+        let actions = parse_bulk_body(&body, None).unwrap();
+        match &actions[0] {
+            BulkAction::Index { pipeline, .. } => assert_eq!(pipeline.as_deref(), Some("tidy")),
+            _ => panic!("Expected Index"),
+        }
+        match &actions[1] {
+            BulkAction::Create { pipeline, .. } => assert!(pipeline.is_none()),
+            _ => panic!("Expected Create"),
+        }
+    }
 }
@@ -0,0 +1,591 @@
+//! ES-compatible `_cat` endpoints
+//!
+//! Each endpoint builds a table of string cells. It is rendered as aligned
+//! text, with a header row under `?v`, or as an array of objects under
+//! `?format=json`; `?h=` picks and orders the columns and `?bytes=` fixes the
+//! unit of sizes.
+
+use crate::endpoints::cluster::{
+    cluster_health, cluster_nodes, format_bytes, health_status, local_node, local_segments,
+    md5_hash, node_ip, shard_routing,
+};
+use crate::endpoints::search::EsCompatState;
+use crate::error::EsCompatError;
+use crate::response::wildcard_matches;
+use crate::topology::TopologyShard;
+use axum::extract::{Path, Query, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use prism::ilm::AliasType;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Query parameters shared by the `_cat` endpoints
+#[derive(Debug, Default, Deserialize)]
+pub struct CatParams {
+    /// `text` (default) or `json`
+    #[serde(default)]
+    pub format: Option<String>,
+    /// Present to print a header row
+    #[serde(default)]
+    pub v: Option<String>,
+    /// Comma-separated columns to show; `*` matches any run of characters
+    #[serde(default)]
+    pub h: Option<String>,
+    /// Unit of size columns: `b`, `kb`, `mb` or `gb`
+    #[serde(default)]
+    pub bytes: Option<String>,
+}
+
+/// A column name and its short aliases
+struct Column(&'static str, &'static [&'static str]);
+
+/// Rows of string cells under fixed columns; `None` cells render as empty
+/// text or JSON `null`
+struct CatTable {
+    columns: &'static [Column],
+    rows: Vec<Vec<Option<String>>>,
+}
+
+const INDICES_COLUMNS: &[Column] = &[
+    Column("health", &["h"]),
+    Column("status", &["s"]),
+    Column("index", &["i", "idx"]),
+    Column("uuid", &["id"]),
+    Column("pri", &["p", "shards.primary"]),
+    Column("rep", &["r", "shards.replica"]),
+    Column("docs.count", &["dc", "docsCount"]),
+    Column("docs.deleted", &["dd", "docsDeleted"]),
+    Column("store.size", &["ss", "storeSize"]),
+    Column("pri.store.size", &[]),
+];
+
+const HEALTH_COLUMNS: &[Column] = &[
+    Column("epoch", &["t", "time"]),
+    Column("timestamp", &["ts", "hms"]),
+    Column("cluster", &["cl"]),
+    Column("status", &["st"]),
+    Column("node.total", &["nt", "nodeTotal"]),
+    Column("node.data", &["nd", "nodeData"]),
+    Column("shards", &["sh", "shards.total"]),
+    Column("pri", &["p", "shards.primary"]),
+    Column("relo", &["r", "shards.relocating"]),
+    Column("init", &["i", "shards.initializing"]),
+    Column("unassign", &["u", "shards.unassigned"]),
+    Column("pending_tasks", &["pt", "pendingTasks"]),
+    Column("max_task_wait_time", &["mtwt", "maxTaskWaitTime"]),
+    Column("active_shards_percent", &["asp", "activeShardsPercent"]),
+];
+
+const COUNT_COLUMNS: &[Column] = &[
+    Column("epoch", &["t", "time"]),
+    Column("timestamp", &["ts", "hms"]),
+    Column("count", &["dc", "docs.count", "docsCount"]),
+];
+
+const ALIASES_COLUMNS: &[Column] = &[
+    Column("alias", &["a"]),
+    Column("index", &["i", "idx"]),
+    Column("filter", &["f", "fi"]),
+    Column("routing.index", &["ri", "routingIndex"]),
+    Column("routing.search", &["rs", "routingSearch"]),
+    Column("is_write_index", &["w", "isWriteIndex"]),
+];
+
+const SHARDS_COLUMNS: &[Column] = &[
+    Column("index", &["i", "idx"]),
+    Column("shard", &["s", "sh"]),
+    Column("prirep", &["p", "pr", "primaryOrReplica"]),
+    Column("state", &["st"]),
+    Column("docs", &["d", "dc"]),
+    Column("store", &["sto"]),
+    Column("ip", &[]),
+    Column("node", &["n"]),
+];
+
+const SEGMENTS_COLUMNS: &[Column] = &[
+    Column("index", &["i", "idx"]),
+    Column("shard", &["s", "sh"]),
+    Column("prirep", &["p", "pr", "primaryOrReplica"]),
+    Column("ip", &[]),
+    Column("segment", &["seg"]),
+    Column("generation", &["g", "gen"]),
+    Column("docs.count", &["dc", "docsCount"]),
+    Column("docs.deleted", &["dd", "docsDeleted"]),
+    Column("size", &["si"]),
+    Column("committed", &["ic", "isCommitted"]),
+    Column("searchable", &["is", "isSearchable"]),
+];
+
+/// GET /_elastic/_cat/indices[/{index}] - One row per index
+pub async fn cat_indices_handler(
+    State(state): State<EsCompatState>,
+    index: Option<Path<String>>,
+    Query(params): Query<CatParams>,
+) -> Result<Response, EsCompatError> {
+    let collections = cat_indices(&state, index.map(|p| p.0)).await?;
+    let shards = shard_routing(&state).await;
+    let segments = local_segments(&state);
+
+    let mut rows = Vec::with_capacity(collections.len());
+    for collection in collections {
+        let copies: Vec<&TopologyShard> = shards.iter().filter(|s| s.index == collection).collect();
+        let primaries = copies.iter().filter(|s| s.primary).count();
+        let docs: u64 = copies
+            .iter()
+            .filter(|s| s.primary && s.state.is_active())
+            .map(|s| s.docs)
+            .sum();
+        let deleted = segments.get(&collection).map_or(0, |s| s.total_deleted);
+        let store: u64 = copies.iter().map(|s| s.size_bytes).sum();
+        let pri_store: u64 = copies
+            .iter()
+            .filter(|s| s.primary)
+            .map(|s| s.size_bytes)
+            .sum();
+
+        rows.push(vec![
+            Some(health_status(copies.iter().copied()).to_string()),
+            Some("open".to_string()),
+            Some(collection.clone()),
+            Some(format!("{:x}", md5_hash(&collection))),
+            Some(primaries.to_string()),
+            Some(replicas_per_primary(copies.len(), primaries).to_string()),
+            Some(docs.to_string()),
+            Some(deleted.to_string()),
+            Some(format_size(store, &params)?),
+            Some(format_size(pri_store, &params)?),
+        ]);
+    }
+
+    render(
+        CatTable {
+            columns: INDICES_COLUMNS,
+            rows,
+        },
+        &params,
+    )
+}
+
+/// GET /_elastic/_cat/health - One-line cluster health
+pub async fn cat_health_handler(
+    State(state): State<EsCompatState>,
+    Query(params): Query<CatParams>,
+) -> Result<Response, EsCompatError> {
+    let health = cluster_health(&cluster_nodes(&state), &shard_routing(&state).await);
+    let (epoch, timestamp) = now();
+
+    let row = vec![
+        epoch,
+        timestamp,
+        health.cluster_name,
+        health.status,
+        health.number_of_nodes.to_string(),
+        health.number_of_data_nodes.to_string(),
+        health.active_shards.to_string(),
+        health.active_primary_shards.to_string(),
+        health.relocating_shards.to_string(),
+        health.initializing_shards.to_string(),
+        health.unassigned_shards.to_string(),
+        health.number_of_pending_tasks.to_string(),
+        "-".to_string(),
+        format!("{:.1}%", health.active_shards_percent_as_number),
+    ];
+    render(
+        CatTable {
+            columns: HEALTH_COLUMNS,
+            rows: vec![row.into_iter().map(Some).collect()],
+        },
+        &params,
+    )
+}
+
+/// GET /_elastic/_cat/count[/{index}] - Document count
+pub async fn cat_count_handler(
+    State(state): State<EsCompatState>,
+    index: Option<Path<String>>,
+    Query(params): Query<CatParams>,
+) -> Result<Response, EsCompatError> {
+    let mut count = 0;
+    for collection in cat_indices(&state, index.map(|p| p.0)).await? {
+        if let Ok(stats) = state.manager.stats(&collection).await {
+            count += stats.document_count;
+        }
+    }
+    let (epoch, timestamp) = now();
+
+    render(
+        CatTable {
+            columns: COUNT_COLUMNS,
+            rows: vec![vec![Some(epoch), Some(timestamp), Some(count.to_string())]],
+        },
+        &params,
+    )
+}
+
+/// GET /_elastic/_cat/aliases[/{name}] - One row per alias and index
+pub async fn cat_aliases_handler(
+    State(state): State<EsCompatState>,
+    name: Option<Path<String>>,
+    Query(params): Query<CatParams>,
+) -> Result<Response, EsCompatError> {
+    let patterns: Vec<String> = match &name {
+        Some(Path(name)) => name.split(',').map(|p| p.trim().to_string()).collect(),
+        None => Vec::new(),
+    };
+
+    let mut rows = Vec::new();
+    if let Some(aliases) = &state.aliases {
+        for alias in aliases.list().await {
+            if !patterns.is_empty() && !patterns.iter().any(|p| wildcard_matches(p, &alias.name)) {
+                continue;
+            }
+            for target in &alias.targets {
+                rows.push(vec![
+                    Some(alias.name.clone()),
+                    Some(target.clone()),
+                    Some(if alias.filter.is_some() { "*" } else { "-" }.to_string()),
+                    Some("-".to_string()),
+                    Some("-".to_string()),
+                    Some(
+                        if alias.alias_type == AliasType::Write {
+                            "true"
+                        } else {
+                            "-"
+                        }
+                        .to_string(),
+                    ),
+                ]);
+            }
+        }
+    }
+    rows.sort();
+
+    render(
+        CatTable {
+            columns: ALIASES_COLUMNS,
+            rows,
+        },
+        &params,
+    )
+}
+
+/// GET /_elastic/_cat/shards[/{index}] - One row per shard copy
+pub async fn cat_shards_handler(
+    State(state): State<EsCompatState>,
+    index: Option<Path<String>>,
+    Query(params): Query<CatParams>,
+) -> Result<Response, EsCompatError> {
+    let collections = cat_indices(&state, index.map(|p| p.0)).await?;
+    let addresses = node_addresses(&state);
+
+    let mut rows = Vec::new();
+    for shard in shard_routing(&state).await {
+        if !collections.contains(&shard.index) {
+            continue;
+        }
+        let assigned = shard.node.is_some();
+        rows.push(vec![
+            Some(shard.index.clone()),
+            Some(shard.shard.to_string()),
+            Some(prirep(shard.primary).to_string()),
+            Some(shard.state.as_str().to_string()),
+            assigned.then(|| shard.docs.to_string()),
+            match assigned {
+                true => Some(format_size(shard.size_bytes, &params)?),
+                false => None,
+            },
+            shard.node.as_ref().and_then(|n| addresses.get(n)).cloned(),
+            shard.node,
+        ]);
+    }
+
+    render(
+        CatTable {
+            columns: SHARDS_COLUMNS,
+            rows,
+        },
+        &params,
+    )
+}
+
+/// GET /_elastic/_cat/segments[/{index}] - Segments of the local shards
+pub async fn cat_segments_handler(
+    State(state): State<EsCompatState>,
+    index: Option<Path<String>>,
+    Query(params): Query<CatParams>,
+) -> Result<Response, EsCompatError> {
+    let collections = cat_indices(&state, index.map(|p| p.0)).await?;
+    let local = local_node(&state);
+    let ip = node_addresses(&state).remove(&local);
+    let segments = local_segments(&state);
+
+    let mut rows = Vec::new();
+    for shard in shard_routing(&state).await {
+        if shard.node.as_deref() != Some(local.as_str()) || !collections.contains(&shard.index) {
+            continue;
+        }
+        let Some(info) = segments.get(&shard.index) else {
+            continue;
+        };
+        for (generation, segment) in info.segments.iter().enumerate() {
+            rows.push(vec![
+                Some(shard.index.clone()),
+                Some(shard.shard.to_string()),
+                Some(prirep(shard.primary).to_string()),
+                ip.clone(),
+                Some(segment.id.clone()),
+                Some(generation.to_string()),
+                Some(segment.doc_count.to_string()),
+                Some(segment.deleted_count.to_string()),
+                Some(format_size(segment.size_bytes, &params)?),
+                Some("true".to_string()),
+                Some("true".to_string()),
+            ]);
+        }
+    }
+
+    render(
+        CatTable {
+            columns: SEGMENTS_COLUMNS,
+            rows,
+        },
+        &params,
+    )
+}
+
+/// Collections named by an index expression, sorted; every collection
+/// without one
+async fn cat_indices(
+    state: &EsCompatState,
+    expression: Option<String>,
+) -> Result<Vec<String>, EsCompatError> {
+    let mut collections = match expression {
+        Some(expression) => {
+            let collections = state.resolve_indices(&expression).await;
+            if collections.is_empty() && !expression.contains('*') {
+                return Err(EsCompatError::IndexNotFound(expression));
+            }
+            collections
+        }
+        None => state.manager.list_collections(),
+    };
+    collections.sort();
+    Ok(collections)
+}
+
+fn node_addresses(state: &EsCompatState) -> HashMap<String, String> {
+    cluster_nodes(state)
+        .into_iter()
+        .map(|node| {
+            let ip = node_ip(&node.address).to_string();
+            (node.id, ip)
+        })
+        .collect()
+}
+
+fn replicas_per_primary(copies: usize, primaries: usize) -> usize {
+    match primaries {
+        0 => 0,
+        _ => (copies - primaries) / primaries,
+    }
+}
+
+fn prirep(primary: bool) -> &'static str {
+    if primary {
+        "p"
+    } else {
+        "r"
+    }
+}
+
+/// Seconds since the epoch and the UTC time of day
+fn now() -> (String, String) {
+    let now = chrono::Utc::now();
+    (
+        now.timestamp().to_string(),
+        now.format("%H:%M:%S").to_string(),
+    )
+}
+
+/// Format a size in the `?bytes=` unit, or human-readable without one
+fn format_size(bytes: u64, params: &CatParams) -> Result<String, EsCompatError> {
+    let divisor: u64 = match params.bytes.as_deref() {
+        None => return Ok(format_bytes(bytes as usize)),
+        Some("b") => 1,
+        Some("kb") => 1 << 10,
+        Some("mb") => 1 << 20,
+        Some("gb") => 1 << 30,
+        Some(unit) => {
+            return Err(EsCompatError::IllegalArgument(format!(
+                "failed to parse [bytes] unit [{}]",
+                unit
+            )))
+        }
+    };
+    Ok((bytes / divisor).to_string())
+}
+
+/// Indices of the columns `?h=` asks for, in its order; every column
+/// without it
+fn select_columns(columns: &[Column], h: Option<&str>) -> Result<Vec<usize>, EsCompatError> {
+    let Some(h) = h else {
+        return Ok((0..columns.len()).collect());
+    };
+
+    let mut selected = Vec::new();
+    for pattern in h.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let matched: Vec<usize> = columns
+            .iter()
+            .enumerate()
+            .filter(|(_, Column(name, aliases))| {
+                wildcard_matches(pattern, name) || aliases.contains(&pattern)
+            })
+            .map(|(i, _)| i)
+            .collect();
+        if matched.is_empty() {
+            return Err(EsCompatError::IllegalArgument(format!(
+                "unknown column [{}]",
+                pattern
+            )));
+        }
+        // An alias can be shared by several columns; the first one wins
+        let matched = match pattern.contains('*') {
+            true => matched,
+            false => matched[..1].to_vec(),
+        };
+        for i in matched {
+            if !selected.contains(&i) {
+                selected.push(i);
+            }
+        }
+    }
+    Ok(selected)
+}
+
+fn render(table: CatTable, params: &CatParams) -> Result<Response, EsCompatError> {
+    let selected = select_columns(table.columns, params.h.as_deref())?;
+
+    match params.format.as_deref() {
+        Some("json") => {
+            let rows: Vec<Value> = table
+                .rows
+                .into_iter()
+                .map(|row| {
+                    let object: Map<String, Value> = selected
+                        .iter()
+                        .map(|&i| {
+                            let cell = row[i].clone().map_or(Value::Null, Value::String);
+                            (table.columns[i].0.to_string(), cell)
+                        })
+                        .collect();
+                    Value::Object(object)
+                })
+                .collect();
+            Ok(Json(rows).into_response())
+        }
+        None | Some("text" | "txt") => {
+            let header = params.v.as_deref().is_some_and(|v| v != "false");
+            let mut lines: Vec<Vec<&str>> = Vec::with_capacity(table.rows.len() + 1);
+            if header {
+                lines.push(selected.iter().map(|&i| table.columns[i].0).collect());
+            }
+            for row in &table.rows {
+                lines.push(
+                    selected
+                        .iter()
+                        .map(|&i| row[i].as_deref().unwrap_or(""))
+                        .collect(),
+                );
+            }
+            Ok((
+                [(header::CONTENT_TYPE, "text/plain; charset=UTF-8")],
+                format_text(&lines),
+            )
+                .into_response())
+        }
+        Some(format) => Err(EsCompatError::IllegalArgument(format!(
+            "unsupported _cat format [{}]",
+            format
+        ))),
+    }
+}
+
+/// Pad cells to their column width, one line per row
+fn format_text(lines: &[Vec<&str>]) -> String {
+    let columns = lines.first().map_or(0, Vec::len);
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| {
+            lines
+                .iter()
+                .map(|l| l[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut text = String::new();
+    for line in lines {
+        let mut out = String::new();
+        for (cell, width) in line.iter().zip(&widths) {
+            out.push_str(&format!("{:<width$} ", cell, width = width));
+        }
+        text.push_str(out.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(h: Option<&str>) -> CatParams {
+        CatParams {
+            h: h.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_select_columns_default() {
+        let selected = select_columns(COUNT_COLUMNS, None).unwrap();
+        assert_eq!(selected, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_select_columns_names_aliases_and_wildcards() {
+        let selected = select_columns(INDICES_COLUMNS, Some("index,dc")).unwrap();
+        assert_eq!(selected, vec![2, 6]);
+
+        let selected = select_columns(INDICES_COLUMNS, Some("docs.*,index,docs.count")).unwrap();
+        assert_eq!(selected, vec![6, 7, 2]);
+    }
+
+    #[test]
+    fn test_select_columns_unknown() {
+        let err = select_columns(INDICES_COLUMNS, Some("index,bogus")).unwrap_err();
+        assert!(matches!(err, EsCompatError::IllegalArgument(_)));
+    }
+
+    #[test]
+    fn test_format_size_units() {
+        let mut p = params(None);
+        assert_eq!(format_size(1536, &p).unwrap(), "1.5kb");
+        p.bytes = Some("b".to_string());
+        assert_eq!(format_size(1536, &p).unwrap(), "1536");
+        p.bytes = Some("kb".to_string());
+        assert_eq!(format_size(1536, &p).unwrap(), "1");
+        p.bytes = Some("tb".to_string());
+        assert!(format_size(1536, &p).is_err());
+    }
+
+    #[test]
+    fn test_format_text_aligns_columns() {
+        let lines = vec![vec!["index", "docs.count"], vec!["a", "5"]];
+        assert_eq!(format_text(&lines), "index docs.count\na     5\n");
+        assert_eq!(format_text(&[]), "");
+    }
+}
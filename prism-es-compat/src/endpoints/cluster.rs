@@ -1,10 +1,20 @@
 //! ES-compatible cluster endpoints
 
 use crate::endpoints::search::EsCompatState;
-use crate::error::EsCompatError;
-use crate::response::{EsCatIndex, EsClusterHealth, EsRootInfo};
+use crate::response::{
+    EsClusterHealth, EsClusterIndicesStats, EsClusterNodeCounts, EsClusterNodesStats,
+    EsClusterShardsStats, EsClusterStatsResponse, EsDocsStats, EsIndicesStats, EsNodeStats,
+    EsNodesHeader, EsNodesStatsResponse, EsRootInfo, EsSegmentsStats, EsStoreStats,
+};
+use crate::topology::{ShardRoutingState, TopologyNode, TopologyShard};
 use axum::extract::State;
 use axum::Json;
+use prism::backends::text::SegmentsInfo;
+use std::collections::{BTreeSet, HashMap};
+
+/// Node ID and address of a standalone server
+const STANDALONE_NODE: &str = "prism";
+const STANDALONE_ADDRESS: &str = "127.0.0.1";
 
 /// GET /_elastic/ - Root info (ES version info)
 pub async fn root_handler() -> Json<EsRootInfo> {
@@ -12,56 +22,267 @@ pub async fn root_handler() -> Json<EsRootInfo> {
 }
 
 /// GET /_elastic/_cluster/health - Cluster health
-pub async fn cluster_health_handler(
+pub async fn cluster_health_handler(State(state): State<EsCompatState>) -> Json<EsClusterHealth> {
+    let nodes = cluster_nodes(&state);
+    let shards = shard_routing(&state).await;
+    Json(cluster_health(&nodes, &shards))
+}
+
+/// GET /_elastic/_cluster/stats - Index, shard and node totals
+pub async fn cluster_stats_handler(
     State(state): State<EsCompatState>,
-) -> Result<Json<EsClusterHealth>, EsCompatError> {
-    // Get basic stats from manager (sync method)
-    let collections = state.manager.list_collections();
+) -> Json<EsClusterStatsResponse> {
+    let nodes = cluster_nodes(&state);
+    let shards = shard_routing(&state).await;
+    let segments = local_segments(&state);
+
+    let indices: BTreeSet<&str> = shards.iter().map(|s| s.index.as_str()).collect();
+    let active: Vec<&TopologyShard> = shards.iter().filter(|s| s.state.is_active()).collect();
+    let primaries: Vec<&TopologyShard> = active.iter().copied().filter(|s| s.primary).collect();
+    let replication = if primaries.is_empty() {
+        0.0
+    } else {
+        (active.len() - primaries.len()) as f64 / primaries.len() as f64
+    };
+    let healthy = nodes.iter().filter(|n| n.healthy).count() as u32;
+    let info = EsRootInfo::default();
+
+    Json(EsClusterStatsResponse {
+        header: nodes_header(&nodes),
+        cluster_name: info.cluster_name,
+        cluster_uuid: info.cluster_uuid,
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        status: health_status(&shards).to_string(),
+        indices: EsClusterIndicesStats {
+            count: indices.len() as u32,
+            shards: EsClusterShardsStats {
+                total: active.len() as u32,
+                primaries: primaries.len() as u32,
+                replication,
+            },
+            docs: EsDocsStats {
+                count: primaries.iter().map(|s| s.docs).sum(),
+                deleted: segments.values().map(|s| s.total_deleted).sum(),
+            },
+            store: EsStoreStats {
+                size_in_bytes: active.iter().map(|s| s.size_bytes).sum(),
+            },
+            segments: EsSegmentsStats {
+                count: segments.values().map(|s| s.segments.len() as u64).sum(),
+            },
+        },
+        nodes: EsClusterNodesStats {
+            count: EsClusterNodeCounts {
+                total: healthy,
+                data: healthy,
+                master: healthy,
+                ingest: healthy,
+            },
+            versions: vec![info.version.number],
+        },
+    })
+}
+
+/// GET /_elastic/_nodes/stats - Per-node index statistics
+///
+/// Segment counts and deleted documents are only known for the local node.
+pub async fn nodes_stats_handler(State(state): State<EsCompatState>) -> Json<EsNodesStatsResponse> {
+    let local = local_node(&state);
+    let nodes = cluster_nodes(&state);
+    let shards = shard_routing(&state).await;
+    let segments = local_segments(&state);
+    let timestamp = chrono::Utc::now().timestamp_millis();
+
+    let mut stats = HashMap::new();
+    for node in nodes.iter().filter(|n| n.healthy) {
+        let held: Vec<&TopologyShard> = shards
+            .iter()
+            .filter(|s| s.state.is_active() && s.node.as_deref() == Some(node.id.as_str()))
+            .collect();
+        let mut indices = EsIndicesStats {
+            docs: EsDocsStats {
+                count: held.iter().map(|s| s.docs).sum(),
+                deleted: 0,
+            },
+            store: EsStoreStats {
+                size_in_bytes: held.iter().map(|s| s.size_bytes).sum(),
+            },
+            segments: EsSegmentsStats::default(),
+        };
+        if node.id == local {
+            indices.docs.deleted = segments.values().map(|s| s.total_deleted).sum();
+            indices.segments.count = segments.values().map(|s| s.segments.len() as u64).sum();
+        }
+
+        let ip = node_ip(&node.address).to_string();
+        stats.insert(
+            node.id.clone(),
+            EsNodeStats {
+                timestamp,
+                name: node.id.clone(),
+                transport_address: node.address.clone(),
+                host: ip.clone(),
+                ip,
+                roles: ["data", "ingest", "master"].map(String::from).to_vec(),
+                indices,
+            },
+        );
+    }
+
+    Json(EsNodesStatsResponse {
+        header: nodes_header(&nodes),
+        cluster_name: EsRootInfo::default().cluster_name,
+        nodes: stats,
+    })
+}
+
+/// ID of the node serving requests
+pub(crate) fn local_node(state: &EsCompatState) -> String {
+    match &state.topology {
+        Some(topology) => topology.local_node(),
+        None => STANDALONE_NODE.to_string(),
+    }
+}
+
+/// Known nodes; a standalone server is its only node
+pub(crate) fn cluster_nodes(state: &EsCompatState) -> Vec<TopologyNode> {
+    let mut nodes = match &state.topology {
+        Some(topology) => topology.nodes(),
+        None => vec![TopologyNode {
+            id: STANDALONE_NODE.to_string(),
+            address: STANDALONE_ADDRESS.to_string(),
+            healthy: true,
+        }],
+    };
+    nodes.sort_by(|a, b| a.id.cmp(&b.id));
+    nodes
+}
+
+/// Every shard copy, ordered by index, shard number and primaries first.
+///
+/// Copies held by the local node report the local collection's document
+/// count and size. Local collections the topology does not place yet, and
+/// every collection of a standalone server, are one started primary on the
+/// local node.
+pub(crate) async fn shard_routing(state: &EsCompatState) -> Vec<TopologyShard> {
+    let local = local_node(state);
+    let mut shards = match &state.topology {
+        Some(topology) => topology.shards(),
+        None => Vec::new(),
+    };
+
+    for collection in state.manager.list_collections() {
+        let (docs, size_bytes) = match state.manager.stats(&collection).await {
+            Ok(stats) => (stats.document_count as u64, stats.size_bytes as u64),
+            Err(_) => (0, 0),
+        };
+        let mut placed = false;
+        for shard in shards.iter_mut().filter(|s| s.index == collection) {
+            placed = true;
+            if shard.node.as_deref() == Some(local.as_str()) {
+                shard.docs = docs;
+                shard.size_bytes = size_bytes;
+            }
+        }
+        if !placed {
+            shards.push(TopologyShard {
+                index: collection,
+                shard: 0,
+                primary: true,
+                node: Some(local.clone()),
+                state: ShardRoutingState::Started,
+                docs,
+                size_bytes,
+            });
+        }
+    }
+
+    shards.sort_by(|a, b| {
+        (&a.index, a.shard, !a.primary, &a.node).cmp(&(&b.index, b.shard, !b.primary, &b.node))
+    });
+    shards
+}
+
+/// Segments of the local collections that have a text index
+pub(crate) fn local_segments(state: &EsCompatState) -> HashMap<String, SegmentsInfo> {
+    state
+        .manager
+        .list_collections()
+        .into_iter()
+        .filter_map(|collection| {
+            let segments = state.manager.get_segments(&collection).ok()?;
+            Some((collection, segments))
+        })
+        .collect()
+}
+
+/// `red` when a primary is not serving, `yellow` when a replica is not,
+/// `green` otherwise
+pub(crate) fn health_status<'a>(
+    shards: impl IntoIterator<Item = &'a TopologyShard>,
+) -> &'static str {
+    let mut status = "green";
+    for shard in shards {
+        if !shard.state.is_active() {
+            if shard.primary {
+                return "red";
+            }
+            status = "yellow";
+        }
+    }
+    status
+}
 
-    let health = EsClusterHealth {
-        active_primary_shards: collections.len() as u32,
-        active_shards: collections.len() as u32,
+pub(crate) fn cluster_health(nodes: &[TopologyNode], shards: &[TopologyShard]) -> EsClusterHealth {
+    let healthy = nodes.iter().filter(|n| n.healthy).count() as u32;
+    let mut health = EsClusterHealth {
+        status: health_status(shards).to_string(),
+        number_of_nodes: healthy,
+        number_of_data_nodes: healthy,
+        active_primary_shards: 0,
+        active_shards: 0,
         ..Default::default()
     };
 
-    Ok(Json(health))
+    for shard in shards {
+        match shard.state {
+            ShardRoutingState::Started => {}
+            ShardRoutingState::Relocating => health.relocating_shards += 1,
+            ShardRoutingState::Initializing => health.initializing_shards += 1,
+            ShardRoutingState::Unassigned => health.unassigned_shards += 1,
+        }
+        if shard.state.is_active() {
+            health.active_shards += 1;
+            if shard.primary {
+                health.active_primary_shards += 1;
+            }
+        }
+    }
+    if !shards.is_empty() {
+        health.active_shards_percent_as_number =
+            health.active_shards as f64 * 100.0 / shards.len() as f64;
+    }
+    health
 }
 
-/// GET /_elastic/_cat/indices - List indices
-pub async fn cat_indices_handler(
-    State(state): State<EsCompatState>,
-) -> Result<Json<Vec<EsCatIndex>>, EsCompatError> {
-    // Sync method
-    let collections = state.manager.list_collections();
-
-    let mut indices = Vec::with_capacity(collections.len());
-
-    for collection in collections {
-        // Get stats for collection (async)
-        let stats = state.manager.stats(&collection).await.ok();
-
-        let (doc_count, store_size) = stats
-            .map(|s| (s.document_count.to_string(), format_bytes(s.size_bytes)))
-            .unwrap_or_else(|| ("0".to_string(), "0b".to_string()));
-
-        indices.push(EsCatIndex {
-            health: "green".to_string(),
-            status: "open".to_string(),
-            index: collection.clone(),
-            uuid: format!("{:x}", md5_hash(&collection)),
-            pri: "1".to_string(),
-            rep: "0".to_string(),
-            docs_count: doc_count.clone(),
-            docs_deleted: "0".to_string(),
-            store_size: store_size.clone(),
-            pri_store_size: store_size,
-        });
-    }
-
-    Ok(Json(indices))
+/// Host part of a `host:port` address
+pub(crate) fn node_ip(address: &str) -> &str {
+    match address.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => address,
+    }
 }
 
-fn format_bytes(bytes: usize) -> String {
+fn nodes_header(nodes: &[TopologyNode]) -> EsNodesHeader {
+    let healthy = nodes.iter().filter(|n| n.healthy).count() as u32;
+    EsNodesHeader {
+        total: nodes.len() as u32,
+        successful: healthy,
+        failed: nodes.len() as u32 - healthy,
+    }
+}
+
+pub(crate) fn format_bytes(bytes: usize) -> String {
     const KB: usize = 1024;
     const MB: usize = KB * 1024;
     const GB: usize = MB * 1024;
@@ -77,7 +298,7 @@ fn format_bytes(bytes: usize) -> String {
     }
 }
 
-fn md5_hash(s: &str) -> u64 {
+pub(crate) fn md5_hash(s: &str) -> u64 {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
 
@@ -176,4 +397,78 @@ mod tests {
         assert_eq!(info.version.number, "7.17.0");
         assert!(info.tagline.contains("Prism"));
     }
+
+    // ===================================================================
+    // cluster_health / health_status
+    // ===================================================================
+
+    fn shard(primary: bool, state: ShardRoutingState) -> TopologyShard {
+        TopologyShard {
+            index: "logs".to_string(),
+            shard: 0,
+            primary,
+            node: (state != ShardRoutingState::Unassigned).then(|| "n1".to_string()),
+            state,
+            docs: 0,
+            size_bytes: 0,
+        }
+    }
+
+    fn node(id: &str, healthy: bool) -> TopologyNode {
+        TopologyNode {
+            id: id.to_string(),
+            address: format!("{}:9300", id),
+            healthy,
+        }
+    }
+
+    #[test]
+    fn test_health_status() {
+        use ShardRoutingState::*;
+        assert_eq!(health_status(&[]), "green");
+        assert_eq!(
+            health_status(&[shard(true, Started), shard(false, Started)]),
+            "green"
+        );
+        assert_eq!(
+            health_status(&[shard(true, Relocating), shard(false, Unassigned)]),
+            "yellow"
+        );
+        assert_eq!(
+            health_status(&[shard(true, Initializing), shard(false, Started)]),
+            "red"
+        );
+    }
+
+    #[test]
+    fn test_cluster_health_counts() {
+        use ShardRoutingState::*;
+        let nodes = [node("n1", true), node("n2", true), node("n3", false)];
+        let shards = [
+            shard(true, Started),
+            shard(false, Relocating),
+            shard(true, Started),
+            shard(false, Unassigned),
+        ];
+        let health = cluster_health(&nodes, &shards);
+        assert_eq!(health.status, "yellow");
+        assert_eq!(health.number_of_nodes, 2);
+        assert_eq!(health.active_primary_shards, 2);
+        assert_eq!(health.active_shards, 3);
+        assert_eq!(health.relocating_shards, 1);
+        assert_eq!(health.unassigned_shards, 1);
+        assert_eq!(health.active_shards_percent_as_number, 75.0);
+
+        let health = cluster_health(&nodes[..1], &[]);
+        assert_eq!(health.status, "green");
+        assert_eq!(health.active_shards, 0);
+        assert_eq!(health.active_shards_percent_as_number, 100.0);
+    }
+
+    #[test]
+    fn test_node_ip() {
+        assert_eq!(node_ip("10.0.0.1:9300"), "10.0.0.1");
+        assert_eq!(node_ip("node-a:7000"), "node-a");
+        assert_eq!(node_ip("127.0.0.1"), "127.0.0.1");
+    }
 }
@@ -0,0 +1,643 @@
+//! ES-compatible single-document endpoints: _doc, _create, _update, _mget
+//! and _count
+
+use crate::endpoints::search::{get_text_fields, resolve_query, EsCompatState};
+use crate::error::EsCompatError;
+use crate::query::{
+    EsCountRequest, EsMgetRequest, EsQuery, EsSearchRequest, EsUpdateRequest, QueryStringQuery,
+    QueryTranslator, SourceFilter,
+};
+use crate::response::{
+    filter_source, EsCountResponse, EsError, EsGetResponse, EsMgetItem, EsMgetResponse,
+    EsWriteResponse, ShardStats,
+};
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use prism::backends::Document;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Fields the text backend adds to every stored document
+const SYSTEM_FIELDS: &[&str] = &["id", "_indexed_at", "_version", "_seq_no"];
+
+/// Per-index `_seq_no` counters for single-document writes.
+///
+/// The `_version` and `_seq_no` of the last write are stored with the
+/// document itself, so they survive restarts; documents written without them
+/// (through the Prism API, or into collections without a text backend)
+/// report version 1. An index's counter resumes after the highest `_seq_no`
+/// it stores. The lock also serialises single-document writes, which makes
+/// the read-modify-write of `_create` and `_update` atomic.
+#[derive(Default)]
+pub struct DocVersions {
+    table: Mutex<VersionTable>,
+}
+
+impl DocVersions {
+    /// Drop the counter of a deleted index so a recreated one starts at 0
+    pub async fn forget_index(&self, index: &str) {
+        self.table.lock().await.next_seq_no.remove(index);
+    }
+}
+
+#[derive(Default)]
+struct VersionTable {
+    /// index -> next seq_no, loaded from the index on its first write
+    next_seq_no: HashMap<String, u64>,
+}
+
+impl VersionTable {
+    /// (version, seq_no) of a write replacing a document stored at
+    /// `current`, or creating one when `current` is `None`
+    fn bump(
+        &mut self,
+        state: &EsCompatState,
+        index: &str,
+        current: Option<u64>,
+    ) -> Result<(u64, u64), EsCompatError> {
+        let next = match self.next_seq_no.entry(index.to_string()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let last = state.manager.max_seq_no(index)?;
+                entry.insert(last.map_or(0, |seq_no| seq_no + 1))
+            }
+        };
+        let seq_no = *next;
+        *next += 1;
+        Ok((current.map_or(1, |version| version + 1), seq_no))
+    }
+}
+
+/// (version, seq_no) stored with a document
+fn stored_version(doc: &Document) -> (u64, u64) {
+    let get = |name: &str| doc.fields.get(name).and_then(Value::as_u64);
+    (get("_version").unwrap_or(1), get("_seq_no").unwrap_or(0))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct WriteParams {
+    /// `create` fails if the document exists
+    #[serde(default)]
+    pub op_type: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GetParams {
+    /// `true`, `false` or a comma-separated field list
+    #[serde(default, rename = "_source")]
+    pub source: Option<String>,
+    #[serde(default, rename = "_source_includes")]
+    pub source_includes: Option<String>,
+    #[serde(default, rename = "_source_excludes")]
+    pub source_excludes: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CountParams {
+    /// Lucene query string, as in `?q=status:active`
+    #[serde(default)]
+    pub q: Option<String>,
+}
+
+/// PUT/POST /_elastic/{index}/_doc/{id} - Index a document
+pub async fn index_doc_handler(
+    State(state): State<EsCompatState>,
+    Path((index, id)): Path<(String, String)>,
+    Query(params): Query<WriteParams>,
+    Json(body): Json<Value>,
+) -> Result<(StatusCode, Json<EsWriteResponse>), EsCompatError> {
+    let create = params.op_type.as_deref() == Some("create");
+    write_document(&state, &index, id, body, create).await
+}
+
+/// POST /_elastic/{index}/_doc - Index a document with a generated ID
+pub async fn index_doc_auto_id_handler(
+    State(state): State<EsCompatState>,
+    Path(index): Path<String>,
+    Json(body): Json<Value>,
+) -> Result<(StatusCode, Json<EsWriteResponse>), EsCompatError> {
+    let id = Uuid::new_v4().to_string();
+    write_document(&state, &index, id, body, true).await
+}
+
+/// PUT/POST /_elastic/{index}/_create/{id} - Index a document only if it
+/// does not exist
+pub async fn create_doc_handler(
+    State(state): State<EsCompatState>,
+    Path((index, id)): Path<(String, String)>,
+    Json(body): Json<Value>,
+) -> Result<(StatusCode, Json<EsWriteResponse>), EsCompatError> {
+    write_document(&state, &index, id, body, true).await
+}
+
+/// GET /_elastic/{index}/_doc/{id} - Get a document
+pub async fn get_doc_handler(
+    State(state): State<EsCompatState>,
+    Path((index, id)): Path<(String, String)>,
+    Query(params): Query<GetParams>,
+) -> Result<(StatusCode, Json<EsGetResponse>), EsCompatError> {
+    let index = resolve_index(&state, &index).await?;
+    let response = get_document(&state, &index, &id, params.source_filter().as_ref()).await?;
+    let status = if response.found {
+        StatusCode::OK
+    } else {
+        StatusCode::NOT_FOUND
+    };
+    Ok((status, Json(response)))
+}
+
+/// HEAD /_elastic/{index}/_doc/{id} - Check whether a document exists
+pub async fn head_doc_handler(
+    State(state): State<EsCompatState>,
+    Path((index, id)): Path<(String, String)>,
+) -> Result<StatusCode, EsCompatError> {
+    let index = resolve_index(&state, &index).await?;
+    Ok(match state.manager.get(&index, &id).await? {
+        Some(_) => StatusCode::OK,
+        None => StatusCode::NOT_FOUND,
+    })
+}
+
+/// DELETE /_elastic/{index}/_doc/{id} - Delete a document
+pub async fn delete_doc_handler(
+    State(state): State<EsCompatState>,
+    Path((index, id)): Path<(String, String)>,
+) -> Result<(StatusCode, Json<EsWriteResponse>), EsCompatError> {
+    let index = resolve_index(&state, &index).await?;
+    let mut versions = state.versions.table.lock().await;
+
+    let existing = state.manager.get(&index, &id).await?;
+    let ((version, seq_no), result, status) = match existing {
+        Some(doc) => {
+            let current = stored_version(&doc).0;
+            state.manager.delete(&index, vec![id.clone()]).await?;
+            (
+                versions.bump(&state, &index, Some(current))?,
+                "deleted",
+                StatusCode::OK,
+            )
+        }
+        None => ((1, 0), "not_found", StatusCode::NOT_FOUND),
+    };
+
+    Ok((
+        status,
+        Json(write_response(index, id, version, seq_no, result)),
+    ))
+}
+
+/// POST /_elastic/{index}/_update/{id} - Merge a partial document, with
+/// `upsert` / `doc_as_upsert` for missing documents
+pub async fn update_doc_handler(
+    State(state): State<EsCompatState>,
+    Path((index, id)): Path<(String, String)>,
+    Json(request): Json<EsUpdateRequest>,
+) -> Result<(StatusCode, Json<EsWriteResponse>), EsCompatError> {
+    if request.script.is_some() {
+        return Err(EsCompatError::InvalidRequestBody(
+            "Scripted updates are not supported; send a partial `doc` instead".to_string(),
+        ));
+    }
+    let index = resolve_index(&state, &index).await?;
+    let mut versions = state.versions.table.lock().await;
+
+    let existing = state.manager.get(&index, &id).await?;
+    let Some(existing) = existing else {
+        let upsert = if request.doc_as_upsert {
+            request.doc
+        } else {
+            request.upsert
+        };
+        let Some(upsert) = upsert else {
+            return Err(EsCompatError::DocumentMissing(format!(
+                "[{}]: document missing",
+                id
+            )));
+        };
+        let (version, seq_no) = versions.bump(&state, &index, None)?;
+        index_fields(
+            &state,
+            &index,
+            &id,
+            upsert.into_iter().collect(),
+            (version, seq_no),
+        )
+        .await?;
+        return Ok((
+            StatusCode::CREATED,
+            Json(write_response(index, id, version, seq_no, "created")),
+        ));
+    };
+
+    let Some(patch) = request.doc else {
+        return Err(EsCompatError::InvalidRequestBody(
+            "Validation Failed: 1: script or doc is missing".to_string(),
+        ));
+    };
+    let current = stored_version(&existing);
+    let original: Map<String, Value> = document_source(existing.fields).into_iter().collect();
+    let mut merged = original.clone();
+    merge_objects(&mut merged, patch);
+
+    if request.detect_noop && merged == original {
+        let (version, seq_no) = current;
+        return Ok((
+            StatusCode::OK,
+            Json(write_response(index, id, version, seq_no, "noop")),
+        ));
+    }
+
+    let (version, seq_no) = versions.bump(&state, &index, Some(current.0))?;
+    index_fields(
+        &state,
+        &index,
+        &id,
+        merged.into_iter().collect(),
+        (version, seq_no),
+    )
+    .await?;
+    Ok((
+        StatusCode::OK,
+        Json(write_response(index, id, version, seq_no, "updated")),
+    ))
+}
+
+/// POST /_elastic/_mget - Get several documents
+/// POST /_elastic/{index}/_mget - Get several documents from a default index
+pub async fn mget_handler(
+    State(state): State<EsCompatState>,
+    default_index: Option<Path<String>>,
+    Json(request): Json<EsMgetRequest>,
+) -> Result<Json<EsMgetResponse>, EsCompatError> {
+    let default_index = default_index.map(|p| p.0);
+
+    let entries: Vec<(Option<String>, String, Option<SourceFilter>)> =
+        match (request.docs, request.ids) {
+            (Some(docs), _) => docs
+                .into_iter()
+                .map(|d| (d.index.or_else(|| default_index.clone()), d.id, d.source))
+                .collect(),
+            (None, Some(ids)) => ids
+                .into_iter()
+                .map(|id| (default_index.clone(), id, None))
+                .collect(),
+            (None, None) => {
+                return Err(EsCompatError::InvalidRequestBody(
+                    "mget requires `docs` or `ids`".to_string(),
+                ))
+            }
+        };
+
+    let mut docs = Vec::with_capacity(entries.len());
+    for (index, id, source) in entries {
+        let index = index.ok_or_else(|| EsCompatError::MissingField("_index".to_string()))?;
+        if !state.manager.collection_exists(&index) {
+            docs.push(EsMgetItem::Error {
+                error: EsError {
+                    error_type: "index_not_found_exception".to_string(),
+                    reason: format!("no such index [{}]", index),
+                },
+                index,
+                id,
+            });
+            continue;
+        }
+        docs.push(EsMgetItem::Doc(
+            get_document(&state, &index, &id, source.as_ref()).await?,
+        ));
+    }
+
+    Ok(Json(EsMgetResponse { docs }))
+}
+
+/// GET/POST /_elastic/_count - Count matching documents in all indices
+/// GET/POST /_elastic/{index}/_count - Count matching documents
+pub async fn count_handler(
+    State(state): State<EsCompatState>,
+    index: Option<Path<String>>,
+    Query(params): Query<CountParams>,
+    body: Bytes,
+) -> Result<Json<EsCountResponse>, EsCompatError> {
+    let index_name = index.map(|p| p.0).unwrap_or_else(|| "*".to_string());
+    let collections = state.resolve_indices(&index_name).await;
+    if collections.is_empty() {
+        return Err(EsCompatError::IndexNotFound(index_name));
+    }
+
+    let request: EsCountRequest = if body.iter().all(u8::is_ascii_whitespace) {
+        EsCountRequest::default()
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| EsCompatError::InvalidRequestBody(e.to_string()))?
+    };
+    let mut query = match params.q {
+        Some(q) => Some(EsQuery::QueryString(QueryStringQuery {
+            query: q,
+            default_field: None,
+            fields: None,
+            default_operator: None,
+            analyze_wildcard: None,
+        })),
+        None => request.query,
+    };
+    if let Some(query) = &mut query {
+        resolve_query(&state.manager, &collections, query)?;
+    }
+    let search = EsSearchRequest {
+        query,
+        size: Some(0),
+        ..Default::default()
+    };
+
+    let mut count = 0;
+    for collection in &collections {
+        let default_fields = get_text_fields(&state.manager, collection);
+        let (query, _) = QueryTranslator::translate(&search, &default_fields)?;
+        count += state
+            .manager
+            .search_with_aggs(collection, &query, vec![])
+            .await?
+            .total;
+    }
+
+    let shards = collections.len() as u32;
+    Ok(Json(EsCountResponse {
+        count,
+        shards: ShardStats {
+            total: shards,
+            successful: shards,
+            skipped: 0,
+            failed: 0,
+        },
+    }))
+}
+
+impl GetParams {
+    fn source_filter(&self) -> Option<SourceFilter> {
+        let split = |s: &String| s.split(',').map(|f| f.trim().to_string()).collect();
+        if self.source_includes.is_some() || self.source_excludes.is_some() {
+            return Some(SourceFilter::Object {
+                includes: self.source_includes.as_ref().map(split),
+                excludes: self.source_excludes.as_ref().map(split),
+            });
+        }
+        self.source.as_ref().map(|s| match s.as_str() {
+            "true" => SourceFilter::Bool(true),
+            "false" => SourceFilter::Bool(false),
+            _ => SourceFilter::Fields(split(s)),
+        })
+    }
+}
+
+/// Single documents are written to exactly one existing collection; an alias
+/// must point at a single index
+async fn resolve_index(state: &EsCompatState, index: &str) -> Result<String, EsCompatError> {
+    if index.contains('*') || index.contains('?') || index.contains(',') {
+        return Err(EsCompatError::InvalidRequestBody(format!(
+            "Index patterns are not allowed for document APIs: [{}]",
+            index
+        )));
+    }
+    if let Some(aliases) = &state.aliases {
+        if let Some(alias) = aliases.get(index).await {
+            return match alias.targets.as_slice() {
+                [target] => Ok(target.clone()),
+                targets => Err(EsCompatError::IllegalArgument(format!(
+                    "alias [{}] has more than one index associated with it [{}], can't execute a single index op",
+                    index,
+                    targets.join(", ")
+                ))),
+            };
+        }
+    }
+    if !state.manager.collection_exists(index) {
+        return Err(EsCompatError::IndexNotFound(index.to_string()));
+    }
+    Ok(index.to_string())
+}
+
+async fn write_document(
+    state: &EsCompatState,
+    index: &str,
+    id: String,
+    body: Value,
+    create: bool,
+) -> Result<(StatusCode, Json<EsWriteResponse>), EsCompatError> {
+    let index = resolve_index(state, index).await?;
+    let Value::Object(fields) = body else {
+        return Err(EsCompatError::InvalidRequestBody(
+            "Document must be an object".to_string(),
+        ));
+    };
+    let mut versions = state.versions.table.lock().await;
+
+    let current = state
+        .manager
+        .get(&index, &id)
+        .await?
+        .map(|doc| stored_version(&doc).0);
+    if let (Some(version), true) = (current, create) {
+        return Err(EsCompatError::VersionConflict(format!(
+            "[{}]: version conflict, document already exists (current version [{}])",
+            id, version
+        )));
+    }
+
+    let (version, seq_no) = versions.bump(state, &index, current)?;
+    index_fields(
+        state,
+        &index,
+        &id,
+        fields.into_iter().collect(),
+        (version, seq_no),
+    )
+    .await?;
+    let (status, result) = if current.is_some() {
+        (StatusCode::OK, "updated")
+    } else {
+        (StatusCode::CREATED, "created")
+    };
+    Ok((
+        status,
+        Json(write_response(index, id, version, seq_no, result)),
+    ))
+}
+
+/// Index a document source, stored with the (version, seq_no) of the write
+async fn index_fields(
+    state: &EsCompatState,
+    index: &str,
+    id: &str,
+    mut fields: HashMap<String, Value>,
+    (version, seq_no): (u64, u64),
+) -> Result<(), EsCompatError> {
+    fields.insert("_version".to_string(), version.into());
+    fields.insert("_seq_no".to_string(), seq_no.into());
+    state
+        .manager
+        .index(
+            index,
+            vec![Document {
+                id: id.to_string(),
+                fields,
+            }],
+        )
+        .await?;
+    Ok(())
+}
+
+async fn get_document(
+    state: &EsCompatState,
+    index: &str,
+    id: &str,
+    source: Option<&SourceFilter>,
+) -> Result<EsGetResponse, EsCompatError> {
+    let Some(doc) = state.manager.get(index, id).await? else {
+        return Ok(EsGetResponse {
+            index: index.to_string(),
+            id: id.to_string(),
+            version: None,
+            seq_no: None,
+            primary_term: None,
+            found: false,
+            source: None,
+        });
+    };
+    let (version, seq_no) = stored_version(&doc);
+    Ok(EsGetResponse {
+        index: index.to_string(),
+        id: id.to_string(),
+        version: Some(version),
+        seq_no: Some(seq_no),
+        primary_term: Some(1),
+        found: true,
+        source: filter_source(document_source(doc.fields), source),
+    })
+}
+
+fn write_response(
+    index: String,
+    id: String,
+    version: u64,
+    seq_no: u64,
+    result: &str,
+) -> EsWriteResponse {
+    EsWriteResponse {
+        index,
+        id,
+        version,
+        result: result.to_string(),
+        shards: ShardStats::default(),
+        seq_no,
+        primary_term: 1,
+    }
+}
+
+/// Stored fields without the ones the backend adds itself
+fn document_source(mut fields: HashMap<String, Value>) -> HashMap<String, Value> {
+    for name in SYSTEM_FIELDS {
+        fields.remove(*name);
+    }
+    fields
+}
+
+/// Merge `patch` into `target`, recursing into objects present in both
+fn merge_objects(target: &mut Map<String, Value>, patch: Map<String, Value>) {
+    for (key, value) in patch {
+        match (target.get_mut(&key), value) {
+            (Some(Value::Object(existing)), Value::Object(inner)) => merge_objects(existing, inner),
+            (_, value) => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn object(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => panic!("expected object"),
+        }
+    }
+
+    #[test]
+    fn test_merge_objects_is_recursive() {
+        let mut target = object(json!({
+            "title": "Old",
+            "stock": 3,
+            "meta": { "color": "red", "size": "M" }
+        }));
+        merge_objects(
+            &mut target,
+            object(json!({ "stock": 5, "meta": { "size": "L" }, "tags": ["new"] })),
+        );
+        assert_eq!(
+            Value::Object(target),
+            json!({
+                "title": "Old",
+                "stock": 5,
+                "meta": { "color": "red", "size": "L" },
+                "tags": ["new"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_params_source_filter() {
+        let params = GetParams {
+            source: Some("false".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            params.source_filter(),
+            Some(SourceFilter::Bool(false))
+        ));
+
+        let params = GetParams {
+            source: Some("a, b".to_string()),
+            ..Default::default()
+        };
+        match params.source_filter() {
+            Some(SourceFilter::Fields(fields)) => assert_eq!(fields, vec!["a", "b"]),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let params = GetParams {
+            source_excludes: Some("secret".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            params.source_filter(),
+            Some(SourceFilter::Object {
+                includes: None,
+                excludes: Some(_)
+            })
+        ));
+    }
+
+    #[test]
+    fn test_stored_version() {
+        // Documents written without a version start at version 1
+        let mut doc = Document {
+            id: "a".to_string(),
+            fields: HashMap::from([("title".to_string(), Value::from("x"))]),
+        };
+        assert_eq!(stored_version(&doc), (1, 0));
+
+        doc.fields.insert("_version".to_string(), Value::from(3));
+        doc.fields.insert("_seq_no".to_string(), Value::from(7));
+        assert_eq!(stored_version(&doc), (3, 7));
+        assert_eq!(document_source(doc.fields).len(), 1);
+    }
+}
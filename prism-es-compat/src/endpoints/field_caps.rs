@@ -0,0 +1,283 @@
+//! ES-compatible _field_caps endpoint
+
+use crate::endpoints::mapping::es_field_type;
+use crate::endpoints::search::EsCompatState;
+use crate::error::EsCompatError;
+use crate::query::EsFieldCapsRequest;
+use crate::response::{wildcard_matches, EsFieldCapability, EsFieldCapsResponse};
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use prism::schema::{CollectionSchema, FieldType};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Metadata fields every index reports as (type, searchable, aggregatable)
+const METADATA_FIELDS: &[(&str, &str, bool, bool)] = &[
+    ("_id", "_id", true, false),
+    ("_index", "_index", true, true),
+    ("_source", "_source", false, false),
+];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct FieldCapsParams {
+    /// Comma-separated field names or wildcard patterns
+    #[serde(default)]
+    pub fields: Option<String>,
+    /// Report indices lacking a field under the `unmapped` type
+    #[serde(default)]
+    pub include_unmapped: bool,
+}
+
+/// What one index knows about a field
+#[derive(Debug, Clone, PartialEq)]
+struct IndexField {
+    es_type: &'static str,
+    metadata: bool,
+    searchable: bool,
+    aggregatable: bool,
+}
+
+/// GET/POST /_elastic/_field_caps - Field capabilities across all indices
+/// GET/POST /_elastic/{index}/_field_caps - Field capabilities of some indices
+pub async fn field_caps_handler(
+    State(state): State<EsCompatState>,
+    index: Option<Path<String>>,
+    Query(params): Query<FieldCapsParams>,
+    body: Bytes,
+) -> Result<Json<EsFieldCapsResponse>, EsCompatError> {
+    let request: EsFieldCapsRequest = if body.iter().all(u8::is_ascii_whitespace) {
+        EsFieldCapsRequest::default()
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| EsCompatError::InvalidRequestBody(e.to_string()))?
+    };
+    let mut patterns: Vec<String> = params
+        .fields
+        .iter()
+        .flat_map(|fields| fields.split(','))
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty())
+        .collect();
+    patterns.extend(request.fields.unwrap_or_default());
+    if patterns.is_empty() {
+        return Err(EsCompatError::IllegalArgument(
+            "specified fields can't be null or empty".to_string(),
+        ));
+    }
+
+    let collections = match index {
+        Some(Path(index)) => {
+            let collections = state.resolve_indices(&index).await;
+            if collections.is_empty() {
+                return Err(EsCompatError::IndexNotFound(index));
+            }
+            collections
+        }
+        None => state.resolve_indices("*").await,
+    };
+    let mut collections = state
+        .filter_indices(collections, request.index_filter.as_ref())
+        .await?;
+    collections.sort();
+
+    // Field name -> index -> capabilities
+    let mut by_field: BTreeMap<String, BTreeMap<String, IndexField>> = BTreeMap::new();
+    for collection in &collections {
+        let Some(schema) = state.manager.get_schema(collection) else {
+            continue;
+        };
+        for (name, field) in index_fields(&schema) {
+            if patterns.iter().any(|p| wildcard_matches(p, &name)) {
+                by_field
+                    .entry(name)
+                    .or_default()
+                    .insert(collection.clone(), field);
+            }
+        }
+    }
+
+    let fields = by_field
+        .into_iter()
+        .map(|(name, per_index)| {
+            let unmapped: Vec<String> = collections
+                .iter()
+                .filter(|c| !per_index.contains_key(*c))
+                .cloned()
+                .collect();
+            let caps = field_capabilities(per_index, unmapped, params.include_unmapped);
+            (name, caps)
+        })
+        .collect();
+
+    Ok(Json(EsFieldCapsResponse {
+        indices: collections,
+        fields,
+    }))
+}
+
+/// Fields of an index with their ES type and capabilities.
+///
+/// Text fields are searchable when indexed; other fields are aggregatable
+/// when stored or fast, as aggregations fall back to stored documents.
+fn index_fields(schema: &CollectionSchema) -> Vec<(String, IndexField)> {
+    let mut fields: Vec<(String, IndexField)> = METADATA_FIELDS
+        .iter()
+        .map(|&(name, es_type, searchable, aggregatable)| {
+            (
+                name.to_string(),
+                IndexField {
+                    es_type,
+                    metadata: true,
+                    searchable,
+                    aggregatable,
+                },
+            )
+        })
+        .collect();
+
+    if let Some(text) = &schema.backends.text {
+        for field in &text.fields {
+            let aggregatable = match field.field_type {
+                FieldType::Text | FieldType::Bytes => false,
+                _ => field.fast || field.stored,
+            };
+            fields.push((
+                field.name.clone(),
+                IndexField {
+                    es_type: es_field_type(&field.field_type),
+                    metadata: false,
+                    searchable: field.indexed && !matches!(field.field_type, FieldType::Bytes),
+                    aggregatable,
+                },
+            ));
+        }
+    }
+
+    if let Some(vector) = &schema.backends.vector {
+        fields.push((
+            vector.embedding_field.clone(),
+            IndexField {
+                es_type: "dense_vector",
+                metadata: false,
+                searchable: true,
+                aggregatable: false,
+            },
+        ));
+    }
+    fields
+}
+
+/// Merge per-index capabilities of one field into one entry per type
+fn field_capabilities(
+    per_index: BTreeMap<String, IndexField>,
+    unmapped: Vec<String>,
+    include_unmapped: bool,
+) -> BTreeMap<String, EsFieldCapability> {
+    let mut by_type: BTreeMap<&'static str, Vec<(String, IndexField)>> = BTreeMap::new();
+    for (index, field) in per_index {
+        by_type
+            .entry(field.es_type)
+            .or_default()
+            .push((index, field));
+    }
+    let several_types = by_type.len() + usize::from(include_unmapped && !unmapped.is_empty()) > 1;
+
+    let mut caps: BTreeMap<String, EsFieldCapability> = by_type
+        .into_iter()
+        .map(|(es_type, indices)| {
+            let not = |flag: fn(&IndexField) -> bool| -> Option<Vec<String>> {
+                let without: Vec<String> = indices
+                    .iter()
+                    .filter(|(_, f)| !flag(f))
+                    .map(|(i, _)| i.clone())
+                    .collect();
+                (!without.is_empty() && without.len() < indices.len()).then_some(without)
+            };
+            let capability = EsFieldCapability {
+                field_type: es_type.to_string(),
+                metadata_field: indices.iter().all(|(_, f)| f.metadata),
+                searchable: indices.iter().all(|(_, f)| f.searchable),
+                aggregatable: indices.iter().all(|(_, f)| f.aggregatable),
+                indices: several_types.then(|| indices.iter().map(|(i, _)| i.clone()).collect()),
+                non_searchable_indices: not(|f| f.searchable),
+                non_aggregatable_indices: not(|f| f.aggregatable),
+            };
+            (es_type.to_string(), capability)
+        })
+        .collect();
+
+    if include_unmapped && !unmapped.is_empty() {
+        caps.insert(
+            "unmapped".to_string(),
+            EsFieldCapability {
+                field_type: "unmapped".to_string(),
+                metadata_field: false,
+                searchable: false,
+                aggregatable: false,
+                indices: Some(unmapped),
+                non_searchable_indices: None,
+                non_aggregatable_indices: None,
+            },
+        );
+    }
+    caps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(es_type: &'static str, searchable: bool, aggregatable: bool) -> IndexField {
+        IndexField {
+            es_type,
+            metadata: false,
+            searchable,
+            aggregatable,
+        }
+    }
+
+    #[test]
+    fn test_single_type_omits_indices() {
+        let per_index = BTreeMap::from([
+            ("a".to_string(), field("keyword", true, true)),
+            ("b".to_string(), field("keyword", true, true)),
+        ]);
+        let caps = field_capabilities(per_index, vec![], false);
+        let keyword = &caps["keyword"];
+        assert!(keyword.searchable && keyword.aggregatable);
+        assert!(keyword.indices.is_none());
+        assert!(keyword.non_aggregatable_indices.is_none());
+    }
+
+    #[test]
+    fn test_mixed_capabilities_list_indices() {
+        let per_index = BTreeMap::from([
+            ("a".to_string(), field("keyword", true, true)),
+            ("b".to_string(), field("keyword", true, false)),
+            ("c".to_string(), field("long", true, true)),
+        ]);
+        let caps = field_capabilities(per_index, vec!["d".to_string()], true);
+        assert_eq!(caps.len(), 3);
+        let keyword = &caps["keyword"];
+        assert!(!keyword.aggregatable);
+        assert_eq!(
+            keyword.indices.as_deref(),
+            Some(&["a".to_string(), "b".to_string()][..])
+        );
+        assert_eq!(
+            keyword.non_aggregatable_indices,
+            Some(vec!["b".to_string()])
+        );
+        assert_eq!(caps["long"].indices, Some(vec!["c".to_string()]));
+        assert_eq!(caps["unmapped"].indices, Some(vec!["d".to_string()]));
+    }
+
+    #[test]
+    fn test_unmapped_ignored_by_default() {
+        let per_index = BTreeMap::from([("a".to_string(), field("text", true, false))]);
+        let caps = field_capabilities(per_index, vec!["b".to_string()], false);
+        assert_eq!(caps.len(), 1);
+        assert!(caps["text"].indices.is_none());
+    }
+}
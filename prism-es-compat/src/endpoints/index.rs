@@ -0,0 +1,385 @@
+//! ES-compatible index management: create, delete and check indices, add
+//! mapping fields, and manage aliases
+
+use crate::endpoints::mapping::es_field_type;
+use crate::endpoints::scroll::parse_optional_body;
+use crate::endpoints::search::EsCompatState;
+use crate::error::EsCompatError;
+use crate::query::{EsAliasAction, EsAliasActionParams, EsAliasesRequest, EsCreateIndexRequest};
+use crate::response::{
+    EsAcknowledgedResponse, EsAliasInfo, EsAliasesResponse, EsCreateIndexResponse, EsIndexAliases,
+};
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use prism::collection::CollectionManager;
+use prism::ilm::{AliasChange, AliasManager, AliasType};
+use prism::schema::es_mapping::{self, EsMappings};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// PUT /_elastic/{index} - Create an index from ES mappings
+pub async fn create_index_handler(
+    State(state): State<EsCompatState>,
+    Path(index): Path<String>,
+    body: Bytes,
+) -> Result<Json<EsCreateIndexResponse>, EsCompatError> {
+    validate_index_name(&index)?;
+    if state.manager.collection_exists(&index) {
+        return Err(EsCompatError::ResourceAlreadyExists(format!(
+            "index [{}] already exists",
+            index
+        )));
+    }
+    if let Some(aliases) = &state.aliases {
+        if aliases.is_alias(&index).await {
+            return Err(EsCompatError::InvalidIndexName(format!(
+                "Invalid index name [{}], an alias with the same name already exists",
+                index
+            )));
+        }
+    }
+
+    let request: EsCreateIndexRequest = parse_optional_body(&body)?;
+    let schema = es_mapping::collection_schema(&index, &request.mappings.unwrap_or_default())
+        .map_err(mapper_error)?;
+
+    // Check the aliases before creating anything
+    let mut changes = Vec::with_capacity(request.aliases.len());
+    for (alias, options) in request.aliases {
+        check_alias_name(&state.manager, &alias)?;
+        changes.push(AliasChange::Add {
+            alias,
+            target: index.clone(),
+            alias_type: alias_type(options.is_write_index),
+        });
+    }
+    let aliases = if changes.is_empty() {
+        None
+    } else {
+        Some(require_aliases(&state)?)
+    };
+
+    state
+        .manager
+        .add_collection(schema)
+        .await
+        .map_err(mapper_error)?;
+    if let Some(aliases) = aliases {
+        aliases.apply(changes).await?;
+    }
+
+    Ok(Json(EsCreateIndexResponse {
+        acknowledged: true,
+        shards_acknowledged: true,
+        index,
+    }))
+}
+
+/// DELETE /_elastic/{index} - Delete one or more comma-separated indices
+///
+/// Wildcards are rejected, as with ES's default
+/// `action.destructive_requires_name`.
+pub async fn delete_index_handler(
+    State(state): State<EsCompatState>,
+    Path(index): Path<String>,
+) -> Result<Json<EsAcknowledgedResponse>, EsCompatError> {
+    let names: Vec<&str> = index.split(',').map(str::trim).collect();
+    for name in &names {
+        if name.contains('*') || name.contains('?') || *name == "_all" {
+            return Err(EsCompatError::IllegalArgument(
+                "Wildcard expressions or all indices are not allowed".to_string(),
+            ));
+        }
+        if state.manager.collection_exists(name) {
+            continue;
+        }
+        if let Some(aliases) = &state.aliases {
+            if aliases.is_alias(name).await {
+                return Err(EsCompatError::IllegalArgument(format!(
+                    "The provided expression [{}] matches an alias, specify the corresponding concrete indices instead.",
+                    name
+                )));
+            }
+        }
+        return Err(EsCompatError::IndexNotFound(name.to_string()));
+    }
+
+    for name in names {
+        state.manager.delete_collection(name).await?;
+        state.versions.forget_index(name).await;
+        if let Some(aliases) = &state.aliases {
+            aliases.remove_target_from_all(name).await?;
+        }
+    }
+
+    Ok(Json(EsAcknowledgedResponse { acknowledged: true }))
+}
+
+/// HEAD /_elastic/{index} - Check whether indices or aliases exist
+pub async fn head_index_handler(
+    State(state): State<EsCompatState>,
+    Path(index): Path<String>,
+) -> StatusCode {
+    if state.resolve_indices(&index).await.is_empty() {
+        StatusCode::NOT_FOUND
+    } else {
+        StatusCode::OK
+    }
+}
+
+/// PUT /_elastic/{index}/_mapping - Add fields to existing indices
+///
+/// Mappings are additive: existing fields keep their type and a
+/// `dense_vector` field can only be repeated as already mapped.
+pub async fn put_mapping_handler(
+    State(state): State<EsCompatState>,
+    Path(index): Path<String>,
+    Json(mappings): Json<EsMappings>,
+) -> Result<Json<EsAcknowledgedResponse>, EsCompatError> {
+    let collections = state.resolve_indices(&index).await;
+    if collections.is_empty() {
+        return Err(EsCompatError::IndexNotFound(index));
+    }
+    let converted = es_mapping::convert_mappings(&mappings).map_err(mapper_error)?;
+
+    // Validate every index before changing any of them
+    for collection in &collections {
+        let schema = state
+            .manager
+            .get_schema(collection)
+            .ok_or_else(|| EsCompatError::IndexNotFound(collection.clone()))?;
+
+        if let Some(vector) = &converted.vector {
+            let unchanged = schema.backends.vector.as_ref().is_some_and(|existing| {
+                existing.embedding_field == vector.embedding_field
+                    && existing.dimension == vector.dimension
+            });
+            if !unchanged {
+                return Err(EsCompatError::IllegalArgument(format!(
+                    "cannot add dense_vector field [{}] to existing index [{}]",
+                    vector.embedding_field, collection
+                )));
+            }
+        }
+
+        if converted.text_fields.is_empty() {
+            continue;
+        }
+        let existing_fields = schema
+            .backends
+            .text
+            .as_ref()
+            .map(|text| text.fields.as_slice())
+            .ok_or_else(|| {
+                EsCompatError::IllegalArgument(format!(
+                    "index [{}] only holds vectors and cannot take new fields",
+                    collection
+                ))
+            })?;
+        for field in &converted.text_fields {
+            if let Some(existing) = existing_fields.iter().find(|f| f.name == field.name) {
+                if existing.field_type != field.field_type {
+                    return Err(EsCompatError::IllegalArgument(format!(
+                        "mapper [{}] cannot be changed from type [{}] to [{}]",
+                        field.name,
+                        es_field_type(&existing.field_type),
+                        es_field_type(&field.field_type)
+                    )));
+                }
+            }
+        }
+    }
+
+    if !converted.text_fields.is_empty() {
+        for collection in &collections {
+            state
+                .manager
+                .add_text_fields(collection, converted.text_fields.clone())
+                .await?;
+        }
+    }
+
+    Ok(Json(EsAcknowledgedResponse { acknowledged: true }))
+}
+
+/// GET /_elastic/_aliases - Aliases of every index
+pub async fn get_aliases_handler(State(state): State<EsCompatState>) -> Json<EsAliasesResponse> {
+    let mut indices: HashMap<String, EsIndexAliases> = state
+        .manager
+        .list_collections()
+        .into_iter()
+        .map(|collection| (collection, EsIndexAliases::default()))
+        .collect();
+
+    if let Some(aliases) = &state.aliases {
+        for alias in aliases.list().await {
+            let info = EsAliasInfo {
+                is_write_index: (alias.alias_type == AliasType::Write).then_some(true),
+            };
+            for target in &alias.targets {
+                if let Some(entry) = indices.get_mut(target) {
+                    entry.aliases.insert(alias.name.clone(), info.clone());
+                }
+            }
+        }
+    }
+
+    Json(EsAliasesResponse { indices })
+}
+
+/// POST /_elastic/_aliases - Add and remove aliases in one atomic update
+pub async fn update_aliases_handler(
+    State(state): State<EsCompatState>,
+    Json(request): Json<EsAliasesRequest>,
+) -> Result<Json<EsAcknowledgedResponse>, EsCompatError> {
+    let aliases = require_aliases(&state)?;
+
+    let mut changes = Vec::new();
+    for action in request.actions {
+        match action {
+            EsAliasAction::Add(params) => {
+                let alias_names = action_aliases(&params)?;
+                for alias in &alias_names {
+                    check_alias_name(&state.manager, alias)?;
+                }
+                for index in action_indices(&state.manager, &params)? {
+                    for alias in &alias_names {
+                        changes.push(AliasChange::Add {
+                            alias: alias.clone(),
+                            target: index.clone(),
+                            alias_type: alias_type(params.is_write_index),
+                        });
+                    }
+                }
+            }
+            EsAliasAction::Remove(params) => {
+                let alias_names = action_aliases(&params)?;
+                for index in action_indices(&state.manager, &params)? {
+                    for alias in &alias_names {
+                        changes.push(AliasChange::Remove {
+                            alias: alias.clone(),
+                            target: index.clone(),
+                        });
+                    }
+                }
+            }
+            EsAliasAction::RemoveIndex(_) => {
+                return Err(EsCompatError::IllegalArgument(
+                    "[remove_index] alias actions are not supported; use DELETE /{index}"
+                        .to_string(),
+                ));
+            }
+        }
+    }
+
+    aliases.apply(changes).await?;
+    Ok(Json(EsAcknowledgedResponse { acknowledged: true }))
+}
+
+/// ES index naming rules on top of Prism's collection naming rules
+fn validate_index_name(name: &str) -> Result<(), EsCompatError> {
+    if name.starts_with(['_', '-', '+']) {
+        return Err(EsCompatError::InvalidIndexName(format!(
+            "Invalid index name [{}], must not start with '_', '-', or '+'",
+            name
+        )));
+    }
+    if name.chars().any(char::is_uppercase) {
+        return Err(EsCompatError::InvalidIndexName(format!(
+            "Invalid index name [{}], must be lowercase",
+            name
+        )));
+    }
+    CollectionManager::validate_collection_name(name).map_err(|_| {
+        EsCompatError::InvalidIndexName(format!(
+            "Invalid index name [{}], must only contain letters, digits, '-' and '_'",
+            name
+        ))
+    })
+}
+
+fn check_alias_name(manager: &CollectionManager, alias: &str) -> Result<(), EsCompatError> {
+    if manager.collection_exists(alias) {
+        return Err(EsCompatError::IllegalArgument(format!(
+            "Invalid alias name [{}]: an index exists with the same name as the alias",
+            alias
+        )));
+    }
+    Ok(())
+}
+
+fn require_aliases(state: &EsCompatState) -> Result<Arc<AliasManager>, EsCompatError> {
+    state.aliases.clone().ok_or_else(|| {
+        EsCompatError::IllegalArgument("index aliases are not enabled on this node".to_string())
+    })
+}
+
+fn alias_type(is_write_index: Option<bool>) -> AliasType {
+    if is_write_index == Some(true) {
+        AliasType::Write
+    } else {
+        AliasType::Read
+    }
+}
+
+fn action_aliases(params: &EsAliasActionParams) -> Result<Vec<String>, EsCompatError> {
+    let names = params.alias_names();
+    if names.is_empty() {
+        return Err(EsCompatError::MissingField("alias".to_string()));
+    }
+    Ok(names)
+}
+
+/// Concrete indices of an alias action; wildcards may match nothing
+fn action_indices(
+    manager: &CollectionManager,
+    params: &EsAliasActionParams,
+) -> Result<Vec<String>, EsCompatError> {
+    let names = params.index_names();
+    if names.is_empty() {
+        return Err(EsCompatError::MissingField("index".to_string()));
+    }
+    if let Some(missing) = names
+        .iter()
+        .find(|name| !name.contains('*') && !manager.collection_exists(name))
+    {
+        return Err(EsCompatError::IndexNotFound(missing.clone()));
+    }
+    Ok(manager.expand_collection_patterns(&names))
+}
+
+/// Schema problems are reported as mapping errors
+fn mapper_error(e: prism::Error) -> EsCompatError {
+    match e {
+        prism::Error::Schema(reason) => EsCompatError::MapperParsing(reason),
+        other => other.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_index_name() {
+        assert!(validate_index_name("logs-2024_01").is_ok());
+        for name in ["_hidden", "-dash", "+plus", "Upper", "with.dot", "a b", ""] {
+            assert!(
+                matches!(
+                    validate_index_name(name),
+                    Err(EsCompatError::InvalidIndexName(_))
+                ),
+                "{name} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_alias_type() {
+        assert_eq!(alias_type(Some(true)), AliasType::Write);
+        assert_eq!(alias_type(Some(false)), AliasType::Read);
+        assert_eq!(alias_type(None), AliasType::Read);
+    }
+}
@@ -0,0 +1,651 @@
+//! ES-compatible ingest pipeline API
+//!
+//! ES pipeline definitions are translated into Prism processors and stored
+//! in the shared [`PipelineRegistry`], so a pipeline created through
+//! `PUT /_ingest/pipeline/{id}` also serves `?pipeline=` on native indexing.
+//! Processors and options without a Prism counterpart are rejected by name
+//! instead of being dropped.
+
+use crate::endpoints::search::EsCompatState;
+use crate::error::EsCompatError;
+use crate::response::wildcard_matches;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use prism::backends::Document;
+use prism::pipeline::processors::{
+    AppendProcessor, ConvertProcessor, ConvertTarget, GsubProcessor, HtmlStripProcessor,
+    IgnoreFailure, IgnoreMissing, JoinProcessor, LowercaseProcessor, RemoveProcessor,
+    RenameProcessor, SetProcessor, SplitProcessor, TrimProcessor, UppercaseProcessor,
+};
+use prism::pipeline::registry::{Pipeline, PipelineRegistry};
+use prism::pipeline::Processor;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Ingest pipelines shared between the ES and native APIs
+pub struct IngestPipelines {
+    registry: Arc<PipelineRegistry>,
+    /// ES definitions of the pipelines created through this API
+    definitions: RwLock<BTreeMap<String, Value>>,
+}
+
+impl Default for IngestPipelines {
+    fn default() -> Self {
+        Self::new(Arc::new(PipelineRegistry::empty()))
+    }
+}
+
+impl IngestPipelines {
+    pub fn new(registry: Arc<PipelineRegistry>) -> Self {
+        Self {
+            registry,
+            definitions: RwLock::default(),
+        }
+    }
+
+    /// Run pipeline `id` over a document.
+    ///
+    /// `_none` disables the pipeline, as in Elasticsearch.
+    pub fn apply(&self, id: &str, doc: &mut Document) -> Result<(), EsCompatError> {
+        if id == "_none" {
+            return Ok(());
+        }
+        let pipeline = self.registry.get(id).ok_or_else(|| {
+            EsCompatError::IllegalArgument(format!("pipeline with id [{}] does not exist", id))
+        })?;
+        pipeline
+            .process(doc)
+            .map_err(|e| EsCompatError::IllegalArgument(e.to_string()))
+    }
+
+    /// Names of the pipelines matching a comma-separated id expression.
+    fn matching(&self, expression: &str) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .registry
+            .list()
+            .into_iter()
+            .map(|(name, _, _)| name)
+            .filter(|name| {
+                expression
+                    .split(',')
+                    .any(|pattern| wildcard_matches(pattern.trim(), name))
+            })
+            .collect();
+        names.sort();
+        names
+    }
+}
+
+/// GET /_elastic/_ingest/pipeline[/{id}] - Get pipeline definitions
+///
+/// Pipelines loaded from Prism's pipeline directory have no ES definition
+/// and are reported with their description only.
+pub async fn get_pipeline_handler(
+    State(state): State<EsCompatState>,
+    id: Option<Path<String>>,
+) -> Result<(StatusCode, Json<Value>), EsCompatError> {
+    let expression = id.map(|p| p.0).unwrap_or_else(|| "*".to_string());
+    let names = state.ingest.matching(&expression);
+    let definitions = state.ingest.definitions.read().await;
+
+    let mut body = Map::new();
+    for name in names {
+        let definition = match definitions.get(&name) {
+            Some(definition) => definition.clone(),
+            None => {
+                let description = state
+                    .ingest
+                    .registry
+                    .get(&name)
+                    .map(|p| p.description.clone())
+                    .unwrap_or_default();
+                json!({ "description": description })
+            }
+        };
+        body.insert(name, definition);
+    }
+
+    let status = if body.is_empty() && expression != "*" {
+        StatusCode::NOT_FOUND
+    } else {
+        StatusCode::OK
+    };
+    Ok((status, Json(Value::Object(body))))
+}
+
+/// PUT /_elastic/_ingest/pipeline/{id} - Create or replace a pipeline
+pub async fn put_pipeline_handler(
+    State(state): State<EsCompatState>,
+    Path(id): Path<String>,
+    Json(definition): Json<Value>,
+) -> Result<Json<Value>, EsCompatError> {
+    let pipeline = translate_pipeline(&id, &definition)?;
+
+    let mut definitions = state.ingest.definitions.write().await;
+    state.ingest.registry.insert(pipeline);
+    definitions.insert(id, definition);
+    Ok(Json(json!({ "acknowledged": true })))
+}
+
+/// DELETE /_elastic/_ingest/pipeline/{id} - Delete pipelines
+pub async fn delete_pipeline_handler(
+    State(state): State<EsCompatState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, EsCompatError> {
+    let names = state.ingest.matching(&id);
+    if names.is_empty() {
+        return Err(EsCompatError::ResourceNotFound(format!(
+            "pipeline [{}] is missing",
+            id
+        )));
+    }
+
+    let mut definitions = state.ingest.definitions.write().await;
+    for name in names {
+        state.ingest.registry.remove(&name);
+        definitions.remove(&name);
+    }
+    Ok(Json(json!({ "acknowledged": true })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SimulateRequest {
+    /// Inline pipeline definition, required without a pipeline id
+    #[serde(default)]
+    pub pipeline: Option<Value>,
+    pub docs: Vec<SimulateDoc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SimulateDoc {
+    #[serde(default, rename = "_index")]
+    pub index: Option<String>,
+    #[serde(default, rename = "_id")]
+    pub id: Option<String>,
+    #[serde(rename = "_source")]
+    pub source: Map<String, Value>,
+}
+
+/// POST /_elastic/_ingest/pipeline/_simulate - Run an inline pipeline
+/// POST /_elastic/_ingest/pipeline/{id}/_simulate - Run a stored pipeline
+///
+/// Documents are processed but not indexed; failures are reported per
+/// document.
+pub async fn simulate_pipeline_handler(
+    State(state): State<EsCompatState>,
+    id: Option<Path<String>>,
+    Json(request): Json<SimulateRequest>,
+) -> Result<Json<Value>, EsCompatError> {
+    let pipeline = match (id, &request.pipeline) {
+        (Some(Path(id)), _) => state.ingest.registry.get(&id).ok_or_else(|| {
+            EsCompatError::ResourceNotFound(format!("pipeline [{}] is missing", id))
+        })?,
+        (None, Some(definition)) => Arc::new(translate_pipeline("_simulate_pipeline", definition)?),
+        (None, None) => return Err(EsCompatError::MissingField("pipeline".to_string())),
+    };
+
+    let docs = request
+        .docs
+        .into_iter()
+        .map(|sim| {
+            let index = sim.index.unwrap_or_else(|| "_index".to_string());
+            let id = sim.id.unwrap_or_else(|| "_id".to_string());
+            let mut doc = Document {
+                id: id.clone(),
+                fields: sim.source.into_iter().collect(),
+            };
+            match pipeline.process(&mut doc) {
+                Ok(()) => json!({
+                    "doc": {
+                        "_index": index,
+                        "_id": id,
+                        "_source": doc.fields.into_iter().collect::<Map<_, _>>(),
+                        "_ingest": { "timestamp": chrono::Utc::now().to_rfc3339() },
+                    }
+                }),
+                Err(e) => json!({
+                    "error": {
+                        "type": "illegal_argument_exception",
+                        "reason": e.to_string(),
+                    }
+                }),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(json!({ "docs": docs })))
+}
+
+/// Options accepted by every processor; `tag` and `description` are
+/// informational.
+const COMMON_OPTIONS: &[&str] = &["tag", "description", "ignore_failure"];
+
+/// Translate an ES pipeline definition into a Prism pipeline.
+///
+/// Unsupported processors and options are collected and reported together,
+/// e.g. `unsupported ingest processors: [grok], [lowercase.target_field]`.
+pub fn translate_pipeline(id: &str, definition: &Value) -> Result<Pipeline, EsCompatError> {
+    let definition = definition.as_object().ok_or_else(|| {
+        EsCompatError::InvalidRequestBody("pipeline definition must be an object".to_string())
+    })?;
+    let description = definition
+        .get("description")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let entries = match definition.get("processors") {
+        Some(Value::Array(entries)) => entries.as_slice(),
+        Some(_) => {
+            return Err(EsCompatError::ParseError(
+                "[processors] must be an array".to_string(),
+            ))
+        }
+        None => {
+            return Err(EsCompatError::ParseError(
+                "[processors] required property is missing".to_string(),
+            ))
+        }
+    };
+
+    let mut processors = Vec::with_capacity(entries.len());
+    let mut unsupported = Vec::new();
+    for entry in entries {
+        let (kind, params) = match entry.as_object() {
+            Some(map) if map.len() == 1 => map.iter().next().unwrap(),
+            _ => {
+                return Err(EsCompatError::ParseError(
+                    "each processor must be an object with a single processor type".to_string(),
+                ))
+            }
+        };
+        let params = params.as_object().ok_or_else(|| {
+            EsCompatError::ParseError(format!("[{}] processor options must be an object", kind))
+        })?;
+
+        match translate_processor(kind, params) {
+            Ok(translated) => processors.extend(translated),
+            Err(Translation::Unsupported(name)) => unsupported.push(format!("[{}]", name)),
+            Err(Translation::Invalid(e)) => return Err(e),
+        }
+    }
+
+    if !unsupported.is_empty() {
+        return Err(EsCompatError::IllegalArgument(format!(
+            "unsupported ingest processors: {}",
+            unsupported.join(", ")
+        )));
+    }
+
+    Ok(Pipeline {
+        name: id.to_string(),
+        description,
+        processors,
+    })
+}
+
+/// Why a processor could not be translated
+enum Translation {
+    /// Processor type or option without a Prism counterpart
+    Unsupported(String),
+    /// Malformed processor definition
+    Invalid(EsCompatError),
+}
+
+impl From<EsCompatError> for Translation {
+    fn from(e: EsCompatError) -> Self {
+        Self::Invalid(e)
+    }
+}
+
+fn translate_processor(
+    kind: &str,
+    params: &Map<String, Value>,
+) -> Result<Vec<Box<dyn Processor>>, Translation> {
+    let options: &[&str] = match kind {
+        "set" => &["field", "value", "override"],
+        "remove" => &["field", "ignore_missing"],
+        "rename" => &["field", "target_field", "ignore_missing"],
+        "lowercase" | "uppercase" | "trim" | "html_strip" => {
+            &["field", "target_field", "ignore_missing"]
+        }
+        "append" => &["field", "value", "allow_duplicates"],
+        "convert" => &["field", "type", "target_field", "ignore_missing"],
+        "split" => &["field", "separator", "ignore_missing"],
+        "join" => &["field", "separator"],
+        "gsub" => &["field", "pattern", "replacement", "ignore_missing"],
+        other => return Err(Translation::Unsupported(other.to_string())),
+    };
+    if let Some(option) = params
+        .keys()
+        .find(|key| !options.contains(&key.as_str()) && !COMMON_OPTIONS.contains(&key.as_str()))
+    {
+        return Err(Translation::Unsupported(format!("{}.{}", kind, option)));
+    }
+
+    let field = string_param(kind, params, "field");
+    // Processors that write in place only support `target_field` naming
+    // the source field
+    let in_place = |field: &str| match params.get("target_field") {
+        Some(target) if target.as_str() != Some(field) => {
+            Err(Translation::Unsupported(format!("{}.target_field", kind)))
+        }
+        _ => Ok(()),
+    };
+
+    let translated: Vec<Box<dyn Processor>> = match kind {
+        "set" => {
+            let field = field?;
+            let value = params
+                .get("value")
+                .ok_or_else(|| missing_param(kind, "value"))?;
+            let overwrite = bool_param(params, "override").unwrap_or(true);
+            let value = match value {
+                Value::String(s) if is_timestamp_template(s) => Value::String("{{_now}}".into()),
+                Value::String(s) if s.contains("{{") => {
+                    return Err(Translation::Unsupported(format!("{}.value template", kind)))
+                }
+                other => other.clone(),
+            };
+            match value {
+                Value::String(value) if overwrite => vec![Box::new(SetProcessor { field, value })],
+                value => vec![Box::new(SetValueProcessor {
+                    field,
+                    value,
+                    overwrite,
+                })],
+            }
+        }
+        "remove" => {
+            let fields = match params.get("field") {
+                Some(Value::String(field)) => vec![field.clone()],
+                Some(Value::Array(fields)) => fields
+                    .iter()
+                    .map(|f| f.as_str().map(String::from))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| invalid_param(kind, "field"))?,
+                Some(_) => return Err(invalid_param(kind, "field").into()),
+                None => return Err(missing_param(kind, "field").into()),
+            };
+            fields
+                .into_iter()
+                .map(|field| Box::new(RemoveProcessor { field }) as Box<dyn Processor>)
+                .collect()
+        }
+        "rename" => {
+            let from = field?;
+            let to = string_param(kind, params, "target_field")?;
+            vec![Box::new(RenameProcessor { from, to })]
+        }
+        "lowercase" => {
+            let field = field?;
+            in_place(&field)?;
+            vec![Box::new(LowercaseProcessor { field })]
+        }
+        "uppercase" => {
+            let field = field?;
+            in_place(&field)?;
+            vec![Box::new(UppercaseProcessor { field })]
+        }
+        "trim" => {
+            let field = field?;
+            in_place(&field)?;
+            vec![Box::new(TrimProcessor { field })]
+        }
+        "html_strip" => {
+            let field = field?;
+            in_place(&field)?;
+            vec![Box::new(HtmlStripProcessor { field })]
+        }
+        "append" => {
+            if bool_param(params, "allow_duplicates") == Some(false) {
+                return Err(Translation::Unsupported(format!(
+                    "{}.allow_duplicates",
+                    kind
+                )));
+            }
+            let values = match params.get("value") {
+                Some(Value::Array(values)) => values.clone(),
+                Some(value) => vec![value.clone()],
+                None => return Err(missing_param(kind, "value").into()),
+            };
+            vec![Box::new(AppendProcessor {
+                field: field?,
+                values,
+            })]
+        }
+        "convert" => {
+            let field = field?;
+            in_place(&field)?;
+            let target = ConvertTarget::parse(&string_param(kind, params, "type")?)
+                .map_err(|e| EsCompatError::IllegalArgument(e.to_string()))?;
+            vec![Box::new(ConvertProcessor { field, target })]
+        }
+        "split" => vec![Box::new(SplitProcessor {
+            field: field?,
+            separator: regex_param(kind, params, "separator")?,
+        })],
+        "join" => vec![Box::new(JoinProcessor {
+            field: field?,
+            separator: string_param(kind, params, "separator")?,
+        })],
+        "gsub" => vec![Box::new(GsubProcessor {
+            field: field?,
+            pattern: regex_param(kind, params, "pattern")?,
+            replacement: string_param(kind, params, "replacement")?,
+        })],
+        _ => unreachable!("processor options are matched above"),
+    };
+
+    Ok(translated
+        .into_iter()
+        .map(|processor| wrap_options(params, processor))
+        .collect())
+}
+
+/// Apply `ignore_missing` and `ignore_failure` around a processor.
+fn wrap_options(params: &Map<String, Value>, processor: Box<dyn Processor>) -> Box<dyn Processor> {
+    let processor = match (
+        bool_param(params, "ignore_missing"),
+        params.get("field").and_then(Value::as_str),
+    ) {
+        (Some(true), Some(field)) => Box::new(IgnoreMissing {
+            field: field.to_string(),
+            processor,
+        }),
+        _ => processor,
+    };
+    match bool_param(params, "ignore_failure") {
+        Some(true) => Box::new(IgnoreFailure { processor }),
+        _ => processor,
+    }
+}
+
+/// Set a field to an arbitrary JSON value, optionally keeping an existing
+/// non-null value as ES `set` does with `override: false`.
+struct SetValueProcessor {
+    field: String,
+    value: Value,
+    overwrite: bool,
+}
+
+impl Processor for SetValueProcessor {
+    fn name(&self) -> &str {
+        "set"
+    }
+
+    fn process(&self, doc: &mut Document) -> prism::Result<()> {
+        let keep = !self.overwrite && doc.fields.get(&self.field).is_some_and(|v| !v.is_null());
+        if !keep {
+            let value = match &self.value {
+                Value::String(s) if s == "{{_now}}" => {
+                    Value::String(chrono::Utc::now().to_rfc3339())
+                }
+                other => other.clone(),
+            };
+            doc.fields.insert(self.field.clone(), value);
+        }
+        Ok(())
+    }
+}
+
+/// `{{_ingest.timestamp}}` or `{{{_ingest.timestamp}}}`
+fn is_timestamp_template(value: &str) -> bool {
+    let inner = value.trim_start_matches('{').trim_end_matches('}');
+    inner.trim() == "_ingest.timestamp" && value.starts_with("{{") && value.ends_with("}}")
+}
+
+fn string_param(kind: &str, params: &Map<String, Value>, key: &str) -> Result<String, Translation> {
+    match params.get(key) {
+        Some(Value::String(s)) => Ok(s.clone()),
+        Some(_) => Err(invalid_param(kind, key).into()),
+        None => Err(missing_param(kind, key).into()),
+    }
+}
+
+fn regex_param(
+    kind: &str,
+    params: &Map<String, Value>,
+    key: &str,
+) -> Result<regex::Regex, Translation> {
+    let pattern = string_param(kind, params, key)?;
+    regex::Regex::new(&pattern).map_err(|e| {
+        EsCompatError::ParseError(format!("[{}] processor [{}] is invalid: {}", kind, key, e))
+            .into()
+    })
+}
+
+fn bool_param(params: &Map<String, Value>, key: &str) -> Option<bool> {
+    params.get(key).and_then(Value::as_bool)
+}
+
+fn missing_param(kind: &str, key: &str) -> EsCompatError {
+    EsCompatError::ParseError(format!("[{}] [{}] required property is missing", kind, key))
+}
+
+fn invalid_param(kind: &str, key: &str) -> EsCompatError {
+    EsCompatError::ParseError(format!("[{}] [{}] has an invalid type", kind, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn doc(fields: Value) -> Document {
+        let Value::Object(fields) = fields else {
+            panic!("fields must be an object")
+        };
+        Document {
+            id: "1".to_string(),
+            fields: fields.into_iter().collect::<HashMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn test_translate_core_processors() {
+        let pipeline = translate_pipeline(
+            "p",
+            &json!({
+                "description": "tidy",
+                "processors": [
+                    {"rename": {"field": "Title", "target_field": "title"}},
+                    {"lowercase": {"field": "title"}},
+                    {"html_strip": {"field": "body"}},
+                    {"remove": {"field": ["tmp", "debug"]}},
+                    {"set": {"field": "source", "value": "es"}},
+                    {"set": {"field": "rank", "value": 3}},
+                ]
+            }),
+        )
+        .unwrap();
+        assert_eq!(pipeline.description, "tidy");
+        assert_eq!(pipeline.processors.len(), 7);
+
+        let mut d = doc(json!({
+            "Title": "HELLO", "body": "<b>x</b>", "tmp": 1, "debug": true
+        }));
+        pipeline.process(&mut d).unwrap();
+        assert_eq!(
+            d.fields,
+            doc(json!({"title": "hello", "body": "x", "source": "es", "rank": 3})).fields
+        );
+    }
+
+    #[test]
+    fn test_translate_reports_unsupported() {
+        let err = translate_pipeline(
+            "p",
+            &json!({"processors": [
+                {"grok": {"field": "message", "patterns": []}},
+                {"lowercase": {"field": "a", "target_field": "b"}},
+                {"set": {"field": "a", "value": "x", "if": "ctx.a == null"}},
+                {"uppercase": {"field": "c"}},
+            ]}),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "unsupported ingest processors: [grok], [lowercase.target_field], [set.if]"
+        );
+    }
+
+    #[test]
+    fn test_translate_invalid_definition() {
+        assert!(translate_pipeline("p", &json!({})).is_err());
+        assert!(
+            translate_pipeline("p", &json!({"processors": [{"set": {"field": "a"}}]})).is_err()
+        );
+        assert!(translate_pipeline(
+            "p",
+            &json!({"processors": [{"convert": {"field": "a", "type": "ip"}}]})
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_ignore_options() {
+        let pipeline = translate_pipeline(
+            "p",
+            &json!({"processors": [
+                {"lowercase": {"field": "missing", "ignore_missing": true}},
+                {"convert": {"field": "n", "type": "integer", "ignore_failure": true}},
+            ]}),
+        )
+        .unwrap();
+        let mut d = doc(json!({"n": "abc"}));
+        pipeline.process(&mut d).unwrap();
+        assert_eq!(d.fields["n"], json!("abc"));
+    }
+
+    #[test]
+    fn test_set_without_override_and_timestamp() {
+        let pipeline = translate_pipeline(
+            "p",
+            &json!({"processors": [
+                {"set": {"field": "a", "value": "new", "override": false}},
+                {"set": {"field": "b", "value": "new", "override": false}},
+                {"set": {"field": "at", "value": "{{{_ingest.timestamp}}}"}},
+            ]}),
+        )
+        .unwrap();
+        let mut d = doc(json!({"a": "old"}));
+        pipeline.process(&mut d).unwrap();
+        assert_eq!(d.fields["a"], json!("old"));
+        assert_eq!(d.fields["b"], json!("new"));
+        assert!(chrono::DateTime::parse_from_rfc3339(d.fields["at"].as_str().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_apply_none_and_unknown_pipeline() {
+        let ingest = IngestPipelines::default();
+        let mut d = doc(json!({"a": 1}));
+        assert!(ingest.apply("_none", &mut d).is_ok());
+        let err = ingest.apply("nope", &mut d).unwrap_err();
+        assert_eq!(err.to_string(), "pipeline with id [nope] does not exist");
+    }
+}
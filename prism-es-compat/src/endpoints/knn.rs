@@ -0,0 +1,475 @@
+//! ES `knn` search and `function_score`/`script_score` rescoring
+//!
+//! Requests with a `knn` section or a top-level scoring query cannot be run
+//! as a single backend search. The `query` leg and each `knn` leg are
+//! retrieved separately, rescored where needed and combined here, either by
+//! summing scores or with reciprocal rank fusion (`rank.rrf`).
+
+use crate::endpoints::scroll::parse_keep_alive;
+use crate::endpoints::search::{check_pinned_sort, field_sort, get_text_fields, EsCompatState};
+use crate::error::EsCompatError;
+use crate::query::{EsKnnQuery, EsQuery, EsSearchRequest, FunctionScorer, QueryTranslator};
+use crate::response::{EsSearchResponse, ResponseMapper};
+use prism::backends::SearchBackend;
+use prism::collection::{MultiSearchResult, PointInTimeResults};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+/// Query hits rescored by a `function_score` or `script_score` query
+const RESCORE_WINDOW: usize = 1000;
+/// Default `rank.rrf.rank_constant`
+const DEFAULT_RANK_CONSTANT: usize = 60;
+/// Default `rank.rrf.rank_window_size`
+const DEFAULT_RANK_WINDOW: usize = 100;
+/// Upper bound of `num_candidates`, as in Elasticsearch
+const MAX_NUM_CANDIDATES: usize = 10_000;
+
+/// Whether a search request needs [`scored_search`]
+pub(crate) fn is_scored(request: &EsSearchRequest) -> bool {
+    request.knn.is_some()
+        || match request.query {
+            Some(EsQuery::FunctionScore(_)) | Some(EsQuery::ScriptScore(_)) => true,
+            // Demoting negative matches only changes the relevance order
+            Some(EsQuery::Boosting(_)) => field_sort(request).is_none(),
+            _ => false,
+        }
+}
+
+/// Run a request with `knn` legs or a scoring query over `collections`, or
+/// over the point in time named in the request
+pub(crate) async fn scored_search(
+    state: &EsCompatState,
+    index_name: &str,
+    collections: &[String],
+    request: &EsSearchRequest,
+    start: Instant,
+) -> Result<EsSearchResponse, EsCompatError> {
+    let pit = request.pit.as_ref();
+    let collections = match pit {
+        Some(pit) => {
+            let keep_alive = pit
+                .keep_alive
+                .as_deref()
+                .map(parse_keep_alive)
+                .transpose()?;
+            state.manager.touch_point_in_time(&pit.id, keep_alive)?
+        }
+        None => collections.to_vec(),
+    };
+    let pit_id = pit.map(|pit| pit.id.as_str());
+    if pit.is_some() && request.search_after.is_some() {
+        return Err(EsCompatError::InvalidRequestBody(
+            "[search_after] is not supported with [knn] or scoring queries".to_string(),
+        ));
+    }
+    check_pinned_sort(request, "[knn] or scoring queries")?;
+
+    let default_fields = get_text_fields(&state.manager, &collections[0]);
+    let (mut query, aggregations) = QueryTranslator::translate(request, &default_fields)?;
+    let (from, size) = (query.offset, query.limit);
+
+    if request.knn.is_some() && !aggregations.is_empty() {
+        return Err(EsCompatError::InvalidQuery(
+            "aggregations are not supported together with [knn]".to_string(),
+        ));
+    }
+    if !aggregations.is_empty() && collections.len() > 1 {
+        return Err(EsCompatError::InvalidQuery(
+            "aggregations with scoring queries are only supported on a single index".to_string(),
+        ));
+    }
+
+    let knn: Vec<&EsKnnQuery> = request.knn.iter().flat_map(|k| k.iter()).collect();
+    let legs = usize::from(request.query.is_some()) + knn.len();
+    let rrf = match &request.rank {
+        Some(rank) => {
+            let rrf = rank.rrf.clone().ok_or_else(|| {
+                EsCompatError::InvalidQuery("[rank] only supports [rrf]".to_string())
+            })?;
+            if legs < 2 {
+                return Err(EsCompatError::InvalidQuery(
+                    "[rank] requires a combination of at least 2 result sets, such as [query] and [knn]"
+                        .to_string(),
+                ));
+            }
+            Some(rrf)
+        }
+        None => None,
+    };
+    let rank_window = rrf.as_ref().map(|rrf| {
+        rrf.rank_window_size
+            .unwrap_or(DEFAULT_RANK_WINDOW.max(from + size))
+    });
+    if rank_window.is_some_and(|window| window < from + size) {
+        return Err(EsCompatError::InvalidQuery(
+            "[rank_window_size] must be greater than or equal to [from + size]".to_string(),
+        ));
+    }
+
+    let scorer = request
+        .query
+        .as_ref()
+        .map(FunctionScorer::from_query)
+        .transpose()?
+        .flatten();
+
+    // Query leg
+    let mut query_hits = Vec::new();
+    let mut total = 0;
+    let mut aggregation_results = HashMap::new();
+    if request.query.is_some() {
+        query.offset = 0;
+        query.limit = match (rank_window, &scorer) {
+            (Some(window), _) => window,
+            (None, Some(_)) => RESCORE_WINDOW.max(from + size),
+            (None, None) => from + size,
+        };
+        match pit_id {
+            Some(id) => {
+                let found =
+                    state
+                        .manager
+                        .search_point_in_time(id, &query, aggregations, None, None)?;
+                total = found.total;
+                aggregation_results = found.aggregations;
+                query_hits = found.results;
+            }
+            None => {
+                for collection in &collections {
+                    if !has_text_backend(state, collection) {
+                        continue;
+                    }
+                    let found = state
+                        .manager
+                        .text_backend()
+                        .search_with_aggs(collection, &query, aggregations.clone())
+                        .await?;
+                    total += found.total;
+                    aggregation_results.extend(found.aggregations);
+                    query_hits.extend(found.results.into_iter().map(|r| MultiSearchResult {
+                        id: r.id,
+                        collection: collection.clone(),
+                        score: r.score,
+                        fields: r.fields,
+                        highlight: r.highlight,
+                    }));
+                }
+            }
+        }
+
+        if let Some(scorer) = &scorer {
+            let dropped = rescore(state, scorer, &mut query_hits, pit_id)?;
+            total = total.saturating_sub(dropped as u64);
+        }
+        sort_by_score(&mut query_hits);
+        query_hits.truncate(query.limit);
+    }
+
+    // knn legs
+    let mut knn_hits = Vec::new();
+    for clause in &knn {
+        knn_hits.push(knn_leg(state, &collections, clause, size, pit_id, rank_window).await?);
+    }
+
+    // knn hits outside the query's matches add to the total
+    let query_string = &query.query_string;
+    let mut knn_only = HashSet::new();
+    for hits in &knn_hits {
+        for hit in hits {
+            knn_only.insert((hit.collection.clone(), hit.id.clone()));
+        }
+    }
+    if request.query.is_some() && !knn_only.is_empty() {
+        for collection in &collections {
+            let ids: Vec<String> = knn_only
+                .iter()
+                .filter(|(c, _)| c == collection)
+                .map(|(_, id)| id.clone())
+                .collect();
+            if ids.is_empty() {
+                continue;
+            }
+            let matching = state
+                .manager
+                .filter_ids(collection, &ids, query_string, pit_id)?;
+            knn_only.retain(|(c, id)| c != collection || !matching.contains(id));
+        }
+    }
+    total += knn_only.len() as u64;
+
+    let mut lists = Vec::new();
+    if request.query.is_some() {
+        lists.push(query_hits);
+    }
+    lists.extend(knn_hits);
+    let mut results = match &rrf {
+        Some(rrf) => fuse_rrf(lists, rrf.rank_constant.unwrap_or(DEFAULT_RANK_CONSTANT)),
+        None => sum_scores(lists),
+    };
+    let results: Vec<MultiSearchResult> = {
+        sort_by_score(&mut results);
+        results.into_iter().skip(from).take(size).collect()
+    };
+
+    let took_ms = start.elapsed().as_millis() as u64;
+    let results = PointInTimeResults {
+        results,
+        positions: Vec::new(),
+        total,
+        aggregations: aggregation_results,
+    };
+    let mut response = ResponseMapper::map_point_in_time_results(
+        index_name,
+        results,
+        from,
+        pit.is_some(),
+        took_ms,
+    );
+    response.pit_id = pit_id.map(str::to_string);
+    Ok(response)
+}
+
+/// Run one `knn` clause over every collection whose vector field it names
+/// and keep the best `k` hits
+async fn knn_leg(
+    state: &EsCompatState,
+    collections: &[String],
+    clause: &EsKnnQuery,
+    size: usize,
+    pit_id: Option<&str>,
+    rank_window: Option<usize>,
+) -> Result<Vec<MultiSearchResult>, EsCompatError> {
+    let k = clause.k.unwrap_or(size);
+    if k == 0 {
+        return Err(EsCompatError::InvalidQuery(
+            "[k] must be greater than 0".to_string(),
+        ));
+    }
+    let num_candidates = clause
+        .num_candidates
+        .unwrap_or_else(|| (k + k / 2).min(MAX_NUM_CANDIDATES));
+    if num_candidates < k {
+        return Err(EsCompatError::InvalidQuery(format!(
+            "[num_candidates] cannot be less than [k], got {} < {}",
+            num_candidates, k
+        )));
+    }
+    if num_candidates > MAX_NUM_CANDIDATES {
+        return Err(EsCompatError::InvalidQuery(format!(
+            "[num_candidates] cannot exceed [{}]",
+            MAX_NUM_CANDIDATES
+        )));
+    }
+
+    let targets: Vec<&String> = collections
+        .iter()
+        .filter(|c| embedding_field(state, c).as_deref() == Some(clause.field.as_str()))
+        .collect();
+    if targets.is_empty() {
+        return Err(EsCompatError::InvalidQuery(format!(
+            "[knn] queries are only supported on [dense_vector] fields, [{}] is not one",
+            clause.field
+        )));
+    }
+
+    let vector = match (&clause.query_vector, &clause.query_vector_builder) {
+        (Some(vector), None) => vector.clone(),
+        (None, Some(builder)) => {
+            state
+                .manager
+                .embed_query(targets[0], &builder.text_embedding.model_text)
+                .await?
+        }
+        _ => {
+            return Err(EsCompatError::InvalidQuery(
+                "[knn] requires exactly one of [query_vector] or [query_vector_builder]"
+                    .to_string(),
+            ))
+        }
+    };
+    let filter = clause
+        .filter
+        .as_ref()
+        .map(QueryTranslator::translate_filter)
+        .transpose()?;
+    let boost = clause.boost.unwrap_or(1.0);
+
+    let mut hits = Vec::new();
+    for collection in targets {
+        let found = state
+            .manager
+            .knn_search(
+                collection,
+                &vector,
+                k,
+                num_candidates,
+                filter.as_deref(),
+                pit_id,
+            )
+            .await?;
+        hits.extend(
+            found
+                .results
+                .into_iter()
+                .filter(|r| clause.similarity.is_none_or(|min| r.score >= min))
+                .map(|r| MultiSearchResult {
+                    id: r.id,
+                    collection: collection.clone(),
+                    score: r.score * boost,
+                    fields: r.fields,
+                    highlight: None,
+                }),
+        );
+    }
+    sort_by_score(&mut hits);
+    hits.truncate(rank_window.map_or(k, |window| k.min(window)));
+    Ok(hits)
+}
+
+/// Apply a scorer to query hits in place; hits below `min_score` are
+/// removed and counted
+fn rescore(
+    state: &EsCompatState,
+    scorer: &FunctionScorer,
+    hits: &mut Vec<MultiSearchResult>,
+    pit_id: Option<&str>,
+) -> Result<usize, EsCompatError> {
+    // Hits matching each function filter, as (function, collection, id)
+    let mut matched = HashSet::new();
+    for (function, filter) in scorer.filters() {
+        let mut by_collection: HashMap<&str, Vec<String>> = HashMap::new();
+        for hit in hits.iter() {
+            by_collection
+                .entry(hit.collection.as_str())
+                .or_default()
+                .push(hit.id.clone());
+        }
+        for (collection, ids) in by_collection {
+            for id in state.manager.filter_ids(collection, &ids, filter, pit_id)? {
+                matched.insert((function, collection.to_string(), id));
+            }
+        }
+    }
+
+    let before = hits.len();
+    let mut rescored = Vec::with_capacity(before);
+    for mut hit in std::mem::take(hits) {
+        let result = prism::backends::SearchResult {
+            id: hit.id.clone(),
+            score: hit.score,
+            fields: std::mem::take(&mut hit.fields),
+            highlight: None,
+        };
+        let score = scorer.score(&result, |function| {
+            matched.contains(&(function, hit.collection.clone(), hit.id.clone()))
+        })?;
+        if let Some(score) = score {
+            hit.score = score;
+            hit.fields = result.fields;
+            rescored.push(hit);
+        }
+    }
+    *hits = rescored;
+    Ok(before - hits.len())
+}
+
+/// Reciprocal rank fusion: each list adds `1 / (rank_constant + rank)`
+fn fuse_rrf(lists: Vec<Vec<MultiSearchResult>>, rank_constant: usize) -> Vec<MultiSearchResult> {
+    combine(lists, |rank, _| 1.0 / (rank_constant + rank + 1) as f32)
+}
+
+/// Without `rank`, a hit's score is the sum of its scores in each list
+fn sum_scores(lists: Vec<Vec<MultiSearchResult>>) -> Vec<MultiSearchResult> {
+    combine(lists, |_, score| score)
+}
+
+fn combine(
+    lists: Vec<Vec<MultiSearchResult>>,
+    contribution: impl Fn(usize, f32) -> f32,
+) -> Vec<MultiSearchResult> {
+    let mut combined: Vec<MultiSearchResult> = Vec::new();
+    let mut positions: HashMap<(String, String), usize> = HashMap::new();
+    for list in lists {
+        for (rank, mut hit) in list.into_iter().enumerate() {
+            let score = contribution(rank, hit.score);
+            match positions.get(&(hit.collection.clone(), hit.id.clone())) {
+                Some(&i) => {
+                    combined[i].score += score;
+                    if combined[i].highlight.is_none() {
+                        combined[i].highlight = hit.highlight;
+                    }
+                }
+                None => {
+                    hit.score = score;
+                    positions.insert((hit.collection.clone(), hit.id.clone()), combined.len());
+                    combined.push(hit);
+                }
+            }
+        }
+    }
+    combined
+}
+
+/// Stable sort: equal scores keep their order
+fn sort_by_score(hits: &mut [MultiSearchResult]) {
+    hits.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+fn has_text_backend(state: &EsCompatState, collection: &str) -> bool {
+    state
+        .manager
+        .get_schema(collection)
+        .is_some_and(|schema| schema.backends.text.is_some())
+}
+
+fn embedding_field(state: &EsCompatState, collection: &str) -> Option<String> {
+    state
+        .manager
+        .get_schema(collection)
+        .and_then(|schema| schema.backends.vector.map(|v| v.embedding_field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit(collection: &str, id: &str, score: f32) -> MultiSearchResult {
+        MultiSearchResult {
+            id: id.to_string(),
+            collection: collection.to_string(),
+            score,
+            fields: HashMap::new(),
+            highlight: None,
+        }
+    }
+
+    #[test]
+    fn test_fuse_rrf() {
+        let query = vec![hit("a", "1", 9.0), hit("a", "2", 5.0)];
+        let knn = vec![hit("a", "2", 0.9), hit("a", "3", 0.8)];
+        let mut fused = fuse_rrf(vec![query, knn], 60);
+        sort_by_score(&mut fused);
+
+        let ids: Vec<&str> = fused.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, vec!["2", "1", "3"]);
+        assert!((fused[0].score - (1.0 / 62.0 + 1.0 / 61.0)).abs() < 1e-6);
+        assert!((fused[1].score - 1.0 / 61.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_sum_scores_keeps_collections_apart() {
+        let query = vec![hit("a", "1", 1.0), hit("b", "1", 0.5)];
+        let knn = vec![hit("b", "1", 0.75)];
+        let mut summed = sum_scores(vec![query, knn]);
+        sort_by_score(&mut summed);
+
+        assert_eq!(summed.len(), 2);
+        assert_eq!(summed[0].collection, "b");
+        assert_eq!(summed[0].score, 1.25);
+        assert_eq!(summed[1].score, 1.0);
+    }
+}
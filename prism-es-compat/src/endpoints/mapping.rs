@@ -13,10 +13,8 @@ pub async fn mapping_handler(
     State(state): State<EsCompatState>,
     Path(index): Path<String>,
 ) -> Result<Json<EsMappingResponse>, EsCompatError> {
-    // Expand index pattern (sync)
-    let collections = state
-        .manager
-        .expand_collection_patterns(std::slice::from_ref(&index));
+    // Expand index pattern and aliases
+    let collections = state.resolve_indices(&index).await;
 
     if collections.is_empty() {
         return Err(EsCompatError::IndexNotFound(index));
@@ -36,15 +34,7 @@ pub async fn mapping_handler(
         // Map text fields
         if let Some(text_config) = &schema.backends.text {
             for field in &text_config.fields {
-                let field_type = match field.field_type {
-                    FieldType::Text => "text",
-                    FieldType::String => "keyword",
-                    FieldType::I64 | FieldType::U64 => "long",
-                    FieldType::F64 => "double",
-                    FieldType::Bool => "boolean",
-                    FieldType::Date => "date",
-                    FieldType::Bytes => "binary",
-                };
+                let field_type = es_field_type(&field.field_type);
 
                 let mut mapping = EsFieldMapping {
                     field_type: field_type.to_string(),
@@ -97,3 +87,16 @@ pub async fn mapping_handler(
 
     Ok(Json(EsMappingResponse { indices }))
 }
+
+/// ES type name reported for a Prism field type
+pub(crate) fn es_field_type(field_type: &FieldType) -> &'static str {
+    match field_type {
+        FieldType::Text => "text",
+        FieldType::String => "keyword",
+        FieldType::I64 | FieldType::U64 => "long",
+        FieldType::F64 => "double",
+        FieldType::Bool => "boolean",
+        FieldType::Date => "date",
+        FieldType::Bytes => "binary",
+    }
+}
@@ -1,13 +1,45 @@
 //! ES-compatible API endpoints
 
 pub mod bulk;
+pub mod cat;
 pub mod cluster;
+pub mod document;
+pub mod field_caps;
+pub mod index;
+pub mod ingest;
+pub mod knn;
 pub mod mapping;
 pub mod msearch;
+pub mod scroll;
 pub mod search;
+pub mod terms_enum;
 
 pub use bulk::bulk_handler;
-pub use cluster::{cat_indices_handler, cluster_health_handler, root_handler};
+pub use cat::{
+    cat_aliases_handler, cat_count_handler, cat_health_handler, cat_indices_handler,
+    cat_segments_handler, cat_shards_handler,
+};
+pub use cluster::{
+    cluster_health_handler, cluster_stats_handler, nodes_stats_handler, root_handler,
+};
+pub use document::{
+    count_handler, create_doc_handler, delete_doc_handler, get_doc_handler, head_doc_handler,
+    index_doc_auto_id_handler, index_doc_handler, mget_handler, update_doc_handler,
+};
+pub use field_caps::field_caps_handler;
+pub use index::{
+    create_index_handler, delete_index_handler, get_aliases_handler, head_index_handler,
+    put_mapping_handler, update_aliases_handler,
+};
+pub use ingest::{
+    delete_pipeline_handler, get_pipeline_handler, put_pipeline_handler,
+    simulate_pipeline_handler,
+};
 pub use mapping::mapping_handler;
 pub use msearch::msearch_handler;
+pub use scroll::{
+    clear_all_scrolls_handler, clear_scroll_handler, close_pit_handler, open_pit_handler,
+    scroll_handler,
+};
 pub use search::search_handler;
+pub use terms_enum::terms_enum_handler;
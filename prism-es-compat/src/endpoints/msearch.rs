@@ -1,14 +1,12 @@
 //! ES-compatible _msearch endpoint
 
-use crate::endpoints::search::{get_text_fields, EsCompatState};
+use crate::endpoints::search::{execute_search, resolve_query, EsCompatState};
 use crate::error::EsCompatError;
-use crate::query::{EsSearchRequest, MSearchHeader, QueryTranslator};
-use crate::response::{EsError, EsMSearchItem, EsMSearchResponse, ResponseMapper};
+use crate::query::{EsSearchRequest, MSearchHeader};
+use crate::response::{EsError, EsMSearchItem, EsMSearchResponse, HitOptions};
 use axum::body::Bytes;
 use axum::extract::State;
 use axum::Json;
-use prism::backends::SearchResult;
-use prism::collection::CollectionManager;
 use std::time::Instant;
 use tracing::warn;
 
@@ -25,7 +23,7 @@ pub async fn msearch_handler(
     let mut responses = Vec::with_capacity(searches.len());
 
     for (header, request) in searches {
-        let result = execute_single_search(&state.manager, header, request).await;
+        let result = execute_single_search(&state, header, request).await;
         responses.push(result);
     }
 
@@ -69,94 +67,51 @@ fn parse_msearch_body(
 
 /// Execute a single search from msearch batch
 async fn execute_single_search(
-    manager: &CollectionManager,
+    state: &EsCompatState,
     header: MSearchHeader,
-    request: EsSearchRequest,
+    mut request: EsSearchRequest,
 ) -> EsMSearchItem {
     let start = Instant::now();
 
     let index_name = header.index.unwrap_or_else(|| "*".to_string());
+    let options = HitOptions::from_request(&request);
 
-    // Expand index pattern (sync)
-    let collections = manager.expand_collection_patterns(std::slice::from_ref(&index_name));
-
-    if collections.is_empty() {
-        return EsMSearchItem::Error {
-            error: EsError {
-                error_type: "index_not_found_exception".to_string(),
-                reason: format!("no such index [{}]", index_name),
-            },
-            status: 404,
-        };
+    let result = async {
+        // Expand index pattern and aliases to collections
+        let collections = state.resolve_indices(&index_name).await;
+        if collections.is_empty() {
+            return Err(EsCompatError::IndexNotFound(index_name.clone()));
+        }
+        if let Some(query) = &mut request.query {
+            resolve_query(&state.manager, &collections, query)?;
+        }
+        execute_search(state, &index_name, &collections, &request, start).await
     }
+    .await;
 
-    // Get default fields (sync)
-    let default_fields = get_text_fields(manager, &collections[0]);
-
-    // Translate query
-    let (query, aggregations) = match QueryTranslator::translate(&request, &default_fields) {
-        Ok(r) => r,
-        Err(e) => {
-            return EsMSearchItem::Error {
-                error: EsError {
-                    error_type: "parsing_exception".to_string(),
-                    reason: e.to_string(),
-                },
-                status: 400,
-            };
+    match result {
+        Ok(mut response) => {
+            options.apply(&mut response);
+            EsMSearchItem::Success(response)
         }
-    };
-
-    // Execute search
-    let results = if collections.len() == 1 {
-        match manager
-            .search_with_aggs(&collections[0], &query, aggregations)
-            .await
-        {
-            Ok(r) => r,
-            Err(e) => {
+        Err(e) => {
+            let status = e.status_code();
+            if status.is_server_error() {
                 warn!("msearch query error: {}", e);
-                return EsMSearchItem::Error {
-                    error: EsError {
-                        error_type: "search_phase_execution_exception".to_string(),
-                        reason: e.to_string(),
-                    },
-                    status: 500,
-                };
             }
-        }
-    } else {
-        match manager.multi_search(&collections, query, None).await {
-            Ok(multi) => prism::backends::SearchResultsWithAggs {
-                results: multi
-                    .results
-                    .into_iter()
-                    .map(|r| SearchResult {
-                        id: r.id,
-                        score: r.score,
-                        fields: r.fields,
-                        highlight: r.highlight,
-                    })
-                    .collect(),
-                total: multi.total as u64,
-                aggregations: std::collections::HashMap::new(),
-            },
-            Err(e) => {
-                warn!("msearch multi query error: {}", e);
-                return EsMSearchItem::Error {
-                    error: EsError {
-                        error_type: "search_phase_execution_exception".to_string(),
-                        reason: e.to_string(),
-                    },
-                    status: 500,
-                };
+            let reason = match &e {
+                EsCompatError::IndexNotFound(index) => format!("no such index [{}]", index),
+                _ => e.to_string(),
+            };
+            EsMSearchItem::Error {
+                error: EsError {
+                    error_type: e.error_type().to_string(),
+                    reason,
+                },
+                status: status.as_u16(),
             }
         }
-    };
-
-    let took_ms = start.elapsed().as_millis() as u64;
-    let response = ResponseMapper::map_search_results(&index_name, results, took_ms);
-    EsMSearchItem::Success(response)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,466 @@
+//! ES-compatible scroll and point-in-time endpoints
+//!
+//! Both are backed by Prism points in time: a scroll is a point in time plus
+//! a cursor holding the translated query and the position of the last hit
+//! returned, so each page resumes right after the previous one.
+
+use crate::endpoints::knn::is_scored;
+use crate::endpoints::search::{check_pinned_sort, get_text_fields, EsCompatState};
+use crate::error::EsCompatError;
+use crate::query::{
+    EsClearContextRequest, EsPit, EsScrollRequest, EsSearchRequest, QueryTranslator,
+};
+use crate::response::{
+    EsClearContextResponse, EsOpenPitResponse, EsSearchResponse, HitOptions, ResponseMapper,
+};
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use prism::backends::{Query as PrismQuery, SnapshotCursor};
+use prism::collection::{CollectionManager, HitPosition};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// How often expired points in time and their scroll cursors are freed
+const REAP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Open scroll cursors, keyed by scroll ID (the ID of the backing point in
+/// time). Expiry is owned by the point in time; cursors whose point in time
+/// is gone are dropped by the reaper or on the next scroll start.
+#[derive(Default)]
+pub struct ScrollCursors {
+    cursors: Mutex<HashMap<String, ScrollCursor>>,
+}
+
+#[derive(Clone)]
+struct ScrollCursor {
+    /// Index expression of the original search
+    index: String,
+    /// Translated query
+    query: PrismQuery,
+    /// Last hit returned so far
+    after: Option<HitPosition>,
+    /// Rendering of every page's hits
+    options: HitOptions,
+}
+
+impl ScrollCursors {
+    fn get(&self, id: &str) -> Option<ScrollCursor> {
+        self.cursors.lock().unwrap().get(id).cloned()
+    }
+
+    fn insert(&self, id: String, cursor: ScrollCursor) {
+        self.cursors.lock().unwrap().insert(id, cursor);
+    }
+
+    fn advance(&self, id: &str, last: Option<HitPosition>) {
+        if let Some(cursor) = self.cursors.lock().unwrap().get_mut(id) {
+            cursor.after = last.or(cursor.after);
+        }
+    }
+
+    fn remove(&self, id: &str) -> bool {
+        self.cursors.lock().unwrap().remove(id).is_some()
+    }
+
+    fn drain(&self) -> Vec<String> {
+        self.cursors
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    fn retain_open(&self, manager: &CollectionManager) {
+        self.cursors
+            .lock()
+            .unwrap()
+            .retain(|id, _| manager.point_in_time_collections(id).is_ok());
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct OpenPitParams {
+    /// Required, e.g. `1m`
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+}
+
+/// POST /_elastic/{index}/_pit - Open a point in time
+pub async fn open_pit_handler(
+    State(state): State<EsCompatState>,
+    Path(index): Path<String>,
+    Query(params): Query<OpenPitParams>,
+) -> Result<Json<EsOpenPitResponse>, EsCompatError> {
+    let keep_alive = params
+        .keep_alive
+        .as_deref()
+        .ok_or_else(|| EsCompatError::MissingField("keep_alive".to_string()))
+        .and_then(parse_keep_alive)?;
+
+    let collections = state.resolve_indices(&index).await;
+    if collections.is_empty() {
+        return Err(EsCompatError::IndexNotFound(index));
+    }
+
+    let id = state.manager.open_point_in_time(&collections, keep_alive)?;
+    Ok(Json(EsOpenPitResponse { id }))
+}
+
+/// DELETE /_elastic/_pit - Close a point in time
+pub async fn close_pit_handler(
+    State(state): State<EsCompatState>,
+    body: Bytes,
+) -> Result<(StatusCode, Json<EsClearContextResponse>), EsCompatError> {
+    let request: EsClearContextRequest = parse_optional_body(&body)?;
+    let id = request
+        .id
+        .ok_or_else(|| EsCompatError::MissingField("id".to_string()))?;
+
+    let num_freed = usize::from(state.manager.close_point_in_time(&id));
+    Ok(clear_response(num_freed))
+}
+
+/// GET/POST /_elastic/_search/scroll - Fetch the next page of a scroll
+pub async fn scroll_handler(
+    State(state): State<EsCompatState>,
+    Query(params): Query<EsScrollRequest>,
+    body: Bytes,
+) -> Result<Json<EsSearchResponse>, EsCompatError> {
+    let start = Instant::now();
+    let request: EsScrollRequest = parse_optional_body(&body)?;
+    let scroll_id = request
+        .scroll_id
+        .or(params.scroll_id)
+        .ok_or_else(|| EsCompatError::MissingField("scroll_id".to_string()))?;
+    let keep_alive = request
+        .scroll
+        .or(params.scroll)
+        .as_deref()
+        .map(parse_keep_alive)
+        .transpose()?;
+
+    let cursor = state
+        .scrolls
+        .get(&scroll_id)
+        .ok_or_else(|| prism::Error::SearchContextMissing(scroll_id.clone()))?;
+    let results = state
+        .manager
+        .search_point_in_time(
+            &scroll_id,
+            &cursor.query,
+            vec![],
+            cursor.after.as_ref(),
+            keep_alive,
+        )
+        .inspect_err(|_| {
+            state.scrolls.remove(&scroll_id);
+        })?;
+    state
+        .scrolls
+        .advance(&scroll_id, results.positions.last().copied());
+
+    let took_ms = start.elapsed().as_millis() as u64;
+    let mut response =
+        ResponseMapper::map_point_in_time_results(&cursor.index, results, 0, false, took_ms);
+    response.scroll_id = Some(scroll_id);
+    cursor.options.apply(&mut response);
+    Ok(Json(response))
+}
+
+/// DELETE /_elastic/_search/scroll - Clear scrolls
+pub async fn clear_scroll_handler(
+    State(state): State<EsCompatState>,
+    body: Bytes,
+) -> Result<(StatusCode, Json<EsClearContextResponse>), EsCompatError> {
+    let request: EsClearContextRequest = parse_optional_body(&body)?;
+    let ids = request
+        .scroll_id
+        .ok_or_else(|| EsCompatError::MissingField("scroll_id".to_string()))?
+        .into_vec();
+
+    let num_freed = ids
+        .iter()
+        .filter(|id| state.scrolls.remove(id) && state.manager.close_point_in_time(id))
+        .count();
+    Ok(clear_response(num_freed))
+}
+
+/// DELETE /_elastic/_search/scroll/_all - Clear every open scroll
+pub async fn clear_all_scrolls_handler(
+    State(state): State<EsCompatState>,
+) -> Json<EsClearContextResponse> {
+    let num_freed = state
+        .scrolls
+        .drain()
+        .iter()
+        .filter(|id| state.manager.close_point_in_time(id))
+        .count();
+    Json(EsClearContextResponse {
+        succeeded: true,
+        num_freed,
+    })
+}
+
+/// Run a search that opens a scroll (`?scroll=1m`) and return its first page
+pub(crate) fn start_scroll(
+    state: &EsCompatState,
+    index_name: &str,
+    collections: &[String],
+    request: &EsSearchRequest,
+    scroll: &str,
+    start: Instant,
+) -> Result<EsSearchResponse, EsCompatError> {
+    if request.from.unwrap_or(0) > 0 {
+        return Err(EsCompatError::InvalidRequestBody(
+            "using [from] is not allowed in a scroll context".to_string(),
+        ));
+    }
+    if request.search_after.is_some() {
+        return Err(EsCompatError::InvalidRequestBody(
+            "[search_after] cannot be used in a scroll context".to_string(),
+        ));
+    }
+    if is_scored(request) {
+        return Err(EsCompatError::InvalidRequestBody(
+            "[knn] and scoring queries cannot be used in a scroll context".to_string(),
+        ));
+    }
+    check_pinned_sort(request, "a scroll")?;
+    let keep_alive = parse_keep_alive(scroll)?;
+
+    let default_fields = get_text_fields(&state.manager, &collections[0]);
+    let (query, aggregations) = QueryTranslator::translate(request, &default_fields)?;
+
+    state.scrolls.retain_open(&state.manager);
+    let id = state.manager.open_point_in_time(collections, keep_alive)?;
+    let results = state
+        .manager
+        .search_point_in_time(&id, &query, aggregations, None, None)
+        .inspect_err(|_| {
+            state.manager.close_point_in_time(&id);
+        })?;
+
+    let cursor = ScrollCursor {
+        index: index_name.to_string(),
+        query,
+        after: results.positions.last().copied(),
+        options: HitOptions::from_request(request),
+    };
+    state.scrolls.insert(id.clone(), cursor);
+
+    let took_ms = start.elapsed().as_millis() as u64;
+    let mut response =
+        ResponseMapper::map_point_in_time_results(index_name, results, 0, false, took_ms);
+    response.scroll_id = Some(id);
+    Ok(response)
+}
+
+/// Run a search against the point in time named in the request body
+pub(crate) fn search_with_pit(
+    state: &EsCompatState,
+    request: &EsSearchRequest,
+    pit: &EsPit,
+    start: Instant,
+) -> Result<EsSearchResponse, EsCompatError> {
+    let keep_alive = pit
+        .keep_alive
+        .as_deref()
+        .map(parse_keep_alive)
+        .transpose()?;
+    let collections = state.manager.point_in_time_collections(&pit.id)?;
+    check_pinned_sort(request, "a point in time")?;
+
+    let default_fields = get_text_fields(&state.manager, &collections[0]);
+    let (query, aggregations) = QueryTranslator::translate(request, &default_fields)?;
+    let after = match &request.search_after {
+        Some(after) => {
+            if request.from.unwrap_or(0) > 0 {
+                return Err(EsCompatError::InvalidRequestBody(
+                    "[from] parameter must be set to 0 when [search_after] is used".to_string(),
+                ));
+            }
+            Some(search_after_position(after)?)
+        }
+        None => None,
+    };
+
+    let offset = query.offset;
+    let results = state.manager.search_point_in_time(
+        &pit.id,
+        &query,
+        aggregations,
+        after.as_ref(),
+        keep_alive,
+    )?;
+
+    let took_ms = start.elapsed().as_millis() as u64;
+    let mut response = ResponseMapper::map_point_in_time_results(
+        &collections.join(","),
+        results,
+        offset,
+        true,
+        took_ms,
+    );
+    response.pit_id = Some(pit.id.clone());
+    Ok(response)
+}
+
+/// Point-in-time hits are sorted by `[_score, _shard_doc]`, where
+/// `_shard_doc` packs the hit's collection, segment and document into one
+/// number: the collection in the top 16 bits, the segment in the next 16
+/// and the document in the low 32.
+pub(crate) fn shard_doc(position: &HitPosition) -> u64 {
+    ((position.collection as u64) << 48)
+        | (u64::from(position.cursor.segment_ord) << 32)
+        | u64::from(position.cursor.doc_id)
+}
+
+/// The position of the hit a `search_after` from a point-in-time page names
+fn search_after_position(after: &[Value]) -> Result<HitPosition, EsCompatError> {
+    match after {
+        [score, shard_doc] => {
+            let score = score.as_f64().ok_or_else(|| {
+                EsCompatError::InvalidRequestBody(format!(
+                    "[search_after] [_score] value [{}] is not a number",
+                    score
+                ))
+            })?;
+            let shard_doc = shard_doc.as_u64().ok_or_else(|| {
+                EsCompatError::InvalidRequestBody(format!(
+                    "[search_after] [_shard_doc] value [{}] is not a point-in-time position",
+                    shard_doc
+                ))
+            })?;
+            Ok(HitPosition {
+                collection: (shard_doc >> 48) as usize,
+                cursor: SnapshotCursor {
+                    score: score as f32,
+                    segment_ord: ((shard_doc >> 32) & 0xffff) as u32,
+                    doc_id: shard_doc as u32,
+                },
+            })
+        }
+        _ => Err(EsCompatError::InvalidRequestBody(
+            "[search_after] must hold the [_score] and [_shard_doc] sort values of the previous page's last hit"
+                .to_string(),
+        )),
+    }
+}
+
+/// Free expired points in time and the scroll cursors on them every
+/// [`REAP_INTERVAL`], so contexts nobody comes back for do not pin segments
+/// until the next request happens to touch them.
+///
+/// Stops once the cursors are dropped with the router. Without a Tokio
+/// runtime to run on, contexts are only freed as requests touch them.
+pub(crate) fn spawn_reaper(manager: &Arc<CollectionManager>, scrolls: &Arc<ScrollCursors>) {
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    let manager = Arc::downgrade(manager);
+    let scrolls = Arc::downgrade(scrolls);
+    runtime.spawn(async move {
+        let mut interval = tokio::time::interval(REAP_INTERVAL);
+        // The first tick completes immediately
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let (Some(manager), Some(scrolls)) = (manager.upgrade(), scrolls.upgrade()) else {
+                break;
+            };
+            let freed = manager.reap_points_in_time();
+            scrolls.retain_open(&manager);
+            if freed > 0 {
+                debug!("Freed {} expired point-in-time contexts", freed);
+            }
+        }
+    });
+}
+
+/// Parse an ES time value such as `30s` or `1m`
+pub(crate) fn parse_keep_alive(value: &str) -> Result<Duration, EsCompatError> {
+    prism::ilm::config::parse_duration(value).ok_or_else(|| {
+        EsCompatError::InvalidRequestBody(format!(
+            "failed to parse setting [keep_alive] with value [{}] as a time value",
+            value
+        ))
+    })
+}
+
+/// Parse a body that clients may omit, such as clear-scroll, close-pit and
+/// create-index bodies
+pub(crate) fn parse_optional_body<T: DeserializeOwned + Default>(
+    body: &Bytes,
+) -> Result<T, EsCompatError> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(T::default());
+    }
+    serde_json::from_slice(body).map_err(|e| EsCompatError::InvalidRequestBody(e.to_string()))
+}
+
+/// ES answers 404 when none of the given contexts existed
+fn clear_response(num_freed: usize) -> (StatusCode, Json<EsClearContextResponse>) {
+    let status = if num_freed == 0 {
+        StatusCode::NOT_FOUND
+    } else {
+        StatusCode::OK
+    };
+    (
+        status,
+        Json(EsClearContextResponse {
+            succeeded: true,
+            num_freed,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_search_after_position() {
+        let position = HitPosition {
+            collection: 2,
+            cursor: SnapshotCursor {
+                score: 1.25,
+                segment_ord: 7,
+                doc_id: 4_000_000_000,
+            },
+        };
+        let after = [json!(position.cursor.score), json!(shard_doc(&position))];
+        assert_eq!(search_after_position(&after).unwrap(), position);
+
+        assert!(search_after_position(&[json!(0)]).is_err());
+        assert!(search_after_position(&[]).is_err());
+        assert!(search_after_position(&[json!(1.5), json!("abc")]).is_err());
+    }
+
+    #[test]
+    fn test_parse_keep_alive() {
+        assert_eq!(parse_keep_alive("1m").unwrap(), Duration::from_secs(60));
+        assert_eq!(parse_keep_alive("30s").unwrap(), Duration::from_secs(30));
+        assert!(parse_keep_alive("soon").is_err());
+    }
+
+    #[test]
+    fn test_parse_optional_body() {
+        let empty: EsScrollRequest = parse_optional_body(&Bytes::from_static(b"  ")).unwrap();
+        assert!(empty.scroll_id.is_none());
+
+        let request: EsClearContextRequest =
+            parse_optional_body(&Bytes::from_static(br#"{"scroll_id": ["a", "b"]}"#)).unwrap();
+        assert_eq!(request.scroll_id.unwrap().into_vec(), vec!["a", "b"]);
+
+        assert!(parse_optional_body::<EsScrollRequest>(&Bytes::from_static(b"{")).is_err());
+    }
+}
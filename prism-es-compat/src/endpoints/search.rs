@@ -1,12 +1,23 @@
 //! ES-compatible _search endpoint
 
+use crate::endpoints::document::DocVersions;
+use crate::endpoints::ingest::IngestPipelines;
+use crate::endpoints::knn::{is_scored, scored_search};
+use crate::endpoints::scroll::{search_with_pit, start_scroll, ScrollCursors};
 use crate::error::EsCompatError;
-use crate::query::{EsSearchRequest, QueryTranslator};
-use crate::response::{EsSearchResponse, ResponseMapper};
-use axum::extract::{Path, State};
+use crate::query::{EsQuery, EsSearchRequest, MltLike, QueryTranslator};
+use crate::response::{EsSearchResponse, HitOptions, ResponseMapper};
+use crate::topology::ClusterTopology;
+use axum::extract::{Path, Query, State};
 use axum::Json;
+use prism::aggregations::{SortDirection, TopHitsSort};
 use prism::backends::SearchResult;
-use prism::collection::CollectionManager;
+use prism::collection::{CollectionManager, MultiSearchResult};
+use prism::ilm::AliasManager;
+use serde::Deserialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -14,6 +25,77 @@ use std::time::Instant;
 #[derive(Clone)]
 pub struct EsCompatState {
     pub manager: Arc<CollectionManager>,
+    /// Document versions reported by the single-document APIs
+    pub versions: Arc<DocVersions>,
+    /// Open scroll cursors
+    pub scrolls: Arc<ScrollCursors>,
+    /// Index aliases; `/_aliases` is unavailable without them
+    pub aliases: Option<Arc<AliasManager>>,
+    /// Cluster membership and shard placement; a single node without it
+    pub topology: Option<Arc<dyn ClusterTopology>>,
+    /// Ingest pipelines applied by `_bulk`
+    pub ingest: Arc<IngestPipelines>,
+}
+
+impl EsCompatState {
+    /// Expand a comma-separated index expression into collections.
+    ///
+    /// Alias names resolve to their targets; other parts are matched as
+    /// collection names or wildcard patterns.
+    pub async fn resolve_indices(&self, expression: &str) -> Vec<String> {
+        let mut patterns = Vec::new();
+        for part in expression.split(',').map(str::trim) {
+            match &self.aliases {
+                Some(aliases) if aliases.is_alias(part).await => {
+                    patterns.extend(aliases.expand(part).await)
+                }
+                _ => patterns.push(part.to_string()),
+            }
+        }
+        self.manager.expand_collection_patterns(&patterns)
+    }
+
+    /// Keep the collections in which `filter` matches at least one document.
+    ///
+    /// This is how `index_filter` works in Elasticsearch: it skips whole
+    /// indices rather than filtering their documents.
+    pub async fn filter_indices(
+        &self,
+        collections: Vec<String>,
+        filter: Option<&EsQuery>,
+    ) -> Result<Vec<String>, EsCompatError> {
+        let Some(filter) = filter else {
+            return Ok(collections);
+        };
+        let mut query = filter.clone();
+        resolve_query(&self.manager, &collections, &mut query)?;
+        let search = EsSearchRequest {
+            query: Some(query),
+            size: Some(0),
+            ..Default::default()
+        };
+
+        let mut matching = Vec::with_capacity(collections.len());
+        for collection in collections {
+            let default_fields = get_text_fields(&self.manager, &collection);
+            let (query, _) = QueryTranslator::translate(&search, &default_fields)?;
+            let result = self
+                .manager
+                .search_with_aggs(&collection, &query, vec![])
+                .await?;
+            if result.total > 0 {
+                matching.push(collection);
+            }
+        }
+        Ok(matching)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchParams {
+    /// Keep-alive of a scroll started by this search, e.g. `1m`
+    #[serde(default)]
+    pub scroll: Option<String>,
 }
 
 /// POST /_elastic/_search - Search across all indices
@@ -21,26 +103,81 @@ pub struct EsCompatState {
 pub async fn search_handler(
     State(state): State<EsCompatState>,
     index: Option<Path<String>>,
-    Json(request): Json<EsSearchRequest>,
+    Query(params): Query<SearchParams>,
+    Json(mut request): Json<EsSearchRequest>,
 ) -> Result<Json<EsSearchResponse>, EsCompatError> {
     let start = Instant::now();
+    let options = HitOptions::from_request(&request);
+
+    // A point in time carries its own indices
+    if let Some(pit) = &request.pit {
+        if index.is_some() {
+            return Err(EsCompatError::InvalidRequestBody(
+                "[indices] cannot be used with point in time".to_string(),
+            ));
+        }
+        if let Some(query) = &mut request.query {
+            let collections = state.manager.point_in_time_collections(&pit.id)?;
+            resolve_query(&state.manager, &collections, query)?;
+        }
+        let mut response = if is_scored(&request) {
+            let index_name = state.manager.point_in_time_collections(&pit.id)?.join(",");
+            scored_search(&state, &index_name, &[], &request, start).await?
+        } else {
+            search_with_pit(&state, &request, pit, start)?
+        };
+        options.apply(&mut response);
+        return Ok(Json(response));
+    }
 
     let index_name = index.map(|p| p.0).unwrap_or_else(|| "*".to_string());
 
-    // Expand index pattern to collections (sync method)
-    let collections = state
-        .manager
-        .expand_collection_patterns(std::slice::from_ref(&index_name));
+    // Expand index pattern and aliases to collections
+    let collections = state.resolve_indices(&index_name).await;
 
     if collections.is_empty() {
         return Err(EsCompatError::IndexNotFound(index_name));
     }
+    if let Some(query) = &mut request.query {
+        resolve_query(&state.manager, &collections, query)?;
+    }
+
+    let mut response = match &params.scroll {
+        Some(scroll) => start_scroll(&state, &index_name, &collections, &request, scroll, start)?,
+        None => execute_search(&state, &index_name, &collections, &request, start).await?,
+    };
+    options.apply(&mut response);
+    Ok(Json(response))
+}
+
+/// Run a search against resolved collections, without a scroll or point in
+/// time. Hit options are left to the caller.
+pub(crate) async fn execute_search(
+    state: &EsCompatState,
+    index_name: &str,
+    collections: &[String],
+    request: &EsSearchRequest,
+    start: Instant,
+) -> Result<EsSearchResponse, EsCompatError> {
+    if is_scored(request) {
+        return scored_search(state, index_name, collections, request, start).await;
+    }
+    if let Some(sort) = field_sort(request) {
+        return sorted_search(
+            &state.manager,
+            index_name,
+            collections,
+            request,
+            &sort,
+            start,
+        );
+    }
 
     // Get default fields from first collection's schema (sync method)
     let default_fields = get_text_fields(&state.manager, &collections[0]);
 
     // Translate ES query to Prism
-    let (query, aggregations) = QueryTranslator::translate(&request, &default_fields)?;
+    let (query, aggregations) = QueryTranslator::translate(request, &default_fields)?;
 
     // Execute search
     let results = if collections.len() == 1 {
@@ -52,7 +189,7 @@ pub async fn search_handler(
         // Multi-collection search (without aggregations for now)
         let multi_results = state
             .manager
-            .multi_search(&collections, query, None) // rrf_k = None
+            .multi_search(collections, query, None) // rrf_k = None
             .await?;
 
         // Convert MultiSearchResults to SearchResultsWithAggs
@@ -75,9 +212,221 @@ pub async fn search_handler(
     let took_ms = start.elapsed().as_millis() as u64;
 
     // Map to ES response format
-    let response = ResponseMapper::map_search_results(&index_name, results, took_ms);
+    Ok(ResponseMapper::map_search_results(
+        index_name, results, took_ms,
+    ))
+}
 
-    Ok(Json(response))
+/// The request's sort, unless it is plain relevance order
+pub(crate) fn field_sort(request: &EsSearchRequest) -> Option<Vec<TopHitsSort>> {
+    let sort = QueryTranslator::translate_sort(request.sort.as_deref().unwrap_or_default());
+    let by_relevance = sort
+        .iter()
+        .all(|s| s.field == "_score" && s.order == SortDirection::Desc);
+    (!by_relevance).then_some(sort)
+}
+
+/// Points in time and scrolls page through a pinned relevance order, so
+/// they only accept sorts that agree with it
+pub(crate) fn check_pinned_sort(
+    request: &EsSearchRequest,
+    context: &str,
+) -> Result<(), EsCompatError> {
+    let sort = QueryTranslator::translate_sort(request.sort.as_deref().unwrap_or_default());
+    match sort
+        .iter()
+        .find(|s| s.field != "_doc" && !(s.field == "_score" && s.order == SortDirection::Desc))
+    {
+        Some(s) => Err(EsCompatError::InvalidRequestBody(format!(
+            "sorting on [{}] is not supported with {}",
+            s.field, context
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Search ordered by sort keys. With several collections, each returns its
+/// first `from + size` hits, which are merged on their sort values.
+fn sorted_search(
+    manager: &CollectionManager,
+    index_name: &str,
+    collections: &[String],
+    request: &EsSearchRequest,
+    sort: &[TopHitsSort],
+    start: Instant,
+) -> Result<EsSearchResponse, EsCompatError> {
+    let default_fields = get_text_fields(manager, &collections[0]);
+    let (query, mut aggregations) = QueryTranslator::translate(request, &default_fields)?;
+
+    let single = collections.len() == 1;
+    let mut per_collection = query.clone();
+    if !single {
+        per_collection.offset = 0;
+        per_collection.limit = query.offset + query.limit;
+        // Multi-collection search (without aggregations for now)
+        aggregations.clear();
+    }
+
+    let mut hits = Vec::new();
+    let mut total = 0;
+    let mut aggregation_results = HashMap::new();
+    for collection in collections {
+        let found = manager.search_sorted(
+            collection,
+            &per_collection,
+            std::mem::take(&mut aggregations),
+            sort,
+        )?;
+        total += found.total;
+        aggregation_results.extend(found.aggregations);
+        let results = found.results.into_iter().zip(found.sort_values);
+        hits.extend(results.map(|(r, values)| {
+            let hit = MultiSearchResult {
+                id: r.id,
+                collection: collection.clone(),
+                score: r.score,
+                fields: r.fields,
+                highlight: r.highlight,
+            };
+            (hit, values)
+        }));
+    }
+    if !single {
+        // Stable sort: equal values keep collection order
+        hits.sort_by(|a, b| compare_sort_values(&a.1, &b.1, sort));
+        hits = hits
+            .into_iter()
+            .skip(query.offset)
+            .take(query.limit)
+            .collect();
+    }
+
+    let track_scores =
+        request.track_scores.unwrap_or(false) || sort.iter().any(|s| s.field == "_score");
+    let took_ms = start.elapsed().as_millis() as u64;
+    Ok(ResponseMapper::map_sorted_results(
+        index_name,
+        hits,
+        total,
+        &aggregation_results,
+        track_scores,
+        took_ms,
+    ))
+}
+
+/// Order two hits on their sort values as the backend did, where `null`
+/// stands for a missing value
+fn compare_sort_values(a: &[Value], b: &[Value], sort: &[TopHitsSort]) -> Ordering {
+    for (i, s) in sort.iter().enumerate() {
+        let (x, y) = (&a[i], &b[i]);
+        let missing_first = s.missing.as_ref().is_some_and(|m| m == "_first");
+        let ord = match (x, y) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (_, Value::Null) if missing_first => Ordering::Greater,
+            (Value::Null, _) if missing_first => Ordering::Less,
+            (_, Value::Null) => Ordering::Less,
+            (Value::Null, _) => Ordering::Greater,
+            _ => {
+                let ord = match (x.as_f64(), y.as_f64(), x.as_str(), y.as_str()) {
+                    (Some(x), Some(y), _, _) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+                    (_, _, Some(x), Some(y)) => x.cmp(y),
+                    _ => x.to_string().cmp(&y.to_string()),
+                };
+                match s.order {
+                    SortDirection::Asc => ord,
+                    SortDirection::Desc => ord.reverse(),
+                }
+            }
+        };
+        if ord.is_ne() {
+            return ord;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Look up what a query needs from the index before translation: the
+/// significant terms of `more_like_this` clauses and the terms multi-term
+/// queries expand to in any of `collections`
+pub(crate) fn resolve_query(
+    manager: &CollectionManager,
+    collections: &[String],
+    query: &mut EsQuery,
+) -> Result<(), EsCompatError> {
+    resolve_more_like_this(manager, collections, query)?;
+    QueryTranslator::expand_multi_term_queries(query, &mut |field, pattern, max_expansions| {
+        let mut terms: Vec<String> = Vec::new();
+        for collection in collections {
+            // Collections without a text index have no terms
+            let found = match manager.expand_terms(collection, field, pattern, max_expansions) {
+                Err(prism::Error::CollectionNotFound(_)) => continue,
+                found => found?,
+            };
+            for term in found {
+                if !terms.contains(&term) {
+                    terms.push(term);
+                }
+            }
+        }
+        terms.truncate(max_expansions);
+        Ok(terms)
+    })
+}
+
+/// Look up the significant terms of `more_like_this` clauses, which the
+/// translator cannot see. `like` documents are read from their `_index`, or
+/// from the first searched collection along with the `like` texts.
+fn resolve_more_like_this(
+    manager: &CollectionManager,
+    collections: &[String],
+    query: &mut EsQuery,
+) -> Result<(), EsCompatError> {
+    let EsQuery::MoreLikeThis(mlt) = query else {
+        for child in query.children_mut() {
+            resolve_more_like_this(manager, collections, child)?;
+        }
+        return Ok(());
+    };
+
+    let default_collection = collections.first().map(String::as_str).unwrap_or_default();
+    let mut texts = Vec::new();
+    let mut docs: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    docs.insert(default_collection, vec![]);
+    for like in mlt.like.iter() {
+        match like {
+            MltLike::Text(text) => texts.push(text.clone()),
+            MltLike::Document { index, id } => docs
+                .entry(index.as_deref().unwrap_or(default_collection))
+                .or_default()
+                .push(id.clone()),
+        }
+    }
+
+    let mut terms: Vec<String> = Vec::new();
+    for (collection, ids) in docs {
+        let like_texts = if collection == default_collection {
+            texts.as_slice()
+        } else {
+            &[]
+        };
+        let found = manager.more_like_this_terms(
+            collection,
+            &ids,
+            like_texts,
+            &mlt.fields,
+            mlt.min_term_freq,
+            mlt.min_doc_freq,
+            mlt.max_query_terms,
+        )?;
+        for term in found {
+            if !terms.contains(&term) {
+                terms.push(term);
+            }
+        }
+    }
+    terms.truncate(mlt.max_query_terms);
+    mlt.resolved_terms = Some(terms);
+    Ok(())
 }
 
 /// Get text field names from collection schema
@@ -0,0 +1,94 @@
+//! ES-compatible _terms_enum endpoint
+
+use crate::endpoints::search::EsCompatState;
+use crate::error::EsCompatError;
+use crate::query::EsTermsEnumRequest;
+use crate::response::{EsTermsEnumResponse, ShardStats};
+use axum::extract::{Path, State};
+use axum::Json;
+use prism::schema::FieldType;
+use std::collections::BTreeSet;
+
+/// Terms returned when the request has no `size`
+const DEFAULT_SIZE: usize = 10;
+
+/// GET/POST /_elastic/{index}/_terms_enum - Terms of a keyword field that
+/// start with a prefix, in sorted order
+///
+/// Only `keyword` (Prism `string`) fields are enumerated, as in
+/// Elasticsearch; other fields return no terms.
+pub async fn terms_enum_handler(
+    State(state): State<EsCompatState>,
+    Path(index): Path<String>,
+    Json(request): Json<EsTermsEnumRequest>,
+) -> Result<Json<EsTermsEnumResponse>, EsCompatError> {
+    let collections = state.resolve_indices(&index).await;
+    if collections.is_empty() {
+        return Err(EsCompatError::IndexNotFound(index));
+    }
+    let total = collections.len() as u32;
+    let collections = state
+        .filter_indices(collections, request.index_filter.as_ref())
+        .await?;
+    let skipped = total - collections.len() as u32;
+
+    let prefix = request.string.as_deref().unwrap_or_default();
+    let mut terms = BTreeSet::new();
+    for collection in &collections {
+        if !is_keyword_field(&state, collection, &request.field) {
+            continue;
+        }
+        if request.case_insensitive {
+            let lowered = prefix.to_lowercase();
+            let all = state
+                .manager
+                .get_top_terms(collection, &request.field, usize::MAX)?;
+            terms.extend(
+                all.into_iter()
+                    .map(|t| t.term)
+                    .filter(|term| term.to_lowercase().starts_with(&lowered)),
+            );
+        } else {
+            let matches =
+                state
+                    .manager
+                    .suggest(collection, &request.field, prefix, usize::MAX, false, 0)?;
+            terms.extend(matches.into_iter().map(|entry| entry.term));
+        }
+    }
+
+    let size = request.size.unwrap_or(DEFAULT_SIZE);
+    let terms = terms
+        .into_iter()
+        .filter(|term| {
+            request
+                .search_after
+                .as_ref()
+                .is_none_or(|after| term > after)
+        })
+        .take(size)
+        .collect();
+
+    Ok(Json(EsTermsEnumResponse {
+        shards: ShardStats {
+            total,
+            successful: total,
+            skipped,
+            failed: 0,
+        },
+        terms,
+        complete: true,
+    }))
+}
+
+fn is_keyword_field(state: &EsCompatState, collection: &str, field: &str) -> bool {
+    state
+        .manager
+        .get_schema(collection)
+        .and_then(|schema| schema.backends.text)
+        .is_some_and(|text| {
+            text.fields
+                .iter()
+                .any(|f| f.name == field && f.indexed && f.field_type == FieldType::String)
+        })
+}
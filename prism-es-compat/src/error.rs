@@ -28,6 +28,27 @@ pub enum EsCompatError {
     #[error("Parse error: {0}")]
     ParseError(String),
 
+    #[error("{0}")]
+    VersionConflict(String),
+
+    #[error("{0}")]
+    DocumentMissing(String),
+
+    #[error("{0}")]
+    ResourceAlreadyExists(String),
+
+    #[error("{0}")]
+    ResourceNotFound(String),
+
+    #[error("{0}")]
+    InvalidIndexName(String),
+
+    #[error("{0}")]
+    MapperParsing(String),
+
+    #[error("{0}")]
+    IllegalArgument(String),
+
     #[error("Prism error: {0}")]
     PrismError(#[from] prism::Error),
 
@@ -58,7 +79,7 @@ struct RootCause {
 }
 
 impl EsCompatError {
-    fn error_type(&self) -> &'static str {
+    pub(crate) fn error_type(&self) -> &'static str {
         match self {
             Self::IndexNotFound(_) => "index_not_found_exception",
             Self::InvalidQuery(_) => "query_shard_exception",
@@ -67,15 +88,25 @@ impl EsCompatError {
             Self::MissingField(_) => "parsing_exception",
             Self::InvalidRequestBody(_) => "parse_exception",
             Self::ParseError(_) => "parse_exception",
+            Self::VersionConflict(_) => "version_conflict_engine_exception",
+            Self::DocumentMissing(_) => "document_missing_exception",
+            Self::ResourceAlreadyExists(_) => "resource_already_exists_exception",
+            Self::ResourceNotFound(_) => "resource_not_found_exception",
+            Self::InvalidIndexName(_) => "invalid_index_name_exception",
+            Self::MapperParsing(_) => "mapper_parsing_exception",
+            Self::IllegalArgument(_) => "illegal_argument_exception",
             Self::PrismError(e) => match e {
                 prism::Error::CollectionNotFound(_) => "index_not_found_exception",
+                prism::Error::SearchContextMissing(_) => "search_context_missing_exception",
+                prism::Error::AliasNotFound(_) => "aliases_not_found_exception",
+                prism::Error::InvalidQuery(_) => "query_shard_exception",
                 _ => "search_phase_execution_exception",
             },
             Self::Internal(_) => "internal_server_error",
         }
     }
 
-    fn status_code(&self) -> StatusCode {
+    pub(crate) fn status_code(&self) -> StatusCode {
         match self {
             Self::IndexNotFound(_) => StatusCode::NOT_FOUND,
             Self::InvalidQuery(_)
@@ -83,9 +114,18 @@ impl EsCompatError {
             | Self::UnsupportedAggregation(_)
             | Self::MissingField(_)
             | Self::InvalidRequestBody(_)
-            | Self::ParseError(_) => StatusCode::BAD_REQUEST,
+            | Self::ParseError(_)
+            | Self::ResourceAlreadyExists(_)
+            | Self::InvalidIndexName(_)
+            | Self::MapperParsing(_)
+            | Self::IllegalArgument(_) => StatusCode::BAD_REQUEST,
+            Self::VersionConflict(_) => StatusCode::CONFLICT,
+            Self::DocumentMissing(_) | Self::ResourceNotFound(_) => StatusCode::NOT_FOUND,
             Self::PrismError(e) => match e {
-                prism::Error::CollectionNotFound(_) => StatusCode::NOT_FOUND,
+                prism::Error::CollectionNotFound(_)
+                | prism::Error::SearchContextMissing(_)
+                | prism::Error::AliasNotFound(_) => StatusCode::NOT_FOUND,
+                prism::Error::InvalidQuery(_) => StatusCode::BAD_REQUEST,
                 _ => StatusCode::INTERNAL_SERVER_ERROR,
             },
             Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -100,6 +140,11 @@ impl IntoResponse for EsCompatError {
 
         // Sanitize error details: log internal details, return generic message to client
         let reason = match &self {
+            Self::PrismError(
+                e @ (prism::Error::SearchContextMissing(_)
+                | prism::Error::AliasNotFound(_)
+                | prism::Error::InvalidQuery(_)),
+            ) => e.to_string(),
             Self::PrismError(_) | Self::Internal(_) => {
                 tracing::error!(error = %self, "Internal error in ES compat layer");
                 "An internal error occurred".to_string()
@@ -158,6 +203,34 @@ mod tests {
                 EsCompatError::ParseError("bad json".into()),
                 "parse_exception",
             ),
+            (
+                EsCompatError::VersionConflict("exists".into()),
+                "version_conflict_engine_exception",
+            ),
+            (
+                EsCompatError::DocumentMissing("gone".into()),
+                "document_missing_exception",
+            ),
+            (
+                EsCompatError::ResourceAlreadyExists("exists".into()),
+                "resource_already_exists_exception",
+            ),
+            (
+                EsCompatError::ResourceNotFound("missing".into()),
+                "resource_not_found_exception",
+            ),
+            (
+                EsCompatError::InvalidIndexName("Bad".into()),
+                "invalid_index_name_exception",
+            ),
+            (
+                EsCompatError::MapperParsing("object".into()),
+                "mapper_parsing_exception",
+            ),
+            (
+                EsCompatError::IllegalArgument("remove_index".into()),
+                "illegal_argument_exception",
+            ),
             (
                 EsCompatError::Internal("panic".into()),
                 "internal_server_error",
@@ -184,6 +257,10 @@ mod tests {
             EsCompatError::MissingField("x".into()),
             EsCompatError::InvalidRequestBody("x".into()),
             EsCompatError::ParseError("x".into()),
+            EsCompatError::ResourceAlreadyExists("x".into()),
+            EsCompatError::InvalidIndexName("x".into()),
+            EsCompatError::MapperParsing("x".into()),
+            EsCompatError::IllegalArgument("x".into()),
         ];
 
         for err in bad_request_errors {
@@ -196,6 +273,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_status_code_document_errors() {
+        assert_eq!(
+            EsCompatError::VersionConflict("x".into()).status_code(),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            EsCompatError::DocumentMissing("x".into()).status_code(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            EsCompatError::ResourceNotFound("x".into()).status_code(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
     #[test]
     fn test_status_code_internal() {
         assert_eq!(
@@ -240,4 +333,27 @@ mod tests {
             "index_not_found_exception"
         );
     }
+
+    #[test]
+    fn test_search_context_missing() {
+        let es_err: EsCompatError = prism::Error::SearchContextMissing("abc".into()).into();
+        assert_eq!(es_err.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(es_err.error_type(), "search_context_missing_exception");
+        let resp = es_err.into_response();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_alias_not_found() {
+        let es_err: EsCompatError = prism::Error::AliasNotFound("logs".into()).into();
+        assert_eq!(es_err.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(es_err.error_type(), "aliases_not_found_exception");
+    }
+
+    #[test]
+    fn test_prism_invalid_query() {
+        let es_err: EsCompatError = prism::Error::InvalidQuery("no mapping".into()).into();
+        assert_eq!(es_err.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(es_err.error_type(), "query_shard_exception");
+    }
 }
@@ -8,11 +8,29 @@
 //! All ES-compatible endpoints are served under the `/_elastic` prefix:
 //!
 //! - `/_elastic/_search` - Search with Query DSL
+//! - `/_elastic/_search/scroll` - Scroll through results
+//! - `/_elastic/{index}/_pit` / `_pit` - Open and close points in time
 //! - `/_elastic/_msearch` - Multi-search
-//! - `/_elastic/_bulk` - Bulk indexing
-//! - `/_elastic/{index}/_mapping` - Field mappings
-//! - `/_elastic/_cluster/health` - Cluster health
-//! - `/_elastic/_cat/indices` - List indices
+//! - `/_elastic/_bulk` - Bulk indexing, with `?pipeline=` or a per-item
+//!   `pipeline` running an ingest pipeline first
+//! - `/_elastic/_ingest/pipeline/{id}` - Ingest pipelines translated into
+//!   Prism processors, and `_simulate` to test them
+//! - `/_elastic/{index}/_doc/{id}` - Single-document index, get and delete
+//! - `/_elastic/{index}/_create/{id}` / `_update/{id}` - Create and partial update
+//! - `/_elastic/_mget` - Multi-get
+//! - `/_elastic/_count` - Count matching documents
+//! - `/_elastic/{index}` - Create, check and delete indices
+//! - `/_elastic/{index}/_mapping` - Get and extend field mappings
+//! - `/_elastic/_aliases` - List and update index aliases
+//! - `/_elastic/{index}/_field_caps` - Searchable and aggregatable fields
+//! - `/_elastic/{index}/_terms_enum` - Keyword terms by prefix, for autocomplete
+//! - `/_elastic/_cluster/health` / `_cluster/stats` - Cluster health and totals
+//! - `/_elastic/_nodes/stats` - Per-node statistics
+//! - `/_elastic/_cat/{indices,health,count,aliases,shards,segments}` - Text
+//!   or JSON (`?format=json`) tables with `?v` headers and `?h=` columns
+//!
+//! A clustered server passes a [`topology::ClusterTopology`] so that health,
+//! stats and `_cat/shards` report its nodes and shard placement.
 //!
 //! # Query DSL Support
 //!
@@ -23,6 +41,14 @@
 //! - `range`
 //! - `exists`
 //! - `query_string`
+//! - `function_score` / `script_score` (applied to the best hits)
+//!
+//! Top-level `knn` search runs against `dense_vector` fields and can be
+//! combined with a query, optionally through `rank.rrf`.
+//!
+//! Search responses honour `_source` filtering, `fields` / `docvalue_fields`,
+//! `highlight`, field `sort` (with `missing` and `unmapped_type`) and
+//! `track_total_hits`; `from + size` is capped at 10,000.
 //!
 //! Supported aggregations:
 //! - `terms`
@@ -33,11 +59,15 @@ pub mod error;
 pub mod query;
 pub mod response;
 pub mod router;
+pub mod topology;
 
 mod endpoints;
 
 pub use error::EsCompatError;
-pub use router::es_compat_router;
+pub use router::{
+    es_compat_router, es_compat_router_with_aliases, es_compat_router_with_pipelines,
+    es_compat_router_with_topology,
+};
 
 /// Result type for ES compat operations
 pub type Result<T> = std::result::Result<T, EsCompatError>;
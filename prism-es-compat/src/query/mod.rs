@@ -1,7 +1,9 @@
 //! Elasticsearch Query DSL to Prism query translator
 
+mod scoring;
 mod translator;
 mod types;
 
+pub use scoring::FunctionScorer;
 pub use translator::QueryTranslator;
 pub use types::*;
@@ -0,0 +1,743 @@
+//! Rescoring for `function_score`, `script_score` and `boosting` queries
+//!
+//! Prism's text backend scores hits by relevance only, so these queries are
+//! applied after retrieval: the inner query's best hits are rescored here and
+//! re-sorted. Expressions run on [`ScoreFunctionReranker`] and decay functions
+//! on [`prism::ranking::decay`].
+
+use crate::error::EsCompatError;
+use crate::query::types::*;
+use crate::query::QueryTranslator;
+use chrono::{DateTime, NaiveDate};
+use prism::backends::SearchResult;
+use prism::ranking::decay::{exponential_decay, gaussian_decay, linear_decay};
+use prism::ranking::{parse_duration, DecayFunction, ScoreFunctionReranker};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Rescores hits of a top-level `function_score`, `script_score` or
+/// `boosting` query
+pub struct FunctionScorer {
+    functions: Vec<ScorerFunction>,
+    score_mode: ScoreMode,
+    boost_mode: BoostMode,
+    max_boost: f32,
+    min_score: Option<f32>,
+    boost: f32,
+}
+
+struct ScorerFunction {
+    /// Query string a hit must match for the function to apply
+    filter: Option<String>,
+    weight: Option<f32>,
+    kind: FunctionKind,
+}
+
+enum FunctionKind {
+    Weight,
+    /// `field_value_factor`, evaluated with the field value bound to `value`
+    FieldValue {
+        field: String,
+        missing: Option<f64>,
+        expression: ScoreFunctionReranker,
+    },
+    Script(ScoreFunctionReranker),
+    Decay(DecaySpec),
+}
+
+struct DecaySpec {
+    field: String,
+    function: DecayFunction,
+    /// Dates are compared in seconds since the epoch
+    is_date: bool,
+    origin: f64,
+    scale: f64,
+    offset: f64,
+    decay: f64,
+    multi_value_mode: MultiValueMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScoreMode {
+    Multiply,
+    Sum,
+    Avg,
+    First,
+    Max,
+    Min,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BoostMode {
+    Multiply,
+    Replace,
+    Sum,
+    Avg,
+    Max,
+    Min,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MultiValueMode {
+    Min,
+    Max,
+    Avg,
+    Sum,
+}
+
+impl FunctionScorer {
+    /// Build a scorer for a top-level query; other query types need none
+    pub fn from_query(query: &EsQuery) -> Result<Option<Self>, EsCompatError> {
+        match query {
+            EsQuery::FunctionScore(fs) => Self::from_function_score(fs).map(Some),
+            EsQuery::ScriptScore(ss) => Ok(Some(Self {
+                functions: vec![ScorerFunction {
+                    filter: None,
+                    weight: None,
+                    kind: FunctionKind::Script(compile_script(&ss.script)?),
+                }],
+                score_mode: ScoreMode::Multiply,
+                boost_mode: BoostMode::Replace,
+                max_boost: f32::MAX,
+                min_score: ss.min_score,
+                boost: ss.boost.unwrap_or(1.0),
+            })),
+            // Hits matching the negative query have their score multiplied
+            // by `negative_boost`
+            EsQuery::Boosting(b) => Ok(Some(Self {
+                functions: vec![ScorerFunction {
+                    filter: Some(QueryTranslator::translate_query(&b.negative)?),
+                    weight: Some(b.negative_boost),
+                    kind: FunctionKind::Weight,
+                }],
+                score_mode: ScoreMode::Multiply,
+                boost_mode: BoostMode::Multiply,
+                max_boost: f32::MAX,
+                min_score: None,
+                boost: 1.0,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    fn from_function_score(fs: &FunctionScoreQuery) -> Result<Self, EsCompatError> {
+        if !fs.functions.is_empty() && has_function(&fs.function) {
+            return Err(EsCompatError::InvalidQuery(
+                "[function_score] already has a [functions] array, cannot also define a function inline"
+                    .to_string(),
+            ));
+        }
+
+        let mut functions = Vec::new();
+        if has_function(&fs.function) || fs.function.weight.is_some() {
+            functions.push(compile_function(&fs.function)?);
+        }
+        for function in &fs.functions {
+            functions.push(compile_function(function)?);
+        }
+
+        Ok(Self {
+            functions,
+            score_mode: parse_score_mode(fs.score_mode.as_deref())?,
+            boost_mode: parse_boost_mode(fs.boost_mode.as_deref())?,
+            max_boost: fs.max_boost.unwrap_or(f32::MAX),
+            min_score: fs.min_score,
+            boost: fs.boost.unwrap_or(1.0),
+        })
+    }
+
+    /// Filter query strings of the functions, by function position
+    pub fn filters(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.functions
+            .iter()
+            .enumerate()
+            .filter_map(|(i, f)| f.filter.as_deref().map(|filter| (i, filter)))
+    }
+
+    /// Score one hit.
+    ///
+    /// `matches(i)` reports whether the hit matches the filter of function
+    /// `i`. Returns `None` when the score falls below `min_score`.
+    pub fn score(
+        &self,
+        hit: &SearchResult,
+        matches: impl Fn(usize) -> bool,
+    ) -> Result<Option<f32>, EsCompatError> {
+        let mut values: Vec<(f64, f64)> = Vec::new();
+        for (i, function) in self.functions.iter().enumerate() {
+            if function.filter.is_some() && !matches(i) {
+                continue;
+            }
+            let weight = function.weight.unwrap_or(1.0) as f64;
+            let value = match &function.kind {
+                FunctionKind::Weight => 1.0,
+                FunctionKind::FieldValue {
+                    field,
+                    missing,
+                    expression,
+                } => {
+                    let value = hit
+                        .fields
+                        .get(field)
+                        .and_then(first_number)
+                        .or(*missing)
+                        .ok_or_else(|| {
+                            EsCompatError::InvalidQuery(format!(
+                                "Missing value for field [{}]",
+                                field
+                            ))
+                        })?;
+                    let bound = HashMap::from([("value".to_string(), Value::from(value))]);
+                    evaluate(expression, hit.score, &bound)? as f64
+                }
+                FunctionKind::Script(script) => evaluate(script, hit.score, &hit.fields)? as f64,
+                FunctionKind::Decay(spec) => spec.evaluate(hit.fields.get(&spec.field)),
+            };
+            values.push((value * weight, weight));
+        }
+
+        let combined = if values.is_empty() {
+            1.0
+        } else {
+            match self.score_mode {
+                ScoreMode::Multiply => values.iter().map(|(v, _)| v).product(),
+                ScoreMode::Sum => values.iter().map(|(v, _)| v).sum(),
+                ScoreMode::Avg => {
+                    let weights: f64 = values.iter().map(|(_, w)| w).sum();
+                    values.iter().map(|(v, _)| v).sum::<f64>() / weights
+                }
+                ScoreMode::First => values[0].0,
+                ScoreMode::Max => values.iter().map(|(v, _)| *v).fold(f64::MIN, f64::max),
+                ScoreMode::Min => values.iter().map(|(v, _)| *v).fold(f64::MAX, f64::min),
+            }
+        };
+        let function_score = (combined as f32).min(self.max_boost);
+        let query_score = hit.score;
+
+        let score = match self.boost_mode {
+            BoostMode::Multiply => query_score * function_score,
+            BoostMode::Replace => function_score,
+            BoostMode::Sum => query_score + function_score,
+            BoostMode::Avg => (query_score + function_score) / 2.0,
+            BoostMode::Max => query_score.max(function_score),
+            BoostMode::Min => query_score.min(function_score),
+        } * self.boost;
+
+        if !score.is_finite() {
+            return Err(EsCompatError::InvalidQuery(format!(
+                "function score for document [{}] is not a finite number",
+                hit.id
+            )));
+        }
+        Ok(match self.min_score {
+            Some(min) if score < min => None,
+            _ => Some(score),
+        })
+    }
+}
+
+impl DecaySpec {
+    fn evaluate(&self, value: Option<&Value>) -> f64 {
+        let values: Vec<f64> = match value {
+            Some(Value::Array(items)) => items.iter().filter_map(|v| self.point(v)).collect(),
+            Some(v) => self.point(v).into_iter().collect(),
+            None => vec![],
+        };
+        // Documents without the field are not decayed
+        if values.is_empty() {
+            return 1.0;
+        }
+
+        let distances = values
+            .iter()
+            .map(|v| ((v - self.origin).abs() - self.offset).max(0.0));
+        let distance = match self.multi_value_mode {
+            MultiValueMode::Min => distances.fold(f64::MAX, f64::min),
+            MultiValueMode::Max => distances.fold(0.0, f64::max),
+            MultiValueMode::Sum => distances.sum(),
+            MultiValueMode::Avg => distances.sum::<f64>() / values.len() as f64,
+        };
+
+        // Scale the shared decay curves so the score is `decay` at `scale`,
+        // as in Elasticsearch
+        match self.function {
+            DecayFunction::Exponential => exponential_decay(distance, self.scale, self.decay),
+            DecayFunction::Linear => {
+                linear_decay(distance, self.scale / (1.0 - self.decay), self.decay)
+            }
+            DecayFunction::Gaussian => {
+                gaussian_decay(distance, self.scale / std::f64::consts::SQRT_2, self.decay)
+            }
+        }
+    }
+
+    fn point(&self, value: &Value) -> Option<f64> {
+        if self.is_date {
+            date_seconds(value)
+        } else {
+            value.as_f64()
+        }
+    }
+}
+
+fn has_function(function: &ScoreFunction) -> bool {
+    function.field_value_factor.is_some()
+        || function.gauss.is_some()
+        || function.exp.is_some()
+        || function.linear.is_some()
+        || function.script_score.is_some()
+        || function.random_score.is_some()
+}
+
+fn compile_function(function: &ScoreFunction) -> Result<ScorerFunction, EsCompatError> {
+    if function.random_score.is_some() {
+        return Err(EsCompatError::UnsupportedQueryType(
+            "random_score".to_string(),
+        ));
+    }
+
+    let mut kinds = Vec::new();
+    if let Some(fvf) = &function.field_value_factor {
+        kinds.push(FunctionKind::FieldValue {
+            field: fvf.field.clone(),
+            missing: fvf.missing,
+            expression: compile_expression(&field_value_expression(fvf)?)?,
+        });
+    }
+    for (params, decay_function) in [
+        (&function.gauss, DecayFunction::Gaussian),
+        (&function.exp, DecayFunction::Exponential),
+        (&function.linear, DecayFunction::Linear),
+    ] {
+        if let Some(params) = params {
+            kinds.push(FunctionKind::Decay(compile_decay(params, decay_function)?));
+        }
+    }
+    if let Some(script) = &function.script_score {
+        kinds.push(FunctionKind::Script(compile_script(&script.script)?));
+    }
+    if kinds.len() > 1 {
+        return Err(EsCompatError::InvalidQuery(
+            "a score function may only define one of field_value_factor, gauss, exp, linear or script_score"
+                .to_string(),
+        ));
+    }
+
+    let filter = function
+        .filter
+        .as_deref()
+        .map(QueryTranslator::translate_query)
+        .transpose()?;
+    Ok(ScorerFunction {
+        filter,
+        weight: function.weight,
+        kind: kinds.pop().unwrap_or(FunctionKind::Weight),
+    })
+}
+
+/// Expression for a `field_value_factor`, with the field value as `value`
+fn field_value_expression(fvf: &FieldValueFactor) -> Result<String, EsCompatError> {
+    let scaled = format!("({} * value)", fvf.factor.unwrap_or(1.0));
+    let log10 = std::f64::consts::LN_10;
+    Ok(match fvf.modifier.as_deref().unwrap_or("none") {
+        "none" => scaled,
+        "log" => format!("log({}) / {}", scaled, log10),
+        "log1p" => format!("log(1 + {}) / {}", scaled, log10),
+        "log2p" => format!("log(2 + {}) / {}", scaled, log10),
+        "ln" => format!("log({})", scaled),
+        "ln1p" => format!("log(1 + {})", scaled),
+        "ln2p" => format!("log(2 + {})", scaled),
+        "square" => format!("{} * {}", scaled, scaled),
+        "sqrt" => format!("sqrt({})", scaled),
+        "reciprocal" => format!("1 / {}", scaled),
+        other => {
+            return Err(EsCompatError::InvalidQuery(format!(
+                "Illegal modifier [{}] for [field_value_factor]",
+                other
+            )))
+        }
+    })
+}
+
+fn compile_decay(
+    params: &DecayParams,
+    function: DecayFunction,
+) -> Result<DecaySpec, EsCompatError> {
+    let mut fields = params.fields.iter();
+    let (field, field_params) = match (fields.next(), fields.next()) {
+        (Some(entry), None) => entry,
+        _ => {
+            return Err(EsCompatError::InvalidQuery(
+                "decay functions take exactly one field".to_string(),
+            ))
+        }
+    };
+
+    let decay = field_params.decay.unwrap_or(0.5);
+    if !(decay > 0.0 && decay < 1.0) {
+        return Err(EsCompatError::InvalidQuery(format!(
+            "[decay] must be in the range (0..1), got [{}]",
+            decay
+        )));
+    }
+    let multi_value_mode = match params.multi_value_mode.as_deref().unwrap_or("min") {
+        "min" => MultiValueMode::Min,
+        "max" => MultiValueMode::Max,
+        "avg" => MultiValueMode::Avg,
+        "sum" => MultiValueMode::Sum,
+        other => {
+            return Err(EsCompatError::InvalidQuery(format!(
+                "Illegal multi_value_mode [{}]",
+                other
+            )))
+        }
+    };
+
+    // A duration scale marks a date field
+    let is_date = field_params.scale.is_string();
+    let (origin, scale, offset) = if is_date {
+        let origin = match &field_params.origin {
+            None => now_seconds(),
+            Some(Value::String(s)) if s == "now" => now_seconds(),
+            Some(value) => date_seconds(value).ok_or_else(|| {
+                EsCompatError::InvalidQuery(format!("failed to parse date origin [{}]", value))
+            })?,
+        };
+        let scale = duration_seconds(&field_params.scale)?;
+        let offset = match &field_params.offset {
+            Some(offset) => duration_seconds(offset)?,
+            None => 0.0,
+        };
+        (origin, scale, offset)
+    } else {
+        let origin = field_params
+            .origin
+            .as_ref()
+            .and_then(Value::as_f64)
+            .ok_or_else(|| {
+                EsCompatError::InvalidQuery(format!(
+                    "[origin] is required for numeric decay on field [{}]",
+                    field
+                ))
+            })?;
+        let scale = field_params.scale.as_f64().unwrap_or(0.0);
+        let offset = field_params
+            .offset
+            .as_ref()
+            .and_then(Value::as_f64)
+            .unwrap_or(0.0);
+        (origin, scale, offset)
+    };
+    if scale <= 0.0 {
+        return Err(EsCompatError::InvalidQuery(format!(
+            "[scale] must be positive for decay on field [{}]",
+            field
+        )));
+    }
+
+    Ok(DecaySpec {
+        field: field.clone(),
+        function,
+        is_date,
+        origin,
+        scale,
+        offset,
+        decay,
+        multi_value_mode,
+    })
+}
+
+/// Compile a Painless script made of arithmetic over `_score`, numeric
+/// doc values, numeric params and `Math.log`/`Math.sqrt`
+fn compile_script(script: &EsScript) -> Result<ScoreFunctionReranker, EsCompatError> {
+    let (source, params) = match script {
+        EsScript::Source(source) => (source.as_str(), None),
+        EsScript::Object {
+            source,
+            lang,
+            params,
+        } => {
+            if lang.as_deref().is_some_and(|lang| lang != "painless") {
+                return Err(EsCompatError::InvalidQuery(format!(
+                    "script lang [{}] is not supported",
+                    lang.as_deref().unwrap_or_default()
+                )));
+            }
+            (source.as_str(), Some(params))
+        }
+    };
+
+    let doc_value = Regex::new(r#"doc\[\s*['"]([^'"]+)['"]\s*\]\.value"#).unwrap();
+    let param = Regex::new(r"params\.([A-Za-z_][A-Za-z0-9_]*)").unwrap();
+
+    let mut missing_param = None;
+    let expression = doc_value.replace_all(source, "$1");
+    let expression = param.replace_all(&expression, |caps: &regex::Captures| {
+        match params.and_then(|p| p.get(&caps[1])).and_then(Value::as_f64) {
+            Some(value) => format!("({})", value),
+            None => {
+                missing_param.get_or_insert_with(|| caps[1].to_string());
+                "0".to_string()
+            }
+        }
+    });
+    if let Some(name) = missing_param {
+        return Err(EsCompatError::InvalidQuery(format!(
+            "script param [{}] is missing or not a number",
+            name
+        )));
+    }
+    let expression = expression
+        .replace("Math.log(", "log(")
+        .replace("Math.sqrt(", "sqrt(");
+    let expression = expression.trim().trim_end_matches(';');
+    let expression = expression.strip_prefix("return ").unwrap_or(expression);
+
+    // Evaluate once up front so unsupported syntax fails the request early
+    ScoreFunctionReranker::new(expression)
+        .and_then(|compiled| {
+            compiled.try_evaluate(0.0, &HashMap::new())?;
+            Ok(compiled)
+        })
+        .map_err(|e| {
+            EsCompatError::InvalidQuery(format!("script [{}] is not supported: {}", source, e))
+        })
+}
+
+fn compile_expression(expression: &str) -> Result<ScoreFunctionReranker, EsCompatError> {
+    ScoreFunctionReranker::new(expression)
+        .map_err(|e| EsCompatError::InvalidQuery(format!("invalid score expression: {}", e)))
+}
+
+fn evaluate(
+    expression: &ScoreFunctionReranker,
+    score: f32,
+    fields: &HashMap<String, Value>,
+) -> Result<f32, EsCompatError> {
+    expression
+        .try_evaluate(score, fields)
+        .map_err(|e| EsCompatError::InvalidQuery(e.to_string()))
+}
+
+fn parse_score_mode(mode: Option<&str>) -> Result<ScoreMode, EsCompatError> {
+    Ok(match mode.unwrap_or("multiply") {
+        "multiply" => ScoreMode::Multiply,
+        "sum" => ScoreMode::Sum,
+        "avg" => ScoreMode::Avg,
+        "first" => ScoreMode::First,
+        "max" => ScoreMode::Max,
+        "min" => ScoreMode::Min,
+        other => {
+            return Err(EsCompatError::InvalidQuery(format!(
+                "illegal score_mode [{}]",
+                other
+            )))
+        }
+    })
+}
+
+fn parse_boost_mode(mode: Option<&str>) -> Result<BoostMode, EsCompatError> {
+    Ok(match mode.unwrap_or("multiply") {
+        "multiply" => BoostMode::Multiply,
+        "replace" => BoostMode::Replace,
+        "sum" => BoostMode::Sum,
+        "avg" => BoostMode::Avg,
+        "max" => BoostMode::Max,
+        "min" => BoostMode::Min,
+        other => {
+            return Err(EsCompatError::InvalidQuery(format!(
+                "illegal boost_mode [{}]",
+                other
+            )))
+        }
+    })
+}
+
+fn first_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Array(items) => items.iter().find_map(Value::as_f64),
+        Value::String(s) => s.parse().ok(),
+        other => other.as_f64(),
+    }
+}
+
+fn now_seconds() -> f64 {
+    chrono::Utc::now().timestamp_millis() as f64 / 1000.0
+}
+
+/// Seconds since the epoch of an RFC 3339 date, `yyyy-MM-dd` date or
+/// epoch-millis number
+fn date_seconds(value: &Value) -> Option<f64> {
+    match value {
+        Value::String(s) => DateTime::parse_from_rfc3339(s)
+            .map(|dt| dt.timestamp_millis() as f64 / 1000.0)
+            .ok()
+            .or_else(|| {
+                NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    .ok()
+                    .and_then(|d| d.and_hms_opt(0, 0, 0))
+                    .map(|dt| dt.and_utc().timestamp() as f64)
+            }),
+        other => other.as_f64().map(|millis| millis / 1000.0),
+    }
+}
+
+fn duration_seconds(value: &Value) -> Result<f64, EsCompatError> {
+    value
+        .as_str()
+        .and_then(parse_duration)
+        .map(|d| d.as_secs_f64())
+        .ok_or_else(|| EsCompatError::InvalidQuery(format!("failed to parse duration [{}]", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn hit(score: f32, fields: Value) -> SearchResult {
+        SearchResult {
+            id: "1".to_string(),
+            score,
+            fields: serde_json::from_value(fields).unwrap(),
+            highlight: None,
+        }
+    }
+
+    fn scorer(query: Value) -> FunctionScorer {
+        let query: EsQuery = serde_json::from_value(query).unwrap();
+        FunctionScorer::from_query(&query).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_field_value_factor() {
+        let scorer = scorer(json!({"function_score": {
+            "query": {"match_all": {}},
+            "field_value_factor": {"field": "likes", "factor": 2.0, "modifier": "ln1p"}
+        }}));
+        let score = scorer.score(&hit(2.0, json!({"likes": 4})), |_| true);
+        // 2.0 * ln(1 + 2 * 4)
+        assert!((score.unwrap().unwrap() - 2.0 * 9f32.ln()).abs() < 1e-4);
+
+        let missing = scorer.score(&hit(2.0, json!({})), |_| true);
+        assert!(matches!(missing, Err(EsCompatError::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn test_weights_with_filters() {
+        let scorer = scorer(json!({"function_score": {
+            "functions": [
+                {"filter": {"term": {"tag": "hot"}}, "weight": 3},
+                {"weight": 2}
+            ],
+            "score_mode": "sum",
+            "boost_mode": "replace"
+        }}));
+        let filters: Vec<_> = scorer.filters().collect();
+        assert_eq!(filters, vec![(0, "tag:hot")]);
+
+        let doc = hit(1.0, json!({}));
+        assert_eq!(scorer.score(&doc, |_| true).unwrap(), Some(5.0));
+        assert_eq!(scorer.score(&doc, |_| false).unwrap(), Some(2.0));
+    }
+
+    #[test]
+    fn test_boosting_demotes_negative_matches() {
+        let scorer = scorer(json!({"boosting": {
+            "positive": {"term": {"text": "apple"}},
+            "negative": {"term": {"text": "pie"}},
+            "negative_boost": 0.25
+        }}));
+        let filters: Vec<_> = scorer.filters().collect();
+        assert_eq!(filters, vec![(0, "text:pie")]);
+
+        let doc = hit(2.0, json!({}));
+        assert_eq!(scorer.score(&doc, |_| true).unwrap(), Some(0.5));
+        assert_eq!(scorer.score(&doc, |_| false).unwrap(), Some(2.0));
+    }
+
+    #[test]
+    fn test_numeric_decay_matches_es_curves() {
+        for (function, field) in [("gauss", "gauss"), ("exp", "exp"), ("linear", "linear")] {
+            let scorer = scorer(json!({"function_score": {
+                function: {"price": {"origin": 10, "scale": 5, "offset": 1, "decay": 0.25}},
+                "boost_mode": "replace"
+            }}));
+            let at_origin = scorer.score(&hit(1.0, json!({"price": 11})), |_| true);
+            assert!((at_origin.unwrap().unwrap() - 1.0).abs() < 1e-6, "{field}");
+            // Score equals `decay` at offset + scale from the origin
+            let at_scale = scorer.score(&hit(1.0, json!({"price": 4})), |_| true);
+            assert!((at_scale.unwrap().unwrap() - 0.25).abs() < 1e-4, "{field}");
+            // Missing fields are not decayed
+            let missing = scorer.score(&hit(1.0, json!({})), |_| true);
+            assert_eq!(missing.unwrap(), Some(1.0));
+        }
+    }
+
+    #[test]
+    fn test_date_decay() {
+        let scorer = scorer(json!({"function_score": {
+            "exp": {"published": {"origin": "2024-01-11T00:00:00Z", "scale": "10d"}},
+            "boost_mode": "replace"
+        }}));
+        let score = scorer.score(
+            &hit(1.0, json!({"published": "2024-01-01T00:00:00Z"})),
+            |_| true,
+        );
+        assert!((score.unwrap().unwrap() - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_min_score_and_max_boost() {
+        let scorer = scorer(json!({"function_score": {
+            "field_value_factor": {"field": "likes"},
+            "max_boost": 3,
+            "min_score": 5
+        }}));
+        assert_eq!(
+            scorer
+                .score(&hit(2.0, json!({"likes": 10})), |_| true)
+                .unwrap(),
+            Some(6.0)
+        );
+        assert_eq!(
+            scorer
+                .score(&hit(2.0, json!({"likes": 2})), |_| true)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_script_score() {
+        let scorer = scorer(json!({"script_score": {
+            "query": {"match": {"title": "shoes"}},
+            "script": {
+                "source": "_score * Math.log(2 + doc['likes'].value) * params.factor",
+                "params": {"factor": 2}
+            }
+        }}));
+        let score = scorer.score(&hit(1.5, json!({"likes": 5})), |_| true);
+        assert!((score.unwrap().unwrap() - 1.5 * 7f32.ln() * 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_unsupported_functions() {
+        let random: EsQuery = serde_json::from_value(json!({"function_score": {
+            "random_score": {}
+        }}))
+        .unwrap();
+        assert!(FunctionScorer::from_query(&random).is_err());
+
+        let vector_script: EsQuery = serde_json::from_value(json!({"script_score": {
+            "query": {"match_all": {}},
+            "script": {"source": "cosineSimilarity(params.v, 'embedding') + 1.0", "params": {"v": [1.0]}}
+        }}))
+        .unwrap();
+        assert!(FunctionScorer::from_query(&vector_script).is_err());
+
+        let plain: EsQuery = serde_json::from_value(json!({"match_all": {}})).unwrap();
+        assert!(FunctionScorer::from_query(&plain).unwrap().is_none());
+    }
+}
@@ -2,13 +2,19 @@
 
 use crate::error::EsCompatError;
 use crate::query::types::*;
-use prism::aggregations::{AggregationRequest, AggregationType, HistogramBounds, RangeEntry};
-use prism::backends::{HighlightConfig, Query};
+use crate::response::field_matches;
+use prism::aggregations::{
+    AggregationRequest, AggregationType, BucketSortField, CompositeSource, CompositeSourceType,
+    GapPolicy, HistogramBounds, MovingFunction, RangeEntry, SortDirection, TopHitsSort,
+};
+use prism::backends::text::TermPattern;
+use prism::backends::{HighlightConfig, HighlightFieldOptions, Query};
 use serde_json::Value;
 use std::collections::HashMap;
 
-/// Maximum allowed search result limit
-const MAX_SEARCH_LIMIT: usize = 10_000;
+/// Largest `from + size` a search may page to, as with Elasticsearch's
+/// default `index.max_result_window`
+pub const MAX_RESULT_WINDOW: usize = 10_000;
 
 /// Translates Elasticsearch Query DSL to Prism query format
 pub struct QueryTranslator;
@@ -16,6 +22,16 @@ pub struct QueryTranslator;
 /// Maximum length for passthrough query strings to prevent DoS
 const MAX_QUERY_STRING_LENGTH: usize = 10_000;
 
+/// Terms a `prefix`, `wildcard` or `regexp` query expands to at most, as
+/// Elasticsearch's former default `indices.query.bool.max_clause_count`
+const MAX_TERM_EXPANSIONS: usize = 1024;
+
+/// Default `max_expansions` of `fuzzy` and phrase prefix queries
+const DEFAULT_MAX_EXPANSIONS: usize = 50;
+
+/// Query string matching no document
+const MATCH_NONE: &str = "id:\"\"";
+
 impl QueryTranslator {
     /// Translate an ES search request to Prism Query + aggregations
     pub fn translate(
@@ -24,6 +40,8 @@ impl QueryTranslator {
     ) -> Result<(Query, Vec<AggregationRequest>), EsCompatError> {
         // Translate query to query string
         let query_string = match &request.query {
+            // Negative matches are demoted when rescoring
+            Some(EsQuery::Boosting(b)) => Self::translate_query(&b.positive)?,
             Some(q) => Self::translate_query(q)?,
             None => "*".to_string(), // Match all
         };
@@ -35,13 +53,27 @@ impl QueryTranslator {
         };
 
         // Translate highlight config
-        let highlight = request.highlight.as_ref().map(Self::translate_highlight);
+        let highlight = request
+            .highlight
+            .as_ref()
+            .map(|h| Self::translate_highlight(h, default_fields));
+
+        let offset = request.from.unwrap_or(0);
+        let limit = request.size.unwrap_or(10);
+        let window = offset.saturating_add(limit);
+        if window > MAX_RESULT_WINDOW {
+            return Err(EsCompatError::IllegalArgument(format!(
+                "Result window is too large, from + size must be less than or equal to: [{}] but was [{}]. \
+                 See the scroll api for a more efficient way to request large data sets.",
+                MAX_RESULT_WINDOW, window
+            )));
+        }
 
         let query = Query {
             query_string,
             fields: default_fields.to_vec(),
-            limit: request.size.unwrap_or(10).min(MAX_SEARCH_LIMIT),
-            offset: request.from.unwrap_or(0),
+            limit,
+            offset,
             merge_strategy: None,
             text_weight: None,
             vector_weight: None,
@@ -118,7 +150,11 @@ impl QueryTranslator {
                         .iter()
                         .map(|v| format!("{}:{}", field, escape_value(&value_to_string(v))))
                         .collect();
-                    parts.push(format!("({})", value_parts.join(" OR ")));
+                    if value_parts.is_empty() {
+                        parts.push(MATCH_NONE.to_string());
+                    } else {
+                        parts.push(format!("({})", value_parts.join(" OR ")));
+                    }
                 }
                 Ok(parts.join(" AND "))
             }
@@ -189,9 +225,326 @@ impl QueryTranslator {
                     .collect();
                 Ok(format!("({})", id_parts.join(" OR ")))
             }
+
+            // Expanded to index terms by `expand_multi_term_queries`
+            EsQuery::Fuzzy(_) => Err(not_expanded("fuzzy")),
+            EsQuery::Regexp(_) => Err(not_expanded("regexp")),
+            EsQuery::MatchBoolPrefix(_) => Err(not_expanded("match_bool_prefix")),
+
+            EsQuery::MatchPhrasePrefix(fields) => {
+                let mut parts = vec![];
+                for (field, match_query) in fields {
+                    let value = match match_query {
+                        MatchPhrasePrefixQuery::Simple(s) => s,
+                        MatchPhrasePrefixQuery::Object { query, .. } => query,
+                    };
+                    // Single terms are plain prefixes, expanded beforehand
+                    if value.split_whitespace().count() < 2 {
+                        return Err(not_expanded("match_phrase_prefix"));
+                    }
+                    parts.push(format!("{}:\"{}\"*", field, escape_phrase(value.trim())));
+                }
+                Ok(parts.join(" AND "))
+            }
+
+            EsQuery::ConstantScore(cs) => {
+                let filter = Self::translate_query(&cs.filter)?;
+                match cs.boost {
+                    Some(boost) if boost != 1.0 => Ok(format!("({})^{}", filter, boost)),
+                    _ => Ok(filter),
+                }
+            }
+
+            EsQuery::DisMax(dm) => {
+                if dm.queries.is_empty() {
+                    return Err(EsCompatError::InvalidQuery(
+                        "[dis_max] requires at least one query".to_string(),
+                    ));
+                }
+                let parts = dm
+                    .queries
+                    .iter()
+                    .map(|q| Self::translate_query(q).map(|s| format!("({})", s)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(parts.join(" OR "))
+            }
+
+            // Negative matches are demoted by query::scoring, which only
+            // rescores top-level queries
+            EsQuery::Boosting(_) => Err(EsCompatError::UnsupportedQueryType(
+                "[boosting] is only supported as the top-level query".to_string(),
+            )),
+
+            // Nested objects are indexed under their full dotted paths
+            EsQuery::Nested(n) => Self::translate_query(&n.query),
+
+            EsQuery::MoreLikeThis(mlt) => Self::translate_more_like_this(mlt),
+
+            // Scores are applied after retrieval by query::scoring
+            EsQuery::FunctionScore(fs) => match &fs.query {
+                Some(q) => Self::translate_query(q),
+                None => Ok("*".to_string()),
+            },
+
+            EsQuery::ScriptScore(ss) => Self::translate_query(&ss.query),
+        }
+    }
+
+    /// Translate filter clauses, which must all match, to a query string
+    pub fn translate_filter(filter: &QueryList) -> Result<String, EsCompatError> {
+        let parts = filter
+            .iter()
+            .map(|q| Self::translate_query(q).map(|s| format!("({})", s)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if parts.is_empty() {
+            Ok("*".to_string())
+        } else {
+            Ok(parts.join(" AND "))
         }
     }
 
+    /// Rewrite multi-term queries to the index terms they match, which
+    /// `expand` looks up for a field, a pattern and an expansion limit.
+    ///
+    /// Tantivy's query parser has no fuzzy, regex or single-term prefix
+    /// syntax, so `prefix`, `wildcard`, `regexp` and `fuzzy` queries and the
+    /// prefix terms of `match_phrase_prefix` and `match_bool_prefix` become
+    /// `terms` queries, as Lucene rewrites them before searching.
+    pub fn expand_multi_term_queries<F>(
+        query: &mut EsQuery,
+        expand: &mut F,
+    ) -> Result<(), EsCompatError>
+    where
+        F: FnMut(&str, &TermPattern, usize) -> Result<Vec<String>, EsCompatError>,
+    {
+        let mut clauses = Vec::new();
+        match query {
+            EsQuery::Prefix(fields) => {
+                for (field, params) in fields.iter() {
+                    let value = match params {
+                        PrefixParams::Simple(s) => s,
+                        PrefixParams::Object { value, .. } => value,
+                    };
+                    let pattern = TermPattern::Prefix(value.clone());
+                    let terms = expand(field, &pattern, MAX_TERM_EXPANSIONS)?;
+                    clauses.push(terms_query(field, terms));
+                }
+            }
+
+            EsQuery::Wildcard(fields) => {
+                for (field, params) in fields.iter() {
+                    let value = match params {
+                        WildcardParams::Simple(s) => s,
+                        WildcardParams::Object { value, .. } => value,
+                    };
+                    let pattern = TermPattern::Wildcard(value.clone());
+                    let terms = expand(field, &pattern, MAX_TERM_EXPANSIONS)?;
+                    clauses.push(terms_query(field, terms));
+                }
+            }
+
+            EsQuery::Regexp(fields) => {
+                for (field, params) in fields.iter() {
+                    let regex = match params {
+                        RegexpParams::Simple(s) => s.clone(),
+                        RegexpParams::Object {
+                            value,
+                            case_insensitive: Some(true),
+                            ..
+                        } => format!("(?i){}", value),
+                        RegexpParams::Object { value, .. } => value.clone(),
+                    };
+                    let terms = expand(field, &TermPattern::Regex(regex), MAX_TERM_EXPANSIONS)?;
+                    clauses.push(terms_query(field, terms));
+                }
+            }
+
+            EsQuery::Fuzzy(fields) => {
+                for (field, params) in fields.iter() {
+                    let (value, fuzziness, prefix_length, max_expansions, transpositions) =
+                        match params {
+                            FuzzyParams::Simple(v) => (v, None, None, None, None),
+                            FuzzyParams::Object {
+                                value,
+                                fuzziness,
+                                prefix_length,
+                                max_expansions,
+                                transpositions,
+                                ..
+                            } => (
+                                value,
+                                fuzziness.as_ref(),
+                                *prefix_length,
+                                *max_expansions,
+                                *transpositions,
+                            ),
+                        };
+                    let term = value_to_string(value);
+                    let pattern = TermPattern::Fuzzy {
+                        distance: edit_distance(&term, fuzziness)? as u8,
+                        term,
+                        prefix_length: prefix_length.unwrap_or(0) as usize,
+                        transpositions: transpositions.unwrap_or(true),
+                    };
+                    let max_expansions =
+                        max_expansions.map_or(DEFAULT_MAX_EXPANSIONS, |m| m as usize);
+                    let terms = expand(field, &pattern, max_expansions)?;
+                    clauses.push(terms_query(field, terms));
+                }
+            }
+
+            EsQuery::MatchPhrasePrefix(fields) => {
+                for (field, match_query) in fields.iter() {
+                    let (value, max_expansions) = match match_query {
+                        MatchPhrasePrefixQuery::Simple(s) => (s, None),
+                        MatchPhrasePrefixQuery::Object {
+                            query,
+                            max_expansions,
+                            ..
+                        } => (query, *max_expansions),
+                    };
+                    // Phrases of two terms or more stay phrase prefix queries
+                    if value.split_whitespace().count() > 1 {
+                        let phrase = HashMap::from([(field.clone(), match_query.clone())]);
+                        clauses.push(EsQuery::MatchPhrasePrefix(phrase));
+                        continue;
+                    }
+                    let pattern = TermPattern::Prefix(value.trim().to_lowercase());
+                    let max_expansions =
+                        max_expansions.map_or(DEFAULT_MAX_EXPANSIONS, |m| m as usize);
+                    let terms = expand(field, &pattern, max_expansions)?;
+                    clauses.push(terms_query(field, terms));
+                }
+            }
+
+            EsQuery::MatchBoolPrefix(fields) => {
+                for (field, match_query) in fields.iter() {
+                    let (value, operator, minimum_should_match) = match match_query {
+                        MatchBoolPrefixQuery::Simple(s) => (s, None, None),
+                        MatchBoolPrefixQuery::Object {
+                            query,
+                            operator,
+                            minimum_should_match,
+                            ..
+                        } => (query, operator.as_deref(), minimum_should_match.clone()),
+                    };
+                    let words: Vec<&str> = value.split_whitespace().collect();
+                    let Some((last, rest)) = words.split_last() else {
+                        return Err(EsCompatError::InvalidQuery(
+                            "[match_bool_prefix] requires a non-empty query".to_string(),
+                        ));
+                    };
+                    let mut terms: Vec<EsQuery> = rest
+                        .iter()
+                        .map(|w| {
+                            EsQuery::Match(HashMap::from([(
+                                field.clone(),
+                                MatchQuery::Simple(w.to_string()),
+                            )]))
+                        })
+                        .collect();
+                    let pattern = TermPattern::Prefix(last.to_lowercase());
+                    terms.push(terms_query(
+                        field,
+                        expand(field, &pattern, MAX_TERM_EXPANSIONS)?,
+                    ));
+                    let terms = Some(QueryList::Multiple(terms));
+                    let bool_query = match operator {
+                        Some(op) if op.eq_ignore_ascii_case("and") => BoolQuery {
+                            must: terms,
+                            ..Default::default()
+                        },
+                        _ => BoolQuery {
+                            should: terms,
+                            minimum_should_match,
+                            ..Default::default()
+                        },
+                    };
+                    clauses.push(EsQuery::Bool(bool_query));
+                }
+            }
+
+            _ => {
+                for child in query.children_mut() {
+                    Self::expand_multi_term_queries(child, expand)?;
+                }
+                return Ok(());
+            }
+        }
+
+        *query = match clauses.len() {
+            0 => return Ok(()),
+            1 => clauses.remove(0),
+            _ => EsQuery::Bool(BoolQuery {
+                must: Some(QueryList::Multiple(clauses)),
+                ..Default::default()
+            }),
+        };
+        Ok(())
+    }
+
+    /// Search the significant terms of a `more_like_this` query.
+    ///
+    /// Terms of `like` documents come from the index, so those queries must
+    /// have `resolved_terms` set; text-only queries fall back to the text's
+    /// own words.
+    fn translate_more_like_this(mlt: &MoreLikeThisQuery) -> Result<String, EsCompatError> {
+        let doc_ids: Vec<&str> = mlt
+            .like
+            .iter()
+            .filter_map(|like| match like {
+                MltLike::Document { id, .. } => Some(id.as_str()),
+                MltLike::Text(_) => None,
+            })
+            .collect();
+
+        let terms = match &mlt.resolved_terms {
+            Some(terms) => terms.clone(),
+            None if doc_ids.is_empty() => {
+                let mut terms: Vec<String> = Vec::new();
+                for like in mlt.like.iter() {
+                    if let MltLike::Text(text) = like {
+                        for word in text.split_whitespace().map(str::to_lowercase) {
+                            if word.len() >= 2 && !terms.contains(&word) {
+                                terms.push(word);
+                            }
+                        }
+                    }
+                }
+                terms.truncate(mlt.max_query_terms);
+                terms
+            }
+            None => {
+                return Err(EsCompatError::InvalidQuery(
+                    "[more_like_this] documents must be looked up before translation".to_string(),
+                ))
+            }
+        };
+        if terms.is_empty() {
+            // Matches nothing, like ES when no term is significant
+            return Ok(MATCH_NONE.to_string());
+        }
+
+        let terms: Vec<String> = terms.iter().map(|t| escape_value(t)).collect();
+        let terms = terms.join(" ");
+        let mut query = if mlt.fields.is_empty() {
+            format!("({})", terms)
+        } else {
+            let parts: Vec<String> = mlt
+                .fields
+                .iter()
+                .map(|f| format!("{}:({})", f, terms))
+                .collect();
+            format!("({})", parts.join(" OR "))
+        };
+        if !mlt.include {
+            for id in doc_ids {
+                query.push_str(&format!(" AND -id:{}", escape_value(id)));
+            }
+        }
+        Ok(query)
+    }
+
     fn translate_range(field: &str, params: &RangeParams) -> Result<String, EsCompatError> {
         let lower = params
             .gte
@@ -271,8 +624,41 @@ impl QueryTranslator {
         }
     }
 
-    fn translate_highlight(highlight: &EsHighlight) -> HighlightConfig {
-        let fields: Vec<String> = highlight.fields.keys().cloned().collect();
+    /// Translate highlight settings. Field names may be wildcard patterns,
+    /// which expand to the matching `text_fields`.
+    fn translate_highlight(highlight: &EsHighlight, text_fields: &[String]) -> HighlightConfig {
+        let mut fields: Vec<String> = Vec::new();
+        let mut field_options = HashMap::new();
+        for (pattern, settings) in &highlight.fields {
+            let matched = if pattern.contains('*') {
+                text_fields
+                    .iter()
+                    .filter(|f| field_matches(pattern, f))
+                    .cloned()
+                    .collect()
+            } else {
+                vec![pattern.clone()]
+            };
+            let options = HighlightFieldOptions {
+                pre_tag: settings.pre_tags.as_ref().and_then(|t| t.first().cloned()),
+                post_tag: settings.post_tags.as_ref().and_then(|t| t.first().cloned()),
+                fragment_size: settings.fragment_size,
+                number_of_fragments: settings.number_of_fragments,
+            };
+            let overrides = options.pre_tag.is_some()
+                || options.post_tag.is_some()
+                || options.fragment_size.is_some()
+                || options.number_of_fragments.is_some();
+            for field in matched {
+                if overrides {
+                    field_options.insert(field.clone(), options.clone());
+                }
+                if !fields.contains(&field) {
+                    fields.push(field);
+                }
+            }
+        }
+        fields.sort();
 
         HighlightConfig {
             fields,
@@ -288,6 +674,7 @@ impl QueryTranslator {
                 .unwrap_or_else(|| "</em>".to_string()),
             fragment_size: highlight.fragment_size.unwrap_or(150),
             number_of_fragments: highlight.number_of_fragments.unwrap_or(3),
+            field_options,
         }
     }
 
@@ -303,6 +690,14 @@ impl QueryTranslator {
             }
         }
 
+        // ES preserves declaration order but the map does not; run pipelines
+        // that compute values before those that reorder or drop buckets
+        requests.sort_by_key(|req| match req.agg_type {
+            AggregationType::BucketSort { .. } | AggregationType::BucketSelector { .. } => 2,
+            ref t if t.is_pipeline() => 1,
+            _ => 0,
+        });
+
         Ok(requests)
     }
 
@@ -390,6 +785,35 @@ impl QueryTranslator {
             }));
         }
 
+        if let Some(cardinality) = &agg.cardinality {
+            return Ok(Some(AggregationRequest {
+                name: name.to_string(),
+                agg_type: AggregationType::Cardinality {
+                    field: cardinality.field.clone(),
+                    precision_threshold: cardinality.precision_threshold,
+                    sketch: false,
+                },
+                aggs: sub_aggs,
+            }));
+        }
+
+        if let Some(top_hits) = &agg.top_hits {
+            return Ok(Some(AggregationRequest {
+                name: name.to_string(),
+                agg_type: AggregationType::TopHits {
+                    size: top_hits.size,
+                    from: top_hits.from,
+                    sort: top_hits
+                        .sort
+                        .as_deref()
+                        .map(Self::translate_sort)
+                        .unwrap_or_default(),
+                    source: top_hits.source.as_ref().and_then(Self::source_includes),
+                },
+                aggs: sub_aggs,
+            }));
+        }
+
         // Check bucket aggregations
         if let Some(terms) = &agg.terms {
             return Ok(Some(AggregationRequest {
@@ -402,6 +826,36 @@ impl QueryTranslator {
             }));
         }
 
+        if let Some(significant) = &agg.significant_terms {
+            let background_filter = significant
+                .background_filter
+                .as_deref()
+                .map(Self::translate_query)
+                .transpose()?;
+            return Ok(Some(AggregationRequest {
+                name: name.to_string(),
+                agg_type: AggregationType::SignificantTerms {
+                    field: significant.field.clone(),
+                    size: significant.size,
+                    min_doc_count: significant.min_doc_count,
+                    background_filter,
+                },
+                aggs: sub_aggs,
+            }));
+        }
+
+        if let Some(composite) = &agg.composite {
+            return Ok(Some(AggregationRequest {
+                name: name.to_string(),
+                agg_type: AggregationType::Composite {
+                    sources: Self::translate_composite_sources(name, &composite.sources)?,
+                    size: composite.size,
+                    after: composite.after.clone(),
+                },
+                aggs: sub_aggs,
+            }));
+        }
+
         if let Some(histogram) = &agg.histogram {
             return Ok(Some(AggregationRequest {
                 name: name.to_string(),
@@ -483,6 +937,85 @@ impl QueryTranslator {
             }));
         }
 
+        // Pipeline aggregations
+        if let Some(derivative) = &agg.derivative {
+            return Ok(Some(AggregationRequest {
+                name: name.to_string(),
+                agg_type: AggregationType::Derivative {
+                    buckets_path: derivative.buckets_path.clone(),
+                    gap_policy: Self::translate_gap_policy(name, &derivative.gap_policy)?,
+                },
+                aggs: sub_aggs,
+            }));
+        }
+
+        if let Some(cumulative_sum) = &agg.cumulative_sum {
+            return Ok(Some(AggregationRequest {
+                name: name.to_string(),
+                agg_type: AggregationType::CumulativeSum {
+                    buckets_path: cumulative_sum.buckets_path.clone(),
+                },
+                aggs: sub_aggs,
+            }));
+        }
+
+        if let Some(moving_fn) = &agg.moving_fn {
+            return Ok(Some(AggregationRequest {
+                name: name.to_string(),
+                agg_type: AggregationType::MovingFn {
+                    buckets_path: moving_fn.buckets_path.clone(),
+                    window: moving_fn.window,
+                    function: Self::translate_moving_function(name, &moving_fn.script)?,
+                    shift: moving_fn.shift.unwrap_or(0),
+                    gap_policy: Self::translate_gap_policy(name, &moving_fn.gap_policy)?,
+                },
+                aggs: sub_aggs,
+            }));
+        }
+
+        if let Some(bucket_sort) = &agg.bucket_sort {
+            return Ok(Some(AggregationRequest {
+                name: name.to_string(),
+                agg_type: AggregationType::BucketSort {
+                    sort: bucket_sort
+                        .sort
+                        .as_deref()
+                        .map(Self::translate_bucket_sort)
+                        .unwrap_or_default(),
+                    from: bucket_sort.from.unwrap_or(0),
+                    size: bucket_sort.size,
+                    gap_policy: Self::translate_gap_policy(name, &bucket_sort.gap_policy)?,
+                },
+                aggs: sub_aggs,
+            }));
+        }
+
+        if let Some(selector) = &agg.bucket_selector {
+            return Ok(Some(AggregationRequest {
+                name: name.to_string(),
+                agg_type: AggregationType::BucketSelector {
+                    buckets_path: selector.buckets_path.clone(),
+                    script: Self::translate_selector_script(&selector.script),
+                    gap_policy: Self::translate_gap_policy(name, &selector.gap_policy)?,
+                },
+                aggs: sub_aggs,
+            }));
+        }
+
+        // Reject rather than silently drop types that are not executed
+        if agg.date_range.is_some() {
+            return Err(EsCompatError::UnsupportedAggregation(format!(
+                "[date_range] aggregation '{}' is not supported",
+                name
+            )));
+        }
+        if let Some(kind) = agg.other.keys().find(|key| *key != "meta") {
+            return Err(EsCompatError::UnsupportedAggregation(format!(
+                "Unknown aggregation type [{}] for aggregation '{}'",
+                kind, name
+            )));
+        }
+
         // If only sub-aggregations, this might be a pure nesting container
         // Return None and let caller handle
         if sub_aggs.is_some() {
@@ -494,6 +1027,235 @@ impl QueryTranslator {
 
         Ok(None)
     }
+
+    /// Translate sort clauses of a search or `top_hits`. Field sorts default
+    /// to ascending and `_score` to descending, as in Elasticsearch;
+    /// `_shard_doc` is index order like `_doc`.
+    pub fn translate_sort(clauses: &[SortClause]) -> Vec<TopHitsSort> {
+        let default_order = |field: &str| {
+            if field == "_score" {
+                SortDirection::Desc
+            } else {
+                SortDirection::Asc
+            }
+        };
+        let field_name = |field: &str| match field {
+            "_shard_doc" => "_doc".to_string(),
+            field => field.to_string(),
+        };
+        let mut sort = Vec::new();
+        for clause in clauses {
+            match clause {
+                SortClause::Field(field) => sort.push(TopHitsSort {
+                    field: field_name(field),
+                    order: default_order(field),
+                    ..Default::default()
+                }),
+                SortClause::Object(fields) => {
+                    for (field, order) in fields {
+                        let (order, missing, unmapped_type) = match order {
+                            SortOrder::Simple(o) => (Some(o), None, None),
+                            SortOrder::Object {
+                                order,
+                                missing,
+                                unmapped_type,
+                            } => (order.as_ref(), missing.clone(), unmapped_type.clone()),
+                        };
+                        sort.push(TopHitsSort {
+                            field: field_name(field),
+                            order: match order.map(|o| o.to_lowercase()).as_deref() {
+                                Some("asc") => SortDirection::Asc,
+                                Some("desc") => SortDirection::Desc,
+                                _ => default_order(field),
+                            },
+                            missing,
+                            unmapped_type,
+                        });
+                    }
+                }
+            }
+        }
+        sort
+    }
+
+    fn translate_gap_policy(
+        agg_name: &str,
+        gap_policy: &Option<String>,
+    ) -> Result<GapPolicy, EsCompatError> {
+        match gap_policy.as_deref() {
+            None | Some("skip") => Ok(GapPolicy::Skip),
+            Some("insert_zeros") => Ok(GapPolicy::InsertZeros),
+            Some(other) => Err(EsCompatError::UnsupportedAggregation(format!(
+                "Aggregation '{}': unsupported gap_policy '{}'",
+                agg_name, other
+            ))),
+        }
+    }
+
+    /// Map a `moving_fn` script such as `MovingFunctions.max(values)` to the
+    /// built-in window function it calls
+    fn translate_moving_function(
+        agg_name: &str,
+        script: &EsScript,
+    ) -> Result<MovingFunction, EsCompatError> {
+        let source = match script {
+            EsScript::Source(source) | EsScript::Object { source, .. } => source,
+        };
+        let function = source
+            .trim()
+            .strip_prefix("MovingFunctions.")
+            .and_then(|rest| rest.split('(').next())
+            .unwrap_or_default();
+        match function {
+            "max" => Ok(MovingFunction::Max),
+            "min" => Ok(MovingFunction::Min),
+            "sum" => Ok(MovingFunction::Sum),
+            "unweightedAvg" => Ok(MovingFunction::UnweightedAvg),
+            "linearWeightedAvg" => Ok(MovingFunction::LinearWeightedAvg),
+            "stdDev" => Ok(MovingFunction::StdDev),
+            _ => Err(EsCompatError::UnsupportedAggregation(format!(
+                "moving_fn '{}': unsupported script '{}'",
+                agg_name, source
+            ))),
+        }
+    }
+
+    /// Rewrite a Painless-style selector script into a score-function
+    /// expression: numeric `params` are inlined and `params.<var>` becomes
+    /// the bare `buckets_path` variable
+    fn translate_selector_script(script: &EsScript) -> String {
+        let (source, params) = match script {
+            EsScript::Source(source) => (source.as_str(), None),
+            EsScript::Object { source, params, .. } => (source.as_str(), Some(params)),
+        };
+        let mut expression = source.trim().trim_end_matches(';').to_string();
+        if let Some(params) = params {
+            // Longest names first so `params.a` does not clobber `params.ab`
+            let mut names: Vec<&String> = params.keys().collect();
+            names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+            for name in names {
+                if let Some(n) = params[name].as_f64() {
+                    expression = expression.replace(&format!("params.{}", name), &n.to_string());
+                }
+            }
+        }
+        expression.replace("params.", "")
+    }
+
+    /// Translate `bucket_sort` sort clauses (ascending unless stated)
+    fn translate_bucket_sort(clauses: &[SortClause]) -> Vec<BucketSortField> {
+        let mut sort = Vec::new();
+        for clause in clauses {
+            match clause {
+                SortClause::Field(path) => sort.push(BucketSortField {
+                    path: path.clone(),
+                    order: SortDirection::Asc,
+                }),
+                SortClause::Object(fields) => {
+                    for (path, order) in fields {
+                        let order = match order {
+                            SortOrder::Simple(o) => Some(o),
+                            SortOrder::Object { order, .. } => order.as_ref(),
+                        };
+                        sort.push(BucketSortField {
+                            path: path.clone(),
+                            order: if order.is_some_and(|o| o.eq_ignore_ascii_case("desc")) {
+                                SortDirection::Desc
+                            } else {
+                                SortDirection::Asc
+                            },
+                        });
+                    }
+                }
+            }
+        }
+        sort
+    }
+
+    fn translate_composite_sources(
+        agg_name: &str,
+        sources: &[HashMap<String, CompositeSourceAgg>],
+    ) -> Result<Vec<CompositeSource>, EsCompatError> {
+        let parse_order = |order: &Option<String>| match order.as_deref() {
+            Some(o) if o.eq_ignore_ascii_case("desc") => SortDirection::Desc,
+            _ => SortDirection::Asc,
+        };
+        let mut translated = Vec::with_capacity(sources.len());
+        for source in sources {
+            let (source_name, def) = match source.iter().next() {
+                Some(entry) if source.len() == 1 => entry,
+                _ => {
+                    return Err(EsCompatError::UnsupportedAggregation(format!(
+                        "Composite aggregation '{}': each source must have exactly one name",
+                        agg_name
+                    )))
+                }
+            };
+            let (source_type, order) = if let Some(terms) = &def.terms {
+                (
+                    CompositeSourceType::Terms {
+                        field: terms.field.clone(),
+                    },
+                    &terms.order,
+                )
+            } else if let Some(histogram) = &def.histogram {
+                (
+                    CompositeSourceType::Histogram {
+                        field: histogram.field.clone(),
+                        interval: histogram.interval,
+                    },
+                    &histogram.order,
+                )
+            } else if let Some(date_histogram) = &def.date_histogram {
+                (
+                    CompositeSourceType::DateHistogram {
+                        field: date_histogram.field.clone(),
+                        calendar_interval: date_histogram
+                            .calendar_interval
+                            .clone()
+                            .unwrap_or_else(|| "1d".to_string()),
+                    },
+                    &date_histogram.order,
+                )
+            } else {
+                return Err(EsCompatError::UnsupportedAggregation(format!(
+                    "Composite source '{}' must be terms, histogram or date_histogram",
+                    source_name
+                )));
+            };
+            translated.push(CompositeSource {
+                name: source_name.clone(),
+                source_type,
+                order: parse_order(order),
+            });
+        }
+        Ok(translated)
+    }
+
+    /// Fields to include for a `_source` filter (`None` keeps every field).
+    /// `excludes` lists are not supported and keep every field.
+    fn source_includes(source: &SourceFilter) -> Option<Vec<String>> {
+        match source {
+            SourceFilter::Bool(true) => None,
+            SourceFilter::Bool(false) => Some(vec![]),
+            SourceFilter::Fields(fields) => Some(fields.clone()),
+            SourceFilter::Object { includes, .. } => includes.clone(),
+        }
+    }
+}
+
+/// `terms` query on the expanded terms of a multi-term query
+fn terms_query(field: &str, terms: Vec<String>) -> EsQuery {
+    let values = terms.into_iter().map(Value::String).collect();
+    EsQuery::Terms(HashMap::from([(field.to_string(), values)]))
+}
+
+/// Error for a multi-term query that reached the translator unexpanded
+fn not_expanded(query_type: &str) -> EsCompatError {
+    EsCompatError::InvalidQuery(format!(
+        "[{}] terms must be expanded against the index before translation",
+        query_type
+    ))
 }
 
 fn escape_value(s: &str) -> String {
@@ -505,6 +1267,45 @@ fn escape_value(s: &str) -> String {
     }
 }
 
+/// Edit distance of a `fuzzy` query; `AUTO` allows none below 3
+/// characters, one up to 5 and two beyond
+fn edit_distance(value: &str, fuzziness: Option<&Value>) -> Result<u32, EsCompatError> {
+    let invalid = || EsCompatError::InvalidQuery(format!("invalid fuzziness [{:?}]", fuzziness));
+    let auto = |low: usize, high: usize| {
+        let len = value.chars().count();
+        if len < low {
+            0
+        } else if len < high {
+            1
+        } else {
+            2
+        }
+    };
+    match fuzziness {
+        None => Ok(auto(3, 6)),
+        Some(Value::Number(n)) => n
+            .as_u64()
+            .filter(|d| *d <= 2)
+            .map(|d| d as u32)
+            .ok_or_else(invalid),
+        Some(Value::String(s)) if s.eq_ignore_ascii_case("auto") => Ok(auto(3, 6)),
+        Some(Value::String(s)) => match s.to_ascii_uppercase().strip_prefix("AUTO:") {
+            Some(bounds) => {
+                let (low, high) = bounds.split_once(',').ok_or_else(invalid)?;
+                let low = low.trim().parse().map_err(|_| invalid())?;
+                let high = high.trim().parse().map_err(|_| invalid())?;
+                Ok(auto(low, high))
+            }
+            None => s
+                .parse::<u32>()
+                .ok()
+                .filter(|d| *d <= 2)
+                .ok_or_else(invalid),
+        },
+        Some(_) => Err(invalid()),
+    }
+}
+
 fn escape_phrase(s: &str) -> String {
     s.replace('\\', "\\\\").replace('"', "\\\"")
 }
@@ -537,6 +1338,9 @@ mod tests {
             value_count: None,
             cardinality: None,
             percentiles: None,
+            top_hits: None,
+            significant_terms: None,
+            composite: None,
             histogram: None,
             date_histogram: None,
             range: None,
@@ -544,7 +1348,13 @@ mod tests {
             filter: None,
             filters: None,
             global: None,
+            derivative: None,
+            cumulative_sum: None,
+            moving_fn: None,
+            bucket_sort: None,
+            bucket_selector: None,
             aggs: None,
+            other: HashMap::new(),
         }
     }
 
@@ -1279,6 +2089,299 @@ mod tests {
         assert!(result.starts_with('('));
     }
 
+    // ===================================================================
+    // Fuzzy, regexp and prefix-matching queries
+    // ===================================================================
+
+    fn translate_json(json: Value) -> Result<String, EsCompatError> {
+        let query: EsQuery = serde_json::from_value(json).unwrap();
+        QueryTranslator::translate_query(&query)
+    }
+
+    /// (field, pattern, max expansions) of a term dictionary lookup
+    type Lookup = (String, TermPattern, usize);
+
+    /// Expand the multi-term queries of a query, with `expand` returning
+    /// `terms` for every pattern, then translate it. Also returns the
+    /// patterns looked up.
+    fn expand_json(json: Value, terms: &[&str]) -> Result<(String, Vec<Lookup>), EsCompatError> {
+        let mut query: EsQuery = serde_json::from_value(json).unwrap();
+        let mut lookups = Vec::new();
+        QueryTranslator::expand_multi_term_queries(&mut query, &mut |field, pattern, max| {
+            lookups.push((field.to_string(), pattern.clone(), max));
+            Ok(terms.iter().map(|t| t.to_string()).collect())
+        })?;
+        Ok((QueryTranslator::translate_query(&query)?, lookups))
+    }
+
+    fn fuzzy(term: &str, distance: u8) -> TermPattern {
+        TermPattern::Fuzzy {
+            term: term.to_string(),
+            distance,
+            prefix_length: 0,
+            transpositions: true,
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_auto() {
+        let json = serde_json::json!({"fuzzy": {"user": "quikc"}});
+        let (query, lookups) = expand_json(json, &["quick", "quirk"]).unwrap();
+        assert_eq!(query, "(user:quick OR user:quirk)");
+        assert_eq!(lookups, vec![("user".to_string(), fuzzy("quikc", 1), 50)]);
+
+        let json = serde_json::json!({"fuzzy": {"user": {"value": "ki", "fuzziness": "AUTO"}}});
+        let (_, lookups) = expand_json(json, &[]).unwrap();
+        assert_eq!(lookups[0].1, fuzzy("ki", 0));
+
+        let json = serde_json::json!({"fuzzy": {"user": {"value": "kimchy", "fuzziness": "auto:2,4"}}});
+        let (_, lookups) = expand_json(json, &[]).unwrap();
+        assert_eq!(lookups[0].1, fuzzy("kimchy", 2));
+    }
+
+    #[test]
+    fn test_fuzzy_explicit_distance() {
+        let json = serde_json::json!({"fuzzy": {"user": {
+            "value": "kimchy",
+            "fuzziness": 1,
+            "prefix_length": 2,
+            "max_expansions": 10,
+            "transpositions": false
+        }}});
+        let (_, lookups) = expand_json(json, &[]).unwrap();
+        let pattern = TermPattern::Fuzzy {
+            term: "kimchy".to_string(),
+            distance: 1,
+            prefix_length: 2,
+            transpositions: false,
+        };
+        assert_eq!(lookups, vec![("user".to_string(), pattern, 10)]);
+
+        let json = serde_json::json!({"fuzzy": {"user": {"value": "kimchy", "fuzziness": "0"}}});
+        let (_, lookups) = expand_json(json, &[]).unwrap();
+        assert_eq!(lookups[0].1, fuzzy("kimchy", 0));
+
+        let json = serde_json::json!({"fuzzy": {"user": {"value": "kimchy", "fuzziness": 3}}});
+        assert!(expand_json(json, &[]).is_err());
+    }
+
+    #[test]
+    fn test_multi_term_queries_need_expansion() {
+        for json in [
+            serde_json::json!({"fuzzy": {"user": "quikc"}}),
+            serde_json::json!({"regexp": {"user": "k.*y"}}),
+            serde_json::json!({"match_phrase_prefix": {"message": "qui"}}),
+            serde_json::json!({"match_bool_prefix": {"message": "quick f"}}),
+        ] {
+            assert!(translate_json(json).is_err());
+        }
+    }
+
+    #[test]
+    fn test_expansion_without_terms_matches_nothing() {
+        let json = serde_json::json!({"regexp": {"user": "zz.*"}});
+        let (query, _) = expand_json(json, &[]).unwrap();
+        assert_eq!(query, MATCH_NONE);
+    }
+
+    #[test]
+    fn test_regexp() {
+        let json = serde_json::json!({"regexp": {"user": "k.*y"}});
+        let (query, lookups) = expand_json(json, &["kimchy"]).unwrap();
+        assert_eq!(query, "(user:kimchy)");
+        assert_eq!(
+            lookups,
+            vec![(
+                "user".to_string(),
+                TermPattern::Regex("k.*y".to_string()),
+                1024
+            )]
+        );
+
+        let json = serde_json::json!({"regexp": {"path": {"value": "a/b[0-9]+", "case_insensitive": true}}});
+        let (_, lookups) = expand_json(json, &[]).unwrap();
+        assert_eq!(
+            lookups[0].1,
+            TermPattern::Regex("(?i)a/b[0-9]+".to_string())
+        );
+    }
+
+    #[test]
+    fn test_prefix_and_wildcard_expansion() {
+        let json = serde_json::json!({"prefix": {"user": "ki"}});
+        let (query, lookups) = expand_json(json, &["kimchy", "kitty"]).unwrap();
+        assert_eq!(query, "(user:kimchy OR user:kitty)");
+        assert_eq!(lookups[0].1, TermPattern::Prefix("ki".to_string()));
+
+        let json = serde_json::json!({"wildcard": {"user": "ki*y"}});
+        let (_, lookups) = expand_json(json, &[]).unwrap();
+        assert_eq!(lookups[0].1, TermPattern::Wildcard("ki*y".to_string()));
+    }
+
+    #[test]
+    fn test_match_phrase_prefix() {
+        let json = serde_json::json!({"match_phrase_prefix": {"message": "quick brown f"}});
+        let (query, lookups) = expand_json(json, &[]).unwrap();
+        assert_eq!(query, "message:\"quick brown f\"*");
+        assert!(lookups.is_empty());
+
+        let json = serde_json::json!({"match_phrase_prefix": {"message": {"query": "Qui", "max_expansions": 10}}});
+        let (query, lookups) = expand_json(json, &["quick", "quiet"]).unwrap();
+        assert_eq!(query, "(message:quick OR message:quiet)");
+        assert_eq!(
+            lookups,
+            vec![(
+                "message".to_string(),
+                TermPattern::Prefix("qui".to_string()),
+                10
+            )]
+        );
+    }
+
+    #[test]
+    fn test_match_bool_prefix() {
+        let json = serde_json::json!({"match_bool_prefix": {"message": "quick brown f"}});
+        let (query, lookups) = expand_json(json, &["fox"]).unwrap();
+        assert_eq!(query, "(message:quick OR message:brown OR (message:fox))");
+        assert_eq!(lookups[0].1, TermPattern::Prefix("f".to_string()));
+
+        let json = serde_json::json!({"match_bool_prefix": {"message": {"query": "quick f", "operator": "and"}}});
+        let (query, _) = expand_json(json, &["fox"]).unwrap();
+        assert_eq!(query, "(message:quick) AND ((message:fox))");
+
+        let json = serde_json::json!({"match_bool_prefix": {"message": "  "}});
+        assert!(expand_json(json, &[]).is_err());
+    }
+
+    #[test]
+    fn test_expansion_inside_compound_queries() {
+        let json = serde_json::json!({"bool": {
+            "must": [{"term": {"tag": "rust"}}],
+            "should": [{"fuzzy": {"title": "serch"}}]
+        }});
+        let (query, _) = expand_json(json, &["search"]).unwrap();
+        assert_eq!(query, "(tag:rust) AND ((title:search))");
+    }
+
+    // ===================================================================
+    // Compound queries
+    // ===================================================================
+
+    #[test]
+    fn test_constant_score() {
+        let json = serde_json::json!({"constant_score": {"filter": {"term": {"user": "kimchy"}}}});
+        assert_eq!(translate_json(json).unwrap(), "user:kimchy");
+
+        let json = serde_json::json!({"constant_score": {"filter": {"term": {"user": "kimchy"}}, "boost": 1.5}});
+        assert_eq!(translate_json(json).unwrap(), "(user:kimchy)^1.5");
+    }
+
+    #[test]
+    fn test_dis_max() {
+        let json = serde_json::json!({"dis_max": {
+            "queries": [{"term": {"title": "quick"}}, {"match": {"body": "fox"}}],
+            "tie_breaker": 0.7
+        }});
+        assert_eq!(translate_json(json).unwrap(), "(title:quick) OR (body:fox)");
+
+        let json = serde_json::json!({"dis_max": {"queries": []}});
+        assert!(translate_json(json).is_err());
+    }
+
+    #[test]
+    fn test_boosting_searches_positive_query() {
+        let request: EsSearchRequest = serde_json::from_value(serde_json::json!({
+            "query": {"boosting": {
+                "positive": {"term": {"text": "apple"}},
+                "negative": {"term": {"text": "pie"}},
+                "negative_boost": 0.5
+            }}
+        }))
+        .unwrap();
+        let (query, _) = QueryTranslator::translate(&request, &[]).unwrap();
+        assert_eq!(query.query_string, "text:apple");
+
+        // Nested boosting queries cannot demote their negative matches
+        let json = serde_json::json!({"bool": {"must": [{"boosting": {
+            "positive": {"term": {"text": "apple"}},
+            "negative": {"term": {"text": "pie"}},
+            "negative_boost": 0.5
+        }}]}});
+        assert!(matches!(
+            translate_json(json),
+            Err(EsCompatError::UnsupportedQueryType(_))
+        ));
+    }
+
+    #[test]
+    fn test_nested() {
+        let json = serde_json::json!({"nested": {
+            "path": "comments",
+            "query": {"bool": {"must": [{"match": {"comments.author": "alice"}}]}},
+            "score_mode": "avg"
+        }});
+        assert_eq!(translate_json(json).unwrap(), "(comments.author:alice)");
+    }
+
+    // ===================================================================
+    // more_like_this
+    // ===================================================================
+
+    #[test]
+    fn test_more_like_this_text_without_lookup() {
+        let json = serde_json::json!({"more_like_this": {
+            "fields": ["title", "body"],
+            "like": "Rust search Engine rust",
+            "max_query_terms": 2
+        }});
+        assert_eq!(
+            translate_json(json).unwrap(),
+            "(title:(rust search) OR body:(rust search))"
+        );
+    }
+
+    #[test]
+    fn test_more_like_this_documents_need_lookup() {
+        let json = serde_json::json!({"more_like_this": {
+            "like": [{"_index": "docs", "_id": "1"}, "some text"]
+        }});
+        let err = translate_json(json).unwrap_err();
+        assert!(matches!(err, EsCompatError::InvalidQuery(_)));
+    }
+
+    #[test]
+    fn test_more_like_this_resolved_terms() {
+        let json = serde_json::json!({"more_like_this": {
+            "fields": ["body"],
+            "like": [{"_id": "1"}, {"_id": "2"}]
+        }});
+        let mut query: EsQuery = serde_json::from_value(json).unwrap();
+        let EsQuery::MoreLikeThis(mlt) = &mut query else {
+            panic!("Expected MoreLikeThis");
+        };
+        mlt.resolved_terms = Some(vec!["tantivy".to_string(), "index".to_string()]);
+        assert_eq!(
+            QueryTranslator::translate_query(&query).unwrap(),
+            "(body:(tantivy index)) AND -id:1 AND -id:2"
+        );
+
+        let EsQuery::MoreLikeThis(mlt) = &mut query else {
+            unreachable!()
+        };
+        mlt.include = true;
+        assert_eq!(
+            QueryTranslator::translate_query(&query).unwrap(),
+            "(body:(tantivy index))"
+        );
+
+        // No significant terms matches nothing
+        let EsQuery::MoreLikeThis(mlt) = &mut query else {
+            unreachable!()
+        };
+        mlt.resolved_terms = Some(vec![]);
+        assert_eq!(QueryTranslator::translate_query(&query).unwrap(), "id:\"\"");
+    }
+
     // ===================================================================
     // Serde round-trip (JSON -> EsQuery -> translate)
     // ===================================================================
@@ -1836,6 +2939,229 @@ mod tests {
         assert!(matches!(result[0].agg_type, AggregationType::Global {}));
     }
 
+    #[test]
+    fn test_agg_cardinality() {
+        let mut aggs = HashMap::new();
+        let mut a = empty_agg();
+        a.cardinality = Some(CardinalityAgg {
+            field: "user_id".to_string(),
+            precision_threshold: Some(100),
+            missing: None,
+        });
+        aggs.insert("unique_users".to_string(), a);
+
+        let result = QueryTranslator::translate_aggregations(&aggs).unwrap();
+        match &result[0].agg_type {
+            AggregationType::Cardinality {
+                field,
+                precision_threshold,
+                ..
+            } => {
+                assert_eq!(field, "user_id");
+                assert_eq!(*precision_threshold, Some(100));
+            }
+            _ => panic!("Expected cardinality aggregation"),
+        }
+    }
+
+    #[test]
+    fn test_agg_top_hits() {
+        let agg: EsAggregation = serde_json::from_value(serde_json::json!({
+            "top_hits": {
+                "size": 2,
+                "_source": { "includes": ["title"] },
+                "sort": ["_score", { "price": { "order": "desc" } }, "rank"]
+            }
+        }))
+        .unwrap();
+        let aggs = HashMap::from([("best".to_string(), agg)]);
+
+        let result = QueryTranslator::translate_aggregations(&aggs).unwrap();
+        match &result[0].agg_type {
+            AggregationType::TopHits {
+                size, sort, source, ..
+            } => {
+                assert_eq!(*size, Some(2));
+                assert_eq!(source.as_deref(), Some(&["title".to_string()][..]));
+                let sort: Vec<_> = sort.iter().map(|s| (s.field.as_str(), s.order)).collect();
+                assert_eq!(
+                    sort,
+                    vec![
+                        ("_score", SortDirection::Desc),
+                        ("price", SortDirection::Desc),
+                        ("rank", SortDirection::Asc),
+                    ]
+                );
+            }
+            _ => panic!("Expected top_hits aggregation"),
+        }
+    }
+
+    #[test]
+    fn test_agg_significant_terms() {
+        let agg: EsAggregation = serde_json::from_value(serde_json::json!({
+            "significant_terms": {
+                "field": "tags",
+                "min_doc_count": 1,
+                "background_filter": { "term": { "status": "active" } }
+            }
+        }))
+        .unwrap();
+        let aggs = HashMap::from([("unusual".to_string(), agg)]);
+
+        let result = QueryTranslator::translate_aggregations(&aggs).unwrap();
+        match &result[0].agg_type {
+            AggregationType::SignificantTerms {
+                field,
+                min_doc_count,
+                background_filter,
+                ..
+            } => {
+                assert_eq!(field, "tags");
+                assert_eq!(*min_doc_count, Some(1));
+                assert_eq!(background_filter.as_deref(), Some("status:active"));
+            }
+            _ => panic!("Expected significant_terms aggregation"),
+        }
+    }
+
+    #[test]
+    fn test_agg_composite() {
+        let agg: EsAggregation = serde_json::from_value(serde_json::json!({
+            "composite": {
+                "size": 100,
+                "sources": [
+                    { "customer": { "terms": { "field": "customer_id" } } },
+                    { "day": { "date_histogram": { "field": "ts", "calendar_interval": "1d", "order": "desc" } } }
+                ],
+                "after": { "customer": "c-17", "day": "2025-01-02T00:00:00+00:00" }
+            }
+        }))
+        .unwrap();
+        let aggs = HashMap::from([("rollup".to_string(), agg)]);
+
+        let result = QueryTranslator::translate_aggregations(&aggs).unwrap();
+        match &result[0].agg_type {
+            AggregationType::Composite {
+                sources,
+                size,
+                after,
+            } => {
+                assert_eq!(*size, Some(100));
+                assert_eq!(sources.len(), 2);
+                assert_eq!(sources[0].name, "customer");
+                assert!(matches!(
+                    &sources[0].source_type,
+                    CompositeSourceType::Terms { field } if field == "customer_id"
+                ));
+                assert_eq!(sources[0].order, SortDirection::Asc);
+                assert_eq!(sources[1].name, "day");
+                assert_eq!(sources[1].order, SortDirection::Desc);
+                assert_eq!(after.as_ref().unwrap()["customer"], "c-17");
+            }
+            _ => panic!("Expected composite aggregation"),
+        }
+    }
+
+    #[test]
+    fn test_agg_composite_rejects_unknown_source() {
+        let agg: EsAggregation = serde_json::from_value(serde_json::json!({
+            "composite": { "sources": [ { "geo": { "geotile_grid": { "field": "loc" } } } ] }
+        }))
+        .unwrap();
+        let aggs = HashMap::from([("tiles".to_string(), agg)]);
+
+        assert!(QueryTranslator::translate_aggregations(&aggs).is_err());
+    }
+
+    #[test]
+    fn test_agg_pipelines() {
+        let agg: EsAggregation = serde_json::from_value(serde_json::json!({
+            "date_histogram": { "field": "ts", "calendar_interval": "month" },
+            "aggs": {
+                "sales": { "sum": { "field": "price" } },
+                "top_months": {
+                    "bucket_sort": { "sort": [ { "sales": { "order": "desc" } } ], "size": 3 }
+                },
+                "sales_deriv": { "derivative": { "buckets_path": "sales", "gap_policy": "insert_zeros" } },
+                "big_months": {
+                    "bucket_selector": {
+                        "buckets_path": { "total": "sales" },
+                        "script": { "source": "params.total > params.min", "params": { "min": 200 } }
+                    }
+                },
+                "sales_avg": {
+                    "moving_fn": {
+                        "buckets_path": "sales",
+                        "window": 3,
+                        "script": "MovingFunctions.linearWeightedAvg(values)"
+                    }
+                },
+                "running": { "cumulative_sum": { "buckets_path": "sales" } }
+            }
+        }))
+        .unwrap();
+        let aggs = HashMap::from([("per_month".to_string(), agg)]);
+
+        let result = QueryTranslator::translate_aggregations(&aggs).unwrap();
+        let subs = result[0].aggs.as_ref().unwrap();
+        assert_eq!(subs.len(), 6);
+        // Metrics first, reordering/filtering pipelines last
+        assert_eq!(subs[0].name, "sales");
+        assert!(matches!(
+            subs[4].agg_type,
+            AggregationType::BucketSort { .. } | AggregationType::BucketSelector { .. }
+        ));
+        for sub in subs {
+            match (sub.name.as_str(), &sub.agg_type) {
+                (
+                    "sales_deriv",
+                    AggregationType::Derivative {
+                        buckets_path,
+                        gap_policy,
+                    },
+                ) => {
+                    assert_eq!(buckets_path, "sales");
+                    assert_eq!(*gap_policy, GapPolicy::InsertZeros);
+                }
+                (
+                    "sales_avg",
+                    AggregationType::MovingFn {
+                        window, function, ..
+                    },
+                ) => {
+                    assert_eq!(*window, 3);
+                    assert_eq!(*function, MovingFunction::LinearWeightedAvg);
+                }
+                ("top_months", AggregationType::BucketSort { sort, size, .. }) => {
+                    assert_eq!(sort[0].path, "sales");
+                    assert_eq!(sort[0].order, SortDirection::Desc);
+                    assert_eq!(*size, Some(3));
+                }
+                ("big_months", AggregationType::BucketSelector { script, .. }) => {
+                    assert_eq!(script, "total > 200");
+                }
+                ("running", AggregationType::CumulativeSum { .. }) | ("sales", _) => {}
+                (name, agg_type) => panic!("unexpected {}: {:?}", name, agg_type),
+            }
+        }
+    }
+
+    #[test]
+    fn test_agg_moving_fn_rejects_custom_script() {
+        let agg: EsAggregation = serde_json::from_value(serde_json::json!({
+            "moving_fn": {
+                "buckets_path": "sales",
+                "window": 2,
+                "script": "return values.length > 0 ? values[0] : 0"
+            }
+        }))
+        .unwrap();
+        let aggs = HashMap::from([("custom".to_string(), agg)]);
+
+        assert!(QueryTranslator::translate_aggregations(&aggs).is_err());
+    }
+
     #[test]
     fn test_agg_empty() {
         let aggs = HashMap::new();
@@ -1872,6 +3198,29 @@ mod tests {
         assert!(result.is_empty());
     }
 
+    #[test]
+    fn test_agg_rejects_unexecuted_types() {
+        for json in [
+            serde_json::json!({ "geo_bounds": { "field": "location" } }),
+            serde_json::json!({ "date_range": { "field": "ts", "ranges": [{ "to": "now" }] } }),
+        ] {
+            let agg: EsAggregation = serde_json::from_value(json).unwrap();
+            let aggs = HashMap::from([("area".to_string(), agg)]);
+            assert!(matches!(
+                QueryTranslator::translate_aggregations(&aggs),
+                Err(EsCompatError::UnsupportedAggregation(_))
+            ));
+        }
+
+        // `meta` is allowed next to a type
+        let agg: EsAggregation = serde_json::from_value(serde_json::json!({
+            "avg": { "field": "price" }, "meta": { "owner": "lens" }
+        }))
+        .unwrap();
+        let aggs = HashMap::from([("avg_price".to_string(), agg)]);
+        assert_eq!(QueryTranslator::translate_aggregations(&aggs).unwrap().len(), 1);
+    }
+
     #[test]
     fn test_agg_with_sub_aggs() {
         let mut aggs = HashMap::new();
@@ -1910,10 +3259,17 @@ mod tests {
             from: None,
             size: None,
             source: None,
+            fields: None,
+            docvalue_fields: None,
             aggs: None,
             sort: None,
             highlight: None,
             track_total_hits: None,
+            track_scores: None,
+            pit: None,
+            search_after: None,
+            knn: None,
+            rank: None,
         };
         let (query, aggs) = QueryTranslator::translate(&request, &["title".to_string()]).unwrap();
         assert_eq!(query.query_string, "*");
@@ -1930,10 +3286,17 @@ mod tests {
             from: Some(20),
             size: Some(50),
             source: None,
+            fields: None,
+            docvalue_fields: None,
             aggs: None,
             sort: None,
             highlight: None,
             track_total_hits: None,
+            track_scores: None,
+            pit: None,
+            search_after: None,
+            knn: None,
+            rank: None,
         };
         let (query, _) = QueryTranslator::translate(&request, &[]).unwrap();
         assert_eq!(query.offset, 20);
@@ -1949,6 +3312,8 @@ mod tests {
             from: None,
             size: None,
             source: None,
+            fields: None,
+            docvalue_fields: None,
             aggs: None,
             sort: None,
             highlight: Some(EsHighlight {
@@ -1959,6 +3324,11 @@ mod tests {
                 number_of_fragments: Some(5),
             }),
             track_total_hits: None,
+            track_scores: None,
+            pit: None,
+            search_after: None,
+            knn: None,
+            rank: None,
         };
         let (query, _) = QueryTranslator::translate(&request, &[]).unwrap();
         let hl = query.highlight.unwrap();
@@ -1978,6 +3348,8 @@ mod tests {
             from: None,
             size: None,
             source: None,
+            fields: None,
+            docvalue_fields: None,
             aggs: None,
             sort: None,
             highlight: Some(EsHighlight {
@@ -1988,6 +3360,11 @@ mod tests {
                 number_of_fragments: None,
             }),
             track_total_hits: None,
+            track_scores: None,
+            pit: None,
+            search_after: None,
+            knn: None,
+            rank: None,
         };
         let (query, _) = QueryTranslator::translate(&request, &[]).unwrap();
         let hl = query.highlight.unwrap();
@@ -2014,10 +3391,17 @@ mod tests {
             from: Some(0),
             size: Some(5),
             source: None,
+            fields: None,
+            docvalue_fields: None,
             aggs: Some(agg_map),
             sort: None,
             highlight: None,
             track_total_hits: None,
+            track_scores: None,
+            pit: None,
+            search_after: None,
+            knn: None,
+            rank: None,
         };
         let (query, aggs) = QueryTranslator::translate(&request, &[]).unwrap();
         assert_eq!(query.query_string, "*");
@@ -2,12 +2,13 @@
 //!
 //! These types represent the subset of ES Query DSL that Prism supports.
 
+use prism::schema::es_mapping::EsMappings;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
 /// Root ES search request body
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct EsSearchRequest {
     /// The query to execute
     #[serde(default)]
@@ -25,6 +26,14 @@ pub struct EsSearchRequest {
     #[serde(default, rename = "_source")]
     pub source: Option<SourceFilter>,
 
+    /// Fields returned as value arrays under each hit's `fields`
+    #[serde(default)]
+    pub fields: Option<Vec<FieldAndFormat>>,
+
+    /// Same as `fields`; values are read from the stored document
+    #[serde(default)]
+    pub docvalue_fields: Option<Vec<FieldAndFormat>>,
+
     /// Aggregations
     #[serde(default, alias = "aggregations")]
     pub aggs: Option<HashMap<String, EsAggregation>>,
@@ -40,6 +49,104 @@ pub struct EsSearchRequest {
     /// Track total hits exactly
     #[serde(default)]
     pub track_total_hits: Option<TrackTotalHits>,
+
+    /// Compute scores when sorting on fields
+    #[serde(default)]
+    pub track_scores: Option<bool>,
+
+    /// Search a point in time opened with `_pit`
+    #[serde(default)]
+    pub pit: Option<EsPit>,
+
+    /// Sort values of the last hit of the previous page
+    #[serde(default)]
+    pub search_after: Option<Vec<Value>>,
+
+    /// Approximate nearest-neighbour search on a dense_vector field
+    #[serde(default)]
+    pub knn: Option<KnnSection>,
+
+    /// How `query` and `knn` hits are combined
+    #[serde(default)]
+    pub rank: Option<EsRank>,
+}
+
+/// One or several `knn` searches
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum KnnSection {
+    Single(Box<EsKnnQuery>),
+    Multiple(Vec<EsKnnQuery>),
+}
+
+impl KnnSection {
+    pub fn iter(&self) -> impl Iterator<Item = &EsKnnQuery> {
+        let slice: &[EsKnnQuery] = match self {
+            KnnSection::Single(q) => std::slice::from_ref(q.as_ref()),
+            KnnSection::Multiple(v) => v.as_slice(),
+        };
+        slice.iter()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EsKnnQuery {
+    pub field: String,
+    #[serde(default)]
+    pub query_vector: Option<Vec<f32>>,
+    /// Embeds text with the collection's embedding provider
+    #[serde(default)]
+    pub query_vector_builder: Option<QueryVectorBuilder>,
+    /// Number of hits to return (defaults to `size`)
+    #[serde(default)]
+    pub k: Option<usize>,
+    /// Neighbours retrieved before filtering
+    #[serde(default)]
+    pub num_candidates: Option<usize>,
+    #[serde(default)]
+    pub filter: Option<QueryList>,
+    /// Minimum score for a hit to be kept
+    #[serde(default)]
+    pub similarity: Option<f32>,
+    #[serde(default)]
+    pub boost: Option<f32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QueryVectorBuilder {
+    pub text_embedding: TextEmbeddingBuilder,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TextEmbeddingBuilder {
+    /// Ignored; Prism embeds with the collection's configured provider
+    #[serde(default)]
+    pub model_id: Option<String>,
+    pub model_text: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EsRank {
+    #[serde(default)]
+    pub rrf: Option<RrfParams>,
+}
+
+/// Reciprocal rank fusion settings
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RrfParams {
+    #[serde(default)]
+    pub rank_constant: Option<usize>,
+    /// Hits taken from each result list before fusing
+    #[serde(default, alias = "window_size")]
+    pub rank_window_size: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EsPit {
+    pub id: String,
+    /// Extends the point in time's expiry, e.g. `1m`
+    #[serde(default)]
+    pub keep_alive: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -60,6 +167,27 @@ pub enum SourceFilter {
     },
 }
 
+/// A `fields` / `docvalue_fields` entry: a field name or pattern, with an
+/// optional (ignored) format
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum FieldAndFormat {
+    Name(String),
+    Object {
+        field: String,
+        #[serde(default)]
+        format: Option<String>,
+    },
+}
+
+impl FieldAndFormat {
+    pub fn field(&self) -> &str {
+        match self {
+            FieldAndFormat::Name(field) | FieldAndFormat::Object { field, .. } => field,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum SortClause {
@@ -71,7 +199,16 @@ pub enum SortClause {
 #[serde(untagged)]
 pub enum SortOrder {
     Simple(String),
-    Object { order: String },
+    Object {
+        #[serde(default)]
+        order: Option<String>,
+        /// `_last` (default), `_first` or a substitute value
+        #[serde(default)]
+        missing: Option<Value>,
+        /// Type assumed for indices where the field is not mapped
+        #[serde(default)]
+        unmapped_type: Option<String>,
+    },
 }
 
 /// ES Query types
@@ -119,6 +256,71 @@ pub enum EsQuery {
 
     /// IDs query
     Ids(IdsQuery),
+
+    /// Fuzzy term query
+    Fuzzy(HashMap<String, FuzzyParams>),
+
+    /// Regular expression query
+    Regexp(HashMap<String, RegexpParams>),
+
+    /// Phrase query whose last term is a prefix
+    MatchPhrasePrefix(HashMap<String, MatchPhrasePrefixQuery>),
+
+    /// Match query whose last term is a prefix
+    MatchBoolPrefix(HashMap<String, MatchBoolPrefixQuery>),
+
+    /// Filter with a constant score
+    ConstantScore(ConstantScoreQuery),
+
+    /// Disjunction of queries
+    DisMax(DisMaxQuery),
+
+    /// Positive query with demoted negative matches
+    Boosting(BoostingQuery),
+
+    /// Query on nested object fields
+    Nested(NestedQuery),
+
+    /// Documents similar to given texts or documents
+    MoreLikeThis(Box<MoreLikeThisQuery>),
+
+    /// Rescore a query's hits with weights, field values and decay functions
+    FunctionScore(Box<FunctionScoreQuery>),
+
+    /// Rescore a query's hits with a script expression
+    ScriptScore(ScriptScoreQuery),
+}
+
+impl EsQuery {
+    /// Queries nested directly inside this one
+    pub fn children_mut(&mut self) -> Vec<&mut EsQuery> {
+        match self {
+            EsQuery::Bool(b) => [&mut b.must, &mut b.should, &mut b.must_not, &mut b.filter]
+                .into_iter()
+                .flatten()
+                .flat_map(QueryList::iter_mut)
+                .collect(),
+            EsQuery::ConstantScore(cs) => vec![cs.filter.as_mut()],
+            EsQuery::DisMax(dm) => dm.queries.iter_mut().collect(),
+            EsQuery::Boosting(b) => vec![b.positive.as_mut(), b.negative.as_mut()],
+            EsQuery::Nested(n) => vec![n.query.as_mut()],
+            EsQuery::FunctionScore(fs) => {
+                let fs = fs.as_mut();
+                fs.query
+                    .as_deref_mut()
+                    .into_iter()
+                    .chain(fs.function.filter.as_deref_mut())
+                    .chain(
+                        fs.functions
+                            .iter_mut()
+                            .filter_map(|f| f.filter.as_deref_mut()),
+                    )
+                    .collect()
+            }
+            EsQuery::ScriptScore(ss) => vec![ss.query.as_mut()],
+            _ => vec![],
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -230,6 +432,14 @@ impl QueryList {
         };
         slice.iter()
     }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut EsQuery> {
+        let slice: &mut [EsQuery] = match self {
+            QueryList::Single(q) => std::slice::from_mut(q.as_mut()),
+            QueryList::Multiple(v) => v.as_mut_slice(),
+        };
+        slice.iter_mut()
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -295,6 +505,271 @@ pub struct IdsQuery {
     pub values: Vec<String>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum FuzzyParams {
+    // Object is listed first: any JSON object is also a valid `Value`
+    Object {
+        value: Value,
+        /// `AUTO`, `AUTO:low,high` or an edit distance of 0, 1 or 2
+        #[serde(default)]
+        fuzziness: Option<Value>,
+        #[serde(default)]
+        prefix_length: Option<u32>,
+        #[serde(default)]
+        max_expansions: Option<u32>,
+        #[serde(default)]
+        transpositions: Option<bool>,
+        #[serde(default)]
+        boost: Option<f32>,
+    },
+    Simple(Value),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum RegexpParams {
+    Simple(String),
+    Object {
+        value: String,
+        #[serde(default)]
+        flags: Option<String>,
+        #[serde(default)]
+        case_insensitive: Option<bool>,
+        #[serde(default)]
+        max_determinized_states: Option<u32>,
+        #[serde(default)]
+        boost: Option<f32>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum MatchPhrasePrefixQuery {
+    Simple(String),
+    Object {
+        query: String,
+        #[serde(default)]
+        slop: Option<u32>,
+        #[serde(default)]
+        max_expansions: Option<u32>,
+        #[serde(default)]
+        boost: Option<f32>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum MatchBoolPrefixQuery {
+    Simple(String),
+    Object {
+        query: String,
+        /// `OR` (default) or `AND`
+        #[serde(default)]
+        operator: Option<String>,
+        #[serde(default)]
+        minimum_should_match: Option<MinimumShouldMatch>,
+        #[serde(default)]
+        boost: Option<f32>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConstantScoreQuery {
+    pub filter: Box<EsQuery>,
+    #[serde(default)]
+    pub boost: Option<f32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DisMaxQuery {
+    pub queries: Vec<EsQuery>,
+    #[serde(default)]
+    pub tie_breaker: Option<f32>,
+    #[serde(default)]
+    pub boost: Option<f32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BoostingQuery {
+    pub positive: Box<EsQuery>,
+    pub negative: Box<EsQuery>,
+    pub negative_boost: f32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NestedQuery {
+    pub path: String,
+    pub query: Box<EsQuery>,
+    #[serde(default)]
+    pub score_mode: Option<String>,
+    #[serde(default)]
+    pub ignore_unmapped: Option<bool>,
+    #[serde(default)]
+    pub inner_hits: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MoreLikeThisQuery {
+    /// Fields to take terms from and search; all text fields if unset
+    #[serde(default)]
+    pub fields: Vec<String>,
+    pub like: MltLikeList,
+    #[serde(default = "default_mlt_min_term_freq")]
+    pub min_term_freq: usize,
+    #[serde(default = "default_mlt_min_doc_freq")]
+    pub min_doc_freq: u64,
+    #[serde(default = "default_mlt_max_query_terms")]
+    pub max_query_terms: usize,
+    /// Whether the `like` documents themselves may match
+    #[serde(default)]
+    pub include: bool,
+    #[serde(default)]
+    pub minimum_should_match: Option<MinimumShouldMatch>,
+    #[serde(default)]
+    pub boost: Option<f32>,
+    /// Significant terms of the `like` texts and documents, looked up in
+    /// the index before translation
+    #[serde(skip)]
+    pub resolved_terms: Option<Vec<String>>,
+}
+
+fn default_mlt_min_term_freq() -> usize {
+    2
+}
+
+fn default_mlt_min_doc_freq() -> u64 {
+    5
+}
+
+fn default_mlt_max_query_terms() -> usize {
+    25
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum MltLikeList {
+    Single(MltLike),
+    Multiple(Vec<MltLike>),
+}
+
+impl MltLikeList {
+    pub fn iter(&self) -> impl Iterator<Item = &MltLike> {
+        let slice: &[MltLike] = match self {
+            MltLikeList::Single(like) => std::slice::from_ref(like),
+            MltLikeList::Multiple(v) => v.as_slice(),
+        };
+        slice.iter()
+    }
+}
+
+/// Free text or a reference to an indexed document
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum MltLike {
+    Text(String),
+    Document {
+        #[serde(rename = "_index", default)]
+        index: Option<String>,
+        #[serde(rename = "_id")]
+        id: String,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FunctionScoreQuery {
+    #[serde(default)]
+    pub query: Option<Box<EsQuery>>,
+    #[serde(default)]
+    pub functions: Vec<ScoreFunction>,
+    /// A single function may be given inline instead of in `functions`
+    #[serde(flatten)]
+    pub function: ScoreFunction,
+    /// How function scores combine: multiply, sum, avg, first, max, min
+    #[serde(default)]
+    pub score_mode: Option<String>,
+    /// How the function score combines with the query score: multiply,
+    /// replace, sum, avg, max, min
+    #[serde(default)]
+    pub boost_mode: Option<String>,
+    #[serde(default)]
+    pub max_boost: Option<f32>,
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    #[serde(default)]
+    pub boost: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ScoreFunction {
+    /// Only documents matching the filter are scored by this function
+    #[serde(default)]
+    pub filter: Option<Box<EsQuery>>,
+    #[serde(default)]
+    pub weight: Option<f32>,
+    #[serde(default)]
+    pub field_value_factor: Option<FieldValueFactor>,
+    #[serde(default)]
+    pub gauss: Option<DecayParams>,
+    #[serde(default)]
+    pub exp: Option<DecayParams>,
+    #[serde(default)]
+    pub linear: Option<DecayParams>,
+    #[serde(default)]
+    pub script_score: Option<ScriptScoreFunction>,
+    #[serde(default)]
+    pub random_score: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FieldValueFactor {
+    pub field: String,
+    #[serde(default)]
+    pub factor: Option<f32>,
+    /// none, log, log1p, log2p, ln, ln1p, ln2p, square, sqrt, reciprocal
+    #[serde(default)]
+    pub modifier: Option<String>,
+    #[serde(default)]
+    pub missing: Option<f64>,
+}
+
+/// Decay function keyed by field name, plus an optional `multi_value_mode`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DecayParams {
+    #[serde(default)]
+    pub multi_value_mode: Option<String>,
+    #[serde(flatten)]
+    pub fields: HashMap<String, DecayFieldParams>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DecayFieldParams {
+    /// Number, date or `now`; dates default to now
+    #[serde(default)]
+    pub origin: Option<Value>,
+    /// Distance at which the score drops to `decay`; a duration for dates
+    pub scale: Value,
+    #[serde(default)]
+    pub offset: Option<Value>,
+    #[serde(default)]
+    pub decay: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ScriptScoreFunction {
+    pub script: EsScript,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ScriptScoreQuery {
+    pub query: Box<EsQuery>,
+    pub script: EsScript,
+    #[serde(default)]
+    pub min_score: Option<f32>,
+    #[serde(default)]
+    pub boost: Option<f32>,
+}
+
 /// ES Highlight configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct EsHighlight {
@@ -339,14 +814,20 @@ pub struct EsAggregation {
     #[serde(default)]
     pub value_count: Option<FieldAgg>,
     #[serde(default)]
-    pub cardinality: Option<FieldAgg>,
+    pub cardinality: Option<CardinalityAgg>,
     #[serde(default)]
     pub percentiles: Option<PercentilesAgg>,
+    #[serde(default)]
+    pub top_hits: Option<TopHitsAgg>,
 
     // Bucket aggregations
     #[serde(default)]
     pub terms: Option<TermsAgg>,
     #[serde(default)]
+    pub significant_terms: Option<SignificantTermsAgg>,
+    #[serde(default)]
+    pub composite: Option<CompositeAgg>,
+    #[serde(default)]
     pub histogram: Option<HistogramAgg>,
     #[serde(default)]
     pub date_histogram: Option<DateHistogramAgg>,
@@ -361,9 +842,25 @@ pub struct EsAggregation {
     #[serde(default)]
     pub global: Option<GlobalAgg>,
 
+    // Pipeline aggregations
+    #[serde(default)]
+    pub derivative: Option<BucketsPathAgg>,
+    #[serde(default)]
+    pub cumulative_sum: Option<BucketsPathAgg>,
+    #[serde(default)]
+    pub moving_fn: Option<MovingFnAgg>,
+    #[serde(default)]
+    pub bucket_sort: Option<BucketSortAgg>,
+    #[serde(default)]
+    pub bucket_selector: Option<BucketSelectorAgg>,
+
     // Nested aggregations
     #[serde(default, alias = "aggregations")]
     pub aggs: Option<HashMap<String, EsAggregation>>,
+
+    /// Keys that are not a known aggregation type, e.g. `geo_bounds`
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -373,6 +870,84 @@ pub struct FieldAgg {
     pub missing: Option<Value>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CardinalityAgg {
+    pub field: String,
+    #[serde(default)]
+    pub precision_threshold: Option<u64>,
+    #[serde(default)]
+    pub missing: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TopHitsAgg {
+    #[serde(default)]
+    pub size: Option<usize>,
+    #[serde(default)]
+    pub from: Option<usize>,
+    #[serde(default, rename = "_source")]
+    pub source: Option<SourceFilter>,
+    #[serde(default)]
+    pub sort: Option<Vec<SortClause>>,
+}
+
+/// `derivative` / `cumulative_sum` pipeline
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BucketsPathAgg {
+    pub buckets_path: String,
+    #[serde(default)]
+    pub gap_policy: Option<String>,
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MovingFnAgg {
+    pub buckets_path: String,
+    pub window: usize,
+    /// e.g. `MovingFunctions.unweightedAvg(values)`
+    pub script: EsScript,
+    #[serde(default)]
+    pub shift: Option<i64>,
+    #[serde(default)]
+    pub gap_policy: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BucketSortAgg {
+    #[serde(default)]
+    pub sort: Option<Vec<SortClause>>,
+    #[serde(default)]
+    pub from: Option<usize>,
+    #[serde(default)]
+    pub size: Option<usize>,
+    #[serde(default)]
+    pub gap_policy: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BucketSelectorAgg {
+    pub buckets_path: HashMap<String, String>,
+    pub script: EsScript,
+    #[serde(default)]
+    pub gap_policy: Option<String>,
+}
+
+/// Inline script, either a bare source string or `{"source", "params"}`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum EsScript {
+    Source(String),
+    Object {
+        source: String,
+        /// Only `painless` is accepted
+        #[serde(default)]
+        lang: Option<String>,
+        #[serde(default)]
+        params: HashMap<String, Value>,
+    },
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PercentilesAgg {
     pub field: String,
@@ -391,6 +966,61 @@ pub struct TermsAgg {
     pub min_doc_count: Option<u64>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SignificantTermsAgg {
+    pub field: String,
+    #[serde(default)]
+    pub size: Option<usize>,
+    #[serde(default)]
+    pub min_doc_count: Option<u64>,
+    #[serde(default)]
+    pub background_filter: Option<Box<EsQuery>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CompositeAgg {
+    /// Ordered list of single-entry maps: source name -> source definition
+    pub sources: Vec<HashMap<String, CompositeSourceAgg>>,
+    #[serde(default)]
+    pub size: Option<usize>,
+    #[serde(default)]
+    pub after: Option<HashMap<String, Value>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CompositeSourceAgg {
+    #[serde(default)]
+    pub terms: Option<CompositeTermsSource>,
+    #[serde(default)]
+    pub histogram: Option<CompositeHistogramSource>,
+    #[serde(default)]
+    pub date_histogram: Option<CompositeDateHistogramSource>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CompositeTermsSource {
+    pub field: String,
+    #[serde(default)]
+    pub order: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CompositeHistogramSource {
+    pub field: String,
+    pub interval: f64,
+    #[serde(default)]
+    pub order: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CompositeDateHistogramSource {
+    pub field: String,
+    #[serde(alias = "fixed_interval")]
+    pub calendar_interval: Option<String>,
+    #[serde(default)]
+    pub order: Option<String>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct HistogramAgg {
     pub field: String,
@@ -484,6 +1114,189 @@ pub struct MSearchHeader {
     pub routing: Option<String>,
 }
 
+/// `_search/scroll` request body
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct EsScrollRequest {
+    #[serde(default)]
+    pub scroll: Option<String>,
+    #[serde(default)]
+    pub scroll_id: Option<String>,
+}
+
+/// `DELETE _search/scroll` and `DELETE _pit` request bodies
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct EsClearContextRequest {
+    /// Scroll IDs to clear (one or many)
+    #[serde(default)]
+    pub scroll_id: Option<OneOrMany>,
+    /// Point-in-time ID to close
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    pub fn into_vec(self) -> Vec<String> {
+        match self {
+            Self::One(s) => vec![s],
+            Self::Many(v) => v,
+        }
+    }
+}
+
+/// `_update` request body
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EsUpdateRequest {
+    /// Partial document merged into the existing one
+    #[serde(default)]
+    pub doc: Option<serde_json::Map<String, Value>>,
+    /// Document indexed when the target does not exist
+    #[serde(default)]
+    pub upsert: Option<serde_json::Map<String, Value>>,
+    /// Index `doc` itself when the target does not exist
+    #[serde(default)]
+    pub doc_as_upsert: bool,
+    /// Skip the write when the merge changes nothing (default true)
+    #[serde(default = "default_true")]
+    pub detect_noop: bool,
+    /// Scripted updates are not supported
+    #[serde(default)]
+    pub script: Option<Value>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// `_mget` request body: either `docs` or, with an index in the path, `ids`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct EsMgetRequest {
+    #[serde(default)]
+    pub docs: Option<Vec<MgetDoc>>,
+    #[serde(default)]
+    pub ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MgetDoc {
+    #[serde(default, rename = "_index")]
+    pub index: Option<String>,
+    #[serde(rename = "_id")]
+    pub id: String,
+    #[serde(default, rename = "_source")]
+    pub source: Option<SourceFilter>,
+}
+
+/// `_count` request body
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct EsCountRequest {
+    #[serde(default)]
+    pub query: Option<EsQuery>,
+}
+
+/// `_field_caps` request body
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct EsFieldCapsRequest {
+    /// Field names or wildcard patterns, merged with `?fields=`
+    #[serde(default)]
+    pub fields: Option<Vec<String>>,
+    /// Skip indices in which this query matches no document
+    #[serde(default)]
+    pub index_filter: Option<EsQuery>,
+}
+
+/// `_terms_enum` request body
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EsTermsEnumRequest {
+    pub field: String,
+    /// Prefix the returned terms start with
+    #[serde(default)]
+    pub string: Option<String>,
+    #[serde(default)]
+    pub size: Option<usize>,
+    #[serde(default)]
+    pub case_insensitive: bool,
+    /// Skip indices in which this query matches no document
+    #[serde(default)]
+    pub index_filter: Option<EsQuery>,
+    /// Return terms after this one, for paging
+    #[serde(default)]
+    pub search_after: Option<String>,
+}
+
+/// `PUT /{index}` request body
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EsCreateIndexRequest {
+    #[serde(default)]
+    pub mappings: Option<EsMappings>,
+    /// Accepted for compatibility; shard and replica counts do not apply
+    #[serde(default)]
+    pub settings: Option<Value>,
+    #[serde(default)]
+    pub aliases: HashMap<String, EsAliasOptions>,
+}
+
+/// Per-alias options in a create-index body
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct EsAliasOptions {
+    #[serde(default)]
+    pub is_write_index: Option<bool>,
+}
+
+/// `POST /_aliases` request body
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EsAliasesRequest {
+    pub actions: Vec<EsAliasAction>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EsAliasAction {
+    Add(EsAliasActionParams),
+    Remove(EsAliasActionParams),
+    RemoveIndex(EsAliasActionParams),
+}
+
+/// Targets of an alias action; `index`/`indices` and `alias`/`aliases`
+/// may be combined
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct EsAliasActionParams {
+    #[serde(default)]
+    pub index: Option<String>,
+    #[serde(default)]
+    pub indices: Option<Vec<String>>,
+    #[serde(default)]
+    pub alias: Option<String>,
+    #[serde(default)]
+    pub aliases: Option<Vec<String>>,
+    #[serde(default)]
+    pub is_write_index: Option<bool>,
+}
+
+impl EsAliasActionParams {
+    pub fn index_names(&self) -> Vec<String> {
+        self.index
+            .iter()
+            .chain(self.indices.iter().flatten())
+            .cloned()
+            .collect()
+    }
+
+    pub fn alias_names(&self) -> Vec<String> {
+        self.alias
+            .iter()
+            .chain(self.aliases.iter().flatten())
+            .cloned()
+            .collect()
+    }
+}
+
 /// Bulk request types
 #[derive(Debug, Clone)]
 pub enum BulkAction {
@@ -491,11 +1304,13 @@ pub enum BulkAction {
         index: String,
         id: Option<String>,
         doc: Value,
+        pipeline: Option<String>,
     },
     Create {
         index: String,
         id: Option<String>,
         doc: Value,
+        pipeline: Option<String>,
     },
     Delete {
         index: String,
@@ -521,6 +1336,9 @@ pub struct BulkMeta {
     pub index: Option<String>,
     #[serde(rename = "_id")]
     pub id: Option<String>,
+    /// Ingest pipeline for this item, overriding `?pipeline=`
+    #[serde(default)]
+    pub pipeline: Option<String>,
 }
 
 #[cfg(test)]
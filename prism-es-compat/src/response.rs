@@ -1,10 +1,15 @@
 //! Response mappers from Prism to Elasticsearch format
 
-use prism::aggregations::{AggregationResult, AggregationValue, Bucket};
+use crate::endpoints::scroll::shard_doc;
+use crate::query::{EsSearchRequest, SourceFilter, TrackTotalHits};
+use prism::aggregations::{
+    AggregationResult, AggregationValue, Bucket, CompositeBucket, TopHitsResult,
+};
 use prism::backends::{SearchResult, SearchResultsWithAggs};
+use prism::collection::{MultiSearchResult, PointInTimeResults};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// ES search response format
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +21,14 @@ pub struct EsSearchResponse {
     pub hits: HitsResponse,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aggregations: Option<HashMap<String, EsAggregationResult>>,
+    #[serde(
+        rename = "_scroll_id",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub scroll_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pit_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,7 +52,9 @@ impl Default for ShardStats {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HitsResponse {
-    pub total: TotalHits,
+    /// Omitted when the request sets `track_total_hits: false`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<TotalHits>,
     pub max_score: Option<f32>,
     pub hits: Vec<Hit>,
 }
@@ -58,18 +73,37 @@ pub struct Hit {
     pub id: String,
     #[serde(rename = "_score")]
     pub score: Option<f32>,
-    #[serde(rename = "_source")]
-    pub source: HashMap<String, Value>,
+    /// Omitted when the request sets `_source: false`
+    #[serde(rename = "_source", default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<HashMap<String, Value>>,
+    /// Values of the `fields` and `docvalue_fields` the request asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<HashMap<String, Vec<Value>>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub highlight: Option<HashMap<String, Vec<String>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<Vec<Value>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum EsAggregationResult {
+    SignificantTerms {
+        doc_count: u64,
+        bg_count: u64,
+        buckets: Vec<EsSignificantBucket>,
+    },
+    Composite {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        after_key: Option<HashMap<String, Value>>,
+        buckets: Vec<EsCompositeBucket>,
+    },
     Buckets {
         buckets: Vec<EsBucket>,
     },
+    TopHits {
+        hits: HitsResponse,
+    },
     Value {
         value: Option<f64>,
     },
@@ -95,10 +129,28 @@ pub struct EsBucket {
     pub from: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub to: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc_count_error_upper_bound: Option<u64>,
+    #[serde(flatten)]
+    pub sub_aggs: HashMap<String, EsAggregationResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsCompositeBucket {
+    pub key: HashMap<String, Value>,
+    pub doc_count: u64,
     #[serde(flatten)]
     pub sub_aggs: HashMap<String, EsAggregationResult>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsSignificantBucket {
+    pub key: Value,
+    pub doc_count: u64,
+    pub score: f64,
+    pub bg_count: u64,
+}
+
 /// Response mapper
 pub struct ResponseMapper;
 
@@ -120,7 +172,7 @@ impl ResponseMapper {
         let aggregations = if results.aggregations.is_empty() {
             None
         } else {
-            Some(Self::map_aggregations(&results.aggregations))
+            Some(Self::map_aggregations(index, &results.aggregations))
         };
 
         EsSearchResponse {
@@ -128,14 +180,127 @@ impl ResponseMapper {
             timed_out: false,
             shards: ShardStats::default(),
             hits: HitsResponse {
-                total: TotalHits {
+                total: Some(TotalHits {
                     value: results.total,
                     relation: "eq".to_string(),
-                },
+                }),
                 max_score,
                 hits,
             },
             aggregations,
+            scroll_id: None,
+            pit_id: None,
+        }
+    }
+
+    /// Map a point-in-time page. Hits carry their own collection as `_index`;
+    /// with `with_sort` each hit gets `[_score, _shard_doc]` sort values,
+    /// where `_shard_doc` locates the hit in the pinned snapshot, or is its
+    /// rank counted from `offset` for hits merged without a position.
+    pub fn map_point_in_time_results(
+        index: &str,
+        results: PointInTimeResults,
+        offset: usize,
+        with_sort: bool,
+        took_ms: u64,
+    ) -> EsSearchResponse {
+        let max_score = results.results.first().map(|r| r.score);
+
+        let positions = &results.positions;
+        let hits: Vec<Hit> = results
+            .results
+            .into_iter()
+            .enumerate()
+            .map(|(i, r)| {
+                let shard_doc = positions
+                    .get(i)
+                    .map_or((offset + i) as u64, shard_doc);
+                Hit {
+                    index: r.collection,
+                    id: r.id,
+                    score: Some(r.score),
+                    source: Some(r.fields),
+                    fields: None,
+                    highlight: r.highlight,
+                    sort: with_sort.then(|| vec![Value::from(r.score), Value::from(shard_doc)]),
+                }
+            })
+            .collect();
+
+        let aggregations = if results.aggregations.is_empty() {
+            None
+        } else {
+            Some(Self::map_aggregations(index, &results.aggregations))
+        };
+
+        EsSearchResponse {
+            took: took_ms,
+            timed_out: false,
+            shards: ShardStats::default(),
+            hits: HitsResponse {
+                total: Some(TotalHits {
+                    value: results.total,
+                    relation: "eq".to_string(),
+                }),
+                max_score,
+                hits,
+            },
+            aggregations,
+            scroll_id: None,
+            pit_id: None,
+        }
+    }
+
+    /// Map hits sorted on fields, each carrying its sort values. Scores are
+    /// only reported when `track_scores` is set or `_score` is a sort key.
+    pub fn map_sorted_results(
+        index: &str,
+        hits: Vec<(MultiSearchResult, Vec<Value>)>,
+        total: u64,
+        aggregations: &HashMap<String, AggregationResult>,
+        track_scores: bool,
+        took_ms: u64,
+    ) -> EsSearchResponse {
+        let max_score = hits
+            .iter()
+            .map(|(r, _)| r.score)
+            .reduce(f32::max)
+            .filter(|_| track_scores);
+
+        let hits: Vec<Hit> = hits
+            .into_iter()
+            .map(|(r, sort)| Hit {
+                index: r.collection,
+                id: r.id,
+                score: track_scores.then_some(r.score),
+                source: Some(r.fields),
+                fields: None,
+                highlight: r.highlight,
+                sort: Some(sort),
+            })
+            .collect();
+
+        let aggregations = if aggregations.is_empty() {
+            None
+        } else {
+            Some(Self::map_aggregations(index, aggregations))
+        };
+
+        EsSearchResponse {
+            took: took_ms,
+            timed_out: false,
+            shards: ShardStats::default(),
+            hits: HitsResponse {
+                total: Some(TotalHits {
+                    value: total,
+                    relation: "eq".to_string(),
+                }),
+                max_score,
+                hits,
+            },
+            aggregations,
+            scroll_id: None,
+            pit_id: None,
         }
     }
 
@@ -144,20 +309,23 @@ impl ResponseMapper {
             index: index.to_string(),
             id: result.id,
             score: Some(result.score),
-            source: result.fields,
+            source: Some(result.fields),
+            fields: None,
             highlight: result.highlight,
+            sort: None,
         }
     }
 
     fn map_aggregations(
+        index: &str,
         aggs: &HashMap<String, AggregationResult>,
     ) -> HashMap<String, EsAggregationResult> {
         aggs.iter()
-            .map(|(name, result)| (name.clone(), Self::map_aggregation_result(result)))
+            .map(|(name, result)| (name.clone(), Self::map_aggregation_result(index, result)))
             .collect()
     }
 
-    fn map_aggregation_result(result: &AggregationResult) -> EsAggregationResult {
+    fn map_aggregation_result(index: &str, result: &AggregationResult) -> EsAggregationResult {
         match &result.value {
             AggregationValue::Single(v) => EsAggregationResult::Value { value: Some(*v) },
 
@@ -174,29 +342,103 @@ impl ResponseMapper {
             },
 
             AggregationValue::Buckets(buckets) => EsAggregationResult::Buckets {
-                buckets: buckets.iter().map(Self::map_bucket).collect(),
+                buckets: buckets.iter().map(|b| Self::map_bucket(index, b)).collect(),
+            },
+
+            AggregationValue::TopHits(top_hits) => EsAggregationResult::TopHits {
+                hits: Self::map_top_hits(index, top_hits),
+            },
+
+            AggregationValue::SignificantTerms(sig) => EsAggregationResult::SignificantTerms {
+                doc_count: sig.doc_count,
+                bg_count: sig.bg_count,
+                buckets: sig
+                    .buckets
+                    .iter()
+                    .map(|b| EsSignificantBucket {
+                        key: Self::bucket_key(&b.key),
+                        doc_count: b.doc_count,
+                        score: b.score,
+                        bg_count: b.bg_count,
+                    })
+                    .collect(),
+            },
+
+            AggregationValue::Cardinality(sketch) => EsAggregationResult::Value {
+                value: Some(sketch.estimate() as f64),
+            },
+
+            AggregationValue::Composite(composite) => EsAggregationResult::Composite {
+                after_key: composite.after_key.clone(),
+                buckets: composite
+                    .buckets
+                    .iter()
+                    .map(|b| Self::map_composite_bucket(index, b))
+                    .collect(),
             },
         }
     }
 
-    fn map_bucket(bucket: &Bucket) -> EsBucket {
-        // Try to parse key as number for proper JSON representation
-        let key = bucket
-            .key
-            .parse::<i64>()
-            .map(Value::from)
-            .or_else(|_| bucket.key.parse::<f64>().map(Value::from))
-            .unwrap_or_else(|_| Value::String(bucket.key.clone()));
+    fn map_composite_bucket(index: &str, bucket: &CompositeBucket) -> EsCompositeBucket {
+        EsCompositeBucket {
+            key: bucket.key.clone(),
+            doc_count: bucket.doc_count,
+            sub_aggs: Self::map_sub_aggs(index, bucket.sub_aggs.as_deref()),
+        }
+    }
 
-        let sub_aggs = bucket
-            .sub_aggs
-            .as_ref()
+    fn map_sub_aggs(
+        index: &str,
+        sub_aggs: Option<&[AggregationResult]>,
+    ) -> HashMap<String, EsAggregationResult> {
+        sub_aggs
             .map(|aggs| {
                 aggs.iter()
-                    .map(|a| (a.name.clone(), Self::map_aggregation_result(a)))
+                    .map(|a| (a.name.clone(), Self::map_aggregation_result(index, a)))
                     .collect()
             })
-            .unwrap_or_default();
+            .unwrap_or_default()
+    }
+
+    fn map_top_hits(index: &str, top_hits: &TopHitsResult) -> HitsResponse {
+        HitsResponse {
+            total: Some(TotalHits {
+                value: top_hits.total,
+                relation: "eq".to_string(),
+            }),
+            max_score: top_hits.max_score,
+            hits: top_hits
+                .hits
+                .iter()
+                .map(|h| Hit {
+                    index: index.to_string(),
+                    id: h.id.clone(),
+                    score: Some(h.score),
+                    source: Some(h.fields.clone()),
+                    fields: None,
+                    highlight: None,
+                    sort: if h.sort.is_empty() {
+                        None
+                    } else {
+                        Some(h.sort.clone())
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    /// Parse a bucket key as a number when possible for proper JSON representation
+    fn bucket_key(key: &str) -> Value {
+        key.parse::<i64>()
+            .map(Value::from)
+            .or_else(|_| key.parse::<f64>().map(Value::from))
+            .unwrap_or_else(|_| Value::String(key.to_string()))
+    }
+
+    fn map_bucket(index: &str, bucket: &Bucket) -> EsBucket {
+        let key = Self::bucket_key(&bucket.key);
+
+        let sub_aggs = Self::map_sub_aggs(index, bucket.sub_aggs.as_deref());
 
         EsBucket {
             key,
@@ -204,11 +446,144 @@ impl ResponseMapper {
             doc_count: bucket.doc_count,
             from: bucket.from,
             to: bucket.to,
+            doc_count_error_upper_bound: bucket.doc_count_error_upper_bound,
             sub_aggs,
         }
     }
 }
 
+/// Totals above this are reported as a lower bound unless
+/// `track_total_hits` asks for more, as in Elasticsearch
+const DEFAULT_TRACK_TOTAL_HITS: u64 = 10_000;
+
+/// How the hits of one search are rendered: `_source` filtering, `fields`
+/// / `docvalue_fields` and the accuracy of `hits.total`
+#[derive(Debug, Clone, Default)]
+pub struct HitOptions {
+    source: Option<SourceFilter>,
+    fields: Vec<String>,
+    track_total_hits: Option<TrackTotalHits>,
+}
+
+impl HitOptions {
+    pub fn from_request(request: &EsSearchRequest) -> Self {
+        Self {
+            source: request.source.clone(),
+            fields: request
+                .fields
+                .iter()
+                .chain(&request.docvalue_fields)
+                .flatten()
+                .map(|f| f.field().to_string())
+                .collect(),
+            track_total_hits: request.track_total_hits.clone(),
+        }
+    }
+
+    /// Apply the options to a search response built from unfiltered hits
+    pub fn apply(&self, response: &mut EsSearchResponse) {
+        let limit = match self.track_total_hits {
+            Some(TrackTotalHits::Bool(true)) => None,
+            Some(TrackTotalHits::Bool(false)) => {
+                response.hits.total = None;
+                None
+            }
+            Some(TrackTotalHits::Count(n)) => Some(n as u64),
+            None => Some(DEFAULT_TRACK_TOTAL_HITS),
+        };
+        if let (Some(total), Some(limit)) = (&mut response.hits.total, limit) {
+            if total.value > limit {
+                total.value = limit;
+                total.relation = "gte".to_string();
+            }
+        }
+
+        for hit in &mut response.hits.hits {
+            let mut source = hit.source.take().unwrap_or_default();
+            // Stored by the document APIs, reported as `_version` / `_seq_no`
+            source.remove("_version");
+            source.remove("_seq_no");
+            if !self.fields.is_empty() {
+                let values = field_values(&source, &self.fields);
+                hit.fields = (!values.is_empty()).then_some(values);
+            }
+            hit.source = filter_source(source, self.source.as_ref());
+        }
+    }
+}
+
+/// Values of the source fields matching `patterns`, always as arrays
+fn field_values(
+    source: &HashMap<String, Value>,
+    patterns: &[String],
+) -> HashMap<String, Vec<Value>> {
+    source
+        .iter()
+        .filter(|(name, _)| patterns.iter().any(|p| field_matches(p, name)))
+        .filter_map(|(name, value)| {
+            let values = match value {
+                Value::Null => return None,
+                Value::Array(values) => values.iter().filter(|v| !v.is_null()).cloned().collect(),
+                value => vec![value.clone()],
+            };
+            Some((name.clone(), values))
+        })
+        .collect()
+}
+
+/// Apply a `_source` filter; `None` means the source is not returned
+pub(crate) fn filter_source(
+    source: HashMap<String, Value>,
+    filter: Option<&SourceFilter>,
+) -> Option<HashMap<String, Value>> {
+    let (includes, excludes) = match filter {
+        None | Some(SourceFilter::Bool(true)) => return Some(source),
+        Some(SourceFilter::Bool(false)) => return None,
+        Some(SourceFilter::Fields(fields)) => (Some(fields.as_slice()), None),
+        Some(SourceFilter::Object { includes, excludes }) => {
+            (includes.as_deref(), excludes.as_deref())
+        }
+    };
+    Some(
+        source
+            .into_iter()
+            .filter(|(name, _)| {
+                includes.is_none_or(|inc| inc.iter().any(|p| field_matches(p, name)))
+                    && !excludes.is_some_and(|exc| exc.iter().any(|p| field_matches(p, name)))
+            })
+            .collect(),
+    )
+}
+
+/// Match a field against a `_source` or `fields` pattern, where `*` matches
+/// any run of characters. A pattern naming an object also matches its
+/// sub-fields (`user` matches `user.name`).
+pub(crate) fn field_matches(pattern: &str, field: &str) -> bool {
+    wildcard_matches(pattern, field)
+        || field
+            .strip_prefix(pattern)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+pub(crate) fn wildcard_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = text.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard: the whole text must match
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
 /// ES multi-search response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EsMSearchResponse {
@@ -230,6 +605,153 @@ pub struct EsError {
     pub reason: String,
 }
 
+/// Response of the single-document write APIs (`_doc`, `_create`,
+/// `_update` and delete)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsWriteResponse {
+    #[serde(rename = "_index")]
+    pub index: String,
+    #[serde(rename = "_id")]
+    pub id: String,
+    #[serde(rename = "_version")]
+    pub version: u64,
+    /// `created`, `updated`, `deleted`, `not_found` or `noop`
+    pub result: String,
+    #[serde(rename = "_shards")]
+    pub shards: ShardStats,
+    #[serde(rename = "_seq_no")]
+    pub seq_no: u64,
+    #[serde(rename = "_primary_term")]
+    pub primary_term: u64,
+}
+
+/// Response of `GET /{index}/_doc/{id}` and each found `_mget` entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsGetResponse {
+    #[serde(rename = "_index")]
+    pub index: String,
+    #[serde(rename = "_id")]
+    pub id: String,
+    #[serde(rename = "_version", skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+    #[serde(rename = "_seq_no", skip_serializing_if = "Option::is_none")]
+    pub seq_no: Option<u64>,
+    #[serde(rename = "_primary_term", skip_serializing_if = "Option::is_none")]
+    pub primary_term: Option<u64>,
+    pub found: bool,
+    #[serde(rename = "_source", skip_serializing_if = "Option::is_none")]
+    pub source: Option<HashMap<String, Value>>,
+}
+
+/// ES multi-get response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsMgetResponse {
+    pub docs: Vec<EsMgetItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EsMgetItem {
+    Doc(EsGetResponse),
+    Error {
+        #[serde(rename = "_index")]
+        index: String,
+        #[serde(rename = "_id")]
+        id: String,
+        error: EsError,
+    },
+}
+
+/// Response of `POST /{index}/_pit`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsOpenPitResponse {
+    pub id: String,
+}
+
+/// Response of `DELETE /_search/scroll` and `DELETE /_pit`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsClearContextResponse {
+    pub succeeded: bool,
+    pub num_freed: usize,
+}
+
+/// Response of index and alias updates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsAcknowledgedResponse {
+    pub acknowledged: bool,
+}
+
+/// Response of `PUT /{index}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsCreateIndexResponse {
+    pub acknowledged: bool,
+    pub shards_acknowledged: bool,
+    pub index: String,
+}
+
+/// Response of `GET /_aliases`, keyed by index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsAliasesResponse {
+    #[serde(flatten)]
+    pub indices: HashMap<String, EsIndexAliases>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EsIndexAliases {
+    pub aliases: HashMap<String, EsAliasInfo>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EsAliasInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_write_index: Option<bool>,
+}
+
+/// ES count response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsCountResponse {
+    pub count: u64,
+    #[serde(rename = "_shards")]
+    pub shards: ShardStats,
+}
+
+/// ES _field_caps response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsFieldCapsResponse {
+    pub indices: Vec<String>,
+    /// Field name -> ES type -> capabilities
+    pub fields: BTreeMap<String, BTreeMap<String, EsFieldCapability>>,
+}
+
+/// Capabilities of a field for one of its types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsFieldCapability {
+    #[serde(rename = "type")]
+    pub field_type: String,
+    pub metadata_field: bool,
+    pub searchable: bool,
+    pub aggregatable: bool,
+    /// Indices with this type, when the field has more than one type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indices: Option<Vec<String>>,
+    /// Indices where the field is not searchable, when that differs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub non_searchable_indices: Option<Vec<String>>,
+    /// Indices where the field is not aggregatable, when that differs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub non_aggregatable_indices: Option<Vec<String>>,
+}
+
+/// ES _terms_enum response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsTermsEnumResponse {
+    #[serde(rename = "_shards")]
+    pub shards: ShardStats,
+    pub terms: Vec<String>,
+    /// Whether every index was searched completely
+    pub complete: bool,
+}
+
 /// ES bulk response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EsBulkResponse {
@@ -370,6 +892,101 @@ pub struct EsCatIndex {
     pub pri_store_size: String,
 }
 
+/// Node counts of a `_nodes` response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsNodesHeader {
+    pub total: u32,
+    pub successful: u32,
+    pub failed: u32,
+}
+
+/// ES `_nodes/stats` response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsNodesStatsResponse {
+    #[serde(rename = "_nodes")]
+    pub header: EsNodesHeader,
+    pub cluster_name: String,
+    pub nodes: HashMap<String, EsNodeStats>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsNodeStats {
+    pub timestamp: i64,
+    pub name: String,
+    pub transport_address: String,
+    pub host: String,
+    pub ip: String,
+    pub roles: Vec<String>,
+    pub indices: EsIndicesStats,
+}
+
+/// Document, store and segment totals
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EsIndicesStats {
+    pub docs: EsDocsStats,
+    pub store: EsStoreStats,
+    pub segments: EsSegmentsStats,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EsDocsStats {
+    pub count: u64,
+    pub deleted: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EsStoreStats {
+    pub size_in_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EsSegmentsStats {
+    pub count: u64,
+}
+
+/// ES `_cluster/stats` response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsClusterStatsResponse {
+    #[serde(rename = "_nodes")]
+    pub header: EsNodesHeader,
+    pub cluster_name: String,
+    pub cluster_uuid: String,
+    pub timestamp: i64,
+    pub status: String,
+    pub indices: EsClusterIndicesStats,
+    pub nodes: EsClusterNodesStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsClusterIndicesStats {
+    pub count: u32,
+    pub shards: EsClusterShardsStats,
+    pub docs: EsDocsStats,
+    pub store: EsStoreStats,
+    pub segments: EsSegmentsStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsClusterShardsStats {
+    pub total: u32,
+    pub primaries: u32,
+    pub replication: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsClusterNodesStats {
+    pub count: EsClusterNodeCounts,
+    pub versions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EsClusterNodeCounts {
+    pub total: u32,
+    pub data: u32,
+    pub master: u32,
+    pub ingest: u32,
+}
+
 /// ES mapping response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EsMappingResponse {
@@ -401,9 +1018,12 @@ pub struct EsFieldMapping {
 mod tests {
     use super::*;
     use prism::aggregations::{
-        AggregationResult, AggregationValue, Bucket, PercentilesResult, StatsResult,
+        AggregationResult, AggregationValue, Bucket, CompositeBucket, CompositeResult,
+        PercentilesResult, SignificantTermsBucket, SignificantTermsResult, StatsResult, TopHit,
+        TopHitsResult,
     };
-    use prism::backends::{SearchResult, SearchResultsWithAggs};
+    use prism::backends::{SearchResult, SearchResultsWithAggs, SnapshotCursor};
+    use prism::collection::HitPosition;
 
     // ===================================================================
     // ShardStats default
@@ -464,8 +1084,8 @@ mod tests {
         let response = ResponseMapper::map_search_results("test_index", results, 5);
         assert_eq!(response.took, 5);
         assert!(!response.timed_out);
-        assert_eq!(response.hits.total.value, 0);
-        assert_eq!(response.hits.total.relation, "eq");
+        assert_eq!(response.hits.total.as_ref().unwrap().value, 0);
+        assert_eq!(response.hits.total.as_ref().unwrap().relation, "eq");
         assert!(response.hits.max_score.is_none());
         assert!(response.hits.hits.is_empty());
         assert!(response.aggregations.is_none());
@@ -504,7 +1124,7 @@ mod tests {
         };
         let response = ResponseMapper::map_search_results("my_index", results, 10);
 
-        assert_eq!(response.hits.total.value, 2);
+        assert_eq!(response.hits.total.as_ref().unwrap().value, 2);
         assert_eq!(response.hits.max_score, Some(1.5));
         assert_eq!(response.hits.hits.len(), 2);
 
@@ -513,7 +1133,7 @@ mod tests {
         assert_eq!(hit0.id, "id1");
         assert_eq!(hit0.score, Some(1.5));
         assert_eq!(
-            hit0.source.get("title"),
+            hit0.source.as_ref().unwrap().get("title"),
             Some(&Value::String("doc1".to_string()))
         );
         assert!(hit0.highlight.is_none());
@@ -558,6 +1178,66 @@ mod tests {
         );
     }
 
+    // ===================================================================
+    // ResponseMapper::map_point_in_time_results
+    // ===================================================================
+
+    #[test]
+    fn test_map_point_in_time_results() {
+        let hit = |id: &str, collection: &str, score: f32| prism::collection::MultiSearchResult {
+            id: id.to_string(),
+            collection: collection.to_string(),
+            score,
+            fields: HashMap::new(),
+            highlight: None,
+        };
+        let position = |collection: usize, score: f32, doc_id: u32| HitPosition {
+            collection,
+            cursor: SnapshotCursor {
+                score,
+                segment_ord: 0,
+                doc_id,
+            },
+        };
+        let results = PointInTimeResults {
+            results: vec![hit("a", "logs-1", 2.0), hit("b", "logs-2", 1.0)],
+            positions: vec![position(0, 2.0, 3), position(1, 1.0, 5)],
+            total: 7,
+            aggregations: HashMap::new(),
+        };
+
+        let response = ResponseMapper::map_point_in_time_results("logs-*", results, 4, true, 3);
+        assert_eq!(response.hits.total.as_ref().unwrap().value, 7);
+        assert_eq!(response.hits.hits[0].index, "logs-1");
+        assert_eq!(response.hits.hits[1].index, "logs-2");
+        assert_eq!(
+            response.hits.hits[1].sort,
+            Some(vec![Value::from(1.0f32), Value::from((1u64 << 48) | 5)])
+        );
+
+        // Hits merged without positions are sorted by rank
+        let results = PointInTimeResults {
+            results: vec![hit("a", "logs-1", 2.0), hit("b", "logs-2", 1.0)],
+            positions: vec![],
+            total: 2,
+            aggregations: HashMap::new(),
+        };
+        let response = ResponseMapper::map_point_in_time_results("logs-*", results, 4, true, 3);
+        assert_eq!(
+            response.hits.hits[1].sort,
+            Some(vec![Value::from(1.0f32), Value::from(5)])
+        );
+
+        let results = PointInTimeResults {
+            results: vec![hit("a", "logs-1", 2.0)],
+            positions: vec![],
+            total: 1,
+            aggregations: HashMap::new(),
+        };
+        let response = ResponseMapper::map_point_in_time_results("logs-*", results, 0, false, 3);
+        assert!(response.hits.hits[0].sort.is_none());
+    }
+
     // ===================================================================
     // Aggregation result mapping — Single value
     // ===================================================================
@@ -689,6 +1369,7 @@ mod tests {
                         from: None,
                         to: None,
                         sub_aggs: None,
+                        doc_count_error_upper_bound: None,
                     },
                     Bucket {
                         key: "inactive".to_string(),
@@ -696,6 +1377,7 @@ mod tests {
                         from: None,
                         to: None,
                         sub_aggs: None,
+                        doc_count_error_upper_bound: None,
                     },
                 ]),
             },
@@ -736,6 +1418,7 @@ mod tests {
                     from: None,
                     to: None,
                     sub_aggs: None,
+                    doc_count_error_upper_bound: None,
                 }]),
             },
         );
@@ -770,6 +1453,7 @@ mod tests {
                     from: None,
                     to: None,
                     sub_aggs: None,
+                    doc_count_error_upper_bound: None,
                 }]),
             },
         );
@@ -808,6 +1492,7 @@ mod tests {
                     from: None,
                     to: Some(50.0),
                     sub_aggs: None,
+                    doc_count_error_upper_bound: None,
                 }]),
             },
         );
@@ -846,6 +1531,7 @@ mod tests {
                     from: None,
                     to: None,
                     sub_aggs: Some(sub),
+                    doc_count_error_upper_bound: None,
                 }]),
             },
         );
@@ -872,6 +1558,114 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_map_agg_top_hits() {
+        let mut aggs = HashMap::new();
+        aggs.insert(
+            "best".to_string(),
+            AggregationResult {
+                name: "best".to_string(),
+                value: AggregationValue::TopHits(TopHitsResult {
+                    total: 7,
+                    max_score: Some(2.5),
+                    hits: vec![TopHit {
+                        id: "42".to_string(),
+                        score: 2.5,
+                        fields: HashMap::from([(
+                            "title".to_string(),
+                            Value::String("Best".to_string()),
+                        )]),
+                        sort: vec![Value::from(10)],
+                    }],
+                }),
+            },
+        );
+
+        let results = SearchResultsWithAggs {
+            results: vec![],
+            total: 0,
+            aggregations: aggs,
+        };
+        let response = ResponseMapper::map_search_results("idx", results, 1);
+        let json = serde_json::to_value(&response.aggregations.unwrap()["best"]).unwrap();
+        assert_eq!(json["hits"]["total"]["value"], 7);
+        assert_eq!(json["hits"]["hits"][0]["_index"], "idx");
+        assert_eq!(json["hits"]["hits"][0]["_id"], "42");
+        assert_eq!(json["hits"]["hits"][0]["_source"]["title"], "Best");
+        assert_eq!(json["hits"]["hits"][0]["sort"][0], 10);
+    }
+
+    #[test]
+    fn test_map_agg_significant_terms() {
+        let mut aggs = HashMap::new();
+        aggs.insert(
+            "unusual".to_string(),
+            AggregationResult {
+                name: "unusual".to_string(),
+                value: AggregationValue::SignificantTerms(SignificantTermsResult {
+                    doc_count: 10,
+                    bg_count: 1000,
+                    buckets: vec![SignificantTermsBucket {
+                        key: "rust".to_string(),
+                        doc_count: 8,
+                        bg_count: 20,
+                        score: 30.8,
+                    }],
+                }),
+            },
+        );
+
+        let results = SearchResultsWithAggs {
+            results: vec![],
+            total: 0,
+            aggregations: aggs,
+        };
+        let response = ResponseMapper::map_search_results("idx", results, 1);
+        let json = serde_json::to_value(&response.aggregations.unwrap()["unusual"]).unwrap();
+        assert_eq!(json["doc_count"], 10);
+        assert_eq!(json["bg_count"], 1000);
+        assert_eq!(json["buckets"][0]["key"], "rust");
+        assert_eq!(json["buckets"][0]["bg_count"], 20);
+    }
+
+    #[test]
+    fn test_map_agg_composite() {
+        let key = HashMap::from([
+            ("customer".to_string(), Value::from("c-1")),
+            ("day".to_string(), Value::from("2025-01-01T00:00:00+00:00")),
+        ]);
+        let mut aggs = HashMap::new();
+        aggs.insert(
+            "rollup".to_string(),
+            AggregationResult {
+                name: "rollup".to_string(),
+                value: AggregationValue::Composite(CompositeResult {
+                    after_key: Some(key.clone()),
+                    buckets: vec![CompositeBucket {
+                        key,
+                        doc_count: 4,
+                        sub_aggs: Some(vec![AggregationResult {
+                            name: "revenue".to_string(),
+                            value: AggregationValue::Single(99.0),
+                        }]),
+                    }],
+                }),
+            },
+        );
+
+        let results = SearchResultsWithAggs {
+            results: vec![],
+            total: 0,
+            aggregations: aggs,
+        };
+        let response = ResponseMapper::map_search_results("idx", results, 1);
+        let json = serde_json::to_value(&response.aggregations.unwrap()["rollup"]).unwrap();
+        assert_eq!(json["after_key"]["customer"], "c-1");
+        assert_eq!(json["buckets"][0]["key"]["customer"], "c-1");
+        assert_eq!(json["buckets"][0]["doc_count"], 4);
+        assert_eq!(json["buckets"][0]["revenue"]["value"], 99.0);
+    }
+
     // ===================================================================
     // Serde round-trip tests for response types
     // ===================================================================
@@ -883,31 +1677,38 @@ mod tests {
             timed_out: false,
             shards: ShardStats::default(),
             hits: HitsResponse {
-                total: TotalHits {
+                total: Some(TotalHits {
                     value: 1,
                     relation: "eq".to_string(),
-                },
+                }),
                 max_score: Some(1.0),
                 hits: vec![Hit {
                     index: "test".to_string(),
                     id: "1".to_string(),
                     score: Some(1.0),
-                    source: {
+                    source: Some({
                         let mut m = HashMap::new();
                         m.insert("title".to_string(), Value::String("doc".to_string()));
                         m
-                    },
+                    }),
+                    fields: None,
                     highlight: None,
+                    sort: None,
                 }],
             },
             aggregations: None,
+            scroll_id: Some("abc".to_string()),
+            pit_id: None,
         };
 
         let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"_scroll_id\":\"abc\""));
+        assert!(!json.contains("pit_id"));
         let deser: EsSearchResponse = serde_json::from_str(&json).unwrap();
         assert_eq!(deser.took, 15);
-        assert_eq!(deser.hits.total.value, 1);
+        assert_eq!(deser.hits.total.as_ref().unwrap().value, 1);
         assert_eq!(deser.hits.hits[0].id, "1");
+        assert_eq!(deser.scroll_id.as_deref(), Some("abc"));
     }
 
     #[test]
@@ -994,14 +1795,16 @@ mod tests {
                     timed_out: false,
                     shards: ShardStats::default(),
                     hits: HitsResponse {
-                        total: TotalHits {
+                        total: Some(TotalHits {
                             value: 0,
                             relation: "eq".to_string(),
-                        },
+                        }),
                         max_score: None,
                         hits: vec![],
                     },
                     aggregations: None,
+                    scroll_id: None,
+                    pit_id: None,
                 }),
                 EsMSearchItem::Error {
                     error: EsError {
@@ -1046,4 +1849,119 @@ mod tests {
         assert!(json.contains("test_error"));
         assert!(json.contains("something broke"));
     }
+
+    // ===================================================================
+    // Hit options (_source, fields, track_total_hits)
+    // ===================================================================
+
+    #[test]
+    fn test_filter_source() {
+        let source: HashMap<String, Value> = HashMap::from([
+            ("title".to_string(), serde_json::json!("Widget")),
+            ("price".to_string(), serde_json::json!(9.5)),
+            ("price_currency".to_string(), serde_json::json!("EUR")),
+            ("user.name".to_string(), serde_json::json!("ann")),
+        ]);
+
+        assert_eq!(filter_source(source.clone(), None).unwrap().len(), 4);
+        assert!(filter_source(source.clone(), Some(&SourceFilter::Bool(false))).is_none());
+
+        let fields = SourceFilter::Fields(vec!["title".to_string(), "user".to_string()]);
+        let filtered = filter_source(source.clone(), Some(&fields)).unwrap();
+        assert_eq!(filtered.len(), 2);
+        assert!(filtered.contains_key("user.name"));
+
+        let object = SourceFilter::Object {
+            includes: Some(vec!["price*".to_string()]),
+            excludes: Some(vec!["price_currency".to_string()]),
+        };
+        let filtered = filter_source(source, Some(&object)).unwrap();
+        assert_eq!(filtered.keys().collect::<Vec<_>>(), vec!["price"]);
+    }
+
+    #[test]
+    fn test_field_matches_wildcards() {
+        assert!(field_matches("*", "title"));
+        assert!(field_matches("user.*", "user.name"));
+        assert!(field_matches("*.name", "user.name"));
+        assert!(field_matches("u*r.n*e", "user.name"));
+        assert!(field_matches("user", "user.name"));
+        assert!(!field_matches("user", "username"));
+        assert!(!field_matches("*.name", "name"));
+        assert!(!field_matches("ti*x", "title"));
+    }
+
+    fn options(request: serde_json::Value) -> HitOptions {
+        HitOptions::from_request(&serde_json::from_value(request).unwrap())
+    }
+
+    fn response_with_total(total: u64) -> EsSearchResponse {
+        let results = SearchResultsWithAggs {
+            results: vec![SearchResult {
+                id: "1".to_string(),
+                score: 1.0,
+                fields: HashMap::from([
+                    ("title".to_string(), serde_json::json!("Widget")),
+                    ("tags".to_string(), serde_json::json!(["a", "b"])),
+                    ("price".to_string(), serde_json::json!(9.5)),
+                ]),
+                highlight: None,
+            }],
+            total,
+            aggregations: HashMap::new(),
+        };
+        ResponseMapper::map_search_results("idx", results, 1)
+    }
+
+    #[test]
+    fn test_hit_options_source_and_fields() {
+        let mut response = response_with_total(1);
+        options(serde_json::json!({
+            "_source": { "excludes": ["t*"] },
+            "fields": ["tags", { "field": "pri*", "format": "use_field_mapping" }],
+            "docvalue_fields": ["title"]
+        }))
+        .apply(&mut response);
+
+        let hit = &response.hits.hits[0];
+        let source = hit.source.as_ref().unwrap();
+        assert_eq!(source.keys().collect::<Vec<_>>(), vec!["price"]);
+        let fields = hit.fields.as_ref().unwrap();
+        assert_eq!(
+            fields["tags"],
+            vec![serde_json::json!("a"), serde_json::json!("b")]
+        );
+        assert_eq!(fields["price"], vec![serde_json::json!(9.5)]);
+        assert_eq!(fields["title"], vec![serde_json::json!("Widget")]);
+
+        let mut response = response_with_total(1);
+        options(serde_json::json!({ "_source": false })).apply(&mut response);
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json["hits"]["hits"][0].get("_source").is_none());
+        assert!(json["hits"]["hits"][0].get("fields").is_none());
+    }
+
+    #[test]
+    fn test_hit_options_track_total_hits() {
+        let mut response = response_with_total(12_000);
+        options(serde_json::json!({})).apply(&mut response);
+        let total = response.hits.total.unwrap();
+        assert_eq!((total.value, total.relation.as_str()), (10_000, "gte"));
+
+        let mut response = response_with_total(12_000);
+        options(serde_json::json!({ "track_total_hits": true })).apply(&mut response);
+        let total = response.hits.total.unwrap();
+        assert_eq!((total.value, total.relation.as_str()), (12_000, "eq"));
+
+        let mut response = response_with_total(50);
+        options(serde_json::json!({ "track_total_hits": 10 })).apply(&mut response);
+        let total = response.hits.total.unwrap();
+        assert_eq!((total.value, total.relation.as_str()), (10, "gte"));
+
+        let mut response = response_with_total(50);
+        options(serde_json::json!({ "track_total_hits": false })).apply(&mut response);
+        assert!(response.hits.total.is_none());
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json["hits"].get("total").is_none());
+    }
 }
@@ -1,13 +1,36 @@
 //! ES-compatible API router
 
+use crate::endpoints::ingest::IngestPipelines;
+use crate::endpoints::scroll::spawn_reaper;
 use crate::endpoints::search::EsCompatState;
 use crate::endpoints::{
-    bulk_handler, cat_indices_handler, cluster_health_handler, mapping_handler, msearch_handler,
-    root_handler, search_handler,
+    bulk_handler, cluster_health_handler, count_handler, create_doc_handler, delete_doc_handler,
+    get_doc_handler, head_doc_handler, index_doc_auto_id_handler, index_doc_handler,
+    mapping_handler, mget_handler, msearch_handler, root_handler, search_handler,
+    update_doc_handler,
 };
-use axum::routing::{get, post};
+use crate::endpoints::{
+    cat_aliases_handler, cat_count_handler, cat_health_handler, cat_indices_handler,
+    cat_segments_handler, cat_shards_handler, cluster_stats_handler, nodes_stats_handler,
+};
+use crate::endpoints::{
+    clear_all_scrolls_handler, clear_scroll_handler, close_pit_handler, open_pit_handler,
+    scroll_handler,
+};
+use crate::endpoints::{
+    create_index_handler, delete_index_handler, get_aliases_handler, head_index_handler,
+    put_mapping_handler, update_aliases_handler,
+};
+use crate::endpoints::{
+    delete_pipeline_handler, get_pipeline_handler, put_pipeline_handler, simulate_pipeline_handler,
+};
+use crate::endpoints::{field_caps_handler, terms_enum_handler};
+use crate::topology::ClusterTopology;
+use axum::routing::{delete, get, post, put};
 use axum::Router;
 use prism::collection::CollectionManager;
+use prism::ilm::AliasManager;
+use prism::pipeline::registry::PipelineRegistry;
 use std::sync::Arc;
 
 /// Create the ES-compatible router
@@ -18,55 +41,320 @@ use std::sync::Arc;
 ///
 /// - `GET /_elastic/` - Cluster info
 /// - `GET /_elastic/_cluster/health` - Cluster health
-/// - `GET /_elastic/_cat/indices` - List indices
+/// - `GET /_elastic/_cluster/stats` - Cluster-wide index and node totals
+/// - `GET /_elastic/_nodes/stats` - Per-node index statistics
+/// - `GET /_elastic/_cat/indices[/{index}]` - List indices
+/// - `GET /_elastic/_cat/health` - One-line cluster health
+/// - `GET /_elastic/_cat/count[/{index}]` - Document count
+/// - `GET /_elastic/_cat/aliases[/{name}]` - List aliases
+/// - `GET /_elastic/_cat/shards[/{index}]` - List shard copies
+/// - `GET /_elastic/_cat/segments[/{index}]` - List segments of local shards
 /// - `POST /_elastic/_search` - Search all indices
 /// - `POST /_elastic/{index}/_search` - Search specific index
+/// - `GET|POST /_elastic/_search/scroll` - Next page of a scroll
+/// - `DELETE /_elastic/_search/scroll` - Clear scrolls (`/_all` for every scroll)
+/// - `POST /_elastic/{index}/_pit` - Open a point in time
+/// - `DELETE /_elastic/_pit` - Close a point in time
 /// - `POST /_elastic/_msearch` - Multi-search
-/// - `POST /_elastic/_bulk` - Bulk operations
+/// - `POST /_elastic/_bulk` - Bulk operations (`?pipeline=` applies an ingest pipeline)
 /// - `POST /_elastic/{index}/_bulk` - Bulk with default index
+/// - `GET /_elastic/_ingest/pipeline[/{id}]` - Get ingest pipelines
+/// - `PUT|DELETE /_elastic/_ingest/pipeline/{id}` - Create or delete an ingest pipeline
+/// - `GET|POST /_elastic/_ingest/pipeline[/{id}]/_simulate` - Run a pipeline without indexing
+/// - `PUT /_elastic/{index}` - Create an index
+/// - `HEAD|DELETE /_elastic/{index}` - Check or delete an index
 /// - `GET /_elastic/{index}/_mapping` - Get mappings
+/// - `GET|POST /_elastic/[{index}/]_field_caps` - Field capabilities
+/// - `GET|POST /_elastic/{index}/_terms_enum` - Keyword terms by prefix
+/// - `PUT|POST /_elastic/{index}/_mapping` - Add fields to mappings
+/// - `GET /_elastic/_aliases` - List aliases
+/// - `POST|PUT /_elastic/_aliases` - Add and remove aliases
+/// - `PUT|POST /_elastic/{index}/_doc/{id}` - Index a document
+/// - `POST /_elastic/{index}/_doc` - Index a document with a generated ID
+/// - `GET|HEAD|DELETE /_elastic/{index}/_doc/{id}` - Get, check or delete a document
+/// - `PUT|POST /_elastic/{index}/_create/{id}` - Create a document
+/// - `POST /_elastic/{index}/_update/{id}` - Partial update / upsert
+/// - `GET|POST /_elastic/_mget` - Multi-get
+/// - `GET|POST /_elastic/{index}/_mget` - Multi-get with default index
+/// - `GET|POST /_elastic/_count` - Count all indices
+/// - `GET|POST /_elastic/{index}/_count` - Count specific index
+///
+/// Alias updates are rejected by this router; use
+/// [`es_compat_router_with_aliases`] to enable them.
 pub fn es_compat_router(manager: Arc<CollectionManager>) -> Router {
-    let state = EsCompatState { manager };
+    build_router(manager, None, None, None)
+}
+
+/// Create the ES-compatible router with alias support
+///
+/// Index expressions resolve through `aliases` and the `_aliases` endpoints
+/// update them.
+pub fn es_compat_router_with_aliases(
+    manager: Arc<CollectionManager>,
+    aliases: Arc<AliasManager>,
+) -> Router {
+    build_router(manager, Some(aliases), None, None)
+}
+
+/// Create the ES-compatible router for a clustered server
+///
+/// Health, stats and `_cat` endpoints report the nodes and shard placement
+/// of `topology` rather than a single node.
+pub fn es_compat_router_with_topology(
+    manager: Arc<CollectionManager>,
+    aliases: Option<Arc<AliasManager>>,
+    topology: Arc<dyn ClusterTopology>,
+) -> Router {
+    build_router(manager, aliases, Some(topology), None)
+}
+
+/// Create the ES-compatible router sharing a server's ingest pipelines
+///
+/// Pipelines created through `/_ingest/pipeline` are added to `pipelines`,
+/// so native indexing can use them as well.
+pub fn es_compat_router_with_pipelines(
+    manager: Arc<CollectionManager>,
+    aliases: Option<Arc<AliasManager>>,
+    topology: Option<Arc<dyn ClusterTopology>>,
+    pipelines: Arc<PipelineRegistry>,
+) -> Router {
+    build_router(manager, aliases, topology, Some(pipelines))
+}
+
+fn build_router(
+    manager: Arc<CollectionManager>,
+    aliases: Option<Arc<AliasManager>>,
+    topology: Option<Arc<dyn ClusterTopology>>,
+    pipelines: Option<Arc<PipelineRegistry>>,
+) -> Router {
+    let scrolls = Arc::default();
+    spawn_reaper(&manager, &scrolls);
+    let state = EsCompatState {
+        manager,
+        versions: Arc::default(),
+        scrolls,
+        aliases,
+        topology,
+        ingest: Arc::new(pipelines.map(IngestPipelines::new).unwrap_or_default()),
+    };
 
     Router::new()
         // Cluster endpoints
         .route("/", get(root_handler))
         .route("/_cluster/health", get(cluster_health_handler))
-        .route("/_cat/indices", get(cat_indices_handler))
+        .route("/_cluster/stats", get(cluster_stats_handler))
+        .route("/_nodes/stats", get(nodes_stats_handler))
+        // Cat endpoints
+        .route("/_cat/indices", get(cat_indices_handler_no_index))
+        .route("/_cat/indices/:index", get(cat_indices_handler))
+        .route("/_cat/health", get(cat_health_handler))
+        .route("/_cat/count", get(cat_count_handler_no_index))
+        .route("/_cat/count/:index", get(cat_count_handler))
+        .route("/_cat/aliases", get(cat_aliases_handler_no_name))
+        .route("/_cat/aliases/:name", get(cat_aliases_handler))
+        .route("/_cat/shards", get(cat_shards_handler_no_index))
+        .route("/_cat/shards/:index", get(cat_shards_handler))
+        .route("/_cat/segments", get(cat_segments_handler_no_index))
+        .route("/_cat/segments/:index", get(cat_segments_handler))
         // Search endpoints
         .route("/_search", post(search_handler_no_index))
         .route("/:index/_search", post(search_handler))
+        // Scroll and point in time
+        .route(
+            "/_search/scroll",
+            get(scroll_handler)
+                .post(scroll_handler)
+                .delete(clear_scroll_handler),
+        )
+        .route("/_search/scroll/_all", delete(clear_all_scrolls_handler))
+        .route("/:index/_pit", post(open_pit_handler))
+        .route("/_pit", delete(close_pit_handler))
         // Multi-search
         .route("/_msearch", post(msearch_handler))
         // Bulk endpoints
         .route("/_bulk", post(bulk_handler_no_index))
         .route("/:index/_bulk", post(bulk_handler))
+        // Ingest pipeline endpoints
+        .route("/_ingest/pipeline", get(get_pipeline_handler_no_id))
+        .route(
+            "/_ingest/pipeline/:id",
+            get(get_pipeline_handler)
+                .put(put_pipeline_handler)
+                .delete(delete_pipeline_handler),
+        )
+        .route(
+            "/_ingest/pipeline/_simulate",
+            get(simulate_pipeline_handler_no_id).post(simulate_pipeline_handler_no_id),
+        )
+        .route(
+            "/_ingest/pipeline/:id/_simulate",
+            get(simulate_pipeline_handler).post(simulate_pipeline_handler),
+        )
+        // Index management endpoints
+        .route(
+            "/:index",
+            put(create_index_handler)
+                .head(head_index_handler)
+                .delete(delete_index_handler),
+        )
+        .route(
+            "/_aliases",
+            get(get_aliases_handler)
+                .post(update_aliases_handler)
+                .put(update_aliases_handler),
+        )
         // Mapping endpoints
-        .route("/:index/_mapping", get(mapping_handler))
+        .route(
+            "/:index/_mapping",
+            get(mapping_handler)
+                .put(put_mapping_handler)
+                .post(put_mapping_handler),
+        )
+        // Field discovery endpoints
+        .route(
+            "/_field_caps",
+            get(field_caps_handler_no_index).post(field_caps_handler_no_index),
+        )
+        .route(
+            "/:index/_field_caps",
+            get(field_caps_handler).post(field_caps_handler),
+        )
+        .route(
+            "/:index/_terms_enum",
+            get(terms_enum_handler).post(terms_enum_handler),
+        )
+        // Document endpoints
+        .route(
+            "/:index/_doc/:id",
+            put(index_doc_handler)
+                .post(index_doc_handler)
+                .get(get_doc_handler)
+                .head(head_doc_handler)
+                .delete(delete_doc_handler),
+        )
+        .route("/:index/_doc", post(index_doc_auto_id_handler))
+        .route(
+            "/:index/_create/:id",
+            put(create_doc_handler).post(create_doc_handler),
+        )
+        .route("/:index/_update/:id", post(update_doc_handler))
+        .route(
+            "/_mget",
+            get(mget_handler_no_index).post(mget_handler_no_index),
+        )
+        .route("/:index/_mget", get(mget_handler).post(mget_handler))
+        // Count endpoints
+        .route(
+            "/_count",
+            get(count_handler_no_index).post(count_handler_no_index),
+        )
+        .route("/:index/_count", get(count_handler).post(count_handler))
         .with_state(state)
 }
 
 // Wrapper handlers for routes without index parameter
+use crate::endpoints::bulk::BulkParams;
+use crate::endpoints::cat::CatParams;
+use crate::endpoints::document::CountParams;
+use crate::endpoints::field_caps::FieldCapsParams;
+use crate::endpoints::ingest::SimulateRequest;
+use crate::endpoints::search::SearchParams;
 use crate::error::EsCompatError;
-use crate::query::EsSearchRequest;
+use crate::query::{EsMgetRequest, EsSearchRequest};
 use crate::response::EsBulkResponse;
-use crate::response::EsSearchResponse;
+use crate::response::{EsCountResponse, EsFieldCapsResponse, EsMgetResponse, EsSearchResponse};
 use axum::body::Bytes;
-use axum::extract::State;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::Response;
 use axum::Json;
+use serde_json::Value;
 
 async fn search_handler_no_index(
     state: State<EsCompatState>,
+    params: Query<SearchParams>,
     body: Json<EsSearchRequest>,
 ) -> Result<Json<EsSearchResponse>, EsCompatError> {
-    search_handler(state, None, body).await
+    search_handler(state, None, params, body).await
 }
 
 async fn bulk_handler_no_index(
     state: State<EsCompatState>,
+    params: Query<BulkParams>,
     body: Bytes,
 ) -> Result<Json<EsBulkResponse>, EsCompatError> {
-    bulk_handler(state, None, body).await
+    bulk_handler(state, None, params, body).await
+}
+
+async fn get_pipeline_handler_no_id(
+    state: State<EsCompatState>,
+) -> Result<(StatusCode, Json<Value>), EsCompatError> {
+    get_pipeline_handler(state, None).await
+}
+
+async fn simulate_pipeline_handler_no_id(
+    state: State<EsCompatState>,
+    body: Json<SimulateRequest>,
+) -> Result<Json<Value>, EsCompatError> {
+    simulate_pipeline_handler(state, None, body).await
+}
+
+async fn mget_handler_no_index(
+    state: State<EsCompatState>,
+    body: Json<EsMgetRequest>,
+) -> Result<Json<EsMgetResponse>, EsCompatError> {
+    mget_handler(state, None, body).await
+}
+
+async fn count_handler_no_index(
+    state: State<EsCompatState>,
+    params: Query<CountParams>,
+    body: Bytes,
+) -> Result<Json<EsCountResponse>, EsCompatError> {
+    count_handler(state, None, params, body).await
+}
+
+async fn field_caps_handler_no_index(
+    state: State<EsCompatState>,
+    params: Query<FieldCapsParams>,
+    body: Bytes,
+) -> Result<Json<EsFieldCapsResponse>, EsCompatError> {
+    field_caps_handler(state, None, params, body).await
+}
+
+async fn cat_indices_handler_no_index(
+    state: State<EsCompatState>,
+    params: Query<CatParams>,
+) -> Result<Response, EsCompatError> {
+    cat_indices_handler(state, None, params).await
+}
+
+async fn cat_count_handler_no_index(
+    state: State<EsCompatState>,
+    params: Query<CatParams>,
+) -> Result<Response, EsCompatError> {
+    cat_count_handler(state, None, params).await
+}
+
+async fn cat_aliases_handler_no_name(
+    state: State<EsCompatState>,
+    params: Query<CatParams>,
+) -> Result<Response, EsCompatError> {
+    cat_aliases_handler(state, None, params).await
+}
+
+async fn cat_shards_handler_no_index(
+    state: State<EsCompatState>,
+    params: Query<CatParams>,
+) -> Result<Response, EsCompatError> {
+    cat_shards_handler(state, None, params).await
+}
+
+async fn cat_segments_handler_no_index(
+    state: State<EsCompatState>,
+    params: Query<CatParams>,
+) -> Result<Response, EsCompatError> {
+    cat_segments_handler(state, None, params).await
 }
 
 #[cfg(test)]
@@ -88,9 +376,60 @@ mod tests {
             .route("/:index/_search", post(|| async { StatusCode::OK }))
             .route("/_bulk", post(|| async { StatusCode::OK }))
             .route("/:index/_bulk", post(|| async { StatusCode::OK }))
-            .route("/:index/_mapping", get(|| async { StatusCode::OK }))
+            .route("/_ingest/pipeline", get(|| async { StatusCode::OK }))
+            .route(
+                "/_ingest/pipeline/:id",
+                get(|| async { StatusCode::OK })
+                    .put(|| async { StatusCode::OK })
+                    .delete(|| async { StatusCode::OK }),
+            )
+            .route(
+                "/_ingest/pipeline/_simulate",
+                post(|| async { StatusCode::OK }),
+            )
+            .route(
+                "/_ingest/pipeline/:id/_simulate",
+                post(|| async { StatusCode::OK }),
+            )
+            .route(
+                "/:index/_mapping",
+                get(|| async { StatusCode::OK }).put(|| async { StatusCode::OK }),
+            )
+            .route(
+                "/:index",
+                put(|| async { StatusCode::OK })
+                    .head(|| async { StatusCode::OK })
+                    .delete(|| async { StatusCode::OK }),
+            )
+            .route(
+                "/_aliases",
+                get(|| async { StatusCode::OK }).post(|| async { StatusCode::OK }),
+            )
             .route("/_cat/indices", get(|| async { StatusCode::OK }))
-            .route("/_cluster/health", get(|| async { StatusCode::OK }));
+            .route("/_cluster/health", get(|| async { StatusCode::OK }))
+            .route(
+                "/:index/_doc/:id",
+                put(|| async { StatusCode::OK })
+                    .get(|| async { StatusCode::OK })
+                    .delete(|| async { StatusCode::OK }),
+            )
+            .route("/:index/_doc", post(|| async { StatusCode::OK }))
+            .route("/:index/_create/:id", put(|| async { StatusCode::OK }))
+            .route("/:index/_update/:id", post(|| async { StatusCode::OK }))
+            .route("/_mget", post(|| async { StatusCode::OK }))
+            .route("/:index/_mget", post(|| async { StatusCode::OK }))
+            .route("/_count", get(|| async { StatusCode::OK }))
+            .route("/:index/_count", post(|| async { StatusCode::OK }))
+            .route("/_field_caps", get(|| async { StatusCode::OK }))
+            .route("/:index/_field_caps", get(|| async { StatusCode::OK }))
+            .route("/:index/_terms_enum", post(|| async { StatusCode::OK }))
+            .route(
+                "/_search/scroll",
+                post(|| async { StatusCode::OK }).delete(|| async { StatusCode::OK }),
+            )
+            .route("/_search/scroll/_all", delete(|| async { StatusCode::OK }))
+            .route("/:index/_pit", post(|| async { StatusCode::OK }))
+            .route("/_pit", delete(|| async { StatusCode::OK }));
 
         let cases = vec![
             ("POST", "/_search"),
@@ -98,9 +437,39 @@ mod tests {
             ("POST", "/logs-2024-01/_search"),
             ("POST", "/_bulk"),
             ("POST", "/my_index/_bulk"),
+            ("GET", "/_ingest/pipeline"),
+            ("PUT", "/_ingest/pipeline/my_pipeline"),
+            ("GET", "/_ingest/pipeline/my_pipeline"),
+            ("DELETE", "/_ingest/pipeline/my_pipeline"),
+            ("POST", "/_ingest/pipeline/_simulate"),
+            ("POST", "/_ingest/pipeline/my_pipeline/_simulate"),
             ("GET", "/my_index/_mapping"),
+            ("PUT", "/my_index/_mapping"),
+            ("PUT", "/my_index"),
+            ("HEAD", "/my_index"),
+            ("DELETE", "/my_index"),
+            ("GET", "/_aliases"),
+            ("POST", "/_aliases"),
             ("GET", "/_cat/indices"),
             ("GET", "/_cluster/health"),
+            ("PUT", "/my_index/_doc/1"),
+            ("GET", "/my_index/_doc/1"),
+            ("DELETE", "/my_index/_doc/1"),
+            ("POST", "/my_index/_doc"),
+            ("PUT", "/my_index/_create/1"),
+            ("POST", "/my_index/_update/1"),
+            ("POST", "/_mget"),
+            ("POST", "/my_index/_mget"),
+            ("GET", "/_count"),
+            ("POST", "/my_index/_count"),
+            ("GET", "/_field_caps"),
+            ("GET", "/my_index/_field_caps"),
+            ("POST", "/my_index/_terms_enum"),
+            ("POST", "/_search/scroll"),
+            ("DELETE", "/_search/scroll"),
+            ("DELETE", "/_search/scroll/_all"),
+            ("POST", "/my_index/_pit"),
+            ("DELETE", "/_pit"),
         ];
 
         for (method, path) in cases {
@@ -0,0 +1,71 @@
+//! Cluster topology reported by the ES-compatible monitoring endpoints
+//!
+//! `_cluster/health`, `_cluster/stats`, `_nodes/stats` and the `_cat` family
+//! describe nodes and shard copies. A standalone server is a single node that
+//! holds one primary shard per index; a clustered server supplies a
+//! [`ClusterTopology`] so that the endpoints report its membership and shard
+//! placement instead.
+
+/// Source of node membership and shard placement
+pub trait ClusterTopology: Send + Sync {
+    /// ID of the node serving the request; its shards are described from the
+    /// local collections.
+    fn local_node(&self) -> String;
+
+    /// Every known node
+    fn nodes(&self) -> Vec<TopologyNode>;
+
+    /// Every shard copy, primaries and replicas
+    fn shards(&self) -> Vec<TopologyShard>;
+}
+
+/// A cluster member
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopologyNode {
+    pub id: String,
+    /// Transport address, `host:port`
+    pub address: String,
+    /// Whether the node is currently reachable
+    pub healthy: bool,
+}
+
+/// One copy of a shard
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopologyShard {
+    pub index: String,
+    pub shard: u32,
+    pub primary: bool,
+    /// Node holding the copy; `None` while unassigned
+    pub node: Option<String>,
+    pub state: ShardRoutingState,
+    pub docs: u64,
+    pub size_bytes: u64,
+}
+
+/// Routing state of a shard copy, as ES names it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardRoutingState {
+    Started,
+    Initializing,
+    Relocating,
+    Unassigned,
+}
+
+impl ShardRoutingState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShardRoutingState::Started => "STARTED",
+            ShardRoutingState::Initializing => "INITIALIZING",
+            ShardRoutingState::Relocating => "RELOCATING",
+            ShardRoutingState::Unassigned => "UNASSIGNED",
+        }
+    }
+
+    /// Whether the copy serves requests
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            ShardRoutingState::Started | ShardRoutingState::Relocating
+        )
+    }
+}
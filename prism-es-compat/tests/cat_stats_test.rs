@@ -0,0 +1,318 @@
+//! Integration tests for the `_cat` family and the `_cluster/stats` and
+//! `_nodes/stats` endpoints, standalone and with a cluster topology.
+
+mod common;
+
+use axum::http::StatusCode;
+use axum::Router;
+use common::{call, call_raw, setup_manager};
+use prism::ilm::AliasManager;
+use prism_es_compat::topology::{ClusterTopology, ShardRoutingState, TopologyNode, TopologyShard};
+use prism_es_compat::{es_compat_router_with_aliases, es_compat_router_with_topology};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// Indices `logs` (3 docs) and `metrics` (1 doc)
+async fn populate(router: &Router) {
+    for index in ["logs", "metrics"] {
+        let (status, body) = call(
+            router,
+            "PUT",
+            &format!("/{}", index),
+            Some(json!({ "mappings": { "properties": { "message": { "type": "text" } } } })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+    for (index, id) in [
+        ("logs", "1"),
+        ("logs", "2"),
+        ("logs", "3"),
+        ("metrics", "1"),
+    ] {
+        let (status, body) = call(
+            router,
+            "PUT",
+            &format!("/{}/_doc/{}", index, id),
+            Some(json!({ "message": format!("event {}", id) })),
+        )
+        .await;
+        assert!(status.is_success(), "{body}");
+    }
+}
+
+async fn setup() -> (TempDir, Router) {
+    let (temp, manager) = setup_manager(&[]).await;
+    let aliases = Arc::new(AliasManager::new(&temp.path().join("data")).await.unwrap());
+    let router = es_compat_router_with_aliases(manager, aliases);
+    populate(&router).await;
+    (temp, router)
+}
+
+async fn get_text(router: &Router, uri: &str) -> String {
+    let (status, text) = call_raw(router, "GET", uri, None).await;
+    assert_eq!(status, StatusCode::OK, "{text}");
+    text
+}
+
+async fn get_json(router: &Router, uri: &str) -> Value {
+    let (status, body) = call(router, "GET", uri, None).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    body
+}
+
+#[tokio::test]
+async fn test_cat_indices_text_and_columns() {
+    let (_temp, router) = setup().await;
+
+    // Text without a header, one line per index
+    let text = get_text(&router, "/_cat/indices").await;
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("green open logs"), "{text}");
+
+    let text = get_text(&router, "/_cat/indices?v&h=index,dc,health").await;
+    assert_eq!(
+        text,
+        "index   docs.count health\nlogs    3          green\nmetrics 1          green\n"
+    );
+
+    let body = get_json(&router, "/_cat/indices/met*?format=json&h=i,docs.*").await;
+    assert_eq!(
+        body,
+        json!([{ "index": "metrics", "docs.count": "1", "docs.deleted": "0" }])
+    );
+
+    let body = get_json(
+        &router,
+        "/_cat/indices?format=json&h=pri.store.size&bytes=b",
+    )
+    .await;
+    assert!(body[0]["pri.store.size"]
+        .as_str()
+        .unwrap()
+        .parse::<u64>()
+        .is_ok());
+
+    let (status, body) = call(&router, "GET", "/_cat/indices?h=index,nope", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "illegal_argument_exception");
+
+    let (status, _) = call(&router, "GET", "/_cat/indices/missing", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_cat_health_count_and_aliases() {
+    let (_temp, router) = setup().await;
+
+    let body = get_json(&router, "/_cat/health?format=json").await;
+    let health = &body[0];
+    assert_eq!(health["cluster"], "prism");
+    assert_eq!(health["status"], "green");
+    assert_eq!(health["node.total"], "1");
+    assert_eq!(health["shards"], "2");
+    assert_eq!(health["unassign"], "0");
+    assert_eq!(health["active_shards_percent"], "100.0%");
+
+    let text = get_text(&router, "/_cat/count?h=count").await;
+    assert_eq!(text, "4\n");
+    let body = get_json(&router, "/_cat/count/logs?format=json").await;
+    assert_eq!(body[0]["count"], "3");
+    assert!(body[0]["epoch"].as_str().unwrap().parse::<i64>().is_ok());
+
+    let (status, body) = call(
+        &router,
+        "POST",
+        "/_aliases",
+        Some(json!({
+            "actions": [
+                { "add": { "index": "logs", "alias": "events", "is_write_index": true } },
+                { "add": { "index": "metrics", "alias": "all" } },
+                { "add": { "index": "logs", "alias": "all" } }
+            ]
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let body = get_json(
+        &router,
+        "/_cat/aliases?format=json&h=alias,index,is_write_index",
+    )
+    .await;
+    assert_eq!(
+        body,
+        json!([
+            { "alias": "all", "index": "logs", "is_write_index": "-" },
+            { "alias": "all", "index": "metrics", "is_write_index": "-" },
+            { "alias": "events", "index": "logs", "is_write_index": "true" }
+        ])
+    );
+    let text = get_text(&router, "/_cat/aliases/ev*?h=alias,index").await;
+    assert_eq!(text, "events logs\n");
+
+    // Counts resolve aliases
+    let body = get_json(&router, "/_cat/count/all?format=json").await;
+    assert_eq!(body[0]["count"], "4");
+}
+
+#[tokio::test]
+async fn test_cat_shards_and_segments_standalone() {
+    let (_temp, router) = setup().await;
+
+    let text = get_text(&router, "/_cat/shards?h=index,shard,prirep,state,docs,node").await;
+    assert_eq!(
+        text,
+        "logs    0 p STARTED 3 prism\nmetrics 0 p STARTED 1 prism\n"
+    );
+
+    let body = get_json(&router, "/_cat/segments/logs?format=json").await;
+    let segments = body.as_array().unwrap();
+    assert!(!segments.is_empty());
+    let docs: u64 = segments
+        .iter()
+        .map(|s| {
+            assert_eq!(s["index"], "logs");
+            assert_eq!(s["prirep"], "p");
+            s["docs.count"].as_str().unwrap().parse::<u64>().unwrap()
+        })
+        .sum();
+    assert_eq!(docs, 3);
+}
+
+#[tokio::test]
+async fn test_cluster_and_nodes_stats_standalone() {
+    let (_temp, router) = setup().await;
+
+    let body = get_json(&router, "/_cluster/stats").await;
+    assert_eq!(body["status"], "green");
+    assert_eq!(body["_nodes"]["total"], 1);
+    assert_eq!(body["indices"]["count"], 2);
+    assert_eq!(body["indices"]["shards"]["total"], 2);
+    assert_eq!(body["indices"]["shards"]["primaries"], 2);
+    assert_eq!(body["indices"]["docs"]["count"], 4);
+    assert!(body["indices"]["segments"]["count"].as_u64().unwrap() >= 2);
+    assert_eq!(body["nodes"]["count"]["total"], 1);
+
+    let body = get_json(&router, "/_nodes/stats").await;
+    assert_eq!(
+        body["_nodes"],
+        json!({ "total": 1, "successful": 1, "failed": 0 })
+    );
+    let node = &body["nodes"]["prism"];
+    assert_eq!(node["name"], "prism");
+    assert_eq!(node["indices"]["docs"]["count"], 4);
+    assert!(node["indices"]["store"]["size_in_bytes"].is_u64());
+}
+
+/// Two live nodes and one that stopped responding. `logs` has a primary on
+/// each live node and a replica that lost its node.
+struct StaticTopology;
+
+impl ClusterTopology for StaticTopology {
+    fn local_node(&self) -> String {
+        "node-a".to_string()
+    }
+
+    fn nodes(&self) -> Vec<TopologyNode> {
+        vec![
+            TopologyNode {
+                id: "node-a".to_string(),
+                address: "10.0.0.1:9300".to_string(),
+                healthy: true,
+            },
+            TopologyNode {
+                id: "node-b".to_string(),
+                address: "10.0.0.2:9300".to_string(),
+                healthy: true,
+            },
+            TopologyNode {
+                id: "node-c".to_string(),
+                address: "10.0.0.3:9300".to_string(),
+                healthy: false,
+            },
+        ]
+    }
+
+    fn shards(&self) -> Vec<TopologyShard> {
+        let copy = |shard, primary, node: Option<&str>, state| TopologyShard {
+            index: "logs".to_string(),
+            shard,
+            primary,
+            node: node.map(String::from),
+            state,
+            docs: 10,
+            size_bytes: 2048,
+        };
+        vec![
+            copy(0, true, Some("node-a"), ShardRoutingState::Started),
+            copy(1, true, Some("node-b"), ShardRoutingState::Started),
+            copy(1, false, None, ShardRoutingState::Unassigned),
+        ]
+    }
+}
+
+#[tokio::test]
+async fn test_endpoints_report_cluster_topology() {
+    let (_temp, manager) = setup_manager(&[]).await;
+    let router = es_compat_router_with_topology(manager, None, Arc::new(StaticTopology));
+    populate(&router).await;
+
+    let body = get_json(&router, "/_cluster/health").await;
+    assert_eq!(body["status"], "yellow");
+    assert_eq!(body["number_of_nodes"], 2);
+    // logs: two primaries; metrics is not placed yet and lives on node-a
+    assert_eq!(body["active_primary_shards"], 3);
+    assert_eq!(body["unassigned_shards"], 1);
+
+    // The local copy reports local documents, the remote copy its placement
+    let text = get_text(
+        &router,
+        "/_cat/shards/logs?v&h=index,shard,prirep,state,docs,ip,node",
+    )
+    .await;
+    assert_eq!(
+        text,
+        "index shard prirep state      docs ip       node\n\
+         logs  0     p      STARTED    3    10.0.0.1 node-a\n\
+         logs  1     p      STARTED    10   10.0.0.2 node-b\n\
+         logs  1     r      UNASSIGNED\n"
+    );
+
+    let body = get_json(
+        &router,
+        "/_cat/indices?format=json&h=index,health,pri,rep,docs.count",
+    )
+    .await;
+    assert_eq!(
+        body,
+        json!([
+            { "index": "logs", "health": "yellow", "pri": "2", "rep": "0", "docs.count": "13" },
+            { "index": "metrics", "health": "green", "pri": "1", "rep": "0", "docs.count": "1" }
+        ])
+    );
+
+    // Segments only cover the local node's shards
+    let body = get_json(&router, "/_cat/segments?format=json&h=index,shard,ip").await;
+    for segment in body.as_array().unwrap() {
+        assert_eq!(segment["ip"], "10.0.0.1");
+        assert_ne!(segment["shard"], "1");
+    }
+
+    let body = get_json(&router, "/_nodes/stats").await;
+    assert_eq!(
+        body["_nodes"],
+        json!({ "total": 3, "successful": 2, "failed": 1 })
+    );
+    assert_eq!(body["nodes"]["node-a"]["indices"]["docs"]["count"], 4);
+    assert_eq!(body["nodes"]["node-b"]["indices"]["docs"]["count"], 10);
+    assert_eq!(body["nodes"]["node-b"]["ip"], "10.0.0.2");
+    assert!(body["nodes"].get("node-c").is_none());
+
+    let body = get_json(&router, "/_cluster/stats").await;
+    assert_eq!(body["status"], "yellow");
+    assert_eq!(body["indices"]["shards"]["total"], 3);
+    assert_eq!(body["indices"]["docs"]["count"], 14);
+}
@@ -0,0 +1,129 @@
+//! Helpers shared by the ES-compatible API integration tests.
+//!
+//! Each test file is its own crate and uses only some of these.
+#![allow(dead_code)]
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use http_body_util::BodyExt;
+use prism::backends::text::TextBackend;
+use prism::backends::VectorBackend;
+use prism::collection::CollectionManager;
+use serde_json::Value;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+/// A collection manager over a temp dir, with `schemas` given as
+/// (collection, YAML schema)
+pub async fn setup_manager(schemas: &[(&str, &str)]) -> (TempDir, Arc<CollectionManager>) {
+    let temp = TempDir::new().unwrap();
+    let schemas_dir = temp.path().join("schemas");
+    std::fs::create_dir_all(&schemas_dir).unwrap();
+    std::fs::create_dir_all(temp.path().join("data")).unwrap();
+    for (collection, schema) in schemas {
+        std::fs::write(schemas_dir.join(format!("{}.yaml", collection)), schema).unwrap();
+    }
+
+    let manager = open_manager(temp.path()).await;
+    (temp, manager)
+}
+
+/// Open the collections of a dir made by `setup_manager`, as a restarted
+/// server would
+pub async fn open_manager(dir: &Path) -> Arc<CollectionManager> {
+    let data_dir = dir.join("data");
+    let text_backend = Arc::new(TextBackend::new(&data_dir).unwrap());
+    let vector_backend = Arc::new(VectorBackend::new(&data_dir).unwrap());
+    let manager = Arc::new(
+        CollectionManager::new(dir.join("schemas"), text_backend, vector_backend, None).unwrap(),
+    );
+    manager.initialize().await.unwrap();
+    manager
+}
+
+/// Send a request with an optional JSON body; returns the response body as text
+pub async fn call_raw(
+    router: &Router,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, String) {
+    send(
+        router,
+        method,
+        uri,
+        "application/json",
+        body.map(|b| b.to_string()),
+    )
+    .await
+}
+
+/// Send a request with an optional JSON body; returns the response body as
+/// JSON, or `Null` when empty
+pub async fn call(
+    router: &Router,
+    method: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let (status, text) = call_raw(router, method, uri, body).await;
+    (status, parse(&text))
+}
+
+/// Send newline-delimited JSON, as `_bulk` expects
+pub async fn call_ndjson(
+    router: &Router,
+    method: &str,
+    uri: &str,
+    lines: &[Value],
+) -> (StatusCode, Value) {
+    let (status, text) = call_ndjson_raw(router, method, uri, lines).await;
+    (status, parse(&text))
+}
+
+/// Send newline-delimited JSON; returns the response body as text
+pub async fn call_ndjson_raw(
+    router: &Router,
+    method: &str,
+    uri: &str,
+    lines: &[Value],
+) -> (StatusCode, String) {
+    let body = lines.iter().map(|line| format!("{}\n", line)).collect();
+    send(router, method, uri, "application/x-ndjson", Some(body)).await
+}
+
+async fn send(
+    router: &Router,
+    method: &str,
+    uri: &str,
+    content_type: &str,
+    body: Option<String>,
+) -> (StatusCode, String) {
+    let mut builder = Request::builder().method(method).uri(uri);
+    let body = match body {
+        Some(body) => {
+            builder = builder.header("content-type", content_type);
+            Body::from(body)
+        }
+        None => Body::empty(),
+    };
+    let resp = router
+        .clone()
+        .oneshot(builder.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = resp.status();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+fn parse(text: &str) -> Value {
+    if text.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_str(text).unwrap()
+    }
+}
@@ -0,0 +1,286 @@
+//! Integration tests for the ES-compatible document APIs against a real
+//! CollectionManager.
+
+mod common;
+
+use axum::http::StatusCode;
+use axum::Router;
+use common::{call, open_manager, setup_manager};
+use prism_es_compat::es_compat_router;
+use serde_json::json;
+use std::path::Path;
+use tempfile::TempDir;
+
+const PRODUCTS_SCHEMA: &str = r#"
+collection: products
+backends:
+  text:
+    fields:
+      - name: title
+        type: text
+        stored: true
+        indexed: true
+      - name: status
+        type: string
+        stored: true
+        indexed: true
+      - name: stock
+        type: i64
+        stored: true
+        indexed: true
+"#;
+
+async fn setup() -> (TempDir, Router) {
+    let (temp, manager) = setup_manager(&[("products", PRODUCTS_SCHEMA)]).await;
+    (temp, es_compat_router(manager))
+}
+
+/// Reopen the collections under `dir`, as a restarted server would
+async fn start(dir: &Path) -> Router {
+    es_compat_router(open_manager(dir).await)
+}
+
+#[tokio::test]
+async fn test_index_get_and_delete() {
+    let (_temp, router) = setup().await;
+
+    let doc = json!({ "title": "Blue widget", "status": "active", "stock": 3 });
+    let (status, body) = call(&router, "PUT", "/products/_doc/1", Some(doc.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["result"], "created");
+    assert_eq!(body["_version"], 1);
+    assert_eq!(body["_shards"]["successful"], 1);
+
+    let (status, body) = call(&router, "PUT", "/products/_doc/1", Some(doc)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["result"], "updated");
+    assert_eq!(body["_version"], 2);
+
+    let (status, body) = call(&router, "GET", "/products/_doc/1", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["found"], true);
+    assert_eq!(body["_version"], 2);
+    assert_eq!(body["_source"]["title"], "Blue widget");
+    assert!(body["_source"].get("id").is_none());
+
+    let (_, body) = call(&router, "GET", "/products/_doc/1?_source=title", None).await;
+    assert_eq!(body["_source"], json!({ "title": "Blue widget" }));
+
+    let (status, _) = call(&router, "HEAD", "/products/_doc/1", None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = call(&router, "DELETE", "/products/_doc/1", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["result"], "deleted");
+    assert_eq!(body["_version"], 3);
+
+    let (status, body) = call(&router, "GET", "/products/_doc/1", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["found"], false);
+
+    let (status, body) = call(&router, "DELETE", "/products/_doc/1", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["result"], "not_found");
+
+    let (status, body) = call(&router, "GET", "/missing/_doc/1", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["type"], "index_not_found_exception");
+}
+
+#[tokio::test]
+async fn test_versions_survive_restart() {
+    let (temp, router) = setup().await;
+    let doc = json!({ "title": "Blue widget", "status": "active", "stock": 3 });
+    call(&router, "PUT", "/products/_doc/1", Some(doc.clone())).await;
+    call(&router, "PUT", "/products/_doc/1", Some(doc.clone())).await;
+    let (_, body) = call(&router, "PUT", "/products/_doc/2", Some(doc.clone())).await;
+    assert_eq!(body["_seq_no"], 2);
+    drop(router);
+
+    let router = start(temp.path()).await;
+    let (_, body) = call(&router, "GET", "/products/_doc/1", None).await;
+    assert_eq!(body["_version"], 2);
+    assert_eq!(body["_seq_no"], 1);
+    assert!(body["_source"].get("_version").is_none());
+
+    // Sequence numbers continue after the highest stored one
+    let (_, body) = call(&router, "PUT", "/products/_doc/1", Some(doc)).await;
+    assert_eq!(body["_version"], 3);
+    assert_eq!(body["_seq_no"], 3);
+
+    let (_, body) = call(
+        &router,
+        "POST",
+        "/products/_update/2",
+        Some(json!({ "doc": { "stock": 4 } })),
+    )
+    .await;
+    assert_eq!(body["_version"], 2);
+    assert_eq!(body["_seq_no"], 4);
+
+    let (_, body) = call(&router, "POST", "/products/_search", Some(json!({}))).await;
+    for hit in body["hits"]["hits"].as_array().unwrap() {
+        assert!(hit["_source"].get("_version").is_none(), "{}", hit);
+        assert!(hit["_source"].get("_seq_no").is_none(), "{}", hit);
+    }
+}
+
+#[tokio::test]
+async fn test_create_and_auto_id() {
+    let (_temp, router) = setup().await;
+
+    let doc = json!({ "title": "Red widget" });
+    let (status, _) = call(&router, "PUT", "/products/_create/a", Some(doc.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = call(&router, "PUT", "/products/_create/a", Some(doc.clone())).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error"]["type"], "version_conflict_engine_exception");
+
+    let (status, _) = call(
+        &router,
+        "PUT",
+        "/products/_doc/a?op_type=create",
+        Some(doc.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, body) = call(&router, "POST", "/products/_doc", Some(doc)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = body["_id"].as_str().unwrap().to_string();
+    let (status, _) = call(&router, "GET", &format!("/products/_doc/{}", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_update_merge_noop_and_upsert() {
+    let (_temp, router) = setup().await;
+
+    let doc = json!({ "title": "Green widget", "status": "active", "stock": 3 });
+    call(&router, "PUT", "/products/_doc/1", Some(doc)).await;
+
+    let (status, body) = call(
+        &router,
+        "POST",
+        "/products/_update/1",
+        Some(json!({ "doc": { "stock": 5 } })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["result"], "updated");
+    assert_eq!(body["_version"], 2);
+
+    let (_, body) = call(&router, "GET", "/products/_doc/1", None).await;
+    assert_eq!(body["_source"]["stock"], 5);
+    assert_eq!(body["_source"]["title"], "Green widget");
+
+    let (_, body) = call(
+        &router,
+        "POST",
+        "/products/_update/1",
+        Some(json!({ "doc": { "stock": 5 } })),
+    )
+    .await;
+    assert_eq!(body["result"], "noop");
+    assert_eq!(body["_version"], 2);
+
+    let (status, body) = call(
+        &router,
+        "POST",
+        "/products/_update/2",
+        Some(json!({ "doc": { "stock": 1 } })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["type"], "document_missing_exception");
+
+    let (status, body) = call(
+        &router,
+        "POST",
+        "/products/_update/2",
+        Some(json!({ "doc": { "stock": 1 }, "upsert": { "title": "New", "stock": 0 } })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["result"], "created");
+    let (_, body) = call(&router, "GET", "/products/_doc/2", None).await;
+    assert_eq!(body["_source"]["stock"], 0);
+
+    let (status, _) = call(
+        &router,
+        "POST",
+        "/products/_update/3",
+        Some(json!({ "doc": { "title": "Doc upsert" }, "doc_as_upsert": true })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, body) = call(&router, "GET", "/products/_doc/3", None).await;
+    assert_eq!(body["_source"]["title"], "Doc upsert");
+
+    let (status, _) = call(
+        &router,
+        "POST",
+        "/products/_update/1",
+        Some(json!({ "script": { "source": "ctx._source.stock += 1" } })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_mget_and_count() {
+    let (_temp, router) = setup().await;
+
+    for (id, status) in [("1", "active"), ("2", "active"), ("3", "retired")] {
+        let doc = json!({ "title": format!("Widget {}", id), "status": status, "stock": 1 });
+        call(&router, "PUT", &format!("/products/_doc/{}", id), Some(doc)).await;
+    }
+
+    let (status, body) = call(
+        &router,
+        "POST",
+        "/_mget",
+        Some(json!({ "docs": [
+            { "_index": "products", "_id": "1" },
+            { "_index": "products", "_id": "9" },
+            { "_index": "missing", "_id": "1" },
+            { "_index": "products", "_id": "2", "_source": ["status"] }
+        ] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let docs = body["docs"].as_array().unwrap();
+    assert_eq!(docs[0]["found"], true);
+    assert_eq!(docs[1]["found"], false);
+    assert_eq!(docs[2]["error"]["type"], "index_not_found_exception");
+    assert_eq!(docs[3]["_source"], json!({ "status": "active" }));
+
+    let (_, body) = call(
+        &router,
+        "POST",
+        "/products/_mget",
+        Some(json!({ "ids": ["3", "1"] })),
+    )
+    .await;
+    let docs = body["docs"].as_array().unwrap();
+    assert_eq!(docs[0]["_id"], "3");
+    assert_eq!(docs[1]["_source"]["title"], "Widget 1");
+
+    let (status, body) = call(&router, "GET", "/products/_count", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["count"], 3);
+
+    let (_, body) = call(
+        &router,
+        "POST",
+        "/products/_count",
+        Some(json!({ "query": { "term": { "status": "active" } } })),
+    )
+    .await;
+    assert_eq!(body["count"], 2);
+
+    let (_, body) = call(&router, "GET", "/_count?q=status:retired", None).await;
+    assert_eq!(body["count"], 1);
+    assert_eq!(body["_shards"]["total"], 1);
+}
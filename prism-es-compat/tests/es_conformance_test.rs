@@ -0,0 +1,423 @@
+//! ES wire-protocol conformance suite.
+//!
+//! Replays the recorded client requests in `tests/fixtures/conformance`
+//! against an in-process router and diffs each response against the
+//! response Elasticsearch gives for the same request. Every fixture starts
+//! from an empty node, runs its `setup` datasets and then its steps in order.
+//!
+//! Expected bodies are matched by shape: objects must contain every expected
+//! key (extra keys are allowed), arrays must have the same length, and the
+//! placeholders `$any`, `$number`, `$string`, `$bool`, `$array` and
+//! `$object` match any value of that kind. A step may list `known_gaps`,
+//! JSON paths where Prism is known to differ; those differences are
+//! tolerated, and a gap that no longer differs fails the suite so the
+//! fixture gets updated. Steps with `"support": "rejected"` record a request
+//! Prism refuses and only check for a 4xx status.
+//!
+//! The steps' `features` feed a coverage report of which APIs, query and
+//! aggregation types are supported, partially supported or rejected. It is
+//! printed and written to `es_conformance.md` in the test target directory.
+
+mod common;
+
+use axum::Router;
+use common::{call_ndjson_raw, call_raw, setup_manager};
+use prism_es_compat::es_compat_router;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+const FIXTURES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/conformance");
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Fixture {
+    description: String,
+    /// Datasets from `datasets/` loaded before the steps
+    #[serde(default)]
+    setup: Vec<String>,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Step {
+    name: String,
+    /// Coverage keys, e.g. `query:match` or `api:_bulk`
+    #[serde(default)]
+    features: Vec<String>,
+    #[serde(default)]
+    support: Option<Support>,
+    /// JSON paths, e.g. `$.hits.hits[0]._ignored`, where Prism differs
+    #[serde(default)]
+    known_gaps: Vec<String>,
+    /// Why the step is partial or rejected
+    #[serde(default)]
+    note: Option<String>,
+    request: FixtureRequest,
+    response: FixtureResponse,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FixtureRequest {
+    method: String,
+    path: String,
+    #[serde(default)]
+    body: Option<Value>,
+    /// Newline-delimited body for `_bulk` and `_msearch`
+    #[serde(default)]
+    ndjson: Option<Vec<Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FixtureResponse {
+    status: u16,
+    #[serde(default)]
+    body: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Support {
+    Supported,
+    Partial,
+    Rejected,
+}
+
+impl Support {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Supported => "supported",
+            Self::Partial => "partial",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// A difference between the expected and the actual response
+#[derive(Debug, PartialEq)]
+struct Mismatch {
+    path: String,
+    message: String,
+}
+
+/// What the suite learned about one feature
+#[derive(Debug, Default)]
+struct Coverage {
+    support: Option<Support>,
+    notes: Vec<String>,
+}
+
+/// Send a fixture request; non-JSON response bodies come back as a string
+async fn send(router: &Router, request: &FixtureRequest) -> (u16, Value) {
+    let (method, path) = (request.method.as_str(), request.path.as_str());
+    let (status, text) = match &request.ndjson {
+        Some(lines) if request.body.is_none() => call_ndjson_raw(router, method, path, lines).await,
+        _ => call_raw(router, method, path, request.body.clone()).await,
+    };
+    let value = if text.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_str(&text).unwrap_or(Value::String(text))
+    };
+    (status.as_u16(), value)
+}
+
+/// Record where `actual` does not have the shape of `expected`
+fn diff_shape(expected: &Value, actual: &Value, path: &str, out: &mut Vec<Mismatch>) {
+    let mut mismatch = |message: String| {
+        out.push(Mismatch {
+            path: path.to_string(),
+            message,
+        })
+    };
+    match expected {
+        Value::String(placeholder) if placeholder.starts_with('$') => {
+            let matches = match placeholder.as_str() {
+                "$any" => true,
+                "$number" => actual.is_number(),
+                "$string" => actual.is_string(),
+                "$bool" => actual.is_boolean(),
+                "$array" => actual.is_array(),
+                "$object" => actual.is_object(),
+                other => panic!("unknown placeholder {} at {}", other, path),
+            };
+            if !matches {
+                mismatch(format!("expected {}, got {}", placeholder, actual));
+            }
+        }
+        Value::Object(fields) => match actual.as_object() {
+            Some(actual_fields) => {
+                for (key, value) in fields {
+                    let child = format!("{}.{}", path, key);
+                    match actual_fields.get(key) {
+                        Some(actual_value) => diff_shape(value, actual_value, &child, out),
+                        None => out.push(Mismatch {
+                            path: child,
+                            message: "missing".to_string(),
+                        }),
+                    }
+                }
+            }
+            None => mismatch(format!("expected an object, got {}", actual)),
+        },
+        Value::Array(items) => match actual.as_array() {
+            Some(actual_items) if actual_items.len() == items.len() => {
+                for (i, (item, actual_item)) in items.iter().zip(actual_items).enumerate() {
+                    diff_shape(item, actual_item, &format!("{}[{}]", path, i), out);
+                }
+            }
+            Some(actual_items) => mismatch(format!(
+                "expected {} items, got {}: {}",
+                items.len(),
+                actual_items.len(),
+                actual
+            )),
+            None => mismatch(format!("expected an array, got {}", actual)),
+        },
+        Value::Number(n) => {
+            let equal = match (n.as_f64(), actual.as_f64()) {
+                (Some(x), Some(y)) => (x - y).abs() <= 1e-6 * x.abs().max(1.0),
+                _ => false,
+            };
+            if !equal {
+                mismatch(format!("expected {}, got {}", expected, actual));
+            }
+        }
+        _ => {
+            if expected != actual {
+                mismatch(format!("expected {}, got {}", expected, actual));
+            }
+        }
+    }
+}
+
+/// Whether a known gap covers a mismatch: the same path or one of its parents
+fn gap_covers(gap: &str, path: &str) -> bool {
+    path.strip_prefix(gap)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.') || rest.starts_with('['))
+}
+
+fn load_json<T: for<'de> Deserialize<'de>>(path: &Path) -> T {
+    let text = std::fs::read_to_string(path).unwrap();
+    serde_json::from_str(&text).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+fn fixture_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(FIXTURES_DIR)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Replay one fixture, returning its failures
+async fn run_fixture(
+    path: &Path,
+    fixture: &Fixture,
+    coverage: &mut BTreeMap<String, Coverage>,
+) -> Vec<String> {
+    let (_temp, manager) = setup_manager(&[]).await;
+    let router = es_compat_router(manager);
+    let name = path.file_name().unwrap().to_string_lossy();
+    let mut failures = Vec::new();
+
+    for dataset in &fixture.setup {
+        let dataset_path = Path::new(FIXTURES_DIR)
+            .join("datasets")
+            .join(format!("{}.json", dataset));
+        let requests: Vec<FixtureRequest> = load_json(&dataset_path);
+        for request in &requests {
+            let (status, body) = send(&router, request).await;
+            assert!(
+                (200..300).contains(&status) && body.get("errors") != Some(&json!(true)),
+                "{}: dataset {} failed on {} {}: {}",
+                name,
+                dataset,
+                request.method,
+                request.path,
+                body
+            );
+        }
+    }
+
+    for step in &fixture.steps {
+        let context = format!("{} / {}", name, step.name);
+        let (status, body) = send(&router, &step.request).await;
+
+        let support = if step.support == Some(Support::Rejected) {
+            if !(400..500).contains(&status) {
+                failures.push(format!(
+                    "{}: expected a rejection, got {}: {}",
+                    context, status, body
+                ));
+            }
+            Support::Rejected
+        } else {
+            let mut mismatches = Vec::new();
+            if status != step.response.status {
+                mismatches.push(Mismatch {
+                    path: "status".to_string(),
+                    message: format!(
+                        "expected {}, got {}: {}",
+                        step.response.status, status, body
+                    ),
+                });
+            }
+            if let Some(expected) = &step.response.body {
+                diff_shape(expected, &body, "$", &mut mismatches);
+            }
+            for m in &mismatches {
+                if !step.known_gaps.iter().any(|gap| gap_covers(gap, &m.path)) {
+                    failures.push(format!("{}: {}: {}", context, m.path, m.message));
+                }
+            }
+            for gap in &step.known_gaps {
+                if !mismatches.iter().any(|m| gap_covers(gap, &m.path)) {
+                    failures.push(format!(
+                        "{}: known gap {} now conforms; remove it from the fixture",
+                        context, gap
+                    ));
+                }
+            }
+            if step.known_gaps.is_empty() {
+                Support::Supported
+            } else {
+                Support::Partial
+            }
+        };
+        if step.support.is_some_and(|declared| declared != support) {
+            failures.push(format!(
+                "{}: declared {} but replays as {}",
+                context,
+                step.support.unwrap().as_str(),
+                support.as_str()
+            ));
+        }
+
+        // A feature is as supported as its least supported step
+        for feature in &step.features {
+            let entry = coverage.entry(feature.clone()).or_default();
+            entry.support = entry.support.max(Some(support));
+            if let Some(note) = &step.note {
+                if !entry.notes.contains(note) {
+                    entry.notes.push(note.clone());
+                }
+            }
+        }
+    }
+    failures
+}
+
+fn coverage_report(coverage: &BTreeMap<String, Coverage>) -> String {
+    let mut report = String::from("# Elasticsearch conformance coverage\n");
+    let mut by_kind: BTreeMap<&str, Vec<(&str, &Coverage)>> = BTreeMap::new();
+    for (feature, entry) in coverage {
+        let (kind, name) = feature.split_once(':').unwrap_or(("other", feature));
+        by_kind.entry(kind).or_default().push((name, entry));
+    }
+    for (kind, features) in by_kind {
+        let count = |support| {
+            features
+                .iter()
+                .filter(|(_, e)| e.support == Some(support))
+                .count()
+        };
+        writeln!(
+            report,
+            "\n## {} ({} supported, {} partial, {} rejected)\n",
+            kind,
+            count(Support::Supported),
+            count(Support::Partial),
+            count(Support::Rejected)
+        )
+        .unwrap();
+        report.push_str("| Feature | Support | Notes |\n|---|---|---|\n");
+        for (name, entry) in features {
+            let support = entry.support.map(Support::as_str).unwrap_or("untested");
+            writeln!(
+                report,
+                "| `{}` | {} | {} |",
+                name,
+                support,
+                entry.notes.join(" ")
+            )
+            .unwrap();
+        }
+    }
+    report
+}
+
+#[tokio::test]
+async fn test_es_conformance() {
+    let mut coverage = BTreeMap::new();
+    let mut failures = Vec::new();
+
+    let files = fixture_files();
+    assert!(!files.is_empty(), "no fixtures in {}", FIXTURES_DIR);
+    for path in &files {
+        let fixture: Fixture = load_json(path);
+        assert!(!fixture.description.is_empty());
+        failures.extend(run_fixture(path, &fixture, &mut coverage).await);
+    }
+
+    let report = coverage_report(&coverage);
+    let report_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("es_conformance.md");
+    std::fs::write(&report_path, &report).unwrap();
+    println!(
+        "{}\nCoverage report written to {}",
+        report,
+        report_path.display()
+    );
+
+    assert!(
+        failures.is_empty(),
+        "{} conformance failures:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn test_diff_shape() {
+    let expected = json!({
+        "took": "$number",
+        "hits": { "total": { "value": 2, "relation": "eq" }, "hits": ["$object", "$object"] }
+    });
+    let mut out = Vec::new();
+    let actual = json!({
+        "took": 3,
+        "extra": true,
+        "hits": { "total": { "value": 2.0, "relation": "eq" }, "hits": [{}, {}] }
+    });
+    diff_shape(&expected, &actual, "$", &mut out);
+    assert!(out.is_empty(), "{:?}", out);
+
+    let actual = json!({ "took": "3", "hits": { "total": { "value": 1 }, "hits": [{}] } });
+    diff_shape(&expected, &actual, "$", &mut out);
+    let paths: Vec<&str> = out.iter().map(|m| m.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "$.hits.hits",
+            "$.hits.total.relation",
+            "$.hits.total.value",
+            "$.took"
+        ]
+    );
+}
+
+#[test]
+fn test_gap_covers() {
+    assert!(gap_covers("$.hits.total", "$.hits.total"));
+    assert!(gap_covers("$.hits.total", "$.hits.total.relation"));
+    assert!(gap_covers("$.hits", "$.hits[0]._id"));
+    assert!(!gap_covers("$.hits.total", "$.hits.total_hits"));
+    assert!(!gap_covers("$.hits.total.value", "$.hits.total"));
+}
@@ -0,0 +1,179 @@
+//! Integration tests for the ES-compatible _field_caps and _terms_enum APIs.
+
+mod common;
+
+use axum::http::StatusCode;
+use axum::Router;
+use common::{call, call_ndjson, setup_manager};
+use prism_es_compat::es_compat_router;
+use serde_json::json;
+use tempfile::TempDir;
+
+const LOGS_2024_SCHEMA: &str = r#"
+collection: logs-2024
+backends:
+  text:
+    fields:
+      - name: message
+        type: text
+        stored: true
+        indexed: true
+      - name: host
+        type: string
+        stored: true
+        indexed: true
+      - name: status
+        type: i64
+        stored: true
+        indexed: true
+        fast: true
+"#;
+
+const LOGS_2025_SCHEMA: &str = r#"
+collection: logs-2025
+backends:
+  text:
+    fields:
+      - name: message
+        type: text
+        stored: true
+        indexed: true
+      - name: host
+        type: string
+        stored: false
+        indexed: true
+      - name: status
+        type: string
+        stored: true
+        indexed: true
+"#;
+
+async fn setup() -> (TempDir, Router) {
+    let (temp, manager) = setup_manager(&[
+        ("logs-2024", LOGS_2024_SCHEMA),
+        ("logs-2025", LOGS_2025_SCHEMA),
+    ])
+    .await;
+
+    let router = es_compat_router(manager);
+    let bulk = [
+        json!({"index": {"_index": "logs-2024", "_id": "1"}}),
+        json!({"message": "disk full", "host": "web-01", "status": 500}),
+        json!({"index": {"_index": "logs-2024", "_id": "2"}}),
+        json!({"message": "ok", "host": "Web-02", "status": 200}),
+        json!({"index": {"_index": "logs-2025", "_id": "3"}}),
+        json!({"message": "ok", "host": "db-01", "status": "green"}),
+    ];
+    let (status, body) = call_ndjson(&router, "POST", "/_bulk", &bulk).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["errors"], false);
+
+    (temp, router)
+}
+
+#[tokio::test]
+async fn test_field_caps_single_index() {
+    let (_temp, router) = setup().await;
+
+    let (status, body) = call(&router, "GET", "/logs-2024/_field_caps?fields=*", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["indices"], json!(["logs-2024"]));
+    assert_eq!(
+        body["fields"]["message"]["text"],
+        json!({"type": "text", "metadata_field": false, "searchable": true, "aggregatable": false})
+    );
+    assert_eq!(body["fields"]["host"]["keyword"]["aggregatable"], true);
+    assert_eq!(body["fields"]["status"]["long"]["aggregatable"], true);
+    assert_eq!(body["fields"]["_id"]["_id"]["metadata_field"], true);
+}
+
+#[tokio::test]
+async fn test_field_caps_across_patterns() {
+    let (_temp, router) = setup().await;
+
+    let (status, body) = call(
+        &router,
+        "GET",
+        "/logs-*/_field_caps?fields=host,status",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["indices"], json!(["logs-2024", "logs-2025"]));
+    assert!(body["fields"].get("message").is_none());
+
+    // Same type everywhere, but only aggregatable where stored
+    let host = &body["fields"]["host"]["keyword"];
+    assert_eq!(host["aggregatable"], false);
+    assert!(host.get("indices").is_none());
+    assert_eq!(host["non_aggregatable_indices"], json!(["logs-2025"]));
+
+    // Conflicting types list their indices
+    let status_caps = &body["fields"]["status"];
+    assert_eq!(status_caps["long"]["indices"], json!(["logs-2024"]));
+    assert_eq!(status_caps["keyword"]["indices"], json!(["logs-2025"]));
+}
+
+#[tokio::test]
+async fn test_field_caps_filter_and_errors() {
+    let (_temp, router) = setup().await;
+
+    let body = json!({"index_filter": {"term": {"host": "db-01"}}});
+    let (status, body) = call(&router, "POST", "/_field_caps?fields=host", Some(body)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["indices"], json!(["logs-2025"]));
+
+    let (status, body) = call(&router, "GET", "/logs-2024/_field_caps", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "illegal_argument_exception");
+
+    let (status, _) = call(&router, "GET", "/missing/_field_caps?fields=*", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_terms_enum_prefix_and_case() {
+    let (_temp, router) = setup().await;
+
+    let request = json!({"field": "host", "string": "web"});
+    let (status, body) = call(&router, "POST", "/logs-*/_terms_enum", Some(request)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["terms"], json!(["web-01"]));
+    assert_eq!(body["complete"], true);
+    assert_eq!(body["_shards"]["total"], 2);
+
+    let request = json!({"field": "host", "string": "WEB", "case_insensitive": true});
+    let (_, body) = call(&router, "POST", "/logs-*/_terms_enum", Some(request)).await;
+    assert_eq!(body["terms"], json!(["Web-02", "web-01"]));
+
+    let request = json!({"field": "host", "size": 2});
+    let (_, body) = call(&router, "POST", "/logs-*/_terms_enum", Some(request)).await;
+    assert_eq!(body["terms"], json!(["Web-02", "db-01"]));
+
+    let request = json!({"field": "host", "search_after": "db-01"});
+    let (_, body) = call(&router, "POST", "/logs-*/_terms_enum", Some(request)).await;
+    assert_eq!(body["terms"], json!(["web-01"]));
+
+    // Text fields are not enumerated
+    let request = json!({"field": "message"});
+    let (_, body) = call(&router, "POST", "/logs-*/_terms_enum", Some(request)).await;
+    assert_eq!(body["terms"], json!([]));
+}
+
+#[tokio::test]
+async fn test_terms_enum_index_filter() {
+    let (_temp, router) = setup().await;
+
+    let request = json!({
+        "field": "host",
+        "index_filter": {"term": {"host": "db-01"}},
+    });
+    let (status, body) = call(&router, "POST", "/logs-*/_terms_enum", Some(request)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["terms"], json!(["db-01"]));
+    assert_eq!(body["_shards"]["skipped"], 1);
+
+    let request = json!({"field": "host"});
+    let (status, _) = call(&router, "POST", "/missing/_terms_enum", Some(request)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
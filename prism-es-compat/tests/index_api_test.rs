@@ -0,0 +1,227 @@
+//! Integration tests for the ES-compatible index management and alias APIs
+//! against a real CollectionManager.
+
+mod common;
+
+use axum::http::StatusCode;
+use axum::Router;
+use common::{call, setup_manager};
+use prism::collection::CollectionManager;
+use prism::ilm::AliasManager;
+use prism_es_compat::es_compat_router_with_aliases;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+async fn setup() -> (TempDir, Arc<CollectionManager>, Router) {
+    let (temp, manager) = setup_manager(&[]).await;
+    let aliases = Arc::new(AliasManager::new(&temp.path().join("data")).await.unwrap());
+    let router = es_compat_router_with_aliases(manager.clone(), aliases);
+    (temp, manager, router)
+}
+
+fn logs_mappings() -> Value {
+    json!({
+        "mappings": {
+            "properties": {
+                "message": { "type": "text" },
+                "level": { "type": "keyword" },
+                "bytes": { "type": "long" }
+            }
+        }
+    })
+}
+
+#[tokio::test]
+async fn test_create_index_with_mappings() {
+    let (_temp, manager, router) = setup().await;
+
+    let (status, body) = call(&router, "PUT", "/logs", Some(logs_mappings())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["acknowledged"], true);
+    assert_eq!(body["index"], "logs");
+    assert!(manager.collection_exists("logs"));
+
+    let (status, body) = call(&router, "GET", "/logs/_mapping", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let properties = &body["logs"]["mappings"]["properties"];
+    assert_eq!(properties["message"]["type"], "text");
+    assert_eq!(properties["level"]["type"], "keyword");
+    assert_eq!(properties["bytes"]["type"], "long");
+
+    let doc = json!({ "message": "disk full", "level": "error", "bytes": 512 });
+    let (status, _) = call(&router, "PUT", "/logs/_doc/1", Some(doc)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, body) = call(&router, "GET", "/logs/_count?q=level:error", None).await;
+    assert_eq!(body["count"], 1);
+
+    let (status, body) = call(&router, "PUT", "/logs", Some(logs_mappings())).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "resource_already_exists_exception");
+}
+
+#[tokio::test]
+async fn test_create_index_rejects_bad_input() {
+    let (_temp, _manager, router) = setup().await;
+
+    let (status, body) = call(&router, "PUT", "/Logs", Some(logs_mappings())).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "invalid_index_name_exception");
+
+    let (status, body) = call(&router, "PUT", "/empty", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "mapper_parsing_exception");
+
+    let nested = json!({
+        "mappings": { "properties": { "user": { "type": "nested" } } }
+    });
+    let (status, body) = call(&router, "PUT", "/nested", Some(nested)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "mapper_parsing_exception");
+}
+
+#[tokio::test]
+async fn test_head_and_delete_index() {
+    let (_temp, manager, router) = setup().await;
+    call(&router, "PUT", "/logs", Some(logs_mappings())).await;
+    let doc = json!({ "message": "hello", "level": "info", "bytes": 1 });
+    call(&router, "PUT", "/logs/_doc/1", Some(doc)).await;
+
+    let (status, _) = call(&router, "HEAD", "/logs", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = call(&router, "HEAD", "/missing", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = call(&router, "DELETE", "/logs*", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "illegal_argument_exception");
+    let (status, _) = call(&router, "DELETE", "/missing", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = call(&router, "DELETE", "/logs", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["acknowledged"], true);
+    assert!(!manager.collection_exists("logs"));
+    let (status, _) = call(&router, "HEAD", "/logs", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // A recreated index starts empty
+    call(&router, "PUT", "/logs", Some(logs_mappings())).await;
+    let (_, body) = call(&router, "GET", "/logs/_count", None).await;
+    assert_eq!(body["count"], 0);
+    let (status, _) = call(&router, "GET", "/logs/_doc/1", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_put_mapping_adds_fields() {
+    let (_temp, _manager, router) = setup().await;
+    call(&router, "PUT", "/logs", Some(logs_mappings())).await;
+    let doc = json!({ "message": "disk full", "level": "error", "bytes": 512 });
+    call(&router, "PUT", "/logs/_doc/1", Some(doc)).await;
+
+    let new_field = json!({ "properties": { "host": { "type": "keyword" } } });
+    let (status, body) = call(&router, "PUT", "/logs/_mapping", Some(new_field)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["acknowledged"], true);
+
+    // Existing documents survive the rebuild
+    let (status, body) = call(&router, "GET", "/logs/_doc/1", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["_source"]["message"], "disk full");
+
+    let doc = json!({ "message": "cpu hot", "level": "warn", "bytes": 8, "host": "web-1" });
+    call(&router, "PUT", "/logs/_doc/2", Some(doc)).await;
+    let (_, body) = call(&router, "GET", "/logs/_count?q=host:web-1", None).await;
+    assert_eq!(body["count"], 1);
+    let (_, body) = call(&router, "GET", "/logs/_count", None).await;
+    assert_eq!(body["count"], 2);
+
+    let (_, body) = call(&router, "GET", "/logs/_mapping", None).await;
+    assert_eq!(
+        body["logs"]["mappings"]["properties"]["host"]["type"],
+        "keyword"
+    );
+
+    let conflict = json!({ "properties": { "bytes": { "type": "text" } } });
+    let (status, body) = call(&router, "PUT", "/logs/_mapping", Some(conflict)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "illegal_argument_exception");
+}
+
+#[tokio::test]
+async fn test_aliases() {
+    let (_temp, _manager, router) = setup().await;
+    call(&router, "PUT", "/logs-1", Some(logs_mappings())).await;
+    call(&router, "PUT", "/logs-2", Some(logs_mappings())).await;
+    let doc = json!({ "message": "one", "level": "info", "bytes": 1 });
+    call(&router, "PUT", "/logs-1/_doc/1", Some(doc)).await;
+    let doc = json!({ "message": "two", "level": "info", "bytes": 2 });
+    call(&router, "PUT", "/logs-2/_doc/2", Some(doc)).await;
+
+    let actions = json!({
+        "actions": [
+            { "add": { "indices": ["logs-1", "logs-2"], "alias": "logs" } },
+            { "add": { "index": "logs-2", "alias": "logs-write", "is_write_index": true } }
+        ]
+    });
+    let (status, body) = call(&router, "POST", "/_aliases", Some(actions)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let (_, body) = call(&router, "GET", "/logs/_count", None).await;
+    assert_eq!(body["count"], 2);
+    let (status, _) = call(&router, "HEAD", "/logs", None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Single-document writes go through a single-target alias
+    let doc = json!({ "message": "three", "level": "info", "bytes": 3 });
+    let (status, _) = call(&router, "PUT", "/logs-write/_doc/3", Some(doc.clone())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (_, body) = call(&router, "GET", "/logs-2/_count", None).await;
+    assert_eq!(body["count"], 2);
+    let (status, _) = call(&router, "PUT", "/logs/_doc/4", Some(doc)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = call(&router, "GET", "/_aliases", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["logs-1"]["aliases"], json!({ "logs": {} }));
+    assert_eq!(
+        body["logs-2"]["aliases"]["logs-write"]["is_write_index"],
+        true
+    );
+
+    let bad = json!({ "actions": [{ "add": { "index": "logs-1", "alias": "logs-2" } }] });
+    let (status, _) = call(&router, "POST", "/_aliases", Some(bad)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = call(&router, "DELETE", "/logs", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let remove = json!({ "actions": [{ "remove": { "index": "logs-1", "alias": "logs" } }] });
+    let (status, _) = call(&router, "POST", "/_aliases", Some(remove)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = call(&router, "GET", "/logs/_count", None).await;
+    assert_eq!(body["count"], 2);
+
+    // Deleting an index drops it from every alias
+    call(&router, "DELETE", "/logs-2", None).await;
+    let (status, _) = call(&router, "HEAD", "/logs", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, body) = call(&router, "GET", "/_aliases", None).await;
+    assert_eq!(body, json!({ "logs-1": { "aliases": {} } }));
+}
+
+#[tokio::test]
+async fn test_create_index_with_aliases() {
+    let (_temp, _manager, router) = setup().await;
+    let mut request = logs_mappings();
+    request["aliases"] = json!({ "current": { "is_write_index": true } });
+
+    let (status, body) = call(&router, "PUT", "/logs-1", Some(request)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    let (_, body) = call(&router, "GET", "/_aliases", None).await;
+    assert_eq!(body["logs-1"]["aliases"]["current"]["is_write_index"], true);
+
+    let (status, body) = call(&router, "PUT", "/current", Some(logs_mappings())).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "invalid_index_name_exception");
+}
@@ -0,0 +1,225 @@
+//! Integration tests for the ES-compatible ingest pipeline API and
+//! pipeline application in `_bulk`.
+
+mod common;
+
+use axum::http::StatusCode;
+use axum::Router;
+use common::{call, call_ndjson, setup_manager};
+use prism::pipeline::registry::PipelineRegistry;
+use prism_es_compat::es_compat_router_with_pipelines;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+const LOGS_SCHEMA: &str = r#"
+collection: logs
+backends:
+  text:
+    fields:
+      - name: message
+        type: text
+        stored: true
+        indexed: true
+      - name: level
+        type: string
+        stored: true
+        indexed: true
+      - name: source
+        type: string
+        stored: true
+        indexed: true
+"#;
+
+async fn setup() -> (TempDir, Router, Arc<PipelineRegistry>) {
+    let (temp, manager) = setup_manager(&[("logs", LOGS_SCHEMA)]).await;
+    let registry = Arc::new(PipelineRegistry::empty());
+    let router = es_compat_router_with_pipelines(manager, None, None, registry.clone());
+    (temp, router, registry)
+}
+
+fn tidy_pipeline() -> Value {
+    json!({
+        "description": "normalise log lines",
+        "processors": [
+            {"lowercase": {"field": "level"}},
+            {"html_strip": {"field": "message"}},
+            {"set": {"field": "source", "value": "es"}},
+            {"remove": {"field": "debug", "ignore_missing": true}},
+        ]
+    })
+}
+
+#[tokio::test]
+async fn test_put_get_and_delete_pipeline() {
+    let (_temp, router, registry) = setup().await;
+
+    let (status, body) = call(
+        &router,
+        "PUT",
+        "/_ingest/pipeline/tidy",
+        Some(tidy_pipeline()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["acknowledged"], true);
+    // The shared registry serves native `?pipeline=` as well
+    assert_eq!(registry.get("tidy").unwrap().processors.len(), 4);
+
+    let (status, body) = call(&router, "GET", "/_ingest/pipeline/tidy", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["tidy"]["description"], "normalise log lines");
+    assert_eq!(body["tidy"]["processors"].as_array().unwrap().len(), 4);
+
+    let (_, body) = call(&router, "GET", "/_ingest/pipeline", None).await;
+    assert!(body.get("tidy").is_some());
+
+    let (status, body) = call(&router, "DELETE", "/_ingest/pipeline/tidy", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["acknowledged"], true);
+    assert!(registry.get("tidy").is_none());
+
+    let (status, body) = call(&router, "GET", "/_ingest/pipeline/tidy", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body, json!({}));
+
+    let (status, body) = call(&router, "DELETE", "/_ingest/pipeline/tidy", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["type"], "resource_not_found_exception");
+}
+
+#[tokio::test]
+async fn test_unsupported_processors_are_reported() {
+    let (_temp, router, registry) = setup().await;
+
+    let definition = json!({
+        "processors": [
+            {"grok": {"field": "message", "patterns": ["%{WORD:w}"]}},
+            {"lowercase": {"field": "level", "on_failure": []}},
+            {"uppercase": {"field": "level"}},
+        ]
+    });
+    let (status, body) = call(&router, "PUT", "/_ingest/pipeline/bad", Some(definition)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "illegal_argument_exception");
+    assert_eq!(
+        body["error"]["reason"],
+        "unsupported ingest processors: [grok], [lowercase.on_failure]"
+    );
+    assert!(registry.get("bad").is_none());
+}
+
+#[tokio::test]
+async fn test_simulate_stored_and_inline_pipeline() {
+    let (_temp, router, _registry) = setup().await;
+    call(
+        &router,
+        "PUT",
+        "/_ingest/pipeline/tidy",
+        Some(tidy_pipeline()),
+    )
+    .await;
+
+    let docs = json!([
+        {"_index": "logs", "_id": "1", "_source": {"level": "WARN", "message": "<p>disk</p>", "debug": 1}},
+        {"_source": {"message": "no level"}},
+    ]);
+    let (status, body) = call(
+        &router,
+        "POST",
+        "/_ingest/pipeline/tidy/_simulate",
+        Some(json!({ "docs": docs })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let first = &body["docs"][0]["doc"];
+    assert_eq!(first["_id"], "1");
+    assert_eq!(
+        first["_source"],
+        json!({"level": "warn", "message": "disk", "source": "es"})
+    );
+    assert!(first["_ingest"]["timestamp"].is_string());
+    // Failures are reported per document
+    assert_eq!(
+        body["docs"][1]["error"]["type"],
+        "illegal_argument_exception"
+    );
+
+    let request = json!({
+        "pipeline": {"processors": [{"rename": {"field": "msg", "target_field": "message"}}]},
+        "docs": [{"_source": {"msg": "hi"}}],
+    });
+    let (status, body) = call(
+        &router,
+        "POST",
+        "/_ingest/pipeline/_simulate",
+        Some(request),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["docs"][0]["doc"]["_index"], "_index");
+    assert_eq!(body["docs"][0]["doc"]["_source"], json!({"message": "hi"}));
+
+    let (status, _) = call(
+        &router,
+        "POST",
+        "/_ingest/pipeline/missing/_simulate",
+        Some(json!({ "docs": [] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_bulk_applies_pipelines() {
+    let (_temp, router, _registry) = setup().await;
+    call(
+        &router,
+        "PUT",
+        "/_ingest/pipeline/tidy",
+        Some(tidy_pipeline()),
+    )
+    .await;
+
+    let bulk = [
+        json!({"index": {"_index": "logs", "_id": "1"}}),
+        json!({"level": "ERROR", "message": "<b>boom</b>"}),
+        json!({"index": {"_index": "logs", "_id": "2", "pipeline": "_none"}}),
+        json!({"level": "INFO", "message": "<i>raw</i>"}),
+        json!({"index": {"_index": "logs", "_id": "3", "pipeline": "missing"}}),
+        json!({"level": "INFO", "message": "lost"}),
+        json!({"index": {"_index": "logs", "_id": "4"}}),
+        json!({"message": "no level"}),
+    ];
+
+    let (status, body) = call_ndjson(&router, "POST", "/_bulk?pipeline=tidy", &bulk).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["errors"], true);
+
+    let failed: Vec<(String, u64)> = body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|item| item["index"]["error"].is_object())
+        .map(|item| {
+            (
+                item["index"]["_id"].as_str().unwrap().to_string(),
+                item["index"]["status"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(failed.len(), 2);
+    assert!(failed.contains(&("3".to_string(), 400)));
+    assert!(failed.contains(&("4".to_string(), 400)));
+
+    let (_, body) = call(&router, "GET", "/logs/_doc/1", None).await;
+    assert_eq!(body["_source"]["level"], "error");
+    assert_eq!(body["_source"]["message"], "boom");
+    assert_eq!(body["_source"]["source"], "es");
+
+    let (_, body) = call(&router, "GET", "/logs/_doc/2", None).await;
+    assert_eq!(body["_source"]["level"], "INFO");
+
+    let (status, _) = call(&router, "GET", "/logs/_doc/3", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
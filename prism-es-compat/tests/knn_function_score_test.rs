@@ -0,0 +1,310 @@
+//! Integration tests for ES-compatible knn search and function_score /
+//! script_score queries against a real CollectionManager.
+
+mod common;
+
+use axum::http::StatusCode;
+use axum::Router;
+use common::{call, setup_manager};
+use prism_es_compat::es_compat_router;
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+async fn setup() -> (TempDir, Router) {
+    let (temp, manager) = setup_manager(&[]).await;
+    let router = es_compat_router(manager);
+    let (status, body) = call(
+        &router,
+        "PUT",
+        "/products",
+        Some(json!({
+            "mappings": {
+                "properties": {
+                    "title": { "type": "text" },
+                    "tag": { "type": "keyword" },
+                    "likes": { "type": "long" },
+                    "published": { "type": "date" },
+                    "embedding": { "type": "dense_vector", "dims": 3, "similarity": "cosine" }
+                }
+            }
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let docs = [
+        (
+            "1",
+            "red running shoes",
+            "sale",
+            10,
+            "2024-01-10T00:00:00Z",
+            [1.0, 0.0, 0.0],
+        ),
+        (
+            "2",
+            "blue running shoes",
+            "new",
+            100,
+            "2024-01-01T00:00:00Z",
+            [0.9, 0.1, 0.0],
+        ),
+        (
+            "3",
+            "green hiking boots",
+            "sale",
+            1,
+            "2023-06-01T00:00:00Z",
+            [0.0, 1.0, 0.0],
+        ),
+        (
+            "4",
+            "leather dress shoes",
+            "new",
+            50,
+            "2023-12-01T00:00:00Z",
+            [0.0, 0.0, 1.0],
+        ),
+    ];
+    for (id, title, tag, likes, published, embedding) in docs {
+        let doc = json!({
+            "title": title,
+            "tag": tag,
+            "likes": likes,
+            "published": published,
+            "embedding": embedding
+        });
+        let (status, body) =
+            call(&router, "PUT", &format!("/products/_doc/{}", id), Some(doc)).await;
+        assert!(status.is_success(), "{body}");
+    }
+    (temp, router)
+}
+
+async fn search(router: &Router, body: Value) -> Value {
+    let (status, body) = call(router, "POST", "/products/_search", Some(body)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    body
+}
+
+fn ids(body: &Value) -> Vec<&str> {
+    body["hits"]["hits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["_id"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_knn_search() {
+    let (_temp, router) = setup().await;
+
+    let body = search(
+        &router,
+        json!({
+            "knn": { "field": "embedding", "query_vector": [1.0, 0.0, 0.0], "k": 2, "num_candidates": 4 }
+        }),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["1", "2"]);
+    assert_eq!(body["hits"]["total"]["value"], 2);
+    assert_eq!(body["hits"]["hits"][0]["_index"], "products");
+    assert_eq!(
+        body["hits"]["hits"][0]["_source"]["title"],
+        "red running shoes"
+    );
+
+    // The filter runs before the best k are kept
+    let body = search(
+        &router,
+        json!({
+            "knn": {
+                "field": "embedding",
+                "query_vector": [1.0, 0.0, 0.0],
+                "k": 2,
+                "num_candidates": 4,
+                "filter": { "term": { "tag": "new" } }
+            }
+        }),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["2", "4"]);
+}
+
+#[tokio::test]
+async fn test_knn_validation() {
+    let (_temp, router) = setup().await;
+
+    let (status, _) = call(
+        &router,
+        "POST",
+        "/products/_search",
+        Some(json!({
+            "knn": { "field": "embedding", "query_vector": [1.0, 0.0, 0.0], "k": 5, "num_candidates": 2 }
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = call(
+        &router,
+        "POST",
+        "/products/_search",
+        Some(json!({
+            "knn": { "field": "title", "query_vector": [1.0, 0.0, 0.0], "k": 1 }
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // rrf needs two result sets
+    let (status, _) = call(
+        &router,
+        "POST",
+        "/products/_search",
+        Some(json!({
+            "knn": { "field": "embedding", "query_vector": [1.0, 0.0, 0.0], "k": 1 },
+            "rank": { "rrf": {} }
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_hybrid_query_and_knn() {
+    let (_temp, router) = setup().await;
+
+    // Summed scores: doc 4 matches the query and is the nearest neighbour
+    let body = search(
+        &router,
+        json!({
+            "query": { "match": { "title": "shoes" } },
+            "knn": { "field": "embedding", "query_vector": [0.0, 0.0, 1.0], "k": 1, "num_candidates": 4, "boost": 10 }
+        }),
+    )
+    .await;
+    assert_eq!(ids(&body)[0], "4");
+    assert_eq!(body["hits"]["total"]["value"], 3);
+
+    // With rrf, the knn-only hit joins the query hits
+    let body = search(
+        &router,
+        json!({
+            "query": { "match": { "title": "shoes" } },
+            "knn": { "field": "embedding", "query_vector": [0.0, 1.0, 0.0], "k": 1, "num_candidates": 4 },
+            "rank": { "rrf": { "rank_constant": 10 } },
+            "size": 10
+        }),
+    )
+    .await;
+    let hits = ids(&body);
+    assert_eq!(hits.len(), 4);
+    // Rank 1 in either list scores 1 / (10 + 1)
+    assert!(hits[..2].contains(&"3"));
+    assert_eq!(body["hits"]["total"]["value"], 4);
+    let score = body["hits"]["hits"][0]["_score"].as_f64().unwrap();
+    assert!((score - 1.0 / 11.0).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn test_function_score() {
+    let (_temp, router) = setup().await;
+
+    let body = search(
+        &router,
+        json!({
+            "query": {
+                "function_score": {
+                    "query": { "match": { "title": "shoes" } },
+                    "field_value_factor": { "field": "likes", "modifier": "log1p" },
+                    "boost_mode": "replace"
+                }
+            }
+        }),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["2", "4", "1"]);
+    let score = body["hits"]["hits"][0]["_score"].as_f64().unwrap();
+    assert!((score - 101f64.log10()).abs() < 1e-4);
+
+    // Filtered weights
+    let body = search(
+        &router,
+        json!({
+            "query": {
+                "function_score": {
+                    "query": { "match_all": {} },
+                    "functions": [
+                        { "filter": { "term": { "tag": "sale" } }, "weight": 5 },
+                        { "filter": { "match": { "title": "boots" } }, "weight": 2 }
+                    ],
+                    "score_mode": "sum",
+                    "boost_mode": "replace",
+                    "min_score": 2
+                }
+            }
+        }),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["3", "1"]);
+    assert_eq!(body["hits"]["hits"][0]["_score"], 7.0);
+    assert_eq!(body["hits"]["total"]["value"], 2);
+
+    // Date decay: the newest document wins
+    let body = search(
+        &router,
+        json!({
+            "query": {
+                "function_score": {
+                    "query": { "match": { "title": "shoes" } },
+                    "gauss": { "published": { "origin": "2024-01-10", "scale": "10d" } },
+                    "boost_mode": "replace"
+                }
+            }
+        }),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["1", "2", "4"]);
+    assert!((body["hits"]["hits"][0]["_score"].as_f64().unwrap() - 1.0).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn test_script_score() {
+    let (_temp, router) = setup().await;
+
+    let body = search(
+        &router,
+        json!({
+            "query": {
+                "script_score": {
+                    "query": { "match": { "title": "shoes" } },
+                    "script": {
+                        "source": "doc['likes'].value * params.factor",
+                        "params": { "factor": 2 }
+                    }
+                }
+            }
+        }),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["2", "4", "1"]);
+    assert_eq!(body["hits"]["hits"][0]["_score"], 200.0);
+
+    let (status, _) = call(
+        &router,
+        "POST",
+        "/products/_search",
+        Some(json!({
+            "query": {
+                "script_score": {
+                    "query": { "match_all": {} },
+                    "script": { "source": "cosineSimilarity(params.v, 'embedding')", "params": { "v": [1, 0, 0] } }
+                }
+            }
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,258 @@
+//! Integration tests for the ES query DSL additions (prefix matching,
+//! compound queries and more_like_this) against a real CollectionManager.
+
+mod common;
+
+use axum::http::StatusCode;
+use axum::Router;
+use common::{call, setup_manager};
+use prism_es_compat::es_compat_router;
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+async fn setup() -> (TempDir, Router) {
+    let (temp, manager) = setup_manager(&[]).await;
+    let router = es_compat_router(manager);
+    let (status, body) = call(
+        &router,
+        "PUT",
+        "/articles",
+        Some(json!({
+            "mappings": {
+                "properties": {
+                    "title": { "type": "text" },
+                    "body": { "type": "text" },
+                    "tag": { "type": "keyword" }
+                }
+            }
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+
+    let docs = [
+        (
+            "1",
+            "rust search engine",
+            "tantivy powers a rust search engine index",
+            "rust",
+        ),
+        (
+            "2",
+            "rust search library",
+            "a search library written in rust with tantivy index",
+            "rust",
+        ),
+        (
+            "3",
+            "cooking pasta",
+            "boil water and cook the pasta for ten minutes",
+            "food",
+        ),
+        (
+            "4",
+            "quick brown fox",
+            "the quick brown fox jumps over the lazy dog",
+            "animals",
+        ),
+    ];
+    for (id, title, body, tag) in docs {
+        let doc = json!({ "title": title, "body": body, "tag": tag });
+        let (status, body) =
+            call(&router, "PUT", &format!("/articles/_doc/{}", id), Some(doc)).await;
+        assert!(status.is_success(), "{body}");
+    }
+    (temp, router)
+}
+
+async fn search(router: &Router, query: Value) -> Value {
+    let (status, body) = call(
+        router,
+        "POST",
+        "/articles/_search",
+        Some(json!({ "query": query })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    body
+}
+
+fn sorted_ids(body: &Value) -> Vec<&str> {
+    let mut ids: Vec<&str> = body["hits"]["hits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["_id"].as_str().unwrap())
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn test_prefix_matching_queries() {
+    let (_temp, router) = setup().await;
+
+    let body = search(
+        &router,
+        json!({ "match_phrase_prefix": { "body": "quick brown f" } }),
+    )
+    .await;
+    assert_eq!(sorted_ids(&body), vec!["4"]);
+
+    // A single partial word only matches through prefix expansion
+    let body = search(&router, json!({ "match_phrase_prefix": { "body": "laz" } })).await;
+    assert_eq!(sorted_ids(&body), vec!["4"]);
+
+    let body = search(
+        &router,
+        json!({ "match_bool_prefix": { "body": { "query": "lazy jum", "operator": "and" } } }),
+    )
+    .await;
+    assert_eq!(sorted_ids(&body), vec!["4"]);
+
+    let body = search(&router, json!({ "prefix": { "body": "tanti" } })).await;
+    assert_eq!(sorted_ids(&body), vec!["1", "2"]);
+
+    let body = search(&router, json!({ "wildcard": { "body": "m?nut*" } })).await;
+    assert_eq!(sorted_ids(&body), vec!["3"]);
+}
+
+#[tokio::test]
+async fn test_fuzzy_and_regexp_queries() {
+    let (_temp, router) = setup().await;
+
+    // Misspelled terms only match through fuzzy expansion
+    let body = search(&router, json!({ "fuzzy": { "body": "quikc" } })).await;
+    assert_eq!(sorted_ids(&body), vec!["4"]);
+
+    let body = search(
+        &router,
+        json!({ "fuzzy": { "body": { "value": "pastx", "fuzziness": 1 } } }),
+    )
+    .await;
+    assert_eq!(sorted_ids(&body), vec!["3"]);
+
+    let body = search(
+        &router,
+        json!({ "fuzzy": { "body": { "value": "pastx", "fuzziness": 0 } } }),
+    )
+    .await;
+    assert!(sorted_ids(&body).is_empty(), "{body}");
+
+    let body = search(&router, json!({ "regexp": { "body": "qu.*k" } })).await;
+    assert_eq!(sorted_ids(&body), vec!["4"]);
+
+    let body = search(&router, json!({ "regexp": { "body": "tan[a-z]+y" } })).await;
+    assert_eq!(sorted_ids(&body), vec!["1", "2"]);
+
+    let body = search(
+        &router,
+        json!({ "regexp": { "body": { "value": "QU.*K", "case_insensitive": true } } }),
+    )
+    .await;
+    assert_eq!(sorted_ids(&body), vec!["4"]);
+}
+
+#[tokio::test]
+async fn test_compound_queries() {
+    let (_temp, router) = setup().await;
+
+    let body = search(
+        &router,
+        json!({
+            "dis_max": {
+                "queries": [
+                    { "match": { "title": "pasta" } },
+                    { "match": { "body": "fox" } }
+                ],
+                "tie_breaker": 0.3
+            }
+        }),
+    )
+    .await;
+    assert_eq!(sorted_ids(&body), vec!["3", "4"]);
+
+    let body = search(
+        &router,
+        json!({ "constant_score": { "filter": { "term": { "tag": "rust" } }, "boost": 2 } }),
+    )
+    .await;
+    assert_eq!(sorted_ids(&body), vec!["1", "2"]);
+
+    let body = search(
+        &router,
+        json!({
+            "nested": {
+                "path": "comments",
+                "query": { "match": { "title": "fox" } }
+            }
+        }),
+    )
+    .await;
+    assert_eq!(sorted_ids(&body), vec!["4"]);
+}
+
+#[tokio::test]
+async fn test_boosting_demotes_negative_matches() {
+    let (_temp, router) = setup().await;
+
+    let body = search(
+        &router,
+        json!({
+            "boosting": {
+                "positive": { "match": { "title": "rust" } },
+                "negative": { "match": { "body": "library" } },
+                "negative_boost": 0.01
+            }
+        }),
+    )
+    .await;
+    let hits = body["hits"]["hits"].as_array().unwrap();
+    assert_eq!(hits.len(), 2, "{body}");
+    assert_eq!(hits[0]["_id"], "1");
+    assert_eq!(hits[1]["_id"], "2");
+    assert!(hits[1]["_score"].as_f64().unwrap() < hits[0]["_score"].as_f64().unwrap() / 10.0);
+}
+
+#[tokio::test]
+async fn test_more_like_this() {
+    let (_temp, router) = setup().await;
+
+    let query = json!({
+        "more_like_this": {
+            "fields": ["title", "body"],
+            "like": [{ "_index": "articles", "_id": "1" }],
+            "min_term_freq": 1,
+            "min_doc_freq": 1
+        }
+    });
+    let body = search(&router, query.clone()).await;
+    let ids = sorted_ids(&body);
+    assert!(ids.contains(&"2"), "{body}");
+    assert!(!ids.contains(&"1"));
+    assert!(!ids.contains(&"3"));
+
+    // Counting resolves liked documents too
+    let (status, body) = call(
+        &router,
+        "POST",
+        "/articles/_count",
+        Some(json!({ "query": query })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["count"], ids.len());
+
+    let (status, _) = call(
+        &router,
+        "POST",
+        "/articles/_search",
+        Some(json!({
+            "query": {
+                "more_like_this": { "like": [{ "_index": "missing", "_id": "1" }] }
+            }
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
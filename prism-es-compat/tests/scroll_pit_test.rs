@@ -0,0 +1,219 @@
+//! Integration tests for ES-compatible scroll and point-in-time search:
+//! pages stay consistent while documents are added and deleted.
+
+mod common;
+
+use axum::http::StatusCode;
+use axum::Router;
+use common::{call, setup_manager};
+use prism_es_compat::es_compat_router;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use tempfile::TempDir;
+
+const EVENTS_SCHEMA: &str = r#"
+collection: events
+backends:
+  text:
+    fields:
+      - name: message
+        type: text
+        stored: true
+        indexed: true
+"#;
+
+async fn setup(docs: usize) -> (TempDir, Router) {
+    let (temp, manager) = setup_manager(&[("events", EVENTS_SCHEMA)]).await;
+    let router = es_compat_router(manager);
+    for i in 0..docs {
+        index_event(&router, &format!("e{}", i)).await;
+    }
+    (temp, router)
+}
+
+async fn index_event(router: &Router, id: &str) {
+    let (status, _) = call(
+        router,
+        "PUT",
+        &format!("/events/_doc/{}", id),
+        Some(json!({ "message": format!("event {}", id) })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+fn hit_ids(body: &Value) -> Vec<String> {
+    body["hits"]["hits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|h| h["_id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_scroll_is_consistent_while_indexing() {
+    let (_temp, router) = setup(25).await;
+
+    let (status, body) = call(
+        &router,
+        "POST",
+        "/events/_search?scroll=1m",
+        Some(json!({ "size": 10, "query": { "match_all": {} } })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["hits"]["total"]["value"], 25);
+    let scroll_id = body["_scroll_id"].as_str().unwrap().to_string();
+    let mut seen: Vec<String> = hit_ids(&body);
+    assert_eq!(seen.len(), 10);
+
+    // Writes after the scroll started are not visible to it
+    for i in 0..5 {
+        index_event(&router, &format!("late{}", i)).await;
+    }
+
+    loop {
+        let (status, body) = call(
+            &router,
+            "POST",
+            "/_search/scroll",
+            Some(json!({ "scroll": "1m", "scroll_id": scroll_id })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["_scroll_id"], scroll_id.as_str());
+        assert_eq!(body["hits"]["total"]["value"], 25);
+        let ids = hit_ids(&body);
+        if ids.is_empty() {
+            break;
+        }
+        seen.extend(ids);
+    }
+
+    let unique: HashSet<&String> = seen.iter().collect();
+    assert_eq!(seen.len(), 25);
+    assert_eq!(unique.len(), 25);
+    assert!(seen.iter().all(|id| id.starts_with('e')));
+
+    let (status, body) = call(
+        &router,
+        "DELETE",
+        "/_search/scroll",
+        Some(json!({ "scroll_id": [scroll_id] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["num_freed"], 1);
+
+    let (status, body) = call(
+        &router,
+        "GET",
+        &format!("/_search/scroll?scroll_id={}", scroll_id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["type"], "search_context_missing_exception");
+}
+
+#[tokio::test]
+async fn test_scroll_rejects_from_and_clear_all() {
+    let (_temp, router) = setup(3).await;
+
+    let (status, _) = call(
+        &router,
+        "POST",
+        "/events/_search?scroll=1m",
+        Some(json!({ "from": 5 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    for _ in 0..2 {
+        let (status, _) = call(
+            &router,
+            "POST",
+            "/_search?scroll=30s",
+            Some(json!({ "size": 1 })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, body) = call(&router, "DELETE", "/_search/scroll/_all", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["num_freed"], 2);
+}
+
+#[tokio::test]
+async fn test_pit_with_search_after() {
+    let (_temp, router) = setup(12).await;
+
+    let (status, _) = call(&router, "POST", "/events/_pit", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = call(&router, "POST", "/events/_pit?keep_alive=1m", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let pit_id = body["id"].as_str().unwrap().to_string();
+
+    // Deletes and inserts after opening are invisible to the point in time
+    call(&router, "DELETE", "/events/_doc/e0", None).await;
+    index_event(&router, "late").await;
+
+    let mut seen = Vec::new();
+    let mut search_after: Option<Value> = None;
+    loop {
+        let mut request = json!({
+            "size": 5,
+            "query": { "match_all": {} },
+            "pit": { "id": pit_id, "keep_alive": "1m" }
+        });
+        if let Some(after) = &search_after {
+            request["search_after"] = after.clone();
+        }
+        let (status, body) = call(&router, "POST", "/_search", Some(request)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["pit_id"], pit_id.as_str());
+        assert_eq!(body["hits"]["total"]["value"], 12);
+
+        let hits = body["hits"]["hits"].as_array().unwrap();
+        if hits.is_empty() {
+            break;
+        }
+        assert_eq!(hits[0]["_index"], "events");
+        search_after = Some(hits.last().unwrap()["sort"].clone());
+        seen.extend(hit_ids(&body));
+    }
+
+    let unique: HashSet<&String> = seen.iter().collect();
+    assert_eq!(unique.len(), 12);
+    assert!(seen.contains(&"e0".to_string()));
+    assert!(!seen.contains(&"late".to_string()));
+
+    // The indices come from the point in time
+    let (status, _) = call(
+        &router,
+        "POST",
+        "/events/_search",
+        Some(json!({ "pit": { "id": pit_id } })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = call(&router, "DELETE", "/_pit", Some(json!({ "id": pit_id }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["num_freed"], 1);
+
+    let (status, body) = call(&router, "DELETE", "/_pit", Some(json!({ "id": pit_id }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["num_freed"], 0);
+
+    let (status, _) = call(
+        &router,
+        "POST",
+        "/_search",
+        Some(json!({ "pit": { "id": pit_id } })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,293 @@
+//! Integration tests for how ES-compatible search responses honour the
+//! request: sort, `_source` filtering, `fields`, highlight, result window
+//! limits and `track_total_hits`.
+
+mod common;
+
+use axum::http::StatusCode;
+use axum::Router;
+use common::{call, setup_manager};
+use prism_es_compat::es_compat_router;
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+async fn setup() -> (TempDir, Router) {
+    let (temp, manager) = setup_manager(&[]).await;
+    let router = es_compat_router(manager);
+    for index in ["books", "films"] {
+        let (status, body) = call(
+            &router,
+            "PUT",
+            &format!("/{}", index),
+            Some(json!({
+                "mappings": {
+                    "properties": {
+                        "title": { "type": "text" },
+                        "summary": { "type": "text" },
+                        "genre": { "type": "keyword" },
+                        "year": { "type": "long" }
+                    }
+                }
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "{body}");
+    }
+
+    let docs = [
+        (
+            "books",
+            "1",
+            json!({
+                "title": "the rust programming language",
+                "summary": "an introduction to rust. ownership and borrowing explained. rust for systems work",
+                "genre": "programming",
+                "year": 2018
+            }),
+        ),
+        (
+            "books",
+            "2",
+            json!({
+                "title": "dune",
+                "summary": "a desert planet and its spice",
+                "genre": "fiction",
+                "year": 1965
+            }),
+        ),
+        (
+            "books",
+            "3",
+            json!({
+                "title": "programming rust",
+                "summary": "fast and safe systems development",
+                "genre": "programming"
+            }),
+        ),
+        (
+            "films",
+            "4",
+            json!({
+                "title": "dune part one",
+                "summary": "the desert planet on screen",
+                "genre": "fiction",
+                "year": 2021
+            }),
+        ),
+    ];
+    for (index, id, doc) in docs {
+        let (status, body) = call(
+            &router,
+            "PUT",
+            &format!("/{}/_doc/{}", index, id),
+            Some(doc),
+        )
+        .await;
+        assert!(status.is_success(), "{body}");
+    }
+    (temp, router)
+}
+
+async fn search(router: &Router, index: &str, body: Value) -> Value {
+    let (status, body) = call(router, "POST", &format!("/{}/_search", index), Some(body)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    body
+}
+
+fn ids(body: &Value) -> Vec<&str> {
+    body["hits"]["hits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hit| hit["_id"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_sort_with_missing_values() {
+    let (_temp, router) = setup().await;
+
+    let body = search(&router, "books", json!({ "sort": [{ "year": "asc" }] })).await;
+    assert_eq!(ids(&body), vec!["2", "1", "3"]);
+    assert_eq!(body["hits"]["hits"][0]["sort"], json!([1965]));
+    assert_eq!(body["hits"]["hits"][2]["sort"], json!([null]));
+    // Scores are not computed for field sorts unless asked for
+    assert!(body["hits"]["hits"][0]["_score"].is_null());
+    assert!(body["hits"]["max_score"].is_null());
+
+    let body = search(
+        &router,
+        "books",
+        json!({ "sort": [{ "year": { "order": "desc", "missing": "_first" } }] }),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["3", "1", "2"]);
+
+    let body = search(
+        &router,
+        "books",
+        json!({ "sort": [{ "year": { "order": "asc", "missing": 2000 } }], "track_scores": true }),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["2", "3", "1"]);
+    assert!(body["hits"]["hits"][0]["_score"].is_number());
+
+    // Keyword sort with a tie broken by the next key
+    let body = search(
+        &router,
+        "books",
+        json!({ "sort": [{ "genre": "desc" }, { "year": "desc" }] }),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["1", "3", "2"]);
+}
+
+#[tokio::test]
+async fn test_sort_unmapped_and_multi_index() {
+    let (_temp, router) = setup().await;
+
+    let (status, body) = call(
+        &router,
+        "POST",
+        "/books/_search",
+        Some(json!({ "sort": [{ "rating": "desc" }] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    assert_eq!(body["error"]["type"], "query_shard_exception");
+
+    let body = search(
+        &router,
+        "books",
+        json!({ "sort": [{ "rating": { "order": "desc", "unmapped_type": "long" } }, { "year": "asc" }] }),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["2", "1", "3"]);
+
+    // Hits from both indices merge on their sort values
+    let body = search(
+        &router,
+        "books,films",
+        json!({ "query": { "match": { "summary": "desert" } }, "sort": [{ "year": "desc" }] }),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["4", "2"]);
+    assert_eq!(body["hits"]["hits"][0]["_index"], "films");
+    assert_eq!(body["hits"]["total"]["value"], 2);
+
+    let body = search(
+        &router,
+        "books,films",
+        json!({ "sort": [{ "year": "desc" }], "from": 1, "size": 2 }),
+    )
+    .await;
+    assert_eq!(ids(&body), vec!["1", "2"]);
+}
+
+#[tokio::test]
+async fn test_source_filtering_and_fields() {
+    let (_temp, router) = setup().await;
+
+    let body = search(
+        &router,
+        "books",
+        json!({
+            "query": { "term": { "genre": "fiction" } },
+            "_source": { "includes": ["t*", "year"], "excludes": ["*le"] }
+        }),
+    )
+    .await;
+    assert_eq!(body["hits"]["hits"][0]["_source"], json!({ "year": 1965 }));
+
+    let body = search(
+        &router,
+        "books",
+        json!({
+            "query": { "term": { "genre": "fiction" } },
+            "_source": false,
+            "fields": ["gen*", { "field": "title" }],
+            "docvalue_fields": ["year"]
+        }),
+    )
+    .await;
+    let hit = &body["hits"]["hits"][0];
+    assert!(hit.get("_source").is_none());
+    assert_eq!(hit["fields"]["genre"], json!(["fiction"]));
+    assert_eq!(hit["fields"]["year"], json!([1965]));
+    assert_eq!(hit["fields"]["title"], json!(["dune"]));
+}
+
+#[tokio::test]
+async fn test_highlight_options() {
+    let (_temp, router) = setup().await;
+
+    let body = search(
+        &router,
+        "books",
+        json!({
+            "query": { "match": { "summary": "rust" } },
+            "highlight": {
+                "pre_tags": ["<b>"],
+                "post_tags": ["</b>"],
+                "fields": { "sum*": {} }
+            }
+        }),
+    )
+    .await;
+    let fragment = body["hits"]["hits"][0]["highlight"]["summary"][0]
+        .as_str()
+        .unwrap();
+    assert!(fragment.contains("<b>rust</b>"), "{fragment}");
+
+    // No fragmenting: the whole value comes back
+    let body = search(
+        &router,
+        "books",
+        json!({
+            "query": { "match": { "summary": "rust" } },
+            "highlight": {
+                "fields": { "summary": { "number_of_fragments": 0 } },
+                "fragment_size": 10
+            }
+        }),
+    )
+    .await;
+    let fragments = body["hits"]["hits"][0]["highlight"]["summary"]
+        .as_array()
+        .unwrap();
+    assert_eq!(fragments.len(), 1);
+    let fragment = fragments[0].as_str().unwrap();
+    assert!(fragment.starts_with("an introduction to <em>rust</em>"));
+    assert!(fragment.ends_with("<em>rust</em> for systems work"));
+}
+
+#[tokio::test]
+async fn test_result_window_and_total_hits() {
+    let (_temp, router) = setup().await;
+
+    let (status, body) = call(
+        &router,
+        "POST",
+        "/books/_search",
+        Some(json!({ "from": 9_995, "size": 10 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["type"], "illegal_argument_exception");
+
+    let body = search(&router, "books", json!({})).await;
+    assert_eq!(
+        body["hits"]["total"],
+        json!({ "value": 3, "relation": "eq" })
+    );
+
+    let body = search(&router, "books", json!({ "track_total_hits": 2 })).await;
+    assert_eq!(
+        body["hits"]["total"],
+        json!({ "value": 2, "relation": "gte" })
+    );
+
+    let body = search(&router, "books", json!({ "track_total_hits": false })).await;
+    assert!(body["hits"].get("total").is_none());
+    assert_eq!(ids(&body).len(), 3);
+}
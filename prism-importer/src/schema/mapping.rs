@@ -1,8 +1,11 @@
 use super::types::{SourceField, SourceFieldType, SourceSchema};
 use crate::error::Result;
+use prism::schema::es_mapping::EsFieldKind;
 use serde::Deserialize;
 use std::collections::HashMap;
 
+pub use prism::schema::es_mapping::{EsFieldMapping, EsMappings};
+
 /// Elasticsearch mapping response structure
 #[derive(Debug, Deserialize)]
 pub struct EsMappingResponse {
@@ -15,22 +18,6 @@ pub struct EsIndexMapping {
     pub mappings: EsMappings,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct EsMappings {
-    #[serde(default)]
-    pub properties: HashMap<String, EsFieldMapping>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct EsFieldMapping {
-    #[serde(rename = "type")]
-    pub field_type: Option<String>,
-    #[serde(default)]
-    pub properties: Option<HashMap<String, EsFieldMapping>>,
-    pub dims: Option<usize>,
-    pub index: Option<bool>,
-}
-
 /// Convert Elasticsearch mapping to SourceSchema
 pub fn convert_es_mapping(index_name: &str, mapping: &EsMappings) -> Result<SourceSchema> {
     let mut fields = Vec::new();
@@ -47,24 +34,22 @@ pub fn convert_es_mapping(index_name: &str, mapping: &EsMappings) -> Result<Sour
 }
 
 fn convert_field(name: &str, prop: &EsFieldMapping) -> Result<SourceField> {
-    let es_type = prop.field_type.as_deref().unwrap_or("object");
-
-    let (field_type, vector_dims) = match es_type {
-        "text" | "match_only_text" => (SourceFieldType::Text, None),
-        "keyword" | "constant_keyword" | "wildcard" => (SourceFieldType::Keyword, None),
-        "long" | "integer" | "short" | "byte" => (SourceFieldType::I64, None),
-        "float" | "double" | "half_float" | "scaled_float" => (SourceFieldType::F64, None),
-        "boolean" => (SourceFieldType::Bool, None),
-        "date" | "date_nanos" => (SourceFieldType::Date, None),
-        "dense_vector" => (SourceFieldType::Vector, prop.dims),
-        "object" | "nested" | "flattened" => (SourceFieldType::Json, None),
-        other => {
+    let (field_type, vector_dims) = match prop.kind() {
+        EsFieldKind::Text => (SourceFieldType::Text, None),
+        EsFieldKind::Keyword => (SourceFieldType::Keyword, None),
+        EsFieldKind::Integer => (SourceFieldType::I64, None),
+        EsFieldKind::Float => (SourceFieldType::F64, None),
+        EsFieldKind::Boolean => (SourceFieldType::Bool, None),
+        EsFieldKind::Date => (SourceFieldType::Date, None),
+        EsFieldKind::DenseVector => (SourceFieldType::Vector, prop.dims),
+        EsFieldKind::Object => (SourceFieldType::Json, None),
+        EsFieldKind::Unknown(other) => {
             tracing::warn!(
                 "Unknown ES type '{}' for field '{}', mapping to text",
                 other,
                 name
             );
-            (SourceFieldType::Unknown(other.to_string()), None)
+            (SourceFieldType::Unknown(other), None)
         }
     };
 
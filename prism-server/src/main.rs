@@ -339,6 +339,10 @@ async fn main() -> Result<()> {
     #[allow(unused_mut)]
     let mut extension_router: axum::Router<()> = axum::Router::new();
 
+    // Cluster view reported by the ES-compat health, stats and _cat endpoints
+    #[cfg(all(feature = "cluster", feature = "es-compat"))]
+    let mut es_topology: Option<Arc<dyn prism_es_compat::topology::ClusterTopology>> = None;
+
     // Start cluster RPC server and federated search if enabled
     #[cfg(feature = "cluster")]
     if config.cluster.enabled {
@@ -357,18 +361,25 @@ async fn main() -> Result<()> {
                 ca_cert_path: config.cluster.tls.ca_cert_path.clone(),
                 skip_verify: config.cluster.tls.skip_verify,
             },
+            op_log: prism_cluster::OperationLogConfig {
+                state_dir: Some(config.storage.data_dir.join("cluster")),
+                ..Default::default()
+            },
+            metadata: prism_cluster::MetadataConfig {
+                state_dir: Some(config.storage.data_dir.join("cluster")),
+                ..Default::default()
+            },
             ..Default::default()
         };
 
         // 1. Create shared cluster state
         let cluster_state = Arc::new(prism_cluster::ClusterState::new());
 
-        // 2. Register self node
+        // 2. Describe this node; it joins the cluster through the metadata log
         let self_node_id = cluster_config.node_id.clone();
-        let self_addr = cluster_config.advertise_address().to_string();
-        cluster_state.register_node(prism_cluster::NodeInfo {
+        let self_node = prism_cluster::NodeInfo {
             node_id: self_node_id.clone(),
-            address: self_addr.clone(),
+            address: cluster_config.advertise_address().to_string(),
             topology: prism_cluster::NodeTopology::default(),
             healthy: true,
             shard_count: 0,
@@ -376,43 +387,11 @@ async fn main() -> Result<()> {
             disk_total_bytes: 0,
             index_size_bytes: 0,
             draining: false,
-        });
-
-        // 3. Register seed nodes (use address as node_id until we discover their real ID)
-        let mut all_node_ids = vec![self_node_id.clone()];
-        for seed in &cluster_config.seed_nodes {
-            let seed_id = seed.clone();
-            cluster_state.register_node(prism_cluster::NodeInfo {
-                node_id: seed_id.clone(),
-                address: seed.clone(),
-                topology: prism_cluster::NodeTopology::default(),
-                healthy: true,
-                shard_count: 0,
-                disk_used_bytes: 0,
-                disk_total_bytes: 0,
-                index_size_bytes: 0,
-                draining: false,
-            });
-            all_node_ids.push(seed_id);
-        }
-
-        // 4. Auto-assign 1 shard per node per collection
-        let collections = server.manager().list_collections();
-        for collection in &collections {
-            for (i, node_id) in all_node_ids.iter().enumerate() {
-                let mut assignment =
-                    prism_cluster::ShardAssignment::new(collection, i as u32, node_id);
-                assignment.state = prism_cluster::ShardState::Active;
-                cluster_state.assign_shard(assignment);
-            }
-        }
-
-        tracing::info!(
-            "Cluster initialized: {} nodes, {} collections, {} total shards",
-            all_node_ids.len(),
-            collections.len(),
-            all_node_ids.len() * collections.len()
-        );
+        };
+        let mut member_addresses = cluster_config.seed_nodes.clone();
+        member_addresses.push(self_node.address.clone());
+        member_addresses.sort();
+        member_addresses.dedup();
 
         // 5. Create ClusterClient
         let cluster_client = match prism_cluster::ClusterClient::new(cluster_config.clone()).await {
@@ -424,30 +403,87 @@ async fn main() -> Result<()> {
         };
 
         // 6. Create FederatedSearch
-        let federation = Arc::new(prism_cluster::FederatedSearch::new(
-            cluster_client,
-            Arc::clone(&cluster_state),
-            prism_cluster::FederationConfig::default(),
-        ));
+        let federation = Arc::new(
+            prism_cluster::FederatedSearch::new(
+                cluster_client,
+                Arc::clone(&cluster_state),
+                prism_cluster::FederationConfig::default(),
+            )
+            .with_embedder(server.manager() as Arc<dyn prism_cluster::QueryEmbedder>),
+        );
 
-        // 7. Build cluster routes
-        extension_router =
-            extension_router.merge(cluster_routes(federation, Arc::clone(&cluster_state)));
+        #[cfg(feature = "es-compat")]
+        {
+            es_topology = Some(Arc::new(EsClusterTopology {
+                node_id: self_node_id.clone(),
+                state: Arc::clone(&cluster_state),
+            }));
+        }
 
-        // 8. Start ClusterServer with shared state
-        let cluster_manager = server.manager();
+        // Follower collections pull writes from leader clusters
+        let ccr_dir = config
+            .storage
+            .data_dir
+            .join("cluster")
+            .join(&cluster_config.node_id);
+        let ccr_transport = Arc::new(prism_cluster::ClientTransport::new(cluster_config.clone()));
+        let ccr_sink = Arc::clone(&federation) as Arc<dyn prism_cluster::FollowerSink>;
+        let ccr = match prism_cluster::CrossClusterReplication::load(
+            &ccr_dir,
+            Arc::clone(&ccr_transport) as _,
+            Arc::clone(&ccr_sink),
+        ) {
+            Ok(ccr) => Arc::new(ccr),
+            Err(e) => {
+                tracing::warn!("Failed to load followers from {}: {}", ccr_dir.display(), e);
+                Arc::new(prism_cluster::CrossClusterReplication::in_memory(
+                    ccr_transport,
+                    ccr_sink,
+                ))
+            }
+        };
+        ccr.start();
+
+        // 7. Create ClusterServer with shared state
         tracing::info!(
             "Starting cluster RPC server on {} (node_id: {})",
             cluster_config.bind_addr,
             cluster_config.node_id
         );
+        let cluster_server = prism_cluster::ClusterServer::with_state(
+            cluster_config,
+            server.manager(),
+            Arc::clone(&cluster_state),
+        );
+
+        // The ILM shrink action shrinks the collection's cluster shards
+        if let Some(ref ilm) = ilm_manager {
+            let resharder: Arc<dyn prism::ilm::ShardShrinker> = cluster_server.resharder();
+            ilm.set_shard_shrinker(resharder);
+        }
 
+        // 8. Join the cluster and assign the shards of each collection once
+        //    every member registered
+        tokio::spawn(bootstrap_cluster_metadata(
+            cluster_server.metadata(),
+            self_node,
+            member_addresses,
+            server.manager(),
+        ));
+
+        // 9. Build cluster routes
+        extension_router = extension_router.merge(cluster_routes(
+            federation,
+            Arc::clone(&cluster_state),
+            cluster_server.metadata(),
+            cluster_server.partition_detector(),
+            cluster_server.conflict_report(),
+            cluster_server.resharder(),
+            ccr,
+        ));
+
+        // 10. Serve cluster RPC
         tokio::spawn(async move {
-            let cluster_server = prism_cluster::ClusterServer::with_state(
-                cluster_config,
-                cluster_manager,
-                cluster_state,
-            );
             if let Err(e) = cluster_server.serve().await {
                 tracing::error!("Cluster server error: {}", e);
             }
@@ -468,10 +504,29 @@ async fn main() -> Result<()> {
     // Add ES-compat routes if enabled
     #[cfg(feature = "es-compat")]
     {
-        extension_router = extension_router.nest(
-            "/_elastic",
-            prism_es_compat::es_compat_router(server.manager()),
+        // Share ILM's aliases so rollover targets resolve through ES requests
+        let aliases = match ilm_manager {
+            Some(ref ilm) => Some(ilm.alias_manager().clone()),
+            None => match prism::ilm::AliasManager::new(&config.storage.data_dir).await {
+                Ok(aliases) => Some(Arc::new(aliases)),
+                Err(e) => {
+                    tracing::error!("Failed to load index aliases: {}", e);
+                    None
+                }
+            },
+        };
+        #[cfg(feature = "cluster")]
+        let topology = es_topology;
+        #[cfg(not(feature = "cluster"))]
+        let topology: Option<Arc<dyn prism_es_compat::topology::ClusterTopology>> = None;
+        // Share the pipeline registry so ES-defined pipelines serve native indexing too
+        let es_router = prism_es_compat::es_compat_router_with_pipelines(
+            server.manager(),
+            aliases,
+            topology,
+            server.pipeline_registry(),
         );
+        extension_router = extension_router.nest("/_elastic", es_router);
         tracing::info!("Elasticsearch compatibility enabled at /_elastic/*");
     }
 
@@ -491,11 +546,190 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Cluster membership and shard placement for the ES-compat endpoints
+#[cfg(all(feature = "cluster", feature = "es-compat"))]
+struct EsClusterTopology {
+    node_id: String,
+    state: Arc<prism_cluster::ClusterState>,
+}
+
+#[cfg(all(feature = "cluster", feature = "es-compat"))]
+impl EsClusterTopology {
+    fn healthy_nodes(&self) -> std::collections::HashSet<String> {
+        self.state
+            .get_healthy_nodes()
+            .into_iter()
+            .map(|node| node.node_id)
+            .collect()
+    }
+}
+
+#[cfg(all(feature = "cluster", feature = "es-compat"))]
+impl prism_es_compat::topology::ClusterTopology for EsClusterTopology {
+    fn local_node(&self) -> String {
+        self.node_id.clone()
+    }
+
+    fn nodes(&self) -> Vec<prism_es_compat::topology::TopologyNode> {
+        let healthy = self.healthy_nodes();
+        self.state
+            .get_nodes()
+            .into_iter()
+            .map(|node| prism_es_compat::topology::TopologyNode {
+                healthy: healthy.contains(&node.info.node_id),
+                id: node.info.node_id,
+                address: node.info.address,
+            })
+            .collect()
+    }
+
+    fn shards(&self) -> Vec<prism_es_compat::topology::TopologyShard> {
+        use prism_cluster::ShardState;
+        use prism_es_compat::topology::{ShardRoutingState, TopologyShard};
+
+        let healthy = self.healthy_nodes();
+        let mut shards = Vec::new();
+        for assignment in self.state.get_all_shards() {
+            let state = match assignment.state {
+                ShardState::Active | ShardState::Resharding => ShardRoutingState::Started,
+                ShardState::Relocating => ShardRoutingState::Relocating,
+                ShardState::Initializing | ShardState::Syncing => ShardRoutingState::Initializing,
+                ShardState::Deleting | ShardState::Error => ShardRoutingState::Unassigned,
+            };
+            // Copies on unreachable nodes are unassigned until they return
+            let copy = |node: &String, primary: bool| {
+                let state = if healthy.contains(node) {
+                    state
+                } else {
+                    ShardRoutingState::Unassigned
+                };
+                TopologyShard {
+                    index: assignment.collection.clone(),
+                    shard: assignment.shard_number,
+                    primary,
+                    node: (state != ShardRoutingState::Unassigned).then(|| node.clone()),
+                    state,
+                    docs: assignment.document_count,
+                    size_bytes: assignment.size_bytes,
+                }
+            };
+            shards.push(copy(&assignment.primary_node, true));
+            for replica in &assignment.replica_nodes {
+                shards.push(copy(replica, false));
+            }
+        }
+        shards
+    }
+}
+
+/// Register this node through the metadata log, then assign 1 shard per
+/// node per collection, with replicas on the following nodes when the schema
+/// asks for replication.
+///
+/// Every node proposes the assignments; the log keeps the first proposal of
+/// each collection, so nodes sort members by address to propose the same.
+#[cfg(feature = "cluster")]
+async fn bootstrap_cluster_metadata(
+    metadata: Arc<prism_cluster::RaftNode>,
+    self_node: prism_cluster::NodeInfo,
+    member_addresses: Vec<String>,
+    manager: Arc<prism::collection::CollectionManager>,
+) {
+    const ATTEMPTS: u32 = 60;
+    let retry_delay = std::time::Duration::from_secs(1);
+
+    let mut registered = false;
+    for _ in 0..ATTEMPTS {
+        match metadata
+            .write(prism_cluster::MetadataCommand::RegisterNode(
+                self_node.clone(),
+            ))
+            .await
+        {
+            Ok(_) => {
+                registered = true;
+                break;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to register node in cluster metadata: {}", e);
+                tokio::time::sleep(retry_delay).await;
+            }
+        }
+    }
+    if !registered {
+        tracing::error!(
+            "Node {} could not join the cluster metadata",
+            self_node.node_id
+        );
+        return;
+    }
+
+    // Wait for the other members, then assign shards across those present
+    let cluster_state = metadata.metadata().cluster_state();
+    let mut nodes = Vec::new();
+    for _ in 0..ATTEMPTS {
+        nodes = cluster_state.get_nodes();
+        if member_addresses
+            .iter()
+            .all(|address| nodes.iter().any(|n| &n.info.address == address))
+        {
+            break;
+        }
+        tokio::time::sleep(retry_delay).await;
+    }
+    nodes.sort_by(|a, b| a.info.address.cmp(&b.info.address));
+    let node_ids: Vec<String> = nodes.into_iter().map(|n| n.info.node_id).collect();
+
+    let collections = manager.list_collections();
+    for collection in &collections {
+        let factor = manager
+            .get_schema(collection)
+            .and_then(|schema| schema.replication)
+            .map_or(1, |replication| replication.factor)
+            .clamp(1, node_ids.len());
+        let assignments = node_ids
+            .iter()
+            .enumerate()
+            .map(|(i, node_id)| {
+                let mut assignment =
+                    prism_cluster::ShardAssignment::new(collection, i as u32, node_id);
+                assignment.replica_nodes = (1..factor)
+                    .map(|offset| node_ids[(i + offset) % node_ids.len()].clone())
+                    .collect();
+                assignment.in_sync_replicas = assignment.replica_nodes.clone();
+                assignment.state = prism_cluster::ShardState::Active;
+                assignment
+            })
+            .collect();
+        if let Err(e) = metadata
+            .write(prism_cluster::MetadataCommand::CreateShards {
+                collection: collection.clone(),
+                assignments,
+            })
+            .await
+        {
+            tracing::warn!("Failed to assign shards of {}: {}", collection, e);
+        }
+    }
+
+    tracing::info!(
+        "Cluster initialized: {} nodes, {} collections, {} total shards",
+        node_ids.len(),
+        collections.len(),
+        cluster_state.get_all_shards().len()
+    );
+}
+
 /// Build cluster federation routes
 #[cfg(feature = "cluster")]
 fn cluster_routes(
     federation: Arc<prism_cluster::FederatedSearch>,
     cluster_state: Arc<prism_cluster::ClusterState>,
+    metadata: Arc<prism_cluster::RaftNode>,
+    partition_detector: Arc<prism_cluster::PartitionDetector>,
+    conflicts: Arc<prism_cluster::ConflictReport>,
+    resharder: Arc<prism_cluster::Resharder>,
+    ccr: Arc<prism_cluster::CrossClusterReplication>,
 ) -> axum::Router<()> {
     use axum::extract::{Path, State};
     use axum::routing::{get, post};
@@ -515,25 +749,49 @@ fn cluster_routes(
             .unwrap_or("*")
             .to_string();
         let limit = body.get("limit").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
+        let aggregations = match body.get("aggregations") {
+            Some(aggs) => match serde_json::from_value(aggs.clone()) {
+                Ok(aggs) => aggs,
+                Err(e) => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({"error": e.to_string()})),
+                    )
+                        .into_response()
+                }
+            },
+            None => vec![],
+        };
+        let query_vector: Option<Vec<f32>> = body
+            .get("vector")
+            .and_then(|v| serde_json::from_value(v.clone()).ok());
+        let weight = |name: &str| body.get(name).and_then(|v| v.as_f64()).map(|w| w as f32);
 
         let rpc_query = prism_cluster::RpcQuery {
             query_string,
             fields: vec![],
             limit,
             offset: 0,
-            merge_strategy: None,
-            text_weight: None,
-            vector_weight: None,
+            merge_strategy: body
+                .get("merge_strategy")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            text_weight: weight("text_weight"),
+            vector_weight: weight("vector_weight"),
             highlight: None,
-            rrf_k: None,
+            rrf_k: body.get("rrf_k").and_then(|v| v.as_u64()).map(|k| k as usize),
             min_score: None,
             score_function: None,
             skip_ranking: false,
+            aggregations,
+            query_vector,
+            dfs: body.get("dfs").and_then(|v| v.as_bool()).unwrap_or(false),
+            term_statistics: None,
         };
 
         match fed.search(&collection, rpc_query).await {
             Ok(results) => {
-                let response = serde_json::json!({
+                let mut response = serde_json::json!({
                     "results": results.results,
                     "total": results.total,
                     "latency_ms": results.latency_ms,
@@ -544,6 +802,9 @@ fn cluster_routes(
                         "failed": results.shard_status.failed,
                     }
                 });
+                if !results.aggregations.is_empty() {
+                    response["aggregations"] = serde_json::json!(results.aggregations);
+                }
                 (StatusCode::OK, Json(response)).into_response()
             }
             Err(e) => {
@@ -555,14 +816,77 @@ fn cluster_routes(
         }
     }
 
-    async fn federated_index(
+    /// Aggregations over every shard, in the shape of `/aggregate`
+    async fn federated_aggregate(
         Path(collection): Path<String>,
         State(fed): State<Arc<prism_cluster::FederatedSearch>>,
+        Json(request): Json<prism::api::routes::AggregateRequest>,
+    ) -> axum::response::Response {
+        use axum::http::StatusCode;
+        use axum::response::IntoResponse;
+
+        let rpc_query = prism_cluster::RpcQuery {
+            query_string: request.query.unwrap_or_else(|| "*".to_string()),
+            fields: vec![],
+            limit: 0,
+            offset: 0,
+            merge_strategy: None,
+            text_weight: None,
+            vector_weight: None,
+            highlight: None,
+            rrf_k: None,
+            min_score: None,
+            score_function: None,
+            skip_ranking: false,
+            aggregations: request.aggregations,
+            query_vector: None,
+            dfs: false,
+            term_statistics: None,
+        };
+
+        match fed.search(&collection, rpc_query).await {
+            Ok(results) => {
+                let response = serde_json::json!({
+                    "results": results.aggregations,
+                    "took_ms": results.latency_ms,
+                    "is_partial": results.is_partial,
+                });
+                (StatusCode::OK, Json(response)).into_response()
+            }
+            Err(e) => {
+                let response = serde_json::json!({"error": e.to_string()});
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(response)).into_response()
+            }
+        }
+    }
+
+    /// State of the federated write route
+    #[derive(Clone)]
+    struct FederatedWriteState {
+        federation: Arc<prism_cluster::FederatedSearch>,
+        ccr: Arc<prism_cluster::CrossClusterReplication>,
+    }
+
+    async fn federated_index(
+        Path(collection): Path<String>,
+        State(write): State<FederatedWriteState>,
         Json(body): Json<serde_json::Value>,
     ) -> axum::response::Response {
         use axum::http::StatusCode;
         use axum::response::IntoResponse;
 
+        // Followers only take the leader's writes until promoted
+        if write.ccr.is_following(&collection) {
+            return (
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({
+                    "error": format!("Collection {} follows a leader and is read-only", collection)
+                })),
+            )
+                .into_response();
+        }
+        let fed = write.federation;
+
         let docs: Vec<prism_cluster::RpcDocument> = match body.get("documents") {
             Some(docs_val) => match serde_json::from_value(docs_val.clone()) {
                 Ok(d) => d,
@@ -600,10 +924,17 @@ fn cluster_routes(
         }
     }
 
-    async fn cluster_health(
-        State(state): State<Arc<prism_cluster::ClusterState>>,
-    ) -> Json<serde_json::Value> {
-        let nodes = state.get_nodes();
+    /// State of the cluster health route
+    #[derive(Clone)]
+    struct HealthRouteState {
+        cluster_state: Arc<prism_cluster::ClusterState>,
+        metadata: Arc<prism_cluster::RaftNode>,
+        partition_detector: Arc<prism_cluster::PartitionDetector>,
+        conflicts: Arc<prism_cluster::ConflictReport>,
+    }
+
+    async fn cluster_health(State(health): State<HealthRouteState>) -> Json<serde_json::Value> {
+        let nodes = health.cluster_state.get_nodes();
         let healthy = nodes.iter().filter(|n| n.reachable).count();
         let node_list: Vec<serde_json::Value> = nodes
             .iter()
@@ -622,23 +953,41 @@ fn cluster_routes(
             "total_nodes": nodes.len(),
             "healthy_nodes": healthy,
             "nodes": node_list,
+            "metadata": health.metadata.status().await,
+            "partition_state": health.partition_detector.state().as_str(),
+            "conflicts": health.conflicts.list(),
         }))
     }
 
     async fn drain_node(
         Path(node_id): Path<String>,
-        State(state): State<Arc<prism_cluster::ClusterState>>,
+        State(metadata): State<Arc<prism_cluster::RaftNode>>,
     ) -> axum::response::Response {
         use axum::http::StatusCode;
         use axum::response::IntoResponse;
 
-        let result = state.drain_node(&node_id);
-        if result {
-            (
-                StatusCode::OK,
-                Json(serde_json::json!({"drained": true, "node_id": node_id})),
-            )
-                .into_response()
+        if metadata
+            .metadata()
+            .cluster_state()
+            .get_node(&node_id)
+            .is_some()
+        {
+            match metadata.drain_node(&node_id).await {
+                Ok(moved) => (
+                    StatusCode::OK,
+                    Json(serde_json::json!({
+                        "drained": true,
+                        "node_id": node_id,
+                        "primaries_handed_off": moved,
+                    })),
+                )
+                    .into_response(),
+                Err(e) => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(serde_json::json!({"error": e.to_string()})),
+                )
+                    .into_response(),
+            }
         } else {
             (
                 StatusCode::NOT_FOUND,
@@ -650,18 +999,35 @@ fn cluster_routes(
 
     async fn undrain_node(
         Path(node_id): Path<String>,
-        State(state): State<Arc<prism_cluster::ClusterState>>,
+        State(metadata): State<Arc<prism_cluster::RaftNode>>,
     ) -> axum::response::Response {
         use axum::http::StatusCode;
         use axum::response::IntoResponse;
 
-        let result = state.undrain_node(&node_id);
-        if result {
-            (
-                StatusCode::OK,
-                Json(serde_json::json!({"undrained": true, "node_id": node_id})),
-            )
-                .into_response()
+        if metadata
+            .metadata()
+            .cluster_state()
+            .get_node(&node_id)
+            .is_some()
+        {
+            match metadata
+                .write(prism_cluster::MetadataCommand::SetDraining {
+                    node_id: node_id.clone(),
+                    draining: false,
+                })
+                .await
+            {
+                Ok(_) => (
+                    StatusCode::OK,
+                    Json(serde_json::json!({"undrained": true, "node_id": node_id})),
+                )
+                    .into_response(),
+                Err(e) => (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(serde_json::json!({"error": e.to_string()})),
+                )
+                    .into_response(),
+            }
         } else {
             (
                 StatusCode::NOT_FOUND,
@@ -672,19 +1038,25 @@ fn cluster_routes(
     }
 
     async fn upgrade_status(
-        State(state): State<Arc<prism_cluster::ClusterState>>,
+        State(metadata): State<Arc<prism_cluster::RaftNode>>,
     ) -> Json<serde_json::Value> {
-        let nodes = state.get_nodes();
+        let cluster_state = metadata.metadata().cluster_state();
+        let nodes = cluster_state.get_nodes();
+        let shards = cluster_state.get_all_shards();
         let node_statuses: Vec<serde_json::Value> = nodes
             .iter()
             .map(|n| {
+                let node_id = n.info.node_id.as_str();
                 serde_json::json!({
-                    "node_id": n.info.node_id,
+                    "node_id": node_id,
                     "version": n.version,
                     "protocol_version": n.protocol_version,
                     "min_supported_version": n.min_supported_version,
+                    "features": n.features,
                     "draining": n.draining,
                     "reachable": n.reachable,
+                    "shards": shards.iter().filter(|s| s.is_on_node(node_id)).count(),
+                    "primary_shards": shards.iter().filter(|s| s.primary_node == node_id).count(),
                 })
             })
             .collect();
@@ -692,34 +1064,220 @@ fn cluster_routes(
         let all_same_version = nodes
             .windows(2)
             .all(|w| w[0].protocol_version == w[1].protocol_version);
+        // Nodes whose version is not known yet are left out
+        let known: Vec<_> = nodes.iter().filter(|n| n.protocol_version > 0).collect();
+        let compatible = known.iter().map(|n| n.min_supported_version).max()
+            <= known.iter().map(|n| n.protocol_version).min();
+        // Features every connection between known nodes negotiates
+        let common_features: Vec<&String> = known
+            .first()
+            .map(|first| {
+                first
+                    .features
+                    .iter()
+                    .filter(|f| known.iter().all(|n| n.features.contains(f)))
+                    .collect()
+            })
+            .unwrap_or_default();
 
         Json(serde_json::json!({
             "nodes": node_statuses,
             "total_nodes": nodes.len(),
             "draining_count": nodes.iter().filter(|n| n.draining).count(),
             "all_same_version": all_same_version,
+            "compatible": compatible,
+            "common_features": common_features,
         }))
     }
 
+    #[derive(serde::Deserialize)]
+    struct ReshardBody {
+        shards: usize,
+    }
+
+    async fn split_collection(
+        Path(collection): Path<String>,
+        State(resharder): State<Arc<prism_cluster::Resharder>>,
+        Json(body): Json<ReshardBody>,
+    ) -> axum::response::Response {
+        reshard_response(resharder.split(&collection, body.shards).await)
+    }
+
+    async fn shrink_collection(
+        Path(collection): Path<String>,
+        State(resharder): State<Arc<prism_cluster::Resharder>>,
+        Json(body): Json<ReshardBody>,
+    ) -> axum::response::Response {
+        reshard_response(resharder.shrink(&collection, body.shards).await)
+    }
+
+    fn reshard_response(
+        result: prism_cluster::error::Result<prism_cluster::ReshardReport>,
+    ) -> axum::response::Response {
+        use axum::http::StatusCode;
+        use axum::response::IntoResponse;
+
+        match result {
+            Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+            Err(e) => {
+                let status = match e {
+                    prism_cluster::ClusterError::CollectionNotFound(_) => StatusCode::NOT_FOUND,
+                    prism_cluster::ClusterError::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status, Json(serde_json::json!({"error": e.to_string()}))).into_response()
+            }
+        }
+    }
+
+    async fn follow_collection(
+        Path(collection): Path<String>,
+        State(ccr): State<Arc<prism_cluster::CrossClusterReplication>>,
+        Json(config): Json<prism_cluster::FollowConfig>,
+    ) -> axum::response::Response {
+        follow_response(ccr.follow(&collection, config).await)
+    }
+
+    async fn pause_follower(
+        Path(collection): Path<String>,
+        State(ccr): State<Arc<prism_cluster::CrossClusterReplication>>,
+    ) -> axum::response::Response {
+        follow_response(ccr.pause(&collection).await)
+    }
+
+    async fn resume_follower(
+        Path(collection): Path<String>,
+        State(ccr): State<Arc<prism_cluster::CrossClusterReplication>>,
+    ) -> axum::response::Response {
+        follow_response(ccr.resume(&collection).await)
+    }
+
+    async fn promote_follower(
+        Path(collection): Path<String>,
+        State(ccr): State<Arc<prism_cluster::CrossClusterReplication>>,
+    ) -> axum::response::Response {
+        follow_response(ccr.promote(&collection).await)
+    }
+
+    async fn follower_stats(
+        Path(collection): Path<String>,
+        State(ccr): State<Arc<prism_cluster::CrossClusterReplication>>,
+    ) -> axum::response::Response {
+        let result = ccr.status(&collection).ok_or_else(|| {
+            prism_cluster::ClusterError::CollectionNotFound(format!(
+                "{} is not a follower",
+                collection
+            ))
+        });
+        follow_response(result)
+    }
+
+    async fn all_follower_stats(
+        State(ccr): State<Arc<prism_cluster::CrossClusterReplication>>,
+    ) -> Json<serde_json::Value> {
+        Json(serde_json::json!({ "followers": ccr.statuses() }))
+    }
+
+    fn follow_response(
+        result: prism_cluster::error::Result<prism_cluster::FollowerStatus>,
+    ) -> axum::response::Response {
+        use axum::http::StatusCode;
+        use axum::response::IntoResponse;
+
+        match result {
+            Ok(status) => (StatusCode::OK, Json(status)).into_response(),
+            Err(e) => {
+                let status = match e {
+                    prism_cluster::ClusterError::CollectionNotFound(_) => StatusCode::NOT_FOUND,
+                    prism_cluster::ClusterError::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status, Json(serde_json::json!({"error": e.to_string()}))).into_response()
+            }
+        }
+    }
+
     // Federation routes (with federation state)
     let federation_routes = axum::Router::new()
         .route(
             "/cluster/collections/:collection/search",
             post(federated_search),
         )
+        .route(
+            "/cluster/collections/:collection/aggregate",
+            post(federated_aggregate),
+        )
+        .with_state(Arc::clone(&federation));
+
+    // Write route (rejects writes to follower collections)
+    let write_routes = axum::Router::new()
         .route(
             "/cluster/collections/:collection/documents",
             post(federated_index),
         )
-        .with_state(federation);
+        .with_state(FederatedWriteState {
+            federation,
+            ccr: Arc::clone(&ccr),
+        });
 
-    // Drain/upgrade/health routes (with cluster_state)
-    let cluster_mgmt_routes = axum::Router::new()
+    // Health route (with partition state and conflict report)
+    let health_routes = axum::Router::new()
         .route("/cluster/health", get(cluster_health))
+        .with_state(HealthRouteState {
+            cluster_state: Arc::clone(&cluster_state),
+            metadata: Arc::clone(&metadata),
+            partition_detector,
+            conflicts,
+        });
+
+    // Drain/upgrade routes (through the metadata log)
+    let cluster_mgmt_routes = axum::Router::new()
         .route("/cluster/nodes/:node_id/drain", post(drain_node))
         .route("/cluster/nodes/:node_id/undrain", post(undrain_node))
         .route("/cluster/upgrade/status", get(upgrade_status))
-        .with_state(cluster_state);
+        .with_state(metadata);
 
-    federation_routes.merge(cluster_mgmt_routes)
+    // Split/shrink routes (source shards read-only until the swap)
+    let reshard_routes = axum::Router::new()
+        .route(
+            "/cluster/collections/:collection/_split",
+            post(split_collection),
+        )
+        .route(
+            "/cluster/collections/:collection/_shrink",
+            post(shrink_collection),
+        )
+        .with_state(resharder);
+
+    // Cross-cluster replication routes (this node runs the followers it starts)
+    let ccr_routes = axum::Router::new()
+        .route(
+            "/cluster/collections/:collection/_ccr/follow",
+            post(follow_collection),
+        )
+        .route(
+            "/cluster/collections/:collection/_ccr/pause",
+            post(pause_follower),
+        )
+        .route(
+            "/cluster/collections/:collection/_ccr/resume",
+            post(resume_follower),
+        )
+        .route(
+            "/cluster/collections/:collection/_ccr/promote",
+            post(promote_follower),
+        )
+        .route(
+            "/cluster/collections/:collection/_ccr/stats",
+            get(follower_stats),
+        )
+        .route("/cluster/_ccr/stats", get(all_follower_stats))
+        .with_state(ccr);
+
+    federation_routes
+        .merge(write_routes)
+        .merge(health_routes)
+        .merge(cluster_mgmt_routes)
+        .merge(reshard_routes)
+        .merge(ccr_routes)
 }
@@ -108,6 +108,7 @@ impl TermsAgg {
                     from: None,
                     to: None,
                     sub_aggs: None,
+                    doc_count_error_upper_bound: None,
                 });
             }
         }
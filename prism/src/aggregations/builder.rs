@@ -1,4 +1,7 @@
-use crate::aggregations::types::{AggregationType, HistogramBounds, RangeEntry};
+use crate::aggregations::types::{
+    AggregationType, BucketSortField, CompositeSource, CompositeSourceType, GapPolicy,
+    HistogramBounds, MovingFunction, RangeEntry, SortDirection, TopHitsSort,
+};
 use std::collections::HashMap;
 
 impl AggregationType {
@@ -81,4 +84,167 @@ impl AggregationType {
     pub fn global() -> AggregationType {
         AggregationType::Global {}
     }
+
+    pub fn cardinality(field: String) -> AggregationType {
+        AggregationType::Cardinality {
+            field,
+            precision_threshold: None,
+            sketch: false,
+        }
+    }
+
+    pub fn cardinality_with_precision(field: String, precision_threshold: u64) -> AggregationType {
+        AggregationType::Cardinality {
+            field,
+            precision_threshold: Some(precision_threshold),
+            sketch: false,
+        }
+    }
+
+    pub fn top_hits(size: usize) -> AggregationType {
+        AggregationType::TopHits {
+            size: Some(size),
+            from: None,
+            sort: vec![],
+            source: None,
+        }
+    }
+
+    pub fn top_hits_sorted(
+        size: usize,
+        sort: Vec<TopHitsSort>,
+        source: Option<Vec<String>>,
+    ) -> AggregationType {
+        AggregationType::TopHits {
+            size: Some(size),
+            from: None,
+            sort,
+            source,
+        }
+    }
+
+    pub fn significant_terms(field: String) -> AggregationType {
+        AggregationType::SignificantTerms {
+            field,
+            size: Some(10),
+            min_doc_count: None,
+            background_filter: None,
+        }
+    }
+
+    pub fn significant_terms_with_background(
+        field: String,
+        size: usize,
+        background_filter: String,
+    ) -> AggregationType {
+        AggregationType::SignificantTerms {
+            field,
+            size: Some(size),
+            min_doc_count: None,
+            background_filter: Some(background_filter),
+        }
+    }
+
+    pub fn composite(sources: Vec<CompositeSource>, size: usize) -> AggregationType {
+        AggregationType::Composite {
+            sources,
+            size: Some(size),
+            after: None,
+        }
+    }
+
+    pub fn composite_after(
+        sources: Vec<CompositeSource>,
+        size: usize,
+        after: HashMap<String, serde_json::Value>,
+    ) -> AggregationType {
+        AggregationType::Composite {
+            sources,
+            size: Some(size),
+            after: Some(after),
+        }
+    }
+
+    pub fn derivative(buckets_path: String) -> AggregationType {
+        AggregationType::Derivative {
+            buckets_path,
+            gap_policy: GapPolicy::Skip,
+        }
+    }
+
+    pub fn cumulative_sum(buckets_path: String) -> AggregationType {
+        AggregationType::CumulativeSum { buckets_path }
+    }
+
+    pub fn moving_fn(
+        buckets_path: String,
+        window: usize,
+        function: MovingFunction,
+    ) -> AggregationType {
+        AggregationType::MovingFn {
+            buckets_path,
+            window,
+            function,
+            shift: 0,
+            gap_policy: GapPolicy::Skip,
+        }
+    }
+
+    pub fn bucket_sort(sort: Vec<BucketSortField>, size: Option<usize>) -> AggregationType {
+        AggregationType::BucketSort {
+            sort,
+            from: 0,
+            size,
+            gap_policy: GapPolicy::Skip,
+        }
+    }
+
+    pub fn bucket_selector(
+        buckets_path: HashMap<String, String>,
+        script: String,
+    ) -> AggregationType {
+        AggregationType::BucketSelector {
+            buckets_path,
+            script,
+            gap_policy: GapPolicy::Skip,
+        }
+    }
+}
+
+impl CompositeSource {
+    pub fn terms(name: String, field: String) -> CompositeSource {
+        CompositeSource {
+            name,
+            source_type: CompositeSourceType::Terms { field },
+            order: SortDirection::Asc,
+        }
+    }
+
+    pub fn histogram(name: String, field: String, interval: f64) -> CompositeSource {
+        CompositeSource {
+            name,
+            source_type: CompositeSourceType::Histogram { field, interval },
+            order: SortDirection::Asc,
+        }
+    }
+
+    pub fn date_histogram(
+        name: String,
+        field: String,
+        calendar_interval: String,
+    ) -> CompositeSource {
+        CompositeSource {
+            name,
+            source_type: CompositeSourceType::DateHistogram {
+                field,
+                calendar_interval,
+            },
+            order: SortDirection::Asc,
+        }
+    }
+
+    pub fn with_order(mut self, order: SortDirection) -> CompositeSource {
+        self.order = order;
+        self
+    }
 }
@@ -1,9 +1,11 @@
 mod avg;
+mod cardinality;
 mod count;
 mod minmax;
 mod sum;
 
 pub use avg::AvgAgg;
+pub use cardinality::HyperLogLogPlusPlus;
 pub use count::CountAgg;
 pub use minmax::MinMaxAgg;
 pub use sum::SumAgg;
@@ -1,12 +1,18 @@
 mod agg_trait;
+mod builder;
 mod bucket;
 mod metric;
+mod pipeline;
 pub mod types;
 
 pub use agg_trait::{Agg, AggSegmentContext, PreparedAgg, SegmentAgg};
 pub use bucket::TermsAgg;
-pub use metric::{AvgAgg, CountAgg, MinMaxAgg, SumAgg};
+pub use metric::{AvgAgg, CountAgg, HyperLogLogPlusPlus, MinMaxAgg, SumAgg};
+pub use pipeline::apply_parent_pipelines;
 pub use types::{
     AggregationRequest, AggregationResult, AggregationType, AggregationValue, Bucket,
-    HistogramBounds, PercentilesResult, RangeEntry, StatsResult,
+    BucketSortField, CompositeBucket, CompositeResult, CompositeSource, CompositeSourceType,
+    GapPolicy, HistogramBounds, MovingFunction, PercentilesResult, RangeEntry,
+    SignificantTermsBucket, SignificantTermsResult, SortDirection, StatsResult, TopHit,
+    TopHitsResult, TopHitsSort,
 };
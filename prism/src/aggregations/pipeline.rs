@@ -0,0 +1,721 @@
+//! Parent pipeline aggregations
+//!
+//! Pipelines are declared as sub-aggregations of a multi-bucket aggregation
+//! and run, in declaration order, once its buckets are built. Each
+//! `buckets_path` is resolved per bucket against the bucket's other
+//! sub-aggregations (including earlier pipelines):
+//!
+//! - `_count` / `_key` — the bucket's document count / numeric key
+//! - `agg` or `agg.value` — a single-value metric
+//! - `agg.avg`, `agg[99]` — a field of a stats or percentiles result
+//! - `filter_agg>agg.sum` — step through a single-bucket aggregation
+
+use crate::aggregations::types::{
+    AggregationRequest, AggregationResult, AggregationType, AggregationValue, Bucket,
+    BucketSortField, GapPolicy, MovingFunction, SortDirection,
+};
+use crate::ranking::ScoreFunctionReranker;
+use crate::{Error, Result};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Apply the pipeline aggregations among `sub_aggs` to a parent's buckets.
+/// Non-pipeline entries are assumed to have been computed already.
+/// Pipelines run in declaration order, except that a pipeline reading
+/// another pipeline's output always runs after it.
+pub fn apply_parent_pipelines(
+    buckets: &mut Vec<Bucket>,
+    sub_aggs: &[AggregationRequest],
+) -> Result<()> {
+    let mut known: Vec<&str> = sub_aggs
+        .iter()
+        .filter(|a| !a.agg_type.is_pipeline())
+        .map(|a| a.name.as_str())
+        .collect();
+    let mut pending: Vec<&AggregationRequest> = sub_aggs
+        .iter()
+        .filter(|a| a.agg_type.is_pipeline())
+        .collect();
+
+    while !pending.is_empty() {
+        // If nothing is ready, the first pending pipeline fails validation
+        let next = pending
+            .iter()
+            .position(|req| {
+                pipeline_paths(&req.agg_type)
+                    .iter()
+                    .all(|path| validate_path(&req.name, path, &known).is_ok())
+            })
+            .unwrap_or(0);
+        let req = pending.remove(next);
+        let name = req.name.as_str();
+        for path in pipeline_paths(&req.agg_type) {
+            validate_path(name, path, &known)?;
+        }
+
+        match &req.agg_type {
+            AggregationType::Derivative {
+                buckets_path,
+                gap_policy,
+            } => derivative(buckets, name, buckets_path, *gap_policy),
+            AggregationType::CumulativeSum { buckets_path } => {
+                cumulative_sum(buckets, name, buckets_path)
+            }
+            AggregationType::MovingFn {
+                buckets_path,
+                window,
+                function,
+                shift,
+                gap_policy,
+            } => {
+                if *window == 0 {
+                    return Err(Error::InvalidQuery(format!(
+                        "moving_fn aggregation [{}] requires a window greater than 0",
+                        name
+                    )));
+                }
+                moving_fn(
+                    buckets,
+                    name,
+                    buckets_path,
+                    *window,
+                    *function,
+                    *shift,
+                    *gap_policy,
+                );
+            }
+            AggregationType::BucketSort {
+                sort,
+                from,
+                size,
+                gap_policy,
+            } => bucket_sort(buckets, sort, *from, *size, *gap_policy),
+            AggregationType::BucketSelector {
+                buckets_path,
+                script,
+                gap_policy,
+            } => bucket_selector(buckets, name, buckets_path, script, *gap_policy)?,
+            _ => {}
+        }
+        known.push(name);
+    }
+
+    Ok(())
+}
+
+/// Every `buckets_path` a pipeline reads
+fn pipeline_paths(agg_type: &AggregationType) -> Vec<&str> {
+    match agg_type {
+        AggregationType::Derivative { buckets_path, .. }
+        | AggregationType::CumulativeSum { buckets_path }
+        | AggregationType::MovingFn { buckets_path, .. } => vec![buckets_path.as_str()],
+        AggregationType::BucketSort { sort, .. } => {
+            sort.iter().map(|field| field.path.as_str()).collect()
+        }
+        AggregationType::BucketSelector { buckets_path, .. } => {
+            buckets_path.values().map(String::as_str).collect()
+        }
+        _ => vec![],
+    }
+}
+
+/// Check that the first step of a path names a sibling aggregation
+fn validate_path(pipeline: &str, path: &str, known: &[&str]) -> Result<()> {
+    let first = path.split('>').next().unwrap_or_default();
+    if first == "_count" || first == "_key" {
+        return Ok(());
+    }
+    let (agg_name, _) = split_metric(first);
+    if known.contains(&agg_name) {
+        Ok(())
+    } else {
+        Err(Error::InvalidQuery(format!(
+            "No aggregation [{}] found for buckets_path [{}] of pipeline aggregation [{}]",
+            agg_name, path, pipeline
+        )))
+    }
+}
+
+/// Split `agg.metric` or `agg[metric]` into its name and metric parts
+fn split_metric(part: &str) -> (&str, Option<&str>) {
+    if let Some(open) = part.find('[') {
+        if let Some(inner) = part[open + 1..].strip_suffix(']') {
+            return (&part[..open], Some(inner));
+        }
+    }
+    match part.split_once('.') {
+        Some((name, metric)) => (name, Some(metric)),
+        None => (part, None),
+    }
+}
+
+fn find_agg<'a>(bucket: &'a Bucket, name: &str) -> Option<&'a AggregationResult> {
+    bucket.sub_aggs.as_ref()?.iter().find(|a| a.name == name)
+}
+
+/// Resolve a path to a raw value within one bucket
+fn resolve_path(bucket: &Bucket, path: &str) -> Option<f64> {
+    let mut current = bucket;
+    let mut steps = path.split('>').peekable();
+    while let Some(step) = steps.next() {
+        if steps.peek().is_none() {
+            return resolve_metric(current, step);
+        }
+        current = match &find_agg(current, step)?.value {
+            AggregationValue::Buckets(inner) if inner.len() == 1 => &inner[0],
+            _ => return None,
+        };
+    }
+    None
+}
+
+fn resolve_metric(bucket: &Bucket, step: &str) -> Option<f64> {
+    match step {
+        "_count" => return Some(bucket.doc_count as f64),
+        "_key" => return bucket.key.parse().ok(),
+        _ => {}
+    }
+    let (name, metric) = split_metric(step);
+    match (&find_agg(bucket, name)?.value, metric) {
+        (AggregationValue::Single(v), None | Some("value")) => Some(*v),
+        (AggregationValue::Stats(stats), Some(metric)) => match metric {
+            "count" => Some(stats.count as f64),
+            "min" => stats.min,
+            "max" => stats.max,
+            "sum" => stats.sum,
+            "avg" => stats.avg,
+            _ => None,
+        },
+        (AggregationValue::Percentiles(p), Some(metric)) => {
+            if let Some(value) = p.values.get(metric) {
+                return *value;
+            }
+            let wanted: f64 = metric.parse().ok()?;
+            p.values
+                .iter()
+                .find(|(k, _)| k.parse::<f64>().ok() == Some(wanted))
+                .and_then(|(_, v)| *v)
+        }
+        (AggregationValue::Buckets(inner), None | Some("_count")) if inner.len() == 1 => {
+            Some(inner[0].doc_count as f64)
+        }
+        _ => None,
+    }
+}
+
+/// Resolve a path and apply the gap policy. Empty buckets count as gaps
+/// unless the path is the document count itself.
+fn bucket_value(bucket: &Bucket, path: &str, gap_policy: GapPolicy) -> Option<f64> {
+    let value = resolve_path(bucket, path).filter(|v| v.is_finite());
+    let is_gap = value.is_none() || (bucket.doc_count == 0 && path != "_count");
+    if !is_gap {
+        return value;
+    }
+    match gap_policy {
+        GapPolicy::Skip => None,
+        GapPolicy::InsertZeros => Some(0.0),
+    }
+}
+
+fn push_value(bucket: &mut Bucket, name: &str, value: f64) {
+    bucket
+        .sub_aggs
+        .get_or_insert_with(Vec::new)
+        .push(AggregationResult {
+            name: name.to_string(),
+            value: AggregationValue::Single(value),
+        });
+}
+
+/// Derivative of the metric; the first bucket and buckets next to a skipped
+/// gap get no value
+fn derivative(buckets: &mut [Bucket], name: &str, path: &str, gap_policy: GapPolicy) {
+    let mut previous = None;
+    for bucket in buckets.iter_mut() {
+        let current = bucket_value(bucket, path, gap_policy);
+        if let (Some(prev), Some(cur)) = (previous, current) {
+            push_value(bucket, name, cur - prev);
+        }
+        previous = current;
+    }
+}
+
+/// Running total of the metric; missing values add nothing
+fn cumulative_sum(buckets: &mut [Bucket], name: &str, path: &str) {
+    let mut sum = 0.0;
+    for bucket in buckets.iter_mut() {
+        sum += bucket_value(bucket, path, GapPolicy::InsertZeros).unwrap_or(0.0);
+        push_value(bucket, name, sum);
+    }
+}
+
+/// Apply `function` to the window `[i - window + shift, i + shift)` of each
+/// bucket `i`. Buckets whose window holds no values get no result.
+fn moving_fn(
+    buckets: &mut [Bucket],
+    name: &str,
+    path: &str,
+    window: usize,
+    function: MovingFunction,
+    shift: i64,
+    gap_policy: GapPolicy,
+) {
+    let values: Vec<Option<f64>> = buckets
+        .iter()
+        .map(|b| bucket_value(b, path, gap_policy))
+        .collect();
+    let len = values.len() as i64;
+
+    for (i, bucket) in buckets.iter_mut().enumerate() {
+        let end = (i as i64 + shift).clamp(0, len);
+        let start = (i as i64 + shift - window as i64).clamp(0, end);
+        let window_values: Vec<f64> = values[start as usize..end as usize]
+            .iter()
+            .flatten()
+            .copied()
+            .collect();
+        if let Some(result) = apply_moving_function(function, &window_values) {
+            push_value(bucket, name, result);
+        }
+    }
+}
+
+fn apply_moving_function(function: MovingFunction, values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    Some(match function {
+        MovingFunction::Max => values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+        MovingFunction::Min => values.iter().cloned().fold(f64::INFINITY, f64::min),
+        MovingFunction::Sum => values.iter().sum(),
+        MovingFunction::UnweightedAvg => mean,
+        MovingFunction::LinearWeightedAvg => {
+            let weights = (1..=values.len()).map(|w| w as f64);
+            let weighted: f64 = values.iter().zip(weights.clone()).map(|(v, w)| v * w).sum();
+            weighted / weights.sum::<f64>()
+        }
+        MovingFunction::StdDev => {
+            let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+            variance.sqrt()
+        }
+    })
+}
+
+/// Sort key of one bucket for `bucket_sort`
+enum SortValue {
+    Num(f64),
+    Str(String),
+}
+
+fn sort_value(bucket: &Bucket, path: &str, gap_policy: GapPolicy) -> Option<SortValue> {
+    if path == "_key" {
+        return Some(match bucket.key.parse::<f64>() {
+            Ok(n) => SortValue::Num(n),
+            Err(_) => SortValue::Str(bucket.key.clone()),
+        });
+    }
+    bucket_value(bucket, path, gap_policy).map(SortValue::Num)
+}
+
+fn compare_sort_values(a: &SortValue, b: &SortValue, order: SortDirection) -> Ordering {
+    let ord = match (a, b) {
+        (SortValue::Num(x), SortValue::Num(y)) => x.partial_cmp(y).unwrap_or(Ordering::Equal),
+        (SortValue::Str(x), SortValue::Str(y)) => x.cmp(y),
+        (SortValue::Num(_), SortValue::Str(_)) => Ordering::Less,
+        (SortValue::Str(_), SortValue::Num(_)) => Ordering::Greater,
+    };
+    match order {
+        SortDirection::Asc => ord,
+        SortDirection::Desc => ord.reverse(),
+    }
+}
+
+/// Sort buckets by the given paths (stable, so ties keep the parent's order)
+/// and keep `size` buckets starting at `from`. With the skip gap policy,
+/// buckets missing a sort value are dropped.
+fn bucket_sort(
+    buckets: &mut Vec<Bucket>,
+    sort: &[BucketSortField],
+    from: usize,
+    size: Option<usize>,
+    gap_policy: GapPolicy,
+) {
+    let mut keyed: Vec<(Vec<SortValue>, Bucket)> = buckets
+        .drain(..)
+        .filter_map(|bucket| {
+            let keys = sort
+                .iter()
+                .map(|field| sort_value(&bucket, &field.path, gap_policy))
+                .collect::<Option<Vec<_>>>()?;
+            Some((keys, bucket))
+        })
+        .collect();
+
+    keyed.sort_by(|(a, _), (b, _)| {
+        a.iter()
+            .zip(b)
+            .zip(sort)
+            .map(|((x, y), field)| compare_sort_values(x, y, field.order))
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    buckets.extend(
+        keyed
+            .into_iter()
+            .skip(from)
+            .take(size.unwrap_or(usize::MAX))
+            .map(|(_, bucket)| bucket),
+    );
+}
+
+/// Keep buckets whose script evaluates to a non-zero value. With the skip
+/// gap policy, buckets missing a variable are kept unevaluated.
+fn bucket_selector(
+    buckets: &mut Vec<Bucket>,
+    name: &str,
+    buckets_path: &HashMap<String, String>,
+    script: &str,
+    gap_policy: GapPolicy,
+) -> Result<()> {
+    let selector = ScoreFunctionReranker::new(script).map_err(|e| {
+        Error::InvalidQuery(format!(
+            "Invalid script for bucket_selector [{}]: {}",
+            name, e
+        ))
+    })?;
+
+    let mut kept = Vec::with_capacity(buckets.len());
+    'buckets: for bucket in buckets.drain(..) {
+        let mut vars = HashMap::new();
+        for (var, path) in buckets_path {
+            match bucket_value(&bucket, path, gap_policy) {
+                Some(v) => {
+                    vars.insert(var.clone(), serde_json::json!(v));
+                }
+                None => {
+                    kept.push(bucket);
+                    continue 'buckets;
+                }
+            }
+        }
+        let result = selector.try_evaluate(0.0, &vars).map_err(|e| {
+            Error::InvalidQuery(format!(
+                "Invalid script for bucket_selector [{}]: {}",
+                name, e
+            ))
+        })?;
+        if result != 0.0 {
+            kept.push(bucket);
+        }
+    }
+    *buckets = kept;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregations::types::StatsResult;
+
+    fn bucket(key: &str, doc_count: u64, sales: f64) -> Bucket {
+        Bucket {
+            key: key.to_string(),
+            doc_count,
+            from: None,
+            to: None,
+            sub_aggs: Some(vec![
+                AggregationResult {
+                    name: "sales".to_string(),
+                    value: AggregationValue::Single(sales),
+                },
+                AggregationResult {
+                    name: "price".to_string(),
+                    value: AggregationValue::Stats(StatsResult {
+                        count: doc_count,
+                        min: Some(1.0),
+                        max: Some(sales),
+                        sum: Some(sales),
+                        avg: Some(sales / doc_count.max(1) as f64),
+                    }),
+                },
+            ]),
+            doc_count_error_upper_bound: None,
+        }
+    }
+
+    fn request(name: &str, agg_type: AggregationType) -> AggregationRequest {
+        AggregationRequest {
+            name: name.to_string(),
+            agg_type,
+            aggs: None,
+        }
+    }
+
+    fn metric_requests(pipelines: Vec<AggregationRequest>) -> Vec<AggregationRequest> {
+        let mut reqs = vec![
+            request(
+                "sales",
+                AggregationType::Sum {
+                    field: "amount".to_string(),
+                },
+            ),
+            request(
+                "price",
+                AggregationType::Stats {
+                    field: "amount".to_string(),
+                },
+            ),
+        ];
+        reqs.extend(pipelines);
+        reqs
+    }
+
+    fn value_of(bucket: &Bucket, name: &str) -> Option<f64> {
+        match &find_agg(bucket, name)?.value {
+            AggregationValue::Single(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    fn sample() -> Vec<Bucket> {
+        vec![
+            bucket("0", 2, 10.0),
+            bucket("10", 3, 30.0),
+            bucket("20", 1, 25.0),
+            bucket("30", 4, 45.0),
+        ]
+    }
+
+    #[test]
+    fn test_derivative_and_cumulative_sum() {
+        let mut buckets = sample();
+        let reqs = metric_requests(vec![
+            request(
+                "diff",
+                AggregationType::Derivative {
+                    buckets_path: "sales".to_string(),
+                    gap_policy: GapPolicy::Skip,
+                },
+            ),
+            request(
+                "running",
+                AggregationType::CumulativeSum {
+                    buckets_path: "sales".to_string(),
+                },
+            ),
+            // Pipelines can reference earlier pipelines
+            request(
+                "diff_of_running",
+                AggregationType::Derivative {
+                    buckets_path: "running".to_string(),
+                    gap_policy: GapPolicy::Skip,
+                },
+            ),
+        ]);
+        apply_parent_pipelines(&mut buckets, &reqs).unwrap();
+
+        let diffs: Vec<_> = buckets.iter().map(|b| value_of(b, "diff")).collect();
+        assert_eq!(diffs, vec![None, Some(20.0), Some(-5.0), Some(20.0)]);
+        let running: Vec<_> = buckets.iter().map(|b| value_of(b, "running")).collect();
+        assert_eq!(
+            running,
+            vec![Some(10.0), Some(40.0), Some(65.0), Some(110.0)]
+        );
+        assert_eq!(value_of(&buckets[3], "diff_of_running"), Some(45.0));
+    }
+
+    #[test]
+    fn test_pipelines_run_after_their_inputs() {
+        let mut buckets = sample();
+        let reqs = metric_requests(vec![
+            request(
+                "growing",
+                AggregationType::BucketSelector {
+                    buckets_path: HashMap::from([("d".to_string(), "diff".to_string())]),
+                    script: "d > 0".to_string(),
+                    gap_policy: GapPolicy::Skip,
+                },
+            ),
+            request(
+                "diff",
+                AggregationType::Derivative {
+                    buckets_path: "sales".to_string(),
+                    gap_policy: GapPolicy::Skip,
+                },
+            ),
+        ]);
+        apply_parent_pipelines(&mut buckets, &reqs).unwrap();
+        // The first bucket has no derivative and is kept unevaluated
+        let keys: Vec<_> = buckets.iter().map(|b| b.key.as_str()).collect();
+        assert_eq!(keys, vec!["0", "10", "30"]);
+    }
+
+    #[test]
+    fn test_gap_policy() {
+        let mut buckets = sample();
+        buckets[1].doc_count = 0;
+        let skip = metric_requests(vec![request(
+            "diff",
+            AggregationType::Derivative {
+                buckets_path: "sales".to_string(),
+                gap_policy: GapPolicy::Skip,
+            },
+        )]);
+        apply_parent_pipelines(&mut buckets, &skip).unwrap();
+        let diffs: Vec<_> = buckets.iter().map(|b| value_of(b, "diff")).collect();
+        assert_eq!(diffs, vec![None, None, None, Some(20.0)]);
+
+        let mut buckets = sample();
+        buckets[1].doc_count = 0;
+        let zeros = metric_requests(vec![request(
+            "diff",
+            AggregationType::Derivative {
+                buckets_path: "sales".to_string(),
+                gap_policy: GapPolicy::InsertZeros,
+            },
+        )]);
+        apply_parent_pipelines(&mut buckets, &zeros).unwrap();
+        let diffs: Vec<_> = buckets.iter().map(|b| value_of(b, "diff")).collect();
+        assert_eq!(diffs, vec![None, Some(-10.0), Some(25.0), Some(20.0)]);
+    }
+
+    #[test]
+    fn test_moving_fn() {
+        let mut buckets = sample();
+        let reqs = metric_requests(vec![
+            request(
+                "avg2",
+                AggregationType::MovingFn {
+                    buckets_path: "sales".to_string(),
+                    window: 2,
+                    function: MovingFunction::UnweightedAvg,
+                    shift: 0,
+                    gap_policy: GapPolicy::Skip,
+                },
+            ),
+            request(
+                "max_incl",
+                AggregationType::MovingFn {
+                    buckets_path: "price.max".to_string(),
+                    window: 2,
+                    function: MovingFunction::Max,
+                    shift: 1,
+                    gap_policy: GapPolicy::Skip,
+                },
+            ),
+        ]);
+        apply_parent_pipelines(&mut buckets, &reqs).unwrap();
+
+        let avgs: Vec<_> = buckets.iter().map(|b| value_of(b, "avg2")).collect();
+        assert_eq!(avgs, vec![None, Some(10.0), Some(20.0), Some(27.5)]);
+        let maxes: Vec<_> = buckets.iter().map(|b| value_of(b, "max_incl")).collect();
+        assert_eq!(maxes, vec![Some(10.0), Some(30.0), Some(30.0), Some(45.0)]);
+    }
+
+    #[test]
+    fn test_moving_functions() {
+        let values = [1.0, 2.0, 3.0];
+        let weighted = apply_moving_function(MovingFunction::LinearWeightedAvg, &values);
+        assert!((weighted.unwrap() - 14.0 / 6.0).abs() < 1e-9);
+        let std_dev = apply_moving_function(MovingFunction::StdDev, &values);
+        assert!((std_dev.unwrap() - (2.0f64 / 3.0).sqrt()).abs() < 1e-9);
+        assert_eq!(apply_moving_function(MovingFunction::Sum, &[]), None);
+    }
+
+    #[test]
+    fn test_bucket_sort_truncates() {
+        let mut buckets = sample();
+        let reqs = metric_requests(vec![request(
+            "top",
+            AggregationType::BucketSort {
+                sort: vec![BucketSortField {
+                    path: "sales".to_string(),
+                    order: SortDirection::Desc,
+                }],
+                from: 1,
+                size: Some(2),
+                gap_policy: GapPolicy::Skip,
+            },
+        )]);
+        apply_parent_pipelines(&mut buckets, &reqs).unwrap();
+        let keys: Vec<_> = buckets.iter().map(|b| b.key.as_str()).collect();
+        assert_eq!(keys, vec!["10", "20"]);
+
+        // No sort: truncation only, parent order preserved
+        let mut buckets = sample();
+        let reqs = metric_requests(vec![request(
+            "page",
+            AggregationType::BucketSort {
+                sort: vec![],
+                from: 0,
+                size: Some(3),
+                gap_policy: GapPolicy::Skip,
+            },
+        )]);
+        apply_parent_pipelines(&mut buckets, &reqs).unwrap();
+        let keys: Vec<_> = buckets.iter().map(|b| b.key.as_str()).collect();
+        assert_eq!(keys, vec!["0", "10", "20"]);
+    }
+
+    #[test]
+    fn test_bucket_selector() {
+        let mut buckets = sample();
+        let reqs = metric_requests(vec![request(
+            "big",
+            AggregationType::BucketSelector {
+                buckets_path: HashMap::from([
+                    ("total".to_string(), "sales".to_string()),
+                    ("docs".to_string(), "_count".to_string()),
+                ]),
+                script: "total > 20 && docs >= 3".to_string(),
+                gap_policy: GapPolicy::Skip,
+            },
+        )]);
+        apply_parent_pipelines(&mut buckets, &reqs).unwrap();
+        let keys: Vec<_> = buckets.iter().map(|b| b.key.as_str()).collect();
+        assert_eq!(keys, vec!["10", "30"]);
+    }
+
+    #[test]
+    fn test_unknown_path_is_rejected() {
+        let mut buckets = sample();
+        let reqs = metric_requests(vec![request(
+            "diff",
+            AggregationType::Derivative {
+                buckets_path: "missing.value".to_string(),
+                gap_policy: GapPolicy::Skip,
+            },
+        )]);
+        let err = apply_parent_pipelines(&mut buckets, &reqs).unwrap_err();
+        assert!(err.to_string().contains("missing"));
+    }
+
+    #[test]
+    fn test_resolve_paths() {
+        let mut inner = bucket("filter", 2, 7.0);
+        inner.sub_aggs.as_mut().unwrap().push(AggregationResult {
+            name: "pct".to_string(),
+            value: AggregationValue::Percentiles(crate::aggregations::PercentilesResult {
+                values: HashMap::from([("99".to_string(), Some(4.5))]),
+            }),
+        });
+        let mut outer = bucket("5", 3, 12.0);
+        outer.sub_aggs.as_mut().unwrap().push(AggregationResult {
+            name: "only_red".to_string(),
+            value: AggregationValue::Buckets(vec![inner]),
+        });
+
+        assert_eq!(resolve_path(&outer, "_count"), Some(3.0));
+        assert_eq!(resolve_path(&outer, "_key"), Some(5.0));
+        assert_eq!(resolve_path(&outer, "sales"), Some(12.0));
+        assert_eq!(resolve_path(&outer, "price.avg"), Some(4.0));
+        assert_eq!(resolve_path(&outer, "only_red"), Some(2.0));
+        assert_eq!(resolve_path(&outer, "only_red>sales"), Some(7.0));
+        assert_eq!(resolve_path(&outer, "only_red>pct[99.0]"), Some(4.5));
+        assert_eq!(resolve_path(&outer, "only_red>pct.99"), Some(4.5));
+        assert_eq!(resolve_path(&outer, "price.median"), None);
+    }
+}
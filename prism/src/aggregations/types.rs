@@ -1,3 +1,4 @@
+use crate::aggregations::metric::HyperLogLogPlusPlus;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -71,6 +72,179 @@ pub enum AggregationType {
     },
     /// Global aggregation — ignores query filter, runs on all docs
     Global {},
+    /// Approximate count of distinct values (HyperLogLog++)
+    Cardinality {
+        field: String,
+        /// Counts below this threshold are exact; above it the estimate has
+        /// about 0.81% standard error (default: 3000, max: 40000)
+        #[serde(default)]
+        precision_threshold: Option<u64>,
+        /// Return the sketch instead of its estimate, so shard results can
+        /// be merged
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        sketch: bool,
+    },
+    /// Best matching documents in the current bucket
+    TopHits {
+        /// Number of hits to return (default: 3)
+        #[serde(default)]
+        size: Option<usize>,
+        #[serde(default)]
+        from: Option<usize>,
+        /// Sort order; defaults to score descending
+        #[serde(default)]
+        sort: Vec<TopHitsSort>,
+        /// Stored fields to include in each hit (default: all)
+        #[serde(default, alias = "_source")]
+        source: Option<Vec<String>>,
+    },
+    /// Terms that are unusually frequent in the matched documents compared to
+    /// a background set (the whole collection by default)
+    SignificantTerms {
+        field: String,
+        /// Number of buckets to return (default: 10)
+        #[serde(default)]
+        size: Option<usize>,
+        /// Minimum foreground document count for a term (default: 3)
+        #[serde(default)]
+        min_doc_count: Option<u64>,
+        /// Query narrowing the background set
+        #[serde(default)]
+        background_filter: Option<String>,
+    },
+    /// Buckets for every combination of source values, in a stable order
+    /// and paged with `after`
+    Composite {
+        sources: Vec<CompositeSource>,
+        /// Buckets per page (default: 10)
+        #[serde(default)]
+        size: Option<usize>,
+        /// `after_key` from the previous page
+        #[serde(default)]
+        after: Option<HashMap<String, serde_json::Value>>,
+    },
+    /// Pipeline: difference between a metric in consecutive parent buckets
+    Derivative {
+        buckets_path: String,
+        #[serde(default)]
+        gap_policy: GapPolicy,
+    },
+    /// Pipeline: running total of a metric across parent buckets
+    CumulativeSum {
+        buckets_path: String,
+    },
+    /// Pipeline: function over a sliding window of parent buckets
+    MovingFn {
+        buckets_path: String,
+        window: usize,
+        #[serde(default)]
+        function: MovingFunction,
+        /// Shifts the window right; 0 ends it just before the current bucket
+        #[serde(default)]
+        shift: i64,
+        #[serde(default)]
+        gap_policy: GapPolicy,
+    },
+    /// Pipeline: sort and truncate the parent's buckets
+    BucketSort {
+        #[serde(default)]
+        sort: Vec<BucketSortField>,
+        #[serde(default)]
+        from: usize,
+        #[serde(default)]
+        size: Option<usize>,
+        #[serde(default)]
+        gap_policy: GapPolicy,
+    },
+    /// Pipeline: keep only parent buckets for which `script` is non-zero.
+    /// `buckets_path` maps script variables to metric paths.
+    BucketSelector {
+        buckets_path: HashMap<String, String>,
+        script: String,
+        #[serde(default)]
+        gap_policy: GapPolicy,
+    },
+}
+
+impl AggregationType {
+    /// Pipeline aggregations run on the buckets of their parent aggregation
+    /// instead of on documents
+    pub fn is_pipeline(&self) -> bool {
+        matches!(
+            self,
+            AggregationType::Derivative { .. }
+                | AggregationType::CumulativeSum { .. }
+                | AggregationType::MovingFn { .. }
+                | AggregationType::BucketSort { .. }
+                | AggregationType::BucketSelector { .. }
+        )
+    }
+}
+
+/// How pipeline aggregations treat buckets whose metric is missing or empty
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapPolicy {
+    /// Leave the bucket out of the calculation
+    #[default]
+    Skip,
+    /// Treat the missing value as zero
+    InsertZeros,
+}
+
+/// Window function of a `moving_fn` aggregation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovingFunction {
+    Max,
+    Min,
+    Sum,
+    #[default]
+    UnweightedAvg,
+    /// Average weighting newer buckets linearly higher
+    LinearWeightedAvg,
+    StdDev,
+}
+
+/// Sort key for `bucket_sort`; `_key` and `_count` sort by bucket key and
+/// document count
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BucketSortField {
+    pub path: String,
+    #[serde(default = "default_asc_order")]
+    pub order: SortDirection,
+}
+
+/// One value source of a `composite` aggregation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompositeSource {
+    /// Name of this source in bucket keys
+    pub name: String,
+    #[serde(flatten)]
+    pub source_type: CompositeSourceType,
+    /// Sort order of this source's values (default: asc)
+    #[serde(default = "default_asc_order")]
+    pub order: SortDirection,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CompositeSourceType {
+    Terms {
+        field: String,
+    },
+    Histogram {
+        field: String,
+        interval: f64,
+    },
+    DateHistogram {
+        field: String,
+        calendar_interval: String,
+    },
+}
+
+fn default_asc_order() -> SortDirection {
+    SortDirection::Asc
 }
 
 fn default_percents() -> Vec<f64> {
@@ -83,6 +257,30 @@ pub struct HistogramBounds {
     pub max: f64,
 }
 
+/// Sort key for `top_hits` and sorted searches; `_score` sorts by relevance
+/// and `_doc` by index order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopHitsSort {
+    pub field: String,
+    #[serde(default)]
+    pub order: SortDirection,
+    /// `_last` (default), `_first`, or a value used for documents without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<serde_json::Value>,
+    /// Sort a field missing from the schema as if no document had a value,
+    /// instead of failing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unmapped_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RangeEntry {
     #[serde(default)]
@@ -107,6 +305,11 @@ pub enum AggregationValue {
     Stats(StatsResult),
     Percentiles(PercentilesResult),
     Buckets(Vec<Bucket>),
+    TopHits(TopHitsResult),
+    SignificantTerms(SignificantTermsResult),
+    Composite(CompositeResult),
+    /// Distinct-value sketch of a `cardinality` aggregation with `sketch: true`
+    Cardinality(HyperLogLogPlusPlus),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -134,4 +337,62 @@ pub struct Bucket {
     pub to: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sub_aggs: Option<Vec<AggregationResult>>,
+    /// Most documents this bucket's `doc_count` may be missing, when it was
+    /// merged from per-shard top terms
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_count_error_upper_bound: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopHitsResult {
+    /// Number of documents in the bucket
+    pub total: u64,
+    pub max_score: Option<f32>,
+    pub hits: Vec<TopHit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopHit {
+    pub id: String,
+    pub score: f32,
+    pub fields: HashMap<String, serde_json::Value>,
+    /// Sort values, present when an explicit sort was requested
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sort: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignificantTermsResult {
+    /// Size of the foreground set
+    pub doc_count: u64,
+    /// Size of the background set
+    pub bg_count: u64,
+    pub buckets: Vec<SignificantTermsBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignificantTermsBucket {
+    pub key: String,
+    /// Foreground documents containing the term
+    pub doc_count: u64,
+    /// Background documents containing the term
+    pub bg_count: u64,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompositeResult {
+    /// Key of the last bucket; pass it as `after` to fetch the next page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_key: Option<HashMap<String, serde_json::Value>>,
+    pub buckets: Vec<CompositeBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompositeBucket {
+    /// Source name -> value
+    pub key: HashMap<String, serde_json::Value>,
+    pub doc_count: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_aggs: Option<Vec<AggregationResult>>,
 }
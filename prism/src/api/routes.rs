@@ -773,13 +773,10 @@ pub struct AggregateRequest {
     pub query: Option<String>,
     /// List of aggregations to run
     pub aggregations: Vec<AggRequest>,
-    /// Max documents to scan (default 10000)
-    #[serde(default = "default_scan_limit")]
-    pub scan_limit: usize,
-}
-
-fn default_scan_limit() -> usize {
-    10000
+    /// Deprecated: aggregations run over the full match set. Accepted for
+    /// compatibility with older clients and ignored.
+    #[serde(default)]
+    pub scan_limit: Option<usize>,
 }
 
 /// Aggregation API response
@@ -807,7 +804,7 @@ pub async fn aggregate(
     let query = Query {
         query_string,
         fields: vec![],
-        limit: request.scan_limit,
+        limit: 0,
         offset: 0,
         merge_strategy: None,
         text_weight: None,
@@ -929,6 +926,52 @@ pub async fn optimize_collection(
     Ok(Json(result))
 }
 
+/// Body of the split and shrink endpoints
+#[derive(Deserialize)]
+pub struct ReshardParams {
+    /// Shard count to split or shrink to
+    pub shards: usize,
+}
+
+/// POST /collections/:collection/_split
+pub async fn split_collection(
+    Path(collection): Path<String>,
+    State(manager): State<Arc<CollectionManager>>,
+    Json(params): Json<ReshardParams>,
+) -> Result<Json<crate::backends::ReshardResult>, (StatusCode, String)> {
+    manager
+        .split_shards(&collection, params.shards)
+        .await
+        .map(Json)
+        .map_err(reshard_error)
+}
+
+/// POST /collections/:collection/_shrink
+pub async fn shrink_collection(
+    Path(collection): Path<String>,
+    State(manager): State<Arc<CollectionManager>>,
+    Json(params): Json<ReshardParams>,
+) -> Result<Json<crate::backends::ReshardResult>, (StatusCode, String)> {
+    manager
+        .shrink_shards(&collection, params.shards)
+        .await
+        .map(Json)
+        .map_err(reshard_error)
+}
+
+fn reshard_error(e: crate::Error) -> (StatusCode, String) {
+    let status = match e {
+        crate::Error::CollectionNotFound(_) => StatusCode::NOT_FOUND,
+        crate::Error::InvalidQuery(_) | crate::Error::Schema(_) => StatusCode::BAD_REQUEST,
+        crate::Error::ReadOnly(_) => StatusCode::CONFLICT,
+        _ => {
+            tracing::error!("Failed to reshard: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, e.to_string())
+}
+
 /// GET /collections/:collection/doc/:id/reconstruct
 pub async fn reconstruct_document(
     Path((collection, id)): Path<(String, String)>,
@@ -213,6 +213,11 @@ impl ApiServer {
         self.manager.clone()
     }
 
+    /// Get the ingest pipeline registry, shared with ES-compat ingest
+    pub fn pipeline_registry(&self) -> Arc<PipelineRegistry> {
+        self.pipeline_registry.clone()
+    }
+
     /// Build router with additional routes merged in
     /// Used for integrating optional features like ES-compat
     ///
@@ -543,6 +548,14 @@ impl ApiServer {
                 "/collections/:collection/optimize",
                 post(crate::api::routes::optimize_collection),
             )
+            .route(
+                "/collections/:collection/_split",
+                post(crate::api::routes::split_collection),
+            )
+            .route(
+                "/collections/:collection/_shrink",
+                post(crate::api::routes::shrink_collection),
+            )
             .route(
                 "/collections/:collection/doc/:id/reconstruct",
                 get(crate::api::routes::reconstruct_document),
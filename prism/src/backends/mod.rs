@@ -7,8 +7,8 @@ pub mod vector;
 pub use graph::{GraphEdge, GraphNode, GraphStats, ShardedGraphBackend};
 pub use hybrid::HybridSearchCoordinator;
 pub use r#trait::{
-    BackendStats, Document, HighlightConfig, Query, SearchBackend, SearchResult, SearchResults,
-    SearchResultsWithAggs,
+    BackendStats, Document, HighlightConfig, HighlightFieldOptions, Query, SearchBackend,
+    SearchResult, SearchResults, SearchResultsWithAggs, SortedSearchResults, TermStatistics,
 };
-pub use text::TextBackend;
-pub use vector::VectorBackend;
+pub use text::{SnapshotCursor, SnapshotResults, TextBackend, TextSnapshot};
+pub use vector::{ReshardResult, VectorBackend, VectorSnapshot};
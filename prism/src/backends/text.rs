@@ -7,20 +7,22 @@ use crate::aggregations::{
     AggregationRequest, AggregationResult, AggregationType, AggregationValue, Bucket,
 };
 use crate::backends::{
-    BackendStats, Document, Query, SearchBackend, SearchResult, SearchResults,
-    SearchResultsWithAggs,
+    BackendStats, Document, HighlightConfig, Query, SearchBackend, SearchResult, SearchResults,
+    SearchResultsWithAggs, SortedSearchResults, TermStatistics,
 };
 use crate::ranking::{apply_ranking_adjustments, RankableResult, RankingConfig};
 use crate::schema::{CollectionSchema, FieldType, TokenizerType};
 use crate::tokenizer::{code_tokenizer, CODE_TOKENIZER_NAME};
 use crate::{Error, Result};
 use async_trait::async_trait;
-use prism_storage::{LocalStorage, SegmentStorage, TantivyStorageAdapter};
-use std::collections::HashMap;
+use prism_storage::{
+    LocalStorage, SegmentStorage, StorageBackend, StoragePath, TantivyStorageAdapter,
+};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use tantivy::{
-    collector::TopDocs, query::QueryParser, schema::*, DateTime, DocSet,
+    collector::TopDocs, query::{Bm25StatisticsProvider, QueryParser}, schema::*, DateTime, DocSet,
     Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term,
 };
 
@@ -45,6 +47,8 @@ struct CollectionIndex {
     boost_enabled: bool,
     /// Boosting configuration for ranking adjustments
     boosting_config: Option<crate::schema::BoostingConfig>,
+    /// Fields declared with `multi: true` (accept arrays, stored values returned as arrays)
+    multi_fields: HashSet<Field>,
 }
 
 /// Convert a Tantivy OwnedValue to a serde_json::Value.
@@ -77,6 +81,32 @@ fn owned_value_to_json(value: &tantivy::schema::OwnedValue) -> Option<serde_json
     }
 }
 
+/// Collect the stored fields of a document as JSON.
+///
+/// Multi-valued fields are returned as arrays of all stored values; other
+/// fields return their first value.
+fn stored_fields(
+    coll: &CollectionIndex,
+    doc: &TantivyDocument,
+) -> HashMap<String, serde_json::Value> {
+    let mut fields = HashMap::new();
+    for (field, entry) in coll.schema.fields() {
+        if !entry.is_stored() {
+            continue;
+        }
+        if coll.multi_fields.contains(&field) {
+            let values: Vec<serde_json::Value> =
+                doc.get_all(field).filter_map(owned_value_to_json).collect();
+            if !values.is_empty() {
+                fields.insert(entry.name().to_string(), serde_json::Value::Array(values));
+            }
+        } else if let Some(json_value) = doc.get_first(field).and_then(owned_value_to_json) {
+            fields.insert(entry.name().to_string(), json_value);
+        }
+    }
+    fields
+}
+
 impl TextBackend {
     /// Create a new TextBackend with local filesystem storage.
     ///
@@ -110,11 +140,71 @@ impl TextBackend {
         })
     }
 
+    /// Storage holding the persisted index files.
+    pub fn segment_storage(&self) -> Arc<dyn SegmentStorage> {
+        Arc::clone(&self.storage)
+    }
+
     /// Remove a collection from this backend, dropping all in-memory state.
     pub fn remove_collection(&self, name: &str) {
         self.collections.write().unwrap().remove(name);
     }
 
+    /// Delete a collection's index files from storage.
+    ///
+    /// Call after [`TextBackend::remove_collection`]; initializing the
+    /// collection again starts from an empty index.
+    pub async fn purge_collection(&self, name: &str) -> Result<()> {
+        self.storage
+            .delete_prefix(&StoragePath::new(name, StorageBackend::Tantivy))
+            .await
+            .map_err(|e| Error::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Recreate a collection's index with a new schema, re-indexing every
+    /// live document from its stored fields.
+    ///
+    /// Tantivy schemas are fixed once an index exists, so this is how fields
+    /// are added. Fields that were not stored are lost, `_indexed_at` is reset,
+    /// and writes made while the rebuild runs are dropped. Returns the number
+    /// of documents re-indexed.
+    pub async fn rebuild_collection(&self, name: &str, schema: &CollectionSchema) -> Result<usize> {
+        let docs = {
+            let collections = self.collections.read().unwrap();
+            let coll = collections
+                .get(name)
+                .ok_or_else(|| Error::CollectionNotFound(name.to_string()))?;
+
+            coll.reader.reload()?;
+            let searcher = coll.reader.searcher();
+            let addresses = searcher.search(
+                &tantivy::query::AllQuery,
+                &tantivy::collector::DocSetCollector,
+            )?;
+            let mut docs = Vec::with_capacity(addresses.len());
+            for address in addresses {
+                let doc: TantivyDocument = searcher.doc(address)?;
+                let mut fields = stored_fields(coll, &doc);
+                let Some(serde_json::Value::String(id)) = fields.remove("id") else {
+                    continue;
+                };
+                fields.remove("_indexed_at");
+                docs.push(Document { id, fields });
+            }
+            docs
+        };
+
+        self.remove_collection(name);
+        self.purge_collection(name).await?;
+        self.initialize(name, schema).await?;
+        let count = docs.len();
+        if !docs.is_empty() {
+            self.index(name, docs).await?;
+        }
+        Ok(count)
+    }
+
     /// Initialize a collection from schema.
     ///
     /// Creates or opens a Tantivy index using the unified SegmentStorage.
@@ -157,6 +247,15 @@ impl TextBackend {
             field_map.insert("_boost".to_string(), boost_field);
         }
 
+        // _version / _seq_no: version of the last write (for optimistic concurrency)
+        if system_fields.document_version {
+            for name in ["_version", "_seq_no"] {
+                let field = schema_builder
+                    .add_u64_field(name, NumericOptions::default().set_stored().set_fast());
+                field_map.insert(name.to_string(), field);
+            }
+        }
+
         // Add configured fields
         for field_def in &text_config.fields {
             let field = match field_def.field_type {
@@ -209,6 +308,10 @@ impl TextBackend {
                     if field_def.stored {
                         options = options.set_stored();
                     }
+                    if field_def.fast {
+                        // Keep whole values in the column so terms buckets match stored values
+                        options = options.set_fast(Some("raw"));
+                    }
                     schema_builder.add_text_field(&field_def.name, options)
                 }
                 FieldType::String => {
@@ -216,6 +319,9 @@ impl TextBackend {
                     if field_def.stored {
                         opts = opts | STORED;
                     }
+                    if field_def.fast {
+                        opts = opts | FAST;
+                    }
                     schema_builder.add_text_field(&field_def.name, opts)
                 }
                 FieldType::I64 => {
@@ -223,6 +329,9 @@ impl TextBackend {
                     if field_def.stored {
                         opts = opts.set_stored();
                     }
+                    if field_def.fast {
+                        opts = opts.set_fast();
+                    }
                     schema_builder.add_i64_field(&field_def.name, opts)
                 }
                 FieldType::U64 => {
@@ -230,6 +339,9 @@ impl TextBackend {
                     if field_def.stored {
                         opts = opts.set_stored();
                     }
+                    if field_def.fast {
+                        opts = opts.set_fast();
+                    }
                     schema_builder.add_u64_field(&field_def.name, opts)
                 }
                 FieldType::F64 => {
@@ -237,6 +349,9 @@ impl TextBackend {
                     if field_def.stored {
                         opts = opts.set_stored();
                     }
+                    if field_def.fast {
+                        opts = opts.set_fast();
+                    }
                     schema_builder.add_f64_field(&field_def.name, opts)
                 }
                 FieldType::Bool => {
@@ -244,6 +359,9 @@ impl TextBackend {
                     if field_def.stored {
                         opts = opts.set_stored();
                     }
+                    if field_def.fast {
+                        opts = opts.set_fast();
+                    }
                     schema_builder.add_bool_field(&field_def.name, opts)
                 }
                 FieldType::Date => {
@@ -251,6 +369,9 @@ impl TextBackend {
                     if field_def.stored {
                         opts = opts.set_stored();
                     }
+                    if field_def.fast {
+                        opts = opts.set_fast();
+                    }
                     schema_builder.add_date_field(&field_def.name, opts)
                 }
                 FieldType::Bytes => schema_builder.add_bytes_field(&field_def.name, STORED),
@@ -317,6 +438,13 @@ impl TextBackend {
         let indexed_at_enabled = existing_field_map.contains_key("_indexed_at");
         let boost_enabled = existing_field_map.contains_key("_boost");
 
+        let multi_fields: HashSet<Field> = text_config
+            .fields
+            .iter()
+            .filter(|f| f.multi)
+            .filter_map(|f| existing_field_map.get(&f.name).copied())
+            .collect();
+
         let collection_index = CollectionIndex {
             index,
             schema: existing_schema,
@@ -326,6 +454,7 @@ impl TextBackend {
             indexed_at_enabled,
             boost_enabled,
             boosting_config: schema.boosting.clone(),
+            multi_fields,
         };
 
         self.collections
@@ -337,6 +466,100 @@ impl TextBackend {
     }
 }
 
+/// Add a single JSON value to a Tantivy document, converting it to the field's
+/// schema type. Returns false when the value cannot be represented.
+fn add_json_value(
+    doc: &mut TantivyDocument,
+    field: Field,
+    field_type: &tantivy::schema::FieldType,
+    value: &serde_json::Value,
+    doc_id: &str,
+    field_name: &str,
+) -> bool {
+    match (value, field_type) {
+        // Date fields — parse ISO 8601 string or epoch micros
+        (serde_json::Value::String(s), tantivy::schema::FieldType::Date(_)) => {
+            // Try RFC 3339 / ISO 8601 parsing via chrono
+            if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
+                let micros = dt.timestamp_micros();
+                doc.add_date(field, DateTime::from_timestamp_micros(micros));
+                true
+            } else {
+                tracing::warn!(
+                    "Document '{}': could not parse date string '{}' for field '{}'",
+                    doc_id,
+                    s,
+                    field_name
+                );
+                false
+            }
+        }
+        (serde_json::Value::Number(n), tantivy::schema::FieldType::Date(_)) => {
+            // Treat number as epoch microseconds
+            if let Some(micros) = n.as_i64() {
+                doc.add_date(field, DateTime::from_timestamp_micros(micros));
+                true
+            } else {
+                false
+            }
+        }
+
+        // String/text values (must come AFTER Date match)
+        (serde_json::Value::String(s), _) => {
+            doc.add_text(field, s);
+            true
+        }
+
+        // Numbers - match against schema type for proper conversion
+        (serde_json::Value::Number(n), tantivy::schema::FieldType::U64(_)) => {
+            // Try u64 first, then i64 (for positive values stored as i64)
+            if let Some(v) = n.as_u64() {
+                doc.add_u64(field, v);
+                true
+            } else if let Some(v) = n.as_i64() {
+                if v >= 0 {
+                    doc.add_u64(field, v as u64);
+                    true
+                } else {
+                    tracing::warn!(
+                        "Document '{}': negative i64 value {} cannot be stored in u64 field '{}'",
+                        doc_id,
+                        v,
+                        field_name
+                    );
+                    false
+                }
+            } else {
+                false
+            }
+        }
+        (serde_json::Value::Number(n), tantivy::schema::FieldType::I64(_)) => {
+            if let Some(v) = n.as_i64() {
+                doc.add_i64(field, v);
+                true
+            } else {
+                false
+            }
+        }
+        (serde_json::Value::Number(n), tantivy::schema::FieldType::F64(_)) => {
+            if let Some(v) = n.as_f64() {
+                doc.add_f64(field, v);
+                true
+            } else {
+                false
+            }
+        }
+
+        // Booleans
+        (serde_json::Value::Bool(b), tantivy::schema::FieldType::Bool(_)) => {
+            doc.add_bool(field, *b);
+            true
+        }
+
+        _ => false,
+    }
+}
+
 #[async_trait]
 impl SearchBackend for TextBackend {
     #[tracing::instrument(name = "text_index", skip(self, docs), fields(collection = %collection, doc_count = docs.len()))]
@@ -394,86 +617,45 @@ impl SearchBackend for TextBackend {
                     let field_entry = coll.schema.get_field_entry(*field);
                     let field_type = field_entry.field_type();
 
-                    let added = match (&value, field_type) {
-                        // Date fields — parse ISO 8601 string or epoch micros
-                        (serde_json::Value::String(s), tantivy::schema::FieldType::Date(_)) => {
-                            // Try RFC 3339 / ISO 8601 parsing via chrono
-                            if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
-                                let micros = dt.timestamp_micros();
-                                tantivy_doc
-                                    .add_date(*field, DateTime::from_timestamp_micros(micros));
-                                true
-                            } else {
+                    if let serde_json::Value::Array(elements) = &value {
+                        if !coll.multi_fields.contains(field) {
+                            tracing::warn!(
+                                "Document '{}': field '{}' is not multi-valued; skipped array value",
+                                doc.id,
+                                field_name
+                            );
+                            continue;
+                        }
+                        // Index each element as a separate value of the same field.
+                        // Empty arrays and nulls add nothing, so the field stays missing.
+                        for element in elements.iter().filter(|e| !e.is_null()) {
+                            if !add_json_value(
+                                &mut tantivy_doc,
+                                *field,
+                                field_type,
+                                element,
+                                &doc.id,
+                                &field_name,
+                            ) {
                                 tracing::warn!(
-                                    "Document '{}': could not parse date string '{}' for field '{}'",
-                                    doc.id, s, field_name
+                                    "Document '{}': skipped element {:?} of field '{}'",
+                                    doc.id,
+                                    element,
+                                    field_name
                                 );
-                                false
-                            }
-                        }
-                        (serde_json::Value::Number(n), tantivy::schema::FieldType::Date(_)) => {
-                            // Treat number as epoch microseconds
-                            if let Some(micros) = n.as_i64() {
-                                tantivy_doc
-                                    .add_date(*field, DateTime::from_timestamp_micros(micros));
-                                true
-                            } else {
-                                false
-                            }
-                        }
-
-                        // String/text values (must come AFTER Date match)
-                        (serde_json::Value::String(s), _) => {
-                            tantivy_doc.add_text(*field, s);
-                            true
-                        }
-
-                        // Numbers - match against schema type for proper conversion
-                        (serde_json::Value::Number(n), tantivy::schema::FieldType::U64(_)) => {
-                            // Try u64 first, then i64 (for positive values stored as i64)
-                            if let Some(v) = n.as_u64() {
-                                tantivy_doc.add_u64(*field, v);
-                                true
-                            } else if let Some(v) = n.as_i64() {
-                                if v >= 0 {
-                                    tantivy_doc.add_u64(*field, v as u64);
-                                    true
-                                } else {
-                                    tracing::warn!(
-                                        "Document '{}': negative i64 value {} cannot be stored in u64 field '{}'",
-                                        doc.id, v, field_name
-                                    );
-                                    false
-                                }
-                            } else {
-                                false
-                            }
-                        }
-                        (serde_json::Value::Number(n), tantivy::schema::FieldType::I64(_)) => {
-                            if let Some(v) = n.as_i64() {
-                                tantivy_doc.add_i64(*field, v);
-                                true
-                            } else {
-                                false
-                            }
-                        }
-                        (serde_json::Value::Number(n), tantivy::schema::FieldType::F64(_)) => {
-                            if let Some(v) = n.as_f64() {
-                                tantivy_doc.add_f64(*field, v);
-                                true
-                            } else {
-                                false
                             }
                         }
+                        continue;
+                    }
 
-                        // Booleans
-                        (serde_json::Value::Bool(b), tantivy::schema::FieldType::Bool(_)) => {
-                            tantivy_doc.add_bool(*field, *b);
-                            true
-                        }
-
-                        _ => false,
-                    };
+                    let added = add_json_value(
+                        &mut tantivy_doc,
+                        *field,
+                        field_type,
+                        &value,
+                        &doc.id,
+                        &field_name,
+                    );
 
                     if !added {
                         tracing::warn!(
@@ -485,7 +667,6 @@ impl SearchBackend for TextBackend {
                     }
                 }
             }
-
             writer.add_document(tantivy_doc)?;
         }
 
@@ -499,8 +680,6 @@ impl SearchBackend for TextBackend {
 
     #[tracing::instrument(name = "text_search", skip(self, query), fields(collection = %collection))]
     async fn search(&self, collection: &str, query: Query) -> Result<SearchResults> {
-        let start = std::time::Instant::now();
-
         let collections = self.collections.read().unwrap();
         let coll = collections
             .get(collection)
@@ -512,187 +691,7 @@ impl SearchBackend for TextBackend {
         coll.reader.reload()?;
         let searcher = coll.reader.searcher();
 
-        // Get searchable fields
-        let mut searchable_fields = Vec::new();
-        for (field, entry) in coll.schema.fields() {
-            if entry.field_type().is_indexed() {
-                if let tantivy::schema::FieldType::Str(_) = entry.field_type() {
-                    searchable_fields.push(field);
-                }
-            }
-        }
-
-        // Determine fields to search
-        let fields_to_search: Vec<Field> = if query.fields.is_empty() {
-            searchable_fields
-        } else {
-            query
-                .fields
-                .iter()
-                .filter_map(|f| coll.field_map.get(f).copied())
-                .collect()
-        };
-
-        if fields_to_search.is_empty() {
-            return Ok(SearchResults {
-                results: vec![],
-                total: 0,
-                latency_ms: start.elapsed().as_millis() as u64,
-            });
-        }
-
-        let query_parser = QueryParser::for_index(&coll.index, fields_to_search.clone());
-
-        // Tantivy's query parser can panic on certain inputs (e.g., bare `*`
-        // triggers "Exist query without a field isn't allowed").  Catch panics
-        // so malicious/malformed queries don't crash the server.
-        let query_string = query.query_string.clone();
-        let parsed_query = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            query_parser.parse_query(&query_string)
-        })) {
-            Ok(Ok(q)) => q,
-            Ok(Err(e)) => return Err(Error::InvalidQuery(e.to_string())),
-            Err(_) => {
-                return Err(Error::InvalidQuery(format!(
-                    "Query parser panicked on input: {:?}",
-                    query_string
-                )));
-            }
-        };
-
-        let top_docs = searcher.search(
-            &parsed_query,
-            &TopDocs::with_limit(query.limit + query.offset),
-        )?;
-
-        let id_field = coll.field_map.get("id").unwrap();
-        let mut results = Vec::new();
-
-        for (rank, (score, doc_addr)) in top_docs.iter().enumerate() {
-            if rank < query.offset {
-                continue;
-            }
-
-            let doc: TantivyDocument = searcher.doc(*doc_addr)?;
-
-            // Get ID
-            let id = doc
-                .get_first(*id_field)
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-
-            // Get all stored fields
-            let mut fields = HashMap::new();
-            for (field, entry) in coll.schema.fields() {
-                if entry.is_stored() {
-                    if let Some(value) = doc.get_first(field) {
-                        if let Some(json_value) = owned_value_to_json(value) {
-                            fields.insert(entry.name().to_string(), json_value);
-                        }
-                    }
-                }
-            }
-
-            results.push(SearchResult {
-                id,
-                score: *score,
-                fields,
-                highlight: None,
-            });
-        }
-
-        // Generate highlights if requested
-        if let Some(ref hl_config) = query.highlight {
-            use tantivy::snippet::SnippetGenerator;
-
-            for hl_field_name in &hl_config.fields {
-                if let Some(&field) = coll.field_map.get(hl_field_name) {
-                    // Only generate snippets for text fields
-                    let entry = coll.schema.get_field_entry(field);
-                    if !matches!(entry.field_type(), tantivy::schema::FieldType::Str(_)) {
-                        continue;
-                    }
-
-                    if let Ok(mut generator) =
-                        SnippetGenerator::create(&searcher, &*parsed_query, field)
-                    {
-                        generator.set_max_num_chars(hl_config.fragment_size);
-
-                        for result in &mut results {
-                            // Get the stored text for this field
-                            if let Some(text_value) =
-                                result.fields.get(hl_field_name).and_then(|v| v.as_str())
-                            {
-                                let mut snippet = generator.snippet(text_value);
-                                if !snippet.is_empty() {
-                                    snippet.set_snippet_prefix_postfix(
-                                        &hl_config.pre_tag,
-                                        &hl_config.post_tag,
-                                    );
-                                    let html = snippet.to_html();
-
-                                    let highlights =
-                                        result.highlight.get_or_insert_with(HashMap::new);
-                                    let fragments = highlights
-                                        .entry(hl_field_name.clone())
-                                        .or_insert_with(Vec::new);
-                                    fragments.push(html);
-                                    // Tantivy returns one best fragment per call; truncate to configured max
-                                    fragments.truncate(hl_config.number_of_fragments);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        // Apply ranking adjustments if boosting is configured
-        let results = if let Some(boosting_config) = &coll.boosting_config {
-            let ranking_config = RankingConfig::from_boosting_config(boosting_config);
-            let now = std::time::SystemTime::now();
-
-            // Preserve highlights before moving results into ranking pipeline
-            let highlight_map: HashMap<String, Option<HashMap<String, Vec<String>>>> = results
-                .iter()
-                .map(|r| (r.id.clone(), r.highlight.clone()))
-                .collect();
-
-            // Convert to rankable results
-            let mut rankable: Vec<RankableResult> = results
-                .into_iter()
-                .map(|r| RankableResult::from_fields(r.id, r.score, r.fields))
-                .collect();
-
-            // Apply ranking adjustments (recency decay, popularity boost)
-            apply_ranking_adjustments(&mut rankable, &ranking_config, now);
-
-            // Convert back to SearchResult, restoring highlights
-            rankable
-                .into_iter()
-                .map(|r| {
-                    let hl = highlight_map.get(&r.id).cloned().flatten();
-                    SearchResult {
-                        id: r.id,
-                        score: r.adjusted_score,
-                        fields: r.fields,
-                        highlight: hl,
-                    }
-                })
-                .collect()
-        } else {
-            results
-        };
-
-        let total = results.len();
-        let latency_ms = start.elapsed().as_millis() as u64;
-
-        Ok(SearchResults {
-            results,
-            total,
-            latency_ms,
-        })
+        search_on(coll, &searcher, &query, None)
     }
 
     async fn get(&self, collection: &str, id: &str) -> Result<Option<Document>> {
@@ -712,16 +711,7 @@ impl SearchBackend for TextBackend {
         if let Some((_score, doc_addr)) = top_docs.first() {
             let doc: TantivyDocument = searcher.doc(*doc_addr)?;
 
-            let mut fields = HashMap::new();
-            for (field, entry) in coll.schema.fields() {
-                if entry.is_stored() {
-                    if let Some(value) = doc.get_first(field) {
-                        if let Some(json_value) = owned_value_to_json(value) {
-                            fields.insert(entry.name().to_string(), json_value);
-                        }
-                    }
-                }
-            }
+            let fields = stored_fields(coll, &doc);
 
             Ok(Some(Document {
                 id: id.to_string(),
@@ -779,8 +769,6 @@ impl SearchBackend for TextBackend {
         query: &Query,
         aggregations: Vec<AggregationRequest>,
     ) -> Result<SearchResultsWithAggs> {
-        let _start = std::time::Instant::now();
-
         let collections = self.collections.read().unwrap();
         let coll = collections
             .get(collection)
@@ -789,107 +777,459 @@ impl SearchBackend for TextBackend {
         coll.reader.reload()?;
         let searcher = coll.reader.searcher();
 
-        // Get searchable text fields
-        let mut searchable_fields = Vec::new();
-        for (field, entry) in coll.schema.fields() {
-            if entry.field_type().is_indexed() {
-                if let tantivy::schema::FieldType::Str(_) = entry.field_type() {
-                    searchable_fields.push(field);
-                }
-            }
-        }
-
-        let fields_to_search: Vec<Field> = if query.fields.is_empty() {
-            searchable_fields.clone()
-        } else {
-            query
-                .fields
-                .iter()
-                .filter_map(|f| coll.field_map.get(f).copied())
-                .collect()
-        };
+        search_with_aggs_on(coll, &searcher, query, &aggregations)
+    }
+}
 
-        if fields_to_search.is_empty() {
-            return Ok(SearchResultsWithAggs {
-                results: vec![],
-                total: 0,
-                aggregations: HashMap::new(),
-            });
-        }
+/// Indexed text and string fields, searched when a query names none
+fn searchable_fields(coll: &CollectionIndex) -> Vec<Field> {
+    coll.schema
+        .fields()
+        .filter(|(_, entry)| {
+            entry.field_type().is_indexed()
+                && matches!(entry.field_type(), tantivy::schema::FieldType::Str(_))
+        })
+        .map(|(field, _)| field)
+        .collect()
+}
 
-        let query_parser = QueryParser::for_index(&coll.index, fields_to_search.clone());
-        let query_string = query.query_string.clone();
-        let parsed_query = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            query_parser.parse_query(&query_string)
-        })) {
-            Ok(Ok(q)) => q,
-            Ok(Err(e)) => return Err(Error::InvalidQuery(e.to_string())),
-            Err(_) => {
-                return Err(Error::InvalidQuery(format!(
-                    "Query parser panicked on input: {:?}",
-                    query_string
-                )));
-            }
-        };
+/// Parse the query string of a relevance-ranked search over its fields; `None`
+/// when none of the fields is searchable
+fn parse_search_query(
+    coll: &CollectionIndex,
+    query: &Query,
+) -> Result<Option<Box<dyn tantivy::query::Query>>> {
+    // Determine fields to search
+    let fields_to_search: Vec<Field> = if query.fields.is_empty() {
+        searchable_fields(coll)
+    } else {
+        query
+            .fields
+            .iter()
+            .filter_map(|f| coll.field_map.get(f).copied())
+            .collect()
+    };
 
-        // Collect all matching docs for aggregations
-        let all_docs = searcher.search(&parsed_query, &TopDocs::with_limit(10000))?;
+    if fields_to_search.is_empty() {
+        return Ok(None);
+    }
 
-        // Build results
-        let id_field = coll.field_map.get("id").unwrap();
-        let mut results = Vec::new();
+    let query_parser = QueryParser::for_index(&coll.index, fields_to_search);
 
-        for (rank, (score, doc_addr)) in all_docs.iter().enumerate() {
-            if rank < query.offset || rank >= query.offset + query.limit {
-                continue;
-            }
+    // Tantivy's query parser can panic on certain inputs (e.g., bare `*`
+    // triggers "Exist query without a field isn't allowed").  Catch panics
+    // so malicious/malformed queries don't crash the server.
+    let query_string = query.query_string.clone();
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        query_parser.parse_query(&query_string)
+    })) {
+        Ok(Ok(q)) => Ok(Some(q)),
+        Ok(Err(e)) => Err(Error::InvalidQuery(e.to_string())),
+        Err(_) => Err(Error::InvalidQuery(format!(
+            "Query parser panicked on input: {:?}",
+            query_string
+        ))),
+    }
+}
 
-            let doc: TantivyDocument = searcher.doc(*doc_addr)?;
+/// Collect the BM25 statistics of a query's terms in one searcher generation
+fn term_statistics_on(
+    coll: &CollectionIndex,
+    searcher: &tantivy::Searcher,
+    query: &Query,
+) -> Result<TermStatistics> {
+    let mut statistics = TermStatistics {
+        total_docs: searcher.num_docs(),
+        ..Default::default()
+    };
+    let Some(parsed_query) = parse_search_query(coll, query)? else {
+        return Ok(statistics);
+    };
 
-            let id = doc
-                .get_first(*id_field)
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
+    let mut terms: Vec<Term> = Vec::new();
+    parsed_query.query_terms(&mut |term, _| terms.push(term.clone()));
+    for term in terms {
+        let Some(text) = term.value().as_str().map(str::to_string) else {
+            continue;
+        };
+        let field_name = coll.schema.get_field_name(term.field()).to_string();
+        if !statistics.field_tokens.contains_key(&field_name) {
+            let tokens = searcher.total_num_tokens(term.field())?;
+            statistics.field_tokens.insert(field_name.clone(), tokens);
+        }
+        let doc_freq = searcher.doc_freq(&term)?;
+        statistics
+            .doc_freqs
+            .entry(field_name)
+            .or_default()
+            .insert(text, doc_freq);
+    }
+    Ok(statistics)
+}
 
-            let mut fields = HashMap::new();
-            for (field, entry) in coll.schema.fields() {
-                if entry.is_stored() {
-                    if let Some(value) = doc.get_first(field) {
-                        if let Some(json_value) = owned_value_to_json(value) {
-                            fields.insert(entry.name().to_string(), json_value);
-                        }
-                    }
-                }
-            }
+/// BM25 statistics gathered by a DFS phase; terms and fields it did not
+/// collect fall back to the local searcher
+struct DfsStatistics<'a> {
+    coll: &'a CollectionIndex,
+    searcher: &'a tantivy::Searcher,
+    statistics: &'a TermStatistics,
+}
 
-            results.push(SearchResult {
-                id,
-                score: *score,
-                fields,
-                highlight: None,
-            });
+impl Bm25StatisticsProvider for DfsStatistics<'_> {
+    fn total_num_tokens(&self, field: Field) -> tantivy::Result<u64> {
+        match self
+            .statistics
+            .field_tokens
+            .get(self.coll.schema.get_field_name(field))
+        {
+            Some(tokens) => Ok(*tokens),
+            None => self.searcher.total_num_tokens(field),
         }
+    }
 
-        // Collect all doc addresses for aggregation processing
-        let doc_addrs: Vec<tantivy::DocAddress> = all_docs.iter().map(|(_s, addr)| *addr).collect();
+    fn total_num_docs(&self) -> tantivy::Result<u64> {
+        if self.statistics.total_docs > 0 {
+            Ok(self.statistics.total_docs)
+        } else {
+            self.searcher.total_num_docs()
+        }
+    }
 
-        // Run aggregations
-        let agg_results = execute_aggregations(
-            &searcher,
+    fn doc_freq(&self, term: &Term) -> tantivy::Result<u64> {
+        let value = term.value();
+        let doc_freq = value.as_str().and_then(|text| {
+            self.statistics
+                .doc_freqs
+                .get(self.coll.schema.get_field_name(term.field()))?
+                .get(text)
+        });
+        match doc_freq {
+            Some(doc_freq) => Ok(*doc_freq),
+            None => self.searcher.doc_freq(term),
+        }
+    }
+}
+
+/// Run a relevance-ranked query against one searcher generation, scoring
+/// BM25 from `statistics` when a DFS phase collected them
+fn search_on(
+    coll: &CollectionIndex,
+    searcher: &tantivy::Searcher,
+    query: &Query,
+    statistics: Option<&TermStatistics>,
+) -> Result<SearchResults> {
+    let start = std::time::Instant::now();
+
+    let Some(parsed_query) = parse_search_query(coll, query)? else {
+        return Ok(SearchResults {
+            results: vec![],
+            total: 0,
+            latency_ms: start.elapsed().as_millis() as u64,
+        });
+    };
+
+    let collector = TopDocs::with_limit(query.limit + query.offset);
+    let top_docs = match statistics {
+        Some(statistics) => searcher.search_with_statistics_provider(
+            &parsed_query,
+            &collector,
+            &DfsStatistics {
+                coll,
+                searcher,
+                statistics,
+            },
+        )?,
+        None => searcher.search(&parsed_query, &collector)?,
+    };
+
+    let id_field = coll.field_map.get("id").unwrap();
+    let mut results = Vec::new();
+
+    for (rank, (score, doc_addr)) in top_docs.iter().enumerate() {
+        if rank < query.offset {
+            continue;
+        }
+
+        let doc: TantivyDocument = searcher.doc(*doc_addr)?;
+
+        // Get ID
+        let id = doc
+            .get_first(*id_field)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        // Get all stored fields
+        let fields = stored_fields(coll, &doc);
+
+        results.push(SearchResult {
+            id,
+            score: *score,
+            fields,
+            highlight: None,
+        });
+    }
+
+    // Generate highlights if requested
+    if let Some(ref hl_config) = query.highlight {
+        highlight_results(coll, searcher, &*parsed_query, hl_config, &mut results);
+    }
+
+    // Apply ranking adjustments if boosting is configured
+    let results = if let Some(boosting_config) = &coll.boosting_config {
+        let ranking_config = RankingConfig::from_boosting_config(boosting_config);
+        let now = std::time::SystemTime::now();
+
+        // Preserve highlights before moving results into ranking pipeline
+        let highlight_map: HashMap<String, Option<HashMap<String, Vec<String>>>> = results
+            .iter()
+            .map(|r| (r.id.clone(), r.highlight.clone()))
+            .collect();
+
+        // Convert to rankable results
+        let mut rankable: Vec<RankableResult> = results
+            .into_iter()
+            .map(|r| RankableResult::from_fields(r.id, r.score, r.fields))
+            .collect();
+
+        // Apply ranking adjustments (recency decay, popularity boost)
+        apply_ranking_adjustments(&mut rankable, &ranking_config, now);
+
+        // Convert back to SearchResult, restoring highlights
+        rankable
+            .into_iter()
+            .map(|r| {
+                let hl = highlight_map.get(&r.id).cloned().flatten();
+                SearchResult {
+                    id: r.id,
+                    score: r.adjusted_score,
+                    fields: r.fields,
+                    highlight: hl,
+                }
+            })
+            .collect()
+    } else {
+        results
+    };
+
+    let total = results.len();
+    let latency_ms = start.elapsed().as_millis() as u64;
+
+    Ok(SearchResults {
+        results,
+        total,
+        latency_ms,
+    })
+}
+
+/// Run a query and its aggregations against one searcher generation
+fn search_with_aggs_on(
+    coll: &CollectionIndex,
+    searcher: &tantivy::Searcher,
+    query: &Query,
+    aggregations: &[AggregationRequest],
+) -> Result<SearchResultsWithAggs> {
+    let found = search_sorted_on(coll, searcher, query, aggregations, &[])?;
+    Ok(SearchResultsWithAggs {
+        results: found.results,
+        total: found.total,
+        aggregations: found.aggregations,
+    })
+}
+
+/// Run a query and its aggregations against one searcher generation, with
+/// hits ordered by `sort` or by relevance when it is empty
+fn search_sorted_on(
+    coll: &CollectionIndex,
+    searcher: &tantivy::Searcher,
+    query: &Query,
+    aggregations: &[AggregationRequest],
+    sort: &[TopHitsSort],
+) -> Result<SortedSearchResults> {
+    let searchable_fields = searchable_fields(coll);
+    let fields_to_search: Vec<Field> = if query.fields.is_empty() {
+        searchable_fields.clone()
+    } else {
+        query
+            .fields
+            .iter()
+            .filter_map(|f| coll.field_map.get(f).copied())
+            .collect()
+    };
+
+    if fields_to_search.is_empty() {
+        return Ok(SortedSearchResults {
+            results: vec![],
+            sort_values: vec![],
+            total: 0,
+            aggregations: HashMap::new(),
+        });
+    }
+
+    let query_parser = QueryParser::for_index(&coll.index, fields_to_search.clone());
+    let query_string = query.query_string.clone();
+    let parsed_query = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        query_parser.parse_query(&query_string)
+    })) {
+        Ok(Ok(q)) => q,
+        Ok(Err(e)) => return Err(Error::InvalidQuery(e.to_string())),
+        Err(_) => {
+            return Err(Error::InvalidQuery(format!(
+                "Query parser panicked on input: {:?}",
+                query_string
+            )));
+        }
+    };
+
+    // Aggregations run over the full match set, not just the returned page
+    let doc_addrs = collect_match_set(searcher, parsed_query.as_ref())?;
+
+    // Build results
+    let id_field = coll.field_map.get("id").unwrap();
+    let mut results = Vec::new();
+    let mut sort_values = Vec::new();
+
+    let top_docs: Vec<(f32, tantivy::DocAddress, Vec<serde_json::Value>)> = if query.limit == 0 {
+        Vec::new()
+    } else if sort.is_empty() {
+        searcher
+            .search(
+                &parsed_query,
+                &TopDocs::with_limit(query.limit + query.offset),
+            )?
+            .into_iter()
+            .map(|(score, doc_addr)| (score, doc_addr, Vec::new()))
+            .collect()
+    } else {
+        sort_documents(searcher, coll, parsed_query.as_ref(), &doc_addrs, sort)?
+            .into_iter()
+            .take(query.limit + query.offset)
+            .map(|(doc_addr, score, keys)| (score, doc_addr, sort_keys_to_json(&keys)))
+            .collect()
+    };
+
+    for (score, doc_addr, keys) in top_docs.into_iter().skip(query.offset) {
+        let doc: TantivyDocument = searcher.doc(doc_addr)?;
+
+        let id = doc
+            .get_first(*id_field)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        let fields = stored_fields(coll, &doc);
+
+        results.push(SearchResult {
+            id,
+            score,
+            fields,
+            highlight: None,
+        });
+        sort_values.push(keys);
+    }
+
+    if let Some(ref hl_config) = query.highlight {
+        highlight_results(
             coll,
-            &searchable_fields,
-            &doc_addrs,
-            &aggregations,
-        )?;
+            searcher,
+            parsed_query.as_ref(),
+            hl_config,
+            &mut results,
+        );
+    }
 
-        let total = all_docs.len() as u64;
+    // Run aggregations
+    let agg_results = execute_aggregations(
+        searcher,
+        coll,
+        &searchable_fields,
+        parsed_query.as_ref(),
+        &doc_addrs,
+        aggregations,
+    )?;
+
+    let total = doc_addrs.len() as u64;
+
+    Ok(SortedSearchResults {
+        results,
+        sort_values,
+        total,
+        aggregations: agg_results,
+    })
+}
 
-        Ok(SearchResultsWithAggs {
-            results,
-            total,
-            aggregations: agg_results,
-        })
+/// Add highlighted fragments of the configured fields to each result
+fn highlight_results(
+    coll: &CollectionIndex,
+    searcher: &tantivy::Searcher,
+    query: &dyn tantivy::query::Query,
+    hl_config: &HighlightConfig,
+    results: &mut [SearchResult],
+) {
+    use tantivy::snippet::SnippetGenerator;
+
+    for hl_field_name in &hl_config.fields {
+        let Some(&field) = coll.field_map.get(hl_field_name) else {
+            continue;
+        };
+        // Only generate snippets for text fields
+        let entry = coll.schema.get_field_entry(field);
+        if !matches!(entry.field_type(), tantivy::schema::FieldType::Str(_)) {
+            continue;
+        }
+        let Ok(mut generator) = SnippetGenerator::create(searcher, query, field) else {
+            continue;
+        };
+
+        let options = hl_config.field_options.get(hl_field_name);
+        let pre_tag = options
+            .and_then(|o| o.pre_tag.as_deref())
+            .unwrap_or(&hl_config.pre_tag);
+        let post_tag = options
+            .and_then(|o| o.post_tag.as_deref())
+            .unwrap_or(&hl_config.post_tag);
+        let fragment_size = options
+            .and_then(|o| o.fragment_size)
+            .unwrap_or(hl_config.fragment_size);
+        let number_of_fragments = options
+            .and_then(|o| o.number_of_fragments)
+            .unwrap_or(hl_config.number_of_fragments);
+
+        for result in results.iter_mut() {
+            // Get the stored text for this field (every element of a
+            // multi-valued field is a candidate for highlighting)
+            let texts: Vec<String> = match result.fields.get(hl_field_name) {
+                Some(serde_json::Value::String(s)) => vec![s.clone()],
+                Some(serde_json::Value::Array(values)) => values
+                    .iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect(),
+                _ => continue,
+            };
+            for text_value in &texts {
+                // Zero fragments highlights the whole value
+                generator.set_max_num_chars(if number_of_fragments == 0 {
+                    text_value.len()
+                } else {
+                    fragment_size
+                });
+                let mut snippet = generator.snippet(text_value);
+                if !snippet.is_empty() {
+                    snippet.set_snippet_prefix_postfix(pre_tag, post_tag);
+                    let html = snippet.to_html();
+
+                    let highlights = result.highlight.get_or_insert_with(HashMap::new);
+                    let fragments = highlights
+                        .entry(hl_field_name.clone())
+                        .or_insert_with(Vec::new);
+                    fragments.push(html);
+                    // Tantivy returns one best fragment per call; truncate to configured max
+                    if number_of_fragments > 0 {
+                        fragments.truncate(number_of_fragments);
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -897,26 +1237,151 @@ impl SearchBackend for TextBackend {
 // Aggregation execution engine
 // ============================================================================
 
-use crate::aggregations::types::{HistogramBounds, PercentilesResult, RangeEntry};
+use crate::aggregations::types::{
+    CompositeBucket, CompositeResult, CompositeSource, CompositeSourceType, HistogramBounds,
+    PercentilesResult, RangeEntry, SignificantTermsBucket, SignificantTermsResult, SortDirection,
+    TopHit, TopHitsResult, TopHitsSort,
+};
+use crate::aggregations::{apply_parent_pipelines, HyperLogLogPlusPlus};
+
+/// Extract a numeric value from a stored field value
+fn numeric_value(value: &tantivy::schema::OwnedValue) -> Option<f64> {
+    match value {
+        tantivy::schema::OwnedValue::U64(n) => Some(*n as f64),
+        tantivy::schema::OwnedValue::I64(n) => Some(*n as f64),
+        tantivy::schema::OwnedValue::F64(n) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Fast field column of one segment
+enum FastColumn {
+    Str(tantivy::columnar::StrColumn),
+    U64(tantivy::columnar::Column<u64>),
+    I64(tantivy::columnar::Column<i64>),
+    F64(tantivy::columnar::Column<f64>),
+    Bool(tantivy::columnar::Column<bool>),
+    Date(tantivy::columnar::Column<DateTime>),
+}
+
+/// Reads the values of one field for aggregation.
+///
+/// Fields declared with `fast: true` are read from the per-segment column
+/// store. Other fields fall back to loading each stored document, which is
+/// much slower on large match sets.
+enum FieldValueReader<'a> {
+    /// One column per segment, indexed by segment ordinal (`None` when the
+    /// segment holds no value for the field)
+    Fast(Vec<Option<FastColumn>>),
+    Stored {
+        searcher: &'a tantivy::Searcher,
+        field: Field,
+    },
+}
+
+impl<'a> FieldValueReader<'a> {
+    fn open(
+        searcher: &'a tantivy::Searcher,
+        coll: &CollectionIndex,
+        field_name: &str,
+    ) -> Result<Self> {
+        let field = resolve_field(coll, field_name)?;
+        let entry = coll.schema.get_field_entry(field);
+        if !entry.is_fast() {
+            return Ok(FieldValueReader::Stored { searcher, field });
+        }
+
+        let mut columns = Vec::with_capacity(searcher.segment_readers().len());
+        for segment_reader in searcher.segment_readers() {
+            let fast_fields = segment_reader.fast_fields();
+            let column = match entry.field_type() {
+                tantivy::schema::FieldType::Str(_) => {
+                    fast_fields.str(field_name)?.map(FastColumn::Str)
+                }
+                tantivy::schema::FieldType::U64(_) => {
+                    fast_fields.column_opt(field_name)?.map(FastColumn::U64)
+                }
+                tantivy::schema::FieldType::I64(_) => {
+                    fast_fields.column_opt(field_name)?.map(FastColumn::I64)
+                }
+                tantivy::schema::FieldType::F64(_) => {
+                    fast_fields.column_opt(field_name)?.map(FastColumn::F64)
+                }
+                tantivy::schema::FieldType::Bool(_) => {
+                    fast_fields.column_opt(field_name)?.map(FastColumn::Bool)
+                }
+                tantivy::schema::FieldType::Date(_) => {
+                    fast_fields.column_opt(field_name)?.map(FastColumn::Date)
+                }
+                _ => return Ok(FieldValueReader::Stored { searcher, field }),
+            };
+            columns.push(column);
+        }
+        Ok(FieldValueReader::Fast(columns))
+    }
+
+    /// All values of the field for one document
+    fn values(&self, doc_addr: tantivy::DocAddress) -> Result<Vec<OwnedValue>> {
+        match self {
+            FieldValueReader::Fast(columns) => {
+                let Some(column) = columns
+                    .get(doc_addr.segment_ord as usize)
+                    .and_then(|c| c.as_ref())
+                else {
+                    return Ok(Vec::new());
+                };
+                let doc = doc_addr.doc_id;
+                let values = match column {
+                    FastColumn::Str(col) => {
+                        let mut out = Vec::new();
+                        for ord in col.term_ords(doc) {
+                            let mut term = String::new();
+                            if col.ord_to_str(ord, &mut term)? {
+                                out.push(OwnedValue::Str(term));
+                            }
+                        }
+                        out
+                    }
+                    FastColumn::U64(col) => col.values_for_doc(doc).map(OwnedValue::U64).collect(),
+                    FastColumn::I64(col) => col.values_for_doc(doc).map(OwnedValue::I64).collect(),
+                    FastColumn::F64(col) => col.values_for_doc(doc).map(OwnedValue::F64).collect(),
+                    FastColumn::Bool(col) => {
+                        col.values_for_doc(doc).map(OwnedValue::Bool).collect()
+                    }
+                    FastColumn::Date(col) => {
+                        col.values_for_doc(doc).map(OwnedValue::Date).collect()
+                    }
+                };
+                Ok(values)
+            }
+            FieldValueReader::Stored { searcher, field } => {
+                let doc: TantivyDocument = searcher.doc(doc_addr)?;
+                Ok(doc.get_all(*field).cloned().collect())
+            }
+        }
+    }
+}
 
-/// Collect field values from a set of document addresses
+/// Collect field values from a set of document addresses.
+///
+/// Every value of a multi-valued field is collected, so metric aggregations
+/// (sum, avg, min, max, stats, percentiles) operate on all elements.
 fn collect_field_values(
-    searcher: &tantivy::Searcher,
-    field: Field,
+    reader: &FieldValueReader,
     doc_addrs: &[tantivy::DocAddress],
 ) -> Result<(Vec<f64>, Vec<String>)> {
     let mut numeric_values = Vec::new();
     let mut string_values = Vec::new();
 
-    for doc_addr in doc_addrs {
-        let doc: TantivyDocument = searcher.doc(*doc_addr)?;
-        if let Some(value) = doc.get_first(field) {
+    for &doc_addr in doc_addrs {
+        for value in reader.values(doc_addr)? {
             match value {
-                tantivy::schema::OwnedValue::U64(n) => numeric_values.push(*n as f64),
-                tantivy::schema::OwnedValue::I64(n) => numeric_values.push(*n as f64),
-                tantivy::schema::OwnedValue::F64(n) => numeric_values.push(*n),
-                tantivy::schema::OwnedValue::Str(s) => string_values.push(s.to_string()),
-                _ => {}
+                tantivy::schema::OwnedValue::Str(s) => string_values.push(s),
+                other => {
+                    if let Some(n) = numeric_value(&other) {
+                        numeric_values.push(n);
+                    }
+                }
             }
         }
     }
@@ -924,6 +1389,22 @@ fn collect_field_values(
     Ok((numeric_values, string_values))
 }
 
+/// Collect the numeric values of each document (one entry per document).
+///
+/// Bucket aggregations use this to count a document once per bucket even when
+/// several of its values fall into the same bucket.
+fn collect_doc_numeric_values(
+    reader: &FieldValueReader,
+    doc_addrs: &[tantivy::DocAddress],
+) -> Result<Vec<Vec<f64>>> {
+    let mut per_doc = Vec::with_capacity(doc_addrs.len());
+    for &doc_addr in doc_addrs {
+        let values = reader.values(doc_addr)?;
+        per_doc.push(values.iter().filter_map(numeric_value).collect());
+    }
+    Ok(per_doc)
+}
+
 /// Compute percentile value from a sorted array using linear interpolation
 fn compute_percentile(sorted: &[f64], p: f64) -> Option<f64> {
     if sorted.is_empty() {
@@ -943,32 +1424,58 @@ fn compute_percentile(sorted: &[f64], p: f64) -> Option<f64> {
     }
 }
 
+/// Convert a stored field value to a terms bucket key
+fn terms_bucket_key(value: &tantivy::schema::OwnedValue) -> Option<String> {
+    match value {
+        tantivy::schema::OwnedValue::Str(s) => Some(s.to_string()),
+        tantivy::schema::OwnedValue::U64(n) => Some(n.to_string()),
+        tantivy::schema::OwnedValue::I64(n) => Some(n.to_string()),
+        tantivy::schema::OwnedValue::F64(n) => Some(n.to_string()),
+        tantivy::schema::OwnedValue::Bool(b) => Some(b.to_string()),
+        tantivy::schema::OwnedValue::Date(dt) => {
+            let micros = dt.into_timestamp_micros();
+            let secs = micros / 1_000_000;
+            let nsecs = ((micros % 1_000_000) * 1000) as u32;
+            Some(
+                chrono::DateTime::from_timestamp(secs, nsecs)
+                    .map(|d| d.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+                    .unwrap_or_else(|| micros.to_string()),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Distinct terms bucket keys for one document (multi-valued fields yield several)
+fn doc_terms_keys(values: &[OwnedValue]) -> Vec<String> {
+    let mut keys: Vec<String> = values.iter().filter_map(terms_bucket_key).collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Count documents per terms bucket key
+fn count_docs_per_term(
+    reader: &FieldValueReader,
+    doc_addrs: &[tantivy::DocAddress],
+) -> Result<HashMap<String, u64>> {
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    for &doc_addr in doc_addrs {
+        for key in doc_terms_keys(&reader.values(doc_addr)?) {
+            *counts.entry(key).or_insert(0) += 1;
+        }
+    }
+    Ok(counts)
+}
+
 /// Collect per-bucket document addresses keyed by bucket key, for sub-aggregation
 fn collect_docs_per_bucket_terms(
-    searcher: &tantivy::Searcher,
-    field: Field,
+    reader: &FieldValueReader,
     doc_addrs: &[tantivy::DocAddress],
 ) -> Result<HashMap<String, Vec<tantivy::DocAddress>>> {
     let mut map: HashMap<String, Vec<tantivy::DocAddress>> = HashMap::new();
     for &doc_addr in doc_addrs {
-        let doc: TantivyDocument = searcher.doc(doc_addr)?;
-        if let Some(value) = doc.get_first(field) {
-            let key = match value {
-                tantivy::schema::OwnedValue::Str(s) => s.to_string(),
-                tantivy::schema::OwnedValue::U64(n) => n.to_string(),
-                tantivy::schema::OwnedValue::I64(n) => n.to_string(),
-                tantivy::schema::OwnedValue::F64(n) => n.to_string(),
-                tantivy::schema::OwnedValue::Bool(b) => b.to_string(),
-                tantivy::schema::OwnedValue::Date(dt) => {
-                    let micros = dt.into_timestamp_micros();
-                    let secs = micros / 1_000_000;
-                    let nsecs = ((micros % 1_000_000) * 1000) as u32;
-                    chrono::DateTime::from_timestamp(secs, nsecs)
-                        .map(|d| d.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
-                        .unwrap_or_else(|| micros.to_string())
-                }
-                _ => continue,
-            };
+        for key in doc_terms_keys(&reader.values(doc_addr)?) {
             map.entry(key).or_default().push(doc_addr);
         }
     }
@@ -980,13 +1487,21 @@ fn execute_aggregations(
     searcher: &tantivy::Searcher,
     coll: &CollectionIndex,
     searchable_fields: &[Field],
+    scoring_query: &dyn tantivy::query::Query,
     doc_addrs: &[tantivy::DocAddress],
     aggregations: &[AggregationRequest],
 ) -> Result<HashMap<String, AggregationResult>> {
     let mut agg_results: HashMap<String, AggregationResult> = HashMap::new();
 
     for agg_req in aggregations {
-        let result = execute_single_agg(searcher, coll, searchable_fields, doc_addrs, agg_req)?;
+        let result = execute_single_agg(
+            searcher,
+            coll,
+            searchable_fields,
+            scoring_query,
+            doc_addrs,
+            agg_req,
+        )?;
         agg_results.insert(agg_req.name.clone(), result);
     }
 
@@ -998,23 +1513,32 @@ fn execute_single_agg(
     searcher: &tantivy::Searcher,
     coll: &CollectionIndex,
     searchable_fields: &[Field],
+    scoring_query: &dyn tantivy::query::Query,
     doc_addrs: &[tantivy::DocAddress],
     agg_req: &AggregationRequest,
 ) -> Result<AggregationResult> {
-    let sub_aggs = agg_req.aggs.as_deref().unwrap_or(&[]);
+    let all_sub_aggs = agg_req.aggs.as_deref().unwrap_or(&[]);
+    // Pipeline sub-aggregations run on the finished buckets, not on documents
+    let has_pipelines = all_sub_aggs.iter().any(|a| a.agg_type.is_pipeline());
+    let doc_sub_aggs: Vec<AggregationRequest> = all_sub_aggs
+        .iter()
+        .filter(|a| !a.agg_type.is_pipeline())
+        .cloned()
+        .collect();
+    let sub_aggs = doc_sub_aggs.as_slice();
 
-    let value = match &agg_req.agg_type {
+    let mut value = match &agg_req.agg_type {
         AggregationType::Count => AggregationValue::Single(doc_addrs.len() as f64),
 
         AggregationType::Sum { field } => {
-            let f = resolve_field(coll, field)?;
-            let (nums, _) = collect_field_values(searcher, f, doc_addrs)?;
+            let reader = FieldValueReader::open(searcher, coll, field)?;
+            let (nums, _) = collect_field_values(&reader, doc_addrs)?;
             AggregationValue::Single(nums.iter().sum())
         }
 
         AggregationType::Avg { field } => {
-            let f = resolve_field(coll, field)?;
-            let (nums, _) = collect_field_values(searcher, f, doc_addrs)?;
+            let reader = FieldValueReader::open(searcher, coll, field)?;
+            let (nums, _) = collect_field_values(&reader, doc_addrs)?;
             let avg = if nums.is_empty() {
                 0.0
             } else {
@@ -1024,22 +1548,22 @@ fn execute_single_agg(
         }
 
         AggregationType::Min { field } => {
-            let f = resolve_field(coll, field)?;
-            let (nums, _) = collect_field_values(searcher, f, doc_addrs)?;
+            let reader = FieldValueReader::open(searcher, coll, field)?;
+            let (nums, _) = collect_field_values(&reader, doc_addrs)?;
             let min = nums.iter().cloned().fold(f64::INFINITY, f64::min);
             AggregationValue::Single(if min.is_infinite() { 0.0 } else { min })
         }
 
         AggregationType::Max { field } => {
-            let f = resolve_field(coll, field)?;
-            let (nums, _) = collect_field_values(searcher, f, doc_addrs)?;
+            let reader = FieldValueReader::open(searcher, coll, field)?;
+            let (nums, _) = collect_field_values(&reader, doc_addrs)?;
             let max = nums.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
             AggregationValue::Single(if max.is_infinite() { 0.0 } else { max })
         }
 
         AggregationType::Stats { field } => {
-            let f = resolve_field(coll, field)?;
-            let (nums, _) = collect_field_values(searcher, f, doc_addrs)?;
+            let reader = FieldValueReader::open(searcher, coll, field)?;
+            let (nums, _) = collect_field_values(&reader, doc_addrs)?;
             let count = nums.len() as u64;
             let sum: f64 = nums.iter().sum();
             let min = nums.iter().cloned().fold(f64::INFINITY, f64::min);
@@ -1059,8 +1583,8 @@ fn execute_single_agg(
         }
 
         AggregationType::Percentiles { field, percents } => {
-            let f = resolve_field(coll, field)?;
-            let (mut nums, _) = collect_field_values(searcher, f, doc_addrs)?;
+            let reader = FieldValueReader::open(searcher, coll, field)?;
+            let (mut nums, _) = collect_field_values(&reader, doc_addrs)?;
             nums.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
             let mut values = HashMap::new();
             for &p in percents {
@@ -1071,19 +1595,12 @@ fn execute_single_agg(
         }
 
         AggregationType::Terms { field, size } => {
-            let f = resolve_field(coll, field)?;
+            let reader = FieldValueReader::open(searcher, coll, field)?;
             let size = size.unwrap_or(10);
 
             if sub_aggs.is_empty() {
                 // Simple case: no sub-aggregations
-                let (nums, strings) = collect_field_values(searcher, f, doc_addrs)?;
-                let mut counts: HashMap<String, u64> = HashMap::new();
-                for s in &strings {
-                    *counts.entry(s.clone()).or_insert(0) += 1;
-                }
-                for n in &nums {
-                    *counts.entry(n.to_string()).or_insert(0) += 1;
-                }
+                let counts = count_docs_per_term(&reader, doc_addrs)?;
                 let mut bucket_vec: Vec<_> = counts.into_iter().collect();
                 bucket_vec.sort_by(|a, b| b.1.cmp(&a.1));
                 let buckets: Vec<Bucket> = bucket_vec
@@ -1095,12 +1612,13 @@ fn execute_single_agg(
                         from: None,
                         to: None,
                         sub_aggs: None,
+                        doc_count_error_upper_bound: None,
                     })
                     .collect();
                 AggregationValue::Buckets(buckets)
             } else {
                 // With sub-aggregations: need per-bucket doc sets
-                let docs_per_bucket = collect_docs_per_bucket_terms(searcher, f, doc_addrs)?;
+                let docs_per_bucket = collect_docs_per_bucket_terms(&reader, doc_addrs)?;
                 let mut bucket_vec: Vec<_> = docs_per_bucket
                     .iter()
                     .map(|(k, addrs)| (k.clone(), addrs.len() as u64, addrs.clone()))
@@ -1110,8 +1628,14 @@ fn execute_single_agg(
 
                 let mut buckets = Vec::new();
                 for (key, doc_count, addrs) in bucket_vec {
-                    let child_aggs =
-                        execute_aggregations(searcher, coll, searchable_fields, &addrs, sub_aggs)?;
+                    let child_aggs = execute_aggregations(
+                        searcher,
+                        coll,
+                        searchable_fields,
+                        scoring_query,
+                        &addrs,
+                        sub_aggs,
+                    )?;
                     let child_vec: Vec<AggregationResult> = child_aggs.into_values().collect();
                     buckets.push(Bucket {
                         key,
@@ -1123,6 +1647,7 @@ fn execute_single_agg(
                         } else {
                             Some(child_vec)
                         },
+                        doc_count_error_upper_bound: None,
                     });
                 }
                 AggregationValue::Buckets(buckets)
@@ -1135,13 +1660,13 @@ fn execute_single_agg(
             min_doc_count,
             extended_bounds,
         } => {
-            let f = resolve_field(coll, field)?;
+            let reader = FieldValueReader::open(searcher, coll, field)?;
             let interval = *interval;
 
             if sub_aggs.is_empty() {
-                let (nums, _) = collect_field_values(searcher, f, doc_addrs)?;
+                let per_doc = collect_doc_numeric_values(&reader, doc_addrs)?;
                 let buckets = compute_histogram_buckets(
-                    &nums,
+                    &per_doc,
                     interval,
                     *min_doc_count,
                     extended_bounds.as_ref(),
@@ -1152,18 +1677,16 @@ fn execute_single_agg(
                 let mut bucket_docs: std::collections::BTreeMap<i64, Vec<tantivy::DocAddress>> =
                     std::collections::BTreeMap::new();
                 for &doc_addr in doc_addrs {
-                    let doc: TantivyDocument = searcher.doc(doc_addr)?;
-                    if let Some(value) = doc.get_first(f) {
-                        let v = match value {
-                            tantivy::schema::OwnedValue::U64(n) => Some(*n as f64),
-                            tantivy::schema::OwnedValue::I64(n) => Some(*n as f64),
-                            tantivy::schema::OwnedValue::F64(n) => Some(*n),
-                            _ => None,
-                        };
-                        if let Some(v) = v {
-                            let bucket_key = (v / interval).floor() as i64;
-                            bucket_docs.entry(bucket_key).or_default().push(doc_addr);
-                        }
+                    let mut keys: Vec<i64> = reader
+                        .values(doc_addr)?
+                        .iter()
+                        .filter_map(numeric_value)
+                        .map(|v| (v / interval).floor() as i64)
+                        .collect();
+                    keys.sort_unstable();
+                    keys.dedup();
+                    for bucket_key in keys {
+                        bucket_docs.entry(bucket_key).or_default().push(doc_addr);
                     }
                 }
 
@@ -1183,8 +1706,14 @@ fn execute_single_agg(
                     if doc_count < min_count {
                         continue;
                     }
-                    let child_aggs =
-                        execute_aggregations(searcher, coll, searchable_fields, addrs, sub_aggs)?;
+                    let child_aggs = execute_aggregations(
+                        searcher,
+                        coll,
+                        searchable_fields,
+                        scoring_query,
+                        addrs,
+                        sub_aggs,
+                    )?;
                     let child_vec: Vec<AggregationResult> = child_aggs.into_values().collect();
                     buckets.push(Bucket {
                         key: format!("{}", (*bucket_key as f64) * interval),
@@ -1196,6 +1725,7 @@ fn execute_single_agg(
                         } else {
                             Some(child_vec)
                         },
+                        doc_count_error_upper_bound: None,
                     });
                 }
                 AggregationValue::Buckets(buckets)
@@ -1207,30 +1737,22 @@ fn execute_single_agg(
             calendar_interval,
             min_doc_count,
         } => {
-            let f = resolve_field(coll, field)?;
+            let reader = FieldValueReader::open(searcher, coll, field)?;
             let interval = crate::query::aggregations::date_histogram::DateInterval::parse_interval(
                 calendar_interval,
             );
 
             if sub_aggs.is_empty() {
-                let buckets = compute_date_histogram_buckets(
-                    searcher,
-                    f,
-                    doc_addrs,
-                    &interval,
-                    *min_doc_count,
-                )?;
+                let buckets =
+                    compute_date_histogram_buckets(&reader, doc_addrs, &interval, *min_doc_count)?;
                 AggregationValue::Buckets(buckets)
             } else {
                 // Per-bucket doc addresses for sub-aggs
                 let mut bucket_docs: std::collections::BTreeMap<String, Vec<tantivy::DocAddress>> =
                     std::collections::BTreeMap::new();
                 for &doc_addr in doc_addrs {
-                    let doc: TantivyDocument = searcher.doc(doc_addr)?;
-                    if let Some(value) = doc.get_first(f) {
-                        if let Some(key) = date_value_to_bucket_key(value, &interval) {
-                            bucket_docs.entry(key).or_default().push(doc_addr);
-                        }
+                    for key in doc_date_bucket_keys(&reader.values(doc_addr)?, &interval) {
+                        bucket_docs.entry(key).or_default().push(doc_addr);
                     }
                 }
                 let min_count = min_doc_count.unwrap_or(0);
@@ -1240,8 +1762,14 @@ fn execute_single_agg(
                     if doc_count < min_count {
                         continue;
                     }
-                    let child_aggs =
-                        execute_aggregations(searcher, coll, searchable_fields, addrs, sub_aggs)?;
+                    let child_aggs = execute_aggregations(
+                        searcher,
+                        coll,
+                        searchable_fields,
+                        scoring_query,
+                        addrs,
+                        sub_aggs,
+                    )?;
                     let child_vec: Vec<AggregationResult> = child_aggs.into_values().collect();
                     buckets.push(Bucket {
                         key: key.clone(),
@@ -1253,6 +1781,7 @@ fn execute_single_agg(
                         } else {
                             Some(child_vec)
                         },
+                        doc_count_error_upper_bound: None,
                     });
                 }
                 AggregationValue::Buckets(buckets)
@@ -1260,41 +1789,39 @@ fn execute_single_agg(
         }
 
         AggregationType::Range { field, ranges } => {
-            let f = resolve_field(coll, field)?;
+            let reader = FieldValueReader::open(searcher, coll, field)?;
 
             if sub_aggs.is_empty() {
-                let (nums, _) = collect_field_values(searcher, f, doc_addrs)?;
-                let buckets = compute_range_buckets(&nums, ranges);
+                let per_doc = collect_doc_numeric_values(&reader, doc_addrs)?;
+                let buckets = compute_range_buckets(&per_doc, ranges);
                 AggregationValue::Buckets(buckets)
             } else {
                 // Per-range bucket doc addresses
                 let mut range_docs: Vec<(RangeEntry, Vec<tantivy::DocAddress>)> =
                     ranges.iter().map(|r| (r.clone(), Vec::new())).collect();
                 for &doc_addr in doc_addrs {
-                    let doc: TantivyDocument = searcher.doc(doc_addr)?;
-                    if let Some(value) = doc.get_first(f) {
-                        let v = match value {
-                            tantivy::schema::OwnedValue::U64(n) => Some(*n as f64),
-                            tantivy::schema::OwnedValue::I64(n) => Some(*n as f64),
-                            tantivy::schema::OwnedValue::F64(n) => Some(*n),
-                            _ => None,
-                        };
-                        if let Some(v) = v {
-                            for (range, addrs) in &mut range_docs {
-                                let above_from = range.from.is_none_or(|from| v >= from);
-                                let below_to = range.to.is_none_or(|to| v < to);
-                                if above_from && below_to {
-                                    addrs.push(doc_addr);
-                                }
-                            }
+                    let values: Vec<f64> = reader
+                        .values(doc_addr)?
+                        .iter()
+                        .filter_map(numeric_value)
+                        .collect();
+                    for (range, addrs) in &mut range_docs {
+                        if values.iter().any(|&v| range_contains(range, v)) {
+                            addrs.push(doc_addr);
                         }
                     }
                 }
                 let mut buckets = Vec::new();
                 for (range, addrs) in &range_docs {
                     let key = range_bucket_key(range);
-                    let child_aggs =
-                        execute_aggregations(searcher, coll, searchable_fields, addrs, sub_aggs)?;
+                    let child_aggs = execute_aggregations(
+                        searcher,
+                        coll,
+                        searchable_fields,
+                        scoring_query,
+                        addrs,
+                        sub_aggs,
+                    )?;
                     let child_vec: Vec<AggregationResult> = child_aggs.into_values().collect();
                     buckets.push(Bucket {
                         key,
@@ -1306,6 +1833,7 @@ fn execute_single_agg(
                         } else {
                             Some(child_vec)
                         },
+                        doc_count_error_upper_bound: None,
                     });
                 }
                 AggregationValue::Buckets(buckets)
@@ -1325,12 +1853,14 @@ fn execute_single_agg(
                     from: None,
                     to: None,
                     sub_aggs: None,
+                    doc_count_error_upper_bound: None,
                 }])
             } else {
                 let child_aggs = execute_aggregations(
                     searcher,
                     coll,
                     searchable_fields,
+                    scoring_query,
                     &filter_addrs,
                     sub_aggs,
                 )?;
@@ -1345,6 +1875,7 @@ fn execute_single_agg(
                     } else {
                         Some(child_vec)
                     },
+                    doc_count_error_upper_bound: None,
                 }])
             }
         }
@@ -1368,12 +1899,14 @@ fn execute_single_agg(
                         from: None,
                         to: None,
                         sub_aggs: None,
+                        doc_count_error_upper_bound: None,
                     });
                 } else {
                     let child_aggs = execute_aggregations(
                         searcher,
                         coll,
                         searchable_fields,
+                        scoring_query,
                         &filter_addrs,
                         sub_aggs,
                     )?;
@@ -1388,6 +1921,7 @@ fn execute_single_agg(
                         } else {
                             Some(child_vec)
                         },
+                        doc_count_error_upper_bound: None,
                     });
                 }
             }
@@ -1406,10 +1940,17 @@ fn execute_single_agg(
                     from: None,
                     to: None,
                     sub_aggs: None,
+                    doc_count_error_upper_bound: None,
                 }])
             } else {
-                let child_aggs =
-                    execute_aggregations(searcher, coll, searchable_fields, &all_addrs, sub_aggs)?;
+                let child_aggs = execute_aggregations(
+                    searcher,
+                    coll,
+                    searchable_fields,
+                    scoring_query,
+                    &all_addrs,
+                    sub_aggs,
+                )?;
                 let child_vec: Vec<AggregationResult> = child_aggs.into_values().collect();
                 AggregationValue::Buckets(vec![Bucket {
                     key: "global".to_string(),
@@ -1421,37 +1962,659 @@ fn execute_single_agg(
                     } else {
                         Some(child_vec)
                     },
+                    doc_count_error_upper_bound: None,
                 }])
             }
         }
-    };
-
-    Ok(AggregationResult {
-        name: agg_req.name.clone(),
-        value,
-    })
-}
 
-/// Resolve a field name to a tantivy Field
-fn resolve_field(coll: &CollectionIndex, field_name: &str) -> Result<Field> {
-    coll.field_map
-        .get(field_name)
-        .copied()
-        .ok_or_else(|| Error::InvalidQuery(format!("Unknown field: {}", field_name)))
-}
+        AggregationType::Cardinality {
+            field,
+            precision_threshold,
+            sketch: return_sketch,
+        } => {
+            let reader = FieldValueReader::open(searcher, coll, field)?;
+            let mut sketch = HyperLogLogPlusPlus::new(
+                precision_threshold.unwrap_or(HyperLogLogPlusPlus::DEFAULT_PRECISION_THRESHOLD),
+            );
+            for &doc_addr in doc_addrs {
+                for value in reader.values(doc_addr)? {
+                    if let Some(key) = terms_bucket_key(&value) {
+                        sketch.insert(key.as_bytes());
+                    }
+                }
+            }
+            if *return_sketch {
+                AggregationValue::Cardinality(sketch)
+            } else {
+                AggregationValue::Single(sketch.estimate() as f64)
+            }
+        }
 
-/// Compute histogram buckets from numeric values
-fn compute_histogram_buckets(
-    values: &[f64],
-    interval: f64,
-    min_doc_count: Option<u64>,
-    extended_bounds: Option<&HistogramBounds>,
-) -> Vec<Bucket> {
-    let mut counts: std::collections::BTreeMap<i64, u64> = std::collections::BTreeMap::new();
+        AggregationType::TopHits {
+            size,
+            from,
+            sort,
+            source,
+        } => AggregationValue::TopHits(compute_top_hits(
+            searcher,
+            coll,
+            scoring_query,
+            doc_addrs,
+            size.unwrap_or(3),
+            from.unwrap_or(0),
+            sort,
+            source.as_deref(),
+        )?),
+
+        AggregationType::SignificantTerms {
+            field,
+            size,
+            min_doc_count,
+            background_filter,
+        } => {
+            let reader = FieldValueReader::open(searcher, coll, field)?;
+            let background = resolve_filter_docs(
+                searcher,
+                coll,
+                searchable_fields,
+                background_filter.as_deref().unwrap_or("*"),
+                None,
+            )?;
+            AggregationValue::SignificantTerms(compute_significant_terms(
+                &reader,
+                doc_addrs,
+                &background,
+                size.unwrap_or(10),
+                min_doc_count.unwrap_or(3),
+            )?)
+        }
 
-    for &v in values {
-        let bucket_key = (v / interval).floor() as i64;
-        *counts.entry(bucket_key).or_insert(0) += 1;
+        AggregationType::Composite {
+            sources,
+            size,
+            after,
+        } => {
+            if sources.is_empty() {
+                return Err(Error::InvalidQuery(
+                    "composite aggregation requires at least one source".to_string(),
+                ));
+            }
+            let after_parts = after
+                .as_ref()
+                .map(|after| composite_after_parts(sources, after))
+                .transpose()?;
+            let pages = collect_composite_buckets(
+                searcher,
+                coll,
+                sources,
+                doc_addrs,
+                after_parts.as_deref(),
+                size.unwrap_or(10),
+            )?;
+
+            let mut buckets = Vec::with_capacity(pages.len());
+            for (parts, addrs) in &pages {
+                let sub = if sub_aggs.is_empty() {
+                    None
+                } else {
+                    let child_aggs = execute_aggregations(
+                        searcher,
+                        coll,
+                        searchable_fields,
+                        scoring_query,
+                        addrs,
+                        sub_aggs,
+                    )?;
+                    Some(child_aggs.into_values().collect())
+                };
+                buckets.push(CompositeBucket {
+                    key: composite_key_json(sources, parts),
+                    doc_count: addrs.len() as u64,
+                    sub_aggs: sub,
+                });
+            }
+            AggregationValue::Composite(CompositeResult {
+                after_key: buckets.last().map(|b| b.key.clone()),
+                buckets,
+            })
+        }
+
+        AggregationType::Derivative { .. }
+        | AggregationType::CumulativeSum { .. }
+        | AggregationType::MovingFn { .. }
+        | AggregationType::BucketSort { .. }
+        | AggregationType::BucketSelector { .. } => {
+            return Err(Error::InvalidQuery(format!(
+                "Pipeline aggregation '{}' must be declared inside a multi-bucket aggregation",
+                agg_req.name
+            )));
+        }
+    };
+
+    if has_pipelines {
+        match (&agg_req.agg_type, &mut value) {
+            (
+                AggregationType::Terms { .. }
+                | AggregationType::Histogram { .. }
+                | AggregationType::DateHistogram { .. }
+                | AggregationType::Range { .. }
+                | AggregationType::Filters { .. },
+                AggregationValue::Buckets(buckets),
+            ) => apply_parent_pipelines(buckets, all_sub_aggs)?,
+            _ => {
+                return Err(Error::InvalidQuery(format!(
+                    "Aggregation '{}' does not support pipeline sub-aggregations",
+                    agg_req.name
+                )))
+            }
+        }
+    }
+
+    Ok(AggregationResult {
+        name: agg_req.name.clone(),
+        value,
+    })
+}
+
+/// One component of a composite bucket key
+#[derive(Debug, Clone, PartialEq)]
+enum CompositeKeyPart {
+    Num(f64),
+    Str(String),
+}
+
+impl CompositeKeyPart {
+    fn compare(&self, other: &CompositeKeyPart) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        match (self, other) {
+            (CompositeKeyPart::Num(a), CompositeKeyPart::Num(b)) => {
+                a.partial_cmp(b).unwrap_or(Ordering::Equal)
+            }
+            (CompositeKeyPart::Str(a), CompositeKeyPart::Str(b)) => a.cmp(b),
+            (CompositeKeyPart::Num(_), CompositeKeyPart::Str(_)) => Ordering::Less,
+            (CompositeKeyPart::Str(_), CompositeKeyPart::Num(_)) => Ordering::Greater,
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            CompositeKeyPart::Num(n) if n.fract() == 0.0 && n.abs() < (1u64 << 53) as f64 => {
+                serde_json::Value::from(*n as i64)
+            }
+            CompositeKeyPart::Num(n) => serde_json::Number::from_f64(*n)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            CompositeKeyPart::Str(s) => serde_json::Value::String(s.clone()),
+        }
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<CompositeKeyPart> {
+        match value {
+            serde_json::Value::Number(n) => n.as_f64().map(CompositeKeyPart::Num),
+            serde_json::Value::String(s) => Some(CompositeKeyPart::Str(s.clone())),
+            serde_json::Value::Bool(b) => Some(CompositeKeyPart::Str(b.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Compare composite keys source by source, honouring each source's order
+fn compare_composite_keys(
+    sources: &[CompositeSource],
+    a: &[CompositeKeyPart],
+    b: &[CompositeKeyPart],
+) -> std::cmp::Ordering {
+    sources
+        .iter()
+        .zip(a.iter().zip(b))
+        .map(|(source, (x, y))| match source.order {
+            SortDirection::Asc => x.compare(y),
+            SortDirection::Desc => x.compare(y).reverse(),
+        })
+        .find(|ord| ord.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// Parse an `after` key into key parts, in source order
+fn composite_after_parts(
+    sources: &[CompositeSource],
+    after: &HashMap<String, serde_json::Value>,
+) -> Result<Vec<CompositeKeyPart>> {
+    sources
+        .iter()
+        .map(|source| {
+            after
+                .get(&source.name)
+                .and_then(CompositeKeyPart::from_json)
+                .ok_or_else(|| {
+                    Error::InvalidQuery(format!(
+                        "composite after key is missing a value for source '{}'",
+                        source.name
+                    ))
+                })
+        })
+        .collect()
+}
+
+fn composite_key_json(
+    sources: &[CompositeSource],
+    parts: &[CompositeKeyPart],
+) -> HashMap<String, serde_json::Value> {
+    sources
+        .iter()
+        .zip(parts)
+        .map(|(source, part)| (source.name.clone(), part.to_json()))
+        .collect()
+}
+
+/// Distinct key parts of one composite source for one document
+fn composite_source_parts(
+    source_type: &CompositeSourceType,
+    values: &[OwnedValue],
+    date_interval: &Option<crate::query::aggregations::date_histogram::DateInterval>,
+) -> Vec<CompositeKeyPart> {
+    let mut parts: Vec<CompositeKeyPart> = match source_type {
+        CompositeSourceType::Terms { .. } => values
+            .iter()
+            .filter_map(|v| match numeric_value(v) {
+                Some(n) => Some(CompositeKeyPart::Num(n)),
+                None => terms_bucket_key(v).map(CompositeKeyPart::Str),
+            })
+            .collect(),
+        CompositeSourceType::Histogram { interval, .. } => values
+            .iter()
+            .filter_map(numeric_value)
+            .map(|v| CompositeKeyPart::Num((v / interval).floor() * interval))
+            .collect(),
+        CompositeSourceType::DateHistogram { .. } => doc_date_bucket_keys(values, date_interval)
+            .into_iter()
+            .map(CompositeKeyPart::Str)
+            .collect(),
+    };
+    parts.sort_by(|a, b| a.compare(b));
+    parts.dedup();
+    parts
+}
+
+/// Group documents by composite key and return one page of buckets after `after`.
+///
+/// A document contributes to every combination of its source values; documents
+/// missing a value for any source are skipped.
+fn collect_composite_buckets(
+    searcher: &tantivy::Searcher,
+    coll: &CollectionIndex,
+    sources: &[CompositeSource],
+    doc_addrs: &[tantivy::DocAddress],
+    after: Option<&[CompositeKeyPart]>,
+    size: usize,
+) -> Result<Vec<(Vec<CompositeKeyPart>, Vec<tantivy::DocAddress>)>> {
+    let mut readers = Vec::with_capacity(sources.len());
+    for source in sources {
+        let (field, date_interval) = match &source.source_type {
+            CompositeSourceType::Terms { field } | CompositeSourceType::Histogram { field, .. } => {
+                (field, None)
+            }
+            CompositeSourceType::DateHistogram {
+                field,
+                calendar_interval,
+            } => (
+                field,
+                crate::query::aggregations::date_histogram::DateInterval::parse_interval(
+                    calendar_interval,
+                ),
+            ),
+        };
+        readers.push((
+            FieldValueReader::open(searcher, coll, field)?,
+            date_interval,
+        ));
+    }
+
+    let mut keyed: Vec<(Vec<CompositeKeyPart>, tantivy::DocAddress)> = Vec::new();
+    for &doc_addr in doc_addrs {
+        let mut combos: Vec<Vec<CompositeKeyPart>> = vec![Vec::new()];
+        for (source, (reader, date_interval)) in sources.iter().zip(&readers) {
+            let values = reader.values(doc_addr)?;
+            let parts = composite_source_parts(&source.source_type, &values, date_interval);
+            combos = combos
+                .into_iter()
+                .flat_map(|prefix| {
+                    parts.iter().map(move |part| {
+                        let mut key = prefix.clone();
+                        key.push(part.clone());
+                        key
+                    })
+                })
+                .collect();
+            if combos.is_empty() {
+                break;
+            }
+        }
+        for key in combos {
+            if let Some(after) = after {
+                if compare_composite_keys(sources, &key, after).is_le() {
+                    continue;
+                }
+            }
+            keyed.push((key, doc_addr));
+        }
+    }
+
+    keyed.sort_by(|a, b| compare_composite_keys(sources, &a.0, &b.0).then_with(|| a.1.cmp(&b.1)));
+
+    let mut pages: Vec<(Vec<CompositeKeyPart>, Vec<tantivy::DocAddress>)> = Vec::new();
+    for (key, doc_addr) in keyed {
+        match pages.last_mut() {
+            Some((last, addrs)) if compare_composite_keys(sources, last, &key).is_eq() => {
+                addrs.push(doc_addr)
+            }
+            _ => {
+                if pages.len() == size {
+                    break;
+                }
+                pages.push((key, vec![doc_addr]));
+            }
+        }
+    }
+    Ok(pages)
+}
+
+/// Score documents against a query; documents that do not match score 0
+fn score_docs(
+    searcher: &tantivy::Searcher,
+    query: &dyn tantivy::query::Query,
+    doc_addrs: &[tantivy::DocAddress],
+) -> Result<Vec<f32>> {
+    use tantivy::query::Scorer;
+
+    let weight = query.weight(tantivy::query::EnableScoring::enabled_from_searcher(
+        searcher,
+    ))?;
+    let mut scores = vec![0.0; doc_addrs.len()];
+
+    // Scorers only move forward, so visit documents in index order
+    let mut order: Vec<usize> = (0..doc_addrs.len()).collect();
+    order.sort_by_key(|&i| doc_addrs[i]);
+
+    let mut current: Option<(u32, Box<dyn Scorer>)> = None;
+    for i in order {
+        let doc_addr = doc_addrs[i];
+        if current.as_ref().map(|(ord, _)| *ord) != Some(doc_addr.segment_ord) {
+            let segment_reader = searcher.segment_reader(doc_addr.segment_ord);
+            current = Some((doc_addr.segment_ord, weight.scorer(segment_reader, 1.0)?));
+        }
+        if let Some((_, scorer)) = current.as_mut() {
+            if scorer.doc() <= doc_addr.doc_id && scorer.seek(doc_addr.doc_id) == doc_addr.doc_id {
+                scores[i] = scorer.score();
+            }
+        }
+    }
+    Ok(scores)
+}
+
+/// Order two sort values. Missing values sort last in either direction,
+/// or first with `missing_first`.
+fn compare_sort_values(
+    a: &Option<OwnedValue>,
+    b: &Option<OwnedValue>,
+    direction: SortDirection,
+    missing_first: bool,
+) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let ord = match (numeric_value(a), numeric_value(b)) {
+                (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+                _ => terms_bucket_key(a).cmp(&terms_bucket_key(b)),
+            };
+            match direction {
+                SortDirection::Asc => ord,
+                SortDirection::Desc => ord.reverse(),
+            }
+        }
+        (Some(_), None) if missing_first => Ordering::Greater,
+        (None, Some(_)) if missing_first => Ordering::Less,
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Where the values of one sort key come from
+enum SortKeySource<'a> {
+    Score,
+    /// Index order
+    Doc,
+    Field(FieldValueReader<'a>),
+    /// A field missing from the schema, sorted with `unmapped_type`
+    Unmapped,
+}
+
+/// A matching document with its score and one value per sort key
+type SortEntry = (tantivy::DocAddress, f32, Vec<Option<OwnedValue>>);
+
+/// Order matching documents by `sort`, or by descending score when it is
+/// empty. Ties keep index order.
+fn sort_documents(
+    searcher: &tantivy::Searcher,
+    coll: &CollectionIndex,
+    scoring_query: &dyn tantivy::query::Query,
+    doc_addrs: &[tantivy::DocAddress],
+    sort: &[TopHitsSort],
+) -> Result<Vec<SortEntry>> {
+    let scores = score_docs(searcher, scoring_query, doc_addrs)?;
+
+    let mut sources = Vec::with_capacity(sort.len());
+    for s in sort {
+        sources.push(match s.field.as_str() {
+            "_score" => SortKeySource::Score,
+            "_doc" => SortKeySource::Doc,
+            field if !coll.field_map.contains_key(field) && s.unmapped_type.is_some() => {
+                SortKeySource::Unmapped
+            }
+            field if !coll.field_map.contains_key(field) => {
+                return Err(Error::InvalidQuery(format!(
+                    "No mapping found for [{}] in order to sort on",
+                    field
+                )));
+            }
+            field => SortKeySource::Field(FieldValueReader::open(searcher, coll, field)?),
+        });
+    }
+    // `missing` values other than `_first` and `_last` stand in for absent ones
+    let substitutes: Vec<Option<OwnedValue>> = sort
+        .iter()
+        .map(|s| match &s.missing {
+            Some(serde_json::Value::Number(n)) => n.as_f64().map(OwnedValue::F64),
+            Some(serde_json::Value::Bool(b)) => Some(OwnedValue::Bool(*b)),
+            Some(serde_json::Value::String(v)) if v != "_first" && v != "_last" => {
+                Some(OwnedValue::Str(v.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut entries = Vec::with_capacity(doc_addrs.len());
+    for (&doc_addr, &score) in doc_addrs.iter().zip(&scores) {
+        let mut keys = Vec::with_capacity(sources.len());
+        for (source, substitute) in sources.iter().zip(&substitutes) {
+            let key = match source {
+                SortKeySource::Score => Some(OwnedValue::F64(score as f64)),
+                SortKeySource::Doc => Some(OwnedValue::U64(
+                    ((doc_addr.segment_ord as u64) << 32) | doc_addr.doc_id as u64,
+                )),
+                SortKeySource::Field(reader) => reader.values(doc_addr)?.into_iter().next(),
+                SortKeySource::Unmapped => None,
+            };
+            keys.push(key.or_else(|| substitute.clone()));
+        }
+        entries.push((doc_addr, score, keys));
+    }
+
+    if sort.is_empty() {
+        entries.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+    } else {
+        entries.sort_by(|a, b| {
+            sort.iter()
+                .enumerate()
+                .map(|(i, s)| {
+                    let missing_first = s.missing.as_ref().is_some_and(|m| m == "_first");
+                    compare_sort_values(&a.2[i], &b.2[i], s.order, missing_first)
+                })
+                .find(|ord| ord.is_ne())
+                .unwrap_or_else(|| a.0.cmp(&b.0))
+        });
+    }
+    Ok(entries)
+}
+
+/// Sort values as JSON; documents without a value get `null`
+fn sort_keys_to_json(keys: &[Option<OwnedValue>]) -> Vec<serde_json::Value> {
+    keys.iter()
+        .map(|k| {
+            k.as_ref()
+                .and_then(owned_value_to_json)
+                .unwrap_or(serde_json::Value::Null)
+        })
+        .collect()
+}
+
+/// Select the best documents of a bucket for a `top_hits` aggregation
+#[allow(clippy::too_many_arguments)]
+fn compute_top_hits(
+    searcher: &tantivy::Searcher,
+    coll: &CollectionIndex,
+    scoring_query: &dyn tantivy::query::Query,
+    doc_addrs: &[tantivy::DocAddress],
+    size: usize,
+    from: usize,
+    sort: &[TopHitsSort],
+    source: Option<&[String]>,
+) -> Result<TopHitsResult> {
+    let entries = sort_documents(searcher, coll, scoring_query, doc_addrs, sort)?;
+
+    let max_score = entries.iter().map(|e| e.1).reduce(f32::max);
+    let id_field = coll.field_map.get("id").copied();
+    let mut hits = Vec::new();
+    for (doc_addr, score, keys) in entries.into_iter().skip(from).take(size) {
+        let doc: TantivyDocument = searcher.doc(doc_addr)?;
+        let id = id_field
+            .and_then(|f| doc.get_first(f))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let mut fields = stored_fields(coll, &doc);
+        if let Some(include) = source {
+            fields.retain(|name, _| include.iter().any(|f| f == name));
+        }
+        let sort_values = if sort.is_empty() {
+            Vec::new()
+        } else {
+            sort_keys_to_json(&keys)
+        };
+        hits.push(TopHit {
+            id,
+            score,
+            fields,
+            sort: sort_values,
+        });
+    }
+
+    Ok(TopHitsResult {
+        total: doc_addrs.len() as u64,
+        max_score,
+        hits,
+    })
+}
+
+/// JLH significance score: how much more common a term is in the foreground
+/// set than in the background set (0 when it is not over-represented)
+fn jlh_score(fg_count: u64, fg_size: u64, bg_count: u64, bg_size: u64) -> f64 {
+    if fg_size == 0 || bg_size == 0 {
+        return 0.0;
+    }
+    let fg_pct = fg_count as f64 / fg_size as f64;
+    // A term missing from a filtered background still counts as rare, not infinite
+    let bg_pct = bg_count.max(1) as f64 / bg_size as f64;
+    if fg_pct <= bg_pct {
+        return 0.0;
+    }
+    (fg_pct - bg_pct) * (fg_pct / bg_pct)
+}
+
+/// Compute `significant_terms` buckets for a foreground set against a background set
+fn compute_significant_terms(
+    reader: &FieldValueReader,
+    foreground: &[tantivy::DocAddress],
+    background: &[tantivy::DocAddress],
+    size: usize,
+    min_doc_count: u64,
+) -> Result<SignificantTermsResult> {
+    let fg_counts = count_docs_per_term(reader, foreground)?;
+    let bg_counts = count_docs_per_term(reader, background)?;
+    let fg_size = foreground.len() as u64;
+    let bg_size = background.len() as u64;
+
+    let mut buckets: Vec<SignificantTermsBucket> = fg_counts
+        .into_iter()
+        .filter(|(_, doc_count)| *doc_count >= min_doc_count)
+        .filter_map(|(key, doc_count)| {
+            let bg_count = bg_counts.get(&key).copied().unwrap_or(0);
+            let score = jlh_score(doc_count, fg_size, bg_count, bg_size);
+            (score > 0.0).then_some(SignificantTermsBucket {
+                key,
+                doc_count,
+                bg_count,
+                score,
+            })
+        })
+        .collect();
+    buckets.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    buckets.truncate(size);
+
+    Ok(SignificantTermsResult {
+        doc_count: fg_size,
+        bg_count: bg_size,
+        buckets,
+    })
+}
+
+/// Resolve a field name to a tantivy Field
+fn resolve_field(coll: &CollectionIndex, field_name: &str) -> Result<Field> {
+    coll.field_map
+        .get(field_name)
+        .copied()
+        .ok_or_else(|| Error::InvalidQuery(format!("Unknown field: {}", field_name)))
+}
+
+/// Compute histogram buckets from per-document numeric values
+fn compute_histogram_buckets(
+    values: &[Vec<f64>],
+    interval: f64,
+    min_doc_count: Option<u64>,
+    extended_bounds: Option<&HistogramBounds>,
+) -> Vec<Bucket> {
+    let mut counts: std::collections::BTreeMap<i64, u64> = std::collections::BTreeMap::new();
+
+    for doc_values in values {
+        let mut keys: Vec<i64> = doc_values
+            .iter()
+            .map(|v| (v / interval).floor() as i64)
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+        for bucket_key in keys {
+            *counts.entry(bucket_key).or_insert(0) += 1;
+        }
     }
 
     // Apply extended bounds: ensure all buckets within range exist
@@ -1474,14 +2637,14 @@ fn compute_histogram_buckets(
             from: None,
             to: None,
             sub_aggs: None,
+            doc_count_error_upper_bound: None,
         })
         .collect()
 }
 
 /// Compute date histogram buckets from document field values
 fn compute_date_histogram_buckets(
-    searcher: &tantivy::Searcher,
-    field: Field,
+    reader: &FieldValueReader,
     doc_addrs: &[tantivy::DocAddress],
     interval: &Option<crate::query::aggregations::date_histogram::DateInterval>,
     min_doc_count: Option<u64>,
@@ -1489,11 +2652,8 @@ fn compute_date_histogram_buckets(
     let mut counts: std::collections::BTreeMap<String, u64> = std::collections::BTreeMap::new();
 
     for &doc_addr in doc_addrs {
-        let doc: TantivyDocument = searcher.doc(doc_addr)?;
-        if let Some(value) = doc.get_first(field) {
-            if let Some(key) = date_value_to_bucket_key(value, interval) {
-                *counts.entry(key).or_insert(0) += 1;
-            }
+        for key in doc_date_bucket_keys(&reader.values(doc_addr)?, interval) {
+            *counts.entry(key).or_insert(0) += 1;
         }
     }
 
@@ -1508,10 +2668,25 @@ fn compute_date_histogram_buckets(
             from: None,
             to: None,
             sub_aggs: None,
+            doc_count_error_upper_bound: None,
         })
         .collect())
 }
 
+/// Distinct date histogram bucket keys for one document
+fn doc_date_bucket_keys(
+    values: &[OwnedValue],
+    interval: &Option<crate::query::aggregations::date_histogram::DateInterval>,
+) -> Vec<String> {
+    let mut keys: Vec<String> = values
+        .iter()
+        .filter_map(|v| date_value_to_bucket_key(v, interval))
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
 /// Convert a tantivy field value to a date histogram bucket key
 fn date_value_to_bucket_key(
     value: &tantivy::schema::OwnedValue,
@@ -1545,18 +2720,23 @@ fn date_value_to_bucket_key(
     }
 }
 
-/// Compute range buckets from numeric values
-fn compute_range_buckets(values: &[f64], ranges: &[RangeEntry]) -> Vec<Bucket> {
+/// Whether a value falls inside a range bucket (`from` inclusive, `to` exclusive)
+fn range_contains(range: &RangeEntry, v: f64) -> bool {
+    let above_from = range.from.is_none_or(|from| v >= from);
+    let below_to = range.to.is_none_or(|to| v < to);
+    above_from && below_to
+}
+
+/// Compute range buckets from per-document numeric values.
+///
+/// A document is counted in a range when any of its values falls inside it.
+fn compute_range_buckets(values: &[Vec<f64>], ranges: &[RangeEntry]) -> Vec<Bucket> {
     ranges
         .iter()
         .map(|range| {
             let count = values
                 .iter()
-                .filter(|&&v| {
-                    let above_from = range.from.is_none_or(|from| v >= from);
-                    let below_to = range.to.is_none_or(|to| v < to);
-                    above_from && below_to
-                })
+                .filter(|doc_values| doc_values.iter().any(|&v| range_contains(range, v)))
                 .count() as u64;
 
             Bucket {
@@ -1565,116 +2745,638 @@ fn compute_range_buckets(values: &[f64], ranges: &[RangeEntry]) -> Vec<Bucket> {
                 from: range.from,
                 to: range.to,
                 sub_aggs: None,
+                doc_count_error_upper_bound: None,
             }
         })
         .collect()
 }
 
-/// Generate a bucket key for a range entry
-fn range_bucket_key(range: &RangeEntry) -> String {
-    if let Some(ref key) = range.key {
-        return key.clone();
+/// Generate a bucket key for a range entry
+fn range_bucket_key(range: &RangeEntry) -> String {
+    if let Some(ref key) = range.key {
+        return key.clone();
+    }
+    match (range.from, range.to) {
+        (Some(from), Some(to)) => format!("{}-{}", from, to),
+        (Some(from), None) => format!("{}-*", from),
+        (None, Some(to)) => format!("*-{}", to),
+        (None, None) => "*-*".to_string(),
+    }
+}
+
+/// Collect every document matching a query, in index order
+fn collect_match_set(
+    searcher: &tantivy::Searcher,
+    query: &dyn tantivy::query::Query,
+) -> Result<Vec<tantivy::DocAddress>> {
+    let mut addrs: Vec<tantivy::DocAddress> = searcher
+        .search(query, &tantivy::collector::DocSetCollector)?
+        .into_iter()
+        .collect();
+    addrs.sort_unstable();
+    Ok(addrs)
+}
+
+/// Run a filter query and return matching doc addresses, optionally intersected with a parent set
+fn resolve_filter_docs(
+    searcher: &tantivy::Searcher,
+    coll: &CollectionIndex,
+    searchable_fields: &[Field],
+    query_str: &str,
+    parent_addrs: Option<&[tantivy::DocAddress]>,
+) -> Result<Vec<tantivy::DocAddress>> {
+    let qp = QueryParser::for_index(&coll.index, searchable_fields.to_vec());
+    let qs = query_str.to_string();
+    let parsed = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        qp.parse_query(&qs)
+    })) {
+        Ok(Ok(q)) => q,
+        Ok(Err(e)) => return Err(Error::InvalidQuery(e.to_string())),
+        Err(_) => {
+            return Err(Error::InvalidQuery(format!(
+                "Query parser panicked on input: {:?}",
+                qs
+            )));
+        }
+    };
+
+    let addrs = collect_match_set(searcher, parsed.as_ref())?;
+
+    if let Some(parent) = parent_addrs {
+        // Intersect: only keep addresses that are in the parent set
+        let parent_set: std::collections::HashSet<_> = parent.iter().collect();
+        Ok(addrs
+            .into_iter()
+            .filter(|a| parent_set.contains(a))
+            .collect())
+    } else {
+        Ok(addrs)
+    }
+}
+
+// ============================================================================
+// Index Inspection API (Issue #24)
+// ============================================================================
+
+/// Term information for index inspection
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TermInfo {
+    pub term: String,
+    pub doc_freq: u64,
+}
+
+/// Suggestion entry returned by suggest_terms
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SuggestEntry {
+    pub term: String,
+    pub score: f32,
+    pub doc_freq: u64,
+}
+
+/// Term pattern of a multi-term query, expanded by
+/// [`TextBackend::expand_terms`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TermPattern {
+    /// Terms starting with a prefix
+    Prefix(String),
+    /// Terms matching a pattern where `*` stands for any characters and `?`
+    /// for one
+    Wildcard(String),
+    /// Terms matching a regular expression as a whole
+    Regex(String),
+    /// Terms within `distance` edits of `term` that share its first
+    /// `prefix_length` characters
+    Fuzzy {
+        term: String,
+        distance: u8,
+        prefix_length: usize,
+        transpositions: bool,
+    },
+}
+
+/// Levenshtein DFA walked over a term dictionary, as in tantivy's
+/// `FuzzyTermQuery`
+struct LevenshteinAutomaton(levenshtein_automata::DFA);
+
+impl tantivy_fst::Automaton for LevenshteinAutomaton {
+    type State = u32;
+
+    fn start(&self) -> u32 {
+        self.0.initial_state()
+    }
+
+    fn is_match(&self, state: &u32) -> bool {
+        matches!(
+            self.0.distance(*state),
+            levenshtein_automata::Distance::Exact(_)
+        )
+    }
+
+    fn can_match(&self, state: &u32) -> bool {
+        *state != levenshtein_automata::SINK_STATE
+    }
+
+    fn accept(&self, state: &u32, byte: u8) -> u32 {
+        self.0.transition(*state, byte)
+    }
+}
+
+/// Regular expression matching the same terms as a wildcard pattern
+fn wildcard_to_regex(pattern: &str) -> String {
+    let mut regex = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex
+}
+
+/// Add the document frequency of a term dictionary entry to its count
+fn count_term(term_counts: &mut HashMap<String, u64>, key: &[u8], doc_freq: u32) {
+    if let Ok(term) = std::str::from_utf8(key) {
+        *term_counts.entry(term.to_string()).or_insert(0) += doc_freq as u64;
+    }
+}
+
+/// Count the terms of a field that match a regular expression as a whole
+fn regex_terms(
+    searcher: &tantivy::Searcher,
+    field: Field,
+    source: &str,
+    term_counts: &mut HashMap<String, u64>,
+) -> Result<()> {
+    let regex = tantivy_fst::Regex::new(source)
+        .map_err(|e| Error::InvalidQuery(format!("Invalid regex '{}': {}", source, e)))?;
+    for segment_reader in searcher.segment_readers() {
+        let inverted_index = segment_reader.inverted_index(field)?;
+        let mut stream = inverted_index.terms().search(&regex).into_stream()?;
+        while stream.advance() {
+            count_term(term_counts, stream.key(), stream.value().doc_freq);
+        }
+    }
+    Ok(())
+}
+
+/// Result of an optimize (segment merge) operation
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OptimizeResult {
+    pub segments_before: usize,
+    pub segments_after: usize,
+    pub merged: bool,
+}
+
+/// Segment information for index inspection
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SegmentInfo {
+    pub id: String,
+    pub doc_count: u32,
+    pub deleted_count: u32,
+    pub size_bytes: u64,
+}
+
+/// Segments overview response
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SegmentsInfo {
+    pub segments: Vec<SegmentInfo>,
+    pub total_docs: u64,
+    pub total_deleted: u64,
+    pub delete_ratio: f64,
+}
+
+/// Reconstructed document with indexed terms
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReconstructedDocument {
+    pub id: String,
+    pub stored_fields: HashMap<String, serde_json::Value>,
+    pub indexed_terms: HashMap<String, Vec<String>>,
+}
+
+/// A pinned searcher generation of one collection.
+///
+/// Searches through a snapshot see the index as it was when the snapshot was
+/// taken; the segments it references stay alive until it is dropped, even if
+/// later commits or merges replace them.
+#[derive(Clone)]
+pub struct TextSnapshot {
+    searcher: tantivy::Searcher,
+}
+
+impl TextSnapshot {
+    /// Number of live documents visible in the snapshot
+    pub fn num_docs(&self) -> u64 {
+        self.searcher.num_docs()
+    }
+}
+
+/// Where a search through a snapshot resumes.
+///
+/// Snapshot hits are ordered by descending score, then document address; a
+/// search given a cursor returns only the hits after it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapshotCursor {
+    pub score: f32,
+    pub segment_ord: u32,
+    pub doc_id: u32,
+}
+
+impl SnapshotCursor {
+    /// Cursor after every hit scoring `score` or more
+    pub fn below(score: f32) -> Self {
+        Self {
+            score,
+            segment_ord: u32::MAX,
+            doc_id: u32::MAX,
+        }
+    }
+
+    /// Cursor before every hit scoring `score` or less
+    pub fn at_most(score: f32) -> Self {
+        Self::below(score.next_up())
+    }
+
+    fn hit(&self) -> (tantivy::Score, tantivy::DocAddress) {
+        (
+            self.score,
+            tantivy::DocAddress::new(self.segment_ord, self.doc_id),
+        )
+    }
+}
+
+/// One page of hits from a snapshot search
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotResults {
+    pub results: Vec<SearchResult>,
+    /// Cursor resuming after each hit, in the order of `results`
+    pub cursors: Vec<SnapshotCursor>,
+    pub total: u64,
+    pub aggregations: HashMap<String, AggregationResult>,
+}
+
+/// Order of snapshot hits: descending score, then document address
+fn snapshot_hit_order(
+    a: &(tantivy::Score, tantivy::DocAddress),
+    b: &(tantivy::Score, tantivy::DocAddress),
+) -> std::cmp::Ordering {
+    b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1))
+}
+
+/// Collects the best `limit` hits after a cursor.
+///
+/// Unlike `TopDocs` with an offset, the hits before the cursor are never
+/// ranked, so each page of a scroll costs the same.
+struct TopDocsAfter {
+    limit: usize,
+    after: Option<(tantivy::Score, tantivy::DocAddress)>,
+}
+
+impl tantivy::collector::Collector for TopDocsAfter {
+    type Fruit = Vec<(tantivy::Score, tantivy::DocAddress)>;
+    type Child = SegmentTopDocsAfter;
+
+    fn for_segment(
+        &self,
+        segment_ord: tantivy::SegmentOrdinal,
+        _segment: &tantivy::SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        Ok(SegmentTopDocsAfter {
+            segment_ord,
+            limit: self.limit,
+            after: self.after,
+            hits: Vec::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn merge_fruits(&self, fruits: Vec<Self::Fruit>) -> tantivy::Result<Self::Fruit> {
+        let mut hits: Vec<_> = fruits.into_iter().flatten().collect();
+        hits.sort_unstable_by(snapshot_hit_order);
+        hits.truncate(self.limit);
+        Ok(hits)
+    }
+}
+
+struct SegmentTopDocsAfter {
+    segment_ord: tantivy::SegmentOrdinal,
+    limit: usize,
+    after: Option<(tantivy::Score, tantivy::DocAddress)>,
+    hits: Vec<(tantivy::Score, tantivy::DocAddress)>,
+}
+
+impl SegmentTopDocsAfter {
+    /// Keep only the best `limit` hits
+    fn trim(&mut self) {
+        if self.hits.len() > self.limit {
+            if self.limit > 0 {
+                self.hits
+                    .select_nth_unstable_by(self.limit - 1, snapshot_hit_order);
+            }
+            self.hits.truncate(self.limit);
+        }
+    }
+}
+
+impl tantivy::collector::SegmentCollector for SegmentTopDocsAfter {
+    type Fruit = Vec<(tantivy::Score, tantivy::DocAddress)>;
+
+    fn collect(&mut self, doc: tantivy::DocId, score: tantivy::Score) {
+        let hit = (score, tantivy::DocAddress::new(self.segment_ord, doc));
+        if self
+            .after
+            .is_some_and(|after| snapshot_hit_order(&hit, &after).is_le())
+        {
+            return;
+        }
+        self.hits.push(hit);
+        // Trimmed in batches rather than on every hit
+        if self.hits.len() >= self.limit.max(1) * 2 {
+            self.trim();
+        }
+    }
+
+    fn harvest(mut self) -> Self::Fruit {
+        self.trim();
+        self.hits
+    }
+}
+
+/// Run a query and its aggregations against a pinned searcher, returning
+/// the page of hits after `after`
+fn search_snapshot_on(
+    coll: &CollectionIndex,
+    searcher: &tantivy::Searcher,
+    query: &Query,
+    aggregations: &[AggregationRequest],
+    after: Option<&SnapshotCursor>,
+) -> Result<SnapshotResults> {
+    let Some(parsed_query) = parse_search_query(coll, query)? else {
+        return Ok(SnapshotResults::default());
+    };
+
+    let collector = TopDocsAfter {
+        limit: query.offset + query.limit,
+        after: after.map(SnapshotCursor::hit),
+    };
+    let (top_docs, total, agg_results) = if aggregations.is_empty() {
+        let (top_docs, count) =
+            searcher.search(&parsed_query, &(collector, tantivy::collector::Count))?;
+        (top_docs, count as u64, HashMap::new())
+    } else {
+        // Aggregations run over the full match set, not just the returned page
+        let doc_addrs = collect_match_set(searcher, parsed_query.as_ref())?;
+        let agg_results = execute_aggregations(
+            searcher,
+            coll,
+            &searchable_fields(coll),
+            parsed_query.as_ref(),
+            &doc_addrs,
+            aggregations,
+        )?;
+        let top_docs = searcher.search(&parsed_query, &collector)?;
+        (top_docs, doc_addrs.len() as u64, agg_results)
+    };
+
+    let id_field = coll.field_map.get("id").unwrap();
+    let mut results = Vec::new();
+    let mut cursors = Vec::new();
+    for (score, doc_addr) in top_docs.into_iter().skip(query.offset) {
+        let doc: TantivyDocument = searcher.doc(doc_addr)?;
+        let id = doc
+            .get_first(*id_field)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        results.push(SearchResult {
+            id,
+            score,
+            fields: stored_fields(coll, &doc),
+            highlight: None,
+        });
+        cursors.push(SnapshotCursor {
+            score,
+            segment_ord: doc_addr.segment_ord,
+            doc_id: doc_addr.doc_id,
+        });
+    }
+
+    if let Some(ref hl_config) = query.highlight {
+        highlight_results(
+            coll,
+            searcher,
+            parsed_query.as_ref(),
+            hl_config,
+            &mut results,
+        );
+    }
+
+    Ok(SnapshotResults {
+        results,
+        cursors,
+        total,
+        aggregations: agg_results,
+    })
+}
+
+impl TextBackend {
+    /// Pin the current searcher generation of a collection.
+    pub fn snapshot(&self, collection: &str) -> Result<TextSnapshot> {
+        let collections = self.collections.read().unwrap();
+        let coll = collections
+            .get(collection)
+            .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
+
+        coll.reader.reload()?;
+        Ok(TextSnapshot {
+            searcher: coll.reader.searcher(),
+        })
+    }
+
+    /// IDs of every live document in a collection.
+    pub fn document_ids(&self, collection: &str) -> Result<Vec<String>> {
+        let collections = self.collections.read().unwrap();
+        let coll = collections
+            .get(collection)
+            .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
+
+        coll.reader.reload()?;
+        let searcher = coll.reader.searcher();
+        let id_field = *coll.field_map.get("id").unwrap();
+        let addresses = searcher.search(
+            &tantivy::query::AllQuery,
+            &tantivy::collector::DocSetCollector,
+        )?;
+        let mut ids = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            if let Some(id) = doc.get_first(id_field).and_then(|v| v.as_str()) {
+                ids.push(id.to_string());
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Highest `_seq_no` stored in a collection, counting documents that were
+    /// overwritten or deleted but not merged away yet. `None` when no document
+    /// carries one.
+    pub fn max_seq_no(&self, collection: &str) -> Result<Option<u64>> {
+        let collections = self.collections.read().unwrap();
+        let coll = collections
+            .get(collection)
+            .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
+        if !coll.field_map.contains_key("_seq_no") {
+            return Ok(None);
+        }
+
+        coll.reader.reload()?;
+        let searcher = coll.reader.searcher();
+        let mut max = None;
+        for segment_reader in searcher.segment_readers() {
+            let Some(column) = segment_reader.fast_fields().column_opt::<u64>("_seq_no")? else {
+                continue;
+            };
+            if column.values.num_vals() > 0 {
+                max = max.max(Some(column.max_value()));
+            }
+        }
+        Ok(max)
+    }
+
+    /// Search with hits ordered by `sort` instead of relevance; each hit
+    /// comes with its sort values.
+    pub fn search_sorted(
+        &self,
+        collection: &str,
+        query: &Query,
+        aggregations: &[AggregationRequest],
+        sort: &[TopHitsSort],
+    ) -> Result<SortedSearchResults> {
+        let collections = self.collections.read().unwrap();
+        let coll = collections
+            .get(collection)
+            .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
+
+        coll.reader.reload()?;
+        let searcher = coll.reader.searcher();
+
+        search_sorted_on(coll, &searcher, query, aggregations, sort)
     }
-    match (range.from, range.to) {
-        (Some(from), Some(to)) => format!("{}-{}", from, to),
-        (Some(from), None) => format!("{}-*", from),
-        (None, Some(to)) => format!("*-{}", to),
-        (None, None) => "*-*".to_string(),
+
+    /// Search a snapshot taken with [`TextBackend::snapshot`], starting
+    /// after `after` when paging on from an earlier page.
+    pub fn search_snapshot(
+        &self,
+        collection: &str,
+        snapshot: &TextSnapshot,
+        query: &Query,
+        aggregations: &[AggregationRequest],
+        after: Option<&SnapshotCursor>,
+    ) -> Result<SnapshotResults> {
+        let collections = self.collections.read().unwrap();
+        let coll = collections
+            .get(collection)
+            .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
+
+        search_snapshot_on(coll, &snapshot.searcher, query, aggregations, after)
     }
-}
 
-/// Run a filter query and return matching doc addresses, optionally intersected with a parent set
-fn resolve_filter_docs(
-    searcher: &tantivy::Searcher,
-    coll: &CollectionIndex,
-    searchable_fields: &[Field],
-    query_str: &str,
-    parent_addrs: Option<&[tantivy::DocAddress]>,
-) -> Result<Vec<tantivy::DocAddress>> {
-    let qp = QueryParser::for_index(&coll.index, searchable_fields.to_vec());
-    let qs = query_str.to_string();
-    let parsed = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-        qp.parse_query(&qs)
-    })) {
-        Ok(Ok(q)) => q,
-        Ok(Err(e)) => return Err(Error::InvalidQuery(e.to_string())),
-        Err(_) => {
-            return Err(Error::InvalidQuery(format!(
-                "Query parser panicked on input: {:?}",
-                qs
-            )));
-        }
-    };
+    /// Collect the BM25 statistics of the terms in `query`, for a DFS phase
+    /// that sums them across shards.
+    pub fn term_statistics(&self, collection: &str, query: &Query) -> Result<TermStatistics> {
+        let collections = self.collections.read().unwrap();
+        let coll = collections
+            .get(collection)
+            .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
 
-    let results = searcher.search(&parsed, &TopDocs::with_limit(10000))?;
-    let addrs: Vec<tantivy::DocAddress> = results.into_iter().map(|(_s, addr)| addr).collect();
+        coll.reader.reload()?;
+        let searcher = coll.reader.searcher();
 
-    if let Some(parent) = parent_addrs {
-        // Intersect: only keep addresses that are in the parent set
-        let parent_set: std::collections::HashSet<_> = parent.iter().collect();
-        Ok(addrs
-            .into_iter()
-            .filter(|a| parent_set.contains(a))
-            .collect())
-    } else {
-        Ok(addrs)
+        term_statistics_on(coll, &searcher, query)
     }
-}
 
-// ============================================================================
-// Index Inspection API (Issue #24)
-// ============================================================================
+    /// Search with BM25 scored from `statistics` instead of this index's own,
+    /// so scores are comparable with other shards searched the same way.
+    pub fn search_with_statistics(
+        &self,
+        collection: &str,
+        query: &Query,
+        statistics: &TermStatistics,
+    ) -> Result<SearchResults> {
+        let collections = self.collections.read().unwrap();
+        let coll = collections
+            .get(collection)
+            .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
 
-/// Term information for index inspection
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct TermInfo {
-    pub term: String,
-    pub doc_freq: u64,
-}
+        coll.reader.reload()?;
+        let searcher = coll.reader.searcher();
 
-/// Suggestion entry returned by suggest_terms
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct SuggestEntry {
-    pub term: String,
-    pub score: f32,
-    pub doc_freq: u64,
-}
+        search_on(coll, &searcher, query, Some(statistics))
+    }
 
-/// Result of an optimize (segment merge) operation
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct OptimizeResult {
-    pub segments_before: usize,
-    pub segments_after: usize,
-    pub merged: bool,
-}
+    /// Return which of `ids` match a query string.
+    ///
+    /// Used to filter hits found by another backend, such as nearest-neighbour
+    /// candidates. The query is evaluated against `snapshot` when given,
+    /// otherwise against the latest commit.
+    pub fn filter_ids(
+        &self,
+        collection: &str,
+        ids: &[String],
+        query_str: &str,
+        snapshot: Option<&TextSnapshot>,
+    ) -> Result<HashSet<String>> {
+        let collections = self.collections.read().unwrap();
+        let coll = collections
+            .get(collection)
+            .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
+        if ids.is_empty() {
+            return Ok(HashSet::new());
+        }
 
-/// Segment information for index inspection
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct SegmentInfo {
-    pub id: String,
-    pub doc_count: u32,
-    pub deleted_count: u32,
-    pub size_bytes: u64,
-}
+        let searcher = match snapshot {
+            Some(snapshot) => snapshot.searcher.clone(),
+            None => {
+                coll.reader.reload()?;
+                coll.reader.searcher()
+            }
+        };
+        let searchable_fields: Vec<Field> = coll
+            .schema
+            .fields()
+            .filter(|(_, entry)| {
+                entry.field_type().is_indexed()
+                    && matches!(entry.field_type(), tantivy::schema::FieldType::Str(_))
+            })
+            .map(|(field, _)| field)
+            .collect();
 
-/// Segments overview response
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct SegmentsInfo {
-    pub segments: Vec<SegmentInfo>,
-    pub total_docs: u64,
-    pub total_deleted: u64,
-    pub delete_ratio: f64,
-}
+        let id_field = *coll.field_map.get("id").unwrap();
+        let id_terms = ids.iter().map(|id| Term::from_field_text(id_field, id));
+        let id_query: Box<dyn tantivy::query::Query> =
+            Box::new(tantivy::query::TermSetQuery::new(id_terms));
+        let candidates = collect_match_set(&searcher, id_query.as_ref())?;
+        let matching = resolve_filter_docs(
+            &searcher,
+            coll,
+            &searchable_fields,
+            query_str,
+            Some(&candidates),
+        )?;
 
-/// Reconstructed document with indexed terms
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct ReconstructedDocument {
-    pub id: String,
-    pub stored_fields: HashMap<String, serde_json::Value>,
-    pub indexed_terms: HashMap<String, Vec<String>>,
-}
+        let mut found = HashSet::with_capacity(matching.len());
+        for addr in matching {
+            let doc: TantivyDocument = searcher.doc(addr)?;
+            if let Some(id) = doc.get_first(id_field).and_then(|v| v.as_str()) {
+                found.insert(id.to_string());
+            }
+        }
+        Ok(found)
+    }
 
-impl TextBackend {
     /// Get top-k most frequent terms for a field.
     pub fn get_top_terms(
         &self,
@@ -1872,31 +3574,15 @@ impl TextBackend {
 
         if let Some(id) = doc_id {
             exclude_id = Some(id.to_string());
-            // Fetch the document
-            let id_field = coll.field_map.get("id").unwrap();
-            let term = Term::from_field_text(*id_field, id);
-            let query = tantivy::query::TermQuery::new(term, IndexRecordOption::Basic);
-            let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
-
-            if let Some((_score, doc_addr)) = top_docs.first() {
-                let doc: TantivyDocument = searcher.doc(*doc_addr)?;
-                let mut texts = Vec::new();
-                for field_name in fields {
-                    if let Some(&field) = coll.field_map.get(field_name) {
-                        if let Some(val) = doc.get_first(field) {
-                            if let Some(s) = val.as_str() {
-                                texts.push(s.to_string());
-                            }
-                        }
-                    }
+            match mlt_document_text(coll, &searcher, id, fields)? {
+                Some(text) => source_text = text,
+                None => {
+                    return Ok(SearchResults {
+                        results: vec![],
+                        total: 0,
+                        latency_ms: 0,
+                    });
                 }
-                source_text = texts.join(" ");
-            } else {
-                return Ok(SearchResults {
-                    results: vec![],
-                    total: 0,
-                    latency_ms: 0,
-                });
             }
         } else if let Some(text) = like_text {
             exclude_id = None;
@@ -1915,58 +3601,15 @@ impl TextBackend {
             });
         }
 
-        // Extract significant terms: tokenize the source text and count term frequencies
-        let mut term_freqs: HashMap<String, usize> = HashMap::new();
-        for word in source_text.split_whitespace() {
-            let w = word.to_lowercase();
-            // Skip very short tokens
-            if w.len() >= 2 {
-                *term_freqs.entry(w).or_insert(0) += 1;
-            }
-        }
-
-        // Filter by min_term_freq and min_doc_freq, then score by TF-IDF-like weighting
-        let resolve_fields: Vec<Field> = if fields.is_empty() {
-            coll.schema
-                .fields()
-                .filter(|(_, e)| {
-                    matches!(e.field_type(), tantivy::schema::FieldType::Str(_))
-                        && e.field_type().is_indexed()
-                })
-                .map(|(f, _)| f)
-                .collect()
-        } else {
-            fields
-                .iter()
-                .filter_map(|f| coll.field_map.get(f).copied())
-                .collect()
-        };
-
-        let num_docs = searcher.num_docs() as f32;
-        let mut scored_terms: Vec<(String, f32)> = Vec::new();
-
-        for (term_str, tf) in &term_freqs {
-            if *tf < min_term_freq {
-                continue;
-            }
-            // Sum doc_freq across all target fields
-            let mut total_df: u64 = 0;
-            for &field in &resolve_fields {
-                let t = Term::from_field_text(field, term_str);
-                total_df += searcher.doc_freq(&t)?;
-            }
-            if total_df < min_doc_freq {
-                continue;
-            }
-            // TF-IDF score
-            let idf = (num_docs / (1.0 + total_df as f32)).ln() + 1.0;
-            let score = (*tf as f32) * idf;
-            scored_terms.push((term_str.clone(), score));
-        }
-
-        // Sort by score desc and take top max_query_terms
-        scored_terms.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        scored_terms.truncate(max_query_terms);
+        let resolve_fields = mlt_fields(coll, fields);
+        let scored_terms = mlt_terms(
+            &searcher,
+            &resolve_fields,
+            &source_text,
+            min_term_freq,
+            min_doc_freq,
+            max_query_terms,
+        )?;
 
         if scored_terms.is_empty() {
             return Ok(SearchResults {
@@ -1977,11 +3620,7 @@ impl TextBackend {
         }
 
         // Build a disjunction query from the top terms
-        let query_string = scored_terms
-            .iter()
-            .map(|(t, _)| t.as_str())
-            .collect::<Vec<_>>()
-            .join(" ");
+        let query_string = scored_terms.join(" ");
 
         let query_parser = QueryParser::for_index(&coll.index, resolve_fields);
         let qs = query_string.clone();
@@ -2020,16 +3659,7 @@ impl TextBackend {
                 }
             }
 
-            let mut fields_map = HashMap::new();
-            for (field, entry) in coll.schema.fields() {
-                if entry.is_stored() {
-                    if let Some(value) = doc.get_first(field) {
-                        if let Some(json_value) = owned_value_to_json(value) {
-                            fields_map.insert(entry.name().to_string(), json_value);
-                        }
-                    }
-                }
-            }
+            let fields_map = stored_fields(coll, &doc);
 
             results.push(SearchResult {
                 id,
@@ -2052,6 +3682,136 @@ impl TextBackend {
         })
     }
 
+    /// Significant terms of `like` documents and texts, as used by
+    /// [`Self::more_like_this`]: the terms a more-like-this query over
+    /// `fields` searches for, best first
+    #[allow(clippy::too_many_arguments)]
+    pub fn more_like_this_terms(
+        &self,
+        collection: &str,
+        doc_ids: &[String],
+        like_texts: &[String],
+        fields: &[String],
+        min_term_freq: usize,
+        min_doc_freq: u64,
+        max_query_terms: usize,
+    ) -> Result<Vec<String>> {
+        let collections = self.collections.read().unwrap();
+        let coll = collections
+            .get(collection)
+            .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
+
+        coll.reader.reload()?;
+        let searcher = coll.reader.searcher();
+
+        let mut texts = like_texts.to_vec();
+        for id in doc_ids {
+            texts.extend(mlt_document_text(coll, &searcher, id, fields)?);
+        }
+        let source_text = texts.join(" ");
+        if source_text.is_empty() {
+            return Ok(vec![]);
+        }
+
+        mlt_terms(
+            &searcher,
+            &mlt_fields(coll, fields),
+            &source_text,
+            min_term_freq,
+            min_doc_freq,
+            max_query_terms,
+        )
+    }
+
+    /// Terms of `field` matching a multi-term query pattern, which the query
+    /// is rewritten to before searching.
+    ///
+    /// Fuzzy matches come closest first, then most frequent; other patterns
+    /// in term order. At most `max_expansions` terms are returned, and
+    /// fields the collection does not have yield none.
+    pub fn expand_terms(
+        &self,
+        collection: &str,
+        field: &str,
+        pattern: &TermPattern,
+        max_expansions: usize,
+    ) -> Result<Vec<String>> {
+        let collections = self.collections.read().unwrap();
+        let coll = collections
+            .get(collection)
+            .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
+        let Some(field_obj) = coll.field_map.get(field).copied() else {
+            return Ok(vec![]);
+        };
+
+        coll.reader.reload()?;
+        let searcher = coll.reader.searcher();
+        let mut term_counts: HashMap<String, u64> = HashMap::new();
+
+        match pattern {
+            TermPattern::Prefix(prefix) => {
+                for segment_reader in searcher.segment_readers() {
+                    let inverted_index = segment_reader.inverted_index(field_obj)?;
+                    let mut stream = inverted_index
+                        .terms()
+                        .range()
+                        .ge(prefix.as_bytes())
+                        .into_stream()?;
+                    while stream.advance() {
+                        if !stream.key().starts_with(prefix.as_bytes()) {
+                            break;
+                        }
+                        count_term(&mut term_counts, stream.key(), stream.value().doc_freq);
+                    }
+                }
+            }
+            TermPattern::Wildcard(wildcard) => regex_terms(
+                &searcher,
+                field_obj,
+                &wildcard_to_regex(wildcard),
+                &mut term_counts,
+            )?,
+            TermPattern::Regex(regex) => {
+                regex_terms(&searcher, field_obj, regex, &mut term_counts)?
+            }
+            TermPattern::Fuzzy {
+                term,
+                distance,
+                prefix_length,
+                transpositions,
+            } => {
+                let builder = levenshtein_automata::LevenshteinAutomatonBuilder::new(
+                    *distance,
+                    *transpositions,
+                );
+                let automaton = LevenshteinAutomaton(builder.build_dfa(term));
+                let prefix: String = term.chars().take(*prefix_length).collect();
+                for segment_reader in searcher.segment_readers() {
+                    let inverted_index = segment_reader.inverted_index(field_obj)?;
+                    let mut stream = inverted_index.terms().search(&automaton).into_stream()?;
+                    while stream.advance() {
+                        if stream.key().starts_with(prefix.as_bytes()) {
+                            count_term(&mut term_counts, stream.key(), stream.value().doc_freq);
+                        }
+                    }
+                }
+
+                let mut terms: Vec<(u8, u64, String)> = term_counts
+                    .into_iter()
+                    .map(|(t, doc_freq)| (automaton.0.eval(&t).to_u8(), doc_freq, t))
+                    .collect();
+                terms.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)).then(a.2.cmp(&b.2)));
+                terms.truncate(max_expansions);
+                return Ok(terms.into_iter().map(|(_, _, t)| t).collect());
+            }
+        }
+
+        let mut terms: Vec<String> = term_counts.into_keys().collect();
+        terms.sort();
+        terms.truncate(max_expansions);
+        Ok(terms)
+    }
+
     /// Get segment information for a collection.
     pub fn get_segments(&self, collection: &str) -> Result<SegmentsInfo> {
         let collections = self.collections.read().unwrap();
@@ -2171,16 +3931,7 @@ impl TextBackend {
         let doc: TantivyDocument = searcher.doc(*doc_addr)?;
 
         // Collect stored fields
-        let mut stored_fields = HashMap::new();
-        for (field, entry) in coll.schema.fields() {
-            if entry.is_stored() {
-                if let Some(value) = doc.get_first(field) {
-                    if let Some(json_value) = owned_value_to_json(value) {
-                        stored_fields.insert(entry.name().to_string(), json_value);
-                    }
-                }
-            }
-        }
+        let stored_fields = stored_fields(coll, &doc);
 
         // Collect indexed terms for text fields
         let mut indexed_terms: HashMap<String, Vec<String>> = HashMap::new();
@@ -2237,6 +3988,104 @@ impl TextBackend {
     }
 }
 
+/// Text of a document's `fields`, or `None` if the document does not exist
+fn mlt_document_text(
+    coll: &CollectionIndex,
+    searcher: &tantivy::Searcher,
+    id: &str,
+    fields: &[String],
+) -> Result<Option<String>> {
+    let id_field = coll.field_map.get("id").unwrap();
+    let term = Term::from_field_text(*id_field, id);
+    let query = tantivy::query::TermQuery::new(term, IndexRecordOption::Basic);
+    let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+
+    let Some((_score, doc_addr)) = top_docs.first() else {
+        return Ok(None);
+    };
+    let doc: TantivyDocument = searcher.doc(*doc_addr)?;
+    let mut texts = Vec::new();
+    for field_name in fields {
+        if let Some(&field) = coll.field_map.get(field_name) {
+            texts.extend(
+                doc.get_all(field)
+                    .filter_map(|val| val.as_str())
+                    .map(String::from),
+            );
+        }
+    }
+    Ok(Some(texts.join(" ")))
+}
+
+/// Fields a more-like-this query searches: the given ones, or every indexed
+/// text field
+fn mlt_fields(coll: &CollectionIndex, fields: &[String]) -> Vec<Field> {
+    if fields.is_empty() {
+        coll.schema
+            .fields()
+            .filter(|(_, e)| {
+                matches!(e.field_type(), tantivy::schema::FieldType::Str(_))
+                    && e.field_type().is_indexed()
+            })
+            .map(|(f, _)| f)
+            .collect()
+    } else {
+        fields
+            .iter()
+            .filter_map(|f| coll.field_map.get(f).copied())
+            .collect()
+    }
+}
+
+/// Most significant terms of `source_text` by TF-IDF over `fields`
+fn mlt_terms(
+    searcher: &tantivy::Searcher,
+    fields: &[Field],
+    source_text: &str,
+    min_term_freq: usize,
+    min_doc_freq: u64,
+    max_query_terms: usize,
+) -> Result<Vec<String>> {
+    // Tokenize the source text and count term frequencies
+    let mut term_freqs: HashMap<String, usize> = HashMap::new();
+    for word in source_text.split_whitespace() {
+        let w = word.to_lowercase();
+        // Skip very short tokens
+        if w.len() >= 2 {
+            *term_freqs.entry(w).or_insert(0) += 1;
+        }
+    }
+
+    // Filter by min_term_freq and min_doc_freq, then score by TF-IDF-like weighting
+    let num_docs = searcher.num_docs() as f32;
+    let mut scored_terms: Vec<(String, f32)> = Vec::new();
+
+    for (term_str, tf) in &term_freqs {
+        if *tf < min_term_freq {
+            continue;
+        }
+        // Sum doc_freq across all target fields
+        let mut total_df: u64 = 0;
+        for &field in fields {
+            let t = Term::from_field_text(field, term_str);
+            total_df += searcher.doc_freq(&t)?;
+        }
+        if total_df < min_doc_freq {
+            continue;
+        }
+        // TF-IDF score
+        let idf = (num_docs / (1.0 + total_df as f32)).ln() + 1.0;
+        let score = (*tf as f32) * idf;
+        scored_terms.push((term_str.clone(), score));
+    }
+
+    // Sort by score desc and take top max_query_terms
+    scored_terms.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    scored_terms.truncate(max_query_terms);
+
+    Ok(scored_terms.into_iter().map(|(term, _)| term).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -48,9 +48,23 @@ pub struct HighlightConfig {
     /// Maximum number of characters per fragment (default: 150)
     #[serde(default = "default_fragment_size")]
     pub fragment_size: usize,
-    /// Maximum number of fragments per field (default: 3)
+    /// Maximum number of fragments per field (default: 3); 0 highlights
+    /// whole field values
     #[serde(default = "default_number_of_fragments")]
     pub number_of_fragments: usize,
+    /// Per-field overrides of the settings above
+    #[serde(default)]
+    pub field_options: HashMap<String, HighlightFieldOptions>,
+}
+
+/// Highlight settings of one field; unset values fall back to the
+/// [`HighlightConfig`] ones
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighlightFieldOptions {
+    pub pre_tag: Option<String>,
+    pub post_tag: Option<String>,
+    pub fragment_size: Option<usize>,
+    pub number_of_fragments: Option<usize>,
 }
 
 fn default_pre_tag() -> String {
@@ -90,6 +104,49 @@ pub struct SearchResultsWithAggs {
     pub aggregations: HashMap<String, AggregationResult>,
 }
 
+/// Corpus statistics BM25 scores are computed from
+///
+/// Each shard scores against its own statistics by default, so the same
+/// document scores differently on different shards. Summing the statistics
+/// of every shard first (a DFS phase) makes scores comparable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TermStatistics {
+    /// Number of documents
+    pub total_docs: u64,
+    /// Number of tokens in each field
+    #[serde(default)]
+    pub field_tokens: HashMap<String, u64>,
+    /// Number of documents containing each term, by field and term text
+    #[serde(default)]
+    pub doc_freqs: HashMap<String, HashMap<String, u64>>,
+}
+
+impl TermStatistics {
+    /// Add the statistics of another shard
+    pub fn merge(&mut self, other: &TermStatistics) {
+        self.total_docs += other.total_docs;
+        for (field, tokens) in &other.field_tokens {
+            *self.field_tokens.entry(field.clone()).or_default() += tokens;
+        }
+        for (field, terms) in &other.doc_freqs {
+            let merged = self.doc_freqs.entry(field.clone()).or_default();
+            for (term, doc_freq) in terms {
+                *merged.entry(term.clone()).or_default() += doc_freq;
+            }
+        }
+    }
+}
+
+/// Results of a search ordered by sort keys rather than relevance
+#[derive(Debug, Clone, Serialize)]
+pub struct SortedSearchResults {
+    pub results: Vec<SearchResult>,
+    /// Sort values of each hit, in the order of `results`
+    pub sort_values: Vec<Vec<Value>>,
+    pub total: u64,
+    pub aggregations: HashMap<String, AggregationResult>,
+}
+
 #[async_trait]
 pub trait SearchBackend: Send + Sync {
     /// Index documents
@@ -4,7 +4,7 @@ pub mod index;
 pub mod segment;
 pub mod shard;
 
-pub use backend::VectorBackend;
+pub use backend::{ReshardResult, VectorBackend, VectorSnapshot};
 pub use index::{HnswBackend, HnswIndex, Metric};
 pub use segment::VectorSegment;
 pub use shard::{shard_for_doc, VectorShard};
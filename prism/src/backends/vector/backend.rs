@@ -4,16 +4,17 @@
 //! Documents are distributed across shards via hash-based assignment.
 
 use crate::backends::r#trait::{
-    BackendStats, Document, Query, SearchBackend, SearchResults, SearchResultsWithAggs,
+    BackendStats, Document, Query, SearchBackend, SearchResult, SearchResults,
+    SearchResultsWithAggs,
 };
 use crate::cache::EmbeddingCacheStats;
 use crate::error::Result;
 use crate::schema::types::{CollectionSchema, VectorCompactionConfig};
 use async_trait::async_trait;
 use parking_lot::RwLock;
-use prism_storage::{Bytes, LocalStorage, SegmentStorage, StoragePath};
+use prism_storage::{Bytes, LocalStorage, SegmentStorage, StorageBackend, StoragePath};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicU32;
@@ -26,6 +27,45 @@ use super::segment::VectorSegment;
 use super::shard::{shard_for_doc, PersistedShard, VectorShard};
 use crate::embedding::CachedEmbeddingProvider;
 
+/// Documents visible in a vector collection at a point in time.
+#[derive(Clone, Debug, Default)]
+pub struct VectorSnapshot {
+    live_ids: Arc<HashSet<String>>,
+}
+
+impl VectorSnapshot {
+    /// Whether a document was live when the snapshot was taken
+    pub fn contains(&self, id: &str) -> bool {
+        self.live_ids.contains(id)
+    }
+
+    /// Number of documents visible in the snapshot
+    pub fn len(&self) -> usize {
+        self.live_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live_ids.is_empty()
+    }
+
+    /// Drop results for documents that were not live in the snapshot
+    pub fn retain_visible(&self, results: &mut Vec<SearchResult>) {
+        results.retain(|r| self.contains(&r.id));
+    }
+}
+
+/// Outcome of splitting or shrinking a collection's vector shards
+#[derive(Clone, Debug, Serialize)]
+pub struct ReshardResult {
+    pub collection: String,
+    /// Shard count before the operation
+    pub from_shards: usize,
+    /// Shard count after the operation
+    pub to_shards: usize,
+    /// Live documents across the new shards
+    pub documents: usize,
+}
+
 pub struct VectorBackend {
     _base_path: PathBuf,
     indexes: Arc<RwLock<HashMap<String, ShardedVectorIndex>>>,
@@ -33,6 +73,8 @@ pub struct VectorBackend {
     embedding_provider: Arc<RwLock<Option<Arc<CachedEmbeddingProvider>>>>,
     /// Unified storage backend (local, S3, cached, etc.)
     storage: Arc<dyn SegmentStorage>,
+    /// Collections whose shards are being split or shrunk; writes are rejected
+    resharding: Arc<RwLock<HashSet<String>>>,
 }
 
 /// A sharded vector index: holds N shards, each with segments.
@@ -88,9 +130,15 @@ impl VectorBackend {
             indexes: Arc::new(RwLock::new(HashMap::new())),
             embedding_provider: Arc::new(RwLock::new(None)),
             storage,
+            resharding: Arc::new(RwLock::new(HashSet::new())),
         })
     }
 
+    /// Storage holding the persisted vector indexes.
+    pub fn segment_storage(&self) -> Arc<dyn SegmentStorage> {
+        Arc::clone(&self.storage)
+    }
+
     /// Remove a collection from this backend, persisting state before dropping.
     pub async fn remove_collection(&self, name: &str) -> Result<()> {
         let data = {
@@ -109,6 +157,177 @@ impl VectorBackend {
         Ok(())
     }
 
+    /// Delete a collection's persisted vector index from storage.
+    ///
+    /// Call after [`VectorBackend::remove_collection`].
+    pub async fn purge_collection(&self, name: &str) -> Result<()> {
+        self.storage
+            .delete_prefix(&StoragePath::new(name, StorageBackend::Vector))
+            .await
+            .map_err(|e| crate::error::Error::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Capture the set of documents currently visible in a collection.
+    ///
+    /// Vector search results filtered through the snapshot exclude documents
+    /// added after it was taken, so paging over them stays consistent.
+    pub fn snapshot(&self, collection: &str) -> Result<VectorSnapshot> {
+        let indexes = self.indexes.read();
+        let sharded = indexes
+            .get(collection)
+            .ok_or_else(|| crate::error::Error::CollectionNotFound(collection.to_string()))?;
+
+        let live_ids = sharded
+            .shards
+            .iter()
+            .flat_map(|shard| shard.live_ids().cloned())
+            .collect();
+        Ok(VectorSnapshot {
+            live_ids: Arc::new(live_ids),
+        })
+    }
+
+    /// IDs of every live document in a collection.
+    pub fn document_ids(&self, collection: &str) -> Result<Vec<String>> {
+        let indexes = self.indexes.read();
+        let sharded = indexes
+            .get(collection)
+            .ok_or_else(|| crate::error::Error::CollectionNotFound(collection.to_string()))?;
+        Ok(sharded
+            .shards
+            .iter()
+            .flat_map(|shard| shard.live_ids().cloned())
+            .collect())
+    }
+
+    /// Split each vector shard of a collection into several.
+    ///
+    /// `num_shards` must be a multiple of the current shard count, so every
+    /// new shard takes its documents from exactly one existing shard: the
+    /// source's segments are copied into each of its targets and documents
+    /// hashing to another target are tombstoned.
+    pub async fn split_shards(&self, collection: &str, num_shards: usize) -> Result<ReshardResult> {
+        self.reshard(collection, num_shards, ReshardKind::Split)
+            .await
+    }
+
+    /// Shrink a collection's vector shards into fewer.
+    ///
+    /// The current shard count must be a multiple of `num_shards`. Segments of
+    /// the shards folding into the same target are moved into it as they are,
+    /// without re-indexing any vectors.
+    pub async fn shrink_shards(
+        &self,
+        collection: &str,
+        num_shards: usize,
+    ) -> Result<ReshardResult> {
+        self.reshard(collection, num_shards, ReshardKind::Shrink)
+            .await
+    }
+
+    /// Whether a collection's shards are being split or shrunk
+    pub fn is_resharding(&self, collection: &str) -> bool {
+        self.resharding.read().contains(collection)
+    }
+
+    /// Build the new shards from a copy of the current ones while the
+    /// collection is read-only, then swap them in.
+    async fn reshard(
+        &self,
+        collection: &str,
+        num_shards: usize,
+        kind: ReshardKind,
+    ) -> Result<ReshardResult> {
+        let (source, from_shards) = {
+            // Marking under the index lock orders the mark after any write
+            // already applying, and every later write sees it
+            let indexes = self.indexes.write();
+            let sharded = indexes
+                .get(collection)
+                .ok_or_else(|| crate::error::Error::CollectionNotFound(collection.to_string()))?;
+            kind.validate(sharded.num_shards, num_shards)?;
+            if !self.resharding.write().insert(collection.to_string()) {
+                return Err(crate::error::Error::ReadOnly(format!(
+                    "shards of '{}' are already being resharded",
+                    collection
+                )));
+            }
+            (
+                sharded
+                    .shards
+                    .iter()
+                    .map(|shard| shard.to_persisted())
+                    .collect::<Result<Vec<_>>>(),
+                sharded.num_shards,
+            )
+        };
+
+        let result = self
+            .swap_resharded(collection, source, num_shards, kind)
+            .await;
+        self.resharding.write().remove(collection);
+
+        let documents = result?;
+        tracing::info!(
+            collection,
+            from_shards,
+            to_shards = num_shards,
+            documents,
+            "Resharded vector index"
+        );
+        Ok(ReshardResult {
+            collection: collection.to_string(),
+            from_shards,
+            to_shards: num_shards,
+            documents,
+        })
+    }
+
+    async fn swap_resharded(
+        &self,
+        collection: &str,
+        source: Result<Vec<PersistedShard>>,
+        num_shards: usize,
+        kind: ReshardKind,
+    ) -> Result<usize> {
+        let source = source?;
+        let mut shards = reshard_shards(&source, num_shards, kind)?;
+
+        let data = {
+            let mut indexes = self.indexes.write();
+            let sharded = indexes
+                .get_mut(collection)
+                .ok_or_else(|| crate::error::Error::CollectionNotFound(collection.to_string()))?;
+            if kind == ReshardKind::Split {
+                // Reclaim the documents tombstoned in each copy
+                for shard in &mut shards {
+                    let _ = compact_shard(shard, &sharded.compaction_config);
+                }
+            }
+            sharded.shards = shards;
+            sharded.num_shards = num_shards;
+            serialize_sharded_index(sharded)?
+        };
+        self.save_index(collection, &data).await?;
+
+        let indexes = self.indexes.read();
+        Ok(indexes.get(collection).map_or(0, |sharded| {
+            sharded.shards.iter().map(|s| s.live_count() as usize).sum()
+        }))
+    }
+
+    /// Reject writes to a collection whose shards are being resharded
+    fn check_writable(&self, collection: &str) -> Result<()> {
+        if self.resharding.read().contains(collection) {
+            return Err(crate::error::Error::ReadOnly(format!(
+                "shards of '{}' are being resharded",
+                collection
+            )));
+        }
+        Ok(())
+    }
+
     /// Set the embedding provider for automatic embedding generation
     pub fn set_embedding_provider(&self, provider: Arc<CachedEmbeddingProvider>) {
         let mut ep = self.embedding_provider.write();
@@ -214,12 +433,12 @@ impl VectorBackend {
     }
 
     /// Embed a single text using the cached provider
-    #[allow(clippy::await_holding_lock)]
     #[tracing::instrument(name = "embed_text", skip(self, text))]
     pub async fn embed_text(&self, text: &str) -> Result<Vec<f32>> {
         let start = std::time::Instant::now();
-        let ep = self.embedding_provider.read();
-        if let Some(ref provider) = *ep {
+        // Clone the provider out so the returned future stays `Send`
+        let provider = self.embedding_provider.read().clone();
+        if let Some(provider) = provider {
             let result = provider
                 .embed(text)
                 .await
@@ -367,8 +586,8 @@ impl SearchBackend for VectorBackend {
                     for (i, doc) in docs.iter().enumerate() {
                         if !doc.fields.contains_key(&target_field) {
                             if let Some(val) = doc.fields.get(&source_field) {
-                                if let Some(s) = val.as_str() {
-                                    texts_to_embed.push((i, s.to_string()));
+                                if let Some(s) = embedding_source_text(val) {
+                                    texts_to_embed.push((i, s));
                                 }
                             }
                         }
@@ -408,6 +627,7 @@ impl SearchBackend for VectorBackend {
             let sharded = indexes
                 .get_mut(collection)
                 .ok_or_else(|| crate::error::Error::CollectionNotFound(collection.to_string()))?;
+            self.check_writable(collection)?;
 
             let target_field = sharded
                 .shards
@@ -524,6 +744,7 @@ impl SearchBackend for VectorBackend {
             let sharded = indexes
                 .get_mut(collection)
                 .ok_or_else(|| crate::error::Error::CollectionNotFound(collection.to_string()))?;
+            self.check_writable(collection)?;
 
             for id in &ids {
                 let shard_id = shard_for_doc(id, sharded.num_shards) as usize;
@@ -579,6 +800,129 @@ impl SearchBackend for VectorBackend {
     }
 }
 
+/// Text to embed for a document's embedding source field.
+///
+/// Arrays of strings (multi-valued fields) are joined with newlines so every
+/// element contributes to the embedding; other value types are not embedded.
+fn embedding_source_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Array(values) => {
+            let parts: Vec<&str> = values.iter().filter_map(|v| v.as_str()).collect();
+            if parts.is_empty() {
+                None
+            } else {
+                Some(parts.join("\n"))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Direction of a reshard
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ReshardKind {
+    Split,
+    Shrink,
+}
+
+impl ReshardKind {
+    /// Shards are assigned by hash modulo the shard count, so a document
+    /// keeps its shard's residue only when one count divides the other
+    fn validate(self, current: usize, target: usize) -> Result<()> {
+        let valid = match self {
+            ReshardKind::Split => target > current && target.is_multiple_of(current),
+            ReshardKind::Shrink => {
+                target >= 1 && target < current && current.is_multiple_of(target)
+            }
+        };
+        if valid {
+            return Ok(());
+        }
+        let (verb, rule) = match self {
+            ReshardKind::Split => ("split", "a larger multiple of"),
+            ReshardKind::Shrink => ("shrink", "a smaller divisor of"),
+        };
+        Err(crate::error::Error::InvalidQuery(format!(
+            "Cannot {} {} shards into {}: the target must be {} the current count",
+            verb, current, target, rule
+        )))
+    }
+}
+
+/// Build `num_shards` shards from copies of the current ones.
+///
+/// Target `t` takes the documents hashing to it: on a split those all come
+/// from shard `t % current`, on a shrink from every shard `s` with
+/// `s % num_shards == t`.
+fn reshard_shards(
+    source: &[PersistedShard],
+    num_shards: usize,
+    kind: ReshardKind,
+) -> Result<Vec<VectorShard>> {
+    let current = source.len();
+    let mut shards = Vec::with_capacity(num_shards);
+    for target in 0..num_shards {
+        let origins: Vec<&PersistedShard> = match kind {
+            ReshardKind::Split => vec![&source[target % current]],
+            ReshardKind::Shrink => source
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| i % num_shards == target)
+                .map(|(_, shard)| shard)
+                .collect(),
+        };
+        let template = origins[0];
+
+        let mut sealed_segments = Vec::new();
+        for origin in &origins {
+            let segments = std::iter::once(&origin.active_segment).chain(&origin.sealed_segments);
+            for persisted in segments {
+                let mut segment = VectorSegment::from_persisted(persisted.clone())?;
+                if kind == ReshardKind::Split {
+                    let moved: Vec<String> = segment
+                        .id_to_key
+                        .keys()
+                        .filter(|id| shard_for_doc(id, num_shards) as usize != target)
+                        .cloned()
+                        .collect();
+                    for id in &moved {
+                        segment.tombstone(id);
+                    }
+                }
+                if segment.live_count() == 0 {
+                    continue;
+                }
+                segment.id = sealed_segments.len() as u64;
+                segment.seal();
+                sealed_segments.push(segment);
+            }
+        }
+
+        let next_segment_id = sealed_segments.len() as u64;
+        shards.push(VectorShard {
+            shard_id: target as u32,
+            active_segment: VectorSegment::new(
+                next_segment_id,
+                template.dimensions,
+                template.metric,
+                template.m,
+                template.ef_construction,
+            )?,
+            sealed_segments,
+            dimensions: template.dimensions,
+            metric: template.metric,
+            m: template.m,
+            ef_construction: template.ef_construction,
+            ef_search: template.ef_search,
+            embedding_source_field: template.embedding_source_field.clone(),
+            embedding_target_field: template.embedding_target_field.clone(),
+            next_segment_id: next_segment_id + 1,
+        });
+    }
+    Ok(shards)
+}
+
 fn serialize_sharded_index(index: &ShardedVectorIndex) -> Result<Vec<u8>> {
     let mut persisted_shards = Vec::new();
     for shard in &index.shards {
@@ -901,6 +1245,120 @@ mod tests {
         }
     }
 
+    fn vector_docs(count: usize) -> Vec<Document> {
+        (0..count)
+            .map(|i| Document {
+                id: format!("doc-{}", i),
+                fields: HashMap::from([(
+                    "embedding".to_string(),
+                    serde_json::json!([1.0, i as f32, 0.0, 0.0]),
+                )]),
+            })
+            .collect()
+    }
+
+    fn shard_doc_counts(backend: &VectorBackend) -> Vec<u64> {
+        let indexes = backend.indexes.read();
+        indexes["test"]
+            .shards
+            .iter()
+            .map(|shard| shard.live_count())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_split_and_shrink_shards() {
+        let dir = tempdir().unwrap();
+        let backend = VectorBackend::new(dir.path()).unwrap();
+        backend
+            .initialize("test", &make_test_schema(2, 4))
+            .await
+            .unwrap();
+        backend.index("test", vector_docs(40)).await.unwrap();
+
+        let split = backend.split_shards("test", 4).await.unwrap();
+        assert_eq!((split.from_shards, split.to_shards), (2, 4));
+        assert_eq!(split.documents, 40);
+        let counts = shard_doc_counts(&backend);
+        assert_eq!(counts.len(), 4);
+        assert_eq!(counts.iter().sum::<u64>(), 40);
+        // Every document is reachable through the new hash routing
+        for i in 0..40 {
+            let id = format!("doc-{}", i);
+            assert!(backend.get("test", &id).await.unwrap().is_some(), "{}", id);
+        }
+
+        let shrunk = backend.shrink_shards("test", 1).await.unwrap();
+        assert_eq!((shrunk.from_shards, shrunk.to_shards), (4, 1));
+        assert_eq!(shard_doc_counts(&backend), vec![40]);
+        let results = backend
+            .search(
+                "test",
+                Query {
+                    query_string: "[1.0, 3.0, 0.0, 0.0]".to_string(),
+                    fields: vec![],
+                    limit: 1,
+                    offset: 0,
+                    merge_strategy: None,
+                    text_weight: None,
+                    vector_weight: None,
+                    highlight: None,
+                    rrf_k: None,
+                    min_score: None,
+                    score_function: None,
+                    skip_ranking: false,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.results[0].id, "doc-3");
+
+        // The new shard count survives a restart
+        let reloaded = VectorBackend::new(dir.path()).unwrap();
+        reloaded
+            .initialize("test", &make_test_schema(2, 4))
+            .await
+            .unwrap();
+        assert_eq!(shard_doc_counts(&reloaded), vec![40]);
+    }
+
+    #[tokio::test]
+    async fn test_reshard_rejects_counts_that_do_not_divide() {
+        let dir = tempdir().unwrap();
+        let backend = VectorBackend::new(dir.path()).unwrap();
+        backend
+            .initialize("test", &make_test_schema(2, 4))
+            .await
+            .unwrap();
+
+        assert!(backend.split_shards("test", 3).await.is_err());
+        assert!(backend.split_shards("test", 2).await.is_err());
+        assert!(backend.shrink_shards("test", 3).await.is_err());
+        assert!(backend.shrink_shards("test", 0).await.is_err());
+        assert!(!backend.is_resharding("test"));
+    }
+
+    #[tokio::test]
+    async fn test_writes_rejected_while_resharding() {
+        let dir = tempdir().unwrap();
+        let backend = VectorBackend::new(dir.path()).unwrap();
+        backend
+            .initialize("test", &make_test_schema(2, 4))
+            .await
+            .unwrap();
+        backend.index("test", vector_docs(2)).await.unwrap();
+
+        backend.resharding.write().insert("test".to_string());
+        let err = backend.index("test", vector_docs(3)).await.unwrap_err();
+        assert!(matches!(err, crate::error::Error::ReadOnly(_)));
+        assert!(backend
+            .delete("test", vec!["doc-0".to_string()])
+            .await
+            .is_err());
+        // Reads are still served
+        assert!(backend.get("test", "doc-0").await.unwrap().is_some());
+    }
+
     fn make_test_schema(num_shards: usize, dimension: usize) -> CollectionSchema {
         use crate::schema::types::*;
         use crate::storage::StorageConfig;
@@ -937,4 +1395,18 @@ mod tests {
             ilm_policy: None,
         }
     }
+
+    #[test]
+    fn test_embedding_source_text_multi_valued() {
+        assert_eq!(
+            embedding_source_text(&serde_json::json!("hello")),
+            Some("hello".to_string())
+        );
+        assert_eq!(
+            embedding_source_text(&serde_json::json!(["first", 2, "second"])),
+            Some("first\nsecond".to_string())
+        );
+        assert_eq!(embedding_source_text(&serde_json::json!([])), None);
+        assert_eq!(embedding_source_text(&serde_json::json!(42)), None);
+    }
 }
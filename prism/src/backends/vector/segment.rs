@@ -30,7 +30,7 @@ pub struct VectorSegment {
 }
 
 /// Serializable segment state for persistence.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PersistedSegment {
     pub id: SegmentId,
     pub dimensions: usize,
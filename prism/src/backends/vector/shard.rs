@@ -28,7 +28,7 @@ pub struct VectorShard {
 }
 
 /// Serializable shard state.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PersistedShard {
     pub shard_id: u32,
     pub dimensions: usize,
@@ -183,6 +183,13 @@ impl VectorShard {
         active + sealed
     }
 
+    /// IDs of all live documents across all segments.
+    pub fn live_ids(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.active_segment)
+            .chain(&self.sealed_segments)
+            .flat_map(|seg| seg.id_to_key.keys())
+    }
+
     /// Total document count (including tombstoned) across all segments.
     pub fn total_count(&self) -> u64 {
         let active = self.active_segment.total_count();
@@ -1,15 +1,19 @@
 use crate::backends::{
-    BackendStats, Document, HybridSearchCoordinator, Query, SearchBackend, SearchResults,
-    SearchResultsWithAggs, ShardedGraphBackend, TextBackend, VectorBackend,
+    BackendStats, Document, HybridSearchCoordinator, Query, ReshardResult, SearchBackend,
+    SearchResults, SearchResultsWithAggs, ShardedGraphBackend, TextBackend, VectorBackend,
+};
+use crate::collection::point_in_time::{
+    HitPosition, PointInTime, PointInTimeRegistry, PointInTimeResults,
 };
 use crate::ranking::reranker::{RerankOptions, Reranker};
-use crate::schema::{CollectionSchema, SchemaLoader};
+use crate::schema::{CollectionSchema, SchemaLoader, TextField};
 use crate::{Error, Result};
 use parking_lot::RwLock;
 use prism_storage::SegmentStorage;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 pub struct CollectionManager {
     schemas: RwLock<HashMap<String, CollectionSchema>>,
@@ -20,6 +24,7 @@ pub struct CollectionManager {
     vector_backend: Arc<VectorBackend>,
     graph_storage: Option<Arc<dyn SegmentStorage>>,
     schemas_dir: PathBuf,
+    points_in_time: PointInTimeRegistry,
 }
 
 impl CollectionManager {
@@ -75,6 +80,7 @@ impl CollectionManager {
             vector_backend: vector_backend.clone(),
             graph_storage,
             schemas_dir: schemas_dir_path,
+            points_in_time: PointInTimeRegistry::default(),
         })
     }
 
@@ -466,6 +472,35 @@ impl CollectionManager {
         Ok(None)
     }
 
+    /// IDs of every document in a collection, sorted.
+    pub fn document_ids(&self, collection: &str) -> Result<Vec<String>> {
+        let schema = self
+            .get_schema(collection)
+            .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
+        let mut ids = HashSet::new();
+        if schema.backends.text.is_some() {
+            ids.extend(self.text_backend.document_ids(collection)?);
+        }
+        if schema.backends.vector.is_some() {
+            ids.extend(self.vector_backend.document_ids(collection)?);
+        }
+        let mut ids: Vec<String> = ids.into_iter().collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Highest `_seq_no` stored in a collection; `None` for collections
+    /// without a text backend or documents written with one.
+    pub fn max_seq_no(&self, collection: &str) -> Result<Option<u64>> {
+        let schema = self
+            .get_schema(collection)
+            .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
+        if schema.backends.text.is_none() {
+            return Ok(None);
+        }
+        self.text_backend.max_seq_no(collection)
+    }
+
     pub async fn delete(&self, collection: &str, ids: Vec<String>) -> Result<()> {
         let (backend, has_text) = {
             let schemas = self.schemas.read();
@@ -631,6 +666,119 @@ impl CollectionManager {
         Ok(())
     }
 
+    /// Remove a collection and delete its text and vector data and schema file.
+    ///
+    /// Unlike [`CollectionManager::remove_collection`], adding a collection
+    /// with the same name afterwards starts empty.
+    pub async fn delete_collection(&self, name: &str) -> Result<()> {
+        let schema = self
+            .get_schema(name)
+            .ok_or_else(|| Error::CollectionNotFound(name.to_string()))?;
+
+        self.remove_collection(name).await?;
+        if schema.backends.text.is_some() {
+            self.text_backend.purge_collection(name).await?;
+        }
+        if schema.backends.vector.is_some() {
+            self.vector_backend.purge_collection(name).await?;
+        }
+        self.remove_schema_file(name)?;
+
+        tracing::info!("Collection '{}' deleted", name);
+        Ok(())
+    }
+
+    /// Add text fields to a collection's schema.
+    ///
+    /// Fields that already exist with the same type are skipped; a type change
+    /// is rejected. When anything is added the text index is rebuilt (see
+    /// [`TextBackend::rebuild_collection`]) and the schema persisted. Returns
+    /// the names of the added fields.
+    pub async fn add_text_fields(&self, name: &str, fields: Vec<TextField>) -> Result<Vec<String>> {
+        let mut schema = self
+            .get_schema(name)
+            .ok_or_else(|| Error::CollectionNotFound(name.to_string()))?;
+        let text =
+            schema.backends.text.as_mut().ok_or_else(|| {
+                Error::Schema(format!("Collection '{}' has no text backend", name))
+            })?;
+
+        let mut added = Vec::new();
+        for field in fields {
+            match text.fields.iter().find(|f| f.name == field.name) {
+                Some(existing) if existing.field_type != field.field_type => {
+                    return Err(Error::Schema(format!(
+                        "Field '{}' already exists with type {:?}",
+                        field.name, existing.field_type
+                    )));
+                }
+                Some(_) => {}
+                None => {
+                    added.push(field.name.clone());
+                    text.fields.push(field);
+                }
+            }
+        }
+        if added.is_empty() {
+            return Ok(added);
+        }
+
+        let reindexed = self.text_backend.rebuild_collection(name, &schema).await?;
+        self.schemas
+            .write()
+            .insert(name.to_string(), schema.clone());
+        self.persist_schema(&schema)?;
+
+        tracing::info!(
+            "Added fields {:?} to '{}' ({} documents re-indexed)",
+            added,
+            name,
+            reindexed
+        );
+        Ok(added)
+    }
+
+    /// Split a collection's vector shards into `num_shards`.
+    ///
+    /// Text indexes are not sharded on a node, so only the vector shards
+    /// change here; in a cluster the cluster layer splits whole shards. Writes
+    /// are rejected while the new shards are built and the new count is
+    /// persisted in the schema. See [`VectorBackend::split_shards`].
+    pub async fn split_shards(&self, name: &str, num_shards: usize) -> Result<ReshardResult> {
+        self.reshard(name, num_shards, true).await
+    }
+
+    /// Shrink a collection's vector shards into `num_shards`.
+    ///
+    /// See [`CollectionManager::split_shards`] and
+    /// [`VectorBackend::shrink_shards`].
+    pub async fn shrink_shards(&self, name: &str, num_shards: usize) -> Result<ReshardResult> {
+        self.reshard(name, num_shards, false).await
+    }
+
+    async fn reshard(&self, name: &str, num_shards: usize, split: bool) -> Result<ReshardResult> {
+        let mut schema = self
+            .get_schema(name)
+            .ok_or_else(|| Error::CollectionNotFound(name.to_string()))?;
+        let vector =
+            schema.backends.vector.as_mut().ok_or_else(|| {
+                Error::Schema(format!("Collection '{}' has no vector backend", name))
+            })?;
+
+        let result = if split {
+            self.vector_backend.split_shards(name, num_shards).await?
+        } else {
+            self.vector_backend.shrink_shards(name, num_shards).await?
+        };
+
+        vector.num_shards = num_shards;
+        self.schemas
+            .write()
+            .insert(name.to_string(), schema.clone());
+        self.persist_schema(&schema)?;
+        Ok(result)
+    }
+
     /// Add a collection to the running server from a schema.
     ///
     /// Lints the schema, creates backend routing, and initializes indexes.
@@ -874,6 +1022,126 @@ impl CollectionManager {
         Ok(merged)
     }
 
+    /// Return which of `ids` match a text query string in a collection.
+    ///
+    /// With `point_in_time`, the query runs against its pinned text snapshot.
+    pub fn filter_ids(
+        &self,
+        collection: &str,
+        ids: &[String],
+        query_string: &str,
+        point_in_time: Option<&str>,
+    ) -> Result<HashSet<String>> {
+        let has_text = {
+            let schemas = self.schemas.read();
+            let schema = schemas
+                .get(collection)
+                .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
+            schema.backends.text.is_some()
+        };
+        if !has_text {
+            if query_string.trim() == "*" {
+                return Ok(ids.iter().cloned().collect());
+            }
+            return Err(Error::InvalidQuery(format!(
+                "Collection '{}' has no text backend to filter on",
+                collection
+            )));
+        }
+
+        match point_in_time {
+            Some(id) => {
+                let pit = self.points_in_time.get(id, None)?;
+                let snapshot = pit.text(collection).ok_or_else(|| {
+                    Error::InvalidQuery(format!(
+                        "Collection '{}' is not part of point in time",
+                        collection
+                    ))
+                })?;
+                self.text_backend
+                    .filter_ids(collection, ids, query_string, Some(snapshot))
+            }
+            None => self
+                .text_backend
+                .filter_ids(collection, ids, query_string, None),
+        }
+    }
+
+    /// Approximate k-nearest-neighbour search over a collection's vectors.
+    ///
+    /// `num_candidates` neighbours are retrieved first; the optional text
+    /// `filter` is applied to them before the best `k` are kept, so filtered
+    /// searches may return fewer than `k` hits. Hits carry the document's
+    /// stored fields. With `point_in_time`, documents added after it was
+    /// opened are excluded and the filter runs against its text snapshot.
+    pub async fn knn_search(
+        &self,
+        collection: &str,
+        vector: &[f32],
+        k: usize,
+        num_candidates: usize,
+        filter: Option<&str>,
+        point_in_time: Option<&str>,
+    ) -> Result<SearchResults> {
+        let start = std::time::Instant::now();
+        let has_text = {
+            let schemas = self.schemas.read();
+            let schema = schemas
+                .get(collection)
+                .ok_or_else(|| Error::CollectionNotFound(collection.to_string()))?;
+            if schema.backends.vector.is_none() {
+                return Err(Error::InvalidQuery(format!(
+                    "Collection '{}' has no vector backend",
+                    collection
+                )));
+            }
+            schema.backends.text.is_some()
+        };
+
+        let query = Query {
+            query_string: serde_json::to_string(vector).unwrap_or_default(),
+            fields: vec![],
+            limit: num_candidates.max(k),
+            offset: 0,
+            merge_strategy: None,
+            text_weight: None,
+            vector_weight: None,
+            highlight: None,
+            rrf_k: None,
+            min_score: None,
+            score_function: None,
+            skip_ranking: false,
+        };
+        let mut results = self.vector_backend.search(collection, query).await?.results;
+
+        if let Some(id) = point_in_time {
+            let pit = self.points_in_time.get(id, None)?;
+            if let Some(snapshot) = pit.vector(collection) {
+                snapshot.retain_visible(&mut results);
+            }
+        }
+        if let Some(filter) = filter {
+            let ids: Vec<String> = results.iter().map(|r| r.id.clone()).collect();
+            let matching = self.filter_ids(collection, &ids, filter, point_in_time)?;
+            results.retain(|r| matching.contains(&r.id));
+        }
+        results.truncate(k);
+
+        if has_text {
+            for result in &mut results {
+                if let Some(doc) = self.text_backend.get(collection, &result.id).await? {
+                    result.fields = doc.fields;
+                }
+            }
+        }
+
+        Ok(SearchResults {
+            total: results.len(),
+            results,
+            latency_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+
     // ========================================================================
     // Index Inspection API (Issue #24)
     // ========================================================================
@@ -913,6 +1181,54 @@ impl CollectionManager {
         )
     }
 
+    /// Significant terms of `like` documents and texts, which a
+    /// more-like-this query over `fields` searches for
+    #[allow(clippy::too_many_arguments)]
+    pub fn more_like_this_terms(
+        &self,
+        collection: &str,
+        doc_ids: &[String],
+        like_texts: &[String],
+        fields: &[String],
+        min_term_freq: usize,
+        min_doc_freq: u64,
+        max_query_terms: usize,
+    ) -> Result<Vec<String>> {
+        self.text_backend.more_like_this_terms(
+            collection,
+            doc_ids,
+            like_texts,
+            fields,
+            min_term_freq,
+            min_doc_freq,
+            max_query_terms,
+        )
+    }
+
+    /// Terms of a field matching a multi-term query pattern
+    pub fn expand_terms(
+        &self,
+        collection: &str,
+        field: &str,
+        pattern: &crate::backends::text::TermPattern,
+        max_expansions: usize,
+    ) -> Result<Vec<String>> {
+        self.text_backend
+            .expand_terms(collection, field, pattern, max_expansions)
+    }
+
+    /// Search with hits ordered by `sort` instead of relevance
+    pub fn search_sorted(
+        &self,
+        collection: &str,
+        query: &Query,
+        aggregations: Vec<crate::aggregations::AggregationRequest>,
+        sort: &[crate::aggregations::TopHitsSort],
+    ) -> Result<crate::backends::SortedSearchResults> {
+        self.text_backend
+            .search_sorted(collection, query, &aggregations, sort)
+    }
+
     /// Suggest terms from the index using prefix matching and optional fuzzy correction.
     pub fn suggest(
         &self,
@@ -1137,6 +1453,170 @@ impl CollectionManager {
         })
     }
 
+    /// Open a point in time over collections (patterns allowed) and return
+    /// its ID.
+    ///
+    /// Text backends pin their current searcher and vector backends their set
+    /// of live documents, so searches through the point in time ignore writes
+    /// made after it was opened. It expires after `keep_alive` unless used.
+    pub fn open_point_in_time(
+        &self,
+        collections: &[String],
+        keep_alive: Duration,
+    ) -> Result<String> {
+        let expanded = self.expand_collection_patterns(collections);
+        if expanded.is_empty() {
+            return Err(Error::CollectionNotFound(collections.join(",")));
+        }
+
+        let mut text = HashMap::new();
+        let mut vector = HashMap::new();
+        for collection in &expanded {
+            let (has_text, has_vector) = {
+                let schemas = self.schemas.read();
+                let schema = schemas
+                    .get(collection)
+                    .ok_or_else(|| Error::CollectionNotFound(collection.clone()))?;
+                (
+                    schema.backends.text.is_some(),
+                    schema.backends.vector.is_some(),
+                )
+            };
+            if has_text {
+                text.insert(collection.clone(), self.text_backend.snapshot(collection)?);
+            }
+            if has_vector {
+                vector.insert(
+                    collection.clone(),
+                    self.vector_backend.snapshot(collection)?,
+                );
+            }
+        }
+
+        self.points_in_time
+            .open(PointInTime::new(expanded, text, vector), keep_alive)
+    }
+
+    /// Search the text snapshots of a point in time.
+    ///
+    /// Hits are merged across collections in [`HitPosition`] order, then
+    /// paged with the query's `offset` and `limit`, starting after `after`
+    /// when given. Because the snapshots do not change, the same query and
+    /// position always return the same page. `keep_alive` extends the point
+    /// in time's expiry.
+    pub fn search_point_in_time(
+        &self,
+        id: &str,
+        query: &Query,
+        aggregations: Vec<crate::aggregations::AggregationRequest>,
+        after: Option<&HitPosition>,
+        keep_alive: Option<Duration>,
+    ) -> Result<PointInTimeResults> {
+        let pit = self.points_in_time.get(id, keep_alive)?;
+        if !aggregations.is_empty() && pit.collections().len() > 1 {
+            return Err(Error::InvalidQuery(
+                "Aggregations are only supported for single-collection point-in-time searches"
+                    .to_string(),
+            ));
+        }
+
+        // Each collection returns its own top offset+limit hits; the page is
+        // cut after merging
+        let mut per_collection = query.clone();
+        per_collection.offset = 0;
+        per_collection.limit = query.offset + query.limit;
+
+        let mut hits = Vec::new();
+        let mut total = 0;
+        let mut aggregation_results = HashMap::new();
+        for (index, collection) in pit.collections().iter().enumerate() {
+            // Vector-only collections have nothing to match a text query against
+            let Some(snapshot) = pit.text(collection) else {
+                continue;
+            };
+            let found = self.text_backend.search_snapshot(
+                collection,
+                snapshot,
+                &per_collection,
+                &aggregations,
+                after.map(|after| after.cursor_for(index)).as_ref(),
+            )?;
+            total += found.total;
+            aggregation_results.extend(found.aggregations);
+            hits.extend(
+                found
+                    .results
+                    .into_iter()
+                    .zip(found.cursors)
+                    .map(|(r, cursor)| {
+                        (
+                            MultiSearchResult {
+                                id: r.id,
+                                collection: collection.clone(),
+                                score: r.score,
+                                fields: r.fields,
+                                highlight: r.highlight,
+                            },
+                            HitPosition {
+                                collection: index,
+                                cursor,
+                            },
+                        )
+                    }),
+            );
+        }
+
+        hits.sort_unstable_by(|a, b| a.1.order(&b.1));
+        let (results, positions) = hits
+            .into_iter()
+            .skip(query.offset)
+            .take(query.limit)
+            .unzip();
+
+        Ok(PointInTimeResults {
+            results,
+            positions,
+            total,
+            aggregations: aggregation_results,
+        })
+    }
+
+    /// Collections covered by an open point in time
+    pub fn point_in_time_collections(&self, id: &str) -> Result<Vec<String>> {
+        Ok(self.points_in_time.get(id, None)?.collections().to_vec())
+    }
+
+    /// Extend a point in time's expiry by `keep_alive` and return its
+    /// collections, for searches that do not go through
+    /// [`Self::search_point_in_time`]
+    pub fn touch_point_in_time(
+        &self,
+        id: &str,
+        keep_alive: Option<Duration>,
+    ) -> Result<Vec<String>> {
+        Ok(self
+            .points_in_time
+            .get(id, keep_alive)?
+            .collections()
+            .to_vec())
+    }
+
+    /// Close a point in time; returns false if it was unknown or expired
+    pub fn close_point_in_time(&self, id: &str) -> bool {
+        self.points_in_time.close(id)
+    }
+
+    /// Free points in time whose keep-alive elapsed and return how many
+    /// there were
+    pub fn reap_points_in_time(&self) -> usize {
+        self.points_in_time.purge_expired()
+    }
+
+    /// Close every open point in time and return how many were freed
+    pub fn close_all_points_in_time(&self) -> usize {
+        self.points_in_time.close_all()
+    }
+
     /// Merge results from multiple collections using Reciprocal Rank Fusion.
     /// Each result gets a `_collection` field indicating its source.
     fn merge_multi_collection_rrf(
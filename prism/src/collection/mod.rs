@@ -1,5 +1,7 @@
 pub mod detach;
 pub mod manager;
+pub mod point_in_time;
 
 pub use manager::CollectionManager;
 pub use manager::{MultiSearchResult, MultiSearchResults};
+pub use point_in_time::{HitPosition, PointInTime, PointInTimeRegistry, PointInTimeResults};
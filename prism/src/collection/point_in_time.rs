@@ -0,0 +1,309 @@
+//! Point-in-time search contexts.
+//!
+//! A point in time pins the current text searcher and vector document set of
+//! one or more collections, so paging through results stays consistent while
+//! indexing continues. Contexts expire once their keep-alive elapses without
+//! being used.
+
+use crate::aggregations::AggregationResult;
+use crate::backends::{SnapshotCursor, TextSnapshot, VectorSnapshot};
+use crate::collection::MultiSearchResult;
+use crate::{Error, Result};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Default cap on concurrently open contexts
+pub const DEFAULT_MAX_OPEN_CONTEXTS: usize = 500;
+
+/// Pinned view of a set of collections
+pub struct PointInTime {
+    collections: Vec<String>,
+    text: HashMap<String, TextSnapshot>,
+    vector: HashMap<String, VectorSnapshot>,
+}
+
+impl PointInTime {
+    pub fn new(
+        collections: Vec<String>,
+        text: HashMap<String, TextSnapshot>,
+        vector: HashMap<String, VectorSnapshot>,
+    ) -> Self {
+        Self {
+            collections,
+            text,
+            vector,
+        }
+    }
+
+    /// Collections covered by this point in time, in search order
+    pub fn collections(&self) -> &[String] {
+        &self.collections
+    }
+
+    pub fn text(&self, collection: &str) -> Option<&TextSnapshot> {
+        self.text.get(collection)
+    }
+
+    pub fn vector(&self, collection: &str) -> Option<&VectorSnapshot> {
+        self.vector.get(collection)
+    }
+}
+
+/// Where a hit sits in the order of a point-in-time search: descending
+/// score, then collection, then document address in the pinned snapshot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitPosition {
+    /// Index of the hit's collection in [`PointInTime::collections`]
+    pub collection: usize,
+    pub cursor: SnapshotCursor,
+}
+
+impl HitPosition {
+    /// Where a search of the `collection`th collection resumes to return
+    /// the hits after this one
+    pub fn cursor_for(&self, collection: usize) -> SnapshotCursor {
+        match collection.cmp(&self.collection) {
+            std::cmp::Ordering::Less => SnapshotCursor::below(self.cursor.score),
+            std::cmp::Ordering::Equal => self.cursor,
+            std::cmp::Ordering::Greater => SnapshotCursor::at_most(self.cursor.score),
+        }
+    }
+
+    /// Which of two hits comes first
+    pub fn order(&self, other: &Self) -> std::cmp::Ordering {
+        other
+            .cursor
+            .score
+            .total_cmp(&self.cursor.score)
+            .then(self.collection.cmp(&other.collection))
+            .then(self.cursor.segment_ord.cmp(&other.cursor.segment_ord))
+            .then(self.cursor.doc_id.cmp(&other.cursor.doc_id))
+    }
+}
+
+/// One page of hits from a point-in-time search
+#[derive(Debug, Clone)]
+pub struct PointInTimeResults {
+    /// Hits in [`HitPosition`] order
+    pub results: Vec<MultiSearchResult>,
+    /// Position of each hit, in the order of `results`; empty for hits
+    /// merged from other searches
+    pub positions: Vec<HitPosition>,
+    /// Matches across all collections in the point in time
+    pub total: u64,
+    /// Only computed for single-collection points in time
+    pub aggregations: HashMap<String, AggregationResult>,
+}
+
+struct Entry {
+    pit: Arc<PointInTime>,
+    expires_at: Instant,
+}
+
+/// Registry of open point-in-time contexts with keep-alive expiry.
+///
+/// Expired contexts are dropped whenever the registry is touched or
+/// [`Self::purge_expired`] runs, which releases their pinned segments.
+pub struct PointInTimeRegistry {
+    entries: Mutex<HashMap<String, Entry>>,
+    max_open: usize,
+}
+
+impl Default for PointInTimeRegistry {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_OPEN_CONTEXTS)
+    }
+}
+
+impl PointInTimeRegistry {
+    pub fn new(max_open: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_open,
+        }
+    }
+
+    /// Register a context and return its ID
+    pub fn open(&self, pit: PointInTime, keep_alive: Duration) -> Result<String> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock();
+        purge_expired(&mut entries, now);
+        if entries.len() >= self.max_open {
+            return Err(Error::Backend(format!(
+                "Trying to create too many point-in-time contexts; must be less than or equal to [{}]",
+                self.max_open
+            )));
+        }
+
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        entries.insert(
+            id.clone(),
+            Entry {
+                pit: Arc::new(pit),
+                expires_at: now + keep_alive,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Look up a live context, extending its expiry when `keep_alive` is given
+    pub fn get(&self, id: &str, keep_alive: Option<Duration>) -> Result<Arc<PointInTime>> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock();
+        purge_expired(&mut entries, now);
+        let entry = entries
+            .get_mut(id)
+            .ok_or_else(|| Error::SearchContextMissing(id.to_string()))?;
+        if let Some(keep_alive) = keep_alive {
+            entry.expires_at = now + keep_alive;
+        }
+        Ok(entry.pit.clone())
+    }
+
+    /// Close a context; returns false if it did not exist or had expired
+    pub fn close(&self, id: &str) -> bool {
+        let mut entries = self.entries.lock();
+        purge_expired(&mut entries, Instant::now());
+        entries.remove(id).is_some()
+    }
+
+    /// Close every open context and return how many were freed
+    pub fn close_all(&self) -> usize {
+        let mut entries = self.entries.lock();
+        purge_expired(&mut entries, Instant::now());
+        let freed = entries.len();
+        entries.clear();
+        freed
+    }
+
+    /// Drop expired contexts and return how many were freed
+    pub fn purge_expired(&self) -> usize {
+        let mut entries = self.entries.lock();
+        let open = entries.len();
+        purge_expired(&mut entries, Instant::now());
+        open - entries.len()
+    }
+
+    /// Number of open (unexpired) contexts
+    pub fn len(&self) -> usize {
+        let mut entries = self.entries.lock();
+        purge_expired(&mut entries, Instant::now());
+        entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn purge_expired(entries: &mut HashMap<String, Entry>, now: Instant) {
+    entries.retain(|_, entry| entry.expires_at > now);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_pit() -> PointInTime {
+        PointInTime::new(vec!["logs".to_string()], HashMap::new(), HashMap::new())
+    }
+
+    #[test]
+    fn test_open_get_close() {
+        let registry = PointInTimeRegistry::default();
+        let id = registry.open(empty_pit(), Duration::from_secs(60)).unwrap();
+
+        let pit = registry.get(&id, None).unwrap();
+        assert_eq!(pit.collections(), ["logs".to_string()]);
+        assert_eq!(registry.len(), 1);
+
+        assert!(registry.close(&id));
+        assert!(!registry.close(&id));
+        assert!(matches!(
+            registry.get(&id, None),
+            Err(Error::SearchContextMissing(_))
+        ));
+    }
+
+    #[test]
+    fn test_expired_contexts_are_dropped() {
+        let registry = PointInTimeRegistry::default();
+        let id = registry.open(empty_pit(), Duration::ZERO).unwrap();
+        assert!(registry.get(&id, None).is_err());
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_hit_positions_resume_across_collections() {
+        let position = |collection, score, doc_id| HitPosition {
+            collection,
+            cursor: SnapshotCursor {
+                score,
+                segment_ord: 0,
+                doc_id,
+            },
+        };
+        let mut hits = [
+            position(1, 1.0, 0),
+            position(0, 1.0, 5),
+            position(0, 2.0, 9),
+            position(0, 1.0, 2),
+        ];
+        hits.sort_by(HitPosition::order);
+        assert_eq!(
+            hits,
+            [
+                position(0, 2.0, 9),
+                position(0, 1.0, 2),
+                position(0, 1.0, 5),
+                position(1, 1.0, 0),
+            ]
+        );
+
+        // After the second hit, an earlier collection only has lower scores
+        // left and a later one every hit with an equal score
+        let after = hits[1];
+        assert_eq!(after.cursor_for(0), after.cursor);
+        assert_eq!(after.cursor_for(1), SnapshotCursor::at_most(1.0));
+        let before = position(1, 1.0, 0);
+        assert_eq!(before.cursor_for(0), SnapshotCursor::below(1.0));
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let registry = PointInTimeRegistry::default();
+        for _ in 0..2 {
+            registry
+                .open(empty_pit(), Duration::from_millis(20))
+                .unwrap();
+        }
+        let id = registry.open(empty_pit(), Duration::from_secs(60)).unwrap();
+        std::thread::sleep(Duration::from_millis(40));
+        assert_eq!(registry.purge_expired(), 2);
+        assert_eq!(registry.purge_expired(), 0);
+        assert!(registry.get(&id, None).is_ok());
+    }
+
+    #[test]
+    fn test_get_extends_keep_alive() {
+        let registry = PointInTimeRegistry::default();
+        let id = registry
+            .open(empty_pit(), Duration::from_millis(20))
+            .unwrap();
+        registry.get(&id, Some(Duration::from_secs(60))).unwrap();
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(registry.get(&id, None).is_ok());
+    }
+
+    #[test]
+    fn test_max_open_contexts() {
+        let registry = PointInTimeRegistry::new(2);
+        registry.open(empty_pit(), Duration::from_secs(60)).unwrap();
+        registry.open(empty_pit(), Duration::from_secs(60)).unwrap();
+        assert!(registry.open(empty_pit(), Duration::from_secs(60)).is_err());
+        assert_eq!(registry.close_all(), 2);
+        assert!(registry.open(empty_pit(), Duration::from_secs(60)).is_ok());
+    }
+}
@@ -64,6 +64,9 @@ pub enum Error {
 
     #[error("Import error: {0}")]
     Import(String),
+
+    #[error("No search context found for id [{0}]")]
+    SearchContextMissing(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -135,6 +135,20 @@ impl AliasState {
     }
 }
 
+/// A single change applied by [`AliasManager::apply`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AliasChange {
+    /// Point an alias at a target, creating the alias with the given type if
+    /// it does not exist. Write aliases replace their single target.
+    Add {
+        alias: String,
+        target: String,
+        alias_type: AliasType,
+    },
+    /// Remove a target from an alias; an alias left without targets is deleted
+    Remove { alias: String, target: String },
+}
+
 /// Manages aliases for ILM
 pub struct AliasManager {
     /// Alias state
@@ -377,6 +391,70 @@ impl AliasManager {
         self.save().await
     }
 
+    /// Apply a batch of alias changes atomically.
+    ///
+    /// If any removal names a missing alias or target, nothing is changed.
+    pub async fn apply(&self, changes: Vec<AliasChange>) -> Result<()> {
+        let mut state = self.state.write().await;
+        let mut aliases = state.aliases.clone();
+
+        for change in changes {
+            match change {
+                AliasChange::Add {
+                    alias,
+                    target,
+                    alias_type,
+                } => match aliases.get_mut(&alias) {
+                    Some(existing) if existing.alias_type == AliasType::Write => {
+                        existing.set_target(target)
+                    }
+                    Some(existing) => existing.add_target(target),
+                    None => {
+                        let created = match alias_type {
+                            AliasType::Write => IndexAlias::write(&alias, target),
+                            AliasType::Read => IndexAlias::read(&alias, vec![target]),
+                        };
+                        aliases.insert(alias, created);
+                    }
+                },
+                AliasChange::Remove { alias, target } => {
+                    let existing = aliases
+                        .get_mut(&alias)
+                        .ok_or_else(|| Error::AliasNotFound(alias.clone()))?;
+                    if !existing.remove_target(&target) {
+                        return Err(Error::AliasNotFound(format!("{} -> {}", alias, target)));
+                    }
+                    if existing.targets.is_empty() {
+                        aliases.remove(&alias);
+                    }
+                }
+            }
+        }
+
+        state.aliases = aliases;
+        drop(state);
+        self.save().await
+    }
+
+    /// Remove a target from every alias, deleting aliases left empty.
+    /// Returns the number of aliases that pointed at the target.
+    pub async fn remove_target_from_all(&self, target: &str) -> Result<usize> {
+        let mut state = self.state.write().await;
+        let mut removed = 0;
+        state.aliases.retain(|_, alias| {
+            if alias.remove_target(target) {
+                removed += 1;
+            }
+            !alias.targets.is_empty()
+        });
+        drop(state);
+
+        if removed > 0 {
+            self.save().await?;
+        }
+        Ok(removed)
+    }
+
     /// Save state to disk
     async fn save(&self) -> Result<()> {
         let mut state = self.state.write().await;
@@ -916,4 +994,64 @@ mod tests {
         let result = manager.resolve_write_target("nonexistent").await;
         assert!(result.is_err());
     }
+
+    // ========================================================================
+    // apply / remove_target_from_all
+    // ========================================================================
+
+    #[tokio::test]
+    async fn test_apply_creates_and_removes_aliases() {
+        let temp = TempDir::new().unwrap();
+        let manager = AliasManager::new(temp.path()).await.unwrap();
+
+        manager
+            .apply(vec![
+                AliasChange::Add {
+                    alias: "logs".to_string(),
+                    target: "logs-1".to_string(),
+                    alias_type: AliasType::Read,
+                },
+                AliasChange::Add {
+                    alias: "logs".to_string(),
+                    target: "logs-2".to_string(),
+                    alias_type: AliasType::Read,
+                },
+                AliasChange::Add {
+                    alias: "logs-current".to_string(),
+                    target: "logs-2".to_string(),
+                    alias_type: AliasType::Write,
+                },
+            ])
+            .await
+            .unwrap();
+        assert_eq!(manager.resolve("logs").await.unwrap(), ["logs-1", "logs-2"]);
+        assert_eq!(
+            manager.get("logs-current").await.unwrap().write_target(),
+            Some("logs-2")
+        );
+
+        // A failing removal leaves everything untouched
+        let result = manager
+            .apply(vec![
+                AliasChange::Remove {
+                    alias: "logs".to_string(),
+                    target: "logs-1".to_string(),
+                },
+                AliasChange::Remove {
+                    alias: "missing".to_string(),
+                    target: "logs-1".to_string(),
+                },
+            ])
+            .await;
+        assert!(matches!(result, Err(Error::AliasNotFound(_))));
+        assert_eq!(manager.resolve("logs").await.unwrap().len(), 2);
+
+        assert_eq!(manager.remove_target_from_all("logs-2").await.unwrap(), 2);
+        assert_eq!(manager.resolve("logs").await.unwrap(), ["logs-1"]);
+        assert!(!manager.is_alias("logs-current").await);
+
+        // State survives a reload
+        let reloaded = AliasManager::new(temp.path()).await.unwrap();
+        assert_eq!(reloaded.resolve("logs").await.unwrap(), ["logs-1"]);
+    }
 }
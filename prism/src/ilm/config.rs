@@ -132,7 +132,7 @@ pub struct PhaseConfigEntry {
     #[serde(default)]
     pub force_merge_segments: Option<usize>,
 
-    /// Shrink to this number of shards (future)
+    /// Shrink to this number of shards
     #[serde(default)]
     pub shrink_shards: Option<usize>,
 }
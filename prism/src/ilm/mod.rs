@@ -31,10 +31,10 @@ pub mod rollover;
 pub mod transition;
 pub mod types;
 
-pub use alias::{AliasManager, AliasType, IndexAlias};
+pub use alias::{AliasChange, AliasManager, AliasType, IndexAlias};
 pub use config::{IlmConfig, IlmPolicyConfig};
 pub use rollover::{RolloverResult, RolloverService};
-pub use transition::{TransitionAction, TransitionResult, TransitionService};
+pub use transition::{ShardShrinker, TransitionAction, TransitionResult, TransitionService};
 pub use types::{
     IlmPolicy, IlmState, ManagedIndex, Phase, PhaseConfig, RolloverConditions, StorageTier,
 };
@@ -445,6 +445,11 @@ impl IlmManager {
             .await
     }
 
+    /// Replace how the shrink phase action shrinks indexes
+    pub fn set_shard_shrinker(&self, shrinker: Arc<dyn ShardShrinker>) {
+        self.transition_service.set_shrinker(shrinker);
+    }
+
     /// Get alias manager
     pub fn alias_manager(&self) -> &Arc<AliasManager> {
         &self.alias_manager
@@ -5,9 +5,37 @@
 use super::types::{IlmPolicy, IlmState, Phase, StorageTier};
 use crate::collection::CollectionManager;
 use crate::{Error, Result};
+use async_trait::async_trait;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Shrinks the shards of an index for the shrink phase action.
+///
+/// Defaults to the local collection manager; a cluster swaps in one that
+/// shrinks the collection's cluster shards.
+#[async_trait]
+pub trait ShardShrinker: Send + Sync {
+    /// Shrink a collection to `num_shards` shards.
+    ///
+    /// Returns false when it already has that many shards or fewer.
+    async fn shrink(&self, collection: &str, num_shards: usize) -> Result<bool>;
+}
+
+#[async_trait]
+impl ShardShrinker for CollectionManager {
+    async fn shrink(&self, collection: &str, num_shards: usize) -> Result<bool> {
+        let current = self
+            .get_schema(collection)
+            .and_then(|schema| schema.backends.vector)
+            .map_or(1, |vector| vector.num_shards);
+        if current <= num_shards {
+            return Ok(false);
+        }
+        self.shrink_shards(collection, num_shards).await?;
+        Ok(true)
+    }
+}
+
 /// Result of a phase transition check
 #[derive(Debug)]
 pub struct TransitionCheckResult {
@@ -50,6 +78,8 @@ pub enum TransitionAction {
     MigrateStorage { from: StorageTier, to: StorageTier },
     /// Force merged segments
     ForceMerge { target_segments: usize },
+    /// Shrunk the index to fewer shards
+    Shrink { target_shards: usize },
     /// Marked for deletion
     MarkForDeletion,
 }
@@ -62,12 +92,25 @@ pub struct TransitionService {
 
     /// ILM state
     state: Arc<RwLock<IlmState>>,
+
+    /// Performs the shrink action
+    shrinker: parking_lot::RwLock<Arc<dyn ShardShrinker>>,
 }
 
 impl TransitionService {
     /// Create a new transition service
     pub fn new(manager: Arc<CollectionManager>, state: Arc<RwLock<IlmState>>) -> Self {
-        Self { manager, state }
+        let shrinker: Arc<dyn ShardShrinker> = manager.clone();
+        Self {
+            manager,
+            state,
+            shrinker: parking_lot::RwLock::new(shrinker),
+        }
+    }
+
+    /// Replace how indexes are shrunk
+    pub fn set_shrinker(&self, shrinker: Arc<dyn ShardShrinker>) {
+        *self.shrinker.write() = shrinker;
     }
 
     /// Check if an index should transition to a new phase
@@ -163,6 +206,22 @@ impl TransitionService {
                     target_segments
                 );
             }
+
+            // Handle shrink; the phase is retried on the next cycle if it fails
+            if let Some(target_shards) = config.shrink_shards {
+                let shrinker = self.shrinker.read().clone();
+                match shrinker.shrink(collection_name, target_shards).await {
+                    Ok(true) => {
+                        actions.push(TransitionAction::Shrink { target_shards });
+                        tracing::info!("Shrunk '{}' to {} shards", collection_name, target_shards);
+                    }
+                    Ok(false) => {}
+                    Err(e) => {
+                        managed.set_error(format!("Shrink failed: {}", e));
+                        return Err(e);
+                    }
+                }
+            }
         }
 
         // Handle delete phase
@@ -315,6 +374,18 @@ backends:
 "#,
         )
         .unwrap();
+        std::fs::write(
+            schemas_dir.join("vectors.yaml"),
+            r#"
+collection: vectors
+backends:
+  vector:
+    embedding_field: embedding
+    dimension: 4
+    num_shards: 4
+"#,
+        )
+        .unwrap();
 
         let text_backend = Arc::new(TextBackend::new(&data_dir).unwrap());
         let vector_backend = Arc::new(VectorBackend::new(&data_dir).unwrap());
@@ -430,4 +501,44 @@ backends:
         assert_eq!(idx.phase, Phase::Warm);
         assert!(idx.readonly);
     }
+
+    #[tokio::test]
+    async fn test_transition_shrinks_shards() {
+        use crate::backends::Document;
+
+        let temp = TempDir::new().unwrap();
+        let (manager, state) = create_test_setup(&temp).await;
+        let docs = (0..20)
+            .map(|i| Document {
+                id: format!("doc-{}", i),
+                fields: HashMap::from([(
+                    "embedding".to_string(),
+                    serde_json::json!([i as f32, 1.0, 0.0, 0.0]),
+                )]),
+            })
+            .collect();
+        manager.index("vectors", docs).await.unwrap();
+        {
+            let mut s = state.write().await;
+            s.upsert(ManagedIndex::new("vectors", "vectors", "test-policy", 1));
+        }
+
+        let service = TransitionService::new(manager.clone(), state);
+        let mut policy = create_test_policy();
+        policy.phases.get_mut(&Phase::Warm).unwrap().shrink_shards = Some(1);
+
+        let result = service
+            .force_transition("vectors", Phase::Warm, &policy)
+            .await
+            .unwrap();
+        assert!(result
+            .actions
+            .iter()
+            .any(|a| matches!(a, TransitionAction::Shrink { target_shards: 1 })));
+
+        let schema = manager.get_schema("vectors").unwrap();
+        assert_eq!(schema.backends.vector.unwrap().num_shards, 1);
+        assert_eq!(manager.stats("vectors").await.unwrap().document_count, 20);
+        assert!(manager.get("vectors", "doc-7").await.unwrap().is_some());
+    }
 }
@@ -226,7 +226,7 @@ pub struct PhaseConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub force_merge_segments: Option<usize>,
 
-    /// Shrink index to this many shards (future feature)
+    /// Shrink index to this many shards
     #[serde(skip_serializing_if = "Option::is_none")]
     pub shrink_shards: Option<usize>,
 }
@@ -77,6 +77,8 @@ impl SchemaDiscoverer {
                 indexed: field_entry.is_indexed(),
                 tokenizer: None,
                 tokenizer_options: None,
+                multi: false,
+                fast: false,
             });
         }
 
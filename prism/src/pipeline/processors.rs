@@ -114,6 +114,286 @@ impl Processor for RenameProcessor {
     }
 }
 
+/// Convert a string field to uppercase.
+pub struct UppercaseProcessor {
+    pub field: String,
+}
+
+impl Processor for UppercaseProcessor {
+    fn name(&self) -> &str {
+        "uppercase"
+    }
+
+    fn process(&self, doc: &mut Document) -> Result<()> {
+        let upper = string_field(doc, "uppercase", &self.field)?.to_uppercase();
+        doc.fields
+            .insert(self.field.clone(), serde_json::Value::String(upper));
+        Ok(())
+    }
+}
+
+/// Remove leading and trailing whitespace from a string field.
+pub struct TrimProcessor {
+    pub field: String,
+}
+
+impl Processor for TrimProcessor {
+    fn name(&self) -> &str {
+        "trim"
+    }
+
+    fn process(&self, doc: &mut Document) -> Result<()> {
+        let trimmed = string_field(doc, "trim", &self.field)?.trim().to_string();
+        doc.fields
+            .insert(self.field.clone(), serde_json::Value::String(trimmed));
+        Ok(())
+    }
+}
+
+/// Append values to a field, turning it into an array. A missing field
+/// starts empty and a single value becomes the first element.
+pub struct AppendProcessor {
+    pub field: String,
+    pub values: Vec<serde_json::Value>,
+}
+
+impl Processor for AppendProcessor {
+    fn name(&self) -> &str {
+        "append"
+    }
+
+    fn process(&self, doc: &mut Document) -> Result<()> {
+        let mut items = match doc.fields.remove(&self.field) {
+            Some(serde_json::Value::Array(items)) => items,
+            Some(value) => vec![value],
+            None => Vec::new(),
+        };
+        items.extend(self.values.iter().cloned());
+        doc.fields
+            .insert(self.field.clone(), serde_json::Value::Array(items));
+        Ok(())
+    }
+}
+
+/// Target type of a [`ConvertProcessor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvertTarget {
+    Integer,
+    Float,
+    Boolean,
+    String,
+    /// Integer, float or boolean when the string parses as one
+    Auto,
+}
+
+impl ConvertTarget {
+    /// Parse a target name; `long` and `double` are accepted as aliases.
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "integer" | "long" => Ok(Self::Integer),
+            "float" | "double" => Ok(Self::Float),
+            "boolean" => Ok(Self::Boolean),
+            "string" => Ok(Self::String),
+            "auto" => Ok(Self::Auto),
+            other => Err(Error::Config(format!("convert: unknown type '{}'", other))),
+        }
+    }
+
+    fn convert(&self, value: &serde_json::Value) -> Option<serde_json::Value> {
+        use serde_json::Value;
+
+        let text = match value {
+            Value::String(s) => s.trim().to_string(),
+            Value::Null | Value::Array(_) | Value::Object(_) => return None,
+            other => other.to_string(),
+        };
+        match self {
+            Self::Integer => text.parse::<i64>().ok().map(Value::from),
+            Self::Float => text.parse::<f64>().ok().map(Value::from),
+            Self::Boolean => match text.to_lowercase().as_str() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            Self::String => Some(Value::String(match value {
+                Value::String(s) => s.clone(),
+                _ => text,
+            })),
+            Self::Auto => Self::Integer
+                .convert(value)
+                .or_else(|| Self::Float.convert(value))
+                .or_else(|| Self::Boolean.convert(value))
+                .or_else(|| Some(value.clone())),
+        }
+    }
+}
+
+/// Convert a field to another type. Arrays are converted element-wise.
+pub struct ConvertProcessor {
+    pub field: String,
+    pub target: ConvertTarget,
+}
+
+impl Processor for ConvertProcessor {
+    fn name(&self) -> &str {
+        "convert"
+    }
+
+    fn process(&self, doc: &mut Document) -> Result<()> {
+        let val = doc
+            .fields
+            .get(&self.field)
+            .ok_or_else(|| Error::Backend(format!("convert: field '{}' not found", self.field)))?;
+        let fail = |v: &serde_json::Value| {
+            Error::Backend(format!(
+                "convert: field '{}' value {} cannot be converted to {:?}",
+                self.field, v, self.target
+            ))
+        };
+        let converted = match val {
+            serde_json::Value::Array(items) => serde_json::Value::Array(
+                items
+                    .iter()
+                    .map(|v| self.target.convert(v).ok_or_else(|| fail(v)))
+                    .collect::<Result<_>>()?,
+            ),
+            v => self.target.convert(v).ok_or_else(|| fail(v))?,
+        };
+        doc.fields.insert(self.field.clone(), converted);
+        Ok(())
+    }
+}
+
+/// Split a string field into an array on a separator pattern.
+pub struct SplitProcessor {
+    pub field: String,
+    pub separator: regex::Regex,
+}
+
+impl Processor for SplitProcessor {
+    fn name(&self) -> &str {
+        "split"
+    }
+
+    fn process(&self, doc: &mut Document) -> Result<()> {
+        let parts = self
+            .separator
+            .split(string_field(doc, "split", &self.field)?)
+            .map(|part| serde_json::Value::String(part.to_string()))
+            .collect();
+        doc.fields
+            .insert(self.field.clone(), serde_json::Value::Array(parts));
+        Ok(())
+    }
+}
+
+/// Join the elements of an array field into a string.
+pub struct JoinProcessor {
+    pub field: String,
+    pub separator: String,
+}
+
+impl Processor for JoinProcessor {
+    fn name(&self) -> &str {
+        "join"
+    }
+
+    fn process(&self, doc: &mut Document) -> Result<()> {
+        let val = doc
+            .fields
+            .get(&self.field)
+            .ok_or_else(|| Error::Backend(format!("join: field '{}' not found", self.field)))?;
+        let items = val.as_array().ok_or_else(|| {
+            Error::Backend(format!("join: field '{}' is not an array", self.field))
+        })?;
+        let joined = items
+            .iter()
+            .map(|v| match v {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(&self.separator);
+        doc.fields
+            .insert(self.field.clone(), serde_json::Value::String(joined));
+        Ok(())
+    }
+}
+
+/// Replace every match of a pattern in a string field.
+pub struct GsubProcessor {
+    pub field: String,
+    pub pattern: regex::Regex,
+    pub replacement: String,
+}
+
+impl Processor for GsubProcessor {
+    fn name(&self) -> &str {
+        "gsub"
+    }
+
+    fn process(&self, doc: &mut Document) -> Result<()> {
+        let replaced = self
+            .pattern
+            .replace_all(
+                string_field(doc, "gsub", &self.field)?,
+                self.replacement.as_str(),
+            )
+            .into_owned();
+        doc.fields
+            .insert(self.field.clone(), serde_json::Value::String(replaced));
+        Ok(())
+    }
+}
+
+/// Run a processor only when its field is present.
+pub struct IgnoreMissing {
+    pub field: String,
+    pub processor: Box<dyn Processor>,
+}
+
+impl Processor for IgnoreMissing {
+    fn name(&self) -> &str {
+        self.processor.name()
+    }
+
+    fn process(&self, doc: &mut Document) -> Result<()> {
+        if doc.fields.contains_key(&self.field) {
+            self.processor.process(doc)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run a processor and keep going if it fails. The document is left as it
+/// was before the processor ran.
+pub struct IgnoreFailure {
+    pub processor: Box<dyn Processor>,
+}
+
+impl Processor for IgnoreFailure {
+    fn name(&self) -> &str {
+        self.processor.name()
+    }
+
+    fn process(&self, doc: &mut Document) -> Result<()> {
+        let before = doc.fields.clone();
+        if self.processor.process(doc).is_err() {
+            doc.fields = before;
+        }
+        Ok(())
+    }
+}
+
+fn string_field<'a>(doc: &'a Document, processor: &str, field: &str) -> Result<&'a str> {
+    let val = doc
+        .fields
+        .get(field)
+        .ok_or_else(|| Error::Backend(format!("{}: field '{}' not found", processor, field)))?;
+    val.as_str()
+        .ok_or_else(|| Error::Backend(format!("{}: field '{}' is not a string", processor, field)))
+}
+
 /// Simple HTML tag stripping using a state machine.
 fn strip_html(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
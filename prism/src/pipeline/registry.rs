@@ -3,9 +3,11 @@ use super::Processor;
 use crate::backends::Document;
 use crate::error::Error;
 use crate::Result;
+use parking_lot::RwLock;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
 
 /// A pipeline is a named, ordered list of processors.
 pub struct Pipeline {
@@ -26,8 +28,11 @@ impl Pipeline {
 }
 
 /// Registry holding all loaded pipelines.
+///
+/// Pipelines loaded from YAML can be joined, replaced or removed at runtime,
+/// e.g. by the Elasticsearch ingest API.
 pub struct PipelineRegistry {
-    pipelines: HashMap<String, Pipeline>,
+    pipelines: RwLock<HashMap<String, Arc<Pipeline>>>,
 }
 
 impl PipelineRegistry {
@@ -36,7 +41,9 @@ impl PipelineRegistry {
         let mut pipelines = HashMap::new();
 
         if !dir.exists() {
-            return Ok(Self { pipelines });
+            return Ok(Self {
+                pipelines: RwLock::new(pipelines),
+            });
         }
 
         let entries = std::fs::read_dir(dir).map_err(|e| {
@@ -56,21 +63,36 @@ impl PipelineRegistry {
                 })?;
                 let def: PipelineDef = serde_yaml::from_str(&content)?;
                 let pipeline = def.into_pipeline()?;
-                pipelines.insert(pipeline.name.clone(), pipeline);
+                pipelines.insert(pipeline.name.clone(), Arc::new(pipeline));
             }
         }
 
-        Ok(Self { pipelines })
+        Ok(Self {
+            pipelines: RwLock::new(pipelines),
+        })
     }
 
     /// Get a pipeline by name.
-    pub fn get(&self, name: &str) -> Option<&Pipeline> {
-        self.pipelines.get(name)
+    pub fn get(&self, name: &str) -> Option<Arc<Pipeline>> {
+        self.pipelines.read().get(name).cloned()
+    }
+
+    /// Add a pipeline, replacing any pipeline with the same name.
+    pub fn insert(&self, pipeline: Pipeline) {
+        self.pipelines
+            .write()
+            .insert(pipeline.name.clone(), Arc::new(pipeline));
+    }
+
+    /// Remove a pipeline by name.
+    pub fn remove(&self, name: &str) -> Option<Arc<Pipeline>> {
+        self.pipelines.write().remove(name)
     }
 
     /// List all pipelines as (name, description, processor_count).
     pub fn list(&self) -> Vec<(String, String, usize)> {
         self.pipelines
+            .read()
             .values()
             .map(|p| (p.name.clone(), p.description.clone(), p.processors.len()))
             .collect()
@@ -79,7 +101,7 @@ impl PipelineRegistry {
     /// Create an empty registry.
     pub fn empty() -> Self {
         Self {
-            pipelines: HashMap::new(),
+            pipelines: RwLock::new(HashMap::new()),
         }
     }
 }
@@ -137,8 +159,67 @@ impl PipelineDef {
                     let to = get_string_field(params, "to")?;
                     Box::new(RenameProcessor { from, to })
                 }
+                "uppercase" => {
+                    let field = get_string_field(params, "field")?;
+                    Box::new(UppercaseProcessor { field })
+                }
+                "trim" => {
+                    let field = get_string_field(params, "field")?;
+                    Box::new(TrimProcessor { field })
+                }
+                "append" => {
+                    let field = get_string_field(params, "field")?;
+                    let values = match params.get("value") {
+                        Some(serde_yaml::Value::Sequence(values)) => values
+                            .iter()
+                            .map(serde_json::to_value)
+                            .collect::<std::result::Result<_, _>>()?,
+                        Some(value) => vec![serde_json::to_value(value)?],
+                        None => return Err(Error::Config("missing field 'value'".to_string())),
+                    };
+                    Box::new(AppendProcessor { field, values })
+                }
+                "convert" => {
+                    let field = get_string_field(params, "field")?;
+                    let target = ConvertTarget::parse(&get_string_field(params, "type")?)?;
+                    Box::new(ConvertProcessor { field, target })
+                }
+                "split" => {
+                    let field = get_string_field(params, "field")?;
+                    let separator = get_regex_field(params, "separator")?;
+                    Box::new(SplitProcessor { field, separator })
+                }
+                "join" => {
+                    let field = get_string_field(params, "field")?;
+                    let separator = get_string_field(params, "separator")?;
+                    Box::new(JoinProcessor { field, separator })
+                }
+                "gsub" => {
+                    let field = get_string_field(params, "field")?;
+                    let pattern = get_regex_field(params, "pattern")?;
+                    let replacement = get_string_field(params, "replacement")?;
+                    Box::new(GsubProcessor {
+                        field,
+                        pattern,
+                        replacement,
+                    })
+                }
                 other => return Err(Error::Config(format!("unknown processor type: {}", other))),
             };
+
+            // `ignore_missing` skips documents without the processor's field
+            let proc: Box<dyn Processor> =
+                match params.get("ignore_missing").and_then(|v| v.as_bool()) {
+                    Some(true) => {
+                        let field = get_string_field(params, "field")
+                            .or_else(|_| get_string_field(params, "from"))?;
+                        Box::new(IgnoreMissing {
+                            field,
+                            processor: proc,
+                        })
+                    }
+                    _ => proc,
+                };
             processors.push(proc);
         }
 
@@ -157,3 +238,9 @@ fn get_string_field(params: &serde_yaml::Value, field: &str) -> Result<String> {
         .map(|s| s.to_string())
         .ok_or_else(|| Error::Config(format!("missing or non-string field '{}'", field)))
 }
+
+fn get_regex_field(params: &serde_yaml::Value, field: &str) -> Result<regex::Regex> {
+    let pattern = get_string_field(params, field)?;
+    regex::Regex::new(&pattern)
+        .map_err(|e| Error::Config(format!("invalid pattern in field '{}': {}", field, e)))
+}
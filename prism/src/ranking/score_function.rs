@@ -1,7 +1,9 @@
 //! Score function reranker — expression-based scoring
 //!
 //! Evaluates simple arithmetic expressions like `_score * popularity * 0.01`
-//! or `_score + log(likes + 1)` against document fields.
+//! or `_score + log(likes + 1)` against document fields. Comparisons and
+//! `&&`/`||` evaluate to 1.0 or 0.0, so the same expressions work as
+//! predicates (e.g. the `bucket_selector` pipeline aggregation).
 
 use crate::backends::SearchResult;
 use crate::ranking::reranker::Reranker;
@@ -14,7 +16,10 @@ use async_trait::async_trait;
 /// - Numeric field names — extracted from document fields
 /// - Numeric literals (integer and float)
 /// - Operators: `+`, `-`, `*`, `/`
+/// - Comparisons `>`, `>=`, `<`, `<=`, `==`, `!=` and logical `&&`, `||`
+///   (true is 1.0, false is 0.0)
 /// - `log(expr)` — natural logarithm
+/// - `sqrt(expr)` — square root
 /// - Parentheses for grouping
 pub struct ScoreFunctionReranker {
     expression: String,
@@ -37,8 +42,7 @@ impl ScoreFunctionReranker {
         score: f32,
         fields: &std::collections::HashMap<String, serde_json::Value>,
     ) -> f32 {
-        let mut parser = ExprParser::new(&self.tokens, score, fields);
-        match parser.parse_expr() {
+        match self.try_evaluate(score, fields) {
             Ok(val) => {
                 if val.is_finite() {
                     val
@@ -49,6 +53,21 @@ impl ScoreFunctionReranker {
             Err(_) => score, // fallback on parse error
         }
     }
+
+    /// Evaluate the expression, surfacing parse errors instead of falling
+    /// back to the original score
+    pub fn try_evaluate(
+        &self,
+        score: f32,
+        fields: &std::collections::HashMap<String, serde_json::Value>,
+    ) -> anyhow::Result<f32> {
+        let mut parser = ExprParser::new(&self.tokens, score, fields);
+        let val = parser.parse_or()?;
+        if let Some(tok) = parser.peek() {
+            return Err(anyhow::anyhow!("Unexpected token: {:?}", tok));
+        }
+        Ok(val)
+    }
 }
 
 #[async_trait]
@@ -91,7 +110,15 @@ enum Token {
     LParen,
     RParen,
     Comma,
-    Func(String), // "log"
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    EqEq,
+    NotEq,
+    AndAnd,
+    OrOr,
+    Func(String), // "log", "sqrt"
 }
 
 fn tokenize(expr: &str) -> anyhow::Result<Vec<Token>> {
@@ -120,6 +147,14 @@ fn tokenize(expr: &str) -> anyhow::Result<Vec<Token>> {
                             | Some(Token::Slash)
                             | Some(Token::LParen)
                             | Some(Token::Comma)
+                            | Some(Token::Gt)
+                            | Some(Token::Ge)
+                            | Some(Token::Lt)
+                            | Some(Token::Le)
+                            | Some(Token::EqEq)
+                            | Some(Token::NotEq)
+                            | Some(Token::AndAnd)
+                            | Some(Token::OrOr)
                     );
                 if is_unary
                     && i + 1 < chars.len()
@@ -161,6 +196,27 @@ fn tokenize(expr: &str) -> anyhow::Result<Vec<Token>> {
                 tokens.push(Token::Comma);
                 i += 1;
             }
+            '>' | '<' | '=' | '!' | '&' | '|' => {
+                let next = chars.get(i + 1).copied();
+                let (token, len) = match (ch, next) {
+                    ('>', Some('=')) => (Token::Ge, 2),
+                    ('>', _) => (Token::Gt, 1),
+                    ('<', Some('=')) => (Token::Le, 2),
+                    ('<', _) => (Token::Lt, 1),
+                    ('=', Some('=')) => (Token::EqEq, 2),
+                    ('!', Some('=')) => (Token::NotEq, 2),
+                    ('&', Some('&')) => (Token::AndAnd, 2),
+                    ('|', Some('|')) => (Token::OrOr, 2),
+                    _ => {
+                        return Err(anyhow::anyhow!(
+                            "Unexpected character '{}' in expression",
+                            ch
+                        ));
+                    }
+                };
+                tokens.push(token);
+                i += len;
+            }
             '0'..='9' | '.' => {
                 let start = i;
                 while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
@@ -185,7 +241,7 @@ fn tokenize(expr: &str) -> anyhow::Result<Vec<Token>> {
                 while j < chars.len() && chars[j].is_whitespace() {
                     j += 1;
                 }
-                if j < chars.len() && chars[j] == '(' && ident == "log" {
+                if j < chars.len() && chars[j] == '(' && matches!(ident.as_str(), "log" | "sqrt") {
                     tokens.push(Token::Func(ident));
                 } else {
                     tokens.push(Token::Ident(ident));
@@ -238,6 +294,49 @@ impl<'a> ExprParser<'a> {
         tok
     }
 
+    // or = and ('||' and)*
+    fn parse_or(&mut self) -> anyhow::Result<f32> {
+        let mut left = self.parse_and()?;
+        while let Some(Token::OrOr) = self.peek() {
+            self.advance();
+            let right = self.parse_and()?;
+            left = bool_value(left != 0.0 || right != 0.0);
+        }
+        Ok(left)
+    }
+
+    // and = comparison ('&&' comparison)*
+    fn parse_and(&mut self) -> anyhow::Result<f32> {
+        let mut left = self.parse_comparison()?;
+        while let Some(Token::AndAnd) = self.peek() {
+            self.advance();
+            let right = self.parse_comparison()?;
+            left = bool_value(left != 0.0 && right != 0.0);
+        }
+        Ok(left)
+    }
+
+    // comparison = expr (('>' | '>=' | '<' | '<=' | '==' | '!=') expr)?
+    fn parse_comparison(&mut self) -> anyhow::Result<f32> {
+        let left = self.parse_expr()?;
+        let op = match self.peek() {
+            Some(
+                op @ (Token::Gt | Token::Ge | Token::Lt | Token::Le | Token::EqEq | Token::NotEq),
+            ) => op.clone(),
+            _ => return Ok(left),
+        };
+        self.advance();
+        let right = self.parse_expr()?;
+        Ok(bool_value(match op {
+            Token::Gt => left > right,
+            Token::Ge => left >= right,
+            Token::Lt => left < right,
+            Token::Le => left <= right,
+            Token::EqEq => left == right,
+            _ => left != right,
+        }))
+    }
+
     // expr = term (('+' | '-') term)*
     fn parse_expr(&mut self) -> anyhow::Result<f32> {
         let mut left = self.parse_term()?;
@@ -322,12 +421,13 @@ impl<'a> ExprParser<'a> {
                 }
                 match fname.as_str() {
                     "log" => Ok(arg.ln()),
+                    "sqrt" => Ok(arg.sqrt()),
                     _ => Err(anyhow::anyhow!("Unknown function: {}", fname)),
                 }
             }
             Some(Token::LParen) => {
                 self.advance();
-                let val = self.parse_expr()?;
+                let val = self.parse_or()?;
                 match self.advance() {
                     Some(Token::RParen) => {}
                     _ => return Err(anyhow::anyhow!("Expected ')'")),
@@ -339,6 +439,14 @@ impl<'a> ExprParser<'a> {
     }
 }
 
+fn bool_value(b: bool) -> f32 {
+    if b {
+        1.0
+    } else {
+        0.0
+    }
+}
+
 impl std::fmt::Display for ScoreFunctionReranker {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "ScoreFunctionReranker({})", self.expression)
@@ -408,6 +516,14 @@ mod tests {
         assert!((val - 5.605).abs() < 0.01);
     }
 
+    #[test]
+    fn test_sqrt_function() {
+        let reranker = ScoreFunctionReranker::new("_score * sqrt(likes)").unwrap();
+        let fields = HashMap::from([("likes".to_string(), json!(16))]);
+        let result = make_result(2.0, fields);
+        assert!((reranker.evaluate(2.0, &result.fields) - 8.0).abs() < 0.001);
+    }
+
     #[test]
     fn test_parentheses() {
         let reranker = ScoreFunctionReranker::new("(_score + 1) * 2").unwrap();
@@ -478,4 +594,27 @@ mod tests {
         let result = make_result(5.0, HashMap::new());
         assert!((reranker.evaluate(5.0, &result.fields) - 4.0).abs() < 0.001);
     }
+
+    #[test]
+    fn test_comparison_and_logical_operators() {
+        let fields = HashMap::from([
+            ("sales".to_string(), json!(250)),
+            ("count".to_string(), json!(3)),
+        ]);
+        let reranker = ScoreFunctionReranker::new("sales > 200 && count <= 3").unwrap();
+        assert_eq!(reranker.try_evaluate(0.0, &fields).unwrap(), 1.0);
+
+        let reranker = ScoreFunctionReranker::new("sales / count >= 100 || count == 0").unwrap();
+        assert_eq!(reranker.try_evaluate(0.0, &fields).unwrap(), 0.0);
+
+        let reranker = ScoreFunctionReranker::new("(count != 3) + 1").unwrap();
+        assert_eq!(reranker.try_evaluate(0.0, &fields).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_try_evaluate_reports_errors() {
+        let reranker = ScoreFunctionReranker::new("sales >").unwrap();
+        assert!(reranker.try_evaluate(0.0, &HashMap::new()).is_err());
+        assert!(ScoreFunctionReranker::new("sales = 1").is_err());
+    }
 }
@@ -0,0 +1,296 @@
+//! Elasticsearch mapping conversion.
+//!
+//! Classifies Elasticsearch field types and turns index mappings into Prism
+//! field definitions. The importer uses the classification for mappings read
+//! from a source cluster; the ES-compatible index APIs use it to create
+//! collections.
+
+use crate::schema::types::{
+    Backends, CollectionSchema, FieldType, TextBackendConfig, TextField, VectorBackendConfig,
+    VectorDistance,
+};
+use crate::{Error, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// The `mappings` object of an Elasticsearch index
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EsMappings {
+    #[serde(default)]
+    pub properties: HashMap<String, EsFieldMapping>,
+}
+
+/// A single entry of a mapping's `properties`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EsFieldMapping {
+    #[serde(rename = "type")]
+    pub field_type: Option<String>,
+    #[serde(default)]
+    pub properties: Option<HashMap<String, EsFieldMapping>>,
+    pub dims: Option<usize>,
+    /// `dense_vector` similarity: `cosine`, `dot_product`, `max_inner_product` or `l2_norm`
+    pub similarity: Option<String>,
+    pub index: Option<bool>,
+    pub doc_values: Option<bool>,
+}
+
+/// Normalized kind of an Elasticsearch field type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EsFieldKind {
+    Text,
+    Keyword,
+    Integer,
+    Float,
+    Boolean,
+    Date,
+    DenseVector,
+    Object,
+    Unknown(String),
+}
+
+impl EsFieldKind {
+    /// Classify an ES `type`; fields without one are objects
+    pub fn from_es_type(es_type: Option<&str>) -> Self {
+        match es_type.unwrap_or("object") {
+            "text" | "match_only_text" => Self::Text,
+            "keyword" | "constant_keyword" | "wildcard" => Self::Keyword,
+            "long" | "integer" | "short" | "byte" => Self::Integer,
+            "float" | "double" | "half_float" | "scaled_float" => Self::Float,
+            "boolean" => Self::Boolean,
+            "date" | "date_nanos" => Self::Date,
+            "dense_vector" => Self::DenseVector,
+            "object" | "nested" | "flattened" => Self::Object,
+            other => Self::Unknown(other.to_string()),
+        }
+    }
+
+    /// Prism field type for scalar kinds; unknown types fall back to text
+    pub fn field_type(&self) -> Option<FieldType> {
+        match self {
+            Self::Text | Self::Unknown(_) => Some(FieldType::Text),
+            Self::Keyword => Some(FieldType::String),
+            Self::Integer => Some(FieldType::I64),
+            Self::Float => Some(FieldType::F64),
+            Self::Boolean => Some(FieldType::Bool),
+            Self::Date => Some(FieldType::Date),
+            Self::DenseVector | Self::Object => None,
+        }
+    }
+}
+
+impl EsFieldMapping {
+    pub fn kind(&self) -> EsFieldKind {
+        EsFieldKind::from_es_type(self.field_type.as_deref())
+    }
+}
+
+/// Prism backend configuration derived from ES mappings
+#[derive(Debug, Clone, Default)]
+pub struct ConvertedMappings {
+    /// Text backend fields, sorted by name
+    pub text_fields: Vec<TextField>,
+    /// Vector backend for the (single) `dense_vector` field
+    pub vector: Option<VectorBackendConfig>,
+}
+
+/// Convert ES mapping properties to Prism field definitions.
+///
+/// Every field is stored so documents can be returned as `_source`.
+/// Keyword, numeric, date and boolean fields get a fast field unless
+/// `doc_values` is disabled, matching ES's default aggregatability.
+pub fn convert_mappings(mappings: &EsMappings) -> Result<ConvertedMappings> {
+    let mut names: Vec<&String> = mappings.properties.keys().collect();
+    names.sort();
+
+    let mut converted = ConvertedMappings::default();
+    for name in names {
+        let prop = &mappings.properties[name];
+        let kind = prop.kind();
+        match kind {
+            EsFieldKind::DenseVector => {
+                if converted.vector.is_some() {
+                    return Err(Error::Schema(format!(
+                        "field [{}]: only one dense_vector field is supported per index",
+                        name
+                    )));
+                }
+                converted.vector = Some(vector_config(name, prop)?);
+            }
+            EsFieldKind::Object => {
+                return Err(Error::Schema(format!(
+                    "field [{}]: object, nested and flattened fields are not supported",
+                    name
+                )));
+            }
+            _ => {
+                if let EsFieldKind::Unknown(es_type) = &kind {
+                    tracing::warn!(
+                        "Unknown ES type '{}' for field '{}', mapping to text",
+                        es_type,
+                        name
+                    );
+                }
+                let field_type = kind.field_type().unwrap_or(FieldType::Text);
+                let fast = field_type != FieldType::Text && prop.doc_values.unwrap_or(true);
+                converted.text_fields.push(TextField {
+                    name: name.clone(),
+                    field_type,
+                    stored: true,
+                    indexed: prop.index.unwrap_or(true),
+                    tokenizer: None,
+                    tokenizer_options: None,
+                    multi: false,
+                    fast,
+                });
+            }
+        }
+    }
+    Ok(converted)
+}
+
+/// Build a collection schema for an index created from ES mappings
+pub fn collection_schema(collection: &str, mappings: &EsMappings) -> Result<CollectionSchema> {
+    let converted = convert_mappings(mappings)?;
+    if converted.text_fields.is_empty() && converted.vector.is_none() {
+        return Err(Error::Schema(format!(
+            "index [{}] needs at least one mapped field; dynamic mappings are not supported",
+            collection
+        )));
+    }
+
+    let text = (!converted.text_fields.is_empty()).then_some(TextBackendConfig {
+        fields: converted.text_fields,
+        bm25_k1: None,
+        bm25_b: None,
+    });
+
+    Ok(CollectionSchema {
+        collection: collection.to_string(),
+        description: None,
+        backends: Backends {
+            text,
+            vector: converted.vector,
+            graph: None,
+        },
+        indexing: Default::default(),
+        quota: Default::default(),
+        embedding_generation: None,
+        facets: None,
+        boosting: None,
+        storage: Default::default(),
+        system_fields: Default::default(),
+        hybrid: None,
+        replication: None,
+        reranking: None,
+        ilm_policy: None,
+    })
+}
+
+fn vector_config(name: &str, prop: &EsFieldMapping) -> Result<VectorBackendConfig> {
+    let dims = prop
+        .dims
+        .ok_or_else(|| Error::Schema(format!("dense_vector field [{}] requires [dims]", name)))?;
+    let distance = match prop.similarity.as_deref().unwrap_or("cosine") {
+        "cosine" => VectorDistance::Cosine,
+        "dot_product" | "max_inner_product" => VectorDistance::Dot,
+        "l2_norm" => VectorDistance::Euclidean,
+        other => {
+            return Err(Error::Schema(format!(
+                "dense_vector field [{}]: unknown similarity [{}]",
+                name, other
+            )))
+        }
+    };
+    Ok(VectorBackendConfig::new(name, dims, distance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mappings(value: serde_json::Value) -> EsMappings {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_classify_types() {
+        assert_eq!(
+            EsFieldKind::from_es_type(Some("match_only_text")),
+            EsFieldKind::Text
+        );
+        assert_eq!(
+            EsFieldKind::from_es_type(Some("wildcard")),
+            EsFieldKind::Keyword
+        );
+        assert_eq!(
+            EsFieldKind::from_es_type(Some("short")),
+            EsFieldKind::Integer
+        );
+        assert_eq!(
+            EsFieldKind::from_es_type(Some("half_float")),
+            EsFieldKind::Float
+        );
+        assert_eq!(
+            EsFieldKind::from_es_type(Some("date_nanos")),
+            EsFieldKind::Date
+        );
+        assert_eq!(EsFieldKind::from_es_type(None), EsFieldKind::Object);
+        assert_eq!(
+            EsFieldKind::from_es_type(Some("geo_point")),
+            EsFieldKind::Unknown("geo_point".to_string())
+        );
+    }
+
+    #[test]
+    fn test_collection_schema_from_mappings() {
+        let schema = collection_schema(
+            "products",
+            &mappings(json!({
+                "properties": {
+                    "title": { "type": "text" },
+                    "sku": { "type": "keyword", "doc_values": false },
+                    "price": { "type": "scaled_float" },
+                    "created": { "type": "date", "index": false },
+                    "embedding": { "type": "dense_vector", "dims": 3, "similarity": "dot_product" }
+                }
+            })),
+        )
+        .unwrap();
+
+        let fields = &schema.backends.text.as_ref().unwrap().fields;
+        let names: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["created", "price", "sku", "title"]);
+        assert!(fields.iter().all(|f| f.stored));
+        assert_eq!(fields[0].field_type, FieldType::Date);
+        assert!(!fields[0].indexed);
+        assert!(fields[1].fast);
+        assert_eq!(fields[2].field_type, FieldType::String);
+        assert!(!fields[2].fast);
+        assert!(!fields[3].fast);
+
+        let vector = schema.backends.vector.unwrap();
+        assert_eq!(vector.embedding_field, "embedding");
+        assert_eq!(vector.dimension, 3);
+        assert_eq!(vector.distance, VectorDistance::Dot);
+    }
+
+    #[test]
+    fn test_rejected_mappings() {
+        let no_dims = mappings(json!({ "properties": { "v": { "type": "dense_vector" } } }));
+        assert!(convert_mappings(&no_dims).is_err());
+
+        let two_vectors = mappings(json!({ "properties": {
+            "a": { "type": "dense_vector", "dims": 2 },
+            "b": { "type": "dense_vector", "dims": 2 }
+        } }));
+        assert!(convert_mappings(&two_vectors).is_err());
+
+        let object = mappings(json!({ "properties": {
+            "user": { "properties": { "name": { "type": "text" } } }
+        } }));
+        assert!(convert_mappings(&object).is_err());
+
+        assert!(collection_schema("empty", &EsMappings::default()).is_err());
+    }
+}
@@ -67,6 +67,14 @@ impl SchemaLoader {
             if t.fields.is_empty() {
                 issues.push("text.fields should have at least one field defined".to_string());
             }
+            for field in &t.fields {
+                if field.fast && field.field_type == crate::schema::FieldType::Bytes {
+                    issues.push(format!(
+                        "text.fields.{}: fast is not supported for bytes fields; ignored",
+                        field.name
+                    ));
+                }
+            }
             if let Some(k1) = t.bm25_k1 {
                 if (k1 - 1.2).abs() > f32::EPSILON {
                     issues.push(format!(
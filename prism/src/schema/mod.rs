@@ -1,3 +1,4 @@
+pub mod es_mapping;
 pub mod loader;
 pub mod types;
 
@@ -6,5 +7,5 @@ pub use types::{
     Backends, BoostingConfig, CollectionSchema, CrossEncoderSchemaConfig, FieldType,
     GraphBackendConfig, IndexingConfig, QuotaConfig, RecencyDecayConfig, RerankerType,
     RerankingConfig, TextBackendConfig, TextField, TokenizerType, TreeSitterOptions,
-    VectorBackendConfig,
+    VectorBackendConfig, VectorDistance,
 };
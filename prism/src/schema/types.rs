@@ -89,6 +89,22 @@ pub struct TextField {
     /// Options for the tree-sitter tokenizer (only used when tokenizer = "code-treesitter")
     #[serde(default)]
     pub tokenizer_options: Option<TreeSitterOptions>,
+    /// Accept JSON arrays for this field (default: false).
+    ///
+    /// Each element is indexed as a separate value and stored values are
+    /// returned as an array. Terms aggregations count a document once per
+    /// distinct value, range queries match when any element is in range, and
+    /// an empty array is treated the same as a missing field.
+    #[serde(default)]
+    pub multi: bool,
+    /// Store a columnar (fast field) copy of this field (default: false).
+    ///
+    /// Aggregations read fast fields directly from the column store instead
+    /// of loading stored documents, which is much faster on large match sets.
+    /// Fields without `fast` are still aggregatable through a slower stored
+    /// document scan. Not supported for `bytes` fields.
+    #[serde(default)]
+    pub fast: bool,
 }
 
 /// Tokenizer type for text fields
@@ -163,6 +179,28 @@ pub struct VectorBackendConfig {
     pub compaction: VectorCompactionConfig,
 }
 
+impl VectorBackendConfig {
+    /// Vector config with default HNSW, sharding and compaction settings
+    pub fn new(
+        embedding_field: impl Into<String>,
+        dimension: usize,
+        distance: VectorDistance,
+    ) -> Self {
+        Self {
+            embedding_field: embedding_field.into(),
+            dimension,
+            distance,
+            hnsw_m: default_hnsw_m(),
+            hnsw_ef_construction: default_hnsw_ef_construction(),
+            hnsw_ef_search: default_hnsw_ef_search(),
+            vector_weight: default_vector_weight(),
+            num_shards: default_num_shards(),
+            shard_oversample: default_shard_oversample(),
+            compaction: VectorCompactionConfig::default(),
+        }
+    }
+}
+
 fn default_vector_weight() -> f32 {
     0.5
 }
@@ -404,6 +442,11 @@ pub struct SystemFieldsConfig {
     /// Allows setting custom boost multipliers per document for popularity signals
     #[serde(default)]
     pub document_boost: bool,
+
+    /// Add _version and _seq_no fields (default: true)
+    /// Keeps the version of the last write with each document across restarts
+    #[serde(default = "default_document_version_enabled")]
+    pub document_version: bool,
 }
 
 impl Default for SystemFieldsConfig {
@@ -411,6 +454,7 @@ impl Default for SystemFieldsConfig {
         Self {
             indexed_at: true,
             document_boost: false,
+            document_version: true,
         }
     }
 }
@@ -419,6 +463,10 @@ fn default_indexed_at_enabled() -> bool {
     true
 }
 
+fn default_document_version_enabled() -> bool {
+    true
+}
+
 /// Score normalization strategy for hybrid search weighted merge
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
@@ -702,6 +750,28 @@ backends:
         assert_eq!(text.fields[0].field_type, FieldType::Text);
     }
 
+    #[test]
+    fn test_parse_multi_valued_field() {
+        let yaml = r#"
+collection: test_collection
+backends:
+  text:
+    fields:
+      - name: title
+        type: text
+        indexed: true
+      - name: tags
+        type: string
+        indexed: true
+        stored: true
+        multi: true
+"#;
+        let schema: CollectionSchema = serde_yaml::from_str(yaml).unwrap();
+        let text = schema.backends.text.unwrap();
+        assert!(!text.fields[0].multi);
+        assert!(text.fields[1].multi);
+    }
+
     #[test]
     fn test_parse_vector_backend_config() {
         let yaml = r#"
@@ -817,6 +887,8 @@ backends:
         assert!(schema.system_fields.indexed_at);
         // _boost should be disabled by default
         assert!(!schema.system_fields.document_boost);
+        // _version and _seq_no should be enabled by default
+        assert!(schema.system_fields.document_version);
     }
 
     #[test]
@@ -832,11 +904,13 @@ backends:
 system_fields:
   indexed_at: false
   document_boost: true
+  document_version: false
 "#;
         let schema: CollectionSchema = serde_yaml::from_str(yaml).unwrap();
 
         assert!(!schema.system_fields.indexed_at);
         assert!(schema.system_fields.document_boost);
+        assert!(!schema.system_fields.document_version);
     }
 
     #[test]
@@ -312,6 +312,8 @@ mod tests {
                         stored: true,
                         tokenizer: None,
                         tokenizer_options: None,
+                        multi: false,
+                        fast: false,
                     },
                     TemplateTextField {
                         name: "level".to_string(),
@@ -320,6 +322,8 @@ mod tests {
                         stored: true,
                         tokenizer: None,
                         tokenizer_options: None,
+                        multi: false,
+                        fast: false,
                     },
                 ],
                 vector: None,
@@ -359,6 +363,8 @@ mod tests {
                         stored: false,
                         tokenizer: None,
                         tokenizer_options: None,
+                        multi: false,
+                        fast: false,
                     }],
                     bm25_k1: None,
                     bm25_b: None,
@@ -82,6 +82,10 @@ pub struct TemplateTextField {
     pub tokenizer: Option<TokenizerType>,
     #[serde(default)]
     pub tokenizer_options: Option<TreeSitterOptions>,
+    #[serde(default)]
+    pub multi: bool,
+    #[serde(default)]
+    pub fast: bool,
 }
 
 fn default_field_type() -> FieldType {
@@ -101,6 +105,8 @@ impl From<TemplateTextField> for TextField {
             stored: t.stored,
             tokenizer: t.tokenizer,
             tokenizer_options: t.tokenizer_options,
+            multi: t.multi,
+            fast: t.fast,
         }
     }
 }
@@ -218,6 +224,8 @@ mod tests {
                     stored: true,
                     tokenizer: None,
                     tokenizer_options: None,
+                    multi: false,
+                    fast: false,
                 }],
                 vector: None,
             },
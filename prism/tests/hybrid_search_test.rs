@@ -27,6 +27,8 @@ async fn test_hybrid_merge_behaviour() {
                     indexed: true,
                     tokenizer: None,
                     tokenizer_options: None,
+                    multi: false,
+                    fast: false,
                 }],
                 bm25_k1: None,
                 bm25_b: None,
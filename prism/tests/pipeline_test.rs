@@ -134,6 +134,132 @@ fn test_rename_missing_field_errors() {
     assert!(proc.process(&mut doc).is_err());
 }
 
+#[test]
+fn test_uppercase_and_trim_processors() {
+    let mut doc = make_doc(vec![("code", Value::String("  ab-1 ".to_string()))]);
+    TrimProcessor {
+        field: "code".to_string(),
+    }
+    .process(&mut doc)
+    .unwrap();
+    UppercaseProcessor {
+        field: "code".to_string(),
+    }
+    .process(&mut doc)
+    .unwrap();
+    assert_eq!(doc.fields["code"], Value::String("AB-1".to_string()));
+}
+
+#[test]
+fn test_append_processor() {
+    let proc = AppendProcessor {
+        field: "tags".to_string(),
+        values: vec![
+            Value::String("b".to_string()),
+            Value::String("c".to_string()),
+        ],
+    };
+    let mut doc = make_doc(vec![("tags", Value::String("a".to_string()))]);
+    proc.process(&mut doc).unwrap();
+    assert_eq!(doc.fields["tags"], serde_json::json!(["a", "b", "c"]));
+
+    let mut doc = make_doc(vec![]);
+    proc.process(&mut doc).unwrap();
+    assert_eq!(doc.fields["tags"], serde_json::json!(["b", "c"]));
+}
+
+#[test]
+fn test_convert_processor() {
+    let convert = |target, value: Value| {
+        let mut doc = make_doc(vec![("v", value)]);
+        ConvertProcessor {
+            field: "v".to_string(),
+            target,
+        }
+        .process(&mut doc)
+        .map(|_| doc.fields["v"].clone())
+    };
+    assert_eq!(
+        convert(ConvertTarget::Integer, Value::String(" 42 ".to_string())).unwrap(),
+        serde_json::json!(42)
+    );
+    assert_eq!(
+        convert(ConvertTarget::Float, serde_json::json!(["1.5", 2])).unwrap(),
+        serde_json::json!([1.5, 2.0])
+    );
+    assert_eq!(
+        convert(ConvertTarget::Boolean, Value::String("TRUE".to_string())).unwrap(),
+        Value::Bool(true)
+    );
+    assert_eq!(
+        convert(ConvertTarget::String, serde_json::json!(7)).unwrap(),
+        Value::String("7".to_string())
+    );
+    assert_eq!(
+        convert(ConvertTarget::Auto, Value::String("x".to_string())).unwrap(),
+        Value::String("x".to_string())
+    );
+    assert!(convert(ConvertTarget::Integer, Value::String("x".to_string())).is_err());
+    assert_eq!(
+        ConvertTarget::parse("long").unwrap(),
+        ConvertTarget::Integer
+    );
+    assert!(ConvertTarget::parse("ip").is_err());
+}
+
+#[test]
+fn test_split_join_and_gsub_processors() {
+    let mut doc = make_doc(vec![("path", Value::String("a/b//c".to_string()))]);
+    SplitProcessor {
+        field: "path".to_string(),
+        separator: regex::Regex::new("/+").unwrap(),
+    }
+    .process(&mut doc)
+    .unwrap();
+    assert_eq!(doc.fields["path"], serde_json::json!(["a", "b", "c"]));
+
+    JoinProcessor {
+        field: "path".to_string(),
+        separator: ".".to_string(),
+    }
+    .process(&mut doc)
+    .unwrap();
+    assert_eq!(doc.fields["path"], Value::String("a.b.c".to_string()));
+
+    GsubProcessor {
+        field: "path".to_string(),
+        pattern: regex::Regex::new("[.]").unwrap(),
+        replacement: "-".to_string(),
+    }
+    .process(&mut doc)
+    .unwrap();
+    assert_eq!(doc.fields["path"], Value::String("a-b-c".to_string()));
+}
+
+#[test]
+fn test_ignore_missing_and_ignore_failure() {
+    let proc = IgnoreMissing {
+        field: "missing".to_string(),
+        processor: Box::new(LowercaseProcessor {
+            field: "missing".to_string(),
+        }),
+    };
+    let mut doc = make_doc(vec![]);
+    assert!(proc.process(&mut doc).is_ok());
+    assert_eq!(proc.name(), "lowercase");
+
+    // A failing processor leaves the document untouched
+    let proc = IgnoreFailure {
+        processor: Box::new(ConvertProcessor {
+            field: "n".to_string(),
+            target: ConvertTarget::Integer,
+        }),
+    };
+    let mut doc = make_doc(vec![("n", Value::String("abc".to_string()))]);
+    assert!(proc.process(&mut doc).is_ok());
+    assert_eq!(doc.fields["n"], Value::String("abc".to_string()));
+}
+
 use prism::pipeline::registry::PipelineRegistry;
 use tempfile::TempDir;
 
@@ -209,3 +335,61 @@ fn test_load_nonexistent_dir() {
     assert!(registry.is_ok());
     assert!(registry.unwrap().get("anything").is_none());
 }
+
+#[test]
+fn test_load_pipeline_with_new_processors() {
+    let tmp = TempDir::new().unwrap();
+    let yaml = r#"
+name: tidy
+processors:
+  - trim:
+      field: title
+  - split:
+      field: tags
+      separator: ",\\s*"
+  - append:
+      field: tags
+      value: [imported]
+  - convert:
+      field: year
+      type: integer
+      ignore_missing: true
+  - gsub:
+      field: title
+      pattern: "\\s+"
+      replacement: " "
+"#;
+    std::fs::write(tmp.path().join("tidy.yaml"), yaml).unwrap();
+
+    let registry = PipelineRegistry::load(tmp.path()).unwrap();
+    let pipeline = registry.get("tidy").unwrap();
+    let mut doc = make_doc(vec![
+        ("title", Value::String("  two   words ".to_string())),
+        ("tags", Value::String("a, b".to_string())),
+    ]);
+    pipeline.process(&mut doc).unwrap();
+    assert_eq!(doc.fields["title"], Value::String("two words".to_string()));
+    assert_eq!(
+        doc.fields["tags"],
+        serde_json::json!(["a", "b", "imported"])
+    );
+    assert!(!doc.fields.contains_key("year"));
+}
+
+#[test]
+fn test_registry_insert_and_remove() {
+    let registry = PipelineRegistry::empty();
+    registry.insert(prism::pipeline::registry::Pipeline {
+        name: "runtime".to_string(),
+        description: String::new(),
+        processors: vec![Box::new(UppercaseProcessor {
+            field: "code".to_string(),
+        })],
+    });
+    assert_eq!(registry.get("runtime").unwrap().processors.len(), 1);
+    assert_eq!(registry.list().len(), 1);
+
+    assert!(registry.remove("runtime").is_some());
+    assert!(registry.get("runtime").is_none());
+    assert!(registry.remove("runtime").is_none());
+}
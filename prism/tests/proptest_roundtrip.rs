@@ -13,7 +13,7 @@ use std::sync::Arc;
 use tempfile::TempDir;
 
 // ---------------------------------------------------------------------------
-// Schema YAML covering all 8 field types
+// Schema YAML covering all 8 field types plus multi-valued fields
 // ---------------------------------------------------------------------------
 
 fn schema_yaml() -> &'static str {
@@ -53,6 +53,16 @@ backends:
       - name: bytes_field
         type: bytes
         stored: true
+      - name: tags
+        type: string
+        indexed: true
+        stored: true
+        multi: true
+      - name: scores
+        type: i64
+        indexed: true
+        stored: true
+        multi: true
 "#
 }
 
@@ -737,3 +747,158 @@ proptest! {
         });
     }
 }
+
+// ---------------------------------------------------------------------------
+// Test 9: Multi-valued fields round-trip as arrays
+// ---------------------------------------------------------------------------
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(30))]
+
+    #[test]
+    fn test_multi_value_roundtrip(
+        tags in prop::collection::vec("[a-z]{1,12}", 0..6),
+        scores in prop::collection::vec(any::<i64>(), 0..6),
+        doc_counter in 0u64..1_000_000,
+    ) {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let (_temp, manager) = setup().await;
+            let doc_id = format!("prop-multi-{}", doc_counter);
+
+            let doc = Document {
+                id: doc_id.clone(),
+                fields: HashMap::from([
+                    ("text_field".into(), json!("multi valued")),
+                    ("tags".into(), json!(tags)),
+                    ("scores".into(), json!(scores)),
+                ]),
+            };
+
+            manager.index("proptest-all", vec![doc]).await.unwrap();
+
+            let retrieved = manager.get("proptest-all", &doc_id).await.unwrap();
+            assert!(retrieved.is_some(), "Document should exist");
+            let fields = &retrieved.unwrap().fields;
+
+            // Every element comes back in order; empty arrays are treated as missing
+            if tags.is_empty() {
+                assert!(fields.get("tags").is_none(), "empty tags should be missing");
+            } else {
+                assert_eq!(fields.get("tags"), Some(&json!(tags)), "tags mismatch");
+            }
+            if scores.is_empty() {
+                assert!(fields.get("scores").is_none(), "empty scores should be missing");
+            } else {
+                assert_eq!(fields.get("scores"), Some(&json!(scores)), "scores mismatch");
+            }
+        });
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Test 10: Terms aggregation counts each distinct value once per document
+// ---------------------------------------------------------------------------
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(15))]
+
+    #[test]
+    fn test_multi_value_terms_agg(
+        doc_tags in prop::collection::vec(
+            prop::collection::vec(prop::sample::select(vec!["red", "green", "blue", "black"]), 0..5),
+            1..20,
+        ),
+    ) {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let (_temp, manager) = setup().await;
+
+            let docs: Vec<Document> = doc_tags
+                .iter()
+                .enumerate()
+                .map(|(i, tags)| Document {
+                    id: format!("terms-{}", i),
+                    fields: HashMap::from([
+                        ("text_field".into(), json!(format!("colour document {}", i))),
+                        ("tags".into(), json!(tags)),
+                    ]),
+                })
+                .collect();
+
+            manager.index("proptest-all", docs).await.unwrap();
+
+            let mut expected: HashMap<String, u64> = HashMap::new();
+            for tags in &doc_tags {
+                let mut distinct: Vec<&str> = tags.clone();
+                distinct.sort();
+                distinct.dedup();
+                for tag in distinct {
+                    *expected.entry(tag.to_string()).or_insert(0) += 1;
+                }
+            }
+
+            let agg: prism::aggregations::AggregationRequest = serde_json::from_value(json!({
+                "name": "by_tag",
+                "type": "terms",
+                "field": "tags",
+                "size": 10
+            }))
+            .unwrap();
+            let results = manager
+                .search_with_aggs("proptest-all", &make_query("*", 10), vec![agg])
+                .await
+                .unwrap();
+
+            let buckets = match &results.aggregations["by_tag"].value {
+                prism::aggregations::AggregationValue::Buckets(b) => b.clone(),
+                other => panic!("expected buckets, got {:?}", other),
+            };
+            let actual: HashMap<String, u64> =
+                buckets.into_iter().map(|b| (b.key, b.doc_count)).collect();
+            assert_eq!(actual, expected, "terms counts mismatch");
+        });
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Test 11: Range queries match when any element is in range
+// ---------------------------------------------------------------------------
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(30))]
+
+    #[test]
+    fn test_multi_value_range_any_element(
+        scores in prop::collection::vec(0i64..1000, 0..6),
+        lo in 0i64..1000,
+        width in 0i64..200,
+    ) {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let (_temp, manager) = setup().await;
+            let hi = lo + width;
+
+            let doc = Document {
+                id: "range-doc".to_string(),
+                fields: HashMap::from([
+                    ("text_field".into(), json!("range document")),
+                    ("scores".into(), json!(scores)),
+                ]),
+            };
+
+            manager.index("proptest-all", vec![doc]).await.unwrap();
+
+            let query = make_query(&format!("scores:[{} TO {}]", lo, hi), 10);
+            let results = manager.search("proptest-all", query, None).await.unwrap();
+
+            let expected = scores.iter().any(|&s| s >= lo && s <= hi);
+            assert_eq!(
+                results.total == 1,
+                expected,
+                "range [{}, {}] over {:?} should match: {}",
+                lo, hi, scores, expected
+            );
+        });
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! Tests prefix-based term suggestions with frequency ranking.
 
+use prism::backends::text::TermPattern;
 use prism::backends::{Document, TextBackend, VectorBackend};
 use prism::collection::CollectionManager;
 use serde_json::json;
@@ -362,3 +363,74 @@ async fn test_nonexistent_collection() {
 
     assert!(result.is_err(), "Should error on nonexistent collection");
 }
+
+#[tokio::test]
+async fn test_expand_multi_term_patterns() {
+    let (_temp, manager) = setup_suggest_environment().await;
+
+    let titles = ["rust", "rush", "rust", "rusty", "dust", "ruby"];
+    let docs = titles
+        .iter()
+        .enumerate()
+        .map(|(i, title)| Document {
+            id: format!("doc{}", i),
+            fields: HashMap::from([("title".to_string(), json!(title))]),
+        })
+        .collect();
+    manager
+        .index("articles", docs)
+        .await
+        .expect("Failed to index documents");
+
+    // Closest first, then most frequent
+    let fuzzy = TermPattern::Fuzzy {
+        term: "rust".to_string(),
+        distance: 1,
+        prefix_length: 0,
+        transpositions: true,
+    };
+    let terms = manager
+        .expand_terms("articles", "title", &fuzzy, 10)
+        .unwrap();
+    assert_eq!(terms, vec!["rust", "dust", "rush", "rusty"]);
+
+    // Matches must keep the first characters
+    let fuzzy = TermPattern::Fuzzy {
+        term: "rust".to_string(),
+        distance: 1,
+        prefix_length: 1,
+        transpositions: true,
+    };
+    let terms = manager
+        .expand_terms("articles", "title", &fuzzy, 2)
+        .unwrap();
+    assert_eq!(terms, vec!["rust", "rush"]);
+
+    let regex = TermPattern::Regex("ru(s|b)[a-z]".to_string());
+    let terms = manager
+        .expand_terms("articles", "title", &regex, 10)
+        .unwrap();
+    assert_eq!(terms, vec!["ruby", "rush", "rust"]);
+
+    let wildcard = TermPattern::Wildcard("?ust*".to_string());
+    let terms = manager
+        .expand_terms("articles", "title", &wildcard, 10)
+        .unwrap();
+    assert_eq!(terms, vec!["dust", "rust", "rusty"]);
+
+    let prefix = TermPattern::Prefix("rus".to_string());
+    let terms = manager
+        .expand_terms("articles", "title", &prefix, 2)
+        .unwrap();
+    assert_eq!(terms, vec!["rush", "rust"]);
+
+    let terms = manager
+        .expand_terms("articles", "missing", &prefix, 10)
+        .unwrap();
+    assert!(terms.is_empty());
+
+    let invalid = TermPattern::Regex("ru(".to_string());
+    assert!(manager
+        .expand_terms("articles", "title", &invalid, 10)
+        .is_err());
+}
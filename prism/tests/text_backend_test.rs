@@ -5,7 +5,8 @@
 //! and document reconstruction.
 
 use prism::aggregations::types::{
-    AggregationRequest, AggregationType, AggregationValue, HistogramBounds, RangeEntry,
+    AggregationRequest, AggregationType, AggregationValue, CompositeSource, HistogramBounds,
+    RangeEntry, SortDirection, TopHitsSort,
 };
 use prism::backends::text::TextBackend;
 use prism::backends::{Document, HighlightConfig, Query, SearchBackend};
@@ -52,6 +53,8 @@ fn make_schema() -> CollectionSchema {
                         indexed: true,
                         tokenizer: None,
                         tokenizer_options: None,
+                        multi: false,
+                        fast: false,
                     },
                     TextField {
                         name: "body".to_string(),
@@ -60,6 +63,8 @@ fn make_schema() -> CollectionSchema {
                         indexed: true,
                         tokenizer: None,
                         tokenizer_options: None,
+                        multi: false,
+                        fast: false,
                     },
                     TextField {
                         name: "count".to_string(),
@@ -68,6 +73,8 @@ fn make_schema() -> CollectionSchema {
                         indexed: true,
                         tokenizer: None,
                         tokenizer_options: None,
+                        multi: false,
+                        fast: false,
                     },
                     TextField {
                         name: "created_at".to_string(),
@@ -76,6 +83,8 @@ fn make_schema() -> CollectionSchema {
                         indexed: true,
                         tokenizer: None,
                         tokenizer_options: None,
+                        multi: false,
+                        fast: false,
                     },
                     TextField {
                         name: "category".to_string(),
@@ -84,6 +93,8 @@ fn make_schema() -> CollectionSchema {
                         indexed: true,
                         tokenizer: None,
                         tokenizer_options: None,
+                        multi: false,
+                        fast: false,
                     },
                     TextField {
                         name: "price".to_string(),
@@ -92,6 +103,8 @@ fn make_schema() -> CollectionSchema {
                         indexed: true,
                         tokenizer: None,
                         tokenizer_options: None,
+                        multi: false,
+                        fast: false,
                     },
                 ],
                 bm25_k1: None,
@@ -109,6 +122,7 @@ fn make_schema() -> CollectionSchema {
         system_fields: SystemFieldsConfig {
             indexed_at: false,
             document_boost: false,
+            document_version: false,
         },
         hybrid: None,
         replication: None,
@@ -611,6 +625,7 @@ async fn test_search_with_highlight() {
         post_tag: "</b>".to_string(),
         fragment_size: 150,
         number_of_fragments: 3,
+        field_options: Default::default(),
     });
 
     let results = backend.search("test", q).await.unwrap();
@@ -649,6 +664,7 @@ async fn test_highlight_non_matching_field_omitted() {
         post_tag: "</em>".to_string(),
         fragment_size: 150,
         number_of_fragments: 3,
+        field_options: Default::default(),
     });
 
     let results = backend.search("test", q).await.unwrap();
@@ -661,17 +677,20 @@ async fn test_highlight_non_matching_field_omitted() {
 // 6. Aggregations (search_with_aggs)
 // =========================================================================
 
-async fn setup_agg_data() -> (TempDir, TextBackend) {
-    let (tmp, backend) = setup().await;
-
-    let docs = vec![
+fn agg_docs() -> Vec<Document> {
+    vec![
         doc_full("1", "Alpha item", "Body one", 10, "2025-01-15T00:00:00Z", "electronics", 99.99),
         doc_full("2", "Beta item", "Body two", 20, "2025-01-16T00:00:00Z", "electronics", 149.50),
         doc_full("3", "Gamma item", "Body three", 30, "2025-02-10T00:00:00Z", "books", 12.99),
         doc_full("4", "Delta item", "Body four", 40, "2025-02-20T00:00:00Z", "books", 24.99),
         doc_full("5", "Epsilon item", "Body five", 50, "2025-03-01T00:00:00Z", "clothing", 59.99),
-    ];
-    backend.index("test", docs).await.unwrap();
+    ]
+}
+
+async fn setup_agg_data() -> (TempDir, TextBackend) {
+    let (tmp, backend) = setup().await;
+
+    backend.index("test", agg_docs()).await.unwrap();
 
     (tmp, backend)
 }
@@ -1225,6 +1244,474 @@ async fn test_agg_multiple_aggregations() {
     assert!(result.aggregations.contains_key("total"));
 }
 
+/// Same schema as `make_schema`, with every aggregatable field marked `fast`
+fn make_fast_schema() -> CollectionSchema {
+    let mut schema = make_schema();
+    for field in &mut schema.backends.text.as_mut().unwrap().fields {
+        if field.field_type != FieldType::Text {
+            field.fast = true;
+        }
+    }
+    schema
+}
+
+fn bucket_counts(value: &AggregationValue) -> HashMap<String, u64> {
+    match value {
+        AggregationValue::Buckets(buckets) => buckets
+            .iter()
+            .map(|b| (b.key.clone(), b.doc_count))
+            .collect(),
+        _ => panic!("Expected Buckets value"),
+    }
+}
+
+#[tokio::test]
+async fn test_agg_fast_fields_match_stored_scan() {
+    let (_tmp, stored_backend) = setup_agg_data().await;
+
+    let fast_tmp = TempDir::new().unwrap();
+    let fast_backend = TextBackend::new(fast_tmp.path()).unwrap();
+    fast_backend
+        .initialize("test", &make_fast_schema())
+        .await
+        .unwrap();
+    fast_backend.index("test", agg_docs()).await.unwrap();
+
+    let aggs = || {
+        vec![
+            AggregationRequest {
+                name: "by_category".to_string(),
+                agg_type: AggregationType::Terms {
+                    field: "category".to_string(),
+                    size: Some(10),
+                },
+                aggs: None,
+            },
+            AggregationRequest {
+                name: "price_stats".to_string(),
+                agg_type: AggregationType::Stats {
+                    field: "price".to_string(),
+                },
+                aggs: None,
+            },
+            AggregationRequest {
+                name: "count_hist".to_string(),
+                agg_type: AggregationType::Histogram {
+                    field: "count".to_string(),
+                    interval: 20.0,
+                    min_doc_count: None,
+                    extended_bounds: None,
+                },
+                aggs: None,
+            },
+            AggregationRequest {
+                name: "by_month".to_string(),
+                agg_type: AggregationType::DateHistogram {
+                    field: "created_at".to_string(),
+                    calendar_interval: "month".to_string(),
+                    min_doc_count: None,
+                },
+                aggs: None,
+            },
+        ]
+    };
+
+    let stored = stored_backend
+        .search_with_aggs("test", &match_all_query(), aggs())
+        .await
+        .unwrap();
+    let fast = fast_backend
+        .search_with_aggs("test", &match_all_query(), aggs())
+        .await
+        .unwrap();
+
+    for name in ["by_category", "count_hist", "by_month"] {
+        assert_eq!(
+            bucket_counts(&fast.aggregations[name].value),
+            bucket_counts(&stored.aggregations[name].value),
+            "bucket mismatch for {}",
+            name
+        );
+    }
+    assert_eq!(
+        serde_json::to_value(&fast.aggregations["price_stats"]).unwrap(),
+        serde_json::to_value(&stored.aggregations["price_stats"]).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_agg_covers_full_match_set() {
+    let tmp = TempDir::new().unwrap();
+    let backend = TextBackend::new(tmp.path()).unwrap();
+    backend
+        .initialize("test", &make_fast_schema())
+        .await
+        .unwrap();
+
+    // More documents than the old 10 000 document scan cap
+    let docs: Vec<Document> = (0..12_000)
+        .map(|i| Document {
+            id: i.to_string(),
+            fields: HashMap::from([
+                ("title".to_string(), json!("bulk item")),
+                ("category".to_string(), json!(if i % 2 == 0 { "even" } else { "odd" })),
+                ("count".to_string(), json!(1)),
+            ]),
+        })
+        .collect();
+    backend.index("test", docs).await.unwrap();
+
+    let aggs = vec![
+        AggregationRequest {
+            name: "total".to_string(),
+            agg_type: AggregationType::Count,
+            aggs: None,
+        },
+        AggregationRequest {
+            name: "by_category".to_string(),
+            agg_type: AggregationType::Terms {
+                field: "category".to_string(),
+                size: Some(10),
+            },
+            aggs: None,
+        },
+        AggregationRequest {
+            name: "count_sum".to_string(),
+            agg_type: AggregationType::Sum {
+                field: "count".to_string(),
+            },
+            aggs: None,
+        },
+    ];
+
+    let mut query = make_query("bulk");
+    query.limit = 5;
+    let result = backend.search_with_aggs("test", &query, aggs).await.unwrap();
+
+    assert_eq!(result.results.len(), 5);
+    assert_eq!(result.total, 12_000);
+    match &result.aggregations["total"].value {
+        AggregationValue::Single(v) => assert_eq!(*v as u64, 12_000),
+        _ => panic!("Expected Single value for Count aggregation"),
+    }
+    let counts = bucket_counts(&result.aggregations["by_category"].value);
+    assert_eq!(counts["even"], 6_000);
+    assert_eq!(counts["odd"], 6_000);
+    match &result.aggregations["count_sum"].value {
+        AggregationValue::Single(v) => assert_eq!(*v as u64, 12_000),
+        _ => panic!("Expected Single value for Sum aggregation"),
+    }
+}
+
+#[tokio::test]
+async fn test_agg_cardinality() {
+    let (_tmp, backend) = setup_agg_data().await;
+
+    let aggs = vec![AggregationRequest {
+        name: "categories".to_string(),
+        agg_type: AggregationType::cardinality("category".to_string()),
+        aggs: None,
+    }];
+
+    let result = backend
+        .search_with_aggs("test", &match_all_query(), aggs)
+        .await
+        .unwrap();
+
+    match &result.aggregations["categories"].value {
+        AggregationValue::Single(v) => assert_eq!(*v as u64, 3),
+        _ => panic!("Expected Single value for Cardinality aggregation"),
+    }
+}
+
+#[tokio::test]
+async fn test_agg_top_hits_per_bucket() {
+    let (_tmp, backend) = setup_agg_data().await;
+
+    let aggs = vec![AggregationRequest {
+        name: "by_category".to_string(),
+        agg_type: AggregationType::terms("category".to_string()),
+        aggs: Some(vec![AggregationRequest {
+            name: "priciest".to_string(),
+            agg_type: AggregationType::top_hits_sorted(
+                1,
+                vec![TopHitsSort {
+                    field: "price".to_string(),
+                    order: SortDirection::Desc,
+                    ..Default::default()
+                }],
+                Some(vec!["title".to_string()]),
+            ),
+            aggs: None,
+        }]),
+    }];
+
+    let result = backend
+        .search_with_aggs("test", &match_all_query(), aggs)
+        .await
+        .unwrap();
+
+    let AggregationValue::Buckets(buckets) = &result.aggregations["by_category"].value else {
+        panic!("Expected Buckets value");
+    };
+    let books = buckets.iter().find(|b| b.key == "books").unwrap();
+    let sub = &books.sub_aggs.as_ref().unwrap()[0];
+    let AggregationValue::TopHits(top) = &sub.value else {
+        panic!("Expected TopHits value");
+    };
+    assert_eq!(top.total, 2);
+    assert_eq!(top.hits.len(), 1);
+    assert_eq!(top.hits[0].id, "4");
+    assert_eq!(top.hits[0].sort, vec![json!(24.99)]);
+    assert_eq!(top.hits[0].fields.len(), 1);
+    assert_eq!(top.hits[0].fields["title"], json!("Delta item"));
+}
+
+#[tokio::test]
+async fn test_agg_top_hits_by_score() {
+    let (_tmp, backend) = setup_agg_data().await;
+
+    let aggs = vec![AggregationRequest {
+        name: "best".to_string(),
+        agg_type: AggregationType::top_hits(2),
+        aggs: None,
+    }];
+
+    let result = backend
+        .search_with_aggs("test", &make_query("item OR gamma"), aggs)
+        .await
+        .unwrap();
+
+    let AggregationValue::TopHits(top) = &result.aggregations["best"].value else {
+        panic!("Expected TopHits value");
+    };
+    assert_eq!(top.total, 5);
+    assert_eq!(top.hits.len(), 2);
+    assert_eq!(top.hits[0].id, "3");
+    assert!(top.hits[0].score > top.hits[1].score);
+    assert_eq!(top.max_score, Some(top.hits[0].score));
+}
+
+#[tokio::test]
+async fn test_agg_significant_terms() {
+    let (_tmp, backend) = setup_agg_data().await;
+
+    let aggs = vec![AggregationRequest {
+        name: "unusual".to_string(),
+        agg_type: AggregationType::SignificantTerms {
+            field: "category".to_string(),
+            size: None,
+            min_doc_count: Some(1),
+            background_filter: None,
+        },
+        aggs: None,
+    }];
+
+    // Foreground: the two books plus one electronics item
+    let result = backend
+        .search_with_aggs("test", &make_query("gamma OR delta OR alpha"), aggs)
+        .await
+        .unwrap();
+
+    let AggregationValue::SignificantTerms(sig) = &result.aggregations["unusual"].value else {
+        panic!("Expected SignificantTerms value");
+    };
+    assert_eq!(sig.doc_count, 3);
+    assert_eq!(sig.bg_count, 5);
+    assert_eq!(sig.buckets.len(), 1);
+    assert_eq!(sig.buckets[0].key, "books");
+    assert_eq!(sig.buckets[0].doc_count, 2);
+    assert_eq!(sig.buckets[0].bg_count, 2);
+}
+
+#[tokio::test]
+async fn test_agg_composite_pages_through_all_buckets() {
+    let (_tmp, backend) = setup_agg_data().await;
+
+    let sources = vec![
+        CompositeSource::terms("category".to_string(), "category".to_string()),
+        CompositeSource::histogram("count".to_string(), "count".to_string(), 20.0),
+    ];
+
+    let mut pages = Vec::new();
+    let mut after = None;
+    loop {
+        let agg_type = match after.take() {
+            Some(after) => AggregationType::composite_after(sources.clone(), 2, after),
+            None => AggregationType::composite(sources.clone(), 2),
+        };
+        let aggs = vec![AggregationRequest {
+            name: "rollup".to_string(),
+            agg_type,
+            aggs: Some(vec![AggregationRequest {
+                name: "total_price".to_string(),
+                agg_type: AggregationType::Sum {
+                    field: "price".to_string(),
+                },
+                aggs: None,
+            }]),
+        }];
+        let result = backend
+            .search_with_aggs("test", &match_all_query(), aggs)
+            .await
+            .unwrap();
+        let AggregationValue::Composite(composite) = &result.aggregations["rollup"].value else {
+            panic!("Expected Composite value");
+        };
+        if composite.buckets.is_empty() {
+            assert!(composite.after_key.is_none());
+            break;
+        }
+        assert!(composite.buckets.len() <= 2);
+        for bucket in &composite.buckets {
+            assert!(bucket.sub_aggs.is_some());
+            pages.push((
+                bucket.key["category"].clone(),
+                bucket.key["count"].clone(),
+                bucket.doc_count,
+            ));
+        }
+        after = composite.after_key.clone();
+    }
+
+    assert_eq!(
+        pages,
+        vec![
+            (json!("books"), json!(20), 1),
+            (json!("books"), json!(40), 1),
+            (json!("clothing"), json!(40), 1),
+            (json!("electronics"), json!(0), 1),
+            (json!("electronics"), json!(20), 1),
+        ]
+    );
+}
+
+fn single_value(bucket: &prism::aggregations::Bucket, name: &str) -> Option<f64> {
+    bucket
+        .sub_aggs
+        .as_ref()?
+        .iter()
+        .find(|a| a.name == name)
+        .and_then(|a| match a.value {
+            AggregationValue::Single(v) => Some(v),
+            _ => None,
+        })
+}
+
+#[tokio::test]
+async fn test_agg_parent_pipelines_on_histogram() {
+    let (_tmp, backend) = setup_agg_data().await;
+
+    let aggs = vec![AggregationRequest {
+        name: "by_count".to_string(),
+        agg_type: AggregationType::Histogram {
+            field: "count".to_string(),
+            interval: 20.0,
+            min_doc_count: None,
+            extended_bounds: None,
+        },
+        aggs: Some(vec![
+            AggregationRequest {
+                name: "revenue".to_string(),
+                agg_type: AggregationType::sum("price".to_string()),
+                aggs: None,
+            },
+            AggregationRequest {
+                name: "revenue_change".to_string(),
+                agg_type: AggregationType::derivative("revenue".to_string()),
+                aggs: None,
+            },
+            AggregationRequest {
+                name: "revenue_total".to_string(),
+                agg_type: AggregationType::cumulative_sum("revenue".to_string()),
+                aggs: None,
+            },
+        ]),
+    }];
+
+    let result = backend
+        .search_with_aggs("test", &match_all_query(), aggs)
+        .await
+        .unwrap();
+    let AggregationValue::Buckets(buckets) = &result.aggregations["by_count"].value else {
+        panic!("Expected Buckets value");
+    };
+    let keys: Vec<_> = buckets.iter().map(|b| b.key.as_str()).collect();
+    assert_eq!(keys, vec!["0", "20", "40"]);
+
+    assert_eq!(single_value(&buckets[0], "revenue_change"), None);
+    let change = single_value(&buckets[1], "revenue_change").unwrap();
+    assert!((change - 62.5).abs() < 1e-6);
+    let change = single_value(&buckets[2], "revenue_change").unwrap();
+    assert!((change + 77.51).abs() < 1e-6);
+
+    let total = single_value(&buckets[2], "revenue_total").unwrap();
+    assert!((total - 347.46).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn test_agg_bucket_sort_and_selector_on_terms() {
+    let (_tmp, backend) = setup_agg_data().await;
+
+    let aggs = vec![AggregationRequest {
+        name: "by_category".to_string(),
+        agg_type: AggregationType::Terms {
+            field: "category".to_string(),
+            size: Some(10),
+        },
+        aggs: Some(vec![
+            AggregationRequest {
+                name: "revenue".to_string(),
+                agg_type: AggregationType::sum("price".to_string()),
+                aggs: None,
+            },
+            AggregationRequest {
+                name: "not_tiny".to_string(),
+                agg_type: AggregationType::bucket_selector(
+                    HashMap::from([("r".to_string(), "revenue".to_string())]),
+                    "r >= 40".to_string(),
+                ),
+                aggs: None,
+            },
+            AggregationRequest {
+                name: "top".to_string(),
+                agg_type: AggregationType::bucket_sort(
+                    vec![prism::aggregations::BucketSortField {
+                        path: "revenue".to_string(),
+                        order: SortDirection::Asc,
+                    }],
+                    Some(1),
+                ),
+                aggs: None,
+            },
+        ]),
+    }];
+
+    let result = backend
+        .search_with_aggs("test", &match_all_query(), aggs)
+        .await
+        .unwrap();
+    let counts = bucket_counts(&result.aggregations["by_category"].value);
+    // books (37.98) is dropped by the selector, then the cheapest remaining wins
+    assert_eq!(counts, HashMap::from([("clothing".to_string(), 1)]));
+}
+
+#[tokio::test]
+async fn test_agg_pipeline_outside_bucket_agg_is_rejected() {
+    let (_tmp, backend) = setup_agg_data().await;
+
+    let aggs = vec![AggregationRequest {
+        name: "change".to_string(),
+        agg_type: AggregationType::derivative("_count".to_string()),
+        aggs: None,
+    }];
+
+    let result = backend
+        .search_with_aggs("test", &match_all_query(), aggs)
+        .await;
+    assert!(result.is_err());
+}
+
 // =========================================================================
 // 7. Other methods: get_top_terms, more_like_this, suggest_terms,
 //    get_segments, reconstruct_document
@@ -1621,3 +2108,138 @@ async fn test_search_with_aggs_returns_paginated_results() {
         assert_eq!(*v as u64, 5);
     }
 }
+
+#[tokio::test]
+async fn test_snapshot_ignores_later_writes() {
+    let (_tmp, backend) = setup().await;
+    backend
+        .index("test", vec![doc("1", "alpha", "one"), doc("2", "beta", "two")])
+        .await
+        .unwrap();
+
+    let snapshot = backend.snapshot("test").unwrap();
+    assert_eq!(snapshot.num_docs(), 2);
+
+    backend.delete("test", vec!["1".to_string()]).await.unwrap();
+    backend
+        .index("test", vec![doc("3", "gamma", "three")])
+        .await
+        .unwrap();
+
+    let pinned = backend
+        .search_snapshot("test", &snapshot, &make_query("*"), &[], None)
+        .unwrap();
+    let mut ids: Vec<_> = pinned.results.iter().map(|r| r.id.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["1", "2"]);
+
+    let live = backend
+        .search_with_aggs("test", &make_query("*"), vec![])
+        .await
+        .unwrap();
+    assert_eq!(live.total, 2);
+    assert!(live.results.iter().any(|r| r.id == "3"));
+}
+
+#[tokio::test]
+async fn test_snapshot_pages_resume_after_cursor() {
+    let (_tmp, backend) = setup().await;
+    // Several segments, with both tied and distinct scores
+    for batch in 0..3 {
+        let docs = (0..7)
+            .map(|i| {
+                let title = if i % 3 == 0 {
+                    "rust rust search"
+                } else {
+                    "rust search"
+                };
+                doc(&format!("{}-{}", batch, i), title, "body")
+            })
+            .collect();
+        backend.index("test", docs).await.unwrap();
+    }
+    let snapshot = backend.snapshot("test").unwrap();
+
+    for query_string in ["rust", "*"] {
+        let all = backend
+            .search_snapshot("test", &snapshot, &make_query(query_string), &[], None)
+            .unwrap();
+        assert_eq!(all.results.len(), 21);
+        assert_eq!(all.cursors.len(), 21);
+
+        let mut page_query = make_query(query_string);
+        page_query.limit = 4;
+        let mut paged = Vec::new();
+        let mut after = None;
+        loop {
+            let page = backend
+                .search_snapshot("test", &snapshot, &page_query, &[], after.as_ref())
+                .unwrap();
+            assert_eq!(page.total, 21);
+            if page.results.is_empty() {
+                break;
+            }
+            after = page.cursors.last().copied();
+            paged.extend(page.results.into_iter().map(|r| r.id));
+        }
+        let expected: Vec<String> = all.results.into_iter().map(|r| r.id).collect();
+        assert_eq!(paged, expected, "query {}", query_string);
+    }
+}
+
+#[tokio::test]
+async fn test_dfs_statistics_equalize_scores_across_shards() {
+    let (_tmp_a, shard_a) = setup().await;
+    let (_tmp_b, shard_b) = setup().await;
+
+    // "rust" is rare on shard A and common on shard B.
+    shard_a
+        .index(
+            "test",
+            vec![
+                doc("1", "rust guide", "systems"),
+                doc("2", "python guide", "scripting"),
+                doc("3", "go guide", "services"),
+                doc("4", "java guide", "enterprise"),
+            ],
+        )
+        .await
+        .unwrap();
+    shard_b
+        .index(
+            "test",
+            vec![
+                doc("1", "rust guide", "systems"),
+                doc("5", "rust book", "ownership"),
+                doc("6", "rust async", "futures"),
+            ],
+        )
+        .await
+        .unwrap();
+
+    let query = Query {
+        fields: vec!["title".to_string()],
+        ..make_query("rust")
+    };
+
+    let score_of = |results: &prism::backends::SearchResults| {
+        results.results.iter().find(|r| r.id == "1").unwrap().score
+    };
+
+    let local_a = shard_a.search("test", query.clone()).await.unwrap();
+    let local_b = shard_b.search("test", query.clone()).await.unwrap();
+    assert!((score_of(&local_a) - score_of(&local_b)).abs() > 1e-4);
+
+    let mut statistics = shard_a.term_statistics("test", &query).unwrap();
+    statistics.merge(&shard_b.term_statistics("test", &query).unwrap());
+    assert_eq!(statistics.total_docs, 7);
+    assert_eq!(statistics.doc_freqs["title"]["rust"], 4);
+
+    let global_a = shard_a
+        .search_with_statistics("test", &query, &statistics)
+        .unwrap();
+    let global_b = shard_b
+        .search_with_statistics("test", &query, &statistics)
+        .unwrap();
+    assert!((score_of(&global_a) - score_of(&global_b)).abs() < 1e-4);
+}
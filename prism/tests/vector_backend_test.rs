@@ -138,3 +138,90 @@ async fn test_index_and_search() {
     assert_eq!(results.total, 2);
     assert_eq!(results.results[0].id, "d1");
 }
+
+#[tokio::test]
+async fn test_snapshot_filters_later_documents() {
+    let temp_dir = TempDir::new().unwrap();
+    let backend = VectorBackend::new(temp_dir.path()).unwrap();
+
+    let schema = CollectionSchema {
+        collection: "snap".to_string(),
+        description: None,
+        backends: Backends {
+            text: None,
+            vector: Some(VectorBackendConfig {
+                embedding_field: "embedding".to_string(),
+                dimension: 2,
+                distance: VectorDistance::Cosine,
+                hnsw_m: 16,
+                hnsw_ef_construction: 200,
+                hnsw_ef_search: 100,
+                vector_weight: 0.5,
+                num_shards: 2,
+                shard_oversample: 2.5,
+                compaction: Default::default(),
+            }),
+            graph: None,
+        },
+        indexing: Default::default(),
+        quota: Default::default(),
+        embedding_generation: None,
+        facets: None,
+        boosting: None,
+        storage: Default::default(),
+        system_fields: Default::default(),
+        hybrid: None,
+        replication: None,
+        reranking: None,
+        ilm_policy: None,
+    };
+    backend.initialize("snap", &schema).await.unwrap();
+
+    let doc = |id: &str, v: [f32; 2]| Document {
+        id: id.to_string(),
+        fields: std::collections::HashMap::from([("embedding".to_string(), serde_json::json!(v))]),
+    };
+    SearchBackend::index(
+        &backend,
+        "snap",
+        vec![doc("a", [1.0, 0.0]), doc("b", [0.0, 1.0])],
+    )
+    .await
+    .unwrap();
+
+    let snapshot = backend.snapshot("snap").unwrap();
+    assert_eq!(snapshot.len(), 2);
+
+    SearchBackend::index(&backend, "snap", vec![doc("c", [1.0, 0.1])])
+        .await
+        .unwrap();
+    SearchBackend::delete(&backend, "snap", vec!["b".to_string()])
+        .await
+        .unwrap();
+
+    assert!(snapshot.contains("a"));
+    assert!(snapshot.contains("b"));
+    assert!(!snapshot.contains("c"));
+
+    let query = prism::backends::r#trait::Query {
+        query_string: serde_json::to_string(&vec![1.0f32, 0.0]).unwrap(),
+        fields: vec![],
+        limit: 10,
+        offset: 0,
+        merge_strategy: None,
+        text_weight: None,
+        vector_weight: None,
+        highlight: None,
+        rrf_k: None,
+        min_score: None,
+        score_function: None,
+        skip_ranking: false,
+    };
+    let mut results = SearchBackend::search(&backend, "snap", query)
+        .await
+        .unwrap()
+        .results;
+    snapshot.retain_visible(&mut results);
+    let ids: Vec<_> = results.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, vec!["a"]);
+}